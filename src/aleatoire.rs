@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Generateur unique du jeu: xorshift64* sur un etat 64 bits, serialisable
+// pour que les sauvegardes reprennent exactement le meme tirage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    pub fn from_seed(seed: u64) -> Self {
+        let base = if seed == 0 {
+            0xA409_3822_299F_31D0
+        } else {
+            seed
+        };
+        Self {
+            state: base ^ 0x517C_C1B7_2722_0A95,
+        }
+    }
+
+    // Etat brut (ancien tirage des PNJ); zero est interdit par xorshift.
+    pub fn from_state(state: u64) -> Self {
+        Self {
+            state: if state == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                state
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    // [0, 1) avec 24 bits de mantisse: au-dela, l'arrondi f32 atteindrait 1.0.
+    pub fn next_f32(&mut self) -> f32 {
+        unit_f32(self.next_u32())
+    }
+
+    // [0, 1) avec 53 bits de mantisse.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn roll_range(&mut self, upper: u32) -> u32 {
+        if upper <= 1 {
+            0
+        } else {
+            (self.next_u64() % upper as u64) as u32
+        }
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    pub fn chance_permille(&mut self, rate: u16) -> bool {
+        self.roll_range(1000) < u32::from(rate)
+    }
+}
+
+fn unit_f32(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1u32 << 24) as f32
+}
+
+// Melange splitmix64: derive une graine independante de (graine, sel).
+pub fn mix_seed(seed: u64, salt: u64) -> u64 {
+    let mut x = seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+// Un flux par sous-systeme: ajouter des tirages dans l'un ne decale pas les autres.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RngStream {
+    Pannes,
+    Personnel,
+    Marche,
+    Deplacement,
+    Social,
+    Pions,
+    Achats,
+    Qualite,
+    Relations,
+}
+
+impl RngStream {
+    fn salt(self) -> u64 {
+        match self {
+            Self::Pannes => 0x0B4E_A4D0_0001,
+            Self::Personnel => 0x0E3B_1077_0002,
+            Self::Marche => 0x3A4C_4E00_0003,
+            Self::Deplacement => 0x0DE9_1ACE_0004,
+            Self::Social => 0x0050_C1A1_0005,
+            Self::Pions => 0x0091_0500_0006,
+            Self::Achats => 0x0AC4_A750_0007,
+            Self::Qualite => 0x0C0A_117E_0008,
+            Self::Relations => 0x0AE1_A710_0009,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngStreams {
+    seed: u64,
+    #[serde(default)]
+    streams: BTreeMap<RngStream, SimRng>,
+}
+
+impl RngStreams {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: BTreeMap::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Graine de depart du flux, pour les sous-systemes qui gardent leur propre etat.
+    pub fn stream_seed(&self, stream: RngStream) -> u64 {
+        mix_seed(self.seed, stream.salt())
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut SimRng {
+        let seed = self.stream_seed(stream);
+        self.streams
+            .entry(stream)
+            .or_insert_with(|| SimRng::from_seed(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_are_reproducible_and_independent() {
+        let mut first = RngStreams::new(42);
+        let mut second = RngStreams::new(42);
+        let a = (0..8)
+            .map(|_| first.stream(RngStream::Pannes).next_u64())
+            .collect::<Vec<_>>();
+        // Tirer dans un autre flux ne change pas la sequence des pannes.
+        second.stream(RngStream::Social).next_u64();
+        let b = (0..8)
+            .map(|_| second.stream(RngStream::Pannes).next_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(a, b);
+        assert_ne!(
+            first.stream_seed(RngStream::Pannes),
+            first.stream_seed(RngStream::Social)
+        );
+        assert_ne!(
+            RngStreams::new(43).stream(RngStream::Pannes).next_u64(),
+            a[0]
+        );
+
+        let mut rng = SimRng::from_state(0);
+        for _ in 0..100 {
+            assert!((0.0..1.0).contains(&rng.next_f64()));
+            assert!(rng.roll_range(7) < 7);
+        }
+    }
+
+    #[test]
+    fn unit_f32_stays_below_one() {
+        assert!(unit_f32(u32::MAX) < 1.0);
+        assert_eq!(unit_f32(0), 0.0);
+        let mut rng = SimRng::from_seed(7);
+        for _ in 0..10_000 {
+            assert!((0.0..1.0).contains(&rng.next_f32()));
+        }
+    }
+}
@@ -0,0 +1,450 @@
+// Simulation sans fenetre pour l'equilibrage: avance N jours a FIXED_DT
+// et sort les KPI, la courbe de tresorerie et les raisons de blocage.
+// Les agents se deplacent en A* sur la carte de l'usine de depart, comme en jeu.
+// cargo run --release --bin simulation_headless -- --days 7 --bootstrap --out runs/base.csv
+// --replay rejoue un journal .rejeu ecrit a cote d'une sauvegarde (rapport de bug).
+use rxchixs::rejeu::{CommandLog, Replay};
+use rxchixs::sim::FactorySim;
+use rxchixs::{FIXED_DT, MAP_H, MAP_W, SIM_CONFIG_PATH, World, generate_starter_factory_world};
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: simulation_headless [--days N] [--sample-h H] [--format csv|json] \
+[--config FICHIER] [--out FICHIER] [--bootstrap] [--replay FICHIER.rejeu]";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct RunOptions {
+    days: f64,
+    sample_hours: f64,
+    format: OutputFormat,
+    config_path: String,
+    out_path: Option<String>,
+    bootstrap: bool,
+    replay_path: Option<String>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            days: 7.0,
+            sample_hours: 1.0,
+            format: OutputFormat::Csv,
+            config_path: SIM_CONFIG_PATH.to_string(),
+            out_path: None,
+            bootstrap: false,
+            replay_path: None,
+        }
+    }
+}
+
+fn parse_positive(flag: &str, value: Option<String>) -> Result<f64, String> {
+    let raw = value.ok_or_else(|| format!("valeur manquante pour {flag}"))?;
+    let parsed = raw
+        .parse::<f64>()
+        .map_err(|_| format!("valeur invalide pour {flag}: {raw}"))?;
+    if !parsed.is_finite() || parsed <= 0.0 {
+        return Err(format!("{flag} doit etre positif: {raw}"));
+    }
+    Ok(parsed)
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<RunOptions, String> {
+    let mut options = RunOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--days" => options.days = parse_positive("--days", args.next())?,
+            "--sample-h" => options.sample_hours = parse_positive("--sample-h", args.next())?,
+            "--format" => {
+                options.format = match args.next().as_deref() {
+                    Some("csv") => OutputFormat::Csv,
+                    Some("json") => OutputFormat::Json,
+                    other => return Err(format!("format inconnu: {}", other.unwrap_or(""))),
+                }
+            }
+            "--config" => {
+                options.config_path = args
+                    .next()
+                    .ok_or_else(|| "valeur manquante pour --config".to_string())?
+            }
+            "--out" => {
+                options.out_path = Some(
+                    args.next()
+                        .ok_or_else(|| "valeur manquante pour --out".to_string())?,
+                )
+            }
+            "--bootstrap" => options.bootstrap = true,
+            "--replay" => {
+                options.replay_path = Some(
+                    args.next()
+                        .ok_or_else(|| "valeur manquante pour --replay".to_string())?,
+                )
+            }
+            other => return Err(format!("argument inconnu: {other}")),
+        }
+    }
+    Ok(options)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Sample {
+    day: u64,
+    hour: f64,
+    cash: f64,
+    revenue_total: f64,
+    cost_total: f64,
+    payroll_per_hour: f64,
+    sold_total: u32,
+    finished_stock: u32,
+    raw_receiving: u32,
+    raw_line_input: u32,
+    throughput_per_hour: f64,
+    otif: f64,
+    scrap_total: u32,
+    downtime_minutes: f64,
+    hauls_completed: u32,
+    mean_haul_seconds: f64,
+    broken_machines: usize,
+    line_block_reasons: String,
+    sales_block_reason: String,
+}
+
+impl Sample {
+    const CSV_HEADER: &'static str = "day,hour,cash,revenue_total,cost_total,payroll_per_hour,\
+sold_total,finished_stock,raw_receiving,raw_line_input,throughput_per_hour,otif,scrap_total,\
+downtime_minutes,hauls_completed,mean_haul_seconds,broken_machines,line_block_reasons,\
+sales_block_reason";
+
+    fn capture(sim: &FactorySim) -> Self {
+        let kpi = sim.kpi();
+        let line_block_reasons = sim
+            .production_lines()
+            .iter()
+            .map(|line| format!("L{}: {}", line.id, line.block_reason))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        Self {
+            day: sim.clock.day_index(),
+            hour: sim.clock.hour_of_day(),
+            cash: sim.cash(),
+            revenue_total: sim.revenue_total(),
+            cost_total: sim.cost_total(),
+            payroll_per_hour: sim.payroll_per_hour(),
+            sold_total: sim.sold_total(),
+            finished_stock: sim.line.finished,
+            raw_receiving: sim.stock().raw_receiving,
+            raw_line_input: sim.stock().raw_line_input,
+            throughput_per_hour: kpi.throughput_per_hour,
+            otif: kpi.otif,
+            scrap_total: kpi.scrap_total,
+            downtime_minutes: kpi.downtime_minutes,
+            hauls_completed: kpi.hauls_completed,
+            mean_haul_seconds: kpi.mean_haul_seconds(),
+            broken_machines: sim.broken_machine_count(),
+            line_block_reasons,
+            sales_block_reason: sim.sales_block_reason().to_string(),
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{:.3},{:.2},{:.2},{:.2},{:.2},{},{},{},{},{:.3},{:.3},{},{:.1},{},{:.1},{},{},{}",
+            self.day,
+            self.hour,
+            self.cash,
+            self.revenue_total,
+            self.cost_total,
+            self.payroll_per_hour,
+            self.sold_total,
+            self.finished_stock,
+            self.raw_receiving,
+            self.raw_line_input,
+            self.throughput_per_hour,
+            self.otif,
+            self.scrap_total,
+            self.downtime_minutes,
+            self.hauls_completed,
+            self.mean_haul_seconds,
+            self.broken_machines,
+            csv_field(&self.line_block_reasons),
+            csv_field(&self.sales_block_reason)
+        )
+    }
+
+    fn json_object(&self) -> String {
+        format!(
+            "{{\"day\":{},\"hour\":{:.3},\"cash\":{:.2},\"revenue_total\":{:.2},\
+\"cost_total\":{:.2},\"payroll_per_hour\":{:.2},\"sold_total\":{},\"finished_stock\":{},\
+\"raw_receiving\":{},\"raw_line_input\":{},\"throughput_per_hour\":{:.3},\"otif\":{:.3},\
+\"scrap_total\":{},\"downtime_minutes\":{:.1},\"hauls_completed\":{},\
+\"mean_haul_seconds\":{:.1},\"broken_machines\":{},\"line_block_reasons\":{},\
+\"sales_block_reason\":{}}}",
+            self.day,
+            self.hour,
+            self.cash,
+            self.revenue_total,
+            self.cost_total,
+            self.payroll_per_hour,
+            self.sold_total,
+            self.finished_stock,
+            self.raw_receiving,
+            self.raw_line_input,
+            self.throughput_per_hour,
+            self.otif,
+            self.scrap_total,
+            self.downtime_minutes,
+            self.hauls_completed,
+            self.mean_haul_seconds,
+            self.broken_machines,
+            json_string(&self.line_block_reasons),
+            json_string(&self.sales_block_reason)
+        )
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Echantillonne toutes les `sample_hours` heures simulees, plus l'etat final.
+fn run_simulation(
+    sim: &mut FactorySim,
+    world: &World,
+    days: f64,
+    sample_hours: f64,
+) -> Vec<Sample> {
+    let end_s = sim.clock.seconds() + days * 86_400.0;
+    let sample_s = sample_hours * 3600.0;
+    let mut next_sample_s = sim.clock.seconds() + sample_s;
+    let mut samples = vec![Sample::capture(sim)];
+
+    while sim.clock.seconds() < end_s {
+        sim.step_in_world(FIXED_DT, Some(world));
+        if sim.clock.seconds() >= next_sample_s {
+            samples.push(Sample::capture(sim));
+            next_sample_s += sample_s;
+        }
+    }
+    if samples
+        .last()
+        .is_none_or(|last| *last != Sample::capture(sim))
+    {
+        samples.push(Sample::capture(sim));
+    }
+    samples
+}
+
+// Meme echantillonnage que run_simulation, mais la duree est celle du journal.
+fn run_replay(replay: &mut Replay, sample_hours: f64) -> Vec<Sample> {
+    let sample_s = sample_hours * 3600.0;
+    let mut next_sample_s = replay.sim().clock.seconds() + sample_s;
+    let mut samples = vec![Sample::capture(replay.sim())];
+
+    while replay.step() {
+        if replay.sim().clock.seconds() >= next_sample_s {
+            samples.push(Sample::capture(replay.sim()));
+            next_sample_s += sample_s;
+        }
+    }
+    let last = Sample::capture(replay.sim());
+    if samples.last() != Some(&last) {
+        samples.push(last);
+    }
+    samples
+}
+
+fn render(options: &RunOptions, samples: &[Sample]) -> String {
+    match options.format {
+        OutputFormat::Csv => {
+            let mut out = String::from(Sample::CSV_HEADER);
+            out.push('\n');
+            for sample in samples {
+                out.push_str(&sample.csv_row());
+                out.push('\n');
+            }
+            out
+        }
+        OutputFormat::Json => {
+            let rows = samples
+                .iter()
+                .map(|sample| format!("    {}", sample.json_object()))
+                .collect::<Vec<_>>()
+                .join(",\n");
+            format!(
+                "{{\n  \"config\": {},\n  \"days\": {},\n  \"bootstrap\": {},\n  \"samples\": [\n{}\n  ]\n}}\n",
+                json_string(&options.config_path),
+                options.days,
+                options.bootstrap,
+                rows
+            )
+        }
+    }
+}
+
+fn simulate(options: &RunOptions) -> Result<Vec<Sample>, String> {
+    if let Some(path) = &options.replay_path {
+        let log = CommandLog::load(Path::new(path))?;
+        eprintln!(
+            "rejeu {path}: pas {} a {}, {} evenements",
+            log.start_tick(),
+            log.end_tick(),
+            log.entries().len()
+        );
+        if let Some(tick) = log.interrupted_at() {
+            eprintln!("rejeu {path}: carte modifiee au pas {tick}, journal arrete la");
+        }
+        let mut replay = Replay::new(log)?;
+        let samples = run_replay(&mut replay, options.sample_hours);
+        if let Some(divergence) = replay.divergence() {
+            return Err(format!("rejeu divergent: {divergence}"));
+        }
+        return Ok(samples);
+    }
+
+    if !Path::new(&options.config_path).exists() {
+        return Err(format!("config introuvable: {}", options.config_path));
+    }
+    let mut sim = FactorySim::load_or_default(&options.config_path, MAP_W, MAP_H);
+    if sim.config.time_scale <= 0.0 {
+        return Err("time_scale nul: la simulation ne peut pas avancer".to_string());
+    }
+    if options.bootstrap {
+        sim.bootstrap_functional_factory()?;
+    }
+    let world = generate_starter_factory_world(MAP_W, MAP_H);
+    Ok(run_simulation(
+        &mut sim,
+        &world,
+        options.days,
+        options.sample_hours,
+    ))
+}
+
+fn run(options: &RunOptions) -> Result<(), String> {
+    let samples = simulate(options)?;
+    let payload = render(options, &samples);
+    match &options.out_path {
+        Some(path) => {
+            if let Some(parent) = Path::new(path).parent()
+                && !parent.as_os_str().is_empty()
+            {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("echec creation dossier sortie: {e}"))?;
+            }
+            fs::write(path, payload).map_err(|e| format!("echec ecriture {path}: {e}"))?;
+        }
+        None => print!("{payload}"),
+    }
+
+    if let Some(last) = samples.last() {
+        eprintln!(
+            "fin jour {} {:.1}h | tresorerie {:.0} EUR | boxes vendues {} | {}",
+            last.day, last.hour, last.cash, last.sold_total, last.line_block_reasons
+        );
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("simulation_headless: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rxchixs::sim::StarterSimConfig;
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_run_options_and_rejects_bad_values() {
+        let options = parse_args(args(&[
+            "--days",
+            "2",
+            "--format",
+            "json",
+            "--out",
+            "runs/a.json",
+            "--bootstrap",
+        ]))
+        .expect("options should parse");
+        assert_eq!(options.days, 2.0);
+        assert_eq!(options.format, OutputFormat::Json);
+        assert_eq!(options.out_path.as_deref(), Some("runs/a.json"));
+        assert!(options.bootstrap);
+        let replay = parse_args(args(&["--replay", "saves/bug.rejeu"])).expect("replay parses");
+        assert_eq!(replay.replay_path.as_deref(), Some("saves/bug.rejeu"));
+
+        assert!(parse_args(args(&["--days", "0"])).is_err());
+        assert!(parse_args(args(&["--format", "xml"])).is_err());
+        assert!(parse_args(args(&["--vite"])).is_err());
+    }
+
+    #[test]
+    fn hourly_samples_cover_the_run_and_render_as_csv() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), MAP_W, MAP_H);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let world = generate_starter_factory_world(MAP_W, MAP_H);
+        let samples = run_simulation(&mut sim, &world, 0.25, 1.0);
+
+        assert_eq!(samples.len(), 7);
+        assert_eq!(samples[0].hour, 0.0);
+        assert!(samples.last().is_some_and(|last| last.hour >= 6.0));
+        assert!(
+            samples
+                .iter()
+                .all(|s| s.line_block_reasons.starts_with("L1: "))
+        );
+
+        let csv = render(&RunOptions::default(), &samples);
+        let mut lines = csv.lines();
+        let header_fields = lines.next().map(|h| h.split(',').count());
+        assert_eq!(header_fields, Some(19));
+        assert_eq!(lines.count(), samples.len());
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(json_string("x\"y"), "\"x\\\"y\"");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::aleatoire::{SimRng, mix_seed};
 use macroquad::prelude::*;
 use serde::Deserialize;
 use std::collections::HashSet;
@@ -223,7 +224,7 @@ impl<T: Copy + Eq> TraitGene<T> {
         Self { a, b }
     }
 
-    fn pick(self, rng: &mut SeedRng) -> T {
+    fn pick(self, rng: &mut SimRng) -> T {
         if rng.next_u32() & 1 == 0 {
             self.a
         } else {
@@ -424,7 +425,7 @@ impl CharacterCatalog {
     }
 
     fn generate_founder_dna(&self, seed: u64) -> CharacterDna {
-        let mut rng = SeedRng::new(seed);
+        let mut rng = SimRng::from_seed(seed);
         CharacterDna {
             body_type: TraitGene::new(self.roll_body_type(&mut rng), self.roll_body_type(&mut rng)),
             skin_tone: TraitGene::new(self.roll_skin_tone(&mut rng), self.roll_skin_tone(&mut rng)),
@@ -449,7 +450,7 @@ impl CharacterCatalog {
     }
 
     fn express_visual(&self, dna: &CharacterDna, seed: u64) -> CharacterVisual {
-        let mut rng = SeedRng::new(seed ^ 0xD0D0_5EED_1337_CAFE);
+        let mut rng = SimRng::from_seed(seed ^ 0xD0D0_5EED_1337_CAFE);
         CharacterVisual {
             body_type: dna.body_type.pick(&mut rng),
             skin_tone: dna.skin_tone.pick(&mut rng),
@@ -468,7 +469,7 @@ impl CharacterCatalog {
         seed: u64,
         mutation_permille: u16,
     ) -> CharacterDna {
-        let mut rng = SeedRng::new(seed ^ 0xBADC_0FFE_F00D_A11A);
+        let mut rng = SimRng::from_seed(seed ^ 0xBADC_0FFE_F00D_A11A);
         let mutation = mutation_permille.min(300);
 
         let mut body_type = TraitGene::new(
@@ -559,31 +560,31 @@ impl CharacterCatalog {
         }
     }
 
-    fn roll_body_type(&self, rng: &mut SeedRng) -> BodyType {
+    fn roll_body_type(&self, rng: &mut SimRng) -> BodyType {
         pick_weighted(rng, &self.body_types)
     }
 
-    fn roll_skin_tone(&self, rng: &mut SeedRng) -> SkinTone {
+    fn roll_skin_tone(&self, rng: &mut SimRng) -> SkinTone {
         pick_weighted(rng, &self.skin_tones)
     }
 
-    fn roll_hair_style(&self, rng: &mut SeedRng) -> HairStyle {
+    fn roll_hair_style(&self, rng: &mut SimRng) -> HairStyle {
         pick_weighted(rng, &self.hair_styles)
     }
 
-    fn roll_hair_color(&self, rng: &mut SeedRng) -> HairColor {
+    fn roll_hair_color(&self, rng: &mut SimRng) -> HairColor {
         pick_weighted(rng, &self.hair_colors)
     }
 
-    fn roll_outfit_style(&self, rng: &mut SeedRng) -> OutfitStyle {
+    fn roll_outfit_style(&self, rng: &mut SimRng) -> OutfitStyle {
         pick_weighted(rng, &self.outfit_styles)
     }
 
-    fn roll_outfit_palette(&self, rng: &mut SeedRng) -> OutfitPalette {
+    fn roll_outfit_palette(&self, rng: &mut SimRng) -> OutfitPalette {
         pick_weighted(rng, &self.outfit_palettes)
     }
 
-    fn roll_accessory(&self, rng: &mut SeedRng) -> Accessory {
+    fn roll_accessory(&self, rng: &mut SimRng) -> Accessory {
         pick_weighted(rng, &self.accessories)
     }
 }
@@ -716,7 +717,7 @@ fn validate_weighted<T: Copy + Eq + std::hash::Hash>(
     Ok(())
 }
 
-fn pick_weighted<T: Copy>(rng: &mut SeedRng, entries: &[(T, u32)]) -> T {
+fn pick_weighted<T: Copy>(rng: &mut SimRng, entries: &[(T, u32)]) -> T {
     let total: u32 = entries.iter().map(|(_, weight)| *weight).sum();
     let mut roll = rng.roll_range(total);
     for (value, weight) in entries {
@@ -731,64 +732,14 @@ fn pick_weighted<T: Copy>(rng: &mut SeedRng, entries: &[(T, u32)]) -> T {
 fn maybe_mutate<T: Copy>(
     allele: &mut T,
     mutation_permille: u16,
-    rng: &mut SeedRng,
-    mut roll: impl FnMut(&mut SeedRng) -> T,
+    rng: &mut SimRng,
+    mut roll: impl FnMut(&mut SimRng) -> T,
 ) {
     if mutation_permille > 0 && rng.chance_permille(mutation_permille) {
         *allele = roll(rng);
     }
 }
 
-fn mix_seed(seed: u64, salt: u64) -> u64 {
-    let mut x = seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15);
-    x ^= x >> 30;
-    x = x.wrapping_mul(0xBF58_476D_1CE4_E5B9);
-    x ^= x >> 27;
-    x = x.wrapping_mul(0x94D0_49BB_1331_11EB);
-    x ^ (x >> 31)
-}
-
-#[derive(Clone, Copy, Debug)]
-struct SeedRng {
-    state: u64,
-}
-
-impl SeedRng {
-    fn new(seed: u64) -> Self {
-        let base = if seed == 0 {
-            0xA409_3822_299F_31D0
-        } else {
-            seed
-        };
-        Self {
-            state: base ^ 0x517C_C1B7_2722_0A95,
-        }
-    }
-
-    fn next_u64(&mut self) -> u64 {
-        self.state ^= self.state >> 12;
-        self.state ^= self.state << 25;
-        self.state ^= self.state >> 27;
-        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
-    }
-
-    fn next_u32(&mut self) -> u32 {
-        (self.next_u64() >> 32) as u32
-    }
-
-    fn roll_range(&mut self, upper: u32) -> u32 {
-        if upper <= 1 {
-            0
-        } else {
-            (self.next_u64() % upper as u64) as u32
-        }
-    }
-
-    fn chance_permille(&mut self, rate: u16) -> bool {
-        self.roll_range(1000) < u32::from(rate)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
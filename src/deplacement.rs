@@ -421,19 +421,11 @@ pub(crate) fn apply_control_inputs(
 }
 
 pub(crate) fn npc_rand_u32(npc: &mut NpcWanderer) -> u32 {
-    let mut x = npc.rng_state;
-    if x == 0 {
-        x = 0x9E37_79B9_7F4A_7C15;
-    }
-    x ^= x >> 12;
-    x ^= x << 25;
-    x ^= x >> 27;
-    npc.rng_state = x;
-    (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as u32
+    npc.rng.next_u32()
 }
 
 pub(crate) fn npc_rand_f32(npc: &mut NpcWanderer) -> f32 {
-    npc_rand_u32(npc) as f32 / u32::MAX as f32
+    npc.rng.next_f32()
 }
 
 pub(crate) fn npc_rand_idle_duration(npc: &mut NpcWanderer) -> f32 {
@@ -1039,7 +1039,6 @@ pub(crate) fn build_game_state_from_map_with_sim(
     let player = Player::new(tile_center(map_copy.player_spawn));
     let chariot = spawn_chariot_pour_map(&map_copy.world, map_copy.player_spawn);
     let chargeur_clark = spawn_chargeur_pour_chariot(&map_copy.world, &chariot);
-    let npc = NpcWanderer::new(tile_center(map_copy.npc_spawn), 0x9922_11AA_77CC_44DD);
     let palette = Palette::new();
     let lineage = build_lineage_preview(character_catalog, lineage_seed);
     let npc_character =
@@ -1048,7 +1047,7 @@ pub(crate) fn build_game_state_from_map_with_sim(
         character_catalog.spawn_founder("Employe-01", lineage_seed ^ 0xCC11_22DD_33EE_44FF);
     let papa_character = character_catalog.spawn_founder("Papa", lineage_seed ^ 0xA114_5A2A);
 
-    let sim = if let Some(sim_save) = sim_save {
+    let mut sim = if let Some(sim_save) = sim_save {
         let config = sim::StarterSimConfig::load_or_create(SIM_CONFIG_PATH);
         sim::FactorySim::from_save_asset(config, sim_save).unwrap_or_else(|err| {
             eprintln!("Etat simulation sauvegarde ignore: {err}");
@@ -1057,27 +1056,34 @@ pub(crate) fn build_game_state_from_map_with_sim(
     } else {
         sim::FactorySim::load_or_default(SIM_CONFIG_PATH, map_copy.world.w, map_copy.world.h)
     };
+    sim.start_recording(Some(&map_copy.world));
+    // PNJ, jauges des pions et social tirent leurs flux de la graine de la simulation.
+    let npc = NpcWanderer::new(
+        tile_center(map_copy.npc_spawn),
+        sim.rng_stream_seed(RngStream::Deplacement),
+    );
+    let pions_seed = sim.rng_stream_seed(RngStream::Pions);
 
     let mut pawns = vec![
         PawnCard {
             key: PawnKey::Player,
             name: "Patron".to_string(),
             role: "Gestion".to_string(),
-            metrics: PawnMetrics::seeded(lineage_seed ^ 0x1111_2222_3333_4444),
+            metrics: PawnMetrics::seeded(mix_seed(pions_seed, 1)),
             history: crate::historique::HistoriqueLog::new(600),
         },
         PawnCard {
             key: PawnKey::Npc,
             name: npc_character.label.clone(),
             role: "Visiteur".to_string(),
-            metrics: PawnMetrics::seeded(lineage_seed ^ 0x9999_AAAA_BBBB_CCCC),
+            metrics: PawnMetrics::seeded(mix_seed(pions_seed, 2)),
             history: crate::historique::HistoriqueLog::new(600),
         },
         PawnCard {
             key: PawnKey::SimWorker,
             name: "Employe 01".to_string(),
             role: "Operateur".to_string(),
-            metrics: PawnMetrics::seeded(lineage_seed ^ 0x0F0F_55AA_00FF_7788),
+            metrics: PawnMetrics::seeded(mix_seed(pions_seed, 3)),
             history: crate::historique::HistoriqueLog::new(600),
         },
     ];
@@ -1089,7 +1095,7 @@ pub(crate) fn build_game_state_from_map_with_sim(
             "Arrive sur le site.".to_string(),
         );
     }
-    let social_state = social::SocialState::new(&pawns, lineage_seed);
+    let social_state = social::SocialState::new(&pawns, sim.rng_stream_seed(RngStream::Social));
 
     let pawn_ui = PawnsUiState {
         selected: Some(PawnKey::Player),
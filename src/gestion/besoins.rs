@@ -0,0 +1,338 @@
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+pub const NEEDS_BOOK_PATH: &str = "data/needs.ron";
+const NEEDS_BOOK_SCHEMA_VERSION: u32 = 1;
+const NEED_FULL: f64 = 100.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeedKind {
+    Manger,
+    Boire,
+    Toilettes,
+    Hygiene,
+    Social,
+}
+
+impl NeedKind {
+    pub const ALL: [Self; 5] = [
+        Self::Manger,
+        Self::Boire,
+        Self::Toilettes,
+        Self::Hygiene,
+        Self::Social,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Manger => "Manger",
+            Self::Boire => "Boire",
+            Self::Toilettes => "Toilettes",
+            Self::Hygiene => "Hygiene",
+            Self::Social => "Social",
+        }
+    }
+}
+
+// Lieu ou l'employe part combler un besoin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakVenue {
+    SallePause,
+    Lavabo,
+    Distributeur,
+}
+
+impl BreakVenue {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SallePause => "salle de pause",
+            Self::Lavabo => "lavabo",
+            Self::Distributeur => "distributeur",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct NeedRule {
+    pub need: NeedKind,
+    // Points perdus par heure de presence au poste.
+    pub decay_per_h: f64,
+    pub venue: BreakVenue,
+    // Points regagnes par heure sur le lieu de pause.
+    pub refill_per_h: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct NeedsBook {
+    pub schema_version: u32,
+    pub rules: Vec<NeedRule>,
+    // Sous ce niveau l'employe quitte son poste; il revient une fois break_target atteint.
+    pub break_threshold: f64,
+    pub break_target: f64,
+    // Recuperation hors poste (a la maison), tous besoins confondus.
+    pub off_shift_recovery_per_h: f64,
+    // Sans lieu de pause atteignable, pause sur place a rendement reduit.
+    pub no_venue_refill_ratio: f64,
+    // Seuil de confort: en dessous, le manque ralentit et fait faire des erreurs.
+    pub comfort_level: f64,
+    pub speed_penalty: f64,
+    pub defect_per_deficit: f64,
+}
+
+fn rule(need: NeedKind, decay_per_h: f64, venue: BreakVenue, refill_per_h: f64) -> NeedRule {
+    NeedRule {
+        need,
+        decay_per_h,
+        venue,
+        refill_per_h,
+    }
+}
+
+impl Default for NeedsBook {
+    fn default() -> Self {
+        Self {
+            schema_version: NEEDS_BOOK_SCHEMA_VERSION,
+            rules: vec![
+                rule(NeedKind::Manger, 12.0, BreakVenue::SallePause, 240.0),
+                rule(NeedKind::Boire, 20.0, BreakVenue::Distributeur, 600.0),
+                rule(NeedKind::Toilettes, 16.0, BreakVenue::Lavabo, 600.0),
+                rule(NeedKind::Hygiene, 6.0, BreakVenue::Lavabo, 400.0),
+                rule(NeedKind::Social, 8.0, BreakVenue::SallePause, 180.0),
+            ],
+            break_threshold: 35.0,
+            break_target: 95.0,
+            off_shift_recovery_per_h: 30.0,
+            no_venue_refill_ratio: 0.35,
+            comfort_level: 60.0,
+            speed_penalty: 0.4,
+            defect_per_deficit: 0.08,
+        }
+    }
+}
+
+impl RonBook for NeedsBook {
+    const LABEL: &'static str = "besoins";
+    const PRETTY_DEPTH: usize = 3;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != NEEDS_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema besoins invalide: attendu={} recu={}",
+                NEEDS_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        let mut needs = HashSet::new();
+        for rule in &self.rules {
+            if !needs.insert(rule.need) {
+                return Err(format!("besoin en double: {}", rule.need.label()));
+            }
+            if !rule.decay_per_h.is_finite()
+                || rule.decay_per_h < 0.0
+                || !rule.refill_per_h.is_finite()
+                || rule.refill_per_h <= 0.0
+            {
+                return Err(format!(
+                    "rythme invalide pour {}: -{}/h, +{}/h",
+                    rule.need.label(),
+                    rule.decay_per_h,
+                    rule.refill_per_h
+                ));
+            }
+        }
+        if !(0.0..NEED_FULL).contains(&self.break_threshold)
+            || self.break_target <= self.break_threshold
+            || self.break_target > NEED_FULL
+        {
+            return Err(format!(
+                "seuils de pause invalides: depart {} retour {}",
+                self.break_threshold, self.break_target
+            ));
+        }
+        if !(0.0..=NEED_FULL).contains(&self.comfort_level) || self.comfort_level <= 0.0 {
+            return Err(format!("seuil de confort invalide: {}", self.comfort_level));
+        }
+        if !self.off_shift_recovery_per_h.is_finite() || self.off_shift_recovery_per_h < 0.0 {
+            return Err("recuperation hors poste invalide".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.no_venue_refill_ratio)
+            || !(0.0..1.0).contains(&self.speed_penalty)
+        {
+            return Err("ratio de pause sur place ou penalite de vitesse hors [0, 1]".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.defect_per_deficit) {
+            return Err(format!(
+                "defaut par manque hors [0, 1]: {}",
+                self.defect_per_deficit
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl NeedsBook {
+    pub fn rule(&self, need: NeedKind) -> Option<&NeedRule> {
+        self.rules.iter().find(|rule| rule.need == need)
+    }
+
+    pub fn venue(&self, need: NeedKind) -> Option<BreakVenue> {
+        self.rule(need).map(|rule| rule.venue)
+    }
+
+    // Multiplicateur de vitesse de travail.
+    pub fn speed_factor(&self, deficit: f64) -> f64 {
+        1.0 - self.speed_penalty * deficit.clamp(0.0, 1.0)
+    }
+
+    // Probabilite de defaut ajoutee par unite produite.
+    pub fn defect(&self, deficit: f64) -> f64 {
+        self.defect_per_deficit * deficit.clamp(0.0, 1.0)
+    }
+}
+
+// Niveaux 0..100 (100 = comble); un besoin absent de la table est plein.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EmployeeNeeds {
+    pub levels: BTreeMap<NeedKind, f64>,
+    // Besoin qui a motive la pause en cours, et son lieu (None = sur place).
+    pub break_need: Option<NeedKind>,
+    pub break_venue: Option<BreakVenue>,
+    pub breaks_total: u32,
+}
+
+impl EmployeeNeeds {
+    // Niveaux de depart decales par employe pour etaler les pauses.
+    pub fn seeded(seed: u64) -> Self {
+        let levels = NeedKind::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, need)| {
+                let offset = (seed.wrapping_mul(53).wrapping_add(i as u64 * 29)) % 40;
+                (*need, NEED_FULL - offset as f64)
+            })
+            .collect();
+        Self {
+            levels,
+            ..Self::default()
+        }
+    }
+
+    pub fn level(&self, need: NeedKind) -> f64 {
+        self.levels.get(&need).copied().unwrap_or(NEED_FULL)
+    }
+
+    fn add(&mut self, need: NeedKind, delta: f64) {
+        let level = (self.level(need) + delta).clamp(0.0, NEED_FULL);
+        self.levels.insert(need, level);
+    }
+
+    pub fn decay(&mut self, book: &NeedsBook, dt_h: f64) {
+        for rule in &book.rules {
+            self.add(rule.need, -rule.decay_per_h * dt_h);
+        }
+    }
+
+    // Remplit les besoins servis par ce lieu (tous si None: pause sur place).
+    pub fn refill_at(&mut self, book: &NeedsBook, venue: Option<BreakVenue>, dt_h: f64) {
+        for rule in &book.rules {
+            match venue {
+                Some(venue) if venue == rule.venue => self.add(rule.need, rule.refill_per_h * dt_h),
+                Some(_) => {}
+                None => self.add(
+                    rule.need,
+                    rule.refill_per_h * book.no_venue_refill_ratio * dt_h,
+                ),
+            }
+        }
+    }
+
+    pub fn recover(&mut self, book: &NeedsBook, dt_h: f64) {
+        for rule in &book.rules {
+            self.add(rule.need, book.off_shift_recovery_per_h * dt_h);
+        }
+    }
+
+    // Manque moyen sous le seuil de confort, 0 (satisfait) .. 1 (tout a zero).
+    pub fn deficit(&self, book: &NeedsBook) -> f64 {
+        if book.rules.is_empty() {
+            return 0.0;
+        }
+        let total = book.rules.iter().fold(0.0, |acc, rule| {
+            acc + ((book.comfort_level - self.level(rule.need)) / book.comfort_level)
+                .clamp(0.0, 1.0)
+        });
+        total / book.rules.len() as f64
+    }
+
+    // Besoin le plus bas sous le seuil de depart en pause.
+    pub fn most_urgent(&self, book: &NeedsBook) -> Option<NeedKind> {
+        book.rules
+            .iter()
+            .map(|rule| (rule.need, self.level(rule.need)))
+            .filter(|(_, level)| *level < book.break_threshold)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(need, _)| need)
+    }
+
+    pub fn summary(&self) -> String {
+        NeedKind::ALL
+            .iter()
+            .map(|need| format!("{} {:.0}", need.label(), self.level(*need)))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = NeedsBook::default();
+        assert!(book.validate().is_ok());
+
+        let mut twice = book.clone();
+        twice.rules.push(twice.rules[0]);
+        assert!(twice.validate().is_err());
+        let mut inverted = book.clone();
+        inverted.break_target = 20.0;
+        assert!(inverted.validate().is_err());
+        assert_eq!(book.venue(NeedKind::Toilettes), Some(BreakVenue::Lavabo));
+    }
+
+    #[test]
+    fn needs_decay_trigger_a_break_and_refill_at_the_venue() {
+        let book = NeedsBook::default();
+        let mut needs = EmployeeNeeds::default();
+        assert_eq!(needs.deficit(&book), 0.0);
+        assert_eq!(needs.most_urgent(&book), None);
+
+        // Boire descend le plus vite: 3.5 h de poste le font passer sous 35.
+        needs.decay(&book, 3.5);
+        assert_eq!(needs.level(NeedKind::Boire), 30.0);
+        assert_eq!(needs.most_urgent(&book), Some(NeedKind::Boire));
+        let deficit = needs.deficit(&book);
+        assert!(deficit > 0.0);
+        assert!(book.speed_factor(deficit) < 1.0);
+        assert!(book.defect(deficit) > 0.0);
+
+        let manger = needs.level(NeedKind::Manger);
+        needs.refill_at(&book, Some(BreakVenue::Distributeur), 0.1);
+        assert_eq!(needs.level(NeedKind::Boire), 90.0);
+        assert_eq!(needs.level(NeedKind::Manger), manger);
+        needs.refill_at(&book, None, 0.1);
+        assert!(needs.level(NeedKind::Manger) > manger);
+
+        let seeded = EmployeeNeeds::seeded(3);
+        assert!(
+            NeedKind::ALL
+                .iter()
+                .all(|need| (61.0..=100.0).contains(&seeded.level(*need)))
+        );
+        assert_ne!(seeded, EmployeeNeeds::seeded(4));
+    }
+}
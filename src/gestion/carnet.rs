@@ -0,0 +1,258 @@
+use super::marche::ContractId;
+use serde::{Deserialize, Serialize};
+
+pub type OrderId = u32;
+
+// Fenetres glissantes affichees dans l'onglet Ventes.
+pub const FULFILMENT_WINDOWS_H: [(&str, f64); 3] = [("24h", 24.0), ("7j", 168.0), ("30j", 720.0)];
+// Fenetre de l'OTIF des KPI usine.
+pub const KPI_FULFILMENT_WINDOW_H: f64 = 168.0;
+// Les commandes closes plus anciennes que la plus longue fenetre sont oubliees.
+const ORDER_RETENTION_H: f64 = 720.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CustomerOrder {
+    pub id: OrderId,
+    pub customer: String,
+    pub product_id: String,
+    #[serde(default)]
+    pub contract_id: Option<ContractId>,
+    pub placed_at_s: f64,
+    pub requested_at_s: f64,
+    pub qty: u32,
+    pub shipped_qty: u32,
+    // Date de la derniere expedition.
+    pub shipped_at_s: Option<f64>,
+    pub closed_at_s: Option<f64>,
+}
+
+impl CustomerOrder {
+    pub fn remaining(&self) -> u32 {
+        self.qty.saturating_sub(self.shipped_qty)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed_at_s.is_some()
+    }
+
+    pub fn on_time_in_full(&self) -> bool {
+        self.remaining() == 0
+            && self
+                .shipped_at_s
+                .is_some_and(|shipped_s| shipped_s <= self.requested_at_s)
+    }
+
+    // Retard en heures: a la livraison complete, sinon a date tant que la commande reste due.
+    pub fn lateness_h(&self, now_s: f64) -> f64 {
+        let reference_s = if self.remaining() == 0 {
+            self.shipped_at_s.unwrap_or(now_s)
+        } else {
+            self.closed_at_s.unwrap_or(now_s)
+        };
+        ((reference_s - self.requested_at_s) / 3600.0).max(0.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FulfilmentStats {
+    pub orders: u32,
+    pub on_time_in_full: u32,
+    pub qty_requested: u32,
+    pub qty_shipped: u32,
+    pub lateness_h_total: f64,
+}
+
+impl FulfilmentStats {
+    // Sans commande echue dans la fenetre, rien n'est en defaut.
+    pub fn otif(&self) -> f64 {
+        if self.orders == 0 {
+            1.0
+        } else {
+            self.on_time_in_full as f64 / self.orders as f64
+        }
+    }
+
+    pub fn fill_rate(&self) -> f64 {
+        if self.qty_requested == 0 {
+            1.0
+        } else {
+            self.qty_shipped as f64 / self.qty_requested as f64
+        }
+    }
+
+    pub fn mean_lateness_h(&self) -> f64 {
+        if self.orders == 0 {
+            0.0
+        } else {
+            self.lateness_h_total / self.orders as f64
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrderLedger {
+    pub next_order_id: OrderId,
+    pub orders: Vec<CustomerOrder>,
+}
+
+impl Default for OrderLedger {
+    fn default() -> Self {
+        Self {
+            next_order_id: 1,
+            orders: Vec::new(),
+        }
+    }
+}
+
+impl OrderLedger {
+    pub fn open(
+        &mut self,
+        customer: &str,
+        product_id: &str,
+        contract_id: Option<ContractId>,
+        qty: u32,
+        placed_at_s: f64,
+        requested_at_s: f64,
+    ) -> OrderId {
+        let id = self.next_order_id;
+        self.next_order_id = self.next_order_id.saturating_add(1);
+        self.orders.push(CustomerOrder {
+            id,
+            customer: customer.to_string(),
+            product_id: product_id.to_string(),
+            contract_id,
+            placed_at_s,
+            requested_at_s,
+            qty,
+            shipped_qty: 0,
+            shipped_at_s: None,
+            closed_at_s: None,
+        });
+        id
+    }
+
+    pub fn order(&self, id: OrderId) -> Option<&CustomerOrder> {
+        self.orders.iter().find(|order| order.id == id)
+    }
+
+    fn open_order_mut(&mut self, id: OrderId) -> Result<&mut CustomerOrder, String> {
+        let order = self
+            .orders
+            .iter_mut()
+            .find(|order| order.id == id)
+            .ok_or_else(|| format!("commande client introuvable: #{id}"))?;
+        if order.is_closed() {
+            return Err(format!("commande client #{id} deja close"));
+        }
+        Ok(order)
+    }
+
+    // La commande se clot d'elle-meme une fois servie en totalite.
+    pub fn record_shipment(&mut self, id: OrderId, qty: u32, now_s: f64) -> Result<(), String> {
+        let order = self.open_order_mut(id)?;
+        if qty == 0 {
+            return Ok(());
+        }
+        if qty > order.remaining() {
+            return Err(format!(
+                "commande client #{id}: expedition {qty} > reste {}",
+                order.remaining()
+            ));
+        }
+        order.shipped_qty += qty;
+        order.shipped_at_s = Some(now_s);
+        if order.remaining() == 0 {
+            order.closed_at_s = Some(now_s);
+        }
+        Ok(())
+    }
+
+    // Annulation du reliquat: la commande reste comptee avec ce qui a ete expedie.
+    pub fn close(&mut self, id: OrderId, now_s: f64) -> Result<(), String> {
+        let order = self.open_order_mut(id)?;
+        order.closed_at_s = Some(now_s);
+        Ok(())
+    }
+
+    // Commandes dont la date demandee tombe dans la fenetre et est deja passee.
+    pub fn stats(&self, now_s: f64, window_h: f64) -> FulfilmentStats {
+        let since_s = now_s - window_h * 3600.0;
+        let mut stats = FulfilmentStats {
+            orders: 0,
+            on_time_in_full: 0,
+            qty_requested: 0,
+            qty_shipped: 0,
+            lateness_h_total: 0.0,
+        };
+        for order in self
+            .orders
+            .iter()
+            .filter(|order| order.requested_at_s > since_s && order.requested_at_s <= now_s)
+        {
+            stats.orders += 1;
+            if order.on_time_in_full() {
+                stats.on_time_in_full += 1;
+            }
+            stats.qty_requested = stats.qty_requested.saturating_add(order.qty);
+            stats.qty_shipped = stats.qty_shipped.saturating_add(order.shipped_qty);
+            stats.lateness_h_total += order.lateness_h(now_s);
+        }
+        stats
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &CustomerOrder> {
+        self.orders.iter().filter(|order| !order.is_closed())
+    }
+
+    pub fn prune(&mut self, now_s: f64) {
+        let horizon_s = now_s - ORDER_RETENTION_H * 3600.0;
+        self.orders
+            .retain(|order| !order.is_closed() || order.requested_at_s > horizon_s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_windows_measure_otif_fill_rate_and_lateness() {
+        let hour = 3600.0;
+        let mut ledger = OrderLedger::default();
+        let on_time = ledger.open("A", "ail", None, 10, 0.0, 10.0 * hour);
+        let late = ledger.open("B", "ail", None, 10, 0.0, 20.0 * hour);
+        let short = ledger.open("C", "ail", None, 10, 0.0, 30.0 * hour);
+        let future = ledger.open("D", "ail", None, 10, 0.0, 90.0 * hour);
+
+        ledger.record_shipment(on_time, 10, 5.0 * hour).expect("ok");
+        assert!(ledger.order(on_time).expect("order").is_closed());
+        ledger.record_shipment(late, 4, 15.0 * hour).expect("ok");
+        ledger.record_shipment(late, 6, 24.0 * hour).expect("ok");
+        ledger.record_shipment(short, 5, 25.0 * hour).expect("ok");
+        ledger.close(short, 40.0 * hour).expect("ok");
+        assert!(ledger.record_shipment(short, 1, 41.0 * hour).is_err());
+        assert!(ledger.record_shipment(future, 11, 41.0 * hour).is_err());
+
+        let now = 48.0 * hour;
+        let all = ledger.stats(now, 48.0);
+        assert_eq!(all.orders, 3);
+        assert_eq!(all.on_time_in_full, 1);
+        assert!((all.otif() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((all.fill_rate() - 25.0 / 30.0).abs() < 1e-9);
+        // 0h + 4h (livree a 24h) + 10h (reliquat annule a 40h).
+        assert!((all.mean_lateness_h() - 14.0 / 3.0).abs() < 1e-9);
+
+        let recent = ledger.stats(now, 24.0);
+        assert_eq!(recent.orders, 1);
+        assert_eq!(recent.otif(), 0.0);
+        assert_eq!(ledger.stats(now, 1.0).otif(), 1.0);
+
+        ledger.prune(now + ORDER_RETENTION_H * hour);
+        assert_eq!(ledger.orders.len(), 1);
+        assert_eq!(
+            ledger.open_orders().next().map(|order| order.id),
+            Some(future)
+        );
+    }
+}
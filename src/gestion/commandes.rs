@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-use super::personnel::{EmployeeId, EmployeeRole, ProductionLineId};
+use super::financement::LoanId;
+use super::fournisseurs::default_raw_supplier_id;
+use super::marche::ContractId;
+use super::personnel::{EmployeeId, EmployeeRole, ProductionLineId, WorkType};
+use super::recrutement::CandidateId;
+use super::stock::ReorderPolicy;
+use crate::sim::{BlockId, BlockKind, BlockOrientation, BuildFloorKind, ZoneKind};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -21,6 +27,103 @@ pub enum SimCommand {
         max_temps: u8,
     },
     BuyRawStock {
+        #[serde(default = "default_raw_supplier_id")]
+        supplier_id: String,
         qty: u32,
     },
+    BuySpareParts {
+        qty: u32,
+    },
+    SetReorderPolicy {
+        policy: ReorderPolicy,
+    },
+    SetWorkPriority {
+        employee_id: EmployeeId,
+        work: WorkType,
+        priority: u8,
+    },
+    ResetWorkPriorities {
+        employee_id: EmployeeId,
+    },
+    SetLineShiftTemplate {
+        line_id: ProductionLineId,
+        template_id: String,
+    },
+    SetEmployeeShift {
+        employee_id: EmployeeId,
+        slot: u8,
+    },
+    SetEmployeeWage {
+        employee_id: EmployeeId,
+        hourly_wage: f64,
+    },
+    ScheduleTraining {
+        employee_id: EmployeeId,
+        course_id: String,
+    },
+    ChangeEmployeeRole {
+        employee_id: EmployeeId,
+        role: EmployeeRole,
+    },
+    ShortlistCandidate {
+        candidate_id: CandidateId,
+    },
+    InterviewCandidate {
+        candidate_id: CandidateId,
+    },
+    OfferCandidate {
+        candidate_id: CandidateId,
+        hourly_wage: f64,
+    },
+    // Construction facturee; les poses scriptees (Papa) ont leur chemin interne.
+    PlaceBlock {
+        kind: BlockKind,
+        tile: (i32, i32),
+        orientation: BlockOrientation,
+    },
+    RemoveBlock {
+        block_id: BlockId,
+    },
+    MoveBlock {
+        block_id: BlockId,
+        tile: (i32, i32),
+    },
+    RotateBlock {
+        block_id: BlockId,
+        orientation: BlockOrientation,
+    },
+    PaintZoneRect {
+        origin: (i32, i32),
+        size: (i32, i32),
+        zone: ZoneKind,
+    },
+    PaintFloorRect {
+        origin: (i32, i32),
+        size: (i32, i32),
+        floor: BuildFloorKind,
+    },
+    // Retour au sol standard, sans frais.
+    ResetFloorRect {
+        origin: (i32, i32),
+        size: (i32, i32),
+    },
+    SetSalePrice {
+        price_eur: f64,
+    },
+    // Decors "Lavabo" poses a l'editeur: lieux de pause du personnel.
+    SetLavaboTiles {
+        tiles: Vec<(i32, i32)>,
+    },
+    AcceptContract {
+        contract_id: ContractId,
+    },
+    DeclineContract {
+        contract_id: ContractId,
+    },
+    TakeLoan {
+        offer_id: String,
+    },
+    RepayLoan {
+        loan_id: LoanId,
+    },
 }
@@ -0,0 +1,634 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::sim::BlockId;
+
+// Durees d'amortissement lineaire, en jours de jeu.
+pub const EQUIPMENT_DEPRECIATION_DAYS: f64 = 90.0;
+pub const FITOUT_DEPRECIATION_DAYS: f64 = 180.0;
+const JOURNAL_CAPACITY: usize = 400;
+const DAILY_REPORTS_KEPT: usize = 30;
+const WEEKLY_REPORTS_KEPT: usize = 12;
+const DAY_S: f64 = 86_400.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountClass {
+    Actif,
+    CapitauxPropres,
+    Dette,
+    Produit,
+    Charge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CashActivity {
+    Exploitation,
+    Investissement,
+    Financement,
+}
+
+impl CashActivity {
+    pub const ALL: [Self; 3] = [Self::Exploitation, Self::Investissement, Self::Financement];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Exploitation => "Exploitation",
+            Self::Investissement => "Investissement",
+            Self::Financement => "Financement",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Account {
+    Tresorerie,
+    Immobilisations,
+    AmortissementsCumules,
+    Capital,
+    Emprunts,
+    Ventes,
+    ProduitsCession,
+    Salaires,
+    AchatsMatiere,
+    PiecesDetachees,
+    Interim,
+    Recrutement,
+    Penalites,
+    Energie,
+    Departs,
+    Formation,
+    Accidents,
+    Amortissements,
+    ValeurCession,
+    Interets,
+}
+
+impl Account {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Tresorerie => "Tresorerie",
+            Self::Immobilisations => "Immobilisations",
+            Self::AmortissementsCumules => "Amortissements cumules",
+            Self::Capital => "Capital",
+            Self::Emprunts => "Emprunts bancaires",
+            Self::Ventes => "Ventes",
+            Self::ProduitsCession => "Produits de cession",
+            Self::Salaires => "Salaires",
+            Self::AchatsMatiere => "Achats matiere",
+            Self::PiecesDetachees => "Pieces detachees",
+            Self::Interim => "Frais d'agence interim",
+            Self::Recrutement => "Recrutement",
+            Self::Penalites => "Penalites clients",
+            Self::Energie => "Energie",
+            Self::Departs => "Soldes de tout compte",
+            Self::Formation => "Formation",
+            Self::Accidents => "Accidents du travail",
+            Self::Amortissements => "Dotations aux amortissements",
+            Self::ValeurCession => "Valeur nette des actifs cedes",
+            Self::Interets => "Charges d'interets",
+        }
+    }
+
+    pub fn class(self) -> AccountClass {
+        match self {
+            Self::Tresorerie | Self::Immobilisations | Self::AmortissementsCumules => {
+                AccountClass::Actif
+            }
+            Self::Capital => AccountClass::CapitauxPropres,
+            Self::Emprunts => AccountClass::Dette,
+            Self::Ventes | Self::ProduitsCession => AccountClass::Produit,
+            Self::Salaires
+            | Self::AchatsMatiere
+            | Self::PiecesDetachees
+            | Self::Interim
+            | Self::Recrutement
+            | Self::Penalites
+            | Self::Energie
+            | Self::Departs
+            | Self::Formation
+            | Self::Accidents
+            | Self::Amortissements
+            | Self::ValeurCession
+            | Self::Interets => AccountClass::Charge,
+        }
+    }
+
+    // Rubrique du tableau de flux pour la contrepartie d'un mouvement de tresorerie.
+    pub fn cash_activity(self) -> CashActivity {
+        match self {
+            Self::Immobilisations | Self::ProduitsCession => CashActivity::Investissement,
+            Self::Capital | Self::Emprunts => CashActivity::Financement,
+            _ => CashActivity::Exploitation,
+        }
+    }
+
+    fn debit_normal(self) -> bool {
+        matches!(self.class(), AccountClass::Actif | AccountClass::Charge)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: u64,
+    pub at_s: f64,
+    pub label: String,
+    pub debit: Account,
+    pub credit: Account,
+    pub amount_eur: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FixedAsset {
+    pub id: u64,
+    pub label: String,
+    #[serde(default)]
+    pub block_id: Option<BlockId>,
+    pub cost_eur: f64,
+    pub life_days: f64,
+    pub depreciated_eur: f64,
+}
+
+impl FixedAsset {
+    pub fn net_book_value(&self) -> f64 {
+        (self.cost_eur - self.depreciated_eur).max(0.0)
+    }
+}
+
+// Compte de resultat et flux de tresorerie d'une periode (jour ou semaine).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeriodReport {
+    pub index: u64,
+    pub start_s: f64,
+    pub end_s: f64,
+    pub opening_cash_eur: f64,
+    // Mouvements dans le sens normal du compte (charges au debit, produits au credit).
+    pub movements: BTreeMap<Account, f64>,
+    // Entrees (+) et sorties (-) de tresorerie par compte de contrepartie.
+    pub cash_flows: BTreeMap<Account, f64>,
+}
+
+impl PeriodReport {
+    fn new(index: u64, start_s: f64, opening_cash_eur: f64) -> Self {
+        Self {
+            index,
+            start_s,
+            end_s: start_s,
+            opening_cash_eur,
+            movements: BTreeMap::new(),
+            cash_flows: BTreeMap::new(),
+        }
+    }
+
+    pub fn amount(&self, account: Account) -> f64 {
+        self.movements.get(&account).copied().unwrap_or(0.0)
+    }
+
+    fn sum_class(&self, class: AccountClass) -> f64 {
+        self.movements
+            .iter()
+            .filter(|(account, _)| account.class() == class)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    pub fn revenue(&self) -> f64 {
+        self.sum_class(AccountClass::Produit)
+    }
+
+    pub fn expenses(&self) -> f64 {
+        self.sum_class(AccountClass::Charge)
+    }
+
+    pub fn net_income(&self) -> f64 {
+        self.revenue() - self.expenses()
+    }
+
+    pub fn income_lines(&self) -> impl Iterator<Item = (Account, f64)> + '_ {
+        self.movements
+            .iter()
+            .filter(|(account, amount)| {
+                matches!(
+                    account.class(),
+                    AccountClass::Produit | AccountClass::Charge
+                ) && amount.abs() > 0.005
+            })
+            .map(|(account, amount)| (*account, *amount))
+    }
+
+    pub fn cash_flow(&self, activity: CashActivity) -> f64 {
+        self.cash_flows
+            .iter()
+            .filter(|(account, _)| account.cash_activity() == activity)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    pub fn net_cash_flow(&self) -> f64 {
+        self.cash_flows.values().sum()
+    }
+
+    pub fn closing_cash_eur(&self) -> f64 {
+        self.opening_cash_eur + self.net_cash_flow()
+    }
+
+    fn record(&mut self, debit: Account, credit: Account, amount: f64) {
+        for (account, on_debit) in [(debit, true), (credit, false)] {
+            let natural = if on_debit == account.debit_normal() {
+                amount
+            } else {
+                -amount
+            };
+            *self.movements.entry(account).or_insert(0.0) += natural;
+        }
+        if debit == Account::Tresorerie {
+            *self.cash_flows.entry(credit).or_insert(0.0) += amount;
+        }
+        if credit == Account::Tresorerie {
+            *self.cash_flows.entry(debit).or_insert(0.0) -= amount;
+        }
+    }
+}
+
+// Une tresorerie negative est presentee au passif comme decouvert bancaire.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BalanceSheet {
+    pub cash_eur: f64,
+    pub fixed_assets_gross_eur: f64,
+    pub accumulated_depreciation_eur: f64,
+    pub capital_eur: f64,
+    pub retained_earnings_eur: f64,
+    pub loans_eur: f64,
+}
+
+impl BalanceSheet {
+    pub fn fixed_assets_net_eur(&self) -> f64 {
+        self.fixed_assets_gross_eur - self.accumulated_depreciation_eur
+    }
+
+    pub fn bank_overdraft_eur(&self) -> f64 {
+        (-self.cash_eur).max(0.0)
+    }
+
+    pub fn total_assets_eur(&self) -> f64 {
+        self.cash_eur.max(0.0) + self.fixed_assets_net_eur()
+    }
+
+    pub fn total_equity_eur(&self) -> f64 {
+        self.capital_eur + self.retained_earnings_eur
+    }
+
+    pub fn total_liabilities_eur(&self) -> f64 {
+        self.loans_eur + self.bank_overdraft_eur()
+    }
+
+    pub fn total_equity_and_liabilities_eur(&self) -> f64 {
+        self.total_equity_eur() + self.total_liabilities_eur()
+    }
+}
+
+// Grand livre en partie double: chaque ecriture debite un compte et en credite un autre.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ledger {
+    // Soldes signes, debit positif.
+    pub balances: BTreeMap<Account, f64>,
+    pub journal: Vec<JournalEntry>,
+    pub next_entry_id: u64,
+    pub assets: Vec<FixedAsset>,
+    pub next_asset_id: u64,
+    pub day: PeriodReport,
+    pub week: PeriodReport,
+    pub daily_reports: Vec<PeriodReport>,
+    pub weekly_reports: Vec<PeriodReport>,
+    pub now_s: f64,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self {
+            balances: BTreeMap::new(),
+            journal: Vec::new(),
+            next_entry_id: 1,
+            assets: Vec::new(),
+            next_asset_id: 1,
+            day: PeriodReport::new(0, 0.0, 0.0),
+            week: PeriodReport::new(0, 0.0, 0.0),
+            daily_reports: Vec::new(),
+            weekly_reports: Vec::new(),
+            now_s: 0.0,
+        }
+    }
+}
+
+impl Ledger {
+    pub fn is_empty(&self) -> bool {
+        self.journal.is_empty() && self.balances.is_empty()
+    }
+
+    // Ecriture d'ouverture: la tresorerie de depart est apportee en capital.
+    pub fn open(&mut self, cash_eur: f64) {
+        if cash_eur >= 0.0 {
+            self.post(
+                "Apport initial",
+                Account::Tresorerie,
+                Account::Capital,
+                cash_eur,
+            );
+        } else {
+            self.post(
+                "Apport initial",
+                Account::Capital,
+                Account::Tresorerie,
+                -cash_eur,
+            );
+        }
+    }
+
+    pub fn balance(&self, account: Account) -> f64 {
+        self.balances.get(&account).copied().unwrap_or(0.0)
+    }
+
+    pub fn post(&mut self, label: &str, debit: Account, credit: Account, amount_eur: f64) {
+        if !amount_eur.is_finite() || amount_eur <= 0.0 || debit == credit {
+            return;
+        }
+        *self.balances.entry(debit).or_insert(0.0) += amount_eur;
+        *self.balances.entry(credit).or_insert(0.0) -= amount_eur;
+        self.day.record(debit, credit, amount_eur);
+        self.week.record(debit, credit, amount_eur);
+
+        // Les flux continus (paie, amortissement) sont regroupes par heure.
+        let hour = (self.now_s / 3600.0).floor();
+        if let Some(last) = self.journal.last_mut()
+            && last.debit == debit
+            && last.credit == credit
+            && last.label == label
+            && (last.at_s / 3600.0).floor() == hour
+        {
+            last.amount_eur += amount_eur;
+            return;
+        }
+        if self.journal.len() >= JOURNAL_CAPACITY {
+            self.journal.remove(0);
+        }
+        self.journal.push(JournalEntry {
+            id: self.next_entry_id,
+            at_s: self.now_s,
+            label: label.to_string(),
+            debit,
+            credit,
+            amount_eur,
+        });
+        self.next_entry_id = self.next_entry_id.saturating_add(1);
+    }
+
+    pub fn acquire_asset(
+        &mut self,
+        label: &str,
+        block_id: Option<BlockId>,
+        cost_eur: f64,
+        life_days: f64,
+    ) {
+        if !cost_eur.is_finite() || cost_eur <= 0.0 {
+            return;
+        }
+        self.post(
+            label,
+            Account::Immobilisations,
+            Account::Tresorerie,
+            cost_eur,
+        );
+        self.assets.push(FixedAsset {
+            id: self.next_asset_id,
+            label: label.to_string(),
+            block_id,
+            cost_eur,
+            life_days: life_days.max(1.0),
+            depreciated_eur: 0.0,
+        });
+        self.next_asset_id = self.next_asset_id.saturating_add(1);
+    }
+
+    // Cession: le prix entre en produit, la valeur nette sort en charge. Renvoie cette valeur nette.
+    pub fn dispose_asset(&mut self, block_id: BlockId, proceeds_eur: f64) -> f64 {
+        self.post(
+            "Cession equipement",
+            Account::Tresorerie,
+            Account::ProduitsCession,
+            proceeds_eur,
+        );
+        let Some(index) = self
+            .assets
+            .iter()
+            .position(|asset| asset.block_id == Some(block_id))
+        else {
+            return 0.0;
+        };
+        let asset = self.assets.remove(index);
+        let net = asset.net_book_value();
+        self.post(
+            "Sortie d'actif",
+            Account::AmortissementsCumules,
+            Account::Immobilisations,
+            asset.depreciated_eur.min(asset.cost_eur),
+        );
+        self.post(
+            "Sortie d'actif",
+            Account::ValeurCession,
+            Account::Immobilisations,
+            net,
+        );
+        net
+    }
+
+    // Dote les amortissements jusqu'a now_s en cloturant chaque journee franchie; renvoie la dotation.
+    pub fn advance(&mut self, now_s: f64) -> f64 {
+        if !now_s.is_finite() || now_s <= self.now_s {
+            return 0.0;
+        }
+        let mut depreciation = 0.0;
+        loop {
+            let day_end_s = (self.day.index + 1) as f64 * DAY_S;
+            depreciation += self.depreciate_until(now_s.min(day_end_s));
+            if now_s < day_end_s {
+                break;
+            }
+            self.close_day();
+        }
+        depreciation
+    }
+
+    // Amene la journee day_index jusqu'a son terme sans la cloturer, pour y passer une
+    // ecriture qui lui appartient (facture close a minuit); renvoie la dotation passee.
+    pub fn settle_day(&mut self, day_index: u64) -> f64 {
+        let mut depreciation = 0.0;
+        while self.day.index < day_index {
+            let day_end_s = (self.day.index + 1) as f64 * DAY_S;
+            depreciation += self.depreciate_until(day_end_s);
+            self.close_day();
+        }
+        if self.day.index == day_index {
+            depreciation += self.depreciate_until((day_index + 1) as f64 * DAY_S);
+        }
+        depreciation
+    }
+
+    // Reprise d'une sauvegarde sans grand livre: les periodes demarrent a la date courante.
+    pub fn start_at(&mut self, now_s: f64) {
+        if !now_s.is_finite() || now_s <= 0.0 {
+            return;
+        }
+        let day_index = (now_s / DAY_S).floor() as u64;
+        let cash = self.balance(Account::Tresorerie);
+        self.now_s = now_s;
+        self.day = PeriodReport::new(day_index, day_index as f64 * DAY_S, cash);
+        self.week = PeriodReport::new(day_index / 7, (day_index / 7) as f64 * 7.0 * DAY_S, cash);
+    }
+
+    fn depreciate_until(&mut self, until_s: f64) -> f64 {
+        let dt_s = until_s - self.now_s;
+        if dt_s <= 0.0 {
+            return 0.0;
+        }
+        self.now_s = until_s;
+        let mut depreciation = 0.0;
+        for asset in &mut self.assets {
+            let share = asset.cost_eur * dt_s / (asset.life_days * DAY_S);
+            let amount = share.min(asset.net_book_value());
+            asset.depreciated_eur += amount;
+            depreciation += amount;
+        }
+        self.post(
+            "Dotation aux amortissements",
+            Account::Amortissements,
+            Account::AmortissementsCumules,
+            depreciation,
+        );
+        depreciation
+    }
+
+    fn close_day(&mut self) {
+        let cash = self.balance(Account::Tresorerie);
+        let day_index = self.day.index + 1;
+        let day_start_s = day_index as f64 * DAY_S;
+        let mut closed = std::mem::replace(
+            &mut self.day,
+            PeriodReport::new(day_index, day_start_s, cash),
+        );
+        closed.end_s = day_start_s;
+        push_capped(&mut self.daily_reports, closed, DAILY_REPORTS_KEPT);
+
+        let week_index = day_index / 7;
+        if week_index > self.week.index {
+            let mut closed = std::mem::replace(
+                &mut self.week,
+                PeriodReport::new(week_index, day_start_s, cash),
+            );
+            closed.end_s = day_start_s;
+            push_capped(&mut self.weekly_reports, closed, WEEKLY_REPORTS_KEPT);
+        }
+    }
+
+    pub fn balance_sheet(&self) -> BalanceSheet {
+        let retained_earnings_eur = -self
+            .balances
+            .iter()
+            .filter(|(account, _)| {
+                matches!(
+                    account.class(),
+                    AccountClass::Produit | AccountClass::Charge
+                )
+            })
+            .map(|(_, balance)| balance)
+            .sum::<f64>();
+        BalanceSheet {
+            cash_eur: self.balance(Account::Tresorerie),
+            fixed_assets_gross_eur: self.balance(Account::Immobilisations),
+            accumulated_depreciation_eur: -self.balance(Account::AmortissementsCumules),
+            capital_eur: -self.balance(Account::Capital),
+            retained_earnings_eur,
+            loans_eur: -self.balance(Account::Emprunts),
+        }
+    }
+
+    // Derniere journee close, sinon la journee en cours.
+    pub fn last_daily_report(&self) -> &PeriodReport {
+        self.daily_reports.last().unwrap_or(&self.day)
+    }
+
+    pub fn last_weekly_report(&self) -> &PeriodReport {
+        self.weekly_reports.last().unwrap_or(&self.week)
+    }
+}
+
+fn push_capped(reports: &mut Vec<PeriodReport>, report: PeriodReport, cap: usize) {
+    if reports.len() >= cap {
+        reports.remove(0);
+    }
+    reports.push(report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn postings_balance_and_feed_period_reports() {
+        let mut ledger = Ledger::default();
+        ledger.open(10_000.0);
+        ledger.post("Vente", Account::Tresorerie, Account::Ventes, 800.0);
+        ledger.post("Paie", Account::Salaires, Account::Tresorerie, 300.0);
+        ledger.acquire_asset("Machine", Some(7), 900.0, 90.0);
+        ledger.advance(DAY_S * 1.5);
+
+        let day0 = ledger.last_daily_report();
+        assert_eq!(day0.index, 0);
+        assert!((day0.revenue() - 800.0).abs() < 1e-9);
+        assert!((day0.expenses() - (300.0 + 10.0)).abs() < 1e-6);
+        assert!((day0.cash_flow(CashActivity::Financement) - 10_000.0).abs() < 1e-9);
+        assert!((day0.cash_flow(CashActivity::Investissement) + 900.0).abs() < 1e-9);
+        assert!((day0.closing_cash_eur() - 9_600.0).abs() < 1e-9);
+        // Dotation de la journee en cours: la moitie d'une journee.
+        assert!((ledger.day.expenses() - 5.0).abs() < 1e-6);
+
+        ledger.post("Pret", Account::Tresorerie, Account::Emprunts, 2_000.0);
+        let sheet = ledger.balance_sheet();
+        assert!((sheet.total_assets_eur() - sheet.total_equity_and_liabilities_eur()).abs() < 1e-6);
+        assert_eq!(sheet.loans_eur, 2_000.0);
+        assert!((sheet.fixed_assets_net_eur() - 885.0).abs() < 1e-6);
+
+        let net = ledger.dispose_asset(7, 540.0);
+        assert!((net - 885.0).abs() < 1e-6);
+        assert!(ledger.assets.is_empty());
+        let sheet = ledger.balance_sheet();
+        assert!(sheet.fixed_assets_gross_eur.abs() < 1e-6);
+        assert!(sheet.accumulated_depreciation_eur.abs() < 1e-6);
+        assert!((sheet.total_assets_eur() - sheet.total_equity_and_liabilities_eur()).abs() < 1e-6);
+        assert!((ledger.balances.values().sum::<f64>()).abs() < 1e-6);
+
+        ledger.advance(DAY_S * 8.0);
+        assert_eq!(ledger.last_weekly_report().index, 0);
+        assert_eq!(ledger.week.index, 1);
+    }
+
+    #[test]
+    fn settled_day_takes_its_own_postings_before_closing() {
+        let mut ledger = Ledger::default();
+        ledger.open(1_000.0);
+        ledger.acquire_asset("Machine", None, 900.0, 90.0);
+        ledger.advance(DAY_S * 0.5);
+
+        let depreciation = ledger.settle_day(0);
+        assert!((depreciation - 5.0).abs() < 1e-6);
+        assert_eq!(ledger.day.index, 0);
+        ledger.post("Energie", Account::Energie, Account::Tresorerie, 40.0);
+        ledger.advance(DAY_S + 60.0);
+
+        let day0 = ledger.last_daily_report();
+        assert_eq!(day0.index, 0);
+        assert!((day0.amount(Account::Energie) - 40.0).abs() < 1e-9);
+        assert!((day0.amount(Account::Amortissements) - 10.0).abs() < 1e-6);
+        assert_eq!(ledger.day.amount(Account::Energie), 0.0);
+    }
+}
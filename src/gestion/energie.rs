@@ -0,0 +1,371 @@
+use super::reglages::RonBook;
+use crate::sim::BlockKind;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const ENERGY_BOOK_PATH: &str = "data/energy.ron";
+const ENERGY_BOOK_SCHEMA_VERSION: u32 = 1;
+const ENERGY_BILL_HISTORY: usize = 14;
+const DAY_S: f64 = 86_400.0;
+// Le compteur integre la charge par quart d'heure, comme un compteur communicant.
+const METER_STEP_S: f64 = 900.0;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BlockPower {
+    pub block_kind: BlockKind,
+    pub busy_kw: f64,
+    // Veille: moteurs a l'arret, four maintenu en temperature.
+    pub idle_kw: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TariffSlot {
+    pub label: String,
+    // Heures de debut/fin; fin < debut = plage a cheval sur minuit.
+    pub start_h: f64,
+    pub end_h: f64,
+    pub eur_per_kwh: f64,
+}
+
+impl TariffSlot {
+    pub fn contains_hour(&self, hour: f64) -> bool {
+        if self.start_h <= self.end_h {
+            hour >= self.start_h && hour < self.end_h
+        } else {
+            hour >= self.start_h || hour < self.end_h
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} {:02.0}h-{:02.0}h {:.3} EUR/kWh",
+            self.label,
+            self.start_h,
+            self.end_h % 24.0,
+            self.eur_per_kwh
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EnergyBook {
+    pub schema_version: u32,
+    // Eclairage, compresseur, bureaux: consomme meme ligne arretee.
+    pub base_load_kw: f64,
+    pub block_power: Vec<BlockPower>,
+    pub tariffs: Vec<TariffSlot>,
+}
+
+fn power(block_kind: BlockKind, busy_kw: f64, idle_kw: f64) -> BlockPower {
+    BlockPower {
+        block_kind,
+        busy_kw,
+        idle_kw,
+    }
+}
+
+fn tariff(label: &str, start_h: f64, end_h: f64, eur_per_kwh: f64) -> TariffSlot {
+    TariffSlot {
+        label: label.to_string(),
+        start_h,
+        end_h,
+        eur_per_kwh,
+    }
+}
+
+impl Default for EnergyBook {
+    fn default() -> Self {
+        Self {
+            schema_version: ENERGY_BOOK_SCHEMA_VERSION,
+            base_load_kw: 12.0,
+            block_power: vec![
+                power(BlockKind::InputHopper, 3.0, 0.5),
+                power(BlockKind::Conveyor, 0.75, 0.1),
+                power(BlockKind::FluidityTank, 5.5, 1.5),
+                power(BlockKind::Cutter, 15.0, 1.0),
+                power(BlockKind::DistributorBelt, 2.2, 0.3),
+                power(BlockKind::DryerOven, 180.0, 60.0),
+                power(BlockKind::OvenExitConveyor, 2.2, 0.3),
+                power(BlockKind::Flaker, 18.0, 1.5),
+                power(BlockKind::SuctionPipe, 4.0, 0.5),
+                power(BlockKind::Sortex, 12.0, 3.0),
+                power(BlockKind::BlueBagChute, 0.5, 0.1),
+                power(BlockKind::RedBagChute, 0.5, 0.1),
+                power(BlockKind::MachineA, 7.5, 1.0),
+                power(BlockKind::MachineB, 7.5, 1.0),
+                power(BlockKind::Seller, 1.5, 1.5),
+            ],
+            tariffs: vec![
+                tariff("Heures creuses", 22.0, 6.0, 0.12),
+                tariff("Heures pleines", 6.0, 17.0, 0.19),
+                tariff("Pointe", 17.0, 20.0, 0.28),
+                tariff("Heures pleines", 20.0, 22.0, 0.19),
+            ],
+        }
+    }
+}
+
+impl RonBook for EnergyBook {
+    const LABEL: &'static str = "energie";
+    const PRETTY_DEPTH: usize = 3;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != ENERGY_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema energie invalide: attendu={} recu={}",
+                ENERGY_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        if !self.base_load_kw.is_finite() || self.base_load_kw < 0.0 {
+            return Err("charge de base invalide".to_string());
+        }
+        let mut kinds = HashSet::new();
+        for entry in &self.block_power {
+            if !entry.busy_kw.is_finite()
+                || !entry.idle_kw.is_finite()
+                || entry.idle_kw < 0.0
+                || entry.idle_kw > entry.busy_kw
+            {
+                return Err(format!(
+                    "puissance invalide pour {}: {} kW en marche, {} kW en veille",
+                    entry.block_kind.label(),
+                    entry.busy_kw,
+                    entry.idle_kw
+                ));
+            }
+            if !kinds.insert(entry.block_kind) {
+                return Err(format!(
+                    "puissance dupliquee pour {}",
+                    entry.block_kind.label()
+                ));
+            }
+        }
+        if self.tariffs.is_empty() {
+            return Err("aucune plage tarifaire".to_string());
+        }
+        for slot in &self.tariffs {
+            let hours_ok = [slot.start_h, slot.end_h]
+                .iter()
+                .all(|hour| hour.is_finite() && (0.0..=24.0).contains(hour));
+            if !hours_ok || slot.start_h == slot.end_h {
+                return Err(format!("plage tarifaire invalide: {}", slot.label));
+            }
+            if !slot.eur_per_kwh.is_finite() || slot.eur_per_kwh <= 0.0 {
+                return Err(format!("prix du kWh invalide: {}", slot.label));
+            }
+        }
+        // Chaque quart d'heure de la journee releve d'une et une seule plage.
+        for quarter in 0..96 {
+            let hour = (quarter as f64 + 0.5) * 0.25;
+            match self
+                .tariffs
+                .iter()
+                .filter(|slot| slot.contains_hour(hour))
+                .count()
+            {
+                0 => return Err(format!("aucun tarif energie a {hour:.2}h")),
+                1 => {}
+                _ => return Err(format!("plages tarifaires superposees a {hour:.2}h")),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EnergyBook {
+    pub fn power_kw(&self, kind: BlockKind, busy: bool) -> f64 {
+        self.block_power
+            .iter()
+            .find(|entry| entry.block_kind == kind)
+            .map_or(
+                0.0,
+                |entry| {
+                    if busy { entry.busy_kw } else { entry.idle_kw }
+                },
+            )
+    }
+
+    pub fn tariff_at(&self, hour_of_day: f64) -> &TariffSlot {
+        self.tariffs
+            .iter()
+            .find(|slot| slot.contains_hour(hour_of_day))
+            .unwrap_or(&self.tariffs[0])
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EnergyBill {
+    pub day_index: u64,
+    pub kwh: f64,
+    pub cost_eur: f64,
+    pub boxes: u32,
+    pub closed_at_s: f64,
+}
+
+impl EnergyBill {
+    pub fn kwh_per_box(&self) -> Option<f64> {
+        (self.boxes > 0).then(|| self.kwh / self.boxes as f64)
+    }
+
+    pub fn mean_eur_per_kwh(&self) -> f64 {
+        if self.kwh > 0.0 {
+            self.cost_eur / self.kwh
+        } else {
+            0.0
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let per_box = self
+            .kwh_per_box()
+            .map_or("-".to_string(), |kwh| format!("{kwh:.1}"));
+        format!(
+            "Facture energie jour {}: {:.0} kWh, {:.0} EUR ({:.3} EUR/kWh, {} kWh/carton)",
+            self.day_index + 1,
+            self.kwh,
+            self.cost_eur,
+            self.mean_eur_per_kwh(),
+            per_box
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnergyState {
+    // Puissance appelee au dernier pas de simulation.
+    pub load_kw: f64,
+    pub day_index: u64,
+    pub day_kwh: f64,
+    pub day_cost_eur: f64,
+    pub day_boxes_start: u32,
+    pub total_kwh: f64,
+    pub total_cost_eur: f64,
+    pub bills: Vec<EnergyBill>,
+}
+
+impl EnergyState {
+    // Integre la charge sur [start_s, start_s + dt_s] au tarif de chaque quart d'heure;
+    // renvoie les factures des journees cloturees au passage.
+    pub fn tick(
+        &mut self,
+        book: &EnergyBook,
+        load_kw: f64,
+        start_s: f64,
+        dt_s: f64,
+        boxes_total: u32,
+    ) -> Vec<EnergyBill> {
+        let mut bills = Vec::new();
+        if !load_kw.is_finite() || !start_s.is_finite() || !dt_s.is_finite() || dt_s <= 0.0 {
+            return bills;
+        }
+        self.load_kw = load_kw.max(0.0);
+        let end_s = start_s + dt_s;
+        let mut at_s = start_s;
+        // Journee anterieure restee ouverte (vieille sauvegarde): cloture avant mesure.
+        let start_day = (start_s / DAY_S).floor() as u64;
+        if start_day != self.day_index {
+            bills.extend(self.close_day(start_day, start_s, boxes_total));
+        }
+        while at_s < end_s {
+            let segment_end_s = (((at_s / METER_STEP_S).floor() + 1.0) * METER_STEP_S).min(end_s);
+            let hour_of_day = ((at_s + segment_end_s) * 0.5 / 3600.0).rem_euclid(24.0);
+            let kwh = self.load_kw * (segment_end_s - at_s) / 3600.0;
+            let cost_eur = kwh * book.tariff_at(hour_of_day).eur_per_kwh;
+            self.day_kwh += kwh;
+            self.day_cost_eur += cost_eur;
+            self.total_kwh += kwh;
+            self.total_cost_eur += cost_eur;
+            at_s = segment_end_s;
+            // Cloture des minuit atteint, avant la cloture comptable de la meme journee.
+            let day_index = (at_s / DAY_S).floor() as u64;
+            if day_index != self.day_index {
+                bills.extend(self.close_day(day_index, at_s, boxes_total));
+            }
+        }
+        bills
+    }
+
+    fn close_day(&mut self, day_index: u64, at_s: f64, boxes_total: u32) -> Option<EnergyBill> {
+        let bill = EnergyBill {
+            day_index: self.day_index,
+            kwh: self.day_kwh,
+            cost_eur: self.day_cost_eur,
+            boxes: boxes_total.saturating_sub(self.day_boxes_start),
+            closed_at_s: at_s,
+        };
+        self.day_index = day_index;
+        self.day_kwh = 0.0;
+        self.day_cost_eur = 0.0;
+        self.day_boxes_start = boxes_total;
+        // Journee sans compteur (partie chargee d'une vieille sauvegarde): rien a facturer.
+        if bill.kwh <= 0.0 {
+            return None;
+        }
+        if self.bills.len() >= ENERGY_BILL_HISTORY {
+            self.bills.remove(0);
+        }
+        self.bills.push(bill.clone());
+        Some(bill)
+    }
+
+    pub fn last_bill(&self) -> Option<&EnergyBill> {
+        self.bills.last()
+    }
+
+    // Intensite energetique glissante: factures conservees plus la journee en cours.
+    pub fn kwh_per_box(&self, boxes_total: u32) -> Option<f64> {
+        let day_boxes = boxes_total.saturating_sub(self.day_boxes_start);
+        let (kwh, boxes) = self
+            .bills
+            .iter()
+            .fold((self.day_kwh, day_boxes), |(kwh, boxes), bill| {
+                (kwh + bill.kwh, boxes.saturating_add(bill.boxes))
+            });
+        (boxes > 0).then(|| kwh / boxes as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = EnergyBook::default();
+        assert!(book.validate().is_ok());
+
+        let mut gap = book.clone();
+        gap.tariffs.pop();
+        assert!(gap.validate().is_err());
+        let mut overlap = book.clone();
+        overlap.tariffs[1].end_h = 18.0;
+        assert!(overlap.validate().is_err());
+        assert_eq!(book.tariff_at(23.5).label, "Heures creuses");
+        assert_eq!(book.tariff_at(18.0).label, "Pointe");
+        assert_eq!(book.power_kw(BlockKind::DryerOven, false), 60.0);
+        assert_eq!(book.power_kw(BlockKind::Storage, true), 0.0);
+    }
+
+    #[test]
+    fn meter_bills_each_day_at_time_of_day_tariffs() {
+        let book = EnergyBook::default();
+        let mut state = EnergyState::default();
+        // 10 kW de 21h a 23h: une heure pleine puis une heure creuse.
+        assert!(state.tick(&book, 10.0, 21.0 * 3600.0, 7200.0, 0).is_empty());
+        assert!((state.day_kwh - 20.0).abs() < 1e-9);
+        assert!((state.day_cost_eur - (10.0 * 0.19 + 10.0 * 0.12)).abs() < 1e-9);
+
+        // Le pas suivant franchit minuit: la journee 0 est facturee.
+        let bills = state.tick(&book, 10.0, 23.0 * 3600.0, 7200.0, 4);
+        assert_eq!(bills.len(), 1);
+        assert_eq!(bills[0].day_index, 0);
+        assert!((bills[0].kwh - 30.0).abs() < 1e-9);
+        assert_eq!(bills[0].boxes, 4);
+        assert_eq!(bills[0].closed_at_s, DAY_S);
+        assert!((state.day_kwh - 10.0).abs() < 1e-9);
+        assert_eq!(state.day_index, 1);
+        assert!((state.kwh_per_box(4).expect("cartons produits") - 10.0).abs() < 1e-9);
+        assert!((state.total_kwh - 40.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,414 @@
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const FINANCE_BOOK_PATH: &str = "data/finance.ron";
+const FINANCE_BOOK_SCHEMA_VERSION: u32 = 1;
+
+pub type LoanId = u32;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LoanOffer {
+    pub id: String,
+    pub label: String,
+    pub principal_eur: f64,
+    pub daily_rate: f64,
+    pub term_days: u32,
+}
+
+impl LoanOffer {
+    fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("offre de pret sans identifiant".to_string());
+        }
+        if !self.principal_eur.is_finite() || self.principal_eur <= 0.0 {
+            return Err(format!("pret {}: montant invalide", self.id));
+        }
+        if !self.daily_rate.is_finite() || !(0.0..0.1).contains(&self.daily_rate) {
+            return Err(format!("pret {}: taux journalier invalide", self.id));
+        }
+        if self.term_days == 0 {
+            return Err(format!("pret {}: duree nulle", self.id));
+        }
+        Ok(())
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: {:.0} EUR sur {} j a {:.2}%/j",
+            self.label,
+            self.principal_eur,
+            self.term_days,
+            self.daily_rate * 100.0
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FinanceBook {
+    pub schema_version: u32,
+    // Decouvert autorise: la tresorerie peut descendre jusqu'a -credit_line_limit_eur.
+    pub credit_line_limit_eur: f64,
+    pub overdraft_daily_rate: f64,
+    // Part du decouvert autorise a partir de laquelle la banque previent.
+    pub overdraft_warning_ratio: f64,
+    // Jours passes au-dela du decouvert autorise avant la faillite.
+    pub insolvency_grace_days: f64,
+    pub max_active_loans: usize,
+    pub loan_offers: Vec<LoanOffer>,
+}
+
+impl Default for FinanceBook {
+    fn default() -> Self {
+        Self {
+            schema_version: FINANCE_BOOK_SCHEMA_VERSION,
+            credit_line_limit_eur: 20_000.0,
+            overdraft_daily_rate: 0.0015,
+            overdraft_warning_ratio: 0.8,
+            insolvency_grace_days: 3.0,
+            max_active_loans: 3,
+            loan_offers: vec![
+                LoanOffer {
+                    id: "pret_court".to_string(),
+                    label: "Pret de tresorerie".to_string(),
+                    principal_eur: 15_000.0,
+                    daily_rate: 0.0008,
+                    term_days: 14,
+                },
+                LoanOffer {
+                    id: "pret_equipement".to_string(),
+                    label: "Pret equipement".to_string(),
+                    principal_eur: 60_000.0,
+                    daily_rate: 0.0004,
+                    term_days: 60,
+                },
+            ],
+        }
+    }
+}
+
+impl RonBook for FinanceBook {
+    const LABEL: &'static str = "finance";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != FINANCE_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema finance invalide: attendu={} recu={}",
+                FINANCE_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        if !self.credit_line_limit_eur.is_finite() || self.credit_line_limit_eur < 0.0 {
+            return Err("decouvert autorise invalide".to_string());
+        }
+        if !self.overdraft_daily_rate.is_finite()
+            || !(0.0..0.1).contains(&self.overdraft_daily_rate)
+        {
+            return Err("taux de decouvert invalide".to_string());
+        }
+        if !self.overdraft_warning_ratio.is_finite()
+            || !(0.0..=1.0).contains(&self.overdraft_warning_ratio)
+        {
+            return Err("seuil d'alerte decouvert invalide".to_string());
+        }
+        if !self.insolvency_grace_days.is_finite() || self.insolvency_grace_days <= 0.0 {
+            return Err("delai avant faillite invalide".to_string());
+        }
+        let mut ids = HashSet::new();
+        for offer in &self.loan_offers {
+            offer.validate()?;
+            if !ids.insert(offer.id.as_str()) {
+                return Err(format!("offre de pret dupliquee: {}", offer.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FinanceBook {
+    pub fn offer(&self, id: &str) -> Option<&LoanOffer> {
+        self.loan_offers.iter().find(|offer| offer.id == id)
+    }
+}
+
+// Pret amortissable: capital rembourse par parts egales chaque jour, interets sur le restant du.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Loan {
+    pub id: LoanId,
+    pub label: String,
+    pub principal_eur: f64,
+    pub outstanding_eur: f64,
+    pub daily_rate: f64,
+    pub daily_principal_eur: f64,
+    pub taken_at_s: f64,
+}
+
+// Niveau d'alerte bancaire, du plus sain au plus grave.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverdraftLevel {
+    #[default]
+    Positif,
+    Decouvert,
+    ProcheLimite,
+    HorsLimite,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FinanceEvent {
+    OverdraftInterest {
+        amount_eur: f64,
+    },
+    LoanInstallment {
+        id: LoanId,
+        interest_eur: f64,
+        principal_eur: f64,
+    },
+    LoanRepaid {
+        id: LoanId,
+    },
+    OverdraftWarning {
+        level: OverdraftLevel,
+        cash_eur: f64,
+        limit_eur: f64,
+    },
+    Bankrupt {
+        insolvent_days: f64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FinanceState {
+    pub loans: Vec<Loan>,
+    pub next_loan_id: LoanId,
+    pub overdraft_interest_accrued_eur: f64,
+    pub interest_paid_eur: f64,
+    pub last_day_index: u64,
+    pub overdraft_level: OverdraftLevel,
+    pub insolvent_since_s: Option<f64>,
+    pub bankrupt_at_s: Option<f64>,
+}
+
+impl Default for FinanceState {
+    fn default() -> Self {
+        Self {
+            loans: Vec::new(),
+            next_loan_id: 1,
+            overdraft_interest_accrued_eur: 0.0,
+            interest_paid_eur: 0.0,
+            last_day_index: 0,
+            overdraft_level: OverdraftLevel::Positif,
+            insolvent_since_s: None,
+            bankrupt_at_s: None,
+        }
+    }
+}
+
+impl FinanceState {
+    pub fn is_bankrupt(&self) -> bool {
+        self.bankrupt_at_s.is_some()
+    }
+
+    pub fn loans_outstanding_eur(&self) -> f64 {
+        self.loans.iter().map(|loan| loan.outstanding_eur).sum()
+    }
+
+    pub fn overdraft_level(book: &FinanceBook, cash_eur: f64) -> OverdraftLevel {
+        let overdraft = -cash_eur;
+        if overdraft <= 0.0 {
+            OverdraftLevel::Positif
+        } else if overdraft > book.credit_line_limit_eur {
+            OverdraftLevel::HorsLimite
+        } else if overdraft >= book.credit_line_limit_eur * book.overdraft_warning_ratio {
+            OverdraftLevel::ProcheLimite
+        } else {
+            OverdraftLevel::Decouvert
+        }
+    }
+
+    // Jours restants avant la faillite tant que la tresorerie reste hors decouvert autorise.
+    pub fn days_before_bankruptcy(&self, book: &FinanceBook, now_s: f64) -> Option<f64> {
+        self.insolvent_since_s
+            .map(|since_s| (book.insolvency_grace_days - (now_s - since_s) / 86_400.0).max(0.0))
+    }
+
+    pub fn take_loan(
+        &mut self,
+        book: &FinanceBook,
+        offer_id: &str,
+        now_s: f64,
+    ) -> Result<&Loan, String> {
+        if self.is_bankrupt() {
+            return Err("usine en faillite: plus aucun pret".to_string());
+        }
+        let offer = book
+            .offer(offer_id)
+            .ok_or_else(|| format!("offre de pret inconnue: {offer_id}"))?;
+        if self.loans.len() >= book.max_active_loans {
+            return Err(format!(
+                "trop de prets en cours: {}/{}",
+                self.loans.len(),
+                book.max_active_loans
+            ));
+        }
+        let id = self.next_loan_id;
+        self.next_loan_id = self.next_loan_id.saturating_add(1);
+        self.loans.push(Loan {
+            id,
+            label: offer.label.clone(),
+            principal_eur: offer.principal_eur,
+            outstanding_eur: offer.principal_eur,
+            daily_rate: offer.daily_rate,
+            daily_principal_eur: offer.principal_eur / offer.term_days as f64,
+            taken_at_s: now_s,
+        });
+        Ok(self.loans.last().expect("pret ajoute"))
+    }
+
+    // Remboursement anticipe du restant du; renvoie le montant a decaisser.
+    pub fn repay_loan(&mut self, id: LoanId, cash_eur: f64) -> Result<f64, String> {
+        let index = self
+            .loans
+            .iter()
+            .position(|loan| loan.id == id)
+            .ok_or_else(|| format!("pret introuvable: #{id}"))?;
+        let amount = self.loans[index].outstanding_eur;
+        if !cash_eur.is_finite() || cash_eur < amount {
+            return Err(format!("tresorerie insuffisante: {amount:.0} EUR requis"));
+        }
+        self.loans.remove(index);
+        Ok(amount)
+    }
+
+    pub fn tick(
+        &mut self,
+        book: &FinanceBook,
+        cash_eur: f64,
+        now_s: f64,
+        dt_days: f64,
+        day_index: u64,
+    ) -> Vec<FinanceEvent> {
+        let mut events = Vec::new();
+        if self.is_bankrupt() {
+            return events;
+        }
+
+        if dt_days.is_finite() && dt_days > 0.0 && cash_eur < 0.0 {
+            self.overdraft_interest_accrued_eur += -cash_eur * book.overdraft_daily_rate * dt_days;
+        }
+
+        // Agios et echeances des prets tombent a chaque changement de jour.
+        if day_index > self.last_day_index {
+            self.last_day_index = day_index;
+            let agios = std::mem::take(&mut self.overdraft_interest_accrued_eur);
+            if agios > 0.0 {
+                self.interest_paid_eur += agios;
+                events.push(FinanceEvent::OverdraftInterest { amount_eur: agios });
+            }
+            for loan in &mut self.loans {
+                let interest_eur = loan.outstanding_eur * loan.daily_rate;
+                let principal_eur = loan.daily_principal_eur.min(loan.outstanding_eur);
+                loan.outstanding_eur -= principal_eur;
+                if loan.outstanding_eur < 0.01 {
+                    loan.outstanding_eur = 0.0;
+                }
+                self.interest_paid_eur += interest_eur;
+                events.push(FinanceEvent::LoanInstallment {
+                    id: loan.id,
+                    interest_eur,
+                    principal_eur,
+                });
+                if loan.outstanding_eur <= 0.0 {
+                    events.push(FinanceEvent::LoanRepaid { id: loan.id });
+                }
+            }
+            self.loans.retain(|loan| loan.outstanding_eur > 0.0);
+        }
+
+        // La banque ne previent qu'en cas d'aggravation.
+        let level = Self::overdraft_level(book, cash_eur);
+        if level > self.overdraft_level {
+            events.push(FinanceEvent::OverdraftWarning {
+                level,
+                cash_eur,
+                limit_eur: book.credit_line_limit_eur,
+            });
+        }
+        self.overdraft_level = level;
+
+        if level == OverdraftLevel::HorsLimite {
+            let since_s = *self.insolvent_since_s.get_or_insert(now_s);
+            let insolvent_days = (now_s - since_s) / 86_400.0;
+            if insolvent_days >= book.insolvency_grace_days {
+                self.bankrupt_at_s = Some(now_s);
+                events.push(FinanceEvent::Bankrupt { insolvent_days });
+            }
+        } else {
+            self.insolvent_since_s = None;
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = FinanceBook::default();
+        assert!(book.validate().is_ok());
+    }
+
+    #[test]
+    fn loans_amortise_daily_and_insolvency_leads_to_bankruptcy() {
+        let book = FinanceBook::default();
+        let mut finance = FinanceState::default();
+        let loan_id = finance
+            .take_loan(&book, "pret_court", 0.0)
+            .expect("pret accorde")
+            .id;
+        assert!(finance.take_loan(&book, "inconnu", 0.0).is_err());
+
+        let events = finance.tick(&book, -1_000.0, 86_400.0, 1.0, 1);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            FinanceEvent::OverdraftInterest { amount_eur } if (amount_eur - 1.5).abs() < 1e-9
+        )));
+        assert!(events.contains(&FinanceEvent::LoanInstallment {
+            id: loan_id,
+            interest_eur: 15_000.0 * 0.0008,
+            principal_eur: 15_000.0 / 14.0,
+        }));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            FinanceEvent::OverdraftWarning {
+                level: OverdraftLevel::Decouvert,
+                ..
+            }
+        )));
+        assert!(finance.repay_loan(loan_id, 100.0).is_err());
+        let due = finance.repay_loan(loan_id, 20_000.0).expect("remboursable");
+        assert!((due - 15_000.0 * 13.0 / 14.0).abs() < 1e-6);
+        assert!(finance.loans.is_empty());
+
+        let over = -book.credit_line_limit_eur - 1.0;
+        let day = 86_400.0;
+        finance.tick(&book, over, 1.5 * day, 0.5, 1);
+        assert_eq!(finance.overdraft_level, OverdraftLevel::HorsLimite);
+        assert_eq!(
+            finance.days_before_bankruptcy(&book, 2.5 * day),
+            Some(book.insolvency_grace_days - 1.0)
+        );
+        // Un retour dans le decouvert autorise remet le compteur a zero.
+        finance.tick(&book, -10.0, 2.0 * day, 0.5, 2);
+        assert_eq!(finance.insolvent_since_s, None);
+        finance.tick(&book, over, 2.5 * day, 0.5, 2);
+        finance.tick(&book, over, 5.0 * day, 2.5, 5);
+        assert!(!finance.is_bankrupt());
+        let events = finance.tick(&book, over, 5.5 * day, 0.5, 5);
+        assert!(finance.is_bankrupt());
+        assert!(matches!(events[0], FinanceEvent::Bankrupt { .. }));
+        assert!(finance.tick(&book, over, 6.0 * day, 0.5, 6).is_empty());
+    }
+}
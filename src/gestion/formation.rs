@@ -0,0 +1,404 @@
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::personnel::{
+    Employee, EmployeeId, EmployeeRole, EmployeeSkills, EmployeeStatus, PersonnelState, SkillKind,
+    WorkType,
+};
+
+pub const TRAINING_BOOK_PATH: &str = "data/training.ron";
+const TRAINING_BOOK_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Certification {
+    Caces,
+    HabilitationMaintenance,
+}
+
+impl Certification {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Caces => "CACES chariot",
+            Self::HabilitationMaintenance => "Habilitation maintenance",
+        }
+    }
+
+    pub fn unlocks(self) -> EmployeeRole {
+        match self {
+            Self::Caces => EmployeeRole::Cariste,
+            Self::HabilitationMaintenance => EmployeeRole::Technicien,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TrainingCourse {
+    pub id: String,
+    pub label: String,
+    pub skill: SkillKind,
+    pub gain: f64,
+    pub duration_h: f64,
+    pub cost_eur: f64,
+    pub certification: Option<Certification>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TrainingBook {
+    pub schema_version: u32,
+    // Points par heure de pratique a competence 0; le gain fond en approchant 100.
+    pub practice_gain_per_h: f64,
+    // Sans pratique depuis decay_grace_h, perte de decay_per_day points par jour
+    // jusqu'au niveau de base du role.
+    pub decay_grace_h: f64,
+    pub decay_per_day: f64,
+    pub courses: Vec<TrainingCourse>,
+}
+
+fn course(
+    id: &str,
+    label: &str,
+    skill: SkillKind,
+    gain: f64,
+    duration_h: f64,
+    cost_eur: f64,
+    certification: Option<Certification>,
+) -> TrainingCourse {
+    TrainingCourse {
+        id: id.to_string(),
+        label: label.to_string(),
+        skill,
+        gain,
+        duration_h,
+        cost_eur,
+        certification,
+    }
+}
+
+impl Default for TrainingBook {
+    fn default() -> Self {
+        Self {
+            schema_version: TRAINING_BOOK_SCHEMA_VERSION,
+            practice_gain_per_h: 0.3,
+            decay_grace_h: 72.0,
+            decay_per_day: 0.5,
+            courses: vec![
+                course(
+                    "caces",
+                    "CACES chariot",
+                    SkillKind::Logistique,
+                    10.0,
+                    14.0,
+                    900.0,
+                    Some(Certification::Caces),
+                ),
+                course(
+                    "habilitation_maintenance",
+                    "Habilitation maintenance",
+                    SkillKind::Technique,
+                    12.0,
+                    21.0,
+                    1_400.0,
+                    Some(Certification::HabilitationMaintenance),
+                ),
+                course(
+                    "animation_equipe",
+                    "Animation d'equipe",
+                    SkillKind::Management,
+                    10.0,
+                    14.0,
+                    1_100.0,
+                    None,
+                ),
+                course(
+                    "techniques_vente",
+                    "Techniques de vente",
+                    SkillKind::Vente,
+                    10.0,
+                    14.0,
+                    800.0,
+                    None,
+                ),
+                course(
+                    "rigueur_qualite",
+                    "Rigueur et qualite",
+                    SkillKind::Fiabilite,
+                    8.0,
+                    7.0,
+                    500.0,
+                    None,
+                ),
+            ],
+        }
+    }
+}
+
+impl RonBook for TrainingBook {
+    const LABEL: &'static str = "formations";
+    const PRETTY_DEPTH: usize = 3;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != TRAINING_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema formations invalide: attendu={} recu={}",
+                TRAINING_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        let rates = [
+            self.practice_gain_per_h,
+            self.decay_grace_h,
+            self.decay_per_day,
+        ];
+        if rates.iter().any(|rate| !rate.is_finite() || *rate < 0.0) {
+            return Err("gain de pratique, delai et oubli doivent etre positifs".to_string());
+        }
+        let mut ids = HashSet::new();
+        for course in &self.courses {
+            if course.id.trim().is_empty() {
+                return Err("formation sans identifiant".to_string());
+            }
+            if !ids.insert(course.id.as_str()) {
+                return Err(format!("formation en double: {}", course.id));
+            }
+            if !(course.duration_h.is_finite() && course.duration_h > 0.0) {
+                return Err(format!("duree invalide pour {}", course.id));
+            }
+            if [course.gain, course.cost_eur]
+                .iter()
+                .any(|value| !value.is_finite() || *value < 0.0)
+            {
+                return Err(format!("gain ou cout invalide pour {}", course.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TrainingBook {
+    pub fn course(&self, id: &str) -> Option<&TrainingCourse> {
+        self.courses.iter().find(|course| course.id == id)
+    }
+
+    // Premiere certification manquante, sinon le cours de la competence la plus faible.
+    pub fn suggested_course(&self, employee: &Employee) -> Option<&TrainingCourse> {
+        self.courses
+            .iter()
+            .find(|course| {
+                course
+                    .certification
+                    .is_some_and(|certification| !employee.certifications.contains(&certification))
+            })
+            .or_else(|| {
+                self.courses
+                    .iter()
+                    .filter(|course| course.certification.is_none())
+                    .min_by_key(|course| employee.skills.level(course.skill))
+            })
+    }
+
+    // Pratique, oubli et fin des formations; `practiced` donne le travail en cours de chacun.
+    pub fn tick(
+        &self,
+        personnel: &mut PersonnelState,
+        practiced: &HashMap<EmployeeId, WorkType>,
+        dt_h: f64,
+    ) -> Vec<TrainingCompletion> {
+        if !dt_h.is_finite() || dt_h <= 0.0 {
+            return Vec::new();
+        }
+        let mut completions = Vec::new();
+        for employee in &mut personnel.employees {
+            if employee.role == EmployeeRole::Patron || employee.status == EmployeeStatus::Termine {
+                continue;
+            }
+            if let Some(completion) = self.tick_training(employee, dt_h) {
+                completions.push(completion);
+            }
+
+            let practiced_skill = practiced.get(&employee.id).map(|work| work.skill_kind());
+            let base = EmployeeSkills::for_role(employee.role);
+            for kind in SkillKind::ALL {
+                let delta = if practiced_skill == Some(kind) {
+                    employee.practice.idle_h.insert(kind, 0.0);
+                    let headroom = 1.0 - employee.skills.level(kind) as f64 / 100.0;
+                    self.practice_gain_per_h * headroom * dt_h
+                } else {
+                    let idle_h = employee.practice.idle_h.entry(kind).or_insert(0.0);
+                    *idle_h += dt_h;
+                    if *idle_h <= self.decay_grace_h {
+                        continue;
+                    }
+                    -self.decay_per_day * dt_h / 24.0
+                };
+                employee
+                    .practice
+                    .shift(&mut employee.skills, kind, delta, base.level(kind));
+            }
+        }
+        completions
+    }
+
+    fn tick_training(&self, employee: &mut Employee, dt_h: f64) -> Option<TrainingCompletion> {
+        let session = employee.training.as_mut()?;
+        session.remaining_h -= dt_h;
+        if session.remaining_h > 0.0 {
+            return None;
+        }
+        let course_id = session.course_id.clone();
+        employee.training = None;
+        employee.set_idle_task();
+        employee.practice.courses_completed = employee.practice.courses_completed.saturating_add(1);
+
+        // Cours retire du catalogue en cours de route: la session se termine sans acquis.
+        let course = self.course(&course_id);
+        if let Some(course) = course {
+            employee
+                .practice
+                .shift(&mut employee.skills, course.skill, course.gain, 0);
+            if let Some(certification) = course.certification {
+                employee.certifications.insert(certification);
+            }
+        }
+        Some(TrainingCompletion {
+            employee_id: employee.id,
+            name: employee.name.clone(),
+            course_label: course.map_or(course_id, |course| course.label.clone()),
+            certification: course.and_then(|course| course.certification),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SkillPractice {
+    // Fraction de point en cours par competence, dans ]-1, 1[.
+    pub progress: BTreeMap<SkillKind, f64>,
+    // Heures depuis la derniere pratique.
+    pub idle_h: BTreeMap<SkillKind, f64>,
+    pub courses_completed: u32,
+}
+
+impl SkillPractice {
+    // Cumule delta et reporte les points entiers, entre floor et 100.
+    fn shift(&mut self, skills: &mut EmployeeSkills, kind: SkillKind, delta: f64, floor: u8) {
+        let progress = self.progress.entry(kind).or_insert(0.0);
+        let level = skills.level_mut(kind);
+        *progress += delta;
+        while *progress >= 1.0 {
+            if *level >= 100 {
+                *progress = 0.0;
+                break;
+            }
+            *level += 1;
+            *progress -= 1.0;
+        }
+        while *progress <= -1.0 {
+            if *level <= floor {
+                *progress = 0.0;
+                break;
+            }
+            *level -= 1;
+            *progress += 1.0;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TrainingSession {
+    pub course_id: String,
+    pub remaining_h: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrainingCompletion {
+    pub employee_id: EmployeeId,
+    pub name: String,
+    pub course_label: String,
+    pub certification: Option<Certification>,
+}
+
+impl TrainingCompletion {
+    pub fn summary(&self) -> String {
+        let certification = self
+            .certification
+            .map(|certification| format!(", certifie {}", certification.label()))
+            .unwrap_or_default();
+        format!(
+            "{} (#{}) a termine {}{certification}",
+            self.name, self.employee_id, self.course_label
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = TrainingBook::default();
+        assert!(book.validate().is_ok());
+
+        let mut duplicated = book.clone();
+        duplicated.courses.push(duplicated.courses[0].clone());
+        assert!(duplicated.validate().is_err());
+    }
+
+    #[test]
+    fn practice_raises_the_worked_skill_and_idle_skills_fade_to_the_role_base() {
+        let book = TrainingBook::default();
+        let mut personnel = PersonnelState::default();
+        let id = personnel.hire(EmployeeRole::Cariste, 0.0).unwrap();
+        let base = EmployeeSkills::for_role(EmployeeRole::Cariste);
+        personnel.employee_mut(id).unwrap().skills.vente = base.vente + 5;
+        let practiced = HashMap::from([(id, WorkType::Manutention)]);
+
+        for _ in 0..(20 * 24) {
+            book.tick(&mut personnel, &practiced, 1.0);
+        }
+        let skills = &personnel.employee(id).unwrap().skills;
+        assert!(skills.logistique > base.logistique);
+        assert!(skills.vente < base.vente + 5);
+        assert!(skills.vente >= base.vente);
+        assert_eq!(skills.technique, base.technique);
+    }
+
+    #[test]
+    fn certification_course_unlocks_a_role_change() {
+        let book = TrainingBook::default();
+        let mut personnel = PersonnelState::default();
+        let id = personnel
+            .hire(EmployeeRole::AdministrateurVente, 0.0)
+            .unwrap();
+        assert!(
+            personnel
+                .change_role(id, EmployeeRole::Cariste)
+                .unwrap_err()
+                .contains("CACES")
+        );
+
+        let caces = book
+            .suggested_course(personnel.employee(id).unwrap())
+            .unwrap();
+        assert_eq!(caces.certification, Some(Certification::Caces));
+        personnel.schedule_training(id, caces).unwrap();
+        assert!(personnel.schedule_training(id, caces).is_err());
+        assert!(!personnel.employee(id).unwrap().is_active_for_business());
+        assert!(personnel.employee(id).unwrap().is_paid());
+
+        let mut completions = Vec::new();
+        for _ in 0..caces.duration_h as usize {
+            completions.extend(book.tick(&mut personnel, &HashMap::new(), 1.0));
+        }
+        assert_eq!(completions.len(), 1);
+        assert!(personnel.employee(id).unwrap().is_active_for_business());
+        personnel.change_role(id, EmployeeRole::Cariste).unwrap();
+        let employee = personnel.employee(id).unwrap();
+        assert_eq!(employee.role, EmployeeRole::Cariste);
+        assert_eq!(
+            employee.hourly_wage,
+            EmployeeRole::AdministrateurVente.hourly_wage_eur()
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use super::lots::QualityGrade;
+use super::reglages::RonBook;
+use crate::aleatoire::SimRng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const SUPPLIER_CATALOGUE_PATH: &str = "data/suppliers.ron";
+const SUPPLIER_CATALOGUE_SCHEMA_VERSION: u32 = 1;
+// Fournisseur historique: premier du catalogue par defaut, cible des vieilles commandes.
+pub const DEFAULT_RAW_SUPPLIER_ID: &str = "cooperative_drome";
+
+pub fn default_raw_supplier_id() -> String {
+    DEFAULT_RAW_SUPPLIER_ID.to_string()
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PriceTier {
+    pub min_qty: u32,
+    pub unit_cost_eur: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Supplier {
+    pub id: String,
+    pub label: String,
+    // Paliers croissants: le prix du plus grand palier atteint s'applique a toute la commande.
+    pub price_tiers: Vec<PriceTier>,
+    pub moq: u32,
+    pub lead_time_h: f64,
+    // Ecart uniforme +/- autour du delai annonce.
+    pub lead_time_variance_h: f64,
+    // Probabilite qu'une commande arrive complete sans incident.
+    pub reliability: f64,
+    pub grade: QualityGrade,
+}
+
+// Tirage fait a la commande: le jeu ne revele le retard qu'une fois l'echeance passee.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeliveryPlan {
+    pub promised_s: f64,
+    pub delivery_s: f64,
+    // Quantite retenue a la premiere livraison, expediee en reliquat.
+    pub short_qty: u32,
+    pub backorder_delay_s: f64,
+}
+
+impl Supplier {
+    fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("fournisseur sans identifiant".to_string());
+        }
+        if self.moq == 0 {
+            return Err(format!("fournisseur {}: minimum de commande nul", self.id));
+        }
+        if self.price_tiers.is_empty() {
+            return Err(format!("fournisseur {}: aucun palier de prix", self.id));
+        }
+        let mut previous = 0;
+        for (idx, tier) in self.price_tiers.iter().enumerate() {
+            if (idx > 0 && tier.min_qty <= previous)
+                || !tier.unit_cost_eur.is_finite()
+                || tier.unit_cost_eur <= 0.0
+            {
+                return Err(format!(
+                    "fournisseur {}: palier invalide ({} unites a {} EUR)",
+                    self.id, tier.min_qty, tier.unit_cost_eur
+                ));
+            }
+            previous = tier.min_qty;
+        }
+        if !self.lead_time_h.is_finite()
+            || self.lead_time_h <= 0.0
+            || !self.lead_time_variance_h.is_finite()
+            || self.lead_time_variance_h < 0.0
+        {
+            return Err(format!("fournisseur {}: delai invalide", self.id));
+        }
+        if !self.reliability.is_finite() || !(0.0..=1.0).contains(&self.reliability) {
+            return Err(format!(
+                "fournisseur {}: fiabilite invalide ({})",
+                self.id, self.reliability
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn unit_cost_eur(&self, qty: u32) -> f64 {
+        self.price_tiers
+            .iter()
+            .rev()
+            .find(|tier| qty >= tier.min_qty)
+            .unwrap_or(&self.price_tiers[0])
+            .unit_cost_eur
+    }
+
+    pub fn order_cost(&self, qty: u32) -> f64 {
+        qty as f64 * self.unit_cost_eur(qty)
+    }
+
+    pub fn plan_delivery(&self, qty: u32, rng: &mut SimRng) -> DeliveryPlan {
+        let promised_s = self.lead_time_h * 3600.0;
+        let variance_s = self.lead_time_variance_h * 3600.0;
+        let jitter_s = variance_s * (rng.next_f64() * 2.0 - 1.0);
+        let mut delivery_s = (promised_s + jitter_s).max(promised_s * 0.5);
+        let mut short_qty = 0;
+        if !rng.chance(self.reliability) {
+            // Incident: une fois sur deux un retard franc, sinon une livraison partielle.
+            if rng.chance(0.5) {
+                delivery_s += variance_s + promised_s * (0.5 + rng.next_f64());
+            } else {
+                let share = 0.2 + 0.3 * rng.next_f64();
+                short_qty = ((qty as f64 * share).round() as u32).clamp(1, qty.saturating_sub(1));
+            }
+        }
+        DeliveryPlan {
+            promised_s,
+            delivery_s,
+            short_qty,
+            backorder_delay_s: promised_s,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let tiers = self
+            .price_tiers
+            .iter()
+            .map(|tier| format!("{}+ {:.2}", tier.min_qty, tier.unit_cost_eur))
+            .collect::<Vec<_>>()
+            .join(" / ");
+        format!(
+            "{} [{}] min {} | {} EUR | {:.1}h +/-{:.1}h | fiabilite {:.0}%",
+            self.label,
+            self.grade.label(),
+            self.moq,
+            tiers,
+            self.lead_time_h,
+            self.lead_time_variance_h,
+            self.reliability * 100.0
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SupplierCatalogue {
+    pub schema_version: u32,
+    pub suppliers: Vec<Supplier>,
+}
+
+fn tier(min_qty: u32, unit_cost_eur: f64) -> PriceTier {
+    PriceTier {
+        min_qty,
+        unit_cost_eur,
+    }
+}
+
+impl Default for SupplierCatalogue {
+    fn default() -> Self {
+        Self {
+            schema_version: SUPPLIER_CATALOGUE_SCHEMA_VERSION,
+            suppliers: vec![
+                Supplier {
+                    id: DEFAULT_RAW_SUPPLIER_ID.to_string(),
+                    label: super::stock::DEFAULT_RAW_SUPPLIER.to_string(),
+                    price_tiers: vec![tier(100, 1.20), tier(1_000, 1.10)],
+                    moq: 100,
+                    lead_time_h: 0.5,
+                    lead_time_variance_h: 0.0,
+                    reliability: 1.0,
+                    grade: QualityGrade::A,
+                },
+                Supplier {
+                    id: "negoce_iberique".to_string(),
+                    label: "Negoce Iberique".to_string(),
+                    price_tiers: vec![tier(300, 0.95), tier(1_000, 0.85)],
+                    moq: 300,
+                    lead_time_h: 6.0,
+                    lead_time_variance_h: 3.0,
+                    reliability: 0.8,
+                    grade: QualityGrade::B,
+                },
+                Supplier {
+                    id: "import_shandong".to_string(),
+                    label: "Import Shandong".to_string(),
+                    price_tiers: vec![tier(1_000, 0.70), tier(1_500, 0.62)],
+                    moq: 1_000,
+                    lead_time_h: 24.0,
+                    lead_time_variance_h: 12.0,
+                    reliability: 0.65,
+                    grade: QualityGrade::C,
+                },
+            ],
+        }
+    }
+}
+
+impl RonBook for SupplierCatalogue {
+    const LABEL: &'static str = "fournisseurs";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != SUPPLIER_CATALOGUE_SCHEMA_VERSION {
+            return Err(format!(
+                "schema fournisseurs invalide: attendu={} recu={}",
+                SUPPLIER_CATALOGUE_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        if self.suppliers.is_empty() {
+            return Err("catalogue fournisseurs vide".to_string());
+        }
+        let mut ids = HashSet::new();
+        for supplier in &self.suppliers {
+            supplier.validate()?;
+            if !ids.insert(supplier.id.as_str()) {
+                return Err(format!("fournisseur duplique: {}", supplier.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SupplierCatalogue {
+    pub fn supplier(&self, id: &str) -> Option<&Supplier> {
+        self.suppliers.iter().find(|supplier| supplier.id == id)
+    }
+
+    // Fournisseur habituel: l'historique s'il existe, sinon le premier du catalogue.
+    pub fn default_supplier(&self) -> &Supplier {
+        self.supplier(DEFAULT_RAW_SUPPLIER_ID)
+            .unwrap_or(&self.suppliers[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_catalogue_is_valid() {
+        let catalogue = SupplierCatalogue::default();
+        assert!(catalogue.validate().is_ok());
+    }
+
+    #[test]
+    fn price_tiers_and_unreliable_deliveries() {
+        let catalogue = SupplierCatalogue::default();
+        let coop = catalogue.default_supplier();
+        assert_eq!(coop.order_cost(100), 120.0);
+        assert!((coop.unit_cost_eur(999) - 1.20).abs() < 1e-9);
+        assert!((coop.unit_cost_eur(1_000) - 1.10).abs() < 1e-9);
+
+        let mut rng = SimRng::from_seed(11);
+        let plan = coop.plan_delivery(500, &mut rng);
+        assert_eq!(plan.delivery_s, plan.promised_s);
+        assert_eq!(plan.short_qty, 0);
+
+        let import = catalogue
+            .supplier("import_shandong")
+            .expect("import present");
+        let plans = (0..200)
+            .map(|_| import.plan_delivery(1_000, &mut rng))
+            .collect::<Vec<_>>();
+        let late = plans
+            .iter()
+            .filter(|plan| plan.delivery_s > plan.promised_s + 12.0 * 3600.0)
+            .count();
+        let partial = plans.iter().filter(|plan| plan.short_qty > 0).count();
+        assert!(late > 0 && partial > 0);
+        assert!(plans.iter().all(|plan| plan.short_qty < 1_000));
+    }
+}
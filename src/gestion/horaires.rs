@@ -0,0 +1,255 @@
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const SHIFT_BOOK_PATH: &str = "data/shifts.ron";
+const SHIFT_BOOK_SCHEMA_VERSION: u32 = 1;
+// Modele historique: la ligne tourne 24h/24 comme avant les horaires.
+pub const DEFAULT_SHIFT_TEMPLATE: &str = "continu";
+
+pub fn default_shift_template_id() -> String {
+    DEFAULT_SHIFT_TEMPLATE.to_string()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ShiftSlot {
+    pub id: String,
+    pub label: String,
+    // Heures de debut/fin; fin < debut = poste de nuit a cheval sur minuit.
+    pub start_h: f64,
+    pub end_h: f64,
+}
+
+impl ShiftSlot {
+    pub fn contains_hour(&self, hour: f64) -> bool {
+        if self.start_h <= self.end_h {
+            hour >= self.start_h && hour < self.end_h
+        } else {
+            hour >= self.start_h || hour < self.end_h
+        }
+    }
+
+    pub fn span_label(&self) -> String {
+        format!(
+            "{} {:02.0}h-{:02.0}h",
+            self.label,
+            self.start_h,
+            self.end_h % 24.0
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ShiftTemplate {
+    pub id: String,
+    pub label: String,
+    pub slots: Vec<ShiftSlot>,
+    #[serde(default)]
+    pub weekend: bool,
+}
+
+impl ShiftTemplate {
+    fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("modele horaire sans identifiant".to_string());
+        }
+        if self.slots.is_empty() {
+            return Err(format!("modele horaire {}: aucun poste", self.id));
+        }
+        for slot in &self.slots {
+            let valid_hour = |h: f64| h.is_finite() && (0.0..=24.0).contains(&h);
+            if !valid_hour(slot.start_h) || !valid_hour(slot.end_h) || slot.start_h == slot.end_h {
+                return Err(format!(
+                    "modele horaire {}: poste {} invalide ({}h-{}h)",
+                    self.id, slot.id, slot.start_h, slot.end_h
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Les postes tournent: l'employe n garde le poste n modulo le nombre de postes.
+    pub fn slot(&self, rotation: u8) -> &ShiftSlot {
+        &self.slots[rotation as usize % self.slots.len()]
+    }
+
+    pub fn slot_open(&self, rotation: u8, hour: f64, weekend: bool) -> bool {
+        (self.weekend || !weekend) && self.slot(rotation).contains_hour(hour)
+    }
+
+    pub fn open_slot(&self, hour: f64, weekend: bool) -> Option<&ShiftSlot> {
+        if weekend && !self.weekend {
+            return None;
+        }
+        self.slots.iter().find(|slot| slot.contains_hour(hour))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ShiftBook {
+    pub schema_version: u32,
+    pub night_start_h: f64,
+    pub night_end_h: f64,
+    pub night_premium: f64,
+    pub weekend_premium: f64,
+    pub templates: Vec<ShiftTemplate>,
+}
+
+fn slot(id: &str, label: &str, start_h: f64, end_h: f64) -> ShiftSlot {
+    ShiftSlot {
+        id: id.to_string(),
+        label: label.to_string(),
+        start_h,
+        end_h,
+    }
+}
+
+impl Default for ShiftBook {
+    fn default() -> Self {
+        Self {
+            schema_version: SHIFT_BOOK_SCHEMA_VERSION,
+            night_start_h: 21.0,
+            night_end_h: 6.0,
+            night_premium: 0.25,
+            weekend_premium: 0.50,
+            templates: vec![
+                ShiftTemplate {
+                    id: DEFAULT_SHIFT_TEMPLATE.to_string(),
+                    label: "Continu 24/7".to_string(),
+                    slots: vec![slot("continu", "Continu", 0.0, 24.0)],
+                    weekend: true,
+                },
+                ShiftTemplate {
+                    id: "2x8".to_string(),
+                    label: "2x8 semaine".to_string(),
+                    slots: vec![
+                        slot("matin", "Matin", 6.0, 14.0),
+                        slot("apres_midi", "Apres-midi", 14.0, 22.0),
+                    ],
+                    weekend: false,
+                },
+                ShiftTemplate {
+                    id: "3x8".to_string(),
+                    label: "3x8 continu".to_string(),
+                    slots: vec![
+                        slot("matin", "Matin", 6.0, 14.0),
+                        slot("apres_midi", "Apres-midi", 14.0, 22.0),
+                        slot("nuit", "Nuit", 22.0, 6.0),
+                    ],
+                    weekend: true,
+                },
+                ShiftTemplate {
+                    id: "jour".to_string(),
+                    label: "Journee".to_string(),
+                    slots: vec![slot("jour", "Journee", 8.0, 17.0)],
+                    weekend: false,
+                },
+            ],
+        }
+    }
+}
+
+impl RonBook for ShiftBook {
+    const LABEL: &'static str = "horaires";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != SHIFT_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema horaires invalide: attendu={} recu={}",
+                SHIFT_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        for premium in [self.night_premium, self.weekend_premium] {
+            if !premium.is_finite() || !(0.0..=3.0).contains(&premium) {
+                return Err(format!("majoration horaire invalide: {premium}"));
+            }
+        }
+        let mut ids = HashSet::new();
+        for template in &self.templates {
+            template.validate()?;
+            if !ids.insert(template.id.as_str()) {
+                return Err(format!("modele horaire duplique: {}", template.id));
+            }
+        }
+        if !ids.contains(DEFAULT_SHIFT_TEMPLATE) {
+            return Err(format!(
+                "modele horaire par defaut manquant: {DEFAULT_SHIFT_TEMPLATE}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ShiftBook {
+    pub fn template(&self, id: &str) -> Option<&ShiftTemplate> {
+        self.templates.iter().find(|template| template.id == id)
+    }
+
+    // Un identifiant inconnu (vieille sauvegarde, fichier edite) retombe sur le continu.
+    pub fn template_or_default(&self, id: &str) -> &ShiftTemplate {
+        self.template(id)
+            .or_else(|| self.template(DEFAULT_SHIFT_TEMPLATE))
+            .unwrap_or(&self.templates[0])
+    }
+
+    pub fn next_template_id(&self, id: &str) -> String {
+        let idx = self
+            .templates
+            .iter()
+            .position(|template| template.id == id)
+            .unwrap_or(0);
+        self.templates[(idx + 1) % self.templates.len()].id.clone()
+    }
+
+    pub fn is_night_hour(&self, hour: f64) -> bool {
+        if self.night_start_h <= self.night_end_h {
+            hour >= self.night_start_h && hour < self.night_end_h
+        } else {
+            hour >= self.night_start_h || hour < self.night_end_h
+        }
+    }
+
+    pub fn wage_multiplier(&self, hour: f64, weekend: bool) -> f64 {
+        let mut multiplier = 1.0;
+        if self.is_night_hour(hour) {
+            multiplier += self.night_premium;
+        }
+        if weekend {
+            multiplier += self.weekend_premium;
+        }
+        multiplier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = ShiftBook::default();
+        assert!(book.validate().is_ok());
+    }
+
+    #[test]
+    fn slots_wrap_midnight_and_skip_weekend() {
+        let book = ShiftBook::default();
+        let three = book.template("3x8").expect("3x8 present");
+        assert_eq!(three.slot(2).id, "nuit");
+        assert!(three.slot_open(2, 23.0, false));
+        assert!(three.slot_open(2, 3.0, true));
+        assert!(!three.slot_open(2, 12.0, false));
+
+        let two = book.template("2x8").expect("2x8 present");
+        assert!(two.open_slot(10.0, false).is_some());
+        assert!(two.open_slot(10.0, true).is_none());
+        assert!(two.open_slot(23.0, false).is_none());
+        assert_eq!(
+            book.template_or_default("inconnu").id,
+            DEFAULT_SHIFT_TEMPLATE
+        );
+
+        assert!((book.wage_multiplier(12.0, false) - 1.0).abs() < 1e-9);
+        assert!((book.wage_multiplier(23.0, true) - 1.75).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,249 @@
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::sim::ZoneKind;
+
+pub const HSE_BOOK_PATH: &str = "data/hse.ron";
+const HSE_BOOK_SCHEMA_VERSION: u32 = 1;
+const HYGIENE_FULL: f64 = 100.0;
+const HSE_RECENT_EVENTS: usize = 5;
+
+// Hygiene des zones de travail et risque d'accident du personnel.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct HseBook {
+    pub schema_version: u32,
+    // Hygiene perdue par heure dans chaque zone ou quelqu'un travaille.
+    pub soiling_per_h: f64,
+    // Hygiene rendue par agent d'entretien et par heure, zone la plus sale d'abord.
+    pub cleaning_per_h: f64,
+    // Sous ce niveau, la zone degrade les unites produites et glisse.
+    pub hygiene_comfort: f64,
+    pub hygiene_defect: f64,
+    // Probabilite d'accident par heure de poste, majoree par fatigue et salete.
+    pub accident_rate_per_h: f64,
+    pub fatigue_risk: f64,
+    pub hygiene_risk: f64,
+    // Multiplicateur du risque tant qu'un responsable HSE est au poste.
+    pub prevention_factor: f64,
+    pub absence_h: f64,
+    pub accident_cost_eur: f64,
+}
+
+impl Default for HseBook {
+    fn default() -> Self {
+        Self {
+            schema_version: HSE_BOOK_SCHEMA_VERSION,
+            soiling_per_h: 4.0,
+            cleaning_per_h: 30.0,
+            hygiene_comfort: 70.0,
+            hygiene_defect: 0.06,
+            accident_rate_per_h: 0.0015,
+            fatigue_risk: 1.5,
+            hygiene_risk: 2.0,
+            prevention_factor: 0.4,
+            absence_h: 48.0,
+            accident_cost_eur: 1_200.0,
+        }
+    }
+}
+
+impl RonBook for HseBook {
+    const LABEL: &'static str = "regles HSE";
+    const PRETTY_DEPTH: usize = 2;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != HSE_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema HSE invalide: attendu={} recu={}",
+                HSE_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        let rates = [
+            self.soiling_per_h,
+            self.cleaning_per_h,
+            self.hygiene_defect,
+            self.accident_rate_per_h,
+            self.fatigue_risk,
+            self.hygiene_risk,
+            self.absence_h,
+            self.accident_cost_eur,
+        ];
+        if rates.iter().any(|rate| !rate.is_finite() || *rate < 0.0) {
+            return Err("taux, risques, arret et cout HSE doivent etre positifs".to_string());
+        }
+        if !(self.hygiene_comfort > 0.0 && self.hygiene_comfort <= HYGIENE_FULL) {
+            return Err(format!(
+                "seuil d'hygiene hors 0..{HYGIENE_FULL}: {}",
+                self.hygiene_comfort
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.prevention_factor) {
+            return Err(format!(
+                "facteur de prevention hors 0..1: {}",
+                self.prevention_factor
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl HseBook {
+    // Manque d'hygiene sous le seuil de confort, 0..1.
+    pub fn hygiene_deficit(&self, hygiene: f64) -> f64 {
+        ((self.hygiene_comfort - hygiene) / self.hygiene_comfort).clamp(0.0, 1.0)
+    }
+
+    pub fn hygiene_defect(&self, hygiene: f64) -> f64 {
+        self.hygiene_defect * self.hygiene_deficit(hygiene)
+    }
+
+    pub fn accident_chance(&self, fatigue: f64, hygiene: f64, prevention: bool, dt_h: f64) -> f64 {
+        let risk = 1.0
+            + self.fatigue_risk * (fatigue / 100.0).clamp(0.0, 1.0)
+            + self.hygiene_risk * self.hygiene_deficit(hygiene);
+        let factor = if prevention {
+            self.prevention_factor
+        } else {
+            1.0
+        };
+        (self.accident_rate_per_h * dt_h.max(0.0) * risk * factor).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HseState {
+    // Zones jamais salies: 100.
+    pub zone_hygiene: BTreeMap<ZoneKind, f64>,
+    pub accidents_total: u32,
+    pub last_accident_s: Option<f64>,
+    pub recent: Vec<String>,
+}
+
+impl HseState {
+    pub fn hygiene(&self, zone: ZoneKind) -> f64 {
+        self.zone_hygiene
+            .get(&zone)
+            .copied()
+            .unwrap_or(HYGIENE_FULL)
+    }
+
+    // Salit les zones occupees puis repartit le nettoyage, la plus sale d'abord.
+    // Renvoie la zone prise en charge par les agents d'entretien, s'il y en a une.
+    pub fn tick_hygiene(
+        &mut self,
+        book: &HseBook,
+        worked: &BTreeSet<ZoneKind>,
+        cleaners: usize,
+        dt_h: f64,
+    ) -> Option<ZoneKind> {
+        if !dt_h.is_finite() || dt_h <= 0.0 {
+            return None;
+        }
+        for zone in worked {
+            let hygiene = self.zone_hygiene.entry(*zone).or_insert(HYGIENE_FULL);
+            *hygiene = (*hygiene - book.soiling_per_h * dt_h).max(0.0);
+        }
+
+        let dirtiest = self
+            .zone_hygiene
+            .iter()
+            .filter(|(_, hygiene)| **hygiene < HYGIENE_FULL)
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(zone, _)| *zone);
+        if cleaners == 0 {
+            return None;
+        }
+
+        let mut budget = book.cleaning_per_h * cleaners as f64 * dt_h;
+        while budget > 1e-9 {
+            let Some(hygiene) = self
+                .zone_hygiene
+                .values_mut()
+                .filter(|hygiene| **hygiene < HYGIENE_FULL)
+                .min_by(|a, b| a.total_cmp(b))
+            else {
+                break;
+            };
+            let restored = (HYGIENE_FULL - *hygiene).min(budget);
+            *hygiene += restored;
+            budget -= restored;
+        }
+        dirtiest
+    }
+
+    pub fn record_accident(&mut self, name: &str, role_label: &str, now_s: f64) {
+        self.accidents_total = self.accidents_total.saturating_add(1);
+        self.last_accident_s = Some(now_s.max(0.0));
+        self.recent.push(format!(
+            "Accident J{}: {name} ({role_label})",
+            (now_s.max(0.0) / 86_400.0) as u64
+        ));
+        if self.recent.len() > HSE_RECENT_EVENTS {
+            self.recent.remove(0);
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let zones = if self.zone_hygiene.is_empty() {
+            "zones propres".to_string()
+        } else {
+            self.zone_hygiene
+                .iter()
+                .map(|(zone, hygiene)| format!("{} {:.0}%", zone.label(), hygiene))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let last = self
+            .last_accident_s
+            .map(|at_s| format!(", dernier J{}", (at_s / 86_400.0) as u64))
+            .unwrap_or_default();
+        format!("Hygiene {zones} | accidents {}{last}", self.accidents_total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = HseBook::default();
+        assert!(book.validate().is_ok());
+
+        let mut broken = book.clone();
+        broken.prevention_factor = 1.5;
+        assert!(broken.validate().is_err());
+    }
+
+    #[test]
+    fn worked_zones_get_dirty_and_cleaners_restore_the_dirtiest_first() {
+        let book = HseBook::default();
+        let mut state = HseState::default();
+        let worked = BTreeSet::from([ZoneKind::Processing]);
+        for _ in 0..10 {
+            state.tick_hygiene(&book, &worked, 0, 1.0);
+        }
+        state.zone_hygiene.insert(ZoneKind::Shipping, 90.0);
+        let dirty = state.hygiene(ZoneKind::Processing);
+        assert!(dirty < HYGIENE_FULL - book.soiling_per_h * 9.0);
+
+        let cleaned = state.tick_hygiene(&book, &BTreeSet::new(), 1, 0.5);
+        assert_eq!(cleaned, Some(ZoneKind::Processing));
+        assert!(state.hygiene(ZoneKind::Processing) > dirty);
+        assert_eq!(state.hygiene(ZoneKind::Shipping), 90.0);
+        assert_eq!(state.hygiene(ZoneKind::Receiving), HYGIENE_FULL);
+    }
+
+    #[test]
+    fn fatigue_and_dirt_raise_accident_risk_and_prevention_lowers_it() {
+        let book = HseBook::default();
+        let rested = book.accident_chance(0.0, HYGIENE_FULL, false, 1.0);
+        let tired_dirty = book.accident_chance(90.0, 20.0, false, 1.0);
+        let prevented = book.accident_chance(90.0, 20.0, true, 1.0);
+        assert!((rested - book.accident_rate_per_h).abs() < 1e-12);
+        assert!(tired_dirty > rested * 2.0);
+        assert!((prevented - tired_dirty * book.prevention_factor).abs() < 1e-12);
+    }
+}
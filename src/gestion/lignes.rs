@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use super::horaires::default_shift_template_id;
 use super::personnel::EmployeeId;
+use super::qualite::QualityStats;
 
 pub type ProductionLineId = u32;
 
@@ -21,22 +23,68 @@ pub struct ProductionLineState {
     pub target_boxes_per_hour: f64,
     pub staffing_factor: f64,
     pub active_temps: u8,
+    #[serde(default)]
+    pub block_ids: Vec<u32>,
+    #[serde(default)]
+    pub good_units_total: u32,
+    #[serde(default)]
+    pub scrap_units_total: u32,
+    #[serde(default)]
+    pub active_hours: f64,
+    #[serde(default)]
+    pub throughput_per_hour: f64,
+    #[serde(default = "default_shift_template_id")]
+    pub shift_template: String,
+    // Grades et rebuts du tri qualite de la ligne.
+    #[serde(default)]
+    pub quality: QualityStats,
 }
 
 impl ProductionLineState {
-    pub fn main_line() -> Self {
+    pub fn new(id: ProductionLineId) -> Self {
         Self {
-            id: 1,
-            label: "Deshydratation 1".to_string(),
+            id,
+            label: format!("Deshydratation {id}"),
             assigned_lead_id: None,
             status: LineOperationalState::Bloquee,
             block_reason: "aucun chef assigne".to_string(),
             target_boxes_per_hour: 6.0,
             staffing_factor: 0.0,
             active_temps: 0,
+            block_ids: Vec::new(),
+            good_units_total: 0,
+            scrap_units_total: 0,
+            active_hours: 0.0,
+            throughput_per_hour: 0.0,
+            shift_template: default_shift_template_id(),
+            quality: QualityStats::default(),
+        }
+    }
+
+    pub fn main_line() -> Self {
+        Self::new(1)
+    }
+
+    pub fn scrap_ratio(&self) -> f64 {
+        let total = self.good_units_total.saturating_add(self.scrap_units_total);
+        if total == 0 {
+            0.0
+        } else {
+            self.scrap_units_total as f64 / total as f64
         }
     }
 
+    pub fn record_output(&mut self, good: u32, scrap: u32, units_per_box: u32, dt_hours: f64) {
+        self.good_units_total = self.good_units_total.saturating_add(good);
+        self.scrap_units_total = self.scrap_units_total.saturating_add(scrap);
+        if dt_hours.is_finite() && dt_hours > 0.0 {
+            self.active_hours += dt_hours;
+        }
+        // Debit en boxes/h, lisse sur au moins une heure de marche.
+        self.throughput_per_hour =
+            self.good_units_total as f64 / units_per_box.max(1) as f64 / self.active_hours.max(1.0);
+    }
+
     pub fn set_blocked(&mut self, reason: impl Into<String>) {
         self.status = LineOperationalState::Bloquee;
         self.block_reason = reason.into();
@@ -71,4 +119,14 @@ mod tests {
         assert_eq!(staffing_factor_for_temps(2), 1.00);
         assert_eq!(staffing_factor_for_temps(9), 1.15);
     }
+
+    #[test]
+    fn record_output_tracks_scrap_ratio_and_box_throughput() {
+        let mut line = ProductionLineState::new(2);
+        assert_eq!(line.label, "Deshydratation 2");
+        line.record_output(294, 0, 294, 0.5);
+        line.record_output(294, 147, 294, 1.5);
+        assert!((line.throughput_per_hour - 1.0).abs() < 1e-9);
+        assert!((line.scrap_ratio() - 0.2).abs() < 1e-9);
+    }
 }
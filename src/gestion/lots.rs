@@ -0,0 +1,461 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+pub type LotId = u64;
+pub type BoxId = u64;
+
+pub const OPENING_STOCK_SUPPLIER: &str = "Stock initial";
+const BOX_TRACE_HISTORY: usize = 10_000;
+// Un lot vendu ou rebute reste tracable 30 jours apres sa derniere box vendue.
+pub const LOT_TRACEABILITY_WINDOW_S: f64 = 30.0 * 86_400.0;
+const LOT_PRUNE_INTERVAL_S: f64 = 86_400.0;
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityGrade {
+    #[default]
+    A,
+    B,
+    C,
+}
+
+impl QualityGrade {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::A => "A",
+            Self::B => "B",
+            Self::C => "C",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LotOrigin {
+    Reception {
+        supplier: String,
+        received_at_s: f64,
+        grade: QualityGrade,
+        qty: u32,
+    },
+    // Lot intermediaire ne du melange de plusieurs lots sur une etape.
+    Fusion {
+        parents: Vec<LotId>,
+        stage_id: String,
+        created_at_s: f64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lot {
+    pub id: LotId,
+    pub origin: LotOrigin,
+}
+
+impl Lot {
+    pub fn label(&self) -> String {
+        match &self.origin {
+            LotOrigin::Reception {
+                supplier, grade, ..
+            } => format!("#{} {} ({})", self.id, supplier, grade.label()),
+            LotOrigin::Fusion { stage_id, .. } => format!("#{} fusion {}", self.id, stage_id),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LotPortion {
+    pub lot_id: LotId,
+    pub qty: u32,
+}
+
+// File FIFO de portions de lots: prendre decoupe, deposer fusionne les portions voisines.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LotQueue {
+    pub portions: VecDeque<LotPortion>,
+}
+
+impl LotQueue {
+    pub fn total(&self) -> u32 {
+        self.portions
+            .iter()
+            .fold(0u32, |acc, portion| acc.saturating_add(portion.qty))
+    }
+
+    pub fn push(&mut self, lot_id: LotId, qty: u32) {
+        if qty == 0 {
+            return;
+        }
+        if let Some(last) = self.portions.back_mut()
+            && last.lot_id == lot_id
+        {
+            last.qty = last.qty.saturating_add(qty);
+            return;
+        }
+        self.portions.push_back(LotPortion { lot_id, qty });
+    }
+
+    pub fn extend(&mut self, portions: &[LotPortion]) {
+        for portion in portions {
+            self.push(portion.lot_id, portion.qty);
+        }
+    }
+
+    pub fn take(&mut self, qty: u32) -> Vec<LotPortion> {
+        let mut left = qty;
+        let mut taken = Vec::new();
+        while left > 0 {
+            let Some(front) = self.portions.front_mut() else {
+                break;
+            };
+            let part = front.qty.min(left);
+            taken.push(LotPortion {
+                lot_id: front.lot_id,
+                qty: part,
+            });
+            front.qty -= part;
+            left -= part;
+            if front.qty == 0 {
+                self.portions.pop_front();
+            }
+        }
+        taken
+    }
+
+    pub fn drain_all(&mut self) -> Vec<LotPortion> {
+        self.portions.drain(..).collect()
+    }
+
+    // Aligne la file sur un compteur: complete avec `fallback_lot` ou retire les plus recents.
+    pub fn reconcile(&mut self, expected: u32, fallback_lot: impl FnOnce() -> LotId) {
+        let total = self.total();
+        if total < expected {
+            self.push(fallback_lot(), expected - total);
+        } else if total > expected {
+            let mut excess = total - expected;
+            while excess > 0 {
+                let Some(back) = self.portions.back_mut() else {
+                    break;
+                };
+                let part = back.qty.min(excess);
+                back.qty -= part;
+                excess -= part;
+                if back.qty == 0 {
+                    self.portions.pop_back();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoxTrace {
+    pub id: BoxId,
+    pub lots: Vec<LotId>,
+    pub packed_at_s: f64,
+    #[serde(default)]
+    pub sold_at_s: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LotBook {
+    pub lots: BTreeMap<LotId, Lot>,
+    pub boxes: VecDeque<BoxTrace>,
+    pub next_lot_id: LotId,
+    pub next_box_id: BoxId,
+    pub last_pruned_s: f64,
+}
+
+impl Default for LotBook {
+    fn default() -> Self {
+        Self {
+            lots: BTreeMap::new(),
+            boxes: VecDeque::new(),
+            next_lot_id: 1,
+            next_box_id: 1,
+            last_pruned_s: 0.0,
+        }
+    }
+}
+
+impl LotBook {
+    fn insert(&mut self, origin: LotOrigin) -> LotId {
+        let id = self.next_lot_id;
+        self.next_lot_id = self.next_lot_id.saturating_add(1).max(1);
+        self.lots.insert(id, Lot { id, origin });
+        id
+    }
+
+    pub fn receive(
+        &mut self,
+        supplier: impl Into<String>,
+        received_at_s: f64,
+        grade: QualityGrade,
+        qty: u32,
+    ) -> LotId {
+        self.insert(LotOrigin::Reception {
+            supplier: supplier.into(),
+            received_at_s: received_at_s.max(0.0),
+            grade,
+            qty,
+        })
+    }
+
+    // Un seul lot parent: on le reutilise; sinon un lot de fusion garde la genealogie.
+    pub fn merge(&mut self, parents: &[LotId], stage_id: &str, now_s: f64) -> Option<LotId> {
+        let unique = parents.iter().copied().collect::<BTreeSet<_>>();
+        match unique.len() {
+            0 => None,
+            1 => unique.first().copied(),
+            _ => Some(self.insert(LotOrigin::Fusion {
+                parents: unique.into_iter().collect(),
+                stage_id: stage_id.to_string(),
+                created_at_s: now_s.max(0.0),
+            })),
+        }
+    }
+
+    pub fn last_box(&self) -> Option<&BoxTrace> {
+        self.boxes.back()
+    }
+
+    pub fn reception_grade(&self, id: LotId) -> Option<QualityGrade> {
+        match self.lots.get(&id).map(|lot| &lot.origin) {
+            Some(LotOrigin::Reception { grade, .. }) => Some(*grade),
+            _ => None,
+        }
+    }
+
+    pub fn raw_lots_of(&self, id: LotId) -> BTreeSet<LotId> {
+        let mut raw = BTreeSet::new();
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![id];
+        while let Some(current) = pending.pop() {
+            if !seen.insert(current) {
+                continue;
+            }
+            match self.lots.get(&current).map(|lot| &lot.origin) {
+                Some(LotOrigin::Reception { .. }) => {
+                    raw.insert(current);
+                }
+                Some(LotOrigin::Fusion { parents, .. }) => pending.extend(parents.iter().copied()),
+                None => {}
+            }
+        }
+        raw
+    }
+
+    // Les gros rattrapages (vieilles sauvegardes) ne gardent que la fenetre d'historique.
+    pub fn record_boxes(&mut self, count: u32, lots: &BTreeSet<LotId>, packed_at_s: f64) {
+        let recorded = (count as usize).min(BOX_TRACE_HISTORY);
+        self.next_box_id = self
+            .next_box_id
+            .saturating_add((count as usize - recorded) as u64);
+        for _ in 0..recorded {
+            self.record_box(lots, packed_at_s);
+        }
+    }
+
+    pub fn record_box(&mut self, lots: &BTreeSet<LotId>, packed_at_s: f64) -> BoxId {
+        let id = self.next_box_id;
+        self.next_box_id = self.next_box_id.saturating_add(1).max(1);
+        self.boxes.push_back(BoxTrace {
+            id,
+            lots: lots.iter().copied().collect(),
+            packed_at_s: packed_at_s.max(0.0),
+            sold_at_s: None,
+        });
+        while self.boxes.len() > BOX_TRACE_HISTORY {
+            self.boxes.pop_front();
+        }
+        id
+    }
+
+    pub fn mark_sold(&mut self, qty: u32, now_s: f64) {
+        for trace in self
+            .boxes
+            .iter_mut()
+            .filter(|trace| trace.sold_at_s.is_none())
+            .take(qty as usize)
+        {
+            trace.sold_at_s = Some(now_s.max(0.0));
+        }
+    }
+
+    pub fn box_trace(&self, box_id: BoxId) -> Option<&BoxTrace> {
+        self.boxes.iter().find(|trace| trace.id == box_id)
+    }
+
+    pub fn raw_lots_of_box(&self, box_id: BoxId) -> Vec<&Lot> {
+        let Some(trace) = self.box_trace(box_id) else {
+            return Vec::new();
+        };
+        trace
+            .lots
+            .iter()
+            .flat_map(|lot_id| self.raw_lots_of(*lot_id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|lot_id| self.lots.get(&lot_id))
+            .collect()
+    }
+
+    pub fn prune_due(&self, now_s: f64) -> bool {
+        now_s - self.last_pruned_s >= LOT_PRUNE_INTERVAL_S
+    }
+
+    // Oublie les boxes vendues hors fenetre et les lots que plus rien ne reference:
+    // ni une file vivante (`live`), ni une box gardee, ni une creation recente.
+    pub fn prune(&mut self, live: &BTreeSet<LotId>, now_s: f64, window_s: f64) {
+        self.last_pruned_s = now_s;
+        let horizon_s = now_s - window_s.max(0.0);
+        self.boxes.retain(|trace| {
+            trace
+                .sold_at_s
+                .is_none_or(|sold_at_s| sold_at_s >= horizon_s)
+        });
+
+        let mut pending = live.iter().copied().collect::<Vec<_>>();
+        pending.extend(
+            self.boxes
+                .iter()
+                .flat_map(|trace| trace.lots.iter().copied()),
+        );
+        pending.extend(self.lots.values().filter_map(|lot| {
+            let created_at_s = match &lot.origin {
+                LotOrigin::Reception { received_at_s, .. } => *received_at_s,
+                LotOrigin::Fusion { created_at_s, .. } => *created_at_s,
+            };
+            (created_at_s >= horizon_s).then_some(lot.id)
+        }));
+
+        let mut kept = BTreeSet::new();
+        while let Some(current) = pending.pop() {
+            if !kept.insert(current) {
+                continue;
+            }
+            if let Some(LotOrigin::Fusion { parents, .. }) =
+                self.lots.get(&current).map(|lot| &lot.origin)
+            {
+                pending.extend(parents.iter().copied());
+            }
+        }
+        self.lots.retain(|id, _| kept.contains(id));
+    }
+
+    // Scenario de rappel: toutes les boxes qui contiennent de la matiere du lot brut.
+    pub fn boxes_from_raw_lot(&self, raw_lot_id: LotId) -> Vec<BoxId> {
+        self.boxes
+            .iter()
+            .filter(|trace| {
+                trace
+                    .lots
+                    .iter()
+                    .any(|lot_id| self.raw_lots_of(*lot_id).contains(&raw_lot_id))
+            })
+            .map(|trace| trace.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lot_queue_splits_and_merges_portions_fifo() {
+        let mut queue = LotQueue::default();
+        queue.push(1, 3);
+        queue.push(1, 2);
+        queue.push(2, 4);
+        assert_eq!(queue.portions.len(), 2);
+
+        let taken = queue.take(6);
+        assert_eq!(
+            taken,
+            vec![
+                LotPortion { lot_id: 1, qty: 5 },
+                LotPortion { lot_id: 2, qty: 1 }
+            ]
+        );
+        assert_eq!(queue.total(), 3);
+
+        queue.reconcile(5, || 9);
+        assert_eq!(queue.total(), 5);
+        queue.reconcile(1, || unreachable!());
+        assert_eq!(queue.take(10), vec![LotPortion { lot_id: 2, qty: 1 }]);
+    }
+
+    #[test]
+    fn box_traces_back_to_raw_lots_through_fusions() {
+        let mut book = LotBook::default();
+        let first = book.receive("Coop", 10.0, QualityGrade::A, 100);
+        let second = book.receive("Coop", 20.0, QualityGrade::B, 100);
+        let third = book.receive("Autre", 30.0, QualityGrade::C, 100);
+        assert_eq!(book.merge(&[first, first], "four", 40.0), Some(first));
+        let mixed = book
+            .merge(&[first, second], "four", 40.0)
+            .expect("two parents should create a fusion lot");
+
+        let box_id = book.record_box(&BTreeSet::from([mixed]), 50.0);
+        let other_box = book.record_box(&BTreeSet::from([third]), 60.0);
+        let raw = book
+            .raw_lots_of_box(box_id)
+            .iter()
+            .map(|lot| lot.id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(raw, vec![first, second]);
+        assert_eq!(book.boxes_from_raw_lot(second), vec![box_id]);
+        assert_eq!(book.boxes_from_raw_lot(third), vec![other_box]);
+
+        book.mark_sold(1, 70.0);
+        assert_eq!(book.box_trace(box_id).and_then(|b| b.sold_at_s), Some(70.0));
+        assert_eq!(book.box_trace(other_box).and_then(|b| b.sold_at_s), None);
+    }
+
+    #[test]
+    fn prune_drops_old_sold_lots_but_keeps_live_and_unsold_genealogy() {
+        let day = 86_400.0;
+        let mut book = LotBook::default();
+        let sold_raw = book.receive("Coop", 0.0, QualityGrade::A, 100);
+        let scrapped = book.receive("Coop", 0.0, QualityGrade::C, 100);
+        let in_stock = book.receive("Coop", 0.0, QualityGrade::B, 100);
+        let first = book.receive("Autre", 0.0, QualityGrade::A, 100);
+        let second = book.receive("Autre", 0.0, QualityGrade::B, 100);
+        let mixed = book
+            .merge(&[first, second], "four", day)
+            .expect("two parents should create a fusion lot");
+        let sold_box = book.record_box(&BTreeSet::from([sold_raw]), day);
+        let unsold_box = book.record_box(&BTreeSet::from([mixed]), day);
+        book.mark_sold(1, 2.0 * day);
+
+        let now_s = 2.0 * day + LOT_TRACEABILITY_WINDOW_S - 1.0;
+        book.prune(
+            &BTreeSet::from([in_stock]),
+            now_s,
+            LOT_TRACEABILITY_WINDOW_S,
+        );
+        assert!(book.box_trace(sold_box).is_some());
+        assert!(book.lots.contains_key(&sold_raw));
+        assert!(!book.lots.contains_key(&scrapped));
+
+        let later_s = now_s + 2.0;
+        assert!(book.prune_due(later_s + LOT_PRUNE_INTERVAL_S));
+        book.prune(
+            &BTreeSet::from([in_stock]),
+            later_s,
+            LOT_TRACEABILITY_WINDOW_S,
+        );
+        assert!(book.box_trace(sold_box).is_none());
+        assert!(!book.lots.contains_key(&sold_raw));
+        assert!(book.lots.contains_key(&in_stock));
+        assert_eq!(book.boxes_from_raw_lot(second), vec![unsold_box]);
+        assert_eq!(book.lots.len(), 4);
+        assert!(!book.prune_due(later_s + 1.0));
+    }
+}
@@ -0,0 +1,693 @@
+use super::carnet::{OrderId, OrderLedger};
+use super::reglages::RonBook;
+use crate::aleatoire::SimRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+pub const MARKET_BOOK_PATH: &str = "data/market.ron";
+const MARKET_BOOK_SCHEMA_VERSION: u32 = 1;
+// Contrats clos gardes pour l'affichage; l'OTIF se calcule sur le carnet de commandes.
+const CLOSED_CONTRACTS_KEPT: usize = 20;
+// Au-dela, le prix fait fuir (ou affluer) les clients sans emballer la demande.
+const MAX_PRICE_FACTOR: f64 = 4.0;
+
+pub type ContractId = u32;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ProductDemand {
+    pub product_id: String,
+    pub reference_price_eur: f64,
+    // Demande spot journaliere au prix de reference, un jour de semaine.
+    pub units_per_day: f64,
+    pub elasticity: f64,
+    // 24 poids horaires, normalises au chargement.
+    pub hourly_profile: Vec<f64>,
+    pub weekend_factor: f64,
+    // Ecart journalier tire au hasard: 0.2 = +/-20%.
+    pub daily_variation: f64,
+}
+
+impl ProductDemand {
+    fn validate(&self) -> Result<(), String> {
+        if self.product_id.trim().is_empty() {
+            return Err("demande marche sans produit".to_string());
+        }
+        let positive = |v: f64| v.is_finite() && v > 0.0;
+        if !positive(self.reference_price_eur) || !positive(self.units_per_day) {
+            return Err(format!(
+                "demande {}: prix de reference ou volume invalide",
+                self.product_id
+            ));
+        }
+        if !self.elasticity.is_finite() || !(0.0..=10.0).contains(&self.elasticity) {
+            return Err(format!(
+                "demande {}: elasticite invalide ({})",
+                self.product_id, self.elasticity
+            ));
+        }
+        if self.hourly_profile.len() != 24
+            || self
+                .hourly_profile
+                .iter()
+                .any(|w| !w.is_finite() || *w < 0.0)
+            || self.hourly_profile.iter().sum::<f64>() <= 0.0
+        {
+            return Err(format!(
+                "demande {}: profil horaire invalide (24 poids positifs attendus)",
+                self.product_id
+            ));
+        }
+        if !self.weekend_factor.is_finite() || self.weekend_factor < 0.0 {
+            return Err(format!(
+                "demande {}: facteur weekend invalide",
+                self.product_id
+            ));
+        }
+        if !self.daily_variation.is_finite() || !(0.0..1.0).contains(&self.daily_variation) {
+            return Err(format!(
+                "demande {}: variation journaliere invalide",
+                self.product_id
+            ));
+        }
+        Ok(())
+    }
+
+    // Elasticite a prix constant: (prix / reference) ^ -elasticite.
+    pub fn price_factor(&self, price_eur: f64) -> f64 {
+        if !price_eur.is_finite() || price_eur <= 0.0 {
+            return MAX_PRICE_FACTOR;
+        }
+        (price_eur / self.reference_price_eur)
+            .powf(-self.elasticity)
+            .clamp(0.0, MAX_PRICE_FACTOR)
+    }
+
+    pub fn units_per_hour(&self, hour: f64, weekend: bool, price_eur: f64, day_factor: f64) -> f64 {
+        let total = self.hourly_profile.iter().sum::<f64>().max(f64::EPSILON);
+        let weight = self.hourly_profile[(hour.floor() as usize).min(23)] / total;
+        let weekend = if weekend { self.weekend_factor } else { 1.0 };
+        self.units_per_day * weight * weekend * day_factor * self.price_factor(price_eur)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ContractTerms {
+    pub offer_every_h: f64,
+    pub offer_validity_h: f64,
+    pub max_open_offers: usize,
+    pub volume_min: u32,
+    pub volume_max: u32,
+    // Delai de livraison compte depuis l'offre, pas depuis l'acceptation.
+    pub due_min_h: f64,
+    pub due_max_h: f64,
+    // Prix contractuel en part du prix de reference du produit.
+    pub price_factor: f64,
+    pub penalty_per_missing_box_eur: f64,
+    // Apres l'echeance, le client accepte encore un retard avant d'annuler le reliquat.
+    #[serde(default = "default_late_grace_h")]
+    pub late_grace_h: f64,
+    pub customers: Vec<String>,
+}
+
+fn default_late_grace_h() -> f64 {
+    24.0
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MarketBook {
+    pub schema_version: u32,
+    // Heures de demande spot que les clients attendent avant d'aller ailleurs.
+    pub backlog_hours: f64,
+    pub products: Vec<ProductDemand>,
+    pub contracts: ContractTerms,
+}
+
+fn demand(product_id: &str, reference_price_eur: f64, units_per_day: f64) -> ProductDemand {
+    ProductDemand {
+        product_id: product_id.to_string(),
+        reference_price_eur,
+        units_per_day,
+        elasticity: 1.5,
+        hourly_profile: vec![
+            1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0, 5.0, 6.0, 6.0, 6.0, 5.0, 5.0, 6.0, 6.0, 5.0,
+            4.0, 3.0, 2.0, 2.0, 1.0, 1.0, 1.0,
+        ],
+        weekend_factor: 0.4,
+        daily_variation: 0.15,
+    }
+}
+
+impl Default for MarketBook {
+    fn default() -> Self {
+        Self {
+            schema_version: MARKET_BOOK_SCHEMA_VERSION,
+            backlog_hours: 8.0,
+            products: vec![demand("ail", 780.0, 420.0), demand("oignon", 640.0, 360.0)],
+            contracts: ContractTerms {
+                offer_every_h: 12.0,
+                offer_validity_h: 8.0,
+                max_open_offers: 3,
+                volume_min: 40,
+                volume_max: 160,
+                due_min_h: 24.0,
+                due_max_h: 72.0,
+                price_factor: 0.92,
+                penalty_per_missing_box_eur: 250.0,
+                late_grace_h: default_late_grace_h(),
+                customers: vec![
+                    "Epices du Sud".to_string(),
+                    "Grossiste Rungis".to_string(),
+                    "Conserverie Marel".to_string(),
+                    "Plats Cuisines Duval".to_string(),
+                ],
+            },
+        }
+    }
+}
+
+impl RonBook for MarketBook {
+    const LABEL: &'static str = "marche";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != MARKET_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema marche invalide: attendu={} recu={}",
+                MARKET_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        if !self.backlog_hours.is_finite() || self.backlog_hours <= 0.0 {
+            return Err(format!("attente clients invalide: {}", self.backlog_hours));
+        }
+        if self.products.is_empty() {
+            return Err("marche sans produit".to_string());
+        }
+        let mut ids = HashSet::new();
+        for product in &self.products {
+            product.validate()?;
+            if !ids.insert(product.product_id.as_str()) {
+                return Err(format!("demande dupliquee: {}", product.product_id));
+            }
+        }
+
+        let terms = &self.contracts;
+        let positive = |v: f64| v.is_finite() && v > 0.0;
+        if !positive(terms.offer_every_h) || !positive(terms.offer_validity_h) {
+            return Err("contrats: frequence ou validite d'offre invalide".to_string());
+        }
+        if terms.volume_min == 0 || terms.volume_min > terms.volume_max {
+            return Err(format!(
+                "contrats: volume invalide ({}-{})",
+                terms.volume_min, terms.volume_max
+            ));
+        }
+        if !positive(terms.due_min_h) || terms.due_min_h > terms.due_max_h {
+            return Err(format!(
+                "contrats: delai invalide ({}h-{}h)",
+                terms.due_min_h, terms.due_max_h
+            ));
+        }
+        if !positive(terms.price_factor)
+            || !terms.penalty_per_missing_box_eur.is_finite()
+            || terms.penalty_per_missing_box_eur < 0.0
+            || !terms.late_grace_h.is_finite()
+            || terms.late_grace_h < 0.0
+        {
+            return Err("contrats: prix ou penalite invalide".to_string());
+        }
+        if terms.customers.is_empty() {
+            return Err("contrats: aucun client".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl MarketBook {
+    pub fn product(&self, product_id: &str) -> Option<&ProductDemand> {
+        self.products
+            .iter()
+            .find(|product| product.product_id == product_id)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractStatus {
+    Offre,
+    EnCours,
+    EnRetard,
+    Livre,
+    Echoue,
+    Refuse,
+    Expire,
+}
+
+impl ContractStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Offre => "Offre",
+            Self::EnCours => "En cours",
+            Self::EnRetard => "En retard",
+            Self::Livre => "Livre",
+            Self::Echoue => "Echoue",
+            Self::Refuse => "Refuse",
+            Self::Expire => "Expire",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CustomerContract {
+    pub id: ContractId,
+    pub customer: String,
+    pub product_id: String,
+    pub volume: u32,
+    pub delivered: u32,
+    pub unit_price_eur: f64,
+    pub penalty_per_box_eur: f64,
+    pub offered_at_s: f64,
+    pub offer_expires_s: f64,
+    pub due_s: f64,
+    pub closed_at_s: Option<f64>,
+    pub status: ContractStatus,
+    // Ligne du carnet de commandes ouverte a l'acceptation.
+    #[serde(default)]
+    pub order_id: Option<OrderId>,
+}
+
+impl CustomerContract {
+    pub fn remaining(&self) -> u32 {
+        self.volume.saturating_sub(self.delivered)
+    }
+
+    // Accepte et pas encore solde: livrable, meme en retard.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.status,
+            ContractStatus::EnCours | ContractStatus::EnRetard
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarketEvent {
+    NewOffer(ContractId),
+    ContractLate {
+        id: ContractId,
+        missing: u32,
+        penalty_eur: f64,
+    },
+    ContractCancelled {
+        id: ContractId,
+        missing: u32,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MarketState {
+    // Clients spot en attente, par produit.
+    pub spot_demand: BTreeMap<String, f64>,
+    pub day_factors: BTreeMap<String, f64>,
+    pub day_index: Option<u64>,
+    pub lost_units: f64,
+    pub last_demand_per_hour: f64,
+    pub next_offer_s: f64,
+    pub next_contract_id: ContractId,
+    pub contracts: Vec<CustomerContract>,
+    pub orders: OrderLedger,
+    pub penalties_total_eur: f64,
+}
+
+impl Default for MarketState {
+    fn default() -> Self {
+        Self {
+            spot_demand: BTreeMap::new(),
+            day_factors: BTreeMap::new(),
+            day_index: None,
+            lost_units: 0.0,
+            last_demand_per_hour: 0.0,
+            next_offer_s: 0.0,
+            next_contract_id: 1,
+            contracts: Vec::new(),
+            orders: OrderLedger::default(),
+            penalties_total_eur: 0.0,
+        }
+    }
+}
+
+impl MarketState {
+    #[allow(clippy::too_many_arguments)]
+    pub fn tick(
+        &mut self,
+        book: &MarketBook,
+        dt_hours: f64,
+        now_s: f64,
+        day_index: u64,
+        hour: f64,
+        weekend: bool,
+        sale_price: f64,
+        active_product: &str,
+        rng: &mut SimRng,
+    ) -> Vec<MarketEvent> {
+        if self.day_index != Some(day_index) {
+            self.day_index = Some(day_index);
+            for product in &book.products {
+                let swing = product.daily_variation * (rng.next_f64() * 2.0 - 1.0);
+                self.day_factors
+                    .insert(product.product_id.clone(), 1.0 + swing);
+            }
+        }
+
+        if dt_hours.is_finite() && dt_hours > 0.0 {
+            for product in &book.products {
+                let day_factor = self
+                    .day_factors
+                    .get(&product.product_id)
+                    .copied()
+                    .unwrap_or(1.0);
+                let active = product.product_id == active_product;
+                // Les produits hors fabrication restent au prix de reference.
+                let price = if active {
+                    sale_price
+                } else {
+                    product.reference_price_eur
+                };
+                let per_hour = product.units_per_hour(hour, weekend, price, day_factor);
+                let cap = product.units_per_day / 24.0 * book.backlog_hours;
+                let waiting = self
+                    .spot_demand
+                    .entry(product.product_id.clone())
+                    .or_insert(0.0);
+                *waiting += per_hour * dt_hours;
+                if *waiting > cap {
+                    if active {
+                        self.lost_units += *waiting - cap;
+                    }
+                    *waiting = cap;
+                }
+                if active {
+                    self.last_demand_per_hour = per_hour;
+                }
+            }
+        }
+
+        let mut events = Vec::new();
+        let cancel_after_s = book.contracts.late_grace_h * 3600.0;
+        for contract in &mut self.contracts {
+            match contract.status {
+                ContractStatus::Offre if now_s >= contract.offer_expires_s => {
+                    contract.status = ContractStatus::Expire;
+                    contract.closed_at_s = Some(now_s);
+                }
+                // Penalite une fois a l'echeance sur le manquant; le reliquat reste livrable.
+                ContractStatus::EnCours if now_s >= contract.due_s => {
+                    let missing = contract.remaining();
+                    let penalty_eur = missing as f64 * contract.penalty_per_box_eur;
+                    contract.status = ContractStatus::EnRetard;
+                    self.penalties_total_eur += penalty_eur;
+                    events.push(MarketEvent::ContractLate {
+                        id: contract.id,
+                        missing,
+                        penalty_eur,
+                    });
+                }
+                ContractStatus::EnRetard if now_s >= contract.due_s + cancel_after_s => {
+                    contract.status = ContractStatus::Echoue;
+                    contract.closed_at_s = Some(now_s);
+                    if let Some(order_id) = contract.order_id {
+                        let _ = self.orders.close(order_id, now_s);
+                    }
+                    events.push(MarketEvent::ContractCancelled {
+                        id: contract.id,
+                        missing: contract.remaining(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if now_s >= self.next_offer_s {
+            let terms = &book.contracts;
+            if self.offers().count() < terms.max_open_offers {
+                let id = self.push_offer(book, now_s, rng);
+                events.push(MarketEvent::NewOffer(id));
+            }
+            self.next_offer_s = now_s + terms.offer_every_h * 3600.0 * (0.5 + rng.next_f64());
+        }
+
+        self.prune_history();
+        self.orders.prune(now_s);
+        events
+    }
+
+    fn push_offer(&mut self, book: &MarketBook, now_s: f64, rng: &mut SimRng) -> ContractId {
+        let terms = &book.contracts;
+        let product = &book.products[rng.roll_range(book.products.len() as u32) as usize];
+        let customer = &terms.customers[rng.roll_range(terms.customers.len() as u32) as usize];
+        let volume = terms.volume_min + rng.roll_range(terms.volume_max - terms.volume_min + 1);
+        let due_h = terms.due_min_h + (terms.due_max_h - terms.due_min_h) * rng.next_f64();
+        let id = self.next_contract_id;
+        self.next_contract_id = self.next_contract_id.saturating_add(1);
+        self.contracts.push(CustomerContract {
+            id,
+            customer: customer.clone(),
+            product_id: product.product_id.clone(),
+            volume,
+            delivered: 0,
+            unit_price_eur: (product.reference_price_eur * terms.price_factor).round(),
+            penalty_per_box_eur: terms.penalty_per_missing_box_eur,
+            offered_at_s: now_s,
+            offer_expires_s: now_s + terms.offer_validity_h * 3600.0,
+            due_s: now_s + due_h * 3600.0,
+            closed_at_s: None,
+            status: ContractStatus::Offre,
+            order_id: None,
+        });
+        id
+    }
+
+    fn prune_history(&mut self) {
+        let finished = self
+            .contracts
+            .iter()
+            .filter(|contract| contract.status != ContractStatus::Offre && !contract.is_active())
+            .count();
+        let mut excess = finished.saturating_sub(CLOSED_CONTRACTS_KEPT);
+        self.contracts.retain(|contract| {
+            let open = contract.status == ContractStatus::Offre || contract.is_active();
+            if open || excess == 0 {
+                true
+            } else {
+                excess -= 1;
+                false
+            }
+        });
+    }
+
+    // Les contrats en cours passent avant les clients spot, echeance la plus proche d'abord.
+    pub fn ship(
+        &mut self,
+        units: u32,
+        active_product: &str,
+        spot_price: f64,
+        now_s: f64,
+    ) -> (u32, f64) {
+        let mut left = units;
+        let mut revenue = 0.0;
+
+        let mut order = self
+            .contracts
+            .iter()
+            .enumerate()
+            .filter(|(_, contract)| contract.is_active() && contract.product_id == active_product)
+            .map(|(idx, contract)| (contract.due_s, idx))
+            .collect::<Vec<_>>();
+        order.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, idx) in order {
+            if left == 0 {
+                break;
+            }
+            let contract = &mut self.contracts[idx];
+            let qty = contract.remaining().min(left);
+            contract.delivered += qty;
+            left -= qty;
+            revenue += qty as f64 * contract.unit_price_eur;
+            if let Some(order_id) = contract.order_id {
+                let _ = self.orders.record_shipment(order_id, qty, now_s);
+            }
+            if contract.remaining() == 0 {
+                contract.status = ContractStatus::Livre;
+                contract.closed_at_s = Some(now_s);
+            }
+        }
+
+        if let Some(waiting) = self.spot_demand.get_mut(active_product) {
+            let spot = (waiting.floor().max(0.0) as u32).min(left);
+            *waiting -= spot as f64;
+            left -= spot;
+            revenue += spot as f64 * spot_price.max(0.0);
+        }
+
+        (units - left, revenue)
+    }
+
+    pub fn has_demand(&self, product_id: &str) -> bool {
+        self.spot_demand
+            .get(product_id)
+            .is_some_and(|waiting| *waiting >= 1.0)
+            || self
+                .contracts
+                .iter()
+                .any(|contract| contract.is_active() && contract.product_id == product_id)
+    }
+
+    pub fn spot_waiting(&self, product_id: &str) -> f64 {
+        self.spot_demand.get(product_id).copied().unwrap_or(0.0)
+    }
+
+    pub fn contract(&self, id: ContractId) -> Option<&CustomerContract> {
+        self.contracts.iter().find(|contract| contract.id == id)
+    }
+
+    pub fn offers(&self) -> impl Iterator<Item = &CustomerContract> {
+        self.contracts
+            .iter()
+            .filter(|contract| contract.status == ContractStatus::Offre)
+    }
+
+    pub fn active_contracts(&self) -> impl Iterator<Item = &CustomerContract> {
+        self.contracts
+            .iter()
+            .filter(|contract| contract.is_active())
+    }
+
+    fn open_offer_mut(&mut self, id: ContractId) -> Result<&mut CustomerContract, String> {
+        let contract = self
+            .contracts
+            .iter_mut()
+            .find(|contract| contract.id == id)
+            .ok_or_else(|| format!("contrat introuvable: #{id}"))?;
+        if contract.status != ContractStatus::Offre {
+            return Err(format!(
+                "contrat #{id} non disponible ({})",
+                contract.status.label().to_lowercase()
+            ));
+        }
+        Ok(contract)
+    }
+
+    pub fn accept(&mut self, id: ContractId, now_s: f64) -> Result<String, String> {
+        let contract = self.open_offer_mut(id)?;
+        contract.status = ContractStatus::EnCours;
+        let message = format!(
+            "Contrat #{id} accepte: {} x {} pour {}",
+            contract.volume, contract.product_id, contract.customer
+        );
+        let (customer, product_id, volume, due_s) = (
+            contract.customer.clone(),
+            contract.product_id.clone(),
+            contract.volume,
+            contract.due_s,
+        );
+        let order_id = self
+            .orders
+            .open(&customer, &product_id, Some(id), volume, now_s, due_s);
+        if let Some(contract) = self.contracts.iter_mut().find(|contract| contract.id == id) {
+            contract.order_id = Some(order_id);
+        }
+        Ok(message)
+    }
+
+    pub fn decline(&mut self, id: ContractId, now_s: f64) -> Result<String, String> {
+        let contract = self.open_offer_mut(id)?;
+        contract.status = ContractStatus::Refuse;
+        contract.closed_at_s = Some(now_s);
+        Ok(format!("Contrat #{id} refuse"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = MarketBook::default();
+        assert!(book.validate().is_ok());
+    }
+
+    #[test]
+    fn demand_follows_price_elasticity_and_daily_curve() {
+        let book = MarketBook::default();
+        let ail = book.product("ail").expect("ail present");
+        assert!((ail.price_factor(ail.reference_price_eur) - 1.0).abs() < 1e-9);
+        assert!(ail.price_factor(ail.reference_price_eur * 1.2) < 1.0);
+        assert!(ail.price_factor(ail.reference_price_eur * 0.8) > 1.0);
+
+        let noon = ail.units_per_hour(10.0, false, 780.0, 1.0);
+        assert!(noon > ail.units_per_hour(3.0, false, 780.0, 1.0));
+        assert!(noon > ail.units_per_hour(10.0, true, 780.0, 1.0));
+        let daily = (0..24)
+            .map(|h| ail.units_per_hour(h as f64, false, 780.0, 1.0))
+            .sum::<f64>();
+        assert!((daily - ail.units_per_day).abs() < 1e-6);
+    }
+
+    #[test]
+    fn contracts_ship_first_and_late_ones_are_penalised() {
+        let book = MarketBook::default();
+        let mut rng = SimRng::from_seed(7);
+        let mut market = MarketState::default();
+        let events = market.tick(&book, 0.0, 0.0, 0, 10.0, false, 780.0, "ail", &mut rng);
+        let MarketEvent::NewOffer(first) = events[0] else {
+            panic!("une offre doit etre publiee au demarrage");
+        };
+        market.contracts[0].product_id = "ail".to_string();
+        market.contracts[0].volume = 5;
+        market.accept(first, 0.0).expect("offre acceptable");
+        assert!(market.accept(first, 0.0).is_err());
+
+        market.spot_demand.insert("ail".to_string(), 10.0);
+        let (sold, revenue) = market.ship(8, "ail", 800.0, 60.0);
+        assert_eq!(sold, 8);
+        let contract = market.contract(first).expect("contrat garde");
+        assert_eq!(contract.status, ContractStatus::Livre);
+        assert!((revenue - (5.0 * contract.unit_price_eur + 3.0 * 800.0)).abs() < 1e-9);
+        let order = market
+            .orders
+            .order(contract.order_id.expect("commande ouverte"))
+            .expect("commande au carnet");
+        assert!(order.on_time_in_full());
+
+        market.next_offer_s = 0.0;
+        market.tick(&book, 0.0, 100.0, 0, 10.0, false, 780.0, "ail", &mut rng);
+        let second = market.offers().next().expect("nouvelle offre").id;
+        market.accept(second, 100.0).expect("offre acceptable");
+        let (due_s, volume) = {
+            let contract = market.contract(second).expect("contrat");
+            (contract.due_s, contract.volume)
+        };
+        let events = market.tick(&book, 0.0, due_s, 0, 10.0, false, 780.0, "ail", &mut rng);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            MarketEvent::ContractLate { id, penalty_eur, .. } if *id == second && *penalty_eur > 0.0
+        )));
+
+        // Livraison tardive partielle, puis le client annule le reliquat.
+        let product_id = market.contract(second).expect("contrat").product_id.clone();
+        market.spot_demand.clear();
+        assert_eq!(market.ship(2, &product_id, 800.0, due_s + 3600.0).0, 2);
+        let cancel_s = due_s + book.contracts.late_grace_h * 3600.0;
+        let events = market.tick(&book, 0.0, cancel_s, 0, 10.0, false, 780.0, "ail", &mut rng);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            MarketEvent::ContractCancelled { id, missing } if *id == second && *missing == volume - 2
+        )));
+
+        let stats = market.orders.stats(cancel_s, 24.0 * 30.0);
+        assert_eq!(stats.orders, 2);
+        assert_eq!(stats.otif(), 0.5);
+        assert!((stats.fill_rate() - 7.0 / (5.0 + volume as f64)).abs() < 1e-9);
+        assert!(stats.mean_lateness_h() > 0.0);
+    }
+}
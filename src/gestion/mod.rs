@@ -1,12 +1,46 @@
+pub mod besoins;
+pub mod carnet;
 pub mod commandes;
+pub mod compta;
+pub mod energie;
+pub mod financement;
+pub mod formation;
+pub mod fournisseurs;
+pub mod horaires;
+pub mod hse;
 pub mod lignes;
+pub mod lots;
+pub mod marche;
+pub mod moral;
 pub mod personnel;
+pub mod qualite;
+pub mod recrutement;
+pub mod reglages;
+pub mod relations;
 pub mod stock;
 pub mod vente;
 
+pub use besoins::{NEEDS_BOOK_PATH, NeedsBook};
+pub use carnet::{CustomerOrder, FulfilmentStats, OrderId, OrderLedger};
 pub use commandes::SimCommand;
+pub use compta::{Account, Ledger};
+pub use energie::{ENERGY_BOOK_PATH, EnergyBook, EnergyState};
+pub use financement::{FINANCE_BOOK_PATH, FinanceBook, FinanceEvent, FinanceState};
+pub use formation::{TRAINING_BOOK_PATH, TrainingBook};
+pub use fournisseurs::{SUPPLIER_CATALOGUE_PATH, Supplier, SupplierCatalogue};
+pub use horaires::{SHIFT_BOOK_PATH, ShiftBook};
+pub use hse::{HSE_BOOK_PATH, HseBook, HseState};
 pub use lignes::{LineOperationalState, ProductionLineId, ProductionLineState};
+pub use lots::{LotBook, LotId, LotQueue, QualityGrade};
+pub use marche::{
+    ContractId, ContractStatus, CustomerContract, MARKET_BOOK_PATH, MarketBook, MarketEvent,
+    MarketState,
+};
+pub use moral::{MORALE_BOOK_PATH, MoraleBook};
 #[allow(unused_imports)]
-pub use personnel::{Employee, EmployeeId, EmployeeRole, EmployeeStatus, PersonnelState};
-pub use stock::{RAW_LINE_INPUT_CAPACITY, StockState};
+pub use personnel::{Employee, EmployeeId, EmployeeRole, EmployeeStatus, PersonnelState, WorkType};
+pub use qualite::{QUALITY_BOOK_PATH, QualityBook, QualityControl, QualityStats};
+pub use recrutement::{RECRUITMENT_BOOK_PATH, RecruitmentBoard, RecruitmentBook};
+pub use reglages::RonBook;
+pub use stock::{RAW_LINE_INPUT_CAPACITY, SPARE_PARTS_CAPACITY, StockState};
 pub use vente::SalesState;
@@ -0,0 +1,506 @@
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::besoins::NeedsBook;
+use super::personnel::{Employee, EmployeeId, EmployeeRole, EmployeeStatus, PersonnelState};
+
+pub const MORALE_BOOK_PATH: &str = "data/morale.ron";
+const MORALE_BOOK_SCHEMA_VERSION: u32 = 1;
+const WEEK_H: f64 = 168.0;
+const DAY_S: f64 = 86_400.0;
+const TURNOVER_RECENT_EVENTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MarketWage {
+    pub role: EmployeeRole,
+    pub eur_per_h: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MoraleBook {
+    pub schema_version: u32,
+    // Moral vise quand rien ne pese ni n'aide.
+    pub baseline: f64,
+    pub market_wages: Vec<MarketWage>,
+    // Points de moral par ecart relatif au salaire du marche (0.1 = 10 %).
+    pub wage_weight: f64,
+    pub wage_cap: f64,
+    // Heures sur 7 jours glissants au-dela du contrat.
+    pub contract_weekly_h: f64,
+    pub overtime_weight: f64,
+    pub overtime_cap: f64,
+    // Points perdus a fatigue 100, a manque de besoins total, gagnes a affinite 1.
+    pub fatigue_weight: f64,
+    pub comfort_weight: f64,
+    pub relationship_weight: f64,
+    // Echanges par heure entre deux collegues en pause au meme endroit; a stress 100,
+    // hostile_exchange_share d'entre eux tournent mal.
+    #[serde(default = "default_break_exchanges_per_h")]
+    pub break_exchanges_per_h: f64,
+    #[serde(default = "default_hostile_exchange_share")]
+    pub hostile_exchange_share: f64,
+    // Constante de temps du rapprochement vers le moral vise.
+    pub inertia_h: f64,
+    // Sous slack_threshold l'employe traine: jusqu'a slack_penalty de vitesse en moins.
+    pub slack_threshold: f64,
+    pub slack_penalty: f64,
+    pub complaint_threshold: f64,
+    pub complaint_interval_h: f64,
+    // Heures cumulees sous resign_threshold avant la lettre de demission.
+    pub resign_threshold: f64,
+    pub resign_after_h: f64,
+    pub notice_days: f64,
+    // Solde de tout compte (conges payes, paperasse) en heures de salaire.
+    pub exit_cost_hours: f64,
+}
+
+fn default_break_exchanges_per_h() -> f64 {
+    6.0
+}
+
+fn default_hostile_exchange_share() -> f64 {
+    0.5
+}
+
+fn market(role: EmployeeRole, eur_per_h: f64) -> MarketWage {
+    MarketWage { role, eur_per_h }
+}
+
+impl Default for MoraleBook {
+    fn default() -> Self {
+        Self {
+            schema_version: MORALE_BOOK_SCHEMA_VERSION,
+            baseline: 72.0,
+            market_wages: vec![
+                market(EmployeeRole::ChefEquipe, 33.0),
+                market(EmployeeRole::Cariste, 23.0),
+                market(EmployeeRole::AdministrateurVente, 27.0),
+                market(EmployeeRole::Interimaire, 30.0),
+                market(EmployeeRole::Technicien, 31.0),
+                market(EmployeeRole::ControleurQualite, 27.0),
+                market(EmployeeRole::AgentEntretien, 19.0),
+                market(EmployeeRole::ResponsableHse, 37.0),
+            ],
+            wage_weight: 100.0,
+            wage_cap: 20.0,
+            contract_weekly_h: 35.0,
+            overtime_weight: 0.5,
+            overtime_cap: 20.0,
+            fatigue_weight: 15.0,
+            comfort_weight: 20.0,
+            relationship_weight: 12.0,
+            break_exchanges_per_h: default_break_exchanges_per_h(),
+            hostile_exchange_share: default_hostile_exchange_share(),
+            inertia_h: 12.0,
+            slack_threshold: 45.0,
+            slack_penalty: 0.3,
+            complaint_threshold: 35.0,
+            complaint_interval_h: 24.0,
+            resign_threshold: 25.0,
+            resign_after_h: 48.0,
+            notice_days: 7.0,
+            exit_cost_hours: 35.0,
+        }
+    }
+}
+
+impl RonBook for MoraleBook {
+    const LABEL: &'static str = "moral";
+    const PRETTY_DEPTH: usize = 3;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != MORALE_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema moral invalide: attendu={} recu={}",
+                MORALE_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        let mut roles = HashSet::new();
+        for wage in &self.market_wages {
+            if !roles.insert(wage.role) {
+                return Err(format!("salaire marche en double: {}", wage.role.label()));
+            }
+            if !wage.eur_per_h.is_finite() || wage.eur_per_h <= 0.0 {
+                return Err(format!(
+                    "salaire marche invalide pour {}: {}",
+                    wage.role.label(),
+                    wage.eur_per_h
+                ));
+            }
+        }
+        let levels = [
+            self.baseline,
+            self.slack_threshold,
+            self.complaint_threshold,
+            self.resign_threshold,
+        ];
+        if levels.iter().any(|level| !(0.0..=100.0).contains(level)) {
+            return Err("niveaux de moral hors [0, 100]".to_string());
+        }
+        if self.resign_threshold > self.complaint_threshold {
+            return Err(format!(
+                "seuil de demission {} au-dessus du seuil de plainte {}",
+                self.resign_threshold, self.complaint_threshold
+            ));
+        }
+        let weights = [
+            self.wage_weight,
+            self.wage_cap,
+            self.contract_weekly_h,
+            self.overtime_weight,
+            self.overtime_cap,
+            self.fatigue_weight,
+            self.comfort_weight,
+            self.relationship_weight,
+            self.break_exchanges_per_h,
+            self.notice_days,
+            self.exit_cost_hours,
+        ];
+        if weights
+            .iter()
+            .any(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err("poids, plafonds et delais du moral doivent etre positifs".to_string());
+        }
+        if !(self.inertia_h > 0.0 && self.complaint_interval_h > 0.0 && self.resign_after_h > 0.0) {
+            return Err("inertie, intervalle de plainte et delai de demission > 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.hostile_exchange_share) {
+            return Err(format!(
+                "part d'echanges hostiles hors [0, 1]: {}",
+                self.hostile_exchange_share
+            ));
+        }
+        if !(0.0..1.0).contains(&self.slack_penalty) {
+            return Err(format!(
+                "penalite de laisser-aller hors [0, 1): {}",
+                self.slack_penalty
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl MoraleBook {
+    pub fn market_wage(&self, role: EmployeeRole) -> Option<f64> {
+        self.market_wages
+            .iter()
+            .find(|wage| wage.role == role)
+            .map(|wage| wage.eur_per_h)
+    }
+
+    // Multiplicateur de vitesse: l'employe demoralise traine au poste.
+    pub fn speed_factor(&self, morale: f64) -> f64 {
+        if self.slack_threshold <= 0.0 || morale >= self.slack_threshold {
+            return 1.0;
+        }
+        let gap = ((self.slack_threshold - morale) / self.slack_threshold).clamp(0.0, 1.0);
+        1.0 - self.slack_penalty * gap
+    }
+
+    pub fn factors(
+        &self,
+        employee: &Employee,
+        week_hours: f64,
+        needs_book: &NeedsBook,
+    ) -> MoraleFactors {
+        let wage = self
+            .market_wage(employee.role)
+            .map_or(0.0, |market| {
+                (employee.hourly_wage / market - 1.0) * self.wage_weight
+            })
+            .clamp(-self.wage_cap, self.wage_cap);
+        let overtime = ((week_hours - self.contract_weekly_h).max(0.0) * self.overtime_weight)
+            .min(self.overtime_cap);
+        MoraleFactors {
+            wage,
+            overtime: -overtime,
+            fatigue: -self.fatigue_weight * (employee.fatigue as f64 / 100.0).clamp(0.0, 1.0),
+            relationship: self.relationship_weight
+                * (employee.relationship as f64).clamp(-1.0, 1.0),
+            comfort: -self.comfort_weight * employee.needs.deficit(needs_book),
+        }
+    }
+
+    // Moral, plaintes, preavis et departs; les partants sont retires du personnel.
+    pub fn tick(
+        &self,
+        personnel: &mut PersonnelState,
+        needs_book: &NeedsBook,
+        now_s: f64,
+        dt_h: f64,
+    ) -> Vec<MoraleEvent> {
+        if !dt_h.is_finite() || dt_h <= 0.0 {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        let mut leaving = Vec::new();
+        let pull = (dt_h / self.inertia_h).min(1.0);
+        for employee in &mut personnel.employees {
+            if employee.role == EmployeeRole::Patron || employee.status == EmployeeStatus::Termine {
+                continue;
+            }
+            let on_site = if employee.is_paid() { 1.0 } else { 0.0 };
+            let track = &mut employee.morale_track;
+            track.week_hours =
+                (track.week_hours + dt_h * on_site - track.week_hours * dt_h / WEEK_H).max(0.0);
+            let factors = self.factors(employee, employee.morale_track.week_hours, needs_book);
+            let target = (self.baseline + factors.total()).clamp(0.0, 100.0);
+            let morale = employee.morale as f64;
+            employee.morale = (morale + (target - morale) * pull).clamp(0.0, 100.0) as f32;
+            let morale = employee.morale as f64;
+
+            let track = &mut employee.morale_track;
+            track.factors = factors;
+            if morale < self.resign_threshold {
+                track.low_morale_h += dt_h;
+            } else {
+                track.low_morale_h = (track.low_morale_h - dt_h).max(0.0);
+            }
+            // Les interimaires finissent leur mission plutot que de demissionner.
+            if employee.role == EmployeeRole::Interimaire {
+                continue;
+            }
+
+            if morale < self.complaint_threshold
+                && track
+                    .last_complaint_s
+                    .is_none_or(|at_s| now_s - at_s >= self.complaint_interval_h * 3600.0)
+            {
+                track.last_complaint_s = Some(now_s);
+                track.complaints = track.complaints.saturating_add(1);
+                events.push(MoraleEvent::Complaint {
+                    employee_id: employee.id,
+                    name: employee.name.clone(),
+                    grievance: factors.main_grievance().unwrap_or("ambiance"),
+                });
+            }
+
+            match track.notice_until_s {
+                None if track.low_morale_h >= self.resign_after_h => {
+                    let until_s = now_s + self.notice_days * DAY_S;
+                    track.notice_until_s = Some(until_s);
+                    events.push(MoraleEvent::Notice {
+                        employee_id: employee.id,
+                        name: employee.name.clone(),
+                        until_s,
+                    });
+                }
+                Some(until_s) if now_s >= until_s => leaving.push(employee.id),
+                _ => {}
+            }
+        }
+
+        for employee_id in leaving {
+            let Some(employee) = personnel.employee(employee_id) else {
+                continue;
+            };
+            let event = MoraleEvent::Departure {
+                employee_id,
+                name: employee.name.clone(),
+                role: employee.role,
+                exit_cost_eur: self.exit_cost_hours * employee.hourly_wage.max(0.0),
+            };
+            if personnel.fire(employee_id).is_ok() {
+                events.push(event);
+            }
+        }
+        for event in &events {
+            personnel.turnover.record(event);
+        }
+        events
+    }
+}
+
+// Contributions (en points) au moral vise; negatif = grief.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MoraleFactors {
+    pub wage: f64,
+    pub overtime: f64,
+    pub fatigue: f64,
+    pub relationship: f64,
+    pub comfort: f64,
+}
+
+impl MoraleFactors {
+    fn labelled(&self) -> [(&'static str, f64); 5] {
+        [
+            ("salaire", self.wage),
+            ("heures sup", self.overtime),
+            ("fatigue", self.fatigue),
+            ("relations", self.relationship),
+            ("confort", self.comfort),
+        ]
+    }
+
+    pub fn total(&self) -> f64 {
+        self.labelled().iter().map(|(_, points)| points).sum()
+    }
+
+    pub fn main_grievance(&self) -> Option<&'static str> {
+        self.labelled()
+            .into_iter()
+            .filter(|(_, points)| *points < 0.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(label, _)| label)
+    }
+
+    pub fn summary(&self) -> String {
+        self.labelled()
+            .iter()
+            .map(|(label, points)| format!("{label} {points:+.0}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MoraleTrack {
+    // Heures de presence sur 7 jours glissants (moyenne exponentielle).
+    pub week_hours: f64,
+    pub factors: MoraleFactors,
+    pub low_morale_h: f64,
+    pub complaints: u32,
+    pub last_complaint_s: Option<f64>,
+    // Fin du preavis une fois la demission posee.
+    pub notice_until_s: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MoraleEvent {
+    Complaint {
+        employee_id: EmployeeId,
+        name: String,
+        grievance: &'static str,
+    },
+    Notice {
+        employee_id: EmployeeId,
+        name: String,
+        until_s: f64,
+    },
+    Departure {
+        employee_id: EmployeeId,
+        name: String,
+        role: EmployeeRole,
+        exit_cost_eur: f64,
+    },
+}
+
+impl MoraleEvent {
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Complaint {
+                employee_id,
+                name,
+                grievance,
+            } => format!("Plainte de {name} (#{employee_id}): {grievance}"),
+            Self::Notice {
+                employee_id,
+                name,
+                until_s,
+            } => format!(
+                "{name} (#{employee_id}) demissionne, depart J{}",
+                (until_s / DAY_S).floor() as u64
+            ),
+            Self::Departure {
+                employee_id,
+                name,
+                role,
+                exit_cost_eur,
+            } => format!(
+                "Depart de {name} (#{employee_id}, {}): solde {exit_cost_eur:.0} EUR",
+                role.label()
+            ),
+        }
+    }
+}
+
+// Rotation du personnel depuis le debut de partie.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TurnoverStats {
+    pub complaints_total: u32,
+    pub notices_total: u32,
+    pub departures_total: u32,
+    pub exit_costs_eur: f64,
+    pub recent: Vec<String>,
+}
+
+impl TurnoverStats {
+    pub fn record(&mut self, event: &MoraleEvent) {
+        match event {
+            MoraleEvent::Complaint { .. } => self.complaints_total += 1,
+            MoraleEvent::Notice { .. } => self.notices_total += 1,
+            MoraleEvent::Departure { exit_cost_eur, .. } => {
+                self.departures_total += 1;
+                self.exit_costs_eur += exit_cost_eur;
+            }
+        }
+        self.recent.push(event.summary());
+        if self.recent.len() > TURNOVER_RECENT_EVENTS {
+            self.recent.remove(0);
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Plaintes {} | demissions {} | departs {} | soldes {:.0} EUR",
+            self.complaints_total, self.notices_total, self.departures_total, self.exit_costs_eur
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = MoraleBook::default();
+        assert!(book.validate().is_ok());
+
+        let mut inverted = book.clone();
+        inverted.resign_threshold = 50.0;
+        assert!(inverted.validate().is_err());
+        assert_eq!(book.speed_factor(80.0), 1.0);
+        assert!(book.speed_factor(10.0) < 1.0);
+    }
+
+    #[test]
+    fn underpaid_employee_complains_then_resigns_after_notice() {
+        let book = MoraleBook::default();
+        let needs_book = NeedsBook::default();
+        let mut personnel = PersonnelState::default();
+        let id = personnel.hire(EmployeeRole::Cariste, 0.0).unwrap();
+        let employee = personnel.employee_mut(id).unwrap();
+        employee.hourly_wage = 15.0;
+        employee.fatigue = 90.0;
+        employee.relationship = -1.0;
+
+        let factors = book.factors(personnel.employee(id).unwrap(), 0.0, &needs_book);
+        assert_eq!(factors.main_grievance(), Some("salaire"));
+
+        let mut events = Vec::new();
+        let mut now_s = 0.0;
+        while now_s < 20.0 * DAY_S && personnel.employee(id).is_some() {
+            now_s += 3600.0;
+            events.extend(book.tick(&mut personnel, &needs_book, now_s, 1.0));
+        }
+        assert!(personnel.employee(id).is_none());
+        assert!(matches!(events[0], MoraleEvent::Complaint { .. }));
+        let notice_at = events
+            .iter()
+            .position(|event| matches!(event, MoraleEvent::Notice { .. }))
+            .expect("notice before departure");
+        let Some(MoraleEvent::Departure { exit_cost_eur, .. }) = events.last() else {
+            panic!("last event should be the departure");
+        };
+        assert_eq!(*exit_cost_eur, 35.0 * 15.0);
+        assert!(notice_at > 0);
+        assert_eq!(personnel.turnover.departures_total, 1);
+        assert_eq!(personnel.turnover.exit_costs_eur, *exit_cost_eur);
+    }
+}
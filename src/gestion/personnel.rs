@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
 
+use super::besoins::{EmployeeNeeds, NeedKind};
+use super::formation::{Certification, SkillPractice, TrainingCourse, TrainingSession};
+use super::moral::{MoraleTrack, TurnoverStats};
+use super::relations::EmployeeRelations;
+use std::collections::{BTreeMap, BTreeSet};
+
 pub type EmployeeId = u64;
 pub type ProductionLineId = u32;
 
@@ -11,6 +17,10 @@ pub enum EmployeeRole {
     Cariste,
     AdministrateurVente,
     Interimaire,
+    Technicien,
+    ControleurQualite,
+    AgentEntretien,
+    ResponsableHse,
 }
 
 impl EmployeeRole {
@@ -21,6 +31,10 @@ impl EmployeeRole {
             Self::Cariste => "Cariste",
             Self::AdministrateurVente => "Administrateur vente",
             Self::Interimaire => "Interimaire",
+            Self::Technicien => "Technicien maintenance",
+            Self::ControleurQualite => "Controleur qualite",
+            Self::AgentEntretien => "Agent d'entretien",
+            Self::ResponsableHse => "Responsable HSE",
         }
     }
 
@@ -31,6 +45,10 @@ impl EmployeeRole {
             Self::Cariste => 24.0,
             Self::AdministrateurVente => 27.0,
             Self::Interimaire => 30.0,
+            Self::Technicien => 29.0,
+            Self::ControleurQualite => 26.0,
+            Self::AgentEntretien => 19.0,
+            Self::ResponsableHse => 35.0,
         }
     }
 
@@ -40,17 +58,137 @@ impl EmployeeRole {
             Self::ChefEquipe => 900.0,
             Self::Cariste => 450.0,
             Self::AdministrateurVente => 520.0,
+            Self::Technicien => 780.0,
+            Self::ControleurQualite => 620.0,
+            Self::AgentEntretien => 280.0,
+            Self::ResponsableHse => 1_150.0,
+        }
+    }
+
+    pub fn default_work_priority(self, work: WorkType) -> u8 {
+        match (self, work) {
+            (Self::Cariste, WorkType::Manutention) => 1,
+            (Self::ChefEquipe, WorkType::Production) => 1,
+            (Self::Interimaire, WorkType::Production) => 2,
+            (Self::Technicien, WorkType::Maintenance) => 1,
+            (Self::AdministrateurVente, WorkType::Vente) => 1,
+            (Self::ControleurQualite, WorkType::Controle) => 1,
+            (Self::AgentEntretien, WorkType::Nettoyage) => 1,
+            (Self::ResponsableHse, WorkType::Prevention) => 1,
+            _ => WORK_PRIORITY_DISABLED,
+        }
+    }
+
+    // Metier pratique quand l'employe est occupe sans job d'agent.
+    pub fn primary_work(self) -> Option<WorkType> {
+        match self {
+            Self::Patron => None,
+            Self::ChefEquipe | Self::Interimaire => Some(WorkType::Production),
+            Self::Cariste => Some(WorkType::Manutention),
+            Self::AdministrateurVente => Some(WorkType::Vente),
+            Self::Technicien => Some(WorkType::Maintenance),
+            Self::ControleurQualite => Some(WorkType::Controle),
+            Self::AgentEntretien => Some(WorkType::Nettoyage),
+            Self::ResponsableHse => Some(WorkType::Prevention),
+        }
+    }
+
+    pub fn required_certification(self) -> Option<Certification> {
+        match self {
+            Self::Cariste => Some(Certification::Caces),
+            Self::Technicien => Some(Certification::HabilitationMaintenance),
+            _ => None,
         }
     }
 
     pub fn can_be_hired_by_player(self) -> bool {
         matches!(
             self,
-            Self::ChefEquipe | Self::Cariste | Self::AdministrateurVente
+            Self::ChefEquipe
+                | Self::Cariste
+                | Self::AdministrateurVente
+                | Self::Technicien
+                | Self::ControleurQualite
+                | Self::AgentEntretien
+                | Self::ResponsableHse
         )
     }
 }
 
+// Matrice de travail: 1 = priorite haute ... 4 = basse, 0 = ne fait pas ce travail.
+pub const WORK_PRIORITY_DISABLED: u8 = 0;
+pub const WORK_PRIORITY_LOWEST: u8 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkType {
+    Manutention,
+    Production,
+    Maintenance,
+    Nettoyage,
+    Vente,
+    Controle,
+    Prevention,
+}
+
+impl WorkType {
+    pub const ALL: [Self; 7] = [
+        Self::Manutention,
+        Self::Production,
+        Self::Maintenance,
+        Self::Nettoyage,
+        Self::Vente,
+        Self::Controle,
+        Self::Prevention,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Manutention => "Manutention",
+            Self::Production => "Production",
+            Self::Maintenance => "Maintenance",
+            Self::Nettoyage => "Nettoyage",
+            Self::Vente => "Vente",
+            Self::Controle => "Controle qualite",
+            Self::Prevention => "Prevention",
+        }
+    }
+
+    pub fn short_label(self) -> &'static str {
+        match self {
+            Self::Manutention => "Manu",
+            Self::Production => "Prod",
+            Self::Maintenance => "Maint",
+            Self::Nettoyage => "Nett",
+            Self::Vente => "Vente",
+            Self::Controle => "Ctrl",
+            Self::Prevention => "Prev",
+        }
+    }
+
+    pub fn skill_kind(self) -> SkillKind {
+        match self {
+            Self::Manutention => SkillKind::Logistique,
+            Self::Production | Self::Maintenance => SkillKind::Technique,
+            Self::Nettoyage | Self::Controle => SkillKind::Fiabilite,
+            Self::Vente => SkillKind::Vente,
+            Self::Prevention => SkillKind::Management,
+        }
+    }
+
+    pub fn skill(self, skills: &EmployeeSkills) -> u8 {
+        skills.level(self.skill_kind())
+    }
+}
+
+pub fn next_work_priority(priority: u8) -> u8 {
+    if priority >= WORK_PRIORITY_LOWEST {
+        WORK_PRIORITY_DISABLED
+    } else {
+        priority + 1
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EmployeeStatus {
@@ -58,6 +196,8 @@ pub enum EmployeeStatus {
     Occupe,
     EnPause,
     Absent,
+    EnFormation,
+    Blesse,
     Termine,
 }
 
@@ -68,16 +208,61 @@ impl EmployeeStatus {
             Self::Occupe => "Occupe",
             Self::EnPause => "En pause",
             Self::Absent => "Absent",
+            Self::EnFormation => "En formation",
+            Self::Blesse => "Arret accident",
             Self::Termine => "Termine",
         }
     }
 
     pub fn is_active_for_business(self) -> bool {
-        !matches!(self, Self::Absent | Self::EnPause | Self::Termine)
+        !matches!(
+            self,
+            Self::Absent | Self::EnPause | Self::EnFormation | Self::Blesse | Self::Termine
+        )
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillKind {
+    Management,
+    Logistique,
+    Vente,
+    Technique,
+    Fiabilite,
+}
+
+impl SkillKind {
+    pub const ALL: [Self; 5] = [
+        Self::Management,
+        Self::Logistique,
+        Self::Vente,
+        Self::Technique,
+        Self::Fiabilite,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Management => "Management",
+            Self::Logistique => "Logistique",
+            Self::Vente => "Vente",
+            Self::Technique => "Technique",
+            Self::Fiabilite => "Fiabilite",
+        }
+    }
+
+    pub fn short_label(self) -> &'static str {
+        match self {
+            Self::Management => "Man",
+            Self::Logistique => "Log",
+            Self::Vente => "Ven",
+            Self::Technique => "Tec",
+            Self::Fiabilite => "Fia",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EmployeeSkills {
     pub management: u8,
     pub logistique: u8,
@@ -87,6 +272,34 @@ pub struct EmployeeSkills {
 }
 
 impl EmployeeSkills {
+    pub fn level(&self, kind: SkillKind) -> u8 {
+        match kind {
+            SkillKind::Management => self.management,
+            SkillKind::Logistique => self.logistique,
+            SkillKind::Vente => self.vente,
+            SkillKind::Technique => self.technique,
+            SkillKind::Fiabilite => self.fiabilite,
+        }
+    }
+
+    pub fn level_mut(&mut self, kind: SkillKind) -> &mut u8 {
+        match kind {
+            SkillKind::Management => &mut self.management,
+            SkillKind::Logistique => &mut self.logistique,
+            SkillKind::Vente => &mut self.vente,
+            SkillKind::Technique => &mut self.technique,
+            SkillKind::Fiabilite => &mut self.fiabilite,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        SkillKind::ALL
+            .iter()
+            .map(|kind| format!("{} {}", kind.short_label(), self.level(*kind)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     pub fn for_role(role: EmployeeRole) -> Self {
         match role {
             EmployeeRole::Patron => Self {
@@ -124,6 +337,34 @@ impl EmployeeSkills {
                 technique: 45,
                 fiabilite: 58,
             },
+            EmployeeRole::Technicien => Self {
+                management: 32,
+                logistique: 40,
+                vente: 18,
+                technique: 84,
+                fiabilite: 76,
+            },
+            EmployeeRole::ControleurQualite => Self {
+                management: 36,
+                logistique: 38,
+                vente: 22,
+                technique: 62,
+                fiabilite: 84,
+            },
+            EmployeeRole::AgentEntretien => Self {
+                management: 22,
+                logistique: 46,
+                vente: 18,
+                technique: 34,
+                fiabilite: 72,
+            },
+            EmployeeRole::ResponsableHse => Self {
+                management: 78,
+                logistique: 42,
+                vente: 30,
+                technique: 58,
+                fiabilite: 82,
+            },
         }
     }
 }
@@ -167,6 +408,10 @@ pub struct Employee {
     #[serde(default = "default_morale")]
     pub morale: f32,
 
+    // Stress de l'agent au poste, garde entre deux prises de poste.
+    #[serde(default)]
+    pub stress: f32,
+
     #[serde(default)]
     pub task_label: String,
 
@@ -175,6 +420,38 @@ pub struct Employee {
 
     #[serde(default)]
     pub last_action_s: f64,
+
+    // Surcharges joueur; les types absents gardent le defaut du role.
+    #[serde(default)]
+    pub work_priorities: BTreeMap<WorkType, u8>,
+
+    // Rang dans la rotation des postes du modele horaire de sa ligne.
+    #[serde(default)]
+    pub shift_slot: u8,
+
+    #[serde(default)]
+    pub needs: EmployeeNeeds,
+
+    #[serde(default)]
+    pub morale_track: MoraleTrack,
+
+    // Affinite moyenne avec les collegues (-1..1), alimentee par les echanges en pause.
+    #[serde(default)]
+    pub relationship: f32,
+
+    // Progression des competences par la pratique, oubli sans usage.
+    #[serde(default)]
+    pub practice: SkillPractice,
+
+    #[serde(default)]
+    pub certifications: BTreeSet<Certification>,
+
+    #[serde(default)]
+    pub training: Option<TrainingSession>,
+
+    // Fin d'arret apres un accident du travail.
+    #[serde(default)]
+    pub injured_until_s: Option<f64>,
 }
 
 impl Employee {
@@ -193,9 +470,19 @@ impl Employee {
             temp_policy: matches!(role, EmployeeRole::ChefEquipe).then(TempPolicy::default),
             fatigue: 0.0,
             morale: default_morale(),
+            stress: 0.0,
             task_label: String::new(),
             ai_score: 0.0,
             last_action_s: now_s.max(0.0),
+            work_priorities: BTreeMap::new(),
+            shift_slot: 0,
+            needs: EmployeeNeeds::seeded(id),
+            morale_track: MoraleTrack::default(),
+            relationship: 0.0,
+            practice: SkillPractice::default(),
+            certifications: role.required_certification().into_iter().collect(),
+            training: None,
+            injured_until_s: None,
         };
         employee.set_idle_task();
         employee
@@ -210,6 +497,10 @@ impl Employee {
             EmployeeRole::Cariste => "Attend un transfert de stock".to_string(),
             EmployeeRole::AdministrateurVente => "Attend des produits finis a vendre".to_string(),
             EmployeeRole::Interimaire => "Attend les consignes du chef".to_string(),
+            EmployeeRole::Technicien => "Attend une panne a reparer".to_string(),
+            EmployeeRole::ControleurQualite => "Attend des unites a echantillonner".to_string(),
+            EmployeeRole::AgentEntretien => "Ronde de proprete".to_string(),
+            EmployeeRole::ResponsableHse => "Prepare l'audit securite".to_string(),
         };
     }
 
@@ -229,12 +520,109 @@ impl Employee {
     pub fn is_active_for_business(&self) -> bool {
         self.status.is_active_for_business()
     }
+
+    // Pointe dans l'usine: au poste ou en pause.
+    pub fn is_on_site(&self) -> bool {
+        self.is_active_for_business() || self.status == EmployeeStatus::EnPause
+    }
+
+    pub fn start_break(&mut self, need: NeedKind, venue_label: &str) {
+        self.status = EmployeeStatus::EnPause;
+        self.task_label = format!("Pause {} ({venue_label})", need.label());
+        self.ai_score = 0.0;
+        self.needs.break_need = Some(need);
+        self.needs.breaks_total = self.needs.breaks_total.saturating_add(1);
+    }
+
+    pub fn start_training(&mut self, course: &TrainingCourse) {
+        self.status = EmployeeStatus::EnFormation;
+        self.task_label = format!("Formation {}", course.label);
+        self.ai_score = 0.0;
+        self.needs.break_need = None;
+        self.training = Some(TrainingSession {
+            course_id: course.id.clone(),
+            remaining_h: course.duration_h,
+        });
+    }
+
+    // Paye: pointe dans l'usine, parti en formation ou en arret apres un accident.
+    pub fn is_paid(&self) -> bool {
+        self.is_on_site()
+            || matches!(
+                self.status,
+                EmployeeStatus::EnFormation | EmployeeStatus::Blesse
+            )
+    }
+
+    pub fn injure(&mut self, until_s: f64) {
+        self.status = EmployeeStatus::Blesse;
+        self.task_label = format!("Arret accident jusqu'a J{}", (until_s / 86_400.0) as u64);
+        self.ai_score = 0.0;
+        self.needs.break_need = None;
+        self.injured_until_s = Some(until_s);
+    }
+
+    // Fin d'arret: l'employe reprend au prochain pointage.
+    pub fn recover(&mut self) {
+        self.injured_until_s = None;
+        if self.status == EmployeeStatus::Blesse {
+            self.status = EmployeeStatus::Absent;
+            self.task_label = "Hors poste".to_string();
+        }
+    }
+
+    pub fn end_break(&mut self) {
+        self.needs.break_need = None;
+        if self.status == EmployeeStatus::EnPause {
+            self.set_idle_task();
+        }
+    }
+
+    // Chef et interimaires couvrent les heures ouvertes de leur ligne, le patron est toujours la.
+    pub fn follows_own_shift(&self) -> bool {
+        !matches!(
+            self.role,
+            EmployeeRole::Patron | EmployeeRole::ChefEquipe | EmployeeRole::Interimaire
+        )
+    }
+
+    pub fn clock_in(&mut self) {
+        if self.status == EmployeeStatus::Absent {
+            self.set_idle_task();
+        }
+    }
+
+    pub fn clock_out(&mut self) {
+        if !matches!(
+            self.status,
+            EmployeeStatus::Termine | EmployeeStatus::EnFormation | EmployeeStatus::Blesse
+        ) {
+            self.status = EmployeeStatus::Absent;
+            self.task_label = "Hors poste".to_string();
+            self.ai_score = 0.0;
+        }
+    }
+
+    pub fn work_priority(&self, work: WorkType) -> u8 {
+        self.work_priorities
+            .get(&work)
+            .copied()
+            .unwrap_or_else(|| self.role.default_work_priority(work))
+    }
+
+    pub fn does_work(&self, work: WorkType) -> bool {
+        self.work_priority(work) != WORK_PRIORITY_DISABLED
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PersonnelState {
     pub employees: Vec<Employee>,
     pub next_employee_id: EmployeeId,
+    #[serde(default)]
+    pub turnover: TurnoverStats,
+    #[serde(default)]
+    pub relations: EmployeeRelations,
 }
 
 impl Default for PersonnelState {
@@ -242,6 +630,8 @@ impl Default for PersonnelState {
         Self {
             employees: Vec::new(),
             next_employee_id: 1,
+            turnover: TurnoverStats::default(),
+            relations: EmployeeRelations::default(),
         }
     }
 }
@@ -282,11 +672,32 @@ impl PersonnelState {
         let id = self.next_employee_id;
         self.next_employee_id = self.next_employee_id.saturating_add(1).max(1);
 
-        self.employees.push(Employee::new(id, role, now_s));
+        // Les embauches d'un meme role se repartissent sur les postes successifs.
+        let mut employee = Employee::new(id, role, now_s);
+        employee.shift_slot = (self.count_role(role) % u8::MAX as usize) as u8;
+        self.employees.push(employee);
 
         Ok(id)
     }
 
+    // Embauche d'un candidat du tableau: nom, competences et salaire negocies.
+    pub fn hire_profile(
+        &mut self,
+        role: EmployeeRole,
+        name: String,
+        skills: EmployeeSkills,
+        hourly_wage: f64,
+        now_s: f64,
+    ) -> Result<EmployeeId, String> {
+        let id = self.hire(role, now_s)?;
+        if let Some(employee) = self.employee_mut(id) {
+            employee.name = name;
+            employee.skills = skills;
+            employee.hourly_wage = hourly_wage;
+        }
+        Ok(id)
+    }
+
     pub fn hire_temp_for_lead(
         &mut self,
         lead_id: EmployeeId,
@@ -316,6 +727,15 @@ impl PersonnelState {
         Ok(id)
     }
 
+    // Annule une embauche qui n'a pas pu aboutir: la fiche disparait et son
+    // identifiant redevient libre s'il etait le dernier attribue.
+    pub fn cancel_hire(&mut self, id: EmployeeId) {
+        self.employees.retain(|employee| employee.id != id);
+        if self.next_employee_id == id.saturating_add(1) {
+            self.next_employee_id = id;
+        }
+    }
+
     pub fn fire(&mut self, id: EmployeeId) -> Result<(), String> {
         let Some(employee) = self.employee(id) else {
             return Err(format!("employe introuvable: {id}"));
@@ -327,10 +747,33 @@ impl PersonnelState {
 
         self.employees
             .retain(|employee| employee.id != id && employee.supervisor_id != Some(id));
+        let employees = &self.employees;
+        self.relations
+            .retain_employees(|id| employees.iter().any(|employee| employee.id == id));
+        self.refresh_relationships();
 
         Ok(())
     }
 
+    // Affinite de chacun = moyenne avec les collegues deja rencontres; sans rencontre,
+    // l'employe garde l'affinite de son embauche.
+    pub fn refresh_relationships(&mut self) {
+        let roster = self
+            .employees
+            .iter()
+            .filter(|employee| employee.status != EmployeeStatus::Termine)
+            .map(|employee| employee.id)
+            .collect::<Vec<_>>();
+        for employee in &mut self.employees {
+            if let Some(mean) = self
+                .relations
+                .mean_affinity(employee.id, roster.iter().copied())
+            {
+                employee.relationship = mean;
+            }
+        }
+    }
+
     pub fn assign_to_line(
         &mut self,
         id: EmployeeId,
@@ -350,6 +793,17 @@ impl PersonnelState {
         Ok(())
     }
 
+    pub fn release_line(&mut self, line_id: ProductionLineId) {
+        for employee in &mut self.employees {
+            if employee.assigned_line == Some(line_id) {
+                employee.assigned_line = None;
+                if employee.role == EmployeeRole::ChefEquipe {
+                    employee.task_label = "Attend une ligne a superviser".to_string();
+                }
+            }
+        }
+    }
+
     pub fn set_temp_policy(
         &mut self,
         lead_id: EmployeeId,
@@ -440,6 +894,46 @@ impl PersonnelState {
             .count()
     }
 
+    // Tout employe au poste qui a ce type de travail dans sa matrice compte, quel que soit son role.
+    pub fn active_count_for_work(&self, work: WorkType) -> usize {
+        self.employees
+            .iter()
+            .filter(|employee| employee.is_active_for_business() && employee.does_work(work))
+            .count()
+    }
+
+    pub fn set_work_priority(
+        &mut self,
+        id: EmployeeId,
+        work: WorkType,
+        priority: u8,
+    ) -> Result<(), String> {
+        if priority > WORK_PRIORITY_LOWEST {
+            return Err(format!(
+                "priorite invalide: {priority} (0 = jamais, 1 a {WORK_PRIORITY_LOWEST})"
+            ));
+        }
+        let employee = self
+            .employee_mut(id)
+            .ok_or_else(|| format!("employe introuvable: {id}"))?;
+
+        if priority == employee.role.default_work_priority(work) {
+            employee.work_priorities.remove(&work);
+        } else {
+            employee.work_priorities.insert(work, priority);
+        }
+
+        Ok(())
+    }
+
+    pub fn reset_work_priorities(&mut self, id: EmployeeId) -> Result<(), String> {
+        let employee = self
+            .employee_mut(id)
+            .ok_or_else(|| format!("employe introuvable: {id}"))?;
+        employee.work_priorities.clear();
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub fn available_role_count(&self, role: EmployeeRole) -> usize {
         self.employees
@@ -480,14 +974,146 @@ impl PersonnelState {
             .count()
     }
 
+    // Interimaires au poste: ceux en pause ne comptent pas dans l'effectif de ligne.
+    pub fn working_temps_for_lead(&self, lead_id: EmployeeId) -> usize {
+        self.employees
+            .iter()
+            .filter(|employee| {
+                employee.role == EmployeeRole::Interimaire
+                    && employee.is_active_for_business()
+                    && employee.supervisor_id == Some(lead_id)
+            })
+            .count()
+    }
+
+    // Chef et interimaires presents qui font tourner la ligne.
+    pub fn line_operators(&self, line_id: ProductionLineId) -> impl Iterator<Item = &Employee> {
+        self.employees.iter().filter(move |employee| {
+            employee.assigned_line == Some(line_id)
+                && employee.is_active_for_business()
+                && employee.does_work(WorkType::Production)
+        })
+    }
+
+    // Seuls les employes pointes sont payes, pauses et formations comprises.
     pub fn hourly_payroll_eur(&self) -> f64 {
         self.employees
             .iter()
-            .filter(|employee| employee.status != EmployeeStatus::Termine)
+            .filter(|employee| employee.is_paid())
             .map(|employee| employee.hourly_wage.max(0.0))
             .sum()
     }
 
+    pub fn set_shift_slot(&mut self, id: EmployeeId, slot: u8) -> Result<(), String> {
+        let employee = self
+            .employee_mut(id)
+            .ok_or_else(|| format!("employe introuvable: {id}"))?;
+        if !employee.follows_own_shift() {
+            return Err(format!(
+                "{}: horaires fixes par la ligne",
+                employee.role.label()
+            ));
+        }
+        employee.shift_slot = slot;
+        Ok(())
+    }
+
+    pub fn set_hourly_wage(&mut self, id: EmployeeId, hourly_wage: f64) -> Result<(), String> {
+        if !hourly_wage.is_finite() || hourly_wage <= 0.0 {
+            return Err(format!("salaire invalide: {hourly_wage}"));
+        }
+        let employee = self
+            .employee_mut(id)
+            .ok_or_else(|| format!("employe introuvable: {id}"))?;
+        if employee.role == EmployeeRole::Patron {
+            return Err("le patron ne se verse pas de salaire".to_string());
+        }
+        employee.hourly_wage = hourly_wage;
+        Ok(())
+    }
+
+    pub fn schedule_training(
+        &mut self,
+        id: EmployeeId,
+        course: &TrainingCourse,
+    ) -> Result<(), String> {
+        let employee = self
+            .employee_mut(id)
+            .ok_or_else(|| format!("employe introuvable: {id}"))?;
+        match employee.role {
+            EmployeeRole::Patron => {
+                return Err("le patron ne part pas en formation".to_string());
+            }
+            EmployeeRole::Interimaire => {
+                return Err("les interimaires sont formes par leur agence".to_string());
+            }
+            _ => {}
+        }
+        if employee.status == EmployeeStatus::Termine {
+            return Err(format!("{} ne fait plus partie de l'equipe", employee.name));
+        }
+        if employee.training.is_some() {
+            return Err(format!("{} est deja en formation", employee.name));
+        }
+        if let Some(certification) = course.certification
+            && employee.certifications.contains(&certification)
+        {
+            return Err(format!(
+                "{} a deja la certification {}",
+                employee.name,
+                certification.label()
+            ));
+        }
+        employee.start_training(course);
+        Ok(())
+    }
+
+    // Reconversion: le nouveau metier exige sa certification, le salaire ne baisse pas.
+    pub fn change_role(&mut self, id: EmployeeId, role: EmployeeRole) -> Result<(), String> {
+        if !role.can_be_hired_by_player() {
+            return Err(format!(
+                "{} ne s'attribue pas par reconversion",
+                role.label()
+            ));
+        }
+        let employee = self
+            .employee_mut(id)
+            .ok_or_else(|| format!("employe introuvable: {id}"))?;
+        if matches!(
+            employee.role,
+            EmployeeRole::Patron | EmployeeRole::Interimaire
+        ) {
+            return Err(format!("{} ne change pas de metier", employee.role.label()));
+        }
+        if employee.role == role {
+            return Err(format!("{} est deja {}", employee.name, role.label()));
+        }
+        if let Some(line_id) = employee.assigned_line {
+            return Err(format!(
+                "{} supervise la ligne {line_id}: assigner un autre chef d'abord",
+                employee.name
+            ));
+        }
+        if let Some(certification) = role.required_certification()
+            && !employee.certifications.contains(&certification)
+        {
+            return Err(format!(
+                "{}: certification {} requise pour {}",
+                employee.name,
+                certification.label(),
+                role.label()
+            ));
+        }
+        employee.role = role;
+        employee.hourly_wage = employee.hourly_wage.max(role.hourly_wage_eur());
+        employee.work_priorities.clear();
+        employee.temp_policy = (role == EmployeeRole::ChefEquipe).then(TempPolicy::default);
+        if employee.is_active_for_business() {
+            employee.set_idle_task();
+        }
+        Ok(())
+    }
+
     pub fn employee(&self, id: EmployeeId) -> Option<&Employee> {
         self.employees.iter().find(|employee| employee.id == id)
     }
@@ -540,7 +1166,7 @@ impl PersonnelState {
         for employee in self
             .employees
             .iter_mut()
-            .filter(|employee| employee.role == role && employee.status != EmployeeStatus::Termine)
+            .filter(|employee| employee.role == role && employee.is_active_for_business())
         {
             if busy_left > 0 {
                 employee.set_work_task(now_s, busy_label.to_string(), score);
@@ -567,7 +1193,7 @@ impl PersonnelState {
         for employee in self.employees.iter_mut().filter(|employee| {
             employee.role == EmployeeRole::Interimaire
                 && employee.assigned_line == Some(line_id)
-                && employee.status != EmployeeStatus::Termine
+                && employee.is_active_for_business()
         }) {
             if busy_left > 0 {
                 employee.set_work_task(now_s, busy_label.to_string(), score);
@@ -586,6 +1212,10 @@ fn deterministic_name(role: EmployeeRole, id: EmployeeId) -> String {
         EmployeeRole::Cariste => &["Karim", "Maya", "Sofiane", "Ines"][..],
         EmployeeRole::AdministrateurVente => &["Alice", "Hugo", "Salma", "Theo"][..],
         EmployeeRole::Interimaire => &["Interim A", "Interim B", "Interim C", "Interim D"][..],
+        EmployeeRole::Technicien => &["Yanis", "Clara", "Mehdi", "Julie"][..],
+        EmployeeRole::ControleurQualite => &["Amel", "Bastien", "Chloe", "Driss"][..],
+        EmployeeRole::AgentEntretien => &["Fatou", "Gilles", "Rania", "Paulo"][..],
+        EmployeeRole::ResponsableHse => &["Helene", "Samir", "Agathe", "Loic"][..],
     };
 
     let index = (id.saturating_sub(1) as usize) % names.len();
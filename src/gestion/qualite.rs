@@ -0,0 +1,459 @@
+use super::lots::{LotId, QualityGrade};
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+pub const QUALITY_BOOK_PATH: &str = "data/quality.ron";
+const QUALITY_BOOK_SCHEMA_VERSION: u32 = 1;
+const QUALITY_SAMPLE_HISTORY: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectCause {
+    Matiere,
+    Usure,
+    Four,
+    Operateur,
+}
+
+impl RejectCause {
+    pub const ALL: [Self; 4] = [Self::Matiere, Self::Usure, Self::Four, Self::Operateur];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Matiere => "matiere",
+            Self::Usure => "usure",
+            Self::Four => "four",
+            Self::Operateur => "operateurs",
+        }
+    }
+}
+
+// Defaut accumule par une unite le long de la ligne, ventile par cause.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefectProfile {
+    pub matiere: f64,
+    pub usure: f64,
+    pub four: f64,
+    pub operateur: f64,
+}
+
+impl DefectProfile {
+    pub fn total(&self) -> f64 {
+        self.matiere + self.usure + self.four + self.operateur
+    }
+
+    pub fn part(&self, cause: RejectCause) -> f64 {
+        match cause {
+            RejectCause::Matiere => self.matiere,
+            RejectCause::Usure => self.usure,
+            RejectCause::Four => self.four,
+            RejectCause::Operateur => self.operateur,
+        }
+    }
+
+    pub fn main_cause(&self) -> RejectCause {
+        RejectCause::ALL
+            .into_iter()
+            .max_by(|a, b| self.part(*a).total_cmp(&self.part(*b)))
+            .unwrap_or(RejectCause::Matiere)
+    }
+
+    // Moyenne ponderee: un tampon garde le profil moyen des unites qu'il contient.
+    pub fn blend(&self, qty: u32, other: &Self, other_qty: u32) -> Self {
+        let total = qty.saturating_add(other_qty);
+        if total == 0 {
+            return Self::default();
+        }
+        let (w, ow) = (qty as f64 / total as f64, other_qty as f64 / total as f64);
+        Self {
+            matiere: self.matiere * w + other.matiere * ow,
+            usure: self.usure * w + other.usure * ow,
+            four: self.four * w + other.four * ow,
+            operateur: self.operateur * w + other.operateur * ow,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradeDefects {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl GradeDefects {
+    pub fn of(&self, grade: QualityGrade) -> f64 {
+        match grade {
+            QualityGrade::A => self.a,
+            QualityGrade::B => self.b,
+            QualityGrade::C => self.c,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradeOutcome {
+    Graded(QualityGrade),
+    Rejected(RejectCause),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct QualityBook {
+    pub schema_version: u32,
+    // Defaut de depart d'une unite selon le grade de son lot matiere.
+    pub raw_grade_defect: GradeDefects,
+    // Defaut ajoute sur toute la ligne par une usure machine de 100%.
+    pub wear_defect: f64,
+    // Defaut par unite d'ecart relatif entre cycle de four reel et nominal.
+    pub oven_deviation_defect: f64,
+    // Ecart de cuisson aleatoire par cycle, amplifie par l'usure du four.
+    pub oven_jitter: f64,
+    // Defaut ajoute sur toute la ligne par des operateurs a 100 de fatigue.
+    pub fatigue_defect: f64,
+    // Competence technique neutre; chaque point en dessous coute skill_defect.
+    pub skill_reference: f64,
+    pub skill_defect: f64,
+    // Dispersion uniforme +/- ajoutee au tri.
+    pub sorting_noise: f64,
+    // Seuils de defaut: grade B, grade C, puis hors classe (toujours rejete).
+    pub grade_b_defect: f64,
+    pub grade_c_defect: f64,
+    pub off_grade_defect: f64,
+    // Un controleur qualite preleve une unite toutes les sample_interval_h heures
+    // et, tant qu'il est au poste, resserre la dispersion du tri de ce facteur.
+    #[serde(default = "default_sample_interval_h")]
+    pub sample_interval_h: f64,
+    #[serde(default = "default_controlled_noise_factor")]
+    pub controlled_noise_factor: f64,
+}
+
+fn default_sample_interval_h() -> f64 {
+    2.0
+}
+
+fn default_controlled_noise_factor() -> f64 {
+    0.4
+}
+
+impl Default for QualityBook {
+    fn default() -> Self {
+        Self {
+            schema_version: QUALITY_BOOK_SCHEMA_VERSION,
+            raw_grade_defect: GradeDefects {
+                a: 0.0,
+                b: 0.05,
+                c: 0.1,
+            },
+            wear_defect: 0.1,
+            oven_deviation_defect: 0.15,
+            oven_jitter: 0.1,
+            fatigue_defect: 0.1,
+            skill_reference: 60.0,
+            skill_defect: 0.002,
+            sorting_noise: 0.05,
+            grade_b_defect: 0.08,
+            grade_c_defect: 0.16,
+            off_grade_defect: 0.28,
+            sample_interval_h: default_sample_interval_h(),
+            controlled_noise_factor: default_controlled_noise_factor(),
+        }
+    }
+}
+
+impl RonBook for QualityBook {
+    const LABEL: &'static str = "qualite";
+    const PRETTY_DEPTH: usize = 2;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != QUALITY_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema qualite invalide: attendu={} recu={}",
+                QUALITY_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        let weights = [
+            ("raw_grade_defect.a", self.raw_grade_defect.a),
+            ("raw_grade_defect.b", self.raw_grade_defect.b),
+            ("raw_grade_defect.c", self.raw_grade_defect.c),
+            ("wear_defect", self.wear_defect),
+            ("oven_deviation_defect", self.oven_deviation_defect),
+            ("oven_jitter", self.oven_jitter),
+            ("fatigue_defect", self.fatigue_defect),
+            ("skill_reference", self.skill_reference),
+            ("skill_defect", self.skill_defect),
+            ("sorting_noise", self.sorting_noise),
+        ];
+        if let Some((name, _)) = weights
+            .iter()
+            .find(|(_, value)| !value.is_finite() || *value < 0.0)
+        {
+            return Err(format!("parametre qualite invalide: {name}"));
+        }
+        if !(self.grade_b_defect > 0.0
+            && self.grade_c_defect > self.grade_b_defect
+            && self.off_grade_defect > self.grade_c_defect
+            && self.off_grade_defect.is_finite())
+        {
+            return Err("seuils de grade qualite non croissants".to_string());
+        }
+        if !(self.sample_interval_h.is_finite() && self.sample_interval_h > 0.0) {
+            return Err("intervalle d'echantillonnage qualite invalide".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.controlled_noise_factor) {
+            return Err(format!(
+                "facteur de tri controle hors 0..1: {}",
+                self.controlled_noise_factor
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl QualityBook {
+    pub fn raw_defect(&self, grade: QualityGrade) -> f64 {
+        self.raw_grade_defect.of(grade)
+    }
+
+    // Hors classe ou sous min_grade, l'unite part au rebut, imputee a sa cause principale.
+    pub fn grade(
+        &self,
+        profile: &DefectProfile,
+        noise: f64,
+        min_grade: QualityGrade,
+    ) -> GradeOutcome {
+        let defect = profile.total() + noise;
+        let grade = if defect >= self.off_grade_defect {
+            None
+        } else if defect >= self.grade_c_defect {
+            Some(QualityGrade::C)
+        } else if defect >= self.grade_b_defect {
+            Some(QualityGrade::B)
+        } else {
+            Some(QualityGrade::A)
+        };
+        match grade {
+            Some(grade) if grade <= min_grade => GradeOutcome::Graded(grade),
+            _ => GradeOutcome::Rejected(profile.main_cause()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityStats {
+    pub graded_total: u32,
+    pub grades: BTreeMap<QualityGrade, u32>,
+    pub rejected_total: u32,
+    pub rejects_by_cause: BTreeMap<RejectCause, u32>,
+}
+
+impl QualityStats {
+    pub fn record(&mut self, outcome: GradeOutcome) {
+        match outcome {
+            GradeOutcome::Graded(grade) => {
+                self.graded_total = self.graded_total.saturating_add(1);
+                *self.grades.entry(grade).or_insert(0) += 1;
+            }
+            GradeOutcome::Rejected(cause) => {
+                self.rejected_total = self.rejected_total.saturating_add(1);
+                *self.rejects_by_cause.entry(cause).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.graded_total = self.graded_total.saturating_add(other.graded_total);
+        self.rejected_total = self.rejected_total.saturating_add(other.rejected_total);
+        for (grade, count) in &other.grades {
+            *self.grades.entry(*grade).or_insert(0) += count;
+        }
+        for (cause, count) in &other.rejects_by_cause {
+            *self.rejects_by_cause.entry(*cause).or_insert(0) += count;
+        }
+    }
+
+    pub fn sorted_total(&self) -> u32 {
+        self.graded_total.saturating_add(self.rejected_total)
+    }
+
+    pub fn scrap_rate(&self) -> f64 {
+        let total = self.sorted_total();
+        if total == 0 {
+            0.0
+        } else {
+            self.rejected_total as f64 / total as f64
+        }
+    }
+
+    pub fn grade_share(&self, grade: QualityGrade) -> f64 {
+        let total = self.sorted_total();
+        if total == 0 {
+            0.0
+        } else {
+            self.grades.get(&grade).copied().unwrap_or(0) as f64 / total as f64
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        if self.sorted_total() == 0 {
+            return "Qualite: aucune unite triee".to_string();
+        }
+        let causes = self
+            .rejects_by_cause
+            .iter()
+            .map(|(cause, count)| format!("{} {}", cause.label(), count))
+            .collect::<Vec<_>>();
+        format!(
+            "Qualite: A {:.0}% B {:.0}% C {:.0}% | rebut {:.1}%{}",
+            self.grade_share(QualityGrade::A) * 100.0,
+            self.grade_share(QualityGrade::B) * 100.0,
+            self.grade_share(QualityGrade::C) * 100.0,
+            self.scrap_rate() * 100.0,
+            if causes.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", causes.join(", "))
+            }
+        )
+    }
+}
+
+// Prelevement d'un controleur: defaut mesure et verdict du tri pour le lot.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LotSample {
+    pub lot_id: Option<LotId>,
+    pub defect: f64,
+    pub main_cause: RejectCause,
+    // None: unite rejetee.
+    pub grade: Option<QualityGrade>,
+    pub sampled_at_s: f64,
+}
+
+impl LotSample {
+    pub fn new(
+        lot_id: Option<LotId>,
+        profile: &DefectProfile,
+        outcome: GradeOutcome,
+        now_s: f64,
+    ) -> Self {
+        Self {
+            lot_id,
+            defect: profile.total(),
+            main_cause: profile.main_cause(),
+            grade: match outcome {
+                GradeOutcome::Graded(grade) => Some(grade),
+                GradeOutcome::Rejected(_) => None,
+            },
+            sampled_at_s: now_s.max(0.0),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Lot {}: defaut {:.1}% ({}) -> {}",
+            self.lot_id
+                .map(|lot_id| format!("#{lot_id}"))
+                .unwrap_or_else(|| "?".to_string()),
+            self.defect * 100.0,
+            self.main_cause.label(),
+            self.grade.map_or("rebut", |grade| grade.label())
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityControl {
+    pub next_sample_s: f64,
+    pub samples_total: u32,
+    pub samples: VecDeque<LotSample>,
+}
+
+impl QualityControl {
+    pub fn due(&self, now_s: f64) -> bool {
+        now_s >= self.next_sample_s
+    }
+
+    // Plusieurs controleurs se partagent la cadence de prelevement.
+    pub fn record(&mut self, book: &QualityBook, controllers: usize, sample: LotSample) {
+        self.next_sample_s =
+            sample.sampled_at_s + book.sample_interval_h * 3600.0 / controllers.max(1) as f64;
+        self.samples_total = self.samples_total.saturating_add(1);
+        self.samples.push_back(sample);
+        while self.samples.len() > QUALITY_SAMPLE_HISTORY {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn last_sample(&self) -> Option<&LotSample> {
+        self.samples.back()
+    }
+
+    pub fn summary(&self) -> String {
+        match self.last_sample() {
+            Some(sample) => format!(
+                "Controle: {} prelevement(s) | dernier {}",
+                self.samples_total,
+                sample.summary()
+            ),
+            None => "Controle: aucun prelevement".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = QualityBook::default();
+        assert!(book.validate().is_ok());
+
+        let mut bad = book.clone();
+        bad.grade_c_defect = bad.grade_b_defect;
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn grading_sorts_by_threshold_and_blames_main_cause() {
+        let book = QualityBook::default();
+        let clean = DefectProfile::default();
+        assert_eq!(
+            book.grade(&clean, 0.0, QualityGrade::B),
+            GradeOutcome::Graded(QualityGrade::A)
+        );
+
+        let worn = DefectProfile {
+            matiere: 0.05,
+            usure: 0.07,
+            ..DefectProfile::default()
+        };
+        assert_eq!(
+            book.grade(&worn, 0.0, QualityGrade::B),
+            GradeOutcome::Graded(QualityGrade::B)
+        );
+        // Grade C refuse par un tri exigeant: la cause est l'usure.
+        assert_eq!(
+            book.grade(&worn, 0.05, QualityGrade::B),
+            GradeOutcome::Rejected(RejectCause::Usure)
+        );
+        assert_eq!(
+            book.grade(&worn, 0.05, QualityGrade::C),
+            GradeOutcome::Graded(QualityGrade::C)
+        );
+
+        let mut stats = QualityStats::default();
+        stats.record(GradeOutcome::Graded(QualityGrade::A));
+        stats.record(GradeOutcome::Graded(QualityGrade::B));
+        stats.record(GradeOutcome::Rejected(RejectCause::Four));
+        stats.record(GradeOutcome::Rejected(RejectCause::Four));
+        assert_eq!(stats.scrap_rate(), 0.5);
+        assert_eq!(stats.rejects_by_cause.get(&RejectCause::Four), Some(&2));
+
+        let blended = clean.blend(3, &worn, 1);
+        assert!((blended.usure - 0.0175).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,587 @@
+use super::reglages::RonBook;
+use serde::{Deserialize, Serialize};
+
+use super::moral::MoraleBook;
+use super::personnel::{EmployeeId, EmployeeRole, EmployeeSkills, PersonnelState, SkillKind};
+use crate::aleatoire::{SimRng, mix_seed};
+use crate::character::{BodyType, CharacterCatalog, OutfitStyle};
+use crate::ui_pawns::{PawnMetrics, SkillBar, SynthBar, TraitBar};
+
+pub const RECRUITMENT_BOOK_PATH: &str = "data/recruitment.ron";
+const RECRUITMENT_BOOK_SCHEMA_VERSION: u32 = 1;
+const DAY_S: f64 = 86_400.0;
+
+// Traits decouverts seulement en entretien; les autres se lisent sur le CV.
+pub const HIDDEN_TRAITS: [TraitBar; 3] = [
+    TraitBar::Discipline,
+    TraitBar::Fiabilite,
+    TraitBar::Patience,
+];
+
+const FIRST_NAMES: [&str; 16] = [
+    "Amina", "Bastien", "Chloe", "Dylan", "Elise", "Farid", "Gaelle", "Hamza", "Irene", "Jonas",
+    "Kenza", "Lucas", "Manon", "Nabil", "Oceane", "Pierre",
+];
+const LAST_NAMES: [&str; 12] = [
+    "Bernard", "Cherif", "Dubois", "Fontaine", "Garnier", "Haddad", "Lefevre", "Martin", "Moreau",
+    "Perrin", "Roux", "Vidal",
+];
+
+pub type CandidateId = u64;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RecruitmentBook {
+    pub schema_version: u32,
+    // Candidats visibles au tableau; un nouveau profil arrive toutes les arrival_interval_h.
+    pub board_size: usize,
+    pub arrival_interval_h: f64,
+    pub roles: Vec<EmployeeRole>,
+    // Presence au tableau: stay_min_h + stay_span_h selon la patience du candidat.
+    pub stay_min_h: f64,
+    pub stay_span_h: f64,
+    // Preavis chez l'ancien employeur, tire entre 0 et availability_max_days jours.
+    pub availability_max_days: u32,
+    // Salaire demande: marche (morale.ron) x (1 + ask_spread x (niveau metier - 50) / 50).
+    pub ask_spread: f64,
+    pub interview_cost_eur: f64,
+    // Chance d'accepter au salaire demande, +wage_sensitivity par 10 % offerts en plus.
+    pub accept_at_asking: f64,
+    pub wage_sensitivity: f64,
+    // Refus avant que le candidat ne se retire.
+    pub max_offers: u8,
+}
+
+impl Default for RecruitmentBook {
+    fn default() -> Self {
+        Self {
+            schema_version: RECRUITMENT_BOOK_SCHEMA_VERSION,
+            board_size: 6,
+            arrival_interval_h: 8.0,
+            roles: vec![
+                EmployeeRole::ChefEquipe,
+                EmployeeRole::Cariste,
+                EmployeeRole::AdministrateurVente,
+                EmployeeRole::Technicien,
+                EmployeeRole::ControleurQualite,
+                EmployeeRole::AgentEntretien,
+                EmployeeRole::ResponsableHse,
+            ],
+            stay_min_h: 48.0,
+            stay_span_h: 96.0,
+            availability_max_days: 10,
+            ask_spread: 0.25,
+            interview_cost_eur: 80.0,
+            accept_at_asking: 0.8,
+            wage_sensitivity: 0.25,
+            max_offers: 2,
+        }
+    }
+}
+
+impl RonBook for RecruitmentBook {
+    const LABEL: &'static str = "recrutement";
+    const PRETTY_DEPTH: usize = 3;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != RECRUITMENT_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema recrutement invalide: attendu={} recu={}",
+                RECRUITMENT_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        if self.roles.is_empty() {
+            return Err("aucun metier au tableau de recrutement".to_string());
+        }
+        if let Some(role) = self
+            .roles
+            .iter()
+            .find(|role| !role.can_be_hired_by_player())
+        {
+            return Err(format!("{} ne se recrute pas", role.label()));
+        }
+        if !(self.arrival_interval_h > 0.0 && self.stay_min_h > 0.0) {
+            return Err("intervalle d'arrivee et presence minimale > 0".to_string());
+        }
+        let amounts = [
+            self.stay_span_h,
+            self.ask_spread,
+            self.interview_cost_eur,
+            self.wage_sensitivity,
+        ];
+        if amounts
+            .iter()
+            .any(|amount| !amount.is_finite() || *amount < 0.0)
+        {
+            return Err("parametres de recrutement negatifs".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.accept_at_asking) {
+            return Err(format!(
+                "acceptation au salaire demande hors [0, 1]: {}",
+                self.accept_at_asking
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl RecruitmentBook {
+    pub fn acceptance_chance(&self, offered_wage: f64, asking_wage: f64) -> f64 {
+        if asking_wage <= 0.0 {
+            return 1.0;
+        }
+        let premium = offered_wage / asking_wage - 1.0;
+        (self.accept_at_asking + self.wage_sensitivity * premium * 10.0).clamp(0.0, 1.0)
+    }
+
+    // Profil tire de la graine: ADN du catalogue pour l'allure, PawnMetrics pour les barres.
+    pub fn generate_candidate(
+        &self,
+        id: CandidateId,
+        role: EmployeeRole,
+        seed: u64,
+        catalog: &CharacterCatalog,
+        morale_book: &MoraleBook,
+        now_s: f64,
+    ) -> Candidate {
+        let name = format!(
+            "{} {}",
+            FIRST_NAMES[(mix_seed(seed, 1) % FIRST_NAMES.len() as u64) as usize],
+            LAST_NAMES[(mix_seed(seed, 2) % LAST_NAMES.len() as u64) as usize]
+        );
+        let record = catalog.spawn_founder(&name, seed);
+        let visual = record.visual;
+        let metrics = PawnMetrics::seeded(seed);
+        let bar = |bars: &[SkillBar]| {
+            bars.iter()
+                .map(|bar| metrics.skills[*bar as usize] as f64)
+                .sum::<f64>()
+                / bars.len() as f64
+        };
+        let trait_level = |bar: TraitBar| metrics.traits[bar as usize] as f64;
+
+        let base = EmployeeSkills::for_role(role);
+        let body_logistique = match visual.body_type {
+            BodyType::Broad => 6.0,
+            BodyType::Standard => 0.0,
+            BodyType::Slim => -4.0,
+        };
+        // Tenue = metier precedent.
+        let background = match visual.outfit_style {
+            OutfitStyle::Worker => SkillKind::Logistique,
+            OutfitStyle::Engineer => SkillKind::Technique,
+            OutfitStyle::Medic => SkillKind::Fiabilite,
+            OutfitStyle::Scout => SkillKind::Vente,
+        };
+        let raw = [
+            (SkillKind::Management, bar(&[SkillBar::Management])),
+            (
+                SkillKind::Logistique,
+                bar(&[SkillBar::Logistique, SkillBar::Force]) + body_logistique,
+            ),
+            (
+                SkillKind::Vente,
+                bar(&[SkillBar::Sociabilite, SkillBar::Planification]),
+            ),
+            (
+                SkillKind::Technique,
+                bar(&[
+                    SkillBar::Mecanique,
+                    SkillBar::Electricite,
+                    SkillBar::Diagnostic,
+                ]),
+            ),
+            (
+                SkillKind::Fiabilite,
+                (bar(&[SkillBar::Qualite, SkillBar::Securite]) * 2.0
+                    + trait_level(TraitBar::Fiabilite)
+                    + trait_level(TraitBar::Discipline))
+                    / 4.0,
+            ),
+        ];
+        let mut skills = base.clone();
+        for (kind, raw_level) in raw {
+            let bonus = if kind == background { 6.0 } else { 0.0 };
+            let level = (base.level(kind) as f64 + raw_level) * 0.5 + bonus;
+            *skills.level_mut(kind) = level.round().clamp(0.0, 100.0) as u8;
+        }
+
+        let market = morale_book
+            .market_wage(role)
+            .unwrap_or_else(|| role.hourly_wage_eur());
+        let craft = role
+            .primary_work()
+            .map_or(50.0, |work| work.skill(&skills) as f64);
+        let asking_wage = (market * (1.0 + self.ask_spread * (craft - 50.0) / 50.0)).round();
+
+        let mut rng = SimRng::from_seed(mix_seed(seed, 3));
+        let patience = trait_level(TraitBar::Patience) / 100.0;
+        Candidate {
+            id,
+            name,
+            role,
+            seed,
+            portrait: format!(
+                "{}, cheveux {} {}, tenue {}",
+                visual.body_type.ui_label(),
+                visual.hair_style.ui_label(),
+                visual.hair_color.ui_label(),
+                visual.outfit_style.ui_label()
+            ),
+            skills,
+            traits: metrics.traits,
+            starting_morale: ((metrics.synth[SynthBar::Moral as usize] as f64
+                + trait_level(TraitBar::Motivation))
+                * 0.5) as f32,
+            asking_wage: asking_wage.max(1.0),
+            availability_days: rng.roll_range(self.availability_max_days + 1),
+            posted_at_s: now_s,
+            expires_at_s: now_s + (self.stay_min_h + self.stay_span_h * patience) * 3600.0,
+            shortlisted: false,
+            interviewed: false,
+            offers_refused: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Candidate {
+    pub id: CandidateId,
+    pub name: String,
+    pub role: EmployeeRole,
+    // Graine ADN (CharacterCatalog::spawn_founder) et barres (PawnMetrics::seeded).
+    pub seed: u64,
+    pub portrait: String,
+    pub skills: EmployeeSkills,
+    pub traits: [u8; TraitBar::COUNT],
+    pub starting_morale: f32,
+    pub asking_wage: f64,
+    pub availability_days: u32,
+    pub posted_at_s: f64,
+    pub expires_at_s: f64,
+    pub shortlisted: bool,
+    pub interviewed: bool,
+    pub offers_refused: u8,
+}
+
+impl Candidate {
+    pub fn trait_visible(&self, bar: TraitBar) -> bool {
+        self.interviewed || !HIDDEN_TRAITS.contains(&bar)
+    }
+
+    // La fiabilite du CV depend de traits caches: inconnue avant l'entretien.
+    pub fn skills_summary(&self) -> String {
+        SkillKind::ALL
+            .iter()
+            .map(|kind| {
+                if *kind == SkillKind::Fiabilite && !self.interviewed {
+                    format!("{} ?", kind.short_label())
+                } else {
+                    format!("{} {}", kind.short_label(), self.skills.level(*kind))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn traits_summary(&self) -> String {
+        TraitBar::ALL
+            .iter()
+            .map(|bar| {
+                if self.trait_visible(*bar) {
+                    format!("{} {}", bar.label(), self.traits[*bar as usize])
+                } else {
+                    format!("{} ?", bar.label())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    // Affinite de depart avec les collegues, tiree de l'empathie.
+    pub fn relationship(&self) -> f32 {
+        (self.traits[TraitBar::Empathie as usize] as f32 - 50.0) / 100.0
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PendingHire {
+    pub candidate: Candidate,
+    pub hourly_wage: f64,
+    pub starts_at_s: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecruitmentEvent {
+    CandidateLeft {
+        name: String,
+        role: EmployeeRole,
+    },
+    Arrived {
+        employee_id: EmployeeId,
+        name: String,
+        role: EmployeeRole,
+    },
+}
+
+impl RecruitmentEvent {
+    pub fn summary(&self) -> String {
+        match self {
+            Self::CandidateLeft { name, role } => {
+                format!("{name} ({}) a trouve un autre poste", role.label())
+            }
+            Self::Arrived {
+                employee_id,
+                name,
+                role,
+            } => format!(
+                "{name} (#{employee_id}) prend son poste de {}",
+                role.label()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RecruitmentBoard {
+    pub candidates: Vec<Candidate>,
+    pub pending: Vec<PendingHire>,
+    pub next_candidate_id: CandidateId,
+    pub next_arrival_s: f64,
+}
+
+impl Default for RecruitmentBoard {
+    fn default() -> Self {
+        Self {
+            candidates: Vec::new(),
+            pending: Vec::new(),
+            next_candidate_id: 1,
+            next_arrival_s: 0.0,
+        }
+    }
+}
+
+impl RecruitmentBoard {
+    // Departs des candidats lasses, nouveaux profils au tableau, prises de poste echues.
+    #[allow(clippy::too_many_arguments)]
+    pub fn tick(
+        &mut self,
+        book: &RecruitmentBook,
+        personnel: &mut PersonnelState,
+        catalog: &CharacterCatalog,
+        morale_book: &MoraleBook,
+        now_s: f64,
+        rng: &mut SimRng,
+    ) -> Vec<RecruitmentEvent> {
+        let mut events = Vec::new();
+        self.candidates.retain(|candidate| {
+            let stays = now_s < candidate.expires_at_s;
+            if !stays && candidate.shortlisted {
+                events.push(RecruitmentEvent::CandidateLeft {
+                    name: candidate.name.clone(),
+                    role: candidate.role,
+                });
+            }
+            stays
+        });
+
+        // Ouverture du tableau: toutes les places sont pourvues d'un coup.
+        let mut arrivals = if self.next_candidate_id == 1 {
+            book.board_size
+        } else {
+            0
+        };
+        while now_s >= self.next_arrival_s {
+            self.next_arrival_s = self
+                .next_arrival_s
+                .max(now_s - book.arrival_interval_h * 3600.0)
+                + book.arrival_interval_h * 3600.0;
+            arrivals += 1;
+        }
+        for _ in 0..arrivals {
+            if self.candidates.len() >= book.board_size {
+                break;
+            }
+            let role = book.roles[rng.roll_range(book.roles.len() as u32) as usize];
+            let id = self.next_candidate_id;
+            self.next_candidate_id = self.next_candidate_id.saturating_add(1);
+            let candidate =
+                book.generate_candidate(id, role, rng.next_u64(), catalog, morale_book, now_s);
+            self.candidates.push(candidate);
+        }
+
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|pending| now_s >= pending.starts_at_s);
+        self.pending = waiting;
+        for pending in due {
+            let candidate = pending.candidate;
+            if let Ok(employee_id) = personnel.hire_profile(
+                candidate.role,
+                candidate.name.clone(),
+                candidate.skills.clone(),
+                pending.hourly_wage,
+                now_s,
+            ) && let Some(employee) = personnel.employee_mut(employee_id)
+            {
+                employee.morale = candidate.starting_morale;
+                employee.relationship = candidate.relationship();
+                events.push(RecruitmentEvent::Arrived {
+                    employee_id,
+                    name: candidate.name,
+                    role: candidate.role,
+                });
+            }
+        }
+        events
+    }
+
+    pub fn candidate(&self, id: CandidateId) -> Option<&Candidate> {
+        self.candidates.iter().find(|candidate| candidate.id == id)
+    }
+
+    fn candidate_mut(&mut self, id: CandidateId) -> Result<&mut Candidate, String> {
+        self.candidates
+            .iter_mut()
+            .find(|candidate| candidate.id == id)
+            .ok_or_else(|| format!("candidat introuvable: {id}"))
+    }
+
+    pub fn toggle_shortlist(&mut self, id: CandidateId) -> Result<bool, String> {
+        let candidate = self.candidate_mut(id)?;
+        candidate.shortlisted = !candidate.shortlisted;
+        Ok(candidate.shortlisted)
+    }
+
+    pub fn interview(&mut self, id: CandidateId) -> Result<&Candidate, String> {
+        let candidate = self.candidate_mut(id)?;
+        if !candidate.shortlisted {
+            return Err(format!("{}: a retenir avant l'entretien", candidate.name));
+        }
+        if candidate.interviewed {
+            return Err(format!("{} deja recu en entretien", candidate.name));
+        }
+        candidate.interviewed = true;
+        Ok(candidate)
+    }
+
+    // Ok(Some(arrivee)) si l'offre est acceptee, Ok(None) si elle est refusee.
+    pub fn make_offer(
+        &mut self,
+        book: &RecruitmentBook,
+        id: CandidateId,
+        hourly_wage: f64,
+        now_s: f64,
+        rng: &mut SimRng,
+    ) -> Result<Option<&PendingHire>, String> {
+        if !hourly_wage.is_finite() || hourly_wage <= 0.0 {
+            return Err(format!("salaire invalide: {hourly_wage}"));
+        }
+        let candidate = self.candidate_mut(id)?;
+        if !candidate.shortlisted {
+            return Err(format!("{}: a retenir avant une offre", candidate.name));
+        }
+        if !rng.chance(book.acceptance_chance(hourly_wage, candidate.asking_wage)) {
+            candidate.offers_refused = candidate.offers_refused.saturating_add(1);
+            if candidate.offers_refused >= book.max_offers {
+                self.candidates.retain(|candidate| candidate.id != id);
+            }
+            return Ok(None);
+        }
+
+        let position = self
+            .candidates
+            .iter()
+            .position(|candidate| candidate.id == id)
+            .ok_or_else(|| format!("candidat introuvable: {id}"))?;
+        let candidate = self.candidates.remove(position);
+        let starts_at_s = now_s + candidate.availability_days as f64 * DAY_S;
+        self.pending.push(PendingHire {
+            candidate,
+            hourly_wage,
+            starts_at_s,
+        });
+        Ok(self.pending.last())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid() {
+        let book = RecruitmentBook::default();
+        assert!(book.validate().is_ok());
+
+        assert_eq!(book.acceptance_chance(20.0, 20.0), book.accept_at_asking);
+        assert!(book.acceptance_chance(22.0, 20.0) > book.accept_at_asking);
+        assert!(book.acceptance_chance(16.0, 20.0) < book.accept_at_asking);
+    }
+
+    #[test]
+    fn board_fills_with_seeded_profiles_and_accepted_offers_arrive_after_notice() {
+        let book = RecruitmentBook::default();
+        let catalog = CharacterCatalog::load_default().unwrap();
+        let morale_book = MoraleBook::default();
+        let mut personnel = PersonnelState::default();
+        let mut board = RecruitmentBoard::default();
+        let mut rng = SimRng::from_seed(7);
+
+        let now_s = 3600.0;
+        board.tick(
+            &book,
+            &mut personnel,
+            &catalog,
+            &morale_book,
+            now_s,
+            &mut rng,
+        );
+        assert_eq!(board.candidates.len(), book.board_size);
+        let first = &board.candidates[0];
+        let again = book.generate_candidate(
+            first.id,
+            first.role,
+            first.seed,
+            &catalog,
+            &morale_book,
+            first.posted_at_s,
+        );
+        assert_eq!(&again, first);
+        assert!(first.traits_summary().contains("Discipline ?"));
+
+        let id = first.id;
+        assert!(board.interview(id).is_err());
+        assert!(board.make_offer(&book, id, 30.0, now_s, &mut rng).is_err());
+        board.toggle_shortlist(id).unwrap();
+        assert!(!board.interview(id).unwrap().traits_summary().contains('?'));
+
+        // Offre tres au-dessus de la demande: acceptation certaine.
+        let asking = board.candidate(id).unwrap().asking_wage;
+        let pending = board
+            .make_offer(&book, id, asking * 2.0, now_s, &mut rng)
+            .unwrap()
+            .cloned()
+            .expect("generous offer is accepted");
+        assert!(board.candidate(id).is_none());
+
+        let events = board.tick(
+            &book,
+            &mut personnel,
+            &catalog,
+            &morale_book,
+            pending.starts_at_s,
+            &mut rng,
+        );
+        let Some(RecruitmentEvent::Arrived { employee_id, .. }) = events.last() else {
+            panic!("the hire should arrive at the end of the notice");
+        };
+        let employee = personnel.employee(*employee_id).unwrap();
+        assert_eq!(employee.name, pending.candidate.name);
+        assert_eq!(employee.hourly_wage, asking * 2.0);
+        assert_eq!(
+            employee.skills.technique,
+            pending.candidate.skills.technique
+        );
+    }
+}
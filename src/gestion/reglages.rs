@@ -0,0 +1,155 @@
+use ron::{
+    de::from_str as ron_from_str,
+    ser::{PrettyConfig, to_string_pretty as ron_to_string_pretty},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+// Carnet de reglages RON sous data/: lecture validee, ecriture, et repli sur le
+// defaut quand le fichier manque ou ne passe pas la validation.
+pub trait RonBook: Default + Serialize + DeserializeOwned {
+    // Nom du carnet dans les messages ("marche", "regles HSE"...).
+    const LABEL: &'static str;
+    // Au-dela de cette profondeur, le RON ecrit reste sur une ligne.
+    const PRETTY_DEPTH: usize = 4;
+
+    fn validate(&self) -> Result<(), String>;
+
+    fn load(path: &str) -> Result<Self, String> {
+        let raw =
+            fs::read_to_string(path).map_err(|e| format!("echec lecture {}: {e}", Self::LABEL))?;
+        parse_book(&raw)
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        let payload =
+            ron_to_string_pretty(self, PrettyConfig::new().depth_limit(Self::PRETTY_DEPTH))
+                .map_err(|e| format!("echec serialisation {}: {e}", Self::LABEL))?;
+
+        if let Some(parent) = Path::new(path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("echec creation dossier {}: {e}", Self::LABEL))?;
+        }
+
+        fs::write(path, payload).map_err(|e| format!("echec ecriture {}: {e}", Self::LABEL))
+    }
+
+    fn load_or_create(path: &str) -> Self {
+        Self::load_or_create_with_warning(path).0
+    }
+
+    // Fichier absent: le defaut est ecrit. Fichier illisible ou invalide: le defaut sert
+    // sans ecraser le fichier, pour que l'erreur reste corrigeable a la main.
+    fn load_or_create_with_warning(path: &str) -> (Self, Option<String>) {
+        let raw = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let book = Self::default();
+                let warning = book.save(path).err().map(|save_err| {
+                    format!(
+                        "{}: defaut actif, ecriture impossible: {save_err}",
+                        Self::LABEL
+                    )
+                });
+                return (book, warning);
+            }
+            Err(err) => {
+                return (
+                    Self::default(),
+                    Some(format!(
+                        "{}: fichier illisible, defaut non persiste: {err}",
+                        Self::LABEL
+                    )),
+                );
+            }
+        };
+
+        match parse_book(&raw) {
+            Ok(book) => (book, None),
+            Err(err) => (
+                Self::default(),
+                Some(format!(
+                    "{}: fichier invalide, defaut non persiste: {err}",
+                    Self::LABEL
+                )),
+            ),
+        }
+    }
+}
+
+fn parse_book<T: RonBook>(raw: &str) -> Result<T, String> {
+    let book: T = ron_from_str(raw).map_err(|e| format!("echec lecture RON {}: {e}", T::LABEL))?;
+    book.validate()?;
+    Ok(book)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct TestBook {
+        limit: u32,
+    }
+
+    impl RonBook for TestBook {
+        const LABEL: &'static str = "test";
+
+        fn validate(&self) -> Result<(), String> {
+            if self.limit > 10 {
+                return Err(format!("limite invalide: {}", self.limit));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn missing_file_writes_default_and_invalid_file_is_kept() {
+        let dir = std::env::temp_dir().join(format!("rxchixs_reglages_{}", std::process::id()));
+        let path = dir.join("test.ron");
+        let path = path.to_str().expect("path utf8");
+
+        let (book, warning) = TestBook::load_or_create_with_warning(path);
+        assert_eq!(book, TestBook::default());
+        assert!(warning.is_none());
+        assert_eq!(TestBook::load(path), Ok(TestBook::default()));
+
+        fs::write(path, "(limit: 42)").expect("test file should be written");
+        let (book, warning) = TestBook::load_or_create_with_warning(path);
+        assert_eq!(book, TestBook::default());
+        assert!(warning.is_some_and(|msg| msg.contains("limite invalide")));
+        assert_eq!(fs::read_to_string(path).expect("file stays"), "(limit: 42)");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // Les fichiers de data/ se reglent a la main: on verifie qu'ils restent valides,
+    // pas qu'ils recopient les valeurs par defaut.
+    #[test]
+    fn every_data_book_loads_and_validates() {
+        fn check<T: RonBook>(path: &str) {
+            if let Err(err) = T::load(path) {
+                panic!("{path}: {err}");
+            }
+        }
+        use crate::gestion::*;
+        use crate::recettes::{RECIPE_BOOK_PATH, RecipeBook};
+
+        check::<RecipeBook>(RECIPE_BOOK_PATH);
+        check::<ShiftBook>(SHIFT_BOOK_PATH);
+        check::<MarketBook>(MARKET_BOOK_PATH);
+        check::<SupplierCatalogue>(SUPPLIER_CATALOGUE_PATH);
+        check::<FinanceBook>(FINANCE_BOOK_PATH);
+        check::<EnergyBook>(ENERGY_BOOK_PATH);
+        check::<QualityBook>(QUALITY_BOOK_PATH);
+        check::<NeedsBook>(NEEDS_BOOK_PATH);
+        check::<MoraleBook>(MORALE_BOOK_PATH);
+        check::<TrainingBook>(TRAINING_BOOK_PATH);
+        check::<RecruitmentBook>(RECRUITMENT_BOOK_PATH);
+        check::<HseBook>(HSE_BOOK_PATH);
+    }
+}
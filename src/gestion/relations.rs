@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::personnel::EmployeeId;
+use crate::aleatoire::SimRng;
+use crate::interactions::SocialActionKind;
+
+// Affinites entre collegues (-1..1), nouees pendant les pauses partagees avec le
+// meme bareme d'echanges que les pions du jeu.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmployeeRelations {
+    // Paire rangee (plus petit id, plus grand id); absente tant qu'ils ne se sont pas parle.
+    #[serde(default)]
+    pairs: BTreeMap<(EmployeeId, EmployeeId), f32>,
+}
+
+fn pair_key(a: EmployeeId, b: EmployeeId) -> (EmployeeId, EmployeeId) {
+    (a.min(b), a.max(b))
+}
+
+impl EmployeeRelations {
+    pub fn affinity(&self, a: EmployeeId, b: EmployeeId) -> Option<f32> {
+        self.pairs.get(&pair_key(a, b)).copied()
+    }
+
+    pub(crate) fn record(&mut self, a: EmployeeId, b: EmployeeId, kind: SocialActionKind) -> f32 {
+        if a == b {
+            return 1.0;
+        }
+        let affinity = self.pairs.entry(pair_key(a, b)).or_insert(0.0);
+        *affinity = (*affinity + kind.affinity_delta()).clamp(-1.0, 1.0);
+        *affinity
+    }
+
+    // Moyenne sur les collegues deja rencontres; None tant que l'employe n'a parle a personne.
+    pub fn mean_affinity(
+        &self,
+        id: EmployeeId,
+        colleagues: impl IntoIterator<Item = EmployeeId>,
+    ) -> Option<f32> {
+        let (total, count) = colleagues
+            .into_iter()
+            .filter(|colleague| *colleague != id)
+            .filter_map(|colleague| self.affinity(id, colleague))
+            .fold((0.0, 0usize), |(total, count), affinity| {
+                (total + affinity, count + 1)
+            });
+        (count > 0).then(|| total / count as f32)
+    }
+
+    pub fn retain_employees(&mut self, keep: impl Fn(EmployeeId) -> bool) {
+        self.pairs.retain(|(a, b), _| keep(*a) && keep(*b));
+    }
+}
+
+// Echange entre deux collegues: la part d'echanges hostiles croit avec leur stress moyen.
+pub(crate) fn pick_exchange(
+    rng: &mut SimRng,
+    stress01: f64,
+    hostile_share: f64,
+) -> SocialActionKind {
+    let hostile = rng.chance(hostile_share * stress01.clamp(0.0, 1.0));
+    let pool = SocialActionKind::ALL
+        .into_iter()
+        .filter(|kind| kind.is_hostile() == hostile)
+        .collect::<Vec<_>>();
+    pool[rng.roll_range(pool.len() as u32) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exchanges_move_pair_affinity_and_mean_ignores_strangers() {
+        let mut relations = EmployeeRelations::default();
+        relations.record(2, 1, SocialActionKind::Compliment);
+        relations.record(1, 2, SocialActionKind::SmallTalk);
+        relations.record(1, 3, SocialActionKind::SEngueuler);
+
+        assert_eq!(relations.affinity(1, 2), relations.affinity(2, 1));
+        assert!((relations.affinity(1, 2).unwrap() - 0.16).abs() < 1e-6);
+        let mean = relations.mean_affinity(1, [1, 2, 3, 4]).unwrap();
+        assert!((mean - (0.16 - 0.22) / 2.0).abs() < 1e-6);
+        assert_eq!(relations.mean_affinity(4, [1, 2, 3]), None);
+
+        relations.retain_employees(|id| id != 3);
+        assert_eq!(relations.affinity(1, 3), None);
+    }
+
+    #[test]
+    fn calm_colleagues_never_trade_hostile_exchanges() {
+        let mut rng = SimRng::from_seed(7);
+        for _ in 0..200 {
+            assert!(!pick_exchange(&mut rng, 0.0, 0.5).is_hostile());
+        }
+        assert!((0..200).any(|_| pick_exchange(&mut rng, 1.0, 0.5).is_hostile()));
+    }
+}
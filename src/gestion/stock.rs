@@ -1,13 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+use super::fournisseurs::{Supplier, SupplierCatalogue, default_raw_supplier_id};
+use super::lots::{LotQueue, QualityGrade};
+use crate::aleatoire::SimRng;
+
 pub type PurchaseOrderId = u64;
 
-pub const RAW_UNIT_COST_EUR: f64 = 1.20;
-pub const RAW_MIN_PURCHASE_QTY: u32 = 100;
-pub const RAW_DELIVERY_DELAY_S: f64 = 30.0 * 60.0;
 pub const RAW_RECEIVING_CAPACITY: u32 = 2_000;
 pub const RAW_LINE_INPUT_CAPACITY: u32 = 120;
-const RAW_TRANSFER_PER_CARISTE_PER_HOUR: f64 = 360.0;
+pub const SPARE_PART_UNIT_COST_EUR: f64 = 85.0;
+pub const SPARE_PART_DELIVERY_DELAY_S: f64 = 2.0 * 3600.0;
+pub const SPARE_PARTS_CAPACITY: u32 = 40;
+pub const DEFAULT_RAW_SUPPLIER: &str = "Cooperative ail Drome";
+// Nombre de decisions de reappro gardees pour l'audit joueur.
+pub const REORDER_LOG_CAPACITY: usize = 32;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -15,6 +21,7 @@ pub enum StockItemKind {
     MatierePremiere,
     ProduitFini,
     Rebut,
+    PieceDetachee,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -24,6 +31,163 @@ pub struct PurchaseOrder {
     pub qty: u32,
     pub remaining_delivery_s: f64,
     pub unit_cost_eur: f64,
+    #[serde(default = "default_raw_supplier")]
+    pub supplier: String,
+    #[serde(default)]
+    pub grade: QualityGrade,
+    #[serde(default = "default_raw_supplier_id")]
+    pub supplier_id: String,
+    // Delai annonce restant; passe a zero avant la livraison = commande en retard.
+    #[serde(default)]
+    pub promised_remaining_s: f64,
+    // Reliquat retenu a la premiere livraison, expedie backorder_delay_s plus tard.
+    #[serde(default)]
+    pub short_qty: u32,
+    #[serde(default)]
+    pub backorder_delay_s: f64,
+}
+
+impl PurchaseOrder {
+    pub fn is_late(&self) -> bool {
+        self.promised_remaining_s <= f64::EPSILON && self.remaining_delivery_s > f64::EPSILON
+    }
+}
+
+fn default_raw_supplier() -> String {
+    DEFAULT_RAW_SUPPLIER.to_string()
+}
+
+// Livraison matiere a enregistrer comme lot par la simulation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RawReceipt {
+    pub order_id: PurchaseOrderId,
+    pub supplier: String,
+    pub grade: QualityGrade,
+    pub qty: u32,
+}
+
+// Politique min/max: sous le point de commande, on recommande jusqu'au niveau cible.
+// La position compte le stock present et les commandes en transit.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReorderPolicy {
+    pub item_kind: StockItemKind,
+    pub enabled: bool,
+    pub reorder_point: u32,
+    pub order_up_to: u32,
+    pub supplier_id: String,
+    // Tresorerie jamais entamee par une commande automatique.
+    pub min_cash_reserve_eur: f64,
+}
+
+impl ReorderPolicy {
+    pub fn raw_default(supplier_id: &str) -> Self {
+        Self {
+            item_kind: StockItemKind::MatierePremiere,
+            enabled: true,
+            reorder_point: 400,
+            order_up_to: 1_500,
+            supplier_id: supplier_id.to_string(),
+            min_cash_reserve_eur: 5_000.0,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.item_kind != StockItemKind::MatierePremiere {
+            return Err("reappro auto reservee a la matiere premiere".to_string());
+        }
+        if self.order_up_to <= self.reorder_point {
+            return Err(format!(
+                "niveau cible {} <= point de commande {}",
+                self.order_up_to, self.reorder_point
+            ));
+        }
+        if self.order_up_to > RAW_RECEIVING_CAPACITY {
+            return Err(format!(
+                "niveau cible {} > capacite reception {RAW_RECEIVING_CAPACITY}",
+                self.order_up_to
+            ));
+        }
+        if !self.min_cash_reserve_eur.is_finite() || self.min_cash_reserve_eur < 0.0 {
+            return Err("reserve de tresorerie invalide".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReorderOutcome {
+    Commande {
+        order_id: PurchaseOrderId,
+        qty: u32,
+        cost_eur: f64,
+    },
+    FournisseurInconnu,
+    CapaciteReception {
+        room: u32,
+        moq: u32,
+    },
+    ReserveTresorerie {
+        available_eur: f64,
+        needed_eur: f64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReorderDecision {
+    pub at_s: f64,
+    pub item_kind: StockItemKind,
+    pub supplier_id: String,
+    pub position: u32,
+    pub reorder_point: u32,
+    pub order_up_to: u32,
+    pub outcome: ReorderOutcome,
+}
+
+impl ReorderDecision {
+    pub fn cost_eur(&self) -> f64 {
+        match self.outcome {
+            ReorderOutcome::Commande { cost_eur, .. } => cost_eur,
+            _ => 0.0,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let day = (self.at_s / 86_400.0).floor() as u64;
+        let minutes = (self.at_s.max(0.0) / 60.0).floor() as u64;
+        let stamp = format!(
+            "J{} {:02}:{:02}",
+            day + 1,
+            (minutes / 60) % 24,
+            minutes % 60
+        );
+        let trigger = format!("position {} <= point {}", self.position, self.reorder_point);
+        match &self.outcome {
+            ReorderOutcome::Commande {
+                order_id,
+                qty,
+                cost_eur,
+            } => format!(
+                "{stamp} {trigger}: commande #{order_id} {qty} u. chez {} ({cost_eur:.0} EUR, cible {})",
+                self.supplier_id, self.order_up_to
+            ),
+            ReorderOutcome::FournisseurInconnu => {
+                format!(
+                    "{stamp} {trigger}: fournisseur inconnu {}",
+                    self.supplier_id
+                )
+            }
+            ReorderOutcome::CapaciteReception { room, moq } => {
+                format!("{stamp} {trigger}: bloque, place reception {room} < minimum {moq}")
+            }
+            ReorderOutcome::ReserveTresorerie {
+                available_eur,
+                needed_eur,
+            } => format!(
+                "{stamp} {trigger}: bloque, {available_eur:.0} EUR hors reserve < {needed_eur:.0} EUR"
+            ),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -32,7 +196,18 @@ pub struct StockState {
     pub raw_line_input: u32,
     pub pending_orders: Vec<PurchaseOrder>,
     pub next_order_id: PurchaseOrderId,
-    transfer_accum: f64,
+    #[serde(default)]
+    pub spare_parts: u32,
+    #[serde(default)]
+    pub raw_receiving_lots: LotQueue,
+    #[serde(default)]
+    pub raw_line_input_lots: LotQueue,
+    #[serde(default)]
+    pending_receipts: Vec<RawReceipt>,
+    #[serde(default)]
+    pub reorder_policies: Vec<ReorderPolicy>,
+    #[serde(default)]
+    pub reorder_log: Vec<ReorderDecision>,
 }
 
 impl Default for StockState {
@@ -42,7 +217,12 @@ impl Default for StockState {
             raw_line_input: 0,
             pending_orders: Vec::new(),
             next_order_id: 1,
-            transfer_accum: 0.0,
+            spare_parts: 0,
+            raw_receiving_lots: LotQueue::default(),
+            raw_line_input_lots: LotQueue::default(),
+            pending_receipts: Vec::new(),
+            reorder_policies: Vec::new(),
+            reorder_log: Vec::new(),
         }
     }
 }
@@ -57,10 +237,6 @@ impl StockState {
         }
     }
 
-    pub fn raw_purchase_cost(qty: u32) -> f64 {
-        qty as f64 * RAW_UNIT_COST_EUR
-    }
-
     pub fn pending_raw_qty(&self) -> u32 {
         self.pending_orders
             .iter()
@@ -75,11 +251,14 @@ impl StockState {
             .saturating_add(self.pending_raw_qty())
     }
 
-    pub fn can_buy_raw(&self, qty: u32, cash: f64) -> Result<f64, String> {
-        if qty < RAW_MIN_PURCHASE_QTY {
-            return Err(format!("commande minimale: {RAW_MIN_PURCHASE_QTY} unites"));
+    pub fn can_buy_raw(&self, supplier: &Supplier, qty: u32, cash: f64) -> Result<f64, String> {
+        if qty < supplier.moq {
+            return Err(format!(
+                "commande minimale {}: {} unites",
+                supplier.label, supplier.moq
+            ));
         }
-        let cost = Self::raw_purchase_cost(qty);
+        let cost = supplier.order_cost(qty);
         if !cash.is_finite() || cash < cost {
             return Err(format!("tresorerie insuffisante: {cost:.0} EUR requis"));
         }
@@ -94,22 +273,222 @@ impl StockState {
 
     pub fn place_raw_order(
         &mut self,
+        supplier: &Supplier,
         qty: u32,
         cash: f64,
+        rng: &mut SimRng,
     ) -> Result<(PurchaseOrderId, f64), String> {
-        let cost = self.can_buy_raw(qty, cash)?;
+        let cost = self.can_buy_raw(supplier, qty, cash)?;
+        let plan = supplier.plan_delivery(qty, rng);
         let id = self.next_order_id;
         self.next_order_id = self.next_order_id.saturating_add(1).max(1);
         self.pending_orders.push(PurchaseOrder {
             id,
             item_kind: StockItemKind::MatierePremiere,
             qty,
-            remaining_delivery_s: RAW_DELIVERY_DELAY_S,
-            unit_cost_eur: RAW_UNIT_COST_EUR,
+            remaining_delivery_s: plan.delivery_s,
+            unit_cost_eur: supplier.unit_cost_eur(qty),
+            supplier: supplier.label.clone(),
+            grade: supplier.grade,
+            supplier_id: supplier.id.clone(),
+            promised_remaining_s: plan.promised_s,
+            short_qty: plan.short_qty,
+            backorder_delay_s: plan.backorder_delay_s,
+        });
+        Ok((id, cost))
+    }
+
+    pub fn reorder_policy(&self, item_kind: StockItemKind) -> Option<&ReorderPolicy> {
+        self.reorder_policies
+            .iter()
+            .find(|policy| policy.item_kind == item_kind)
+    }
+
+    pub fn set_reorder_policy(&mut self, policy: ReorderPolicy) -> Result<(), String> {
+        policy.validate()?;
+        match self
+            .reorder_policies
+            .iter_mut()
+            .find(|existing| existing.item_kind == policy.item_kind)
+        {
+            Some(existing) => *existing = policy,
+            None => self.reorder_policies.push(policy),
+        }
+        Ok(())
+    }
+
+    // Passe les commandes automatiques; renvoie les decisions nouvellement journalisees.
+    pub fn run_reorder_policies(
+        &mut self,
+        suppliers: &SupplierCatalogue,
+        cash: f64,
+        now_s: f64,
+        rng: &mut SimRng,
+    ) -> Vec<ReorderDecision> {
+        let mut decisions = Vec::new();
+        let mut spent = 0.0;
+        for policy in self.reorder_policies.clone() {
+            if !policy.enabled || policy.item_kind != StockItemKind::MatierePremiere {
+                continue;
+            }
+            let position = self.raw_capacity_reserved();
+            if position > policy.reorder_point {
+                continue;
+            }
+            let outcome = match suppliers.supplier(&policy.supplier_id) {
+                None => ReorderOutcome::FournisseurInconnu,
+                Some(supplier) => self.raw_reorder(&policy, supplier, position, cash - spent, rng),
+            };
+            let decision = ReorderDecision {
+                at_s: now_s,
+                item_kind: policy.item_kind,
+                supplier_id: policy.supplier_id.clone(),
+                position,
+                reorder_point: policy.reorder_point,
+                order_up_to: policy.order_up_to,
+                outcome,
+            };
+            spent += decision.cost_eur();
+            if self.log_reorder_decision(&decision) {
+                decisions.push(decision);
+            }
+        }
+        decisions
+    }
+
+    fn raw_reorder(
+        &mut self,
+        policy: &ReorderPolicy,
+        supplier: &Supplier,
+        position: u32,
+        cash: f64,
+        rng: &mut SimRng,
+    ) -> ReorderOutcome {
+        let room = RAW_RECEIVING_CAPACITY.saturating_sub(position);
+        let mut qty = policy
+            .order_up_to
+            .saturating_sub(position)
+            .max(supplier.moq)
+            .min(room);
+        if qty < supplier.moq {
+            return ReorderOutcome::CapaciteReception {
+                room,
+                moq: supplier.moq,
+            };
+        }
+        let available_eur = cash - policy.min_cash_reserve_eur;
+        if supplier.order_cost(qty) > available_eur {
+            // Commande reduite a ce que la reserve laisse, palier par palier.
+            qty = supplier
+                .price_tiers
+                .iter()
+                .map(|tier| {
+                    let fit =
+                        ((available_eur.max(0.0) / tier.unit_cost_eur).floor() as u32).min(qty);
+                    if supplier.unit_cost_eur(fit) <= tier.unit_cost_eur {
+                        fit
+                    } else {
+                        0
+                    }
+                })
+                .max()
+                .unwrap_or(0);
+            if qty < supplier.moq {
+                return ReorderOutcome::ReserveTresorerie {
+                    available_eur: available_eur.max(0.0),
+                    needed_eur: supplier.order_cost(supplier.moq),
+                };
+            }
+        }
+        match self.place_raw_order(supplier, qty, cash, rng) {
+            Ok((order_id, cost_eur)) => ReorderOutcome::Commande {
+                order_id,
+                qty,
+                cost_eur,
+            },
+            Err(_) => ReorderOutcome::ReserveTresorerie {
+                available_eur: available_eur.max(0.0),
+                needed_eur: supplier.order_cost(qty),
+            },
+        }
+    }
+
+    // Un blocage identique au precedent pour le meme article n'est pas rejournalise.
+    fn log_reorder_decision(&mut self, decision: &ReorderDecision) -> bool {
+        let repeated = self
+            .reorder_log
+            .iter()
+            .rev()
+            .find(|previous| previous.item_kind == decision.item_kind)
+            .is_some_and(|previous| {
+                !matches!(decision.outcome, ReorderOutcome::Commande { .. })
+                    && std::mem::discriminant(&previous.outcome)
+                        == std::mem::discriminant(&decision.outcome)
+            });
+        if repeated {
+            return false;
+        }
+        if self.reorder_log.len() >= REORDER_LOG_CAPACITY {
+            self.reorder_log.remove(0);
+        }
+        self.reorder_log.push(decision.clone());
+        true
+    }
+
+    pub fn pending_spare_parts_qty(&self) -> u32 {
+        self.pending_orders
+            .iter()
+            .filter(|order| order.item_kind == StockItemKind::PieceDetachee)
+            .map(|order| order.qty)
+            .sum()
+    }
+
+    pub fn place_spare_parts_order(
+        &mut self,
+        qty: u32,
+        cash: f64,
+    ) -> Result<(PurchaseOrderId, f64), String> {
+        if qty == 0 {
+            return Err("quantite de pieces nulle".to_string());
+        }
+        let cost = qty as f64 * SPARE_PART_UNIT_COST_EUR;
+        if !cash.is_finite() || cash < cost {
+            return Err(format!("tresorerie insuffisante: {cost:.0} EUR requis"));
+        }
+        let reserved = self
+            .spare_parts
+            .saturating_add(self.pending_spare_parts_qty());
+        if reserved.saturating_add(qty) > SPARE_PARTS_CAPACITY {
+            return Err(format!(
+                "magasin pieces plein: {reserved}/{SPARE_PARTS_CAPACITY} reserves"
+            ));
+        }
+        let id = self.next_order_id;
+        self.next_order_id = self.next_order_id.saturating_add(1).max(1);
+        self.pending_orders.push(PurchaseOrder {
+            id,
+            item_kind: StockItemKind::PieceDetachee,
+            qty,
+            remaining_delivery_s: SPARE_PART_DELIVERY_DELAY_S,
+            unit_cost_eur: SPARE_PART_UNIT_COST_EUR,
+            supplier: "Magasin maintenance".to_string(),
+            grade: QualityGrade::A,
+            supplier_id: "magasin_maintenance".to_string(),
+            promised_remaining_s: SPARE_PART_DELIVERY_DELAY_S,
+            short_qty: 0,
+            backorder_delay_s: 0.0,
         });
         Ok((id, cost))
     }
 
+    pub fn take_spare_parts(&mut self, qty: u32) -> bool {
+        if self.spare_parts < qty {
+            return false;
+        }
+        self.spare_parts -= qty;
+        true
+    }
+
     pub fn tick_purchase_orders(&mut self, dt_s: f64) -> u32 {
         if !dt_s.is_finite() || dt_s <= 0.0 {
             return 0;
@@ -118,44 +497,64 @@ impl StockState {
         let mut delivered = 0u32;
         for order in &mut self.pending_orders {
             order.remaining_delivery_s = (order.remaining_delivery_s - dt_s).max(0.0);
+            order.promised_remaining_s = (order.promised_remaining_s - dt_s).max(0.0);
             if order.remaining_delivery_s <= f64::EPSILON {
                 match order.item_kind {
                     StockItemKind::MatierePremiere => {
                         let room = RAW_RECEIVING_CAPACITY.saturating_sub(self.raw_receiving);
-                        let accepted = order.qty.min(room);
+                        let accepted = order.qty.saturating_sub(order.short_qty).min(room);
                         self.raw_receiving = self.raw_receiving.saturating_add(accepted);
                         delivered = delivered.saturating_add(accepted);
                         order.qty -= accepted;
+                        if accepted > 0 {
+                            self.pending_receipts.push(RawReceipt {
+                                order_id: order.id,
+                                supplier: order.supplier.clone(),
+                                grade: order.grade,
+                                qty: accepted,
+                            });
+                        }
+                    }
+                    StockItemKind::PieceDetachee => {
+                        let room = SPARE_PARTS_CAPACITY.saturating_sub(self.spare_parts);
+                        let accepted = order.qty.min(room);
+                        self.spare_parts = self.spare_parts.saturating_add(accepted);
+                        order.qty -= accepted;
                     }
                     StockItemKind::ProduitFini | StockItemKind::Rebut => {}
                 }
+                // Premiere livraison partielle soldee: le reliquat repart en transit.
+                if order.short_qty > 0 && order.qty == order.short_qty {
+                    order.short_qty = 0;
+                    order.remaining_delivery_s = order.backorder_delay_s;
+                }
             }
         }
         self.pending_orders.retain(|order| order.qty > 0);
         delivered
     }
 
-    pub fn tick_cariste_transfer(&mut self, dt_hours: f64, caristes: usize) -> u32 {
-        if !dt_hours.is_finite() || dt_hours <= 0.0 || caristes == 0 {
-            return 0;
-        }
-        let room = RAW_LINE_INPUT_CAPACITY.saturating_sub(self.raw_line_input);
-        if room == 0 || self.raw_receiving == 0 {
-            self.transfer_accum = 0.0;
-            return 0;
-        }
-
-        self.transfer_accum += dt_hours * RAW_TRANSFER_PER_CARISTE_PER_HOUR * caristes as f64;
-        let wanted = self.transfer_accum.floor() as u32;
-        let moved = wanted.min(room).min(self.raw_receiving);
+    // Depose d'une manutention: au plus `qty` unites passent de la reception a
+    // l'entree ligne, bornees par la place restante; les lots suivent en FIFO.
+    pub fn transfer_raw_to_line(&mut self, qty: u32) -> u32 {
+        let moved = qty.min(self.raw_line_input_room()).min(self.raw_receiving);
         if moved > 0 {
             self.raw_receiving -= moved;
             self.raw_line_input += moved;
-            self.transfer_accum -= moved as f64;
+            let portions = self.raw_receiving_lots.take(moved);
+            self.raw_line_input_lots.extend(&portions);
         }
         moved
     }
 
+    pub fn raw_line_input_room(&self) -> u32 {
+        RAW_LINE_INPUT_CAPACITY.saturating_sub(self.raw_line_input)
+    }
+
+    pub fn take_raw_receipts(&mut self) -> Vec<RawReceipt> {
+        std::mem::take(&mut self.pending_receipts)
+    }
+
     pub fn has_any_raw_for_line(&self) -> bool {
         self.raw_receiving > 0 || self.raw_line_input > 0 || self.pending_raw_qty() > 0
     }
@@ -164,39 +563,186 @@ impl StockState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gestion::fournisseurs::SupplierCatalogue;
 
     #[test]
     fn buy_raw_stock_checks_cash_and_capacity() {
+        let catalogue = SupplierCatalogue::default();
+        let coop = catalogue.default_supplier();
+        let mut rng = SimRng::from_seed(1);
         let mut stock = StockState::default();
-        assert!(stock.place_raw_order(50, 1_000.0).is_err());
-        assert!(stock.place_raw_order(100, 10.0).is_err());
-        let (id, cost) = stock.place_raw_order(100, 1_000.0).unwrap();
+        assert!(stock.place_raw_order(coop, 50, 1_000.0, &mut rng).is_err());
+        assert!(stock.place_raw_order(coop, 100, 10.0, &mut rng).is_err());
+        let (id, cost) = stock.place_raw_order(coop, 100, 1_000.0, &mut rng).unwrap();
         assert_eq!(id, 1);
         assert_eq!(cost, 120.0);
         assert_eq!(stock.pending_raw_qty(), 100);
+        assert!(
+            stock
+                .place_raw_order(coop, RAW_RECEIVING_CAPACITY, 1_000_000.0, &mut rng)
+                .is_err()
+        );
     }
 
     #[test]
     fn purchase_order_delivers_after_delay() {
+        let catalogue = SupplierCatalogue::default();
+        let coop = catalogue.default_supplier();
         let mut stock = StockState::default();
-        stock.place_raw_order(100, 1_000.0).unwrap();
+        stock
+            .place_raw_order(coop, 100, 1_000.0, &mut SimRng::from_seed(1))
+            .unwrap();
 
-        assert_eq!(stock.tick_purchase_orders(RAW_DELIVERY_DELAY_S - 1.0), 0);
+        let delay_s = coop.lead_time_h * 3600.0;
+        assert_eq!(stock.tick_purchase_orders(delay_s - 1.0), 0);
         assert_eq!(stock.raw_receiving, 0);
         assert_eq!(stock.tick_purchase_orders(1.0), 100);
         assert_eq!(stock.raw_receiving, 100);
         assert_eq!(stock.pending_orders.len(), 0);
+        let receipts = stock.take_raw_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].qty, 100);
+        assert_eq!(receipts[0].supplier, DEFAULT_RAW_SUPPLIER);
+        assert_eq!(receipts[0].grade, coop.grade);
+        assert!(stock.take_raw_receipts().is_empty());
+    }
+
+    #[test]
+    fn partial_delivery_ships_backorder_later() {
+        let catalogue = SupplierCatalogue::default();
+        let supplier = catalogue
+            .supplier("negoce_iberique")
+            .expect("negoce present");
+        let mut stock = StockState::default();
+        stock
+            .place_raw_order(supplier, 400, 1_000.0, &mut SimRng::from_seed(1))
+            .unwrap();
+        let order = &mut stock.pending_orders[0];
+        order.remaining_delivery_s = 10.0 * 3600.0;
+        order.short_qty = 150;
+        order.backorder_delay_s = 3600.0;
+
+        stock.tick_purchase_orders(7.0 * 3600.0);
+        assert!(stock.pending_orders[0].is_late());
+        assert_eq!(stock.tick_purchase_orders(3.0 * 3600.0), 250);
+        assert_eq!(stock.pending_raw_qty(), 150);
+        assert_eq!(stock.tick_purchase_orders(1800.0), 0);
+        assert_eq!(stock.tick_purchase_orders(1800.0), 150);
+        assert!(stock.pending_orders.is_empty());
+        let receipts = stock.take_raw_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert!(
+            receipts
+                .iter()
+                .all(|receipt| receipt.grade == QualityGrade::B)
+        );
+    }
+
+    #[test]
+    fn reorder_policy_orders_up_to_target_within_reserve_and_capacity() {
+        let catalogue = SupplierCatalogue::default();
+        let mut rng = SimRng::from_seed(3);
+        let policy =
+            ReorderPolicy::raw_default(crate::gestion::fournisseurs::DEFAULT_RAW_SUPPLIER_ID);
+        let mut stock = StockState {
+            raw_receiving: 300,
+            ..StockState::default()
+        };
+        assert!(
+            stock
+                .set_reorder_policy(ReorderPolicy {
+                    order_up_to: 300,
+                    ..policy.clone()
+                })
+                .is_err()
+        );
+        stock.set_reorder_policy(policy.clone()).unwrap();
+
+        let decisions = stock.run_reorder_policies(&catalogue, 10_000.0, 60.0, &mut rng);
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].position, 300);
+        assert!(matches!(
+            decisions[0].outcome,
+            ReorderOutcome::Commande { qty: 1_200, .. }
+        ));
+        assert_eq!(decisions[0].cost_eur(), 1_320.0);
+        assert_eq!(stock.pending_raw_qty(), 1_200);
+        assert!(
+            stock
+                .run_reorder_policies(&catalogue, 10_000.0, 120.0, &mut rng)
+                .is_empty()
+        );
+
+        // La reserve de 5000 EUR ne laisse que 300 EUR: commande reduite au palier de base.
+        let mut stock = StockState::default();
+        stock.set_reorder_policy(policy.clone()).unwrap();
+        let decisions = stock.run_reorder_policies(&catalogue, 5_300.0, 0.0, &mut rng);
+        assert_eq!(decisions[0].cost_eur(), 300.0);
+        assert_eq!(stock.pending_raw_qty(), 250);
+
+        let mut stock = StockState::default();
+        stock.set_reorder_policy(policy.clone()).unwrap();
+        let blocked = stock.run_reorder_policies(&catalogue, 5_050.0, 0.0, &mut rng);
+        assert!(matches!(
+            blocked[0].outcome,
+            ReorderOutcome::ReserveTresorerie { .. }
+        ));
+        assert!(
+            stock
+                .run_reorder_policies(&catalogue, 5_050.0, 60.0, &mut rng)
+                .is_empty()
+        );
+        assert_eq!(stock.reorder_log.len(), 1);
+        assert!(stock.pending_orders.is_empty());
+
+        let mut stock = StockState {
+            raw_receiving: RAW_RECEIVING_CAPACITY - 50,
+            ..StockState::default()
+        };
+        stock
+            .set_reorder_policy(ReorderPolicy {
+                reorder_point: RAW_RECEIVING_CAPACITY - 40,
+                order_up_to: RAW_RECEIVING_CAPACITY,
+                ..policy
+            })
+            .unwrap();
+        let blocked = stock.run_reorder_policies(&catalogue, 100_000.0, 0.0, &mut rng);
+        assert_eq!(
+            blocked[0].outcome,
+            ReorderOutcome::CapaciteReception { room: 50, moq: 100 }
+        );
+    }
+
+    #[test]
+    fn spare_parts_order_delivers_to_parts_store() {
+        let mut stock = StockState::default();
+        assert!(stock.place_spare_parts_order(2, 100.0).is_err());
+        assert!(
+            stock
+                .place_spare_parts_order(SPARE_PARTS_CAPACITY + 1, 1_000_000.0)
+                .is_err()
+        );
+        let (_, cost) = stock.place_spare_parts_order(2, 1_000.0).unwrap();
+        assert_eq!(cost, 2.0 * SPARE_PART_UNIT_COST_EUR);
+
+        assert!(!stock.take_spare_parts(1));
+        stock.tick_purchase_orders(SPARE_PART_DELIVERY_DELAY_S);
+        assert_eq!(stock.spare_parts, 2);
+        assert_eq!(stock.raw_receiving, 0);
+        assert!(stock.take_spare_parts(1));
+        assert_eq!(stock.spare_parts, 1);
     }
 
     #[test]
-    fn cariste_transfer_respects_line_capacity() {
+    fn raw_transfer_respects_line_capacity() {
         let mut stock = StockState {
             raw_receiving: 500,
             raw_line_input: RAW_LINE_INPUT_CAPACITY - 2,
             ..StockState::default()
         };
-        let moved = stock.tick_cariste_transfer(1.0, 4);
+        let moved = stock.transfer_raw_to_line(40);
         assert_eq!(moved, 2);
+        assert_eq!(stock.raw_receiving, 498);
         assert_eq!(stock.raw_line_input, RAW_LINE_INPUT_CAPACITY);
     }
 }
@@ -1,3 +1,4 @@
+use super::marche::MarketState;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -7,6 +8,7 @@ pub enum SalesBlockReason {
     BureauManquant,
     AdministrateurManquant,
     StockFiniAbsent,
+    DemandeInsuffisante,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -34,13 +36,17 @@ impl SalesState {
         seats as f64 * 6.0
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn tick(
         &mut self,
         dt_hours: f64,
         finished_stock: &mut u32,
         admins: usize,
         offices: usize,
+        market: &mut MarketState,
+        product_id: &str,
         sale_price: f64,
+        now_s: f64,
     ) -> (u32, f64) {
         self.last_units_per_hour = 0.0;
         self.last_revenue_per_hour = 0.0;
@@ -66,16 +72,32 @@ impl SalesState {
         }
 
         let capacity = Self::capacity_units_per_hour(admins, offices);
-        self.sale_accum += capacity * dt_hours;
-        let sellable = self.sale_accum.floor() as u32;
-        let sold = sellable.min(*finished_stock);
+        // Sans clients, la capacite inutilisee ne s'accumule pas au-dela d'une heure.
+        self.sale_accum = (self.sale_accum + capacity * dt_hours).min(capacity.max(1.0));
+        let sellable = (self.sale_accum.floor() as u32).min(*finished_stock);
+        let (sold, revenue) = if sellable > 0 {
+            market.ship(sellable, product_id, sale_price, now_s)
+        } else {
+            (0, 0.0)
+        };
         if sold > 0 {
             *finished_stock -= sold;
             self.sale_accum -= sold as f64;
         }
-        let revenue = sold as f64 * sale_price.max(0.0);
-        self.last_units_per_hour = capacity;
-        self.last_revenue_per_hour = capacity * sale_price.max(0.0);
+        if sold == 0 && !market.has_demand(product_id) {
+            self.last_block_reason = SalesBlockReason::DemandeInsuffisante;
+            return (0, 0.0);
+        }
+        let contract_open = market
+            .active_contracts()
+            .any(|contract| contract.product_id == product_id);
+        let units_per_hour = if contract_open {
+            capacity
+        } else {
+            capacity.min(market.last_demand_per_hour)
+        };
+        self.last_units_per_hour = units_per_hour;
+        self.last_revenue_per_hour = units_per_hour * sale_price.max(0.0);
         self.last_block_reason = SalesBlockReason::Operationnel;
         (sold, revenue)
     }
@@ -88,9 +110,11 @@ mod tests {
     #[test]
     fn sales_are_progressive_and_require_admin_and_office() {
         let mut sales = SalesState::default();
+        let mut market = MarketState::default();
+        market.spot_demand.insert("ail".to_string(), 10.0);
         let mut stock = 3;
 
-        let (sold, revenue) = sales.tick(1.0, &mut stock, 0, 1, 10.0);
+        let (sold, revenue) = sales.tick(1.0, &mut stock, 0, 1, &mut market, "ail", 10.0, 0.0);
         assert_eq!(sold, 0);
         assert_eq!(revenue, 0.0);
         assert_eq!(
@@ -98,9 +122,20 @@ mod tests {
             SalesBlockReason::AdministrateurManquant
         );
 
-        let (sold, revenue) = sales.tick(0.5, &mut stock, 1, 1, 10.0);
+        let (sold, revenue) = sales.tick(0.5, &mut stock, 1, 1, &mut market, "ail", 10.0, 0.0);
         assert_eq!(sold, 3);
         assert_eq!(revenue, 30.0);
         assert_eq!(stock, 0);
+
+        // Sans clients en attente, le stock reste en rayon.
+        let mut stock = 5;
+        market.spot_demand.insert("ail".to_string(), 0.0);
+        let (sold, _) = sales.tick(1.0, &mut stock, 1, 1, &mut market, "ail", 10.0, 0.0);
+        assert_eq!(sold, 0);
+        assert_eq!(stock, 5);
+        assert_eq!(
+            sales.last_block_reason,
+            SalesBlockReason::DemandeInsuffisante
+        );
     }
 }
@@ -99,6 +99,22 @@ impl SocialActionKind {
         )
     }
 
+    // Variation d'affinite de base d'un echange, avant temperament des pions.
+    pub fn affinity_delta(self) -> f32 {
+        match self {
+            SocialActionKind::DireBonjour => 0.04,
+            SocialActionKind::SmallTalk => 0.06,
+            SocialActionKind::Compliment => 0.10,
+            SocialActionKind::DemanderAide => 0.05,
+            SocialActionKind::Blague => 0.07,
+            SocialActionKind::Ragot => -0.02,
+            SocialActionKind::SExcuser => 0.09,
+            SocialActionKind::Menacer => -0.15,
+            SocialActionKind::Insulter => -0.18,
+            SocialActionKind::SEngueuler => -0.22,
+        }
+    }
+
     pub fn duration_s(self) -> f32 {
         match self {
             SocialActionKind::DireBonjour => 0.9,
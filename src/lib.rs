@@ -0,0 +1,1664 @@
+mod aleatoire;
+mod character;
+mod chariot_elevateur;
+mod deplacement;
+mod edition;
+mod editor_tools;
+mod four_texture;
+pub mod gestion;
+mod historique;
+mod interactions;
+mod modes;
+mod papa;
+pub mod recettes;
+pub mod rejeu;
+mod render_safety;
+mod rendu;
+mod sauvegarde;
+pub mod sim;
+mod social;
+mod telephone;
+mod ui_editor;
+mod ui_hud;
+mod ui_kit;
+mod ui_pawns;
+mod utilitaires;
+
+use aleatoire::{RngStream, mix_seed};
+use character::{
+    CharacterCatalog, CharacterFacing, CharacterGesture, CharacterRecord, CharacterRenderParams,
+    build_lineage_preview, compact_visual_summary, draw_character, inspector_lines,
+};
+use chariot_elevateur::*;
+use deplacement::*;
+use edition::*;
+use editor_tools::*;
+use macroquad::prelude::*;
+use modes::*;
+use render_safety::*;
+use rendu::*;
+use ron::{
+    de::from_str as ron_from_str,
+    ser::{PrettyConfig, to_string_pretty as ron_to_string_pretty},
+};
+use sauvegarde::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::fs;
+use std::path::Path;
+use ui_editor::*;
+use ui_hud::*;
+use ui_pawns::*;
+use utilitaires::*;
+
+// Carte de l'usine de depart, aussi utilisee par la simulation sans fenetre.
+pub use utilitaires::generate_starter_factory_world;
+
+const TILE_SIZE: f32 = 32.0;
+pub const MAP_W: i32 = 168;
+pub const MAP_H: i32 = 108;
+const MAX_MAP_TILES: usize = 512 * 512;
+const WINDOW_W: i32 = 1536;
+const WINDOW_H: i32 = 864;
+pub const FIXED_DT: f32 = 1.0 / 60.0;
+const DIRECTION_HYSTERESIS: f32 = 0.18;
+const WALK_CYCLE_SPEED: f32 = 9.0;
+const AUTO_ACCEL: f32 = 720.0;
+const AUTO_ARRIVE_RADIUS: f32 = 34.0;
+const AUTO_WAYPOINT_REACH: f32 = 5.0;
+const NPC_WANDER_SPEED: f32 = 92.0;
+const NPC_IDLE_MIN: f32 = 0.7;
+const NPC_IDLE_MAX: f32 = 2.0;
+const MAP_FILE_PATH: &str = "maps/main_map.ron";
+const EDITOR_LAYOUTS_DIR_PATH: &str = "maps/layouts";
+const EDITOR_BLUEPRINTS_DIR_PATH: &str = "maps/blueprints";
+const EDITOR_AUTOSAVE_PATH: &str = "maps/layouts/autosave_editor.ron";
+const EDITOR_AUTOSAVE_INTERVAL_S: f32 = 20.0;
+const EDITOR_UI_SETTINGS_PATH: &str = "data/editor_ui.ron";
+const SAVE_DIR_PATH: &str = "saves";
+const SAVE_SCHEMA_VERSION: u32 = 1;
+const MAP_SCHEMA_VERSION: u32 = 2;
+pub const SIM_CONFIG_PATH: &str = "data/starter_sim.ron";
+const PAPA_PLAN_PATH: &str = "data/papa/plan_ligne.ron";
+const FLOOR_TEXTURE_CANDIDATES: [&str; 5] = [
+    "textures/herbe_sol.png",
+    "textures/model/grass_tile_0.png",
+    "textures/model/grass_tile_1.png",
+    "textures/sol1.png",
+    "Textures/sol1.png",
+];
+const FLOOR_METAL_TEXTURE_CANDIDATES: [&str; 3] = [
+    "textures/model/concrete_grid_tile.png",
+    "textures/sol2.png",
+    "Textures/sol2.png",
+];
+const FLOOR_WOOD_TEXTURE_CANDIDATES: [&str; 1] = ["textures/model/wood_parquet_tile.png"];
+const WALL_STONE_TEXTURE_CANDIDATES: [&str; 1] = ["textures/model/stone_wall_tile.png"];
+const TREE_OAK_TEXTURE_CANDIDATES: [&str; 1] = ["textures/model/tree_oak.png"];
+const TREE_POPLAR_TEXTURE_CANDIDATES: [&str; 1] = ["textures/model/tree_poplar.png"];
+const TREE_PINE_TEXTURE_CANDIDATES: [&str; 1] = ["textures/model/tree_pine.png"];
+const POT_DE_FLEUR_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/pot-fleur.png", "Textures/pot-fleur.png"];
+const INITIAL_RAW_MATERIAL_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/caisseboisail1.png", "Textures/caisseboisail1.png"];
+const BROKEN_GARLIC_CRATE_TEXTURE_CANDIDATES: [&str; 4] = [
+    "textures/caisseail1.png",
+    "Textures/caisseail1.png",
+    "textures/caisseailail1.png",
+    "Textures/caisseailail1.png",
+];
+const BOX_CARTON_VIDE_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/boxcartonvide.png", "Textures/boxcartonvide.png"];
+const BOX_SAC_BLEU_TEXTURE_CANDIDATES: [&str; 4] = [
+    "textures/boxsacbleu1.png",
+    "Textures/boxsacbleu1.png",
+    "textures/boxsableu1.png",
+    "Textures/boxsableu1.png",
+];
+const BOX_SAC_ROUGE_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/boxsacrouge1.png", "Textures/boxsacrouge1.png"];
+const BOX_SAC_VERT_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/boxsacvert1.png", "Textures/boxsacvert1.png"];
+const PALETTE_LOGISTIQUE_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/palette1.png", "Textures/palette1.png"];
+const BUREAU_PC_ON_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/bureaupc1.png", "Textures/bureaupc1.png"];
+const BUREAU_PC_OFF_TEXTURE_CANDIDATES: [&str; 2] =
+    ["textures/bureaupc2.png", "Textures/bureaupc2.png"];
+const LAVABO_TEXTURE_CANDIDATES: [&str; 2] = ["textures/lavabo1.png", "Textures/lavabo1.png"];
+const MAIN_MENU_BACKGROUND_TEXTURE_CANDIDATES: [&str; 2] = ["fond1.png", "Fond1.png"];
+const MAP_FILE_VERSION: u32 = 4;
+const EDITOR_UNDO_LIMIT: usize = 160;
+const PLAY_CAMERA_MARGIN: f32 = 10.0;
+const PLAY_CAMERA_PAN_SPEED: f32 = 880.0;
+const PLAY_CAMERA_ZOOM_MIN: f32 = 0.55;
+const PLAY_CAMERA_ZOOM_MAX: f32 = 2.65;
+const PLAY_CAMERA_ZOOM_STEP: f32 = 0.03;
+const EDITOR_CAMERA_PAN_SPEED: f32 = 980.0;
+const EDITOR_CAMERA_ZOOM_MIN: f32 = 0.45;
+const EDITOR_CAMERA_ZOOM_MAX: f32 = 3.2;
+const EDITOR_CAMERA_ZOOM_STEP: f32 = 0.28;
+const MAX_SIM_STEPS_PER_FRAME: usize = 8;
+
+const MASK_N: u8 = 1 << 0;
+const MASK_E: u8 = 1 << 1;
+const MASK_S: u8 = 1 << 2;
+const MASK_W: u8 = 1 << 3;
+
+fn autosave_status_message(path: &str, save_result: Result<(), String>) -> String {
+    match save_result {
+        Ok(()) => format!("Sauvegarde auto: {path}"),
+        Err(err) => format!("Sauvegarde auto echouee ({path}): {err}"),
+    }
+}
+
+pub fn window_conf() -> Conf {
+    Conf {
+        window_title: "Rxchixs - Prototype visuel".to_string(),
+        window_width: WINDOW_W,
+        window_height: WINDOW_H,
+        high_dpi: true,
+        window_resizable: true,
+        ..Default::default()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Tile {
+    Floor,
+    FloorMetal,
+    FloorWood,
+    FloorMoss,
+    FloorSand,
+    Wall,
+    WallBrick,
+    WallSteel,
+    WallNeon,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct World {
+    w: i32,
+    h: i32,
+    tiles: Vec<Tile>,
+    #[serde(skip)]
+    revision: u64,
+}
+
+impl World {
+    fn new_room(w: i32, h: i32) -> Self {
+        let mut world = Self {
+            w,
+            h,
+            tiles: vec![Tile::Floor; (w * h) as usize],
+            revision: 0,
+        };
+
+        for x in 0..w {
+            world.set(x, 0, Tile::Wall);
+            world.set(x, h - 1, Tile::Wall);
+        }
+
+        for y in 0..h {
+            world.set(0, y, Tile::Wall);
+            world.set(w - 1, y, Tile::Wall);
+        }
+
+        for y in 4..11 {
+            world.set(12, y, Tile::Wall);
+        }
+
+        for x in 5..10 {
+            world.set(x, 8, Tile::Wall);
+        }
+
+        world
+    }
+
+    fn idx(&self, x: i32, y: i32) -> usize {
+        (y * self.w + x) as usize
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.w && y >= 0 && y < self.h
+    }
+
+    fn get(&self, x: i32, y: i32) -> Tile {
+        if !self.in_bounds(x, y) {
+            return Tile::Wall;
+        }
+        self.tiles[self.idx(x, y)]
+    }
+
+    fn set(&mut self, x: i32, y: i32, tile: Tile) {
+        if self.in_bounds(x, y) {
+            let i = self.idx(x, y);
+            if self.tiles[i] != tile {
+                self.tiles[i] = tile;
+                self.revision = self.revision.wrapping_add(1);
+            }
+        }
+    }
+
+    fn is_solid(&self, x: i32, y: i32) -> bool {
+        tile_is_wall(self.get(x, y))
+    }
+
+    fn tile_rect(x: i32, y: i32) -> Rect {
+        Rect::new(
+            x as f32 * TILE_SIZE,
+            y as f32 * TILE_SIZE,
+            TILE_SIZE,
+            TILE_SIZE,
+        )
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Aabb {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Aabb {
+    fn from_center(center: Vec2, half: Vec2) -> Self {
+        Self {
+            min: center - half,
+            max: center + half,
+        }
+    }
+
+    fn intersects_rect(&self, r: Rect) -> bool {
+        self.min.x < r.x + r.w && self.max.x > r.x && self.min.y < r.y + r.h && self.max.y > r.y
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum PropKind {
+    Crate,
+    Pipe,
+    Lamp,
+    Banner,
+    Plant,
+    Bench,
+    Crystal,
+    BoxCartonVide,
+    BoxSacBleu,
+    BoxSacRouge,
+    BoxSacVert,
+    PaletteLogistique,
+    BureauPcOn,
+    BureauPcOff,
+    CaisseAilBrut,
+    CaisseAilCasse,
+    Lavabo,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+struct Prop {
+    tile_x: i32,
+    tile_y: i32,
+    kind: PropKind,
+    phase: f32,
+    #[serde(default)]
+    rotation_quarter: i8,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum ControlMode {
+    Manual,
+    AutoMove,
+}
+
+#[derive(Default)]
+struct AutoMoveState {
+    target_tile: Option<(i32, i32)>,
+    target_world: Option<Vec2>,
+    path_tiles: Vec<(i32, i32)>,
+    path_world: Vec<Vec2>,
+    next_waypoint: usize,
+}
+
+struct NpcWanderer {
+    pos: Vec2,
+    half: Vec2,
+    speed: f32,
+    facing: CharacterFacing,
+    facing_left: bool,
+    velocity: Vec2,
+    is_walking: bool,
+    anim_frame: usize,
+    walk_cycle: f32,
+    auto: AutoMoveState,
+    idle_timer: f32,
+    hold_timer: f32,
+    rng: aleatoire::SimRng,
+}
+
+async fn load_first_available_texture(candidates: &[&str]) -> Option<Texture2D> {
+    for path in candidates {
+        if let Ok(texture) = load_texture(path).await {
+            return Some(texture);
+        }
+    }
+    None
+}
+
+async fn load_floor_tile_texture() -> Option<Texture2D> {
+    load_first_available_texture(&FLOOR_TEXTURE_CANDIDATES).await
+}
+
+async fn load_floor_metal_tile_texture() -> Option<Texture2D> {
+    load_first_available_texture(&FLOOR_METAL_TEXTURE_CANDIDATES).await
+}
+
+async fn load_floor_wood_tile_texture() -> Option<Texture2D> {
+    load_first_available_texture(&FLOOR_WOOD_TEXTURE_CANDIDATES).await
+}
+
+async fn load_wall_stone_texture() -> Option<Texture2D> {
+    load_first_available_texture(&WALL_STONE_TEXTURE_CANDIDATES).await
+}
+
+async fn load_tree_oak_texture() -> Option<Texture2D> {
+    load_first_available_texture(&TREE_OAK_TEXTURE_CANDIDATES).await
+}
+
+async fn load_tree_poplar_texture() -> Option<Texture2D> {
+    load_first_available_texture(&TREE_POPLAR_TEXTURE_CANDIDATES).await
+}
+
+async fn load_tree_pine_texture() -> Option<Texture2D> {
+    load_first_available_texture(&TREE_PINE_TEXTURE_CANDIDATES).await
+}
+
+async fn load_pot_de_fleur_texture() -> Option<Texture2D> {
+    load_first_available_texture(&POT_DE_FLEUR_TEXTURE_CANDIDATES).await
+}
+
+async fn load_initial_raw_material_texture() -> Option<Texture2D> {
+    load_first_available_texture(&INITIAL_RAW_MATERIAL_TEXTURE_CANDIDATES).await
+}
+
+async fn load_broken_garlic_crate_texture() -> Option<Texture2D> {
+    load_first_available_texture(&BROKEN_GARLIC_CRATE_TEXTURE_CANDIDATES).await
+}
+
+async fn load_box_carton_vide_texture() -> Option<Texture2D> {
+    load_first_available_texture(&BOX_CARTON_VIDE_TEXTURE_CANDIDATES).await
+}
+
+async fn load_box_sac_bleu_texture() -> Option<Texture2D> {
+    load_first_available_texture(&BOX_SAC_BLEU_TEXTURE_CANDIDATES).await
+}
+
+async fn load_box_sac_rouge_texture() -> Option<Texture2D> {
+    load_first_available_texture(&BOX_SAC_ROUGE_TEXTURE_CANDIDATES).await
+}
+
+async fn load_box_sac_vert_texture() -> Option<Texture2D> {
+    load_first_available_texture(&BOX_SAC_VERT_TEXTURE_CANDIDATES).await
+}
+
+async fn load_palette_logistique_texture() -> Option<Texture2D> {
+    load_first_available_texture(&PALETTE_LOGISTIQUE_TEXTURE_CANDIDATES).await
+}
+
+async fn load_bureau_pc_on_texture() -> Option<Texture2D> {
+    load_first_available_texture(&BUREAU_PC_ON_TEXTURE_CANDIDATES).await
+}
+
+async fn load_bureau_pc_off_texture() -> Option<Texture2D> {
+    load_first_available_texture(&BUREAU_PC_OFF_TEXTURE_CANDIDATES).await
+}
+
+async fn load_lavabo_texture() -> Option<Texture2D> {
+    load_first_available_texture(&LAVABO_TEXTURE_CANDIDATES).await
+}
+
+async fn load_main_menu_background_texture() -> Option<Texture2D> {
+    load_first_available_texture(&MAIN_MENU_BACKGROUND_TEXTURE_CANDIDATES).await
+}
+
+impl NpcWanderer {
+    fn new(pos: Vec2, seed: u64) -> Self {
+        Self {
+            pos,
+            half: vec2(9.0, 13.0),
+            speed: NPC_WANDER_SPEED,
+            facing: CharacterFacing::Front,
+            facing_left: false,
+            velocity: Vec2::ZERO,
+            is_walking: false,
+            anim_frame: 0,
+            walk_cycle: 0.0,
+            auto: AutoMoveState::default(),
+            idle_timer: 1.0,
+            hold_timer: 0.0,
+            rng: aleatoire::SimRng::from_state(seed ^ 0xC0FF_EE11_D00D_CAFE),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct OpenNode {
+    f: i32,
+    g: i32,
+    idx: usize,
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f
+            .cmp(&self.f)
+            .then_with(|| other.g.cmp(&self.g))
+            .then_with(|| other.idx.cmp(&self.idx))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+struct Player {
+    pos: Vec2,
+    half: Vec2,
+    speed: f32,
+    control_mode: ControlMode,
+    facing: CharacterFacing,
+    facing_left: bool,
+    velocity: Vec2,
+    is_walking: bool,
+    anim_frame: usize,
+    walk_cycle: f32,
+    auto: AutoMoveState,
+}
+
+impl Player {
+    fn new(pos: Vec2) -> Self {
+        Self {
+            pos,
+            half: vec2(10.0, 14.0),
+            speed: 140.0,
+            control_mode: ControlMode::Manual,
+            facing: CharacterFacing::Front,
+            facing_left: false,
+            velocity: Vec2::ZERO,
+            is_walking: false,
+            anim_frame: 0,
+            walk_cycle: 0.0,
+            auto: AutoMoveState::default(),
+        }
+    }
+}
+
+struct GameState {
+    world: World,
+    player: Player,
+    chariot: ChariotElevateur,
+    chargeur_clark: ChargeurClark,
+    npc: NpcWanderer,
+    camera_center: Vec2,
+    camera_zoom: f32,
+    palette: Palette,
+    sim: sim::FactorySim,
+    props: Vec<Prop>,
+    character_catalog: CharacterCatalog,
+    lineage_seed: u64,
+    lineage: Vec<CharacterRecord>,
+    player_lineage_index: usize,
+    npc_character: CharacterRecord,
+    sim_worker_character: CharacterRecord,
+    papa_character: CharacterRecord,
+    pawns: Vec<PawnCard>,
+    social_state: social::SocialState,
+    pawn_ui: PawnsUiState,
+    hud_ui: HudUiState,
+    telephone: telephone::TelephoneEtat,
+    papa: papa::PapaEtat,
+    pause_menu_open: bool,
+    pause_panel: PausePanel,
+    pause_status_text: Option<String>,
+    pause_status_timer: f32,
+    pause_save_name: String,
+    pause_sauvegardes: Vec<SauvegardeInfo>,
+    pause_sauvegardes_warning: Option<String>,
+    pause_sauvegardes_offset: usize,
+    pause_selected_sauvegarde: Option<usize>,
+    show_character_inspector: bool,
+    debug: bool,
+    perf_stats: FramePerfStats,
+    minimap_cache: MinimapTextureCache,
+    last_input: Vec2,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct FramePerfStats {
+    frame_ms: f64,
+    sim_ms: f64,
+    world_ms: f64,
+    ui_ms: f64,
+}
+
+#[derive(Default)]
+struct MinimapTextureCache {
+    dirty: bool,
+    texture: Option<Texture2D>,
+    world_revision: u64,
+    width_px: u16,
+    height_px: u16,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum PausePanel {
+    Aucun,
+    Aide,
+    Options,
+    Sauvegarder,
+    Charger,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum AppMode {
+    MainMenu,
+    Playing,
+    Editor,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum MainMenuView {
+    Principal,
+    Charger,
+    Options,
+}
+
+struct MainMenuState {
+    view: MainMenuView,
+    saves: Vec<SauvegardeInfo>,
+    saves_warning: Option<String>,
+    selected_save: Option<usize>,
+    saves_offset: usize,
+    status_text: Option<String>,
+    status_timer: f32,
+    show_fps: bool,
+    ambiance_motion: bool,
+}
+
+impl MainMenuState {
+    fn new() -> Self {
+        Self {
+            view: MainMenuView::Principal,
+            saves: Vec::new(),
+            saves_warning: None,
+            selected_save: None,
+            saves_offset: 0,
+            status_text: None,
+            status_timer: 0.0,
+            show_fps: false,
+            ambiance_motion: true,
+        }
+    }
+}
+
+enum MainMenuAction {
+    None,
+    StartNewGame,
+    StartFromSave(String),
+    OpenEditor,
+    Quit,
+}
+
+fn set_main_menu_status(menu: &mut MainMenuState, msg: impl Into<String>) {
+    menu.status_text = Some(msg.into());
+    menu.status_timer = 4.0;
+}
+
+fn tick_main_menu_status(menu: &mut MainMenuState, frame_dt: f32) {
+    if menu.status_timer <= 0.0 {
+        return;
+    }
+    menu.status_timer = (menu.status_timer - frame_dt).max(0.0);
+    if menu.status_timer <= f32::EPSILON {
+        menu.status_text = None;
+    }
+}
+
+fn refresh_main_menu_saves(menu: &mut MainMenuState) {
+    match lister_sauvegardes() {
+        Ok(listing) => {
+            menu.saves = listing.slots;
+            menu.saves_warning = if listing.warnings.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "{} sauvegarde(s) ignoree(s): {}",
+                    listing.warnings.len(),
+                    listing.warnings[0]
+                ))
+            };
+            if menu.saves.is_empty() {
+                menu.selected_save = None;
+                menu.saves_offset = 0;
+            } else {
+                let max_index = menu.saves.len() - 1;
+                if menu.selected_save.is_none() {
+                    menu.selected_save = Some(0);
+                } else if let Some(selected) = menu.selected_save {
+                    menu.selected_save = Some(selected.min(max_index));
+                }
+                menu.saves_offset = menu.saves_offset.min(max_index);
+            }
+        }
+        Err(err) => {
+            menu.saves.clear();
+            menu.selected_save = None;
+            menu.saves_offset = 0;
+            menu.saves_warning = Some(err);
+        }
+    }
+}
+
+fn parse_bool_env_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "oui" | "on"
+    )
+}
+
+fn should_autostart_play_from_env() -> bool {
+    std::env::var("RXCHIXS_AUTOSTART_PLAY").is_ok_and(|value| parse_bool_env_flag(&value))
+}
+
+fn capture_frame_path_from_env() -> Option<String> {
+    std::env::var("RXCHIXS_CAPTURE_FRAME")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_capture_after_frames(value: &str) -> u64 {
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|frames| *frames <= 600)
+        .unwrap_or(6)
+}
+
+fn capture_after_frames_from_env() -> u64 {
+    std::env::var("RXCHIXS_CAPTURE_AFTER_FRAMES")
+        .ok()
+        .map(|value| parse_capture_after_frames(&value))
+        .unwrap_or(6)
+}
+
+fn should_exit_after_capture_from_env() -> bool {
+    std::env::var("RXCHIXS_EXIT_AFTER_CAPTURE").is_ok_and(|value| parse_bool_env_flag(&value))
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct MapAsset {
+    #[serde(default = "default_map_schema_version")]
+    schema_version: u32,
+    version: u32,
+    label: String,
+    world: World,
+    props: Vec<Prop>,
+    #[serde(default)]
+    zones: Vec<ZoneRegion>,
+    player_spawn: (i32, i32),
+    npc_spawn: (i32, i32),
+}
+
+fn default_map_schema_version() -> u32 {
+    1
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+enum ZoneKind {
+    #[default]
+    Logistique,
+    Propre,
+    Froide,
+    Production,
+    Stockage,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ZoneRegion {
+    id: u16,
+    label: String,
+    kind: ZoneKind,
+    #[serde(default)]
+    acces_restreint: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    tiles: Vec<(i32, i32)>,
+}
+
+impl MapAsset {
+    fn new_default() -> Self {
+        let world = generate_starter_factory_world(MAP_W, MAP_H);
+        let props = default_props(&world);
+        let (fx0, fx1, fy0, fy1) = starter_factory_bounds(MAP_W, MAP_H);
+        let span_y = (fy1 - fy0).max(6);
+        let road_y = clamp_i32(fy0 + span_y / 2, fy0 + 2, fy1 - 2);
+        let ship_y = clamp_i32(fy0 + (span_y * 3) / 4, fy0 + 2, fy1 - 2);
+        let player_spawn = nearest_walkable_tile(&world, (fx0 - 11, road_y)).unwrap_or((2, 2));
+        let npc_spawn =
+            nearest_walkable_tile(&world, (fx1 + 10, ship_y + 2)).unwrap_or((MAP_W - 4, MAP_H / 2));
+
+        Self {
+            schema_version: MAP_SCHEMA_VERSION,
+            version: MAP_FILE_VERSION,
+            label: "Usine de depart".to_string(),
+            world,
+            props,
+            zones: Vec::new(),
+            player_spawn,
+            npc_spawn,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum EditorBrush {
+    Floor,
+    FloorMetal,
+    FloorWood,
+    FloorMoss,
+    FloorSand,
+    Wall,
+    WallBrick,
+    WallSteel,
+    WallNeon,
+    Crate,
+    Pipe,
+    Lamp,
+    Banner,
+    Plant,
+    Bench,
+    Crystal,
+    BoxCartonVide,
+    BoxSacBleu,
+    BoxSacRouge,
+    BoxSacVert,
+    PaletteLogistique,
+    BureauPcOn,
+    BureauPcOff,
+    CaisseAilBrut,
+    CaisseAilCasse,
+    Lavabo,
+    EraseProp,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum EditorTool {
+    Select,
+    Brush,
+    Rect,
+    Line,
+    Fill,
+    Paste,
+}
+
+#[derive(Clone)]
+struct EditorSnapshot {
+    world: World,
+    props: Vec<Prop>,
+    zones: Vec<ZoneRegion>,
+    player_spawn: (i32, i32),
+    npc_spawn: (i32, i32),
+}
+
+struct EditorState {
+    brush: EditorBrush,
+    tool: EditorTool,
+    hover_tile: Option<(i32, i32)>,
+    selected_tile: Option<(i32, i32)>,
+    selected_prop: Option<usize>,
+    selection_rect: Option<((i32, i32), (i32, i32))>,
+    clipboard: Option<EditorClipboard>,
+    drag_start: Option<(i32, i32)>,
+    show_grid: bool,
+    camera_center: Vec2,
+    camera_zoom: f32,
+    camera_initialized: bool,
+    status_text: String,
+    status_timer: f32,
+    undo_stack: Vec<EditorSnapshot>,
+    redo_stack: Vec<EditorSnapshot>,
+    stroke_active: bool,
+    stroke_changed: bool,
+    brush_size: u8,
+    prop_rotation: i8,
+    zone_kind: ZoneKind,
+    validation_issues: Vec<ValidationIssue>,
+    validation_scroll: usize,
+    validation_refresh_timer: f32,
+    autosave_timer: f32,
+    ui: EditorUiState,
+}
+
+impl EditorState {
+    fn new() -> Self {
+        Self {
+            brush: EditorBrush::Wall,
+            tool: EditorTool::Brush,
+            hover_tile: None,
+            selected_tile: None,
+            selected_prop: None,
+            selection_rect: None,
+            clipboard: None,
+            drag_start: None,
+            show_grid: true,
+            camera_center: Vec2::ZERO,
+            camera_zoom: 1.05,
+            camera_initialized: false,
+            status_text: "Editeur pret".to_string(),
+            status_timer: 0.0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            stroke_active: false,
+            stroke_changed: false,
+            brush_size: 1,
+            prop_rotation: 0,
+            zone_kind: ZoneKind::Logistique,
+            validation_issues: Vec::new(),
+            validation_scroll: 0,
+            validation_refresh_timer: 0.0,
+            autosave_timer: 0.0,
+            ui: load_editor_ui_state(EDITOR_UI_SETTINGS_PATH),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum EditorAction {
+    None,
+    StartPlay,
+    BackToMenu,
+}
+
+struct EditorRuntimeFrame {
+    mouse: Vec2,
+    left_pressed: bool,
+    left_down: bool,
+    left_released: bool,
+    right_pressed: bool,
+    middle_down: bool,
+    ctrl_down: bool,
+    shift_down: bool,
+    alt_down: bool,
+    space_down: bool,
+    wheel: f32,
+    layout: EditorUiLayout,
+    map_view_rect: Rect,
+    mouse_over_map: bool,
+}
+
+pub async fn run() {
+    let palette = Palette::new();
+    prewarm_default_font_cache_ui();
+    let floor_texture = load_floor_tile_texture().await;
+    let floor_metal_texture = load_floor_metal_tile_texture().await;
+    let floor_wood_texture = load_floor_wood_tile_texture().await;
+    let wall_stone_texture = load_wall_stone_texture().await;
+    let tree_oak_texture = load_tree_oak_texture().await;
+    let tree_poplar_texture = load_tree_poplar_texture().await;
+    let tree_pine_texture = load_tree_pine_texture().await;
+    let pot_de_fleur_texture = load_pot_de_fleur_texture().await;
+    let initial_raw_material_texture = load_initial_raw_material_texture().await;
+    let broken_garlic_crate_texture = load_broken_garlic_crate_texture().await;
+    let box_carton_vide_texture = load_box_carton_vide_texture().await;
+    let box_sac_bleu_texture = load_box_sac_bleu_texture().await;
+    let box_sac_rouge_texture = load_box_sac_rouge_texture().await;
+    let box_sac_vert_texture = load_box_sac_vert_texture().await;
+    let palette_logistique_texture = load_palette_logistique_texture().await;
+    let bureau_pc_on_texture = load_bureau_pc_on_texture().await;
+    let bureau_pc_off_texture = load_bureau_pc_off_texture().await;
+    let lavabo_texture = load_lavabo_texture().await;
+    let main_menu_background_texture = load_main_menu_background_texture().await;
+    if floor_texture.is_some() {
+        eprintln!("Texture sol exterieur chargee.");
+    } else {
+        eprintln!("Texture sol1 introuvable, procedurale active.");
+    }
+    if floor_metal_texture.is_some() {
+        eprintln!("Texture sol interieur chargee.");
+    } else {
+        eprintln!("Texture sol interieur introuvable, procedurale active.");
+    }
+    if floor_wood_texture.is_some()
+        && wall_stone_texture.is_some()
+        && tree_oak_texture.is_some()
+        && tree_poplar_texture.is_some()
+        && tree_pine_texture.is_some()
+    {
+        eprintln!("Pack visuel modele charge: textures/model/");
+    } else {
+        eprintln!("Pack visuel modele incomplet, fallbacks proceduraux actifs.");
+    }
+    if pot_de_fleur_texture.is_some() {
+        eprintln!("Texture prop chargee: textures/pot-fleur.png");
+    } else {
+        eprintln!("Texture pot-fleur introuvable, procedurale active.");
+    }
+    if initial_raw_material_texture.is_some() {
+        eprintln!("Texture matiere premiere initiale chargee: textures/caisseboisail1.png");
+    } else {
+        eprintln!("Texture matiere premiere initiale introuvable, fallback UI actif.");
+    }
+    if broken_garlic_crate_texture.is_some() {
+        eprintln!("Texture caisse d'ail cassé chargee: textures/caisseail1.png");
+    }
+    if box_carton_vide_texture.is_some() {
+        eprintln!("Texture box carton vide chargee: textures/boxcartonvide.png");
+    }
+    if box_sac_bleu_texture.is_some() {
+        eprintln!("Texture box sac bleu chargee: textures/boxsacbleu1.png");
+    }
+    if box_sac_rouge_texture.is_some() {
+        eprintln!("Texture box sac rouge chargee: textures/boxsacrouge1.png");
+    }
+    if box_sac_vert_texture.is_some() {
+        eprintln!("Texture box sac vert chargee: textures/boxsacvert1.png");
+    }
+    if palette_logistique_texture.is_some() {
+        eprintln!("Texture palette logistique chargee: textures/palette1.png");
+    }
+    if bureau_pc_on_texture.is_some() {
+        eprintln!("Texture bureau PC ON chargee: textures/bureaupc1.png");
+    }
+    if bureau_pc_off_texture.is_some() {
+        eprintln!("Texture bureau PC OFF chargee: textures/bureaupc2.png");
+    }
+    if lavabo_texture.is_some() {
+        eprintln!("Texture lavabo chargee: textures/lavabo1.png");
+    }
+    if main_menu_background_texture.is_some() {
+        eprintln!("Fond menu charge: fond1.png");
+    } else {
+        eprintln!("Fond menu introuvable (fond1.png), fallback procedural actif.");
+    }
+    set_floor_tile_textures(floor_texture, floor_metal_texture);
+    set_model_world_textures(
+        floor_wood_texture,
+        wall_stone_texture,
+        tree_oak_texture,
+        tree_poplar_texture,
+        tree_pine_texture,
+    );
+    set_pot_de_fleur_texture(pot_de_fleur_texture);
+    set_storage_raw_texture(initial_raw_material_texture.clone());
+    set_initial_raw_material_texture(initial_raw_material_texture);
+    set_broken_garlic_crate_texture(broken_garlic_crate_texture);
+    set_box_carton_vide_texture(box_carton_vide_texture);
+    set_box_sac_bleu_texture(box_sac_bleu_texture);
+    set_box_sac_rouge_texture(box_sac_rouge_texture);
+    set_box_sac_vert_texture(box_sac_vert_texture);
+    set_palette_logistique_texture(palette_logistique_texture);
+    set_bureau_pc_on_texture(bureau_pc_on_texture);
+    set_bureau_pc_off_texture(bureau_pc_off_texture);
+    set_lavabo_texture(lavabo_texture);
+    set_main_menu_background_texture(main_menu_background_texture);
+
+    let mut map = match load_map_asset_with_report(MAP_FILE_PATH) {
+        Ok((loaded, report)) => {
+            if report.changed
+                && let Err(err) = save_map_asset(MAP_FILE_PATH, &loaded)
+            {
+                eprintln!("Impossible de sauvegarder la carte migree ({MAP_FILE_PATH}): {err}");
+            }
+            loaded
+        }
+        Err(_) => {
+            let default_map = MapAsset::new_default();
+            if let Err(err) = save_map_asset(MAP_FILE_PATH, &default_map) {
+                eprintln!("Impossible de sauvegarder la carte par defaut ({MAP_FILE_PATH}): {err}");
+            }
+            default_map
+        }
+    };
+    sanitize_map_asset(&mut map);
+
+    let character_catalog =
+        CharacterCatalog::load_default().expect("default character catalog should be valid");
+    let mut lineage_seed = 0x51A7_2026_D00D_F00D;
+    let mut game_state = build_game_state_from_map(&map, &character_catalog, lineage_seed);
+    let mut editor_state = EditorState::new();
+    let mut main_menu_state = MainMenuState::new();
+    refresh_main_menu_saves(&mut main_menu_state);
+    let mut mode = if should_autostart_play_from_env() {
+        AppMode::Playing
+    } else {
+        AppMode::MainMenu
+    };
+    let mut accumulator = 0.0;
+    let mut is_fullscreen_mode = false;
+    let mut capture_frame_path = capture_frame_path_from_env();
+    let capture_after_frames = capture_after_frames_from_env();
+    let capture_exit = should_exit_after_capture_from_env();
+    let mut rendered_frames = 0_u64;
+
+    loop {
+        if handle_fullscreen_hotkey(&mut is_fullscreen_mode) {
+            editor_set_status(
+                &mut editor_state,
+                if is_fullscreen_mode {
+                    "Plein ecran active"
+                } else {
+                    "Plein ecran desactive"
+                },
+            );
+        }
+
+        let frame_dt = get_frame_time().min(0.25);
+        let time = get_time() as f32;
+        let mut should_quit = false;
+
+        let previous_mode = mode;
+        mode = match mode {
+            AppMode::MainMenu => {
+                match menu_principal::run_main_menu_frame(
+                    &map,
+                    &palette,
+                    time,
+                    frame_dt,
+                    &mut main_menu_state,
+                ) {
+                    MainMenuAction::None => AppMode::MainMenu,
+                    MainMenuAction::StartNewGame => {
+                        map = MapAsset::new_default();
+                        sanitize_map_asset(&mut map);
+                        lineage_seed = advance_seed(lineage_seed);
+                        game_state =
+                            build_game_state_from_map(&map, &character_catalog, lineage_seed);
+                        accumulator = 0.0;
+                        main_menu_state.view = MainMenuView::Principal;
+                        AppMode::Playing
+                    }
+                    MainMenuAction::OpenEditor => AppMode::Editor,
+                    MainMenuAction::Quit => {
+                        should_quit = true;
+                        AppMode::MainMenu
+                    }
+                    MainMenuAction::StartFromSave(file_name) => {
+                        match charger_sauvegarde_complete(&file_name) {
+                            Ok(mut loaded) => {
+                                sanitize_map_asset(&mut loaded.map);
+                                map = loaded.map;
+                                lineage_seed = advance_seed(lineage_seed);
+                                game_state = build_game_state_from_map_with_sim(
+                                    &map,
+                                    loaded.sim,
+                                    &character_catalog,
+                                    lineage_seed,
+                                );
+                                accumulator = 0.0;
+                                main_menu_state.view = MainMenuView::Principal;
+                                AppMode::Playing
+                            }
+                            Err(err) => {
+                                set_main_menu_status(
+                                    &mut main_menu_state,
+                                    format!("Chargement echoue: {err}"),
+                                );
+                                refresh_main_menu_saves(&mut main_menu_state);
+                                AppMode::MainMenu
+                            }
+                        }
+                    }
+                }
+            }
+            AppMode::Playing => match run_play_frame(&mut game_state, frame_dt, &mut accumulator) {
+                PlayAction::None => AppMode::Playing,
+                PlayAction::OpenEditor => {
+                    map.world = game_state.world.clone();
+                    map.props = game_state.props.clone();
+                    map.player_spawn =
+                        tile_from_world_clamped(&game_state.world, game_state.player.pos);
+                    map.npc_spawn = tile_from_world_clamped(&game_state.world, game_state.npc.pos);
+                    sanitize_map_asset(&mut map);
+                    AppMode::Editor
+                }
+            },
+            AppMode::Editor => {
+                match run_editor_frame(&mut editor_state, &mut map, &palette, time) {
+                    EditorAction::None => AppMode::Editor,
+                    EditorAction::BackToMenu => {
+                        refresh_main_menu_saves(&mut main_menu_state);
+                        main_menu_state.view = MainMenuView::Principal;
+                        AppMode::MainMenu
+                    }
+                    EditorAction::StartPlay => {
+                        lineage_seed = advance_seed(lineage_seed);
+                        game_state =
+                            build_game_state_from_map(&map, &character_catalog, lineage_seed);
+                        accumulator = 0.0;
+                        AppMode::Playing
+                    }
+                }
+            }
+        };
+
+        if mode != previous_mode {
+            // Defensive reset to avoid carrying any transient render state across mode switches.
+            begin_ui_pass();
+        }
+
+        if should_quit {
+            break;
+        }
+
+        if is_key_pressed(KeyCode::F12) {
+            sanitize_map_asset(&mut map);
+            let status =
+                autosave_status_message(MAP_FILE_PATH, save_map_asset(MAP_FILE_PATH, &map));
+            editor_set_status(&mut editor_state, status);
+        }
+
+        if capture_frame_path.is_some() && rendered_frames >= capture_after_frames {
+            if let Some(path) = capture_frame_path.take() {
+                // Là je capture le framebuffer complet pour les vérifications visuelles automatisées.
+                get_screen_data().export_png(&path);
+            }
+            if capture_exit {
+                std::process::exit(0);
+            }
+        }
+
+        rendered_frames = rendered_frames.saturating_add(1);
+        next_frame().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn autosave_status_message_reports_success() {
+        let msg = autosave_status_message("maps/main_map.ron", Ok(()));
+        assert!(msg.contains("Sauvegarde auto: maps/main_map.ron"));
+    }
+
+    #[test]
+    fn autosave_status_message_reports_failure_reason() {
+        let msg = autosave_status_message("maps/main_map.ron", Err("disk full".to_string()));
+        assert!(msg.contains("Sauvegarde auto echouee"));
+        assert!(msg.contains("maps/main_map.ron"));
+        assert!(msg.contains("disk full"));
+    }
+
+    #[test]
+    fn tile_hash_is_stable_and_varied() {
+        assert_eq!(tile_hash(3, 7), tile_hash(3, 7));
+        assert_ne!(tile_hash(3, 7), tile_hash(4, 7));
+        assert_ne!(tile_hash(3, 7), tile_hash(3, 8));
+    }
+
+    #[test]
+    fn bool_env_flag_parser_accepts_only_explicit_truthy_values() {
+        assert!(parse_bool_env_flag("1"));
+        assert!(parse_bool_env_flag(" true "));
+        assert!(parse_bool_env_flag("OUI"));
+        assert!(parse_bool_env_flag("yes"));
+        assert!(parse_bool_env_flag("on"));
+        assert!(!parse_bool_env_flag(""));
+        assert!(!parse_bool_env_flag("0"));
+        assert!(!parse_bool_env_flag("false"));
+        assert!(!parse_bool_env_flag("non"));
+    }
+
+    #[test]
+    fn capture_after_frames_parser_keeps_safe_bounds() {
+        assert_eq!(parse_capture_after_frames("0"), 0);
+        assert_eq!(parse_capture_after_frames("12"), 12);
+        assert_eq!(parse_capture_after_frames("601"), 6);
+        assert_eq!(parse_capture_after_frames("abc"), 6);
+    }
+
+    #[test]
+    fn floor_texture_candidates_are_stable_and_cover_expected_paths() {
+        assert_eq!(FLOOR_TEXTURE_CANDIDATES.len(), 5);
+        assert!(FLOOR_TEXTURE_CANDIDATES.contains(&"textures/herbe_sol.png"));
+        assert!(FLOOR_TEXTURE_CANDIDATES.contains(&"textures/model/grass_tile_0.png"));
+        assert!(FLOOR_TEXTURE_CANDIDATES.contains(&"textures/model/grass_tile_1.png"));
+        assert!(FLOOR_TEXTURE_CANDIDATES.contains(&"textures/sol1.png"));
+        assert!(FLOOR_TEXTURE_CANDIDATES.contains(&"Textures/sol1.png"));
+        assert_eq!(FLOOR_METAL_TEXTURE_CANDIDATES.len(), 3);
+        assert!(FLOOR_METAL_TEXTURE_CANDIDATES.contains(&"textures/model/concrete_grid_tile.png"));
+        assert!(FLOOR_METAL_TEXTURE_CANDIDATES.contains(&"textures/sol2.png"));
+        assert!(FLOOR_METAL_TEXTURE_CANDIDATES.contains(&"Textures/sol2.png"));
+        assert_eq!(
+            FLOOR_WOOD_TEXTURE_CANDIDATES,
+            ["textures/model/wood_parquet_tile.png"]
+        );
+        assert_eq!(
+            WALL_STONE_TEXTURE_CANDIDATES,
+            ["textures/model/stone_wall_tile.png"]
+        );
+        assert_eq!(TREE_OAK_TEXTURE_CANDIDATES, ["textures/model/tree_oak.png"]);
+        assert_eq!(
+            TREE_POPLAR_TEXTURE_CANDIDATES,
+            ["textures/model/tree_poplar.png"]
+        );
+        assert_eq!(
+            TREE_PINE_TEXTURE_CANDIDATES,
+            ["textures/model/tree_pine.png"]
+        );
+        assert_eq!(POT_DE_FLEUR_TEXTURE_CANDIDATES.len(), 2);
+        assert!(POT_DE_FLEUR_TEXTURE_CANDIDATES.contains(&"textures/pot-fleur.png"));
+        assert!(POT_DE_FLEUR_TEXTURE_CANDIDATES.contains(&"Textures/pot-fleur.png"));
+        assert_eq!(INITIAL_RAW_MATERIAL_TEXTURE_CANDIDATES.len(), 2);
+        assert!(INITIAL_RAW_MATERIAL_TEXTURE_CANDIDATES.contains(&"textures/caisseboisail1.png"));
+        assert!(INITIAL_RAW_MATERIAL_TEXTURE_CANDIDATES.contains(&"Textures/caisseboisail1.png"));
+        assert!(BROKEN_GARLIC_CRATE_TEXTURE_CANDIDATES.contains(&"textures/caisseail1.png"));
+        assert!(BOX_CARTON_VIDE_TEXTURE_CANDIDATES.contains(&"textures/boxcartonvide.png"));
+        assert!(BOX_SAC_BLEU_TEXTURE_CANDIDATES.contains(&"textures/boxsacbleu1.png"));
+        assert!(BOX_SAC_ROUGE_TEXTURE_CANDIDATES.contains(&"textures/boxsacrouge1.png"));
+        assert!(BOX_SAC_VERT_TEXTURE_CANDIDATES.contains(&"textures/boxsacvert1.png"));
+        assert!(PALETTE_LOGISTIQUE_TEXTURE_CANDIDATES.contains(&"textures/palette1.png"));
+        assert!(BUREAU_PC_ON_TEXTURE_CANDIDATES.contains(&"textures/bureaupc1.png"));
+        assert!(BUREAU_PC_OFF_TEXTURE_CANDIDATES.contains(&"textures/bureaupc2.png"));
+        assert!(LAVABO_TEXTURE_CANDIDATES.contains(&"textures/lavabo1.png"));
+        assert_eq!(MAIN_MENU_BACKGROUND_TEXTURE_CANDIDATES.len(), 2);
+        assert!(MAIN_MENU_BACKGROUND_TEXTURE_CANDIDATES.contains(&"fond1.png"));
+        assert!(MAIN_MENU_BACKGROUND_TEXTURE_CANDIDATES.contains(&"Fond1.png"));
+    }
+
+    #[test]
+    fn plant_is_exposed_as_pot_de_fleur_in_editor_labels() {
+        assert_eq!(prop_kind_label(PropKind::Plant), "pot de fleur");
+        assert_eq!(editor_brush_label(EditorBrush::Plant), "Pot de fleur");
+    }
+
+    #[test]
+    fn logistic_props_use_expected_editor_labels() {
+        assert_eq!(prop_kind_label(PropKind::BureauPcOn), "bureau PC ON");
+        assert_eq!(prop_kind_label(PropKind::BureauPcOff), "bureau PC OFF");
+        assert_eq!(prop_kind_label(PropKind::Lavabo), "lavabo");
+        assert_eq!(
+            prop_kind_label(PropKind::CaisseAilCasse),
+            "caisse d'ail cassé"
+        );
+        assert_eq!(
+            editor_brush_label(EditorBrush::CaisseAilCasse),
+            "Caisse d'ail cassé"
+        );
+        assert_eq!(editor_brush_label(EditorBrush::BureauPcOn), "Bureau PC ON");
+        assert_eq!(
+            editor_brush_label(EditorBrush::BureauPcOff),
+            "Bureau PC OFF"
+        );
+        assert_eq!(editor_brush_label(EditorBrush::Lavabo), "Lavabo");
+    }
+
+    #[test]
+    fn room_has_closed_wall_border() {
+        let world = World::new_room(10, 6);
+
+        for x in 0..10 {
+            assert_eq!(world.get(x, 0), Tile::Wall);
+            assert_eq!(world.get(x, 5), Tile::Wall);
+        }
+        for y in 0..6 {
+            assert_eq!(world.get(0, y), Tile::Wall);
+            assert_eq!(world.get(9, y), Tile::Wall);
+        }
+    }
+
+    #[test]
+    fn world_revision_changes_only_when_tile_changes() {
+        let mut world = World {
+            w: 3,
+            h: 3,
+            tiles: vec![Tile::Floor; 9],
+            revision: 0,
+        };
+
+        world.set(1, 1, Tile::Floor);
+        assert_eq!(world.revision, 0);
+
+        world.set(1, 1, Tile::Wall);
+        assert_eq!(world.revision, 1);
+
+        world.set(1, 1, Tile::Wall);
+        assert_eq!(world.revision, 1);
+    }
+
+    #[test]
+    fn wall_mask_bits_match_neighbors() {
+        let mut world = World {
+            w: 3,
+            h: 3,
+            tiles: vec![Tile::Floor; 9],
+            revision: 0,
+        };
+        world.set(1, 1, Tile::Wall);
+        world.set(1, 0, Tile::Wall);
+        world.set(2, 1, Tile::Wall);
+        world.set(1, 2, Tile::Wall);
+
+        let mask = wall_mask_4(&world, 1, 1);
+        assert_eq!(mask, MASK_N | MASK_E | MASK_S);
+    }
+
+    #[test]
+    fn facing_selection_prefers_vertical_directions() {
+        assert_eq!(
+            select_character_facing(vec2(0.1, 1.0), CharacterFacing::Side),
+            CharacterFacing::Front
+        );
+        assert_eq!(
+            select_character_facing(vec2(-0.2, -1.0), CharacterFacing::Side),
+            CharacterFacing::Back
+        );
+    }
+
+    #[test]
+    fn facing_selection_prefers_side_for_horizontal_motion() {
+        assert_eq!(
+            select_character_facing(vec2(1.0, 0.1), CharacterFacing::Front),
+            CharacterFacing::Side
+        );
+        assert_eq!(
+            select_character_facing(vec2(-1.0, -0.05), CharacterFacing::Back),
+            CharacterFacing::Side
+        );
+    }
+
+    #[test]
+    fn facing_selection_uses_hysteresis_on_diagonals() {
+        assert_eq!(
+            select_character_facing(vec2(0.7, 0.7), CharacterFacing::Side),
+            CharacterFacing::Side
+        );
+        assert_eq!(
+            select_character_facing(vec2(0.7, 0.7), CharacterFacing::Front),
+            CharacterFacing::Front
+        );
+        assert_eq!(
+            select_character_facing(vec2(0.0, 0.0), CharacterFacing::Back),
+            CharacterFacing::Back
+        );
+    }
+
+    #[test]
+    fn nearest_walkable_tile_finds_floor_from_blocked_click() {
+        let world = World::new_room(12, 8);
+        let found = nearest_walkable_tile(&world, (0, 0)).expect("should find floor");
+        assert!(!world.is_solid(found.0, found.1));
+    }
+
+    #[test]
+    fn a_star_finds_path_around_internal_walls() {
+        let world = World::new_room(25, 15);
+        let path = a_star_path(&world, (2, 2), (22, 12)).expect("path should exist");
+        assert!(path.len() > 2);
+        assert_eq!(path.first().copied(), Some((2, 2)));
+        assert_eq!(path.last().copied(), Some((22, 12)));
+        assert!(path.iter().all(|&(x, y)| !world.is_solid(x, y)));
+    }
+
+    #[test]
+    fn click_command_enables_auto_move_and_keyboard_cancels_it() {
+        let world = World::new_room(25, 15);
+        let mut player = Player::new(tile_center((2, 2)));
+
+        let moved = issue_auto_move_command(&mut player, &world, (20, 10));
+        assert!(moved);
+        assert_eq!(player.control_mode, ControlMode::AutoMove);
+        assert!(!player.auto.path_world.is_empty());
+
+        apply_control_inputs(&mut player, &world, vec2(1.0, 0.0), None);
+        assert_eq!(player.control_mode, ControlMode::Manual);
+        assert!(player.auto.path_world.is_empty());
+        assert!(player.auto.path_tiles.is_empty());
+    }
+
+    #[test]
+    fn auto_move_progresses_along_path() {
+        let world = World::new_room(25, 15);
+        let mut player = Player::new(tile_center((2, 2)));
+        let _ = issue_auto_move_command(&mut player, &world, (20, 10));
+        let before = player.pos;
+
+        for _ in 0..120 {
+            update_player(&mut player, &world, Vec2::ZERO, FIXED_DT);
+        }
+
+        assert!(player.pos.distance(before) > TILE_SIZE);
+    }
+
+    #[test]
+    fn game_state_initializes_forklift_on_walkable_tile() {
+        let map = MapAsset::new_default();
+        let catalog = CharacterCatalog::load_default().expect("default catalog should parse");
+        let state = build_game_state_from_map(&map, &catalog, 0xABCD_1234_5678_90EF);
+
+        let tile = tile_from_world_clamped(&state.world, state.chariot.pos);
+        assert!(state.world.in_bounds(tile.0, tile.1));
+        assert!(!state.world.is_solid(tile.0, tile.1));
+        assert!(!state.chariot.pilote_a_bord);
+        assert!(state.chariot.caisse_chargee.is_none());
+    }
+
+    #[test]
+    fn npc_wander_command_creates_path() {
+        let world = World::new_room(25, 15);
+        let mut npc = NpcWanderer::new(tile_center((4, 4)), 77);
+        let ok = issue_npc_wander_command(&mut npc, &world, (20, 10));
+        assert!(ok);
+        assert!(!npc.auto.path_world.is_empty());
+        assert!(npc.auto.target_tile.is_some());
+    }
+
+    #[test]
+    fn social_proximity_greeting_creates_emote() {
+        let world = World::new_room(25, 15);
+        let sim = sim::FactorySim::new(sim::StarterSimConfig::default(), 25, 15);
+        let mut player = Player::new(tile_center((6, 6)));
+        let mut npc = NpcWanderer::new(tile_center((6, 6)), 99);
+
+        let mut pawns = vec![
+            PawnCard {
+                key: PawnKey::Player,
+                name: "Player".to_string(),
+                role: "Test".to_string(),
+                metrics: PawnMetrics::seeded(1),
+                history: crate::historique::HistoriqueLog::new(64),
+            },
+            PawnCard {
+                key: PawnKey::Npc,
+                name: "NPC".to_string(),
+                role: "Test".to_string(),
+                metrics: PawnMetrics::seeded(2),
+                history: crate::historique::HistoriqueLog::new(64),
+            },
+            PawnCard {
+                key: PawnKey::SimWorker,
+                name: "Worker".to_string(),
+                role: "Test".to_string(),
+                metrics: PawnMetrics::seeded(3),
+                history: crate::historique::HistoriqueLog::new(64),
+            },
+        ];
+        let mut social_state = social::SocialState::new(&pawns, 0x1234);
+
+        social_state.tick(
+            0.25,
+            0.0,
+            social::SocialTickContext {
+                world: &world,
+                sim: &sim,
+            },
+            social::SocialTickActors {
+                player: &mut player,
+                npc: &mut npc,
+                pawns: &mut pawns,
+            },
+        );
+
+        let view = social_state.emote_view(PawnKey::Npc);
+        assert!(view.is_some());
+        assert_eq!(
+            view.and_then(|entry| entry.kind),
+            Some(interactions::SocialActionKind::DireBonjour)
+        );
+    }
+
+    #[test]
+    fn npc_wanderer_leaves_idle_and_moves_over_time() {
+        let world = World::new_room(25, 15);
+        let mut npc = NpcWanderer::new(tile_center((6, 6)), 1234);
+        let start = npc.pos;
+        npc.idle_timer = 0.0;
+
+        let mut had_path = false;
+        let mut walked = false;
+
+        for _ in 0..360 {
+            update_npc_wanderer(&mut npc, &world, FIXED_DT);
+            had_path |= !npc.auto.path_world.is_empty();
+            walked |= npc.is_walking;
+        }
+
+        assert!(had_path, "npc should pick at least one wander path");
+        assert!(walked, "npc should enter walking state");
+        assert!(
+            npc.pos.distance(start) > TILE_SIZE * 0.25,
+            "npc should move away from spawn point"
+        );
+    }
+
+    #[test]
+    fn map_asset_roundtrip_serialization_preserves_content() {
+        let mut map = MapAsset::new_default();
+        assert!(set_map_tile(&mut map, (3, 3), Tile::WallBrick));
+        assert!(set_prop_at_tile(&mut map, (4, 4), PropKind::Crystal));
+        map.player_spawn = (2, 2);
+        map.npc_spawn = (7, 7);
+
+        let encoded = serialize_map_asset(&map).expect("map should serialize");
+        let decoded = deserialize_map_asset(&encoded).expect("map should deserialize");
+
+        assert_eq!(decoded.world.w, map.world.w);
+        assert_eq!(decoded.world.h, map.world.h);
+        assert_eq!(decoded.world.get(3, 3), Tile::WallBrick);
+        assert!(prop_index_at_tile(&decoded.props, (4, 4)).is_some());
+        assert!(
+            !decoded
+                .world
+                .is_solid(decoded.player_spawn.0, decoded.player_spawn.1)
+        );
+        assert!(
+            !decoded
+                .world
+                .is_solid(decoded.npc_spawn.0, decoded.npc_spawn.1)
+        );
+    }
+
+    #[test]
+    fn sanitize_map_enforces_border_and_removes_invalid_props() {
+        let mut map = MapAsset::new_default();
+        map.world.set(0, 3, Tile::Floor);
+        map.world.set(5, 0, Tile::Floor);
+        map.player_spawn = (0, 0);
+        map.npc_spawn = (0, 0);
+        map.props.push(Prop {
+            tile_x: 0,
+            tile_y: 0,
+            kind: PropKind::Crate,
+            phase: 0.0,
+            rotation_quarter: 0,
+        });
+
+        sanitize_map_asset(&mut map);
+
+        assert_eq!(map.world.get(0, 3), Tile::Wall);
+        assert_eq!(map.world.get(5, 0), Tile::Wall);
+        assert!(prop_index_at_tile(&map.props, (0, 0)).is_none());
+        assert!(!map.world.is_solid(map.player_spawn.0, map.player_spawn.1));
+        assert!(!map.world.is_solid(map.npc_spawn.0, map.npc_spawn.1));
+    }
+
+    #[test]
+    fn sanitize_upgrades_legacy_small_map_to_starter_factory_layout() {
+        let mut map = MapAsset {
+            schema_version: 1,
+            version: 1,
+            label: "Legacy".to_string(),
+            world: World::new_room(25, 15),
+            props: Vec::new(),
+            zones: Vec::new(),
+            player_spawn: (2, 2),
+            npc_spawn: (20, 10),
+        };
+
+        sanitize_map_asset(&mut map);
+
+        assert_eq!(map.version, MAP_FILE_VERSION);
+        assert_eq!(map.world.w, MAP_W);
+        assert_eq!(map.world.h, MAP_H);
+        assert_eq!(map.label, "Usine de depart");
+    }
+
+    #[test]
+    fn editor_brush_can_paint_floor_and_wall() {
+        let mut map = MapAsset::new_default();
+
+        assert!(editor_apply_brush(&mut map, EditorBrush::WallSteel, (6, 6)));
+        assert_eq!(map.world.get(6, 6), Tile::WallSteel);
+        assert!(editor_apply_brush(&mut map, EditorBrush::FloorMoss, (6, 6)));
+        assert_eq!(map.world.get(6, 6), Tile::FloorMoss);
+    }
+
+    #[test]
+    fn editor_brush_can_place_and_remove_props() {
+        let mut map = MapAsset::new_default();
+        let tile = (6, 6);
+
+        assert!(editor_apply_brush(&mut map, EditorBrush::Crystal, tile));
+        assert!(prop_index_at_tile(&map.props, tile).is_some());
+        assert!(editor_apply_brush(&mut map, EditorBrush::EraseProp, tile));
+        assert!(prop_index_at_tile(&map.props, tile).is_none());
+    }
+
+    #[test]
+    fn all_new_wall_variants_are_solid() {
+        let mut world = World::new_room(8, 8);
+        world.set(3, 3, Tile::WallBrick);
+        world.set(4, 3, Tile::WallSteel);
+        world.set(5, 3, Tile::WallNeon);
+
+        assert!(world.is_solid(3, 3));
+        assert!(world.is_solid(4, 3));
+        assert!(world.is_solid(5, 3));
+        assert!(!world.is_solid(2, 2));
+    }
+
+    #[test]
+    fn editor_undo_redo_restores_map_state() {
+        let mut map = MapAsset::new_default();
+        let mut editor = EditorState::new();
+        let tile = (7, 7);
+        let initial = map.world.get(tile.0, tile.1);
+
+        editor_push_undo(&mut editor, &map);
+        assert!(editor_apply_brush(&mut map, EditorBrush::WallNeon, tile));
+        assert_eq!(map.world.get(tile.0, tile.1), Tile::WallNeon);
+
+        assert!(editor_undo(&mut editor, &mut map));
+        assert_eq!(map.world.get(tile.0, tile.1), initial);
+
+        assert!(editor_redo(&mut editor, &mut map));
+        assert_eq!(map.world.get(tile.0, tile.1), Tile::WallNeon);
+    }
+}
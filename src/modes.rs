@@ -1677,13 +1677,28 @@ pub(crate) fn run_play_frame(
             (*accumulator + frame_dt * sim_factor).min(FIXED_DT * MAX_SIM_STEPS_PER_FRAME as f32);
     }
     let mut sim_steps = 0usize;
+    // Les lavabos poses a l'editeur servent de lieu de pause au personnel: la
+    // commande n'est emise (et journalisee) que quand la liste change.
+    let mut lavabos = state
+        .props
+        .iter()
+        .filter(|prop| prop.kind == PropKind::Lavabo)
+        .map(|prop| (prop.tile_x, prop.tile_y))
+        .collect::<Vec<_>>();
+    lavabos.sort_unstable();
+    lavabos.dedup();
+    if lavabos.as_slice() != state.sim.lavabo_tiles() {
+        let _ = state
+            .sim
+            .apply_command(crate::gestion::SimCommand::SetLavaboTiles { tiles: lavabos });
+    }
     let fork_input = if state.chariot.pilote_a_bord {
         read_chariot_fork_input()
     } else {
         0.0
     };
     while *accumulator >= FIXED_DT && sim_steps < MAX_SIM_STEPS_PER_FRAME {
-        state.sim.step(FIXED_DT);
+        state.sim.step_in_world(FIXED_DT, Some(&state.world));
         let drive_input = if state.chariot.pilote_a_bord {
             state.last_input
         } else {
@@ -1,5 +1,6 @@
 use super::etat::PapaPnjEtat;
 use super::plan::PapaPlanAsset;
+use crate::gestion::SimCommand;
 use macroquad::prelude::*;
 
 fn bloc_center_world(tile: (i32, i32), footprint: (i32, i32)) -> Vec2 {
@@ -62,8 +63,17 @@ pub fn tick_construction(
 
     let tile = (pnj.ancre.0 + etape.offset.0, pnj.ancre.1 + etape.offset.1);
 
-    match sim.poser_bloc_script(world, etape.kind, tile, etape.orientation, false) {
-        Ok(_id) => {
+    let pose = sim.apply_scripted_build(
+        SimCommand::PlaceBlock {
+            kind: etape.kind,
+            tile,
+            orientation: etape.orientation,
+        },
+        Some(world),
+    );
+    match pose {
+        Ok(status) => {
+            sim.set_status_line(status);
             let footprint = etape.kind.footprint_for_orientation(etape.orientation);
 
             pnj.pos = bloc_center_world(tile, footprint);
@@ -113,7 +123,7 @@ fn preparer_chantier(
     world: &mut crate::World,
     sim: &mut crate::sim::FactorySim,
 ) -> Result<(usize, usize), String> {
-    let sols = preparer_sols_chantier(pnj, plan, world)?;
+    let sols = preparer_sols_chantier(pnj, plan, world, sim)?;
     let zones = preparer_zones_chantier(pnj, plan, sim)?;
 
     Ok((sols, zones))
@@ -123,6 +133,7 @@ fn preparer_sols_chantier(
     pnj: &PapaPnjEtat,
     plan: &PapaPlanAsset,
     world: &mut crate::World,
+    sim: &mut crate::sim::FactorySim,
 ) -> Result<usize, String> {
     let mut count = 0usize;
 
@@ -153,19 +164,16 @@ fn preparer_sols_chantier(
     }
 
     for sol in &plan.sols {
-        let tile_kind = sol.kind.to_tile();
-
-        for dy in 0..sol.size.1 {
-            for dx in 0..sol.size.0 {
-                let tile = (
-                    pnj.ancre.0 + sol.offset.0 + dx,
-                    pnj.ancre.1 + sol.offset.1 + dy,
-                );
-
-                world.set(tile.0, tile.1, tile_kind);
-                count += 1;
-            }
-        }
+        let origin = (pnj.ancre.0 + sol.offset.0, pnj.ancre.1 + sol.offset.1);
+        sim.apply_scripted_build(
+            SimCommand::PaintFloorRect {
+                origin,
+                size: sol.size,
+                floor: sol.kind.to_floor_kind(),
+            },
+            Some(world),
+        )?;
+        count += (sol.size.0 * sol.size.1) as usize;
     }
 
     Ok(count)
@@ -181,7 +189,15 @@ fn preparer_zones_chantier(
     for zone in &plan.zones {
         let origin = (pnj.ancre.0 + zone.offset.0, pnj.ancre.1 + zone.offset.1);
 
-        count += sim.paint_zone_rect_script(origin, zone.size, zone.kind)?;
+        sim.apply_scripted_build(
+            SimCommand::PaintZoneRect {
+                origin,
+                size: zone.size,
+                zone: zone.kind,
+            },
+            None,
+        )?;
+        count += (zone.size.0 * zone.size.1) as usize;
     }
 
     Ok(count)
@@ -1,3 +1,4 @@
+use crate::gestion::SimCommand;
 use crate::sim;
 use ron::de::from_str as ron_from_str;
 use serde::Deserialize;
@@ -46,11 +47,11 @@ pub enum PapaPlanSolKind {
 }
 
 impl PapaPlanSolKind {
-    pub fn to_tile(self) -> crate::Tile {
+    pub fn to_floor_kind(self) -> sim::BuildFloorKind {
         match self {
-            Self::FloorMetal => crate::Tile::FloorMetal,
-            Self::FloorWood => crate::Tile::FloorWood,
-            Self::Floor => crate::Tile::Floor,
+            Self::FloorMetal => sim::BuildFloorKind::Metal,
+            Self::FloorWood => sim::BuildFloorKind::Bois,
+            Self::Floor => sim::BuildFloorKind::Standard,
         }
     }
 }
@@ -229,32 +230,43 @@ impl PapaPlanAsset {
         let anchor_y = 30 - min_y;
 
         for sol in &self.sols {
-            let tile_kind = sol.kind.to_tile();
-            for dy in 0..sol.size.1 {
-                for dx in 0..sol.size.0 {
-                    let tile = (anchor_x + sol.offset.0 + dx, anchor_y + sol.offset.1 + dy);
-                    if !world.in_bounds(tile.0, tile.1) || world.is_solid(tile.0, tile.1) {
-                        return Err(format!(
-                            "plan Papa invalide: dalle impossible en {:?}",
-                            tile
-                        ));
-                    }
-                    world.set(tile.0, tile.1, tile_kind);
-                }
-            }
+            let origin = (anchor_x + sol.offset.0, anchor_y + sol.offset.1);
+            sim.apply_scripted_build(
+                SimCommand::PaintFloorRect {
+                    origin,
+                    size: sol.size,
+                    floor: sol.kind.to_floor_kind(),
+                },
+                Some(&mut world),
+            )
+            .map_err(|err| format!("plan Papa invalide: dalle impossible ({err})"))?;
         }
 
         for zone in &self.zones {
             let origin = (anchor_x + zone.offset.0, anchor_y + zone.offset.1);
 
-            sim.paint_zone_rect_script(origin, zone.size, zone.kind)
-                .map_err(|err| format!("plan Papa invalide: zone impossible ({err})"))?;
+            sim.apply_scripted_build(
+                SimCommand::PaintZoneRect {
+                    origin,
+                    size: zone.size,
+                    zone: zone.kind,
+                },
+                None,
+            )
+            .map_err(|err| format!("plan Papa invalide: zone impossible ({err})"))?;
         }
 
         for bloc in &self.blocs {
             let tile = (anchor_x + bloc.offset.0, anchor_y + bloc.offset.1);
-            sim.poser_bloc_script(&world, bloc.kind, tile, bloc.orientation, false)
-                .map_err(|err| format!("plan Papa invalide: pose impossible ({err})"))?;
+            sim.apply_scripted_build(
+                SimCommand::PlaceBlock {
+                    kind: bloc.kind,
+                    tile,
+                    orientation: bloc.orientation,
+                },
+                Some(&mut world),
+            )
+            .map_err(|err| format!("plan Papa invalide: pose impossible ({err})"))?;
         }
 
         if !sim.modern_line_ready() {
@@ -323,7 +335,10 @@ mod tests {
         let plan: PapaPlanAsset = ron_from_str(payload).expect("slab plan should deserialize");
         assert_eq!(plan.sols.len(), 1);
         assert_eq!(plan.sols[0].kind, PapaPlanSolKind::FloorMetal);
-        assert_eq!(plan.sols[0].kind.to_tile(), crate::Tile::FloorMetal);
+        assert_eq!(
+            plan.sols[0].kind.to_floor_kind().to_tile(),
+            crate::Tile::FloorMetal
+        );
     }
 
     #[test]
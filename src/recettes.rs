@@ -0,0 +1,394 @@
+use crate::gestion::{QualityGrade, RonBook};
+use crate::sim::{BlockKind, ItemKind, ZoneKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const RECIPE_BOOK_PATH: &str = "data/recipes.ron";
+const RECIPE_BOOK_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ItemQty {
+    pub item: ItemKind,
+    pub qty: u32,
+}
+
+impl ItemQty {
+    pub const fn new(item: ItemKind, qty: u32) -> Self {
+        Self { item, qty }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RecipeStage {
+    pub id: String,
+    pub label: String,
+    pub block_kind: BlockKind,
+    pub inputs: Vec<ItemQty>,
+    pub outputs: Vec<ItemQty>,
+    pub cycle_s: f64,
+    #[serde(default = "default_yield_ratio")]
+    pub yield_ratio: f64,
+    // Sortie de remplacement quand le cycle tombe dans la part non conforme.
+    #[serde(default)]
+    pub reject: Option<ItemQty>,
+    // Tri qualite: les unites sous ce grade partent en reject, yield_ratio ignore.
+    #[serde(default)]
+    pub sort_min_grade: Option<QualityGrade>,
+    #[serde(default)]
+    pub counts_as_wip: bool,
+    #[serde(default)]
+    pub kpi_zone: Option<ZoneKind>,
+    // Cuisson: un cycle hors consigne (ou un bloc use) degrade la qualite.
+    #[serde(default)]
+    pub cycle_accuracy_defect: bool,
+}
+
+fn default_yield_ratio() -> f64 {
+    1.0
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PackagingSpec {
+    pub good_item: ItemKind,
+    pub reject_item: ItemKind,
+    pub units_per_bag: u32,
+    pub bags_per_box: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ProductRecipe {
+    pub id: String,
+    pub label: String,
+    pub stages: Vec<RecipeStage>,
+    pub packaging: PackagingSpec,
+}
+
+impl ProductRecipe {
+    fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("recette sans identifiant".to_string());
+        }
+        if self.stages.is_empty() {
+            return Err(format!("recette {}: aucune etape", self.id));
+        }
+
+        let mut stage_ids = HashSet::new();
+        // Une etape par bloc: deux etapes sur le meme bloc compteraient ses cycles deux fois.
+        let mut stage_blocks = HashSet::new();
+        let mut available = HashSet::from([ItemKind::Raw]);
+        for stage in &self.stages {
+            if !stage_ids.insert(stage.id.as_str()) {
+                return Err(format!("recette {}: etape {} en double", self.id, stage.id));
+            }
+            if !stage_blocks.insert(stage.block_kind) {
+                return Err(format!(
+                    "recette {}: bloc {} porte deja une etape ({})",
+                    self.id,
+                    stage.block_kind.label(),
+                    stage.id
+                ));
+            }
+            if !stage.block_kind.is_modern_line_component() {
+                return Err(format!(
+                    "recette {}: etape {} sur un bloc hors ligne moderne ({})",
+                    self.id,
+                    stage.id,
+                    stage.block_kind.label()
+                ));
+            }
+            if !stage.cycle_s.is_finite() || stage.cycle_s <= 0.0 {
+                return Err(format!(
+                    "recette {}: cycle_s de {} doit etre fini et > 0",
+                    self.id, stage.id
+                ));
+            }
+            if !stage.yield_ratio.is_finite() || stage.yield_ratio <= 0.0 || stage.yield_ratio > 1.0
+            {
+                return Err(format!(
+                    "recette {}: yield_ratio de {} doit etre dans ]0, 1]",
+                    self.id, stage.id
+                ));
+            }
+            if stage.sort_min_grade.is_some() && stage.reject.is_none() {
+                return Err(format!(
+                    "recette {}: le tri qualite de {} demande une sortie reject",
+                    self.id, stage.id
+                ));
+            }
+            if stage.inputs.is_empty() || stage.outputs.is_empty() {
+                return Err(format!(
+                    "recette {}: etape {} sans entree ou sans sortie",
+                    self.id, stage.id
+                ));
+            }
+            let quantities = stage
+                .inputs
+                .iter()
+                .chain(&stage.outputs)
+                .chain(stage.reject.as_ref());
+            for entry in quantities {
+                if entry.qty == 0 {
+                    return Err(format!(
+                        "recette {}: quantite nulle dans l'etape {}",
+                        self.id, stage.id
+                    ));
+                }
+                if entry.item == ItemKind::Finished {
+                    return Err(format!(
+                        "recette {}: l'etape {} ne peut pas manipuler le produit fini",
+                        self.id, stage.id
+                    ));
+                }
+            }
+            // Les etapes sont ordonnees: une entree doit venir d'une etape precedente.
+            if let Some(missing) = stage
+                .inputs
+                .iter()
+                .find(|input| !available.contains(&input.item))
+            {
+                return Err(format!(
+                    "recette {}: entree {:?} de {} produite par aucune etape amont",
+                    self.id, missing.item, stage.id
+                ));
+            }
+            available.extend(stage.outputs.iter().map(|output| output.item));
+            available.extend(stage.reject.iter().map(|reject| reject.item));
+        }
+
+        let packaging = &self.packaging;
+        if packaging.units_per_bag == 0 || packaging.bags_per_box == 0 {
+            return Err(format!(
+                "recette {}: conditionnement units_per_bag et bags_per_box doivent etre > 0",
+                self.id
+            ));
+        }
+        if packaging.good_item == ItemKind::Raw || !available.contains(&packaging.good_item) {
+            return Err(format!(
+                "recette {}: produit conditionne {:?} jamais produit par la ligne",
+                self.id, packaging.good_item
+            ));
+        }
+        if packaging.reject_item == packaging.good_item {
+            return Err(format!(
+                "recette {}: rebut et produit conditionne doivent differer",
+                self.id
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RecipeBook {
+    pub schema_version: u32,
+    pub active_product: String,
+    pub products: Vec<ProductRecipe>,
+}
+
+impl Default for RecipeBook {
+    fn default() -> Self {
+        Self {
+            schema_version: RECIPE_BOOK_SCHEMA_VERSION,
+            active_product: "ail".to_string(),
+            products: vec![garlic_recipe()],
+        }
+    }
+}
+
+impl RonBook for RecipeBook {
+    const LABEL: &'static str = "recettes";
+    const PRETTY_DEPTH: usize = 6;
+
+    fn validate(&self) -> Result<(), String> {
+        if self.schema_version != RECIPE_BOOK_SCHEMA_VERSION {
+            return Err(format!(
+                "schema recettes invalide: attendu={} recu={}",
+                RECIPE_BOOK_SCHEMA_VERSION, self.schema_version
+            ));
+        }
+        let mut ids = HashSet::new();
+        for product in &self.products {
+            product.validate()?;
+            if !ids.insert(product.id.as_str()) {
+                return Err(format!("recette {} en double", product.id));
+            }
+        }
+        if self.product(&self.active_product).is_none() {
+            return Err(format!("produit actif inconnu: {}", self.active_product));
+        }
+        Ok(())
+    }
+}
+
+fn stage(
+    id: &str,
+    label: &str,
+    block_kind: BlockKind,
+    input: ItemKind,
+    output: ItemKind,
+    cycle_s: f64,
+) -> RecipeStage {
+    RecipeStage {
+        id: id.to_string(),
+        label: label.to_string(),
+        block_kind,
+        inputs: vec![ItemQty::new(input, 1)],
+        outputs: vec![ItemQty::new(output, 1)],
+        cycle_s,
+        yield_ratio: 1.0,
+        reject: None,
+        sort_min_grade: None,
+        counts_as_wip: false,
+        kpi_zone: None,
+        cycle_accuracy_defect: false,
+    }
+}
+
+fn garlic_recipe() -> ProductRecipe {
+    let mut lavage = stage(
+        "lavage",
+        "Lavage",
+        BlockKind::FluidityTank,
+        ItemKind::Raw,
+        ItemKind::Washed,
+        16.0,
+    );
+    lavage.counts_as_wip = true;
+    lavage.kpi_zone = Some(ZoneKind::Processing);
+    let mut coupe = stage(
+        "coupe",
+        "Coupe",
+        BlockKind::Cutter,
+        ItemKind::Washed,
+        ItemKind::Sliced,
+        11.0,
+    );
+    coupe.counts_as_wip = true;
+    let mut four = stage(
+        "four",
+        "Four",
+        BlockKind::DryerOven,
+        ItemKind::Sliced,
+        ItemKind::Dehydrated,
+        42.0,
+    );
+    four.kpi_zone = Some(ZoneKind::Shipping);
+    four.cycle_accuracy_defect = true;
+    let floc = stage(
+        "floc",
+        "Floconneuse",
+        BlockKind::Flaker,
+        ItemKind::Dehydrated,
+        ItemKind::Flakes,
+        14.0,
+    );
+    let mut sortex = stage(
+        "sortex",
+        "Tri optique",
+        BlockKind::Sortex,
+        ItemKind::Flakes,
+        ItemKind::Sorted,
+        9.0,
+    );
+    sortex.reject = Some(ItemQty::new(ItemKind::Scrap, 1));
+    sortex.sort_min_grade = Some(QualityGrade::B);
+
+    ProductRecipe {
+        id: "ail".to_string(),
+        label: "Ail deshydrate".to_string(),
+        stages: vec![lavage, coupe, four, floc, sortex],
+        packaging: PackagingSpec {
+            good_item: ItemKind::Sorted,
+            reject_item: ItemKind::Scrap,
+            units_per_bag: 14,
+            bags_per_box: 21,
+        },
+    }
+}
+
+impl RecipeBook {
+    pub fn product(&self, id: &str) -> Option<&ProductRecipe> {
+        self.products.iter().find(|product| product.id == id)
+    }
+
+    pub fn active(&self) -> &ProductRecipe {
+        self.product(&self.active_product)
+            .or_else(|| self.products.first())
+            .expect("livre de recettes valide sans produit")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_book_is_valid_and_models_garlic_line() {
+        let book = RecipeBook::default();
+        assert!(book.validate().is_ok());
+        let ail = book.active();
+        let kinds = ail
+            .stages
+            .iter()
+            .map(|stage| stage.block_kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::FluidityTank,
+                BlockKind::Cutter,
+                BlockKind::DryerOven,
+                BlockKind::Flaker,
+                BlockKind::Sortex,
+            ]
+        );
+        assert_eq!(ail.packaging.units_per_bag, 14);
+        assert_eq!(ail.packaging.bags_per_box, 21);
+    }
+
+    #[test]
+    fn data_file_parses_and_declares_a_second_product() {
+        let book = RecipeBook::load(RECIPE_BOOK_PATH).expect("data/recipes.ron valide");
+        assert!(book.product("ail").is_some());
+        assert!(book.product("oignon").is_some());
+    }
+
+    #[test]
+    fn validate_rejects_input_without_upstream_stage() {
+        let mut book = RecipeBook::default();
+        book.products[0].stages.swap(0, 1);
+        let err = book.validate().expect_err("ordre invalide");
+        assert!(err.contains("amont"), "{err}");
+    }
+
+    #[test]
+    fn validate_rejects_two_stages_on_the_same_block() {
+        let mut book = RecipeBook::default();
+        let mut second_cut = book.products[0].stages[1].clone();
+        second_cut.id = "recoupe".to_string();
+        book.products[0].stages.insert(2, second_cut);
+        let err = book.validate().expect_err("bloc en double");
+        assert!(err.contains("porte deja une etape"), "{err}");
+    }
+
+    #[test]
+    fn validate_rejects_bad_cycle_yield_and_unknown_active_product() {
+        let mut book = RecipeBook::default();
+        book.products[0].stages[2].cycle_s = 0.0;
+        assert!(book.validate().is_err());
+
+        let mut book = RecipeBook::default();
+        book.products[0].stages[4].yield_ratio = 1.2;
+        assert!(book.validate().is_err());
+
+        let mut book = RecipeBook::default();
+        book.products[0].stages[3].sort_min_grade = Some(QualityGrade::B);
+        assert!(book.validate().is_err());
+
+        let book = RecipeBook {
+            active_product: "echalote".to_string(),
+            ..RecipeBook::default()
+        };
+        assert!(book.validate().is_err());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::World;
+use crate::gestion::SimCommand;
+use crate::sim::{FactorySim, FactorySimSaveAsset, StarterSimConfig};
+use ron::{
+    de::from_str as ron_from_str,
+    ser::{PrettyConfig, to_string_pretty as ron_to_string_pretty},
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Le journal de rejeu vit a cote de la sauvegarde: meme nom, extension propre
+// pour ne pas apparaitre dans la liste des sauvegardes.
+pub const REPLAY_FILE_EXTENSION: &str = "rejeu";
+const REPLAY_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayEvent {
+    // Les clics de construction arrivent ici deja traduits en commandes.
+    Command(SimCommand),
+    // Poses offertes des scripts (Papa), hors commandes joueur.
+    ScriptedBuild(SimCommand),
+    Bootstrap,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    // Pas de simulation deja joues quand l'evenement est applique.
+    pub tick: u64,
+    pub event: ReplayEvent,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CommandLog {
+    schema_version: u32,
+    config: StarterSimConfig,
+    initial: FactorySimSaveAsset,
+    #[serde(default)]
+    world: Option<World>,
+    #[serde(default)]
+    end_tick: u64,
+    #[serde(default)]
+    entries: Vec<ReplayEntry>,
+    // Pas ou la carte a ete retouchee hors simulation (editeur): le journal s'arrete
+    // la, le rejeu ne peut pas reproduire la suite.
+    #[serde(default)]
+    interrupted_at: Option<u64>,
+}
+
+impl CommandLog {
+    pub(crate) fn new(
+        config: StarterSimConfig,
+        initial: FactorySimSaveAsset,
+        world: Option<World>,
+    ) -> Self {
+        let end_tick = initial.tick;
+        Self {
+            schema_version: REPLAY_SCHEMA_VERSION,
+            config,
+            initial,
+            world,
+            end_tick,
+            entries: Vec::new(),
+            interrupted_at: None,
+        }
+    }
+
+    pub(crate) fn record(&mut self, tick: u64, event: ReplayEvent) {
+        if self.interrupted_at.is_some() {
+            return;
+        }
+        self.end_tick = self.end_tick.max(tick);
+        self.entries.push(ReplayEntry { tick, event });
+    }
+
+    pub(crate) fn interrupt(&mut self, tick: u64) {
+        if self.interrupted_at.is_none() {
+            self.end_tick = self.end_tick.max(tick);
+            self.interrupted_at = Some(self.end_tick);
+        }
+    }
+
+    pub(crate) fn finished_at(mut self, tick: u64) -> Self {
+        if self.interrupted_at.is_none() {
+            self.end_tick = self.end_tick.max(tick);
+        }
+        self
+    }
+
+    pub fn interrupted_at(&self) -> Option<u64> {
+        self.interrupted_at
+    }
+
+    pub fn entries(&self) -> &[ReplayEntry] {
+        &self.entries
+    }
+
+    pub fn start_tick(&self) -> u64 {
+        self.initial.tick
+    }
+
+    pub fn end_tick(&self) -> u64 {
+        self.end_tick
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.schema_version > REPLAY_SCHEMA_VERSION {
+            return Err(format!(
+                "schema rejeu futur non supporte ({} > {})",
+                self.schema_version, REPLAY_SCHEMA_VERSION
+            ));
+        }
+        let mut previous = self.start_tick();
+        for entry in &self.entries {
+            if entry.tick < previous || entry.tick > self.end_tick {
+                return Err(format!("journal de rejeu desordonne au pas {}", entry.tick));
+            }
+            previous = entry.tick;
+        }
+        if self
+            .interrupted_at
+            .is_some_and(|tick| tick != self.end_tick)
+        {
+            return Err("journal de rejeu interrompu apres sa fin".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = fs::read_to_string(path).map_err(|e| format!("echec lecture rejeu: {e}"))?;
+        let log: Self = ron_from_str(&raw).map_err(|e| format!("echec lecture RON rejeu: {e}"))?;
+        log.validate()?;
+        Ok(log)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let payload = ron_to_string_pretty(self, PrettyConfig::new().depth_limit(4))
+            .map_err(|e| format!("echec serialisation rejeu: {e}"))?;
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).map_err(|e| format!("echec creation dossier rejeu: {e}"))?;
+        }
+        fs::write(path, payload).map_err(|e| format!("echec ecriture rejeu: {e}"))
+    }
+}
+
+pub fn replay_path_for_save(save_path: &Path) -> PathBuf {
+    save_path.with_extension(REPLAY_FILE_EXTENSION)
+}
+
+// Reconstruit la simulation depuis l'instantane initial et reapplique le journal
+// au meme pas; le jeu avance toujours par pas de FIXED_DT.
+pub struct Replay {
+    sim: FactorySim,
+    world: Option<World>,
+    entries: Vec<ReplayEntry>,
+    cursor: usize,
+    end_tick: u64,
+    divergence: Option<String>,
+}
+
+impl Replay {
+    pub fn new(log: CommandLog) -> Result<Self, String> {
+        log.validate()?;
+        let sim = FactorySim::from_save_asset(log.config, log.initial)?;
+        Ok(Self {
+            sim,
+            world: log.world,
+            entries: log.entries,
+            cursor: 0,
+            end_tick: log.end_tick,
+            divergence: None,
+        })
+    }
+
+    pub fn sim(&self) -> &FactorySim {
+        &self.sim
+    }
+
+    // Premiere commande journalisee que le rejeu a refusee, s'il y en a une.
+    pub fn divergence(&self) -> Option<&str> {
+        self.divergence.as_deref()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.sim.tick() >= self.end_tick && self.cursor >= self.entries.len()
+    }
+
+    fn apply_due_events(&mut self) {
+        while let Some(entry) = self.entries.get(self.cursor) {
+            if entry.tick > self.sim.tick() {
+                break;
+            }
+            // Seuls les succes sont journalises: un refus au rejeu signale une divergence.
+            let result = match &entry.event {
+                ReplayEvent::Command(command) => self
+                    .sim
+                    .apply_command_in_world(command.clone(), self.world.as_mut()),
+                ReplayEvent::ScriptedBuild(command) => self
+                    .sim
+                    .apply_scripted_build(command.clone(), self.world.as_mut()),
+                ReplayEvent::Bootstrap => self.sim.bootstrap_functional_factory(),
+            };
+            if let Err(err) = result
+                && self.divergence.is_none()
+            {
+                self.divergence = Some(format!("tick {}: {err}", entry.tick));
+            }
+            self.cursor += 1;
+        }
+    }
+
+    // Un pas de rejeu; renvoie false une fois le journal epuise.
+    pub fn step(&mut self) -> bool {
+        self.apply_due_events();
+        if self.sim.tick() >= self.end_tick {
+            return false;
+        }
+        self.sim.step_in_world(crate::FIXED_DT, self.world.as_ref());
+        true
+    }
+
+    pub fn run_to_end(mut self) -> FactorySim {
+        while self.step() {}
+        self.sim
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gestion::EmployeeRole;
+
+    fn snapshot(sim: &FactorySim) -> String {
+        ron_to_string_pretty(&sim.to_save_asset(), PrettyConfig::new())
+            .expect("save asset should serialize")
+    }
+
+    #[test]
+    fn recorded_session_replays_to_identical_state() {
+        let mut world = World::new_room(25, 15);
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.start_recording(Some(&world));
+
+        sim.bootstrap_functional_factory()
+            .expect("bootstrap should succeed");
+        for _ in 0..300 {
+            sim.step_in_world(crate::FIXED_DT, Some(&world));
+        }
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::Technicien,
+        })
+        .expect("technician hire should succeed");
+        sim.toggle_build_mode();
+        sim.apply_build_click(&mut world, (5, 5), false);
+        for _ in 0..300 {
+            sim.step_in_world(crate::FIXED_DT, Some(&world));
+        }
+        sim.apply_command(SimCommand::BuyRawStock {
+            supplier_id: crate::gestion::fournisseurs::default_raw_supplier_id(),
+            qty: 200,
+        })
+        .expect("raw purchase should succeed");
+        // Un refus ne laisse aucune trace dans le journal.
+        assert!(
+            sim.apply_command(SimCommand::FireEmployee { employee_id: 9_999 })
+                .is_err()
+        );
+
+        let log = sim.command_log().expect("recording should be active");
+        assert_eq!(log.end_tick(), 600);
+        // L'amorcage n'enregistre que lui-meme, pas ses embauches internes.
+        assert_eq!(log.entries().len(), 4);
+        assert!(matches!(log.entries()[0].event, ReplayEvent::Bootstrap));
+        // Le clic de construction est journalise sous forme de commande.
+        assert!(matches!(
+            log.entries()[2].event,
+            ReplayEvent::Command(SimCommand::PlaceBlock { tile: (5, 5), .. })
+        ));
+
+        let path = std::env::temp_dir().join(format!(
+            "rxchixs_rejeu_test_{}.{}",
+            std::process::id(),
+            REPLAY_FILE_EXTENSION
+        ));
+        log.save(&path).expect("replay should be written");
+        let loaded = CommandLog::load(&path).expect("replay should load");
+        let _ = fs::remove_file(&path);
+
+        let mut replay = Replay::new(loaded).expect("replay should start");
+        while replay.step() {}
+        assert_eq!(replay.divergence(), None);
+        let replayed = replay.run_to_end();
+        assert_eq!(replayed.tick(), sim.tick());
+        assert_eq!(snapshot(&replayed), snapshot(&sim));
+    }
+
+    #[test]
+    fn world_edit_outside_commands_stops_the_recording() {
+        let mut world = World::new_room(25, 15);
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.start_recording(Some(&world));
+        sim.bootstrap_functional_factory()
+            .expect("bootstrap should succeed");
+        sim.toggle_build_mode();
+        // Une pose par commande modifie la carte sans couper le journal.
+        sim.apply_build_click(&mut world, (5, 5), false);
+        for _ in 0..120 {
+            sim.step_in_world(crate::FIXED_DT, Some(&world));
+        }
+        let before_edit = snapshot(&sim);
+        assert_eq!(sim.command_log().and_then(|log| log.interrupted_at()), None);
+
+        // Retouche de l'editeur: le rejeu ne la connait pas.
+        world.set(3, 3, crate::Tile::Wall);
+        for _ in 0..120 {
+            sim.step_in_world(crate::FIXED_DT, Some(&world));
+        }
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::Technicien,
+        })
+        .expect("technician hire should succeed");
+
+        let log = sim.command_log().expect("recording should stay readable");
+        assert_eq!(log.interrupted_at(), Some(120));
+        assert_eq!(log.end_tick(), 120);
+        assert_eq!(log.entries().len(), 2);
+        let replayed = Replay::new(log).expect("replay should start").run_to_end();
+        assert_eq!(replayed.tick(), 120);
+        assert_eq!(snapshot(&replayed), before_edit);
+    }
+
+    #[test]
+    fn out_of_order_log_is_rejected() {
+        let sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let mut log = CommandLog::new(StarterSimConfig::default(), sim.to_save_asset(), None);
+        log.record(5, ReplayEvent::Bootstrap);
+        log.record(2, ReplayEvent::Bootstrap);
+        assert!(log.validate().is_err());
+        assert_eq!(
+            replay_path_for_save(Path::new("saves/partie.ron")),
+            PathBuf::from("saves/partie.rejeu")
+        );
+    }
+}
@@ -4867,16 +4867,18 @@ fn draw_modern_block_visual(
     time: f32,
     modern_ready: bool,
 ) {
-    let lavage = modern_ready && sim.modern_lavage_busy();
+    let line_id = sim.modern_line_for_block(block.id);
+    let flow = sim.line.flow(line_id);
+    let lavage = modern_ready && sim.modern_stage_busy(line_id, sim::BlockKind::FluidityTank);
     let lavage_progress = if modern_ready {
-        sim.modern_lavage_progress_ratio()
+        sim.modern_stage_progress_ratio(line_id, sim::BlockKind::FluidityTank)
     } else {
         0.0
     };
-    let coupe = modern_ready && sim.modern_coupe_busy();
-    let four = modern_ready && sim.modern_four_busy();
-    let floc = modern_ready && sim.modern_floc_busy();
-    let sortex = modern_ready && sim.modern_sortex_busy();
+    let coupe = modern_ready && sim.modern_stage_busy(line_id, sim::BlockKind::Cutter);
+    let four = modern_ready && sim.modern_stage_busy(line_id, sim::BlockKind::DryerOven);
+    let floc = modern_ready && sim.modern_stage_busy(line_id, sim::BlockKind::Flaker);
+    let sortex = modern_ready && sim.modern_stage_busy(line_id, sim::BlockKind::Sortex);
     let line_active = lavage || coupe || four || floc || sortex;
 
     match block.kind {
@@ -4907,11 +4909,13 @@ fn draw_modern_block_visual(
                 block.orientation,
                 time,
                 if line_active { 1.0 } else { 0.0 },
-                (sim.line.wip as f32 / 24.0).clamp(0.0, 1.0),
+                (flow.map_or(0, |flow| flow.wip()) as f32 / 24.0).clamp(0.0, 1.0),
             );
         }
         sim::BlockKind::FluidityTank => {
-            let fill_ratio = (sim.line.washed as f32 / 10.0).clamp(0.0, 1.0);
+            let fill_ratio = (flow.map_or(0, |flow| flow.buffer_qty(sim::ItemKind::Washed)) as f32
+                / 10.0)
+                .clamp(0.0, 1.0);
             draw_fluidity_tank_visual(rect, time, fill_ratio, lavage || coupe);
         }
         sim::BlockKind::Cutter => {
@@ -4920,7 +4924,7 @@ fn draw_modern_block_visual(
                 block.orientation,
                 time,
                 coupe,
-                sim.modern_coupe_progress_ratio(),
+                sim.modern_stage_progress_ratio(line_id, sim::BlockKind::Cutter),
             );
         }
         sim::BlockKind::DistributorBelt => {
@@ -4929,7 +4933,8 @@ fn draw_modern_block_visual(
         sim::BlockKind::DryerOven => {
             draw_dryer_oven_visual(rect, block.orientation, time);
             if four {
-                let heat = 0.25 + 0.75 * sim.modern_four_progress_ratio();
+                let heat = 0.25
+                    + 0.75 * sim.modern_stage_progress_ratio(line_id, sim::BlockKind::DryerOven);
                 draw_rectangle(
                     rect.x,
                     rect.y,
@@ -4958,7 +4963,8 @@ fn draw_modern_block_visual(
                 block.orientation,
                 time,
                 if four || floc || sortex { 1.0 } else { 0.0 },
-                (sim.line.dehydrated as f32 / 10.0).clamp(0.0, 1.0),
+                (flow.map_or(0, |flow| flow.buffer_qty(sim::ItemKind::Dehydrated)) as f32 / 10.0)
+                    .clamp(0.0, 1.0),
             );
         }
         sim::BlockKind::Flaker => {
@@ -4967,7 +4973,7 @@ fn draw_modern_block_visual(
                 block.orientation,
                 time,
                 floc,
-                sim.modern_floc_progress_ratio(),
+                sim.modern_stage_progress_ratio(line_id, sim::BlockKind::Flaker),
             );
         }
         sim::BlockKind::SuctionPipe => {
@@ -4985,23 +4991,23 @@ fn draw_modern_block_visual(
                 block.orientation,
                 time,
                 sortex,
-                sim.modern_sortex_progress_ratio(),
+                sim.modern_stage_progress_ratio(line_id, sim::BlockKind::Sortex),
             );
         }
         sim::BlockKind::BlueBagChute => draw_bag_chute_visual(
             rect,
             block.orientation,
             true,
-            sim.descente_bleue_fill_ratio(),
-            sim.descente_bleue_beacon_active(),
+            sim.descente_bleue_fill_ratio(line_id),
+            sim.descente_bleue_beacon_active(line_id),
             time,
         ),
         sim::BlockKind::RedBagChute => draw_bag_chute_visual(
             rect,
             block.orientation,
             false,
-            sim.descente_rouge_fill_ratio(),
-            sim.descente_rouge_beacon_active(),
+            sim.descente_rouge_fill_ratio(line_id),
+            sim.descente_rouge_beacon_active(line_id),
             time,
         ),
         sim::BlockKind::Storage => draw_storage_block_visual(rect, block.raw_qty),
@@ -5062,6 +5068,27 @@ pub(crate) fn draw_sim_zone_overlay_region(sim: &sim::FactorySim, bounds: (i32,
     }
 }
 
+fn draw_broken_block_marker(rect: Rect, time: f32) {
+    let pulse = 0.55 + 0.35 * (time * 5.0).sin().abs();
+    draw_rectangle_lines(
+        rect.x - 1.0,
+        rect.y - 1.0,
+        rect.w + 2.0,
+        rect.h + 2.0,
+        3.0,
+        Color::new(0.92, 0.18, 0.14, pulse),
+    );
+    draw_text_chip(
+        "PANNE",
+        rect.x + 3.0,
+        rect.y + rect.h * 0.5,
+        12.0,
+        Color::from_rgba(255, 236, 230, 255),
+        Color::from_rgba(120, 16, 12, 220),
+        Color::from_rgba(236, 72, 54, 220),
+    );
+}
+
 pub(crate) fn draw_sim_blocks_overlay(
     sim: &sim::FactorySim,
     show_labels: bool,
@@ -5069,7 +5096,6 @@ pub(crate) fn draw_sim_blocks_overlay(
 ) {
     let storage_texture = storage_raw_texture();
     let time = get_time() as f32;
-    let modern_ready = sim.modern_line_ready_cached_for_render();
     if show_labels {
         let blocks = sim.block_debug_views();
         for block in &blocks {
@@ -5086,7 +5112,7 @@ pub(crate) fn draw_sim_blocks_overlay(
                 sim,
                 sim.blocks(),
                 time,
-                modern_ready,
+                sim.modern_block_ready_cached_for_render(block.id),
             );
             draw_rectangle_lines(
                 rect.x + 1.5,
@@ -5096,6 +5122,9 @@ pub(crate) fn draw_sim_blocks_overlay(
                 1.7,
                 with_alpha(color, 0.74),
             );
+            if sim.block_is_broken(block.id) {
+                draw_broken_block_marker(rect, time);
+            }
             if block.kind == sim::BlockKind::Storage && block.raw_qty > 0 {
                 draw_storage_raw_stack(rect, block.raw_qty, storage_texture.as_ref());
             }
@@ -5133,7 +5162,14 @@ pub(crate) fn draw_sim_blocks_overlay(
             }
             let rect = sim_block_rect(block.tile, block.footprint);
             let color = production::sim_block_overlay_color(block.kind);
-            draw_modern_block_visual(block, rect, sim, sim.blocks(), time, modern_ready);
+            draw_modern_block_visual(
+                block,
+                rect,
+                sim,
+                sim.blocks(),
+                time,
+                sim.modern_block_ready_cached_for_render(block.id),
+            );
             draw_rectangle_lines(
                 rect.x + 1.5,
                 rect.y + 1.5,
@@ -5142,6 +5178,9 @@ pub(crate) fn draw_sim_blocks_overlay(
                 1.7,
                 with_alpha(color, 0.74),
             );
+            if sim.block_is_broken(block.id) {
+                draw_broken_block_marker(rect, time);
+            }
             if block.kind == sim::BlockKind::Storage && block.raw_qty > 0 {
                 draw_storage_raw_stack(rect, block.raw_qty, storage_texture.as_ref());
             }
@@ -9,6 +9,8 @@ pub(crate) fn sim_zone_overlay_color(zone: sim::ZoneKind) -> Option<Color> {
         sim::ZoneKind::Processing => Some(with_alpha(feedback.warning, 0.25)),
         sim::ZoneKind::Shipping => Some(with_alpha(feedback.positive, 0.22)),
         sim::ZoneKind::Support => Some(with_alpha(feedback.money, 0.22)),
+        sim::ZoneKind::BreakRoom => Some(with_alpha(feedback.info, 0.20)),
+        sim::ZoneKind::Vending => Some(with_alpha(feedback.danger, 0.18)),
     }
 }
 
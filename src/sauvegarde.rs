@@ -262,6 +262,7 @@ fn enregistrer_sauvegarde_dans(
     dir: &Path,
     map: &MapAsset,
     sim_asset: Option<sim::FactorySimSaveAsset>,
+    journal: Option<&rejeu::CommandLog>,
     save_name: &str,
     unix_s: i64,
 ) -> Result<SauvegardeInfo, String> {
@@ -272,6 +273,9 @@ fn enregistrer_sauvegarde_dans(
     let slug = sanitize_save_name_for_file(&clean_name);
     let file_name = unique_file_name(dir, &slug, unix_s);
     let path = dir.join(&file_name);
+    if let Some(journal) = journal {
+        journal.save(&rejeu::replay_path_for_save(&path))?;
+    }
     let mut map_copy = map.clone();
     sanitize_map_asset(&mut map_copy);
 
@@ -298,7 +302,7 @@ pub(crate) fn enregistrer_sauvegarde(
     save_name: &str,
 ) -> Result<SauvegardeInfo, String> {
     let unix_s = now_unix_seconds();
-    enregistrer_sauvegarde_dans(Path::new(SAVE_DIR_PATH), map, None, save_name, unix_s)
+    enregistrer_sauvegarde_dans(Path::new(SAVE_DIR_PATH), map, None, None, save_name, unix_s)
 }
 
 pub(crate) fn enregistrer_sauvegarde_avec_sim(
@@ -311,6 +315,7 @@ pub(crate) fn enregistrer_sauvegarde_avec_sim(
         Path::new(SAVE_DIR_PATH),
         map,
         Some(sim.to_save_asset()),
+        sim.command_log().as_ref(),
         save_name,
         unix_s,
     )
@@ -374,7 +379,7 @@ mod tests {
         let dir = test_save_dir("roundtrip");
         let map = MapAsset::new_default();
 
-        let slot = enregistrer_sauvegarde_dans(&dir, &map, None, "Test Save", 1_700_000_000)
+        let slot = enregistrer_sauvegarde_dans(&dir, &map, None, None, "Test Save", 1_700_000_000)
             .expect("save should succeed");
         let listing = lister_sauvegardes_dans(&dir).expect("listing should succeed");
         assert_eq!(listing.warnings.len(), 0);
@@ -395,14 +400,19 @@ mod tests {
         let dir = test_save_dir("sim_roundtrip");
         let map = MapAsset::new_default();
         let mut sim = sim::FactorySim::new(sim::StarterSimConfig::default(), 25, 15);
-        sim.apply_command(crate::gestion::SimCommand::BuyRawStock { qty: 500 })
-            .expect("stock purchase should be saved");
+        sim.start_recording(None);
+        sim.apply_command(crate::gestion::SimCommand::BuyRawStock {
+            supplier_id: crate::gestion::fournisseurs::default_raw_supplier_id(),
+            qty: 500,
+        })
+        .expect("stock purchase should be saved");
         let cash_after_order = sim.cash();
 
         let slot = enregistrer_sauvegarde_dans(
             &dir,
             &map,
             Some(sim.to_save_asset()),
+            sim.command_log().as_ref(),
             "Sim Save",
             1_700_000_300,
         )
@@ -414,6 +424,15 @@ mod tests {
         assert_eq!(sim_asset.stock.pending_raw_qty(), 500);
         assert_eq!(sim_asset.economy.cash, cash_after_order);
 
+        // Le journal de rejeu est ecrit a cote sans polluer la liste des sauvegardes.
+        let journal =
+            rejeu::CommandLog::load(&rejeu::replay_path_for_save(&dir.join(&slot.file_name)))
+                .expect("replay log should be written next to the save");
+        assert_eq!(journal.entries().len(), 1);
+        let listing = lister_sauvegardes_dans(&dir).expect("listing should succeed");
+        assert_eq!(listing.slots.len(), 1);
+        assert!(listing.warnings.is_empty());
+
         let _ = fs::remove_dir_all(&dir);
     }
 
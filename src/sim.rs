@@ -1,13 +1,33 @@
+use crate::aleatoire::{RngStream, RngStreams, SimRng};
+use crate::character::CharacterCatalog;
+use crate::gestion::besoins::{BreakVenue, EmployeeNeeds, NeedKind};
+use crate::gestion::carnet::KPI_FULFILMENT_WINDOW_H;
+use crate::gestion::compta::{EQUIPMENT_DEPRECIATION_DAYS, FITOUT_DEPRECIATION_DAYS};
+use crate::gestion::financement::OverdraftLevel;
+use crate::gestion::lots::{LOT_TRACEABILITY_WINDOW_S, LotPortion, OPENING_STOCK_SUPPLIER};
+use crate::gestion::moral::MoraleEvent;
+use crate::gestion::personnel::{WORK_PRIORITY_DISABLED, WORK_PRIORITY_LOWEST};
+use crate::gestion::qualite::{DefectProfile, GradeOutcome, LotSample};
+use crate::gestion::recrutement::{CandidateId, RecruitmentEvent};
+use crate::gestion::relations::pick_exchange;
 use crate::gestion::{
-    EmployeeRole, LineOperationalState, PersonnelState, ProductionLineId, ProductionLineState,
-    SalesState, SimCommand, StockState,
+    Account, ENERGY_BOOK_PATH, Employee, EmployeeId, EmployeeRole, EmployeeStatus, EnergyBook,
+    EnergyState, FINANCE_BOOK_PATH, FinanceBook, FinanceEvent, FinanceState, HSE_BOOK_PATH,
+    HseBook, HseState, Ledger, LineOperationalState, LotBook, LotId, LotQueue, MARKET_BOOK_PATH,
+    MORALE_BOOK_PATH, MarketBook, MarketEvent, MarketState, MoraleBook, NEEDS_BOOK_PATH, NeedsBook,
+    PersonnelState, ProductionLineId, ProductionLineState, QUALITY_BOOK_PATH, QualityBook,
+    QualityControl, QualityGrade, QualityStats, RECRUITMENT_BOOK_PATH, RecruitmentBoard,
+    RecruitmentBook, RonBook, SHIFT_BOOK_PATH, SUPPLIER_CATALOGUE_PATH, SalesState, ShiftBook,
+    SimCommand, StockState, SupplierCatalogue, TRAINING_BOOK_PATH, TrainingBook, WorkType,
 };
+use crate::recettes::{ItemQty, ProductRecipe, RECIPE_BOOK_PATH, RecipeBook};
+use crate::rejeu::{CommandLog, ReplayEvent};
 use ron::{
     de::from_str as ron_from_str,
     ser::{PrettyConfig, to_string_pretty as ron_to_string_pretty},
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::io::ErrorKind;
 use std::path::Path;
@@ -18,26 +38,43 @@ const FACTORY_LAYOUT_SCHEMA_VERSION: u32 = 1;
 const RESERVATION_TTL_SECONDS: f64 = 8.0;
 const ACTION_STATUS_TTL_SIM_SECONDS: f64 = 240.0;
 const RACK_NIVEAU_COUNT: usize = 6;
-const SAC_CAPACITY_UNITS: u32 = 14;
-const SACS_PAR_BOX: u32 = 21;
-const MODERN_CYCLE_LAVAGE_S: f64 = 16.0;
-const MODERN_CYCLE_COUPE_S: f64 = 11.0;
-const MODERN_CYCLE_FOUR_S: f64 = 42.0;
-const MODERN_CYCLE_FLOC_S: f64 = 14.0;
-const MODERN_CYCLE_SORTEX_S: f64 = 9.0;
+const DESCENTE_BEACON_SECONDS: f64 = 7.0;
 const FACTORY_SIM_SAVE_SCHEMA_VERSION: u32 = 1;
-const MAIN_PRODUCTION_LINE_ID: ProductionLineId = 1;
+pub(crate) const MAIN_PRODUCTION_LINE_ID: ProductionLineId = 1;
 const TEMP_CONTRACT_SECONDS: f64 = 2.0 * 3600.0;
 const TEMP_AGENCY_FEE_EUR: f64 = 120.0;
 const TEST_FACTORY_RAW_RECEIVING_UNITS: u32 = 1_500;
 const TEST_FACTORY_RAW_LINE_INPUT_UNITS: u32 = 120;
 const DEFAULT_FINISHED_BOX_PRICE_EUR: f64 = 780.0;
+const MIN_SALE_PRICE_EUR: f64 = 100.0;
+const MAX_SALE_PRICE_EUR: f64 = 20_000.0;
+const DEFAULT_SIM_SEED: u64 = 0x005E_ED0F_FAC7;
+const MACHINE_WEAR_PER_CYCLE: f64 = 0.004;
+const MACHINE_BREAKDOWN_BASE_CHANCE: f64 = 0.0005;
+const MACHINE_BREAKDOWN_WEAR_CHANCE: f64 = 0.03;
+const HAUL_HANDLING_SECONDS: f64 = 6.0;
+const MACHINE_REPAIR_SECONDS: f64 = 45.0 * 60.0;
+const SPARE_PARTS_PER_REPAIR: u32 = 1;
+// Deux collegues en pause se parlent s'ils sont a portee de voix.
+const BREAK_EXCHANGE_RANGE_TILES: i32 = 4;
+const LINE_RAW_BUFFER_UNITS: u32 = 4;
+// Charge d'un trajet de manutention: une palette matiere ou quelques boxes.
+const HAUL_RAW_UNITS_PER_TRIP: u32 = 40;
+const HAUL_BOXES_PER_TRIP: u32 = 4;
+const MAX_HAUL_TRIPS_PER_LINE: u32 = 4;
+const TEST_FACTORY_SPARE_PARTS: u32 = 4;
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SimClock {
     sim_seconds: f64,
 }
 
+impl Default for SimClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SimClock {
     pub fn new() -> Self {
         Self { sim_seconds: 0.0 }
@@ -65,6 +102,11 @@ impl SimClock {
         (self.hours() % 24.0 + 24.0) % 24.0
     }
 
+    // Le jour 0 est un lundi: samedi et dimanche sont les jours 5 et 6.
+    pub fn is_weekend(&self) -> bool {
+        self.day_index() % 7 >= 5
+    }
+
     pub fn minute_of_hour(&self) -> u32 {
         ((self.sim_seconds / 60.0).floor() as u64 % 60) as u32
     }
@@ -81,31 +123,93 @@ pub struct Economy {
     pub cash: f64,
     pub revenue_total: f64,
     pub cost_total: f64,
+    // Chaque mouvement de tresorerie y est passe en partie double.
+    #[serde(default)]
+    pub ledger: Ledger,
 }
 
 impl Economy {
     pub fn new(starting_cash: f64) -> Self {
+        let mut ledger = Ledger::default();
+        ledger.open(starting_cash);
         Self {
             cash: starting_cash,
             revenue_total: 0.0,
             cost_total: 0.0,
+            ledger,
+        }
+    }
+
+    // Sauvegardes anterieures au grand livre: la tresorerie devient l'apport d'ouverture.
+    fn ensure_ledger(&mut self, now_s: f64) {
+        if self.ledger.is_empty() {
+            self.ledger.start_at(now_s);
+            self.ledger.open(self.cash);
         }
     }
 
-    pub fn earn(&mut self, amount: f64) {
+    pub fn earn(&mut self, account: Account, amount: f64) {
         if amount.is_finite() && amount > 0.0 {
             self.cash += amount;
             self.revenue_total += amount;
+            self.ledger
+                .post(account.label(), Account::Tresorerie, account, amount);
         }
     }
 
-    pub fn spend(&mut self, amount: f64) {
+    pub fn spend(&mut self, account: Account, amount: f64) {
         if amount.is_finite() && amount > 0.0 {
             self.cash -= amount;
             self.cost_total += amount;
+            self.ledger
+                .post(account.label(), account, Account::Tresorerie, amount);
+        }
+    }
+
+    // Charge rattachee a une journee precise, meme si son minuit vient d'etre franchi.
+    pub fn spend_on_day(&mut self, account: Account, amount: f64, day_index: u64) {
+        self.cost_total += self.ledger.settle_day(day_index);
+        self.spend(account, amount);
+    }
+
+    // Investissement immobilise: sort de la tresorerie, entre en charges par amortissement.
+    pub fn invest(&mut self, label: &str, block_id: Option<BlockId>, amount: f64, life_days: f64) {
+        if amount.is_finite() && amount > 0.0 {
+            self.cash -= amount;
+            self.ledger
+                .acquire_asset(label, block_id, amount, life_days);
         }
     }
 
+    pub fn sell_asset(&mut self, block_id: BlockId, proceeds: f64) {
+        if proceeds.is_finite() && proceeds >= 0.0 {
+            self.cash += proceeds;
+            self.revenue_total += proceeds;
+            self.cost_total += self.ledger.dispose_asset(block_id, proceeds);
+        }
+    }
+
+    // Emprunt ou remboursement: mouvement de bilan, sans effet sur le resultat.
+    pub fn borrow(&mut self, account: Account, amount: f64) {
+        if amount.is_finite() && amount > 0.0 {
+            self.cash += amount;
+            self.ledger
+                .post(account.label(), Account::Tresorerie, account, amount);
+        }
+    }
+
+    pub fn repay(&mut self, account: Account, amount: f64) {
+        if amount.is_finite() && amount > 0.0 {
+            self.cash -= amount;
+            self.ledger
+                .post(account.label(), account, Account::Tresorerie, amount);
+        }
+    }
+
+    pub fn tick(&mut self, now_s: f64) {
+        self.cost_total += self.ledger.advance(now_s);
+    }
+
     pub fn profit(&self) -> f64 {
         self.revenue_total - self.cost_total
     }
@@ -122,6 +226,13 @@ pub struct StarterSimConfig {
     pub machine_a_cycle_s: f64,
     pub machine_b_cycle_s: f64,
     pub sale_price: f64,
+    // Graine unique de la simulation; chaque sous-systeme en derive son flux.
+    #[serde(default = "default_sim_seed", alias = "breakdown_seed")]
+    pub seed: u64,
+}
+
+fn default_sim_seed() -> u64 {
+    DEFAULT_SIM_SEED
 }
 
 impl Default for StarterSimConfig {
@@ -136,6 +247,7 @@ impl Default for StarterSimConfig {
             machine_a_cycle_s: 90.0,
             machine_b_cycle_s: 120.0,
             sale_price: DEFAULT_FINISHED_BOX_PRICE_EUR,
+            seed: DEFAULT_SIM_SEED,
         }
     }
 }
@@ -244,35 +356,281 @@ impl StarterSimConfig {
     }
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StageRuntime {
+    pub stage_id: String,
+    pub busy: bool,
+    pub progress_s: f64,
+    reject_accum: f64,
+    #[serde(default)]
+    lot_id: Option<LotId>,
+    #[serde(default)]
+    defect: DefectProfile,
+}
+
+impl StageRuntime {
+    fn new(stage_id: &str) -> Self {
+        Self {
+            stage_id: stage_id.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineFlowState {
+    buffers: BTreeMap<ItemKind, u32>,
+    stages: Vec<StageRuntime>,
+    blue_bag_fill: u32,
+    red_bag_fill: u32,
+    descente_bleue_beacon_s: f64,
+    descente_rouge_beacon_s: f64,
+    wip: u32,
+    // Tampon matiere propre a la ligne, servi depuis le stock d'entree.
+    raw: u32,
+    raw_lots: LotQueue,
+    lots: BTreeMap<ItemKind, LotQueue>,
+    // Profil de defaut moyen des unites de chaque tampon.
+    defects: BTreeMap<ItemKind, DefectProfile>,
+    // Boxes pleines en bout de ligne, vendables une fois portees a l'expedition.
+    boxes_to_ship: u32,
+}
+
+impl LineFlowState {
+    pub fn buffer_qty(&self, kind: ItemKind) -> u32 {
+        self.buffers.get(&kind).copied().unwrap_or(0)
+    }
+
+    pub fn wip(&self) -> u32 {
+        self.wip
+    }
+
+    pub fn raw_qty(&self) -> u32 {
+        self.raw
+    }
+
+    pub fn boxes_to_ship(&self) -> u32 {
+        self.boxes_to_ship
+    }
+
+    fn has_items(&self, items: &[ItemQty]) -> bool {
+        items.iter().all(|entry| {
+            let available = if entry.item == ItemKind::Raw {
+                self.raw
+            } else {
+                self.buffer_qty(entry.item)
+            };
+            available >= entry.qty
+        })
+    }
+
+    // Retourne les lots consommes, pris en FIFO dans la file de chaque entree, et le
+    // profil de defaut des unites prises (grade matiere pour le brut).
+    fn take_items(
+        &mut self,
+        items: &[ItemQty],
+        raw_defect: impl Fn(LotId) -> f64,
+    ) -> (Vec<LotId>, DefectProfile) {
+        let mut lots = Vec::new();
+        let mut defect = DefectProfile::default();
+        let mut taken = 0u32;
+        for entry in items {
+            let (portions, entry_defect) = if entry.item == ItemKind::Raw {
+                self.raw = self.raw.saturating_sub(entry.qty);
+                let portions = self.raw_lots.take(entry.qty);
+                let matiere = portions
+                    .iter()
+                    .map(|portion| raw_defect(portion.lot_id) * portion.qty as f64)
+                    .sum::<f64>()
+                    / entry.qty.max(1) as f64;
+                let entry_defect = DefectProfile {
+                    matiere,
+                    ..DefectProfile::default()
+                };
+                (portions, entry_defect)
+            } else {
+                if let Some(qty) = self.buffers.get_mut(&entry.item) {
+                    *qty = qty.saturating_sub(entry.qty);
+                }
+                let portions = self
+                    .lots
+                    .get_mut(&entry.item)
+                    .map(|queue| queue.take(entry.qty))
+                    .unwrap_or_default();
+                let entry_defect = self.defects.get(&entry.item).copied().unwrap_or_default();
+                (portions, entry_defect)
+            };
+            lots.extend(portions.iter().map(|portion| portion.lot_id));
+            defect = defect.blend(taken, &entry_defect, entry.qty);
+            taken = taken.saturating_add(entry.qty);
+        }
+        self.buffers.retain(|_, qty| *qty > 0);
+        self.lots.retain(|_, queue| queue.total() > 0);
+        let buffers = &self.buffers;
+        self.defects.retain(|kind, _| buffers.contains_key(kind));
+        (lots, defect)
+    }
+
+    // Reprend les en-cours d'une ligne demantelee: tampons, sacs entames et cycles en
+    // cours sur les etapes libres; renvoie les cycles perdus faute d'etape libre.
+    fn absorb(&mut self, mut other: LineFlowState) -> u32 {
+        for (kind, qty) in std::mem::take(&mut other.buffers) {
+            let defect = other.defects.get(&kind).copied().unwrap_or_default();
+            let previous = self.buffer_qty(kind);
+            self.buffers.insert(kind, previous.saturating_add(qty));
+            let mean = self.defects.entry(kind).or_default();
+            *mean = mean.blend(previous, &defect, qty);
+            if let Some(mut queue) = other.lots.remove(&kind) {
+                self.lots
+                    .entry(kind)
+                    .or_default()
+                    .extend(&queue.drain_all());
+            }
+        }
+        self.blue_bag_fill = self.blue_bag_fill.saturating_add(other.blue_bag_fill);
+        self.red_bag_fill = self.red_bag_fill.saturating_add(other.red_bag_fill);
+        let mut lost = 0;
+        for runtime in other.stages.into_iter().filter(|runtime| runtime.busy) {
+            match self
+                .stages
+                .iter_mut()
+                .find(|own| own.stage_id == runtime.stage_id && !own.busy)
+            {
+                Some(own) => {
+                    let reject_accum = own.reject_accum;
+                    *own = runtime;
+                    own.reject_accum = reject_accum;
+                }
+                None => lost += 1,
+            }
+        }
+        lost
+    }
+
+    // Unites en cours perdues si plus aucune ligne ne peut les reprendre.
+    fn units_in_progress(&self) -> u32 {
+        let buffered = self
+            .buffers
+            .values()
+            .fold(0u32, |acc, qty| acc.saturating_add(*qty));
+        let busy = self.stages.iter().filter(|runtime| runtime.busy).count() as u32;
+        buffered
+            .saturating_add(busy)
+            .saturating_add(self.blue_bag_fill)
+            .saturating_add(self.red_bag_fill)
+    }
+
+    fn collect_live_lots(&self, live: &mut BTreeSet<LotId>) {
+        live.extend(self.stages.iter().filter_map(|runtime| runtime.lot_id));
+        live.extend(
+            self.lots
+                .values()
+                .chain(std::iter::once(&self.raw_lots))
+                .flat_map(|queue| queue.portions.iter().map(|portion| portion.lot_id)),
+        );
+    }
+
+    fn put_items(&mut self, items: &[ItemQty], lot_id: Option<LotId>, defect: &DefectProfile) {
+        for entry in items {
+            let qty = self.buffers.entry(entry.item).or_insert(0);
+            let previous = *qty;
+            *qty = qty.saturating_add(entry.qty);
+            let mean = self.defects.entry(entry.item).or_default();
+            *mean = mean.blend(previous, defect, entry.qty);
+            if let Some(lot_id) = lot_id {
+                self.lots
+                    .entry(entry.item)
+                    .or_default()
+                    .push(lot_id, entry.qty);
+            }
+        }
+    }
+
+    fn drain_buffer(&mut self, kind: ItemKind) -> (u32, Vec<LotPortion>) {
+        let qty = self.buffers.remove(&kind).unwrap_or(0);
+        self.defects.remove(&kind);
+        let lots = self
+            .lots
+            .remove(&kind)
+            .map(|mut queue| queue.drain_all())
+            .unwrap_or_default();
+        (qty, lots)
+    }
+
+    fn sync_stage_runtimes(&mut self, recipe: &ProductRecipe) {
+        let aligned = self.stages.len() == recipe.stages.len()
+            && self
+                .stages
+                .iter()
+                .zip(&recipe.stages)
+                .all(|(runtime, stage)| runtime.stage_id == stage.id);
+        if aligned {
+            return;
+        }
+        let mut previous = std::mem::take(&mut self.stages);
+        self.stages = recipe
+            .stages
+            .iter()
+            .map(|stage| {
+                previous
+                    .iter()
+                    .position(|runtime| runtime.stage_id == stage.id)
+                    .map(|index| previous.swap_remove(index))
+                    .unwrap_or_else(|| StageRuntime::new(&stage.id))
+            })
+            .collect();
+    }
+
+    fn has_work_in_progress(&self) -> bool {
+        !self.buffers.is_empty() || self.stages.iter().any(|stage| stage.busy)
+    }
+
+    fn tick_beacons(&mut self, dt_sim: f64) {
+        self.descente_bleue_beacon_s = (self.descente_bleue_beacon_s - dt_sim).max(0.0);
+        self.descente_rouge_beacon_s = (self.descente_rouge_beacon_s - dt_sim).max(0.0);
+    }
+
+    // Vue compacte historique: premiere moitie des etapes = machine A, le reste = machine B.
+    fn machine_halves_progress(&self) -> (Option<f64>, Option<f64>) {
+        let split = self.stages.len() / 2;
+        let (first_half, second_half) = self.stages.split_at(split);
+        let latest_busy = |stages: &[StageRuntime]| {
+            stages
+                .iter()
+                .rev()
+                .find(|stage| stage.busy)
+                .map(|stage| stage.progress_s)
+        };
+        (latest_busy(first_half), latest_busy(second_half))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StarterLineState {
+    // Stock d'entree ligne (miroir de stock.raw_line_input) alimente par les
+    // caristes; chaque ligne moderne y puise son propre tampon (LineFlowState).
     pub raw: u32,
     pub wip: u32,
     pub finished: u32,
-    pub washed: u32,
-    pub sliced: u32,
-    pub dehydrated: u32,
-    pub flakes: u32,
     pub sacs_bleus_total: u32,
     pub sacs_rouges_total: u32,
     pub boxes_bleues_total: u32,
     pub sold_total: u32,
     pub produced_wip_total: u32,
     pub produced_finished_total: u32,
-    blue_bag_fill: u32,
-    red_bag_fill: u32,
-    lavage_busy: bool,
-    coupe_busy: bool,
-    four_busy: bool,
-    floc_busy: bool,
-    sortex_busy: bool,
-    lavage_progress_s: f64,
-    coupe_progress_s: f64,
-    four_progress_s: f64,
-    floc_progress_s: f64,
-    sortex_progress_s: f64,
-    descente_bleue_beacon_s: f64,
-    descente_rouge_beacon_s: f64,
+    #[serde(default)]
+    flows: BTreeMap<ProductionLineId, LineFlowState>,
+    #[serde(default)]
+    packing_lots: BTreeSet<LotId>,
+    #[serde(default)]
+    packing_last_lot: Option<LotId>,
+    #[serde(default)]
+    legacy_wip_lots: LotQueue,
+    #[serde(default)]
+    machine_a_lot: Option<LotId>,
+    #[serde(default)]
+    machine_b_lot: Option<LotId>,
     delivery_accum: f64,
     machine_a_busy: bool,
     machine_b_busy: bool,
@@ -280,36 +638,30 @@ pub struct StarterLineState {
     machine_b_progress: f64,
 }
 
+impl Default for StarterLineState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StarterLineState {
     pub fn new() -> Self {
         Self {
             raw: 0,
             wip: 0,
             finished: 0,
-            washed: 0,
-            sliced: 0,
-            dehydrated: 0,
-            flakes: 0,
             sacs_bleus_total: 0,
             sacs_rouges_total: 0,
             boxes_bleues_total: 0,
             sold_total: 0,
             produced_wip_total: 0,
             produced_finished_total: 0,
-            blue_bag_fill: 0,
-            red_bag_fill: 0,
-            lavage_busy: false,
-            coupe_busy: false,
-            four_busy: false,
-            floc_busy: false,
-            sortex_busy: false,
-            lavage_progress_s: 0.0,
-            coupe_progress_s: 0.0,
-            four_progress_s: 0.0,
-            floc_progress_s: 0.0,
-            sortex_progress_s: 0.0,
-            descente_bleue_beacon_s: 0.0,
-            descente_rouge_beacon_s: 0.0,
+            flows: BTreeMap::new(),
+            packing_lots: BTreeSet::new(),
+            packing_last_lot: None,
+            legacy_wip_lots: LotQueue::default(),
+            machine_a_lot: None,
+            machine_b_lot: None,
             delivery_accum: 0.0,
             machine_a_busy: false,
             machine_b_busy: false,
@@ -317,12 +669,56 @@ impl StarterLineState {
             machine_b_progress: 0.0,
         }
     }
+
+    pub fn buffer_qty(&self, kind: ItemKind) -> u32 {
+        match kind {
+            ItemKind::Raw => self
+                .flows
+                .values()
+                .fold(self.raw, |acc, flow| acc.saturating_add(flow.raw)),
+            ItemKind::Finished => self.flows.values().fold(self.finished, |acc, flow| {
+                acc.saturating_add(flow.boxes_to_ship)
+            }),
+            _ => self
+                .flows
+                .values()
+                .fold(0u32, |acc, flow| acc.saturating_add(flow.buffer_qty(kind))),
+        }
+    }
+
+    pub fn flow(&self, line_id: ProductionLineId) -> Option<&LineFlowState> {
+        self.flows.get(&line_id)
+    }
+
+    // Boxes en bout de ligne qui attendent encore une manutention.
+    pub fn boxes_to_ship_total(&self) -> u32 {
+        self.flows
+            .values()
+            .fold(0u32, |acc, flow| acc.saturating_add(flow.boxes_to_ship))
+    }
+
+    fn refresh_aggregates(&mut self) {
+        self.wip = self
+            .flows
+            .values()
+            .fold(0u32, |acc, flow| acc.saturating_add(flow.wip));
+        let mut machine_a = None;
+        let mut machine_b = None;
+        for flow in self.flows.values() {
+            let (a, b) = flow.machine_halves_progress();
+            machine_a = machine_a.or(a);
+            machine_b = machine_b.or(b);
+        }
+        self.machine_a_busy = machine_a.is_some();
+        self.machine_b_busy = machine_b.is_some();
+        self.machine_a_progress = machine_a.unwrap_or(0.0);
+        self.machine_b_progress = machine_b.unwrap_or(0.0);
+    }
 }
 
 pub type BlockId = u32;
 pub type JobId = u64;
 pub type AgentId = u32;
-pub type LotId = u64;
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
@@ -331,6 +727,11 @@ pub enum ItemKind {
     Wip,
     Finished,
     Scrap,
+    Washed,
+    Sliced,
+    Dehydrated,
+    Flakes,
+    Sorted,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -371,6 +772,28 @@ impl BlockInventory {
     }
 }
 
+// Une pile par portion de lot; le reliquat sans lot connu reste en lot 0.
+fn add_lot_stacks(inventory: &mut BlockInventory, kind: ItemKind, qty: u32, lots: &LotQueue) {
+    let mut remaining = qty;
+    for portion in &lots.portions {
+        let part = portion.qty.min(remaining);
+        if part == 0 {
+            break;
+        }
+        inventory.add_stack(ItemStack {
+            kind,
+            qty: part,
+            lot_id: portion.lot_id,
+        });
+        remaining -= part;
+    }
+    inventory.add_stack(ItemStack {
+        kind,
+        qty: remaining,
+        lot_id: 0,
+    });
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
 pub enum BlockKind {
@@ -593,6 +1016,31 @@ impl BlockKind {
     pub fn is_modern_line_component(self) -> bool {
         MODERN_LINE_REQUIRED_KINDS.contains(&self)
     }
+
+    fn is_machine(self) -> bool {
+        matches!(
+            self,
+            Self::FluidityTank
+                | Self::Cutter
+                | Self::DryerOven
+                | Self::Flaker
+                | Self::Sortex
+                | Self::MachineA
+                | Self::MachineB
+        )
+    }
+
+    fn anchors_production_line(self) -> bool {
+        matches!(
+            self,
+            Self::InputHopper
+                | Self::FluidityTank
+                | Self::Cutter
+                | Self::DryerOven
+                | Self::Flaker
+                | Self::Sortex
+        )
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -609,6 +1057,13 @@ pub struct MachineState {
     pub progress_s: f64,
     pub cycle_s: f64,
     pub wear: f64,
+    pub broken: bool,
+    pub cycles_total: u64,
+    pub breakdowns_total: u32,
+    pub repair_progress_s: f64,
+    pub downtime_minutes: f64,
+    // Pieces deja sorties du stock pour la panne en cours: une seule fois par panne.
+    pub repair_parts_taken: bool,
 }
 
 impl Default for MachineState {
@@ -618,10 +1073,41 @@ impl Default for MachineState {
             progress_s: 0.0,
             cycle_s: 60.0,
             wear: 0.0,
+            broken: false,
+            cycles_total: 0,
+            breakdowns_total: 0,
+            repair_progress_s: 0.0,
+            downtime_minutes: 0.0,
+            repair_parts_taken: false,
         }
     }
 }
 
+impl MachineState {
+    // Chaque cycle use la machine; le tirage de panne vient du flux "pannes"
+    // de la simulation et devient plus probable avec l'usure.
+    fn record_cycle(&mut self, rng: &mut SimRng) -> bool {
+        self.cycles_total = self.cycles_total.saturating_add(1);
+        self.wear = (self.wear + MACHINE_WEAR_PER_CYCLE).clamp(0.0, 1.0);
+        let chance =
+            MACHINE_BREAKDOWN_BASE_CHANCE + MACHINE_BREAKDOWN_WEAR_CHANCE * self.wear * self.wear;
+        if rng.chance(chance) {
+            self.broken = true;
+            self.repair_progress_s = 0.0;
+            self.repair_parts_taken = false;
+            self.breakdowns_total = self.breakdowns_total.saturating_add(1);
+        }
+        self.broken
+    }
+
+    fn repair(&mut self) {
+        self.broken = false;
+        self.wear = 0.0;
+        self.repair_progress_s = 0.0;
+        self.repair_parts_taken = false;
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct BlockInstance {
@@ -658,6 +1144,9 @@ pub enum ZoneKind {
     Processing,
     Shipping,
     Support,
+    // Lieux de pause du personnel (voir data/needs.ron).
+    BreakRoom,
+    Vending,
 }
 
 impl ZoneKind {
@@ -668,6 +1157,8 @@ impl ZoneKind {
             Self::Processing => "cassage",
             Self::Shipping => "dehy_finition",
             Self::Support => "vente",
+            Self::BreakRoom => "pause",
+            Self::Vending => "distributeurs",
         }
     }
 
@@ -678,6 +1169,8 @@ impl ZoneKind {
             Self::Processing => 19.0,
             Self::Shipping => 21.0,
             Self::Support => 17.0,
+            Self::BreakRoom => 14.0,
+            Self::Vending => 24.0,
         }
     }
 }
@@ -764,6 +1257,12 @@ fn zone_rules(kind: ZoneKind) -> ZoneRules {
             risk_factor: 0.2,
             target_per_hour: 14.0,
         },
+        ZoneKind::BreakRoom | ZoneKind::Vending => ZoneRules {
+            speed_multiplier: 0.8,
+            fatigue_factor: 0.2,
+            risk_factor: 0.1,
+            target_per_hour: 0.0,
+        },
     }
 }
 
@@ -798,19 +1297,34 @@ impl ZoneLayer {
         let idx = (tile.1 * self.w + tile.0) as usize;
         self.zones[idx] = zone;
     }
+
+    fn tiles_of(&self, zone: ZoneKind) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let w = self.w.max(1);
+        self.zones
+            .iter()
+            .enumerate()
+            .filter(move |(_, kind)| **kind == zone)
+            .map(move |(idx, _)| (idx as i32 % w, idx as i32 / w))
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum JobKind {
+    // Un trajet par (ligne, numero): plusieurs manutentionnaires servent la meme ligne.
     Haul {
         from_block: BlockId,
         to_block: BlockId,
         item_kind: ItemKind,
         qty: u32,
+        line_id: ProductionLineId,
+        trip: u32,
     },
     OperateMachine {
         block_id: BlockId,
     },
+    Repair {
+        block_id: BlockId,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -836,7 +1350,6 @@ pub struct Job {
 pub enum ReservationKey {
     BlockInput(BlockId),
     BlockOutput(BlockId),
-    InventorySlot(BlockId, u16),
 }
 
 #[derive(Clone, Debug)]
@@ -845,24 +1358,160 @@ pub struct Reservation {
     pub ttl_s: f64,
 }
 
+// Deroule d'un job: les manutentions vont chercher puis deposer, les autres travaillent sur place.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JobPhase {
+    #[default]
+    GoToSource,
+    Pick,
+    GoToDestination,
+    Drop,
+    Work,
+}
+
+impl JobPhase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::GoToSource => "trajet source",
+            Self::Pick => "prise",
+            Self::GoToDestination => "trajet destination",
+            Self::Drop => "depose",
+            Self::Work => "travail",
+        }
+    }
+
+    fn is_travel(self) -> bool {
+        matches!(self, Self::GoToSource | Self::GoToDestination)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SimAgent {
     pub id: AgentId,
+    // None: le patron tant que le personnel n'a pas de fiche Patron.
+    pub employee_id: Option<EmployeeId>,
+    pub role: Option<EmployeeRole>,
     pub tile: (i32, i32),
+    pub path: VecDeque<(i32, i32)>,
+    pub move_progress_s: f64,
     pub speed_tiles_per_s: f64,
     pub fatigue: f64,
     pub stress: f64,
     pub current_job: Option<JobId>,
+    pub job_phase: JobPhase,
     pub job_progress_s: f64,
+    pub job_started_s: f64,
     pub decision_debug: String,
 }
 
+impl SimAgent {
+    fn new(
+        id: AgentId,
+        employee_id: Option<EmployeeId>,
+        role: Option<EmployeeRole>,
+        tile: (i32, i32),
+    ) -> Self {
+        Self {
+            id,
+            employee_id,
+            role,
+            tile,
+            path: VecDeque::new(),
+            move_progress_s: 0.0,
+            speed_tiles_per_s: agent_speed_for_role(role),
+            fatigue: 10.0,
+            stress: 7.0,
+            current_job: None,
+            job_phase: JobPhase::default(),
+            job_progress_s: 0.0,
+            job_started_s: 0.0,
+            decision_debug: "inactif".to_string(),
+        }
+    }
+
+    // Avance sur le chemin a la vitesse donnee; vrai une fois arrive.
+    fn follow_path(&mut self, dt_s: f64, speed: f64) -> bool {
+        let speed = speed.max(0.1);
+        self.move_progress_s += dt_s;
+        while self.move_progress_s >= 1.0 / speed {
+            let Some(next) = self.path.pop_front() else {
+                break;
+            };
+            self.tile = next;
+            self.move_progress_s -= 1.0 / speed;
+        }
+        if self.path.is_empty() {
+            self.move_progress_s = 0.0;
+        }
+        self.path.is_empty()
+    }
+
+    fn clear_job(&mut self) {
+        self.current_job = None;
+        self.job_phase = JobPhase::default();
+        self.job_progress_s = 0.0;
+        self.move_progress_s = 0.0;
+        self.path.clear();
+    }
+}
+
+fn agent_speed_for_role(role: Option<EmployeeRole>) -> f64 {
+    match role {
+        Some(EmployeeRole::Cariste) => 2.6,
+        Some(EmployeeRole::Patron | EmployeeRole::AdministrateurVente) => 1.4,
+        _ => 1.8,
+    }
+}
+
+fn job_work_type(kind: &JobKind) -> WorkType {
+    match kind {
+        JobKind::Haul { .. } => WorkType::Manutention,
+        JobKind::OperateMachine { .. } => WorkType::Production,
+        JobKind::Repair { .. } => WorkType::Maintenance,
+    }
+}
+
+// Ouvrier polyvalent sans fiche: manutention et production a priorite moyenne.
+// Sans monde (tests, simulation hors jeu): chemin en L (x puis y), sans la tuile de depart.
+fn straight_path(from: (i32, i32), to: (i32, i32)) -> VecDeque<(i32, i32)> {
+    let mut path = VecDeque::new();
+    let (mut x, mut y) = from;
+    while x != to.0 {
+        x += (to.0 - x).signum();
+        path.push_back((x, y));
+    }
+    while y != to.1 {
+        y += (to.1 - y).signum();
+        path.push_back((x, y));
+    }
+    path
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct FactoryKpi {
     pub throughput_per_hour: f64,
     pub scrap_total: u32,
     pub downtime_minutes: f64,
+    pub downtime_by_block: BTreeMap<BlockId, f64>,
     pub otif: f64,
+    pub hauls_completed: u32,
+    // Matiere deposee en entree ligne par les manutentions.
+    pub raw_hauled_total: u32,
+    pub haul_seconds_total: f64,
+    pub haul_tiles_total: u32,
+    pub energy_kwh_per_box: f64,
+    // Tri qualite cumule de toutes les lignes.
+    pub quality: QualityStats,
+}
+
+impl FactoryKpi {
+    pub fn mean_haul_seconds(&self) -> f64 {
+        if self.hauls_completed == 0 {
+            0.0
+        } else {
+            self.haul_seconds_total / self.hauls_completed as f64
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -925,10 +1574,37 @@ pub struct AgentDebugView {
     pub label: String,
 }
 
+#[derive(Clone, Debug)]
+struct ModernLineGroup {
+    line_id: ProductionLineId,
+    block_ids: Vec<BlockId>,
+    reason: Option<String>,
+}
+
+// Trajet deja introuvable pour un job: tant que le monde, l'agent et les cibles
+// n'ont pas bouge, A* donnerait le meme echec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct UnreachablePath {
+    world_revision: u64,
+    from: (i32, i32),
+    target: Option<(i32, i32)>,
+    destination: Option<(i32, i32)>,
+}
+
+// Trajet source -> destination d'un job deja trouve dans cette revision du monde:
+// seul le trajet de l'agent vers la source reste a calculer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ReachableLeg {
+    world_revision: u64,
+    from: (i32, i32),
+    to: (i32, i32),
+}
+
 #[derive(Clone, Debug)]
 struct ModernLineReadinessCache {
     dirty: bool,
     reason: Option<String>,
+    groups: Vec<ModernLineGroup>,
 }
 
 impl Default for ModernLineReadinessCache {
@@ -936,6 +1612,7 @@ impl Default for ModernLineReadinessCache {
         Self {
             dirty: true,
             reason: None,
+            groups: Vec::new(),
         }
     }
 }
@@ -949,6 +1626,8 @@ pub(crate) struct FactoryLayoutAsset {
     zones: ZoneLayer,
     blocks: Vec<BlockInstance>,
     agent_tile: (i32, i32),
+    // Lavabos de la carte: sauvegardes avec l'usine pour les runs sans editeur.
+    lavabo_tiles: Vec<(i32, i32)>,
 }
 
 impl Default for FactoryLayoutAsset {
@@ -960,6 +1639,7 @@ impl Default for FactoryLayoutAsset {
             zones: ZoneLayer::new(25, 15, ZoneKind::Neutral),
             blocks: Vec::new(),
             agent_tile: (4, 10),
+            lavabo_tiles: Vec::new(),
         }
     }
 }
@@ -1066,6 +1746,28 @@ pub(crate) struct FactorySimSaveAsset {
     pub production_lines: Vec<ProductionLineState>,
     pub line: StarterLineState,
     pub layout: FactoryLayoutAsset,
+    #[serde(default)]
+    pub lots: LotBook,
+    // Absent des vieilles sauvegardes: on repart de la graine de la config.
+    #[serde(default)]
+    pub rng: Option<RngStreams>,
+    #[serde(default)]
+    pub tick: u64,
+    // Prix fixe en partie (SetSalePrice); absent = prix de la config.
+    #[serde(default)]
+    pub sale_price: Option<f64>,
+    #[serde(default)]
+    pub market: MarketState,
+    #[serde(default)]
+    pub finance: FinanceState,
+    #[serde(default)]
+    pub energy: EnergyState,
+    #[serde(default)]
+    pub recruitment: RecruitmentBoard,
+    #[serde(default)]
+    pub hse: HseState,
+    #[serde(default)]
+    pub quality_control: QualityControl,
 }
 
 fn default_factory_sim_save_schema_version() -> u32 {
@@ -1077,15 +1779,47 @@ pub struct FactorySim {
     pub economy: Economy,
     pub config: StarterSimConfig,
     pub line: StarterLineState,
+    recipes: RecipeBook,
+    shifts: ShiftBook,
+    market_book: MarketBook,
+    suppliers: SupplierCatalogue,
+    finance_book: FinanceBook,
+    finance: FinanceState,
+    energy_book: EnergyBook,
+    energy: EnergyState,
+    quality_book: QualityBook,
+    needs_book: NeedsBook,
+    morale_book: MoraleBook,
+    training_book: TrainingBook,
+    recruitment_book: RecruitmentBook,
+    recruitment: RecruitmentBoard,
+    hse_book: HseBook,
+    hse: HseState,
+    quality_control: QualityControl,
+    // Allure des candidats au recrutement.
+    character_catalog: CharacterCatalog,
+    // Decors "Lavabo" de la carte, poses par commande (SetLavaboTiles).
+    lavabo_tiles: Vec<(i32, i32)>,
+    rng: RngStreams,
+    // Nombre de pas de simulation depuis le debut de partie.
+    tick: u64,
+    journal: Option<CommandLog>,
+    // Revision de carte attendue par le journal; toute autre valeur vient d'une
+    // retouche hors commandes et coupe l'enregistrement.
+    journal_world_revision: Option<u64>,
+    lots: LotBook,
     personnel: PersonnelState,
     stock: StockState,
     production_lines: Vec<ProductionLineState>,
     sales: SalesState,
+    market: MarketState,
     zones: ZoneLayer,
     blocks: Vec<BlockInstance>,
     jobs: Vec<Job>,
     reservations: HashMap<ReservationKey, Reservation>,
-    agent: SimAgent,
+    agents: Vec<SimAgent>,
+    agent_spawn_tile: (i32, i32),
+    next_agent_id: AgentId,
     kpi: FactoryKpi,
     zone_kpi: BTreeMap<ZoneKind, ZoneKpi>,
     next_block_id: BlockId,
@@ -1107,6 +1841,8 @@ pub struct FactorySim {
     build_status_ttl_s: f64,
     production_status: String,
     modern_line_cache: ModernLineReadinessCache,
+    unreachable_paths: HashMap<JobId, UnreachablePath>,
+    reachable_legs: HashMap<JobId, ReachableLeg>,
 }
 
 impl FactorySim {
@@ -1120,11 +1856,58 @@ impl FactorySim {
         let (cfg, cfg_warning) = StarterSimConfig::load_or_create_with_warning(path);
         let (layout, layout_warning) =
             Self::load_or_create_layout(FACTORY_LAYOUT_PATH, map_w, map_h, &cfg);
+        let (recipes, recipes_warning) = RecipeBook::load_or_create_with_warning(RECIPE_BOOK_PATH);
+        let (shifts, shifts_warning) = ShiftBook::load_or_create_with_warning(SHIFT_BOOK_PATH);
+        let (market_book, market_warning) =
+            MarketBook::load_or_create_with_warning(MARKET_BOOK_PATH);
+        let (suppliers, suppliers_warning) =
+            SupplierCatalogue::load_or_create_with_warning(SUPPLIER_CATALOGUE_PATH);
+        let (finance_book, finance_warning) =
+            FinanceBook::load_or_create_with_warning(FINANCE_BOOK_PATH);
+        let (energy_book, energy_warning) =
+            EnergyBook::load_or_create_with_warning(ENERGY_BOOK_PATH);
+        let (quality_book, quality_warning) =
+            QualityBook::load_or_create_with_warning(QUALITY_BOOK_PATH);
+        let (needs_book, needs_warning) = NeedsBook::load_or_create_with_warning(NEEDS_BOOK_PATH);
+        let (morale_book, morale_warning) =
+            MoraleBook::load_or_create_with_warning(MORALE_BOOK_PATH);
+        let (training_book, training_warning) =
+            TrainingBook::load_or_create_with_warning(TRAINING_BOOK_PATH);
+        let (recruitment_book, recruitment_warning) =
+            RecruitmentBook::load_or_create_with_warning(RECRUITMENT_BOOK_PATH);
+        let (hse_book, hse_warning) = HseBook::load_or_create_with_warning(HSE_BOOK_PATH);
         let mut sim = Self::from_layout(cfg, layout);
-        let warnings = [cfg_warning, layout_warning]
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>();
+        sim.recipes = recipes;
+        sim.shifts = shifts;
+        sim.market_book = market_book;
+        sim.suppliers = suppliers;
+        sim.finance_book = finance_book;
+        sim.energy_book = energy_book;
+        sim.quality_book = quality_book;
+        sim.needs_book = needs_book;
+        sim.morale_book = morale_book;
+        sim.training_book = training_book;
+        sim.recruitment_book = recruitment_book;
+        sim.hse_book = hse_book;
+        let warnings = [
+            cfg_warning,
+            layout_warning,
+            recipes_warning,
+            shifts_warning,
+            market_warning,
+            suppliers_warning,
+            finance_warning,
+            energy_warning,
+            quality_warning,
+            needs_warning,
+            morale_warning,
+            training_warning,
+            recruitment_warning,
+            hse_warning,
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
         if !warnings.is_empty() {
             sim.set_status_line(format!(
                 "Donnees de demarrage degradees: {}",
@@ -1184,30 +1967,57 @@ impl FactorySim {
         let personnel = PersonnelState::default();
         let stock = StockState::default();
         let line = StarterLineState::new();
+        let rng = RngStreams::new(config.seed);
 
         Self {
             clock: SimClock::new(),
             economy: Economy::new(config.starting_cash),
             config,
             line,
+            recipes: RecipeBook::default(),
+            shifts: ShiftBook::default(),
+            market_book: MarketBook::default(),
+            suppliers: SupplierCatalogue::default(),
+            finance_book: FinanceBook::default(),
+            finance: FinanceState::default(),
+            energy_book: EnergyBook::default(),
+            energy: EnergyState::default(),
+            quality_book: QualityBook::default(),
+            needs_book: NeedsBook::default(),
+            morale_book: MoraleBook::default(),
+            training_book: TrainingBook::default(),
+            recruitment_book: RecruitmentBook::default(),
+            recruitment: RecruitmentBoard::default(),
+            hse_book: HseBook::default(),
+            hse: HseState::default(),
+            quality_control: QualityControl::default(),
+            character_catalog: CharacterCatalog::load_default()
+                .expect("default character catalog should be valid"),
+            lavabo_tiles: layout.lavabo_tiles,
+            rng,
+            tick: 0,
+            journal: None,
+            journal_world_revision: None,
+            lots: LotBook::default(),
             personnel,
             stock,
             production_lines,
             sales: SalesState::default(),
+            market: MarketState::default(),
             zones: layout.zones,
             blocks: layout.blocks,
             jobs: Vec::new(),
             reservations: HashMap::new(),
-            agent: SimAgent {
-                id: 1,
-                tile: layout.agent_tile,
-                speed_tiles_per_s: 1.8,
-                fatigue: 10.0,
-                stress: 7.0,
-                current_job: None,
-                job_progress_s: 0.0,
-                decision_debug: "inactif".to_string(),
-            },
+            // Agent 1: le patron en personne; sans fiche paie tant qu'aucun employe Patron
+            // n'existe, il ne prend aucun job.
+            agents: vec![SimAgent::new(
+                1,
+                None,
+                Some(EmployeeRole::Patron),
+                layout.agent_tile,
+            )],
+            agent_spawn_tile: layout.agent_tile,
+            next_agent_id: 2,
             kpi: FactoryKpi::default(),
             zone_kpi,
             next_block_id,
@@ -1229,6 +2039,8 @@ impl FactorySim {
             build_status_ttl_s: 0.0,
             production_status: "Simulation initialisee".to_string(),
             modern_line_cache: ModernLineReadinessCache::default(),
+            unreachable_paths: HashMap::new(),
+            reachable_legs: HashMap::new(),
         }
     }
 
@@ -1257,62 +2069,313 @@ impl FactorySim {
 
     fn cached_modern_line_readiness_reason(&mut self) -> Option<String> {
         if self.modern_line_cache.dirty {
-            self.modern_line_cache.reason = self.modern_line_readiness_reason_uncached();
+            self.refresh_modern_line_topology();
             self.modern_line_cache.dirty = false;
         }
         self.modern_line_cache.reason.clone()
     }
 
-    fn refresh_production_status(&mut self, modern_readiness_reason: Option<&str>) {
-        if self.line.finished > 0 && !self.sales_operational() {
-            self.set_production_status(format!(
-                "Vente en attente: {} (stock fini={})",
-                self.sales_block_reason(),
-                self.line.finished
-            ));
-            return;
-        }
+    fn refresh_modern_line_topology(&mut self) {
+        let groups = self.modern_line_groups();
+        let group_block_ids = groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|&idx| self.blocks[idx].id)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let line_ids = self.assign_modern_line_ids(&group_block_ids);
+        let reasons = groups
+            .iter()
+            .map(|group| self.modern_group_readiness_reason(group))
+            .collect::<Vec<_>>();
+        self.modern_line_cache.reason = self.overall_modern_readiness_reason(&groups, &reasons);
+        self.modern_line_cache.groups = line_ids
+            .into_iter()
+            .zip(group_block_ids)
+            .zip(reasons)
+            .map(|((line_id, block_ids), reason)| ModernLineGroup {
+                line_id,
+                block_ids,
+                reason,
+            })
+            .collect();
+    }
 
-        if self.main_line_state().status == LineOperationalState::Bloquee {
-            let reason = self.main_line_state().block_reason.clone();
-            self.set_production_status(format!("Ligne 1 bloquee: {reason}"));
-            return;
-        }
+    // Rattache chaque groupe connexe a une ligne: recouvrement de blocs d'abord,
+    // puis lignes libres, puis nouvelle ligne. Les lignes orphelines disparaissent
+    // (sauf la ligne principale qui porte aussi la ligne legacy).
+    fn assign_modern_line_ids(&mut self, groups: &[Vec<BlockId>]) -> Vec<ProductionLineId> {
+        let mut taken = HashSet::new();
+        let mut assigned: Vec<Option<ProductionLineId>> = vec![None; groups.len()];
 
-        if let Some(reason) = modern_readiness_reason {
-            if self.modern_line_present() {
-                self.set_production_status(format!(
-                    "Ligne de production non operationnelle: {reason}"
-                ));
-            } else {
-                self.set_production_status(format!(
-                    "Ligne legacy active | finis={} ventes={}",
-                    self.line.finished, self.line.sold_total
-                ));
+        for (slot, group) in assigned.iter_mut().zip(groups) {
+            let best = self
+                .production_lines
+                .iter()
+                .filter(|line| !taken.contains(&line.id))
+                .map(|line| {
+                    let overlap = line
+                        .block_ids
+                        .iter()
+                        .filter(|id| group.contains(id))
+                        .count();
+                    (overlap, std::cmp::Reverse(line.id))
+                })
+                .filter(|(overlap, _)| *overlap > 0)
+                .max();
+            if let Some((_, std::cmp::Reverse(line_id))) = best {
+                taken.insert(line_id);
+                *slot = Some(line_id);
             }
-            return;
         }
 
-        self.set_production_status(format!(
-            "Ligne complete active | sacs bleus={} sacs rouges={} boxes bleues={}",
-            self.line.sacs_bleus_total, self.line.sacs_rouges_total, self.line.boxes_bleues_total
-        ));
-    }
+        for slot in assigned.iter_mut().filter(|slot| slot.is_none()) {
+            let free = self
+                .production_lines
+                .iter()
+                .filter(|line| !taken.contains(&line.id) && line.block_ids.is_empty())
+                .map(|line| line.id)
+                .min();
+            let line_id = free.unwrap_or_else(|| {
+                let id = self
+                    .production_lines
+                    .iter()
+                    .map(|line| line.id)
+                    .max()
+                    .unwrap_or(0)
+                    .saturating_add(1);
+                self.production_lines.push(ProductionLineState::new(id));
+                id
+            });
+            taken.insert(line_id);
+            *slot = Some(line_id);
+        }
+
+        let assigned = assigned.into_iter().flatten().collect::<Vec<_>>();
+        for line in &mut self.production_lines {
+            line.block_ids = assigned
+                .iter()
+                .position(|id| *id == line.id)
+                .map(|index| groups[index].clone())
+                .unwrap_or_default();
+        }
 
-    fn sale_office_count(&self) -> usize {
-        self.blocks
+        let orphaned = self
+            .production_lines
             .iter()
-            .filter(|block| {
-                block.kind == BlockKind::Seller
-                    && self.zones.get(block.origin_tile) == ZoneKind::Support
-            })
-            .count()
+            .filter(|line| line.id != MAIN_PRODUCTION_LINE_ID && !taken.contains(&line.id))
+            .map(|line| line.id)
+            .collect::<Vec<_>>();
+        let heir = taken.iter().min().copied();
+        for line_id in orphaned {
+            self.personnel.release_line(line_id);
+            if let Some(flow) = self.line.flows.remove(&line_id) {
+                self.hand_over_orphan_flow(line_id, flow, heir);
+            }
+            self.production_lines.retain(|line| line.id != line_id);
+        }
+        self.production_lines.sort_by_key(|line| line.id);
+        assigned
     }
 
-    fn effective_sale_price_eur(&self) -> f64 {
-        // Les anciennes configs avaient sale_price=45, ce qui rend une box moderne non rentable.
+    // Matiere rendue au stock (le surplus repart en reception), boxes a l'expedition,
+    // en-cours repris par la ligne heritiere; ce qu'elle ne peut pas prendre part au rebut.
+    fn hand_over_orphan_flow(
+        &mut self,
+        line_id: ProductionLineId,
+        mut flow: LineFlowState,
+        heir: Option<ProductionLineId>,
+    ) {
+        self.line.raw = self.line.raw.saturating_add(flow.raw);
+        self.stock
+            .raw_line_input_lots
+            .extend(&flow.raw_lots.drain_all());
+        let overflow = self
+            .line
+            .raw
+            .saturating_sub(crate::gestion::RAW_LINE_INPUT_CAPACITY);
+        if overflow > 0 {
+            self.line.raw -= overflow;
+            let portions = self.stock.raw_line_input_lots.take(overflow);
+            self.stock.raw_receiving_lots.extend(&portions);
+            self.stock.raw_receiving = self.stock.raw_receiving.saturating_add(overflow);
+        }
+        self.stock.raw_line_input = self.line.raw;
+        self.line.finished = self.line.finished.saturating_add(flow.boxes_to_ship);
+        flow.boxes_to_ship = 0;
+
+        let lost = match heir {
+            Some(heir) => {
+                let recipe = self.recipes.active().clone();
+                let target = self.line.flows.entry(heir).or_default();
+                target.sync_stage_runtimes(&recipe);
+                target.absorb(flow)
+            }
+            None => flow.units_in_progress(),
+        };
+        if lost > 0 {
+            self.kpi.scrap_total = self.kpi.scrap_total.saturating_add(lost);
+            self.set_action_status(format!(
+                "Ligne {line_id} demontee: {lost} unite(s) en cours au rebut"
+            ));
+        }
+    }
+
+    fn modern_line_groups(&self) -> Vec<Vec<usize>> {
+        let modern = self
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, block)| block.kind.is_modern_line_component().then_some(idx))
+            .collect::<Vec<_>>();
+        let mut visited = HashSet::new();
+        let mut groups = Vec::new();
+        for &start in &modern {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut group = vec![start];
+            let mut cursor = 0;
+            while cursor < group.len() {
+                let current = group[cursor];
+                cursor += 1;
+                for &other in &modern {
+                    if visited.contains(&other) {
+                        continue;
+                    }
+                    let a = &self.blocks[current];
+                    let b = &self.blocks[other];
+                    if Self::modern_line_blocks_touch(
+                        (a.kind, a.origin_tile, a.footprint, a.orientation),
+                        (b.kind, b.origin_tile, b.footprint, b.orientation),
+                    ) {
+                        visited.insert(other);
+                        group.push(other);
+                    }
+                }
+            }
+            if group
+                .iter()
+                .any(|&idx| self.blocks[idx].kind.anchors_production_line())
+            {
+                group.sort_by_key(|&idx| self.blocks[idx].id);
+                groups.push(group);
+            }
+        }
+        groups.sort_by_key(|group| self.blocks[group[0]].id);
+        groups
+    }
+
+    fn overall_modern_readiness_reason(
+        &self,
+        groups: &[Vec<usize>],
+        reasons: &[Option<String>],
+    ) -> Option<String> {
+        if groups.is_empty() {
+            return Some(self.missing_modern_block_reason().unwrap_or_else(|| {
+                format!("Bloc manquant: {}", BlockKind::InputHopper.buyable_label())
+            }));
+        }
+        if reasons.iter().any(Option::is_none) {
+            return None;
+        }
+        // On remonte en priorite le diagnostic d'une ligne qui a deja son entree.
+        groups
+            .iter()
+            .zip(reasons)
+            .find(|(group, _)| {
+                group
+                    .iter()
+                    .any(|&idx| self.blocks[idx].kind == BlockKind::InputHopper)
+            })
+            .or_else(|| groups.iter().zip(reasons).next())
+            .and_then(|(_, reason)| reason.clone())
+    }
+
+    fn refresh_production_status(&mut self, modern_readiness_reason: Option<&str>) {
+        if self.line.finished > 0 && !self.sales_operational() {
+            self.set_production_status(format!(
+                "Vente en attente: {} (stock fini={})",
+                self.sales_block_reason(),
+                self.line.finished
+            ));
+            return;
+        }
+
+        if self.production_lines.len() > 1 {
+            let active = self
+                .production_lines
+                .iter()
+                .filter(|line| line.status == LineOperationalState::Active)
+                .count();
+            if active == 0 {
+                let reasons = self
+                    .production_lines
+                    .iter()
+                    .map(|line| format!("L{} {}", line.id, line.block_reason))
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                self.set_production_status(format!("Lignes bloquees: {reasons}"));
+            } else {
+                self.set_production_status(format!(
+                    "Lignes actives {}/{} | sacs bleus={} sacs rouges={} boxes bleues={}",
+                    active,
+                    self.production_lines.len(),
+                    self.line.sacs_bleus_total,
+                    self.line.sacs_rouges_total,
+                    self.line.boxes_bleues_total
+                ));
+            }
+            return;
+        }
+
+        if self.main_line_state().status == LineOperationalState::Bloquee {
+            let reason = self.main_line_state().block_reason.clone();
+            self.set_production_status(format!("Ligne 1 bloquee: {reason}"));
+            return;
+        }
+
+        if let Some(reason) = modern_readiness_reason {
+            if self.modern_line_present() {
+                self.set_production_status(format!(
+                    "Ligne de production non operationnelle: {reason}"
+                ));
+            } else {
+                self.set_production_status(format!(
+                    "Ligne legacy active | finis={} ventes={}",
+                    self.line.finished, self.line.sold_total
+                ));
+            }
+            return;
+        }
+
+        self.set_production_status(format!(
+            "Ligne complete active | sacs bleus={} sacs rouges={} boxes bleues={}",
+            self.line.sacs_bleus_total, self.line.sacs_rouges_total, self.line.boxes_bleues_total
+        ));
+    }
+
+    fn sale_office_count(&self) -> usize {
+        self.blocks
+            .iter()
+            .filter(|block| {
+                block.kind == BlockKind::Seller
+                    && self.zones.get(block.origin_tile) == ZoneKind::Support
+            })
+            .count()
+    }
+
+    pub fn sale_price_eur(&self) -> f64 {
+        self.effective_sale_price_eur()
+    }
+
+    fn effective_sale_price_eur(&self) -> f64 {
+        // Les anciennes configs avaient sale_price=45, ce qui rend une box moderne non rentable.
         // On protege la boucle economique: une box moderne doit financer matiere + salaires.
-        if self.config.sale_price.is_finite() && self.config.sale_price >= 100.0 {
+        if self.config.sale_price.is_finite() && self.config.sale_price >= MIN_SALE_PRICE_EUR {
             self.config.sale_price
         } else {
             DEFAULT_FINISHED_BOX_PRICE_EUR
@@ -1339,80 +2402,151 @@ impl FactorySim {
         &mut self.production_lines[index]
     }
 
-    fn line_has_work_in_progress(&self) -> bool {
-        self.line.wip > 0
-            || self.line.washed > 0
-            || self.line.sliced > 0
-            || self.line.dehydrated > 0
-            || self.line.flakes > 0
-            || self.line.machine_a_busy
-            || self.line.machine_b_busy
-            || self.line.lavage_busy
-            || self.line.coupe_busy
-            || self.line.four_busy
-            || self.line.floc_busy
-            || self.line.sortex_busy
+    fn line_state(&self, line_id: ProductionLineId) -> Option<&ProductionLineState> {
+        self.production_lines.iter().find(|line| line.id == line_id)
+    }
+
+    fn line_state_mut(&mut self, line_id: ProductionLineId) -> Option<&mut ProductionLineState> {
+        self.production_lines
+            .iter_mut()
+            .find(|line| line.id == line_id)
+    }
+
+    fn production_line_ids(&self) -> Vec<ProductionLineId> {
+        self.production_lines.iter().map(|line| line.id).collect()
+    }
+
+    fn modern_group_for_line(&self, line_id: ProductionLineId) -> Option<&ModernLineGroup> {
+        self.modern_line_cache
+            .groups
+            .iter()
+            .find(|group| group.line_id == line_id)
+    }
+
+    fn line_has_work_in_progress(&self, line_id: ProductionLineId) -> bool {
+        if self.modern_line_present() {
+            return self
+                .line
+                .flow(line_id)
+                .is_some_and(LineFlowState::has_work_in_progress);
+        }
+        line_id == MAIN_PRODUCTION_LINE_ID
+            && (self.line.wip > 0 || self.line.machine_a_busy || self.line.machine_b_busy)
     }
 
-    fn production_staffing_factor(&self) -> f64 {
-        self.main_line_state().staffing_factor.max(0.1)
+    fn production_staffing_factor(&self, line_id: ProductionLineId) -> f64 {
+        let staffing = self
+            .line_state(line_id)
+            .map(|line| line.staffing_factor)
+            .unwrap_or(0.0);
+        (staffing * self.line_crew_speed_factor(line_id)).max(0.1)
     }
 
-    fn refresh_line_statuses(&mut self, modern_readiness_reason: Option<&str>) -> Option<String> {
-        let Some(lead_id) = self
+    // Vitesse moyenne des operateurs presents selon leurs besoins et leur moral.
+    fn line_crew_speed_factor(&self, line_id: ProductionLineId) -> f64 {
+        let (count, factor) = self
             .personnel
-            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
-            .map(|lead| lead.id)
-        else {
-            let line = self.main_line_state_mut();
-            line.assigned_lead_id = None;
-            line.set_blocked("aucun chef d'equipe assigne");
+            .line_operators(line_id)
+            .fold((0usize, 0.0), |(count, factor), employee| {
+                (count + 1, factor + self.employee_work_speed(employee))
+            });
+        if count == 0 {
+            1.0
+        } else {
+            factor / count as f64
+        }
+    }
+
+    fn employee_work_speed(&self, employee: &Employee) -> f64 {
+        self.needs_book
+            .speed_factor(employee.needs.deficit(&self.needs_book))
+            * self.morale_book.speed_factor(employee.morale as f64)
+    }
+
+    fn line_block_reason(
+        &self,
+        line_id: ProductionLineId,
+        has_lead: bool,
+        modern_present: bool,
+    ) -> Option<String> {
+        if !has_lead {
             return Some("aucun chef d'equipe assigne".to_string());
-        };
+        }
 
-        if self.modern_line_present()
-            && let Some(reason) = modern_readiness_reason
-        {
-            let line = self.main_line_state_mut();
-            line.assigned_lead_id = Some(lead_id);
-            line.set_blocked(reason.to_string());
-            return Some(reason.to_string());
+        if modern_present {
+            match self.modern_group_for_line(line_id) {
+                None => return Some("aucun bloc de ligne relie".to_string()),
+                Some(group) => {
+                    if let Some(reason) = &group.reason {
+                        return Some(reason.clone());
+                    }
+                }
+            }
         }
 
-        if self.line.raw == 0 && !self.line_has_work_in_progress() {
+        let line_raw = self.line.flow(line_id).map_or(0, LineFlowState::raw_qty);
+        if self.line.raw == 0 && line_raw == 0 && !self.line_has_work_in_progress(line_id) {
             let reason = if self.stock.raw_receiving > 0
-                && self.personnel.active_role_count(EmployeeRole::Cariste) == 0
+                && self.personnel.active_count_for_work(WorkType::Manutention) == 0
             {
                 "stock entree vide, caristes absents"
             } else {
                 "stock entree vide"
             };
-            let line = self.main_line_state_mut();
-            line.assigned_lead_id = Some(lead_id);
-            line.set_blocked(reason);
             return Some(reason.to_string());
         }
 
-        let active_temps = self.personnel.active_temps_for_lead(lead_id);
-        self.main_line_state_mut().set_active(lead_id, active_temps);
         None
     }
 
+    fn refresh_line_statuses(&mut self) {
+        let modern_present = self.modern_line_present();
+        for line_id in self.production_line_ids() {
+            let lead_id = self
+                .personnel
+                .team_lead_for_line(line_id)
+                .map(|lead| lead.id);
+            let reason = if self.line_in_staffed_hours(line_id) {
+                self.line_block_reason(line_id, lead_id.is_some(), modern_present)
+            } else {
+                let template = self
+                    .shifts
+                    .template_or_default(self.line_shift_template_id(line_id));
+                Some(format!("hors horaires ({})", template.label))
+            };
+            let active_temps = lead_id
+                .map(|id| self.personnel.working_temps_for_lead(id))
+                .unwrap_or(0);
+            let Some(line) = self.line_state_mut(line_id) else {
+                continue;
+            };
+            line.assigned_lead_id = lead_id;
+            match (reason, lead_id) {
+                (Some(reason), _) => line.set_blocked(reason),
+                (None, Some(lead_id)) => line.set_active(lead_id, active_temps),
+                (None, None) => line.set_blocked("aucun chef d'equipe assigne"),
+            }
+        }
+    }
+
     fn tick_team_leads_and_temps(&mut self, dt_sim: f64) {
         self.personnel.tick_temp_contracts(dt_sim);
 
-        let Some(lead) = self
-            .personnel
-            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
-            .cloned()
-        else {
+        for line_id in self.production_line_ids() {
+            self.tick_line_temps(line_id);
+        }
+    }
+
+    fn tick_line_temps(&mut self, line_id: ProductionLineId) {
+        let Some(lead) = self.personnel.team_lead_for_line(line_id).cloned() else {
             return;
         };
+        if !lead.is_active_for_business() {
+            return;
+        }
 
-        if !self.stock.has_any_raw_for_line() && !self.line_has_work_in_progress() {
-            let _ = self
-                .personnel
-                .release_finished_temps_without_stock(MAIN_PRODUCTION_LINE_ID);
+        if !self.stock.has_any_raw_for_line() && !self.line_has_work_in_progress(line_id) {
+            let _ = self.personnel.release_finished_temps_without_stock(line_id);
             return;
         }
 
@@ -1442,7 +2576,7 @@ impl FactorySim {
                 .personnel
                 .hire_temp_for_lead(
                     lead.id,
-                    MAIN_PRODUCTION_LINE_ID,
+                    line_id,
                     self.clock.seconds(),
                     TEMP_CONTRACT_SECONDS,
                 )
@@ -1451,25 +2585,181 @@ impl FactorySim {
                 break;
             }
 
-            self.economy.spend(TEMP_AGENCY_FEE_EUR);
+            self.economy.spend(Account::Interim, TEMP_AGENCY_FEE_EUR);
             active += 1;
 
             self.set_action_status(format!(
                 "{} recrute un interimaire pour la ligne {}",
-                lead.name, MAIN_PRODUCTION_LINE_ID
+                lead.name, line_id
             ));
         }
 
         if active > desired {
-            let _ = self
-                .personnel
-                .release_idle_finished_temps(MAIN_PRODUCTION_LINE_ID, desired);
+            let _ = self.personnel.release_idle_finished_temps(line_id, desired);
         }
     }
 
     fn tick_payroll(&mut self, dt_hours: f64) {
-        let payroll = self.personnel.hourly_payroll_eur();
-        self.economy.spend(payroll * dt_hours);
+        let payroll = self.payroll_per_hour();
+        self.economy.spend(Account::Salaires, payroll * dt_hours);
+    }
+
+    fn line_shift_template_id(&self, line_id: ProductionLineId) -> &str {
+        self.line_state(line_id)
+            .map(|line| line.shift_template.as_str())
+            .unwrap_or(crate::gestion::horaires::DEFAULT_SHIFT_TEMPLATE)
+    }
+
+    // Heures ouvertes: un poste du modele de la ligne couvre l'heure courante.
+    fn line_in_staffed_hours(&self, line_id: ProductionLineId) -> bool {
+        self.shifts
+            .template_or_default(self.line_shift_template_id(line_id))
+            .open_slot(self.clock.hour_of_day(), self.clock.is_weekend())
+            .is_some()
+    }
+
+    fn employee_on_shift(&self, employee: &Employee) -> bool {
+        let line_id = employee.assigned_line.unwrap_or(MAIN_PRODUCTION_LINE_ID);
+        match employee.role {
+            EmployeeRole::Patron => true,
+            _ if !employee.follows_own_shift() => self.line_in_staffed_hours(line_id),
+            _ => self
+                .shifts
+                .template_or_default(self.line_shift_template_id(line_id))
+                .slot_open(
+                    employee.shift_slot,
+                    self.clock.hour_of_day(),
+                    self.clock.is_weekend(),
+                ),
+        }
+    }
+
+    // Pointage: chacun prend ou quitte son poste selon le modele horaire de sa ligne.
+    fn tick_shifts(&mut self) {
+        let changes = self
+            .personnel
+            .employees
+            .iter()
+            .filter(|employee| {
+                !matches!(
+                    employee.status,
+                    EmployeeStatus::Termine | EmployeeStatus::EnFormation | EmployeeStatus::Blesse
+                )
+            })
+            .filter_map(|employee| {
+                let on_shift = self.employee_on_shift(employee);
+                let clocked_out = employee.status == EmployeeStatus::Absent;
+                (on_shift == clocked_out).then_some((employee.id, on_shift))
+            })
+            .collect::<Vec<_>>();
+        if changes.is_empty() {
+            return;
+        }
+
+        let mut arrived = 0;
+        for (employee_id, on_shift) in &changes {
+            if let Some(employee) = self.personnel.employee_mut(*employee_id) {
+                if *on_shift {
+                    employee.clock_in();
+                    arrived += 1;
+                } else {
+                    employee.clock_out();
+                }
+            }
+        }
+        self.set_action_status(format!(
+            "Pointage {}: {} arrivee(s), {} depart(s)",
+            self.clock.format_hhmm(),
+            arrived,
+            changes.len() - arrived
+        ));
+    }
+
+    // Commandes automatiques des politiques min/max; chaque decision reste dans le journal du stock.
+    fn tick_reorder_policies(&mut self) {
+        let decisions = self.stock.run_reorder_policies(
+            &self.suppliers,
+            self.economy.cash,
+            self.clock.seconds(),
+            self.rng.stream(RngStream::Achats),
+        );
+        for decision in decisions {
+            self.economy
+                .spend(Account::AchatsMatiere, decision.cost_eur());
+            self.set_action_status(format!("Reappro auto: {}", decision.summary()));
+        }
+    }
+
+    // Enregistre les livraisons comme lots et couvre le stock non trace par un lot d'ouverture.
+    fn sync_lot_queues(&mut self) {
+        let now_s = self.clock.seconds();
+        for receipt in self.stock.take_raw_receipts() {
+            let lot_id = self
+                .lots
+                .receive(receipt.supplier, now_s, receipt.grade, receipt.qty);
+            self.stock.raw_receiving_lots.push(lot_id, receipt.qty);
+        }
+
+        let missing = self
+            .stock
+            .raw_receiving
+            .saturating_sub(self.stock.raw_receiving_lots.total())
+            .saturating_add(
+                self.stock
+                    .raw_line_input
+                    .saturating_sub(self.stock.raw_line_input_lots.total()),
+            );
+        let opening_lot = if missing > 0 {
+            self.lots
+                .receive(OPENING_STOCK_SUPPLIER, now_s, QualityGrade::A, missing)
+        } else {
+            0
+        };
+        self.stock
+            .raw_receiving_lots
+            .reconcile(self.stock.raw_receiving, || opening_lot);
+        self.stock
+            .raw_line_input_lots
+            .reconcile(self.stock.raw_line_input, || opening_lot);
+        self.prune_lots();
+    }
+
+    // Une fois par jour: les lots encore en file, en machine ou echantillonnes
+    // restent, le reste suit la fenetre de tracabilite du registre.
+    fn prune_lots(&mut self) {
+        let now_s = self.clock.seconds();
+        if !self.lots.prune_due(now_s) {
+            return;
+        }
+        let line = &self.line;
+        let mut live = BTreeSet::new();
+        for flow in line.flows.values() {
+            flow.collect_live_lots(&mut live);
+        }
+        live.extend(line.packing_lots.iter().copied());
+        live.extend(
+            [
+                line.packing_last_lot,
+                line.machine_a_lot,
+                line.machine_b_lot,
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        for queue in [
+            &line.legacy_wip_lots,
+            &self.stock.raw_receiving_lots,
+            &self.stock.raw_line_input_lots,
+        ] {
+            live.extend(queue.portions.iter().map(|portion| portion.lot_id));
+        }
+        live.extend(
+            self.quality_control
+                .samples
+                .iter()
+                .filter_map(|sample| sample.lot_id),
+        );
+        self.lots.prune(&live, now_s, LOT_TRACEABILITY_WINDOW_S);
     }
 
     fn sync_line_raw_from_stock(&mut self) {
@@ -1480,73 +2770,261 @@ impl FactorySim {
         self.stock.raw_line_input = self.line.raw.min(crate::gestion::RAW_LINE_INPUT_CAPACITY);
     }
 
+    // Complete le tampon matiere de chaque ligne active depuis le stock d'entree,
+    // une unite a la fois au tampon le plus bas: une ligne ne peut pas affamer l'autre.
+    fn allocate_line_raw(&mut self, line_ids: &[ProductionLineId]) {
+        for line_id in line_ids {
+            self.line.flows.entry(*line_id).or_default();
+        }
+        while self.line.raw > 0 {
+            let Some(flow) = self
+                .line
+                .flows
+                .iter_mut()
+                .filter(|(line_id, flow)| {
+                    line_ids.contains(line_id) && flow.raw < LINE_RAW_BUFFER_UNITS
+                })
+                .map(|(_, flow)| flow)
+                .min_by_key(|flow| flow.raw)
+            else {
+                break;
+            };
+            self.line.raw -= 1;
+            flow.raw += 1;
+            flow.raw_lots
+                .extend(&self.stock.raw_line_input_lots.take(1));
+        }
+    }
+
+    // Demande spot, offres de contrats et echeances; les penalites sortent de la tresorerie.
+    fn tick_market(&mut self, dt_hours: f64) {
+        let sale_price = self.effective_sale_price_eur();
+        let events = self.market.tick(
+            &self.market_book,
+            dt_hours,
+            self.clock.seconds(),
+            self.clock.day_index(),
+            self.clock.hour_of_day(),
+            self.clock.is_weekend(),
+            sale_price,
+            &self.recipes.active().id,
+            self.rng.stream(RngStream::Marche),
+        );
+        for event in events {
+            match event {
+                // Les offres s'affichent dans l'onglet Ventes sans voler la ligne de statut.
+                MarketEvent::NewOffer(_) => {}
+                MarketEvent::ContractLate {
+                    id,
+                    missing,
+                    penalty_eur,
+                } => {
+                    self.economy.spend(Account::Penalites, penalty_eur);
+                    self.set_action_status(format!(
+                        "Contrat #{id} en retard: {missing} box manquantes, penalite {penalty_eur:.0} EUR"
+                    ));
+                }
+                MarketEvent::ContractCancelled { id, missing } => {
+                    self.set_action_status(format!(
+                        "Contrat #{id} annule par le client: {missing} box jamais livrees"
+                    ));
+                }
+            }
+        }
+    }
+
+    // La charge mesuree en fin de pas precedent s'applique a tout l'intervalle ecoule.
+    // Chaque facture est passee juste avant la cloture comptable de sa journee.
+    fn tick_energy(&mut self, start_s: f64, dt_sim: f64) {
+        let load_kw = self.factory_load_kw();
+        let bills = self.energy.tick(
+            &self.energy_book,
+            load_kw,
+            start_s,
+            dt_sim,
+            self.line.produced_finished_total,
+        );
+        for bill in bills {
+            self.economy
+                .spend_on_day(Account::Energie, bill.cost_eur, bill.day_index);
+            self.set_action_status(bill.summary());
+        }
+    }
+
+    fn block_running(&self, block: &BlockInstance, modern: bool) -> bool {
+        if block.machine.is_some_and(|machine| machine.broken) {
+            return false;
+        }
+        match block.kind {
+            BlockKind::MachineA => self.line.machine_a_busy,
+            BlockKind::MachineB => self.line.machine_b_busy,
+            BlockKind::Storage | BlockKind::Buffer => false,
+            BlockKind::Seller => self.sales_operational(),
+            kind if modern
+                && self
+                    .active_recipe()
+                    .stages
+                    .iter()
+                    .any(|stage| stage.block_kind == kind) =>
+            {
+                self.modern_stage_busy(self.modern_line_for_block(block.id), kind)
+            }
+            _ => self
+                .line_state(self.modern_line_for_block(block.id))
+                .is_some_and(|line| line.status == LineOperationalState::Active),
+        }
+    }
+
+    pub fn factory_load_kw(&self) -> f64 {
+        let modern = self.modern_line_present();
+        self.energy_book.base_load_kw
+            + self
+                .blocks
+                .iter()
+                .map(|block| {
+                    self.energy_book
+                        .power_kw(block.kind, self.block_running(block, modern))
+                })
+                .sum::<f64>()
+    }
+
+    // Agios, echeances de prets et alertes bancaires; la faillite arrete la partie.
+    fn tick_finance(&mut self, dt_hours: f64) {
+        let events = self.finance.tick(
+            &self.finance_book,
+            self.economy.cash,
+            self.clock.seconds(),
+            dt_hours / 24.0,
+            self.clock.day_index(),
+        );
+        for event in events {
+            match event {
+                FinanceEvent::OverdraftInterest { amount_eur } => {
+                    self.economy.spend(Account::Interets, amount_eur);
+                }
+                FinanceEvent::LoanInstallment {
+                    interest_eur,
+                    principal_eur,
+                    ..
+                } => {
+                    self.economy.spend(Account::Interets, interest_eur);
+                    self.economy.repay(Account::Emprunts, principal_eur);
+                }
+                FinanceEvent::LoanRepaid { id } => {
+                    self.set_action_status(format!("Pret #{id} integralement rembourse"));
+                }
+                FinanceEvent::OverdraftWarning {
+                    level,
+                    cash_eur,
+                    limit_eur,
+                } => {
+                    let message = match level {
+                        OverdraftLevel::Positif => continue,
+                        OverdraftLevel::Decouvert => format!(
+                            "Banque: compte a decouvert ({cash_eur:.0} EUR, autorise -{limit_eur:.0} EUR)"
+                        ),
+                        OverdraftLevel::ProcheLimite => format!(
+                            "Banque: decouvert proche de la limite ({cash_eur:.0} / -{limit_eur:.0} EUR)"
+                        ),
+                        OverdraftLevel::HorsLimite => format!(
+                            "Banque: decouvert autorise depasse, faillite dans {:.0} j sans redressement",
+                            self.finance_book.insolvency_grace_days
+                        ),
+                    };
+                    self.set_action_status(message);
+                }
+                FinanceEvent::Bankrupt { insolvent_days } => {
+                    self.set_status_line(format!(
+                        "FAILLITE: tresorerie hors decouvert autorise depuis {insolvent_days:.1} j, partie terminee"
+                    ));
+                }
+            }
+        }
+    }
+
     fn tick_sales(&mut self, dt_hours: f64) -> u32 {
-        let admins = self
-            .personnel
-            .active_role_count(EmployeeRole::AdministrateurVente);
+        let admins = self.personnel.active_count_for_work(WorkType::Vente);
 
         let offices = self.sale_office_count();
         let sale_price = self.effective_sale_price_eur();
+        let product_id = self.recipes.active().id.clone();
 
         let (sold, revenue) = self.sales.tick(
             dt_hours,
             &mut self.line.finished,
             admins,
             offices,
+            &mut self.market,
+            &product_id,
             sale_price,
+            self.clock.seconds(),
         );
 
         if sold > 0 {
             self.line.sold_total = self.line.sold_total.saturating_add(sold);
-            self.economy.earn(revenue);
+            self.economy.earn(Account::Ventes, revenue);
+            self.lots.mark_sold(sold, self.clock.seconds());
         }
 
         sold
     }
 
-    fn refresh_autonomous_employee_ai(&mut self, moved_raw: u32, sold: u32) {
+    fn refresh_autonomous_employee_ai(&mut self, sold: u32) {
         let now_s = self.clock.seconds();
-        let line_id = MAIN_PRODUCTION_LINE_ID;
-        let line_snapshot = self.main_line_state().clone();
         let offices = self.sale_office_count();
 
-        if let Some(lead) = self.personnel.team_lead_for_line(line_id).cloned() {
-            let label = if line_snapshot.status == LineOperationalState::Active {
-                format!(
-                    "Supervise {} | interimaires {}/3 | cadence x{:.2}",
-                    line_snapshot.label, line_snapshot.active_temps, line_snapshot.staffing_factor
-                )
-            } else {
-                format!(
-                    "Diagnostique {}: {}",
-                    line_snapshot.label, line_snapshot.block_reason
-                )
-            };
+        for line_snapshot in self.production_lines.clone() {
+            let line_id = line_snapshot.id;
+            if let Some(lead) = self.personnel.team_lead_for_line(line_id).cloned()
+                && lead.is_active_for_business()
+            {
+                let label = if line_snapshot.status == LineOperationalState::Active {
+                    format!(
+                        "Supervise {} | interimaires {}/3 | cadence x{:.2}",
+                        line_snapshot.label,
+                        line_snapshot.active_temps,
+                        line_snapshot.staffing_factor
+                    )
+                } else {
+                    format!(
+                        "Diagnostique {}: {}",
+                        line_snapshot.label, line_snapshot.block_reason
+                    )
+                };
 
-            self.personnel
-                .set_employee_work_task(lead.id, now_s, label, 88.0);
-        }
-
-        let active_temps = self.personnel.active_temps_for_line(line_id);
+                self.personnel
+                    .set_employee_work_task(lead.id, now_s, label, 88.0);
+            }
 
-        let temps_busy = if line_snapshot.status == LineOperationalState::Active {
-            active_temps
-        } else {
-            0
-        };
+            let active_temps = self.personnel.active_temps_for_line(line_id);
 
-        self.personnel.set_line_temps_activity(
-            line_id,
-            temps_busy,
-            now_s,
-            "Renfort production: lavage/coupe/deshydratation",
-            "Attend la relance de la ligne",
-            72.0,
-        );
+            let temps_busy = if line_snapshot.status == LineOperationalState::Active {
+                active_temps
+            } else {
+                0
+            };
 
-        let caristes = self.personnel.active_role_count(EmployeeRole::Cariste);
+            self.personnel.set_line_temps_activity(
+                line_id,
+                temps_busy,
+                now_s,
+                "Renfort production: lavage/coupe/deshydratation",
+                "Attend la relance de la ligne",
+                72.0,
+            );
+        }
 
-        let busy_caristes = if moved_raw > 0 { caristes.max(1) } else { 0 }.min(caristes);
+        let hauling = self
+            .agents
+            .iter()
+            .filter(|agent| agent.role == Some(EmployeeRole::Cariste))
+            .filter_map(|agent| agent.current_job)
+            .filter(|job_id| {
+                self.jobs
+                    .iter()
+                    .any(|job| job.id == *job_id && matches!(job.kind, JobKind::Haul { .. }))
+            })
+            .count();
 
         let cariste_idle = if self.stock.raw_receiving == 0 {
             "Attend une livraison matiere"
@@ -1558,16 +3036,14 @@ impl FactorySim {
 
         self.personnel.set_role_activity(
             EmployeeRole::Cariste,
-            busy_caristes,
+            hauling,
             now_s,
-            format!("Alimente la ligne: +{moved_raw} unites"),
+            format!("Manutention: {hauling} trajet(s) en cours"),
             cariste_idle,
             80.0,
         );
 
-        let admins = self
-            .personnel
-            .active_role_count(EmployeeRole::AdministrateurVente);
+        let admins = self.personnel.active_count_for_work(WorkType::Vente);
 
         let busy_admins = if sold > 0 || (self.line.finished > 0 && offices > 0) {
             admins.min(offices)
@@ -1595,51 +3071,105 @@ impl FactorySim {
             admin_idle,
             78.0,
         );
+
+        let controllers = self.personnel.active_count_for_work(WorkType::Controle);
+        let sample_window_s = self.quality_book.sample_interval_h * 3600.0;
+        let recent_sample = self
+            .quality_control
+            .last_sample()
+            .filter(|sample| now_s - sample.sampled_at_s <= sample_window_s);
+
+        self.personnel.set_role_activity(
+            EmployeeRole::ControleurQualite,
+            if recent_sample.is_some() {
+                controllers
+            } else {
+                0
+            },
+            now_s,
+            recent_sample
+                .map(|sample| format!("Analyse {}", sample.summary()))
+                .unwrap_or_default(),
+            "Attend des unites a echantillonner",
+            78.0,
+        );
     }
 
     pub fn step(&mut self, real_dt_seconds: f32) {
+        self.step_in_world(real_dt_seconds, None);
+    }
+
+    // Le monde vivant sert au calcul des trajets des agents (murs, allees).
+    pub fn step_in_world(&mut self, real_dt_seconds: f32, world: Option<&crate::World>) {
         let real_dt = real_dt_seconds as f64;
         if !real_dt.is_finite() || real_dt <= 0.0 {
             return;
         }
+        self.check_journal_world(world);
+        // Partie terminee: l'usine en faillite ne tourne plus.
+        if self.finance.is_bankrupt() {
+            return;
+        }
 
+        self.tick += 1;
         let dt_sim = real_dt * self.config.time_scale.max(0.0);
+        let step_start_s = self.clock.seconds();
         self.clock.advance(dt_sim);
+        self.tick_energy(step_start_s, dt_sim);
+        self.economy.tick(self.clock.seconds());
         self.tick_action_status(dt_sim);
         let dt_hours = dt_sim / 3600.0;
 
         self.stock.tick_purchase_orders(dt_sim);
+        self.tick_reorder_policies();
+        self.sync_lot_queues();
         self.refresh_static_capabilities();
+        self.tick_shifts();
+        self.tick_needs(dt_sim, world);
+        self.tick_break_exchanges(dt_sim);
+        self.tick_morale(dt_hours);
+        self.tick_training(dt_hours);
+        self.tick_recruitment();
         self.tick_payroll(dt_hours);
         self.tick_team_leads_and_temps(dt_sim);
 
-        let caristes = self.personnel.active_role_count(EmployeeRole::Cariste);
-        let moved_raw = self.stock.tick_cariste_transfer(dt_hours, caristes);
-
         self.sync_line_raw_from_stock();
 
         let modern_readiness_reason = self.cached_modern_line_readiness_reason();
 
-        let production_block_reason =
-            self.refresh_line_statuses(modern_readiness_reason.as_deref());
+        self.refresh_line_statuses();
 
-        if production_block_reason.is_none() {
-            if modern_readiness_reason.is_some() {
-                self.tick_legacy_line(dt_sim, dt_hours);
-            } else {
-                self.tick_modern_line(dt_sim);
+        if self.modern_line_present() {
+            let active_lines = self
+                .production_lines
+                .iter()
+                .filter(|line| line.status == LineOperationalState::Active)
+                .map(|line| line.id)
+                .collect::<Vec<_>>();
+            self.allocate_line_raw(&active_lines);
+            for line_id in &active_lines {
+                self.tick_modern_line(*line_id, dt_sim);
             }
-
+            if !active_lines.is_empty() {
+                self.sync_stock_raw_from_line();
+            }
+        } else if self.main_line_state().status == LineOperationalState::Active {
+            self.tick_legacy_line(dt_sim, dt_hours);
             self.sync_stock_raw_from_line();
         }
 
+        self.tick_market(dt_hours);
         let sold = self.tick_sales(dt_hours);
+        self.tick_finance(dt_hours);
 
-        self.refresh_autonomous_employee_ai(moved_raw, sold);
+        self.refresh_autonomous_employee_ai(sold);
         self.tick_reservations(dt_sim);
         self.sync_blocks_from_line();
         self.refresh_jobs();
-        self.tick_agent(dt_sim);
+        self.sync_agents_with_personnel();
+        self.tick_maintenance(dt_sim);
+        self.tick_hse(dt_sim);
+        self.tick_agents(dt_sim, world);
         self.refresh_kpi(dt_hours);
         self.refresh_production_status(modern_readiness_reason.as_deref());
     }
@@ -1655,19 +3185,33 @@ impl FactorySim {
 
     // --- Public, stable accessors for UI/debug (no string parsing) ---
     pub fn primary_agent_tile(&self) -> (i32, i32) {
-        self.agent.tile
+        self.primary_agent().tile
     }
 
     pub fn primary_agent_fatigue(&self) -> f64 {
-        self.agent.fatigue
+        self.primary_agent().fatigue
     }
 
     pub fn primary_agent_stress(&self) -> f64 {
-        self.agent.stress
+        self.primary_agent().stress
     }
 
     pub fn primary_agent_current_job_id(&self) -> Option<JobId> {
-        self.agent.current_job
+        self.primary_agent().current_job
+    }
+
+    // Le patron, quel que soit l'ordre de creation des agents.
+    fn primary_agent(&self) -> &SimAgent {
+        self.agents
+            .iter()
+            .find(|agent| agent.role == Some(EmployeeRole::Patron))
+            .unwrap_or(&self.agents[0])
+    }
+
+    pub fn agent_for_employee(&self, employee_id: EmployeeId) -> Option<&SimAgent> {
+        self.agents
+            .iter()
+            .find(|agent| agent.employee_id == Some(employee_id))
     }
 
     pub fn job_brief(&self, job_id: JobId) -> Option<String> {
@@ -1678,18 +3222,29 @@ impl FactorySim {
                 to_block,
                 item_kind,
                 qty,
+                line_id,
+                trip,
             } => {
                 let item_label = match item_kind {
                     ItemKind::Raw => "Matiere",
                     ItemKind::Wip => "Encours",
                     ItemKind::Finished => "Produit fini",
                     ItemKind::Scrap => "Rebut",
+                    ItemKind::Washed => "Lave",
+                    ItemKind::Sliced => "Coupe",
+                    ItemKind::Dehydrated => "Deshydrate",
+                    ItemKind::Flakes => "Flocons",
+                    ItemKind::Sorted => "Trie",
                 };
-                format!("Transport: {qty} x {item_label} (B{from_block} -> B{to_block})")
+                format!(
+                    "Transport: {qty} x {item_label} (B{from_block} -> B{to_block}, L{line_id} trajet {})",
+                    trip + 1
+                )
             }
             JobKind::OperateMachine { block_id } => {
                 format!("Operation machine (B{block_id})")
             }
+            JobKind::Repair { block_id } => format!("Reparation panne (B{block_id})"),
         };
         Some(brief)
     }
@@ -1701,18 +3256,26 @@ impl FactorySim {
                 to_block,
                 item_kind,
                 qty,
+                line_id,
+                ..
             } => {
                 let item_label = match item_kind {
                     ItemKind::Raw => "matiere",
                     ItemKind::Wip => "encours",
                     ItemKind::Finished => "produit fini",
                     ItemKind::Scrap => "rebut",
+                    ItemKind::Washed => "lave",
+                    ItemKind::Sliced => "coupe",
+                    ItemKind::Dehydrated => "deshydrate",
+                    ItemKind::Flakes => "flocons",
+                    ItemKind::Sorted => "trie",
                 };
-                format!("transport {qty}x {item_label} (B{from_block}->B{to_block})")
+                format!("transport {qty}x {item_label} L{line_id} (B{from_block}->B{to_block})")
             }
             JobKind::OperateMachine { block_id } => {
                 format!("operation machine (B{block_id})")
             }
+            JobKind::Repair { block_id } => format!("reparation (B{block_id})"),
         }
     }
 
@@ -1723,7 +3286,9 @@ impl FactorySim {
                 to_block,
                 ..
             } => format!("B{from_block}->B{to_block}"),
-            JobKind::OperateMachine { block_id } => format!("B{block_id}"),
+            JobKind::OperateMachine { block_id } | JobKind::Repair { block_id } => {
+                format!("B{block_id}")
+            }
         }
     }
 
@@ -1765,7 +3330,9 @@ impl FactorySim {
             ZoneKind::Receiving => ZoneKind::Processing,
             ZoneKind::Processing => ZoneKind::Shipping,
             ZoneKind::Shipping => ZoneKind::Support,
-            ZoneKind::Support => ZoneKind::Neutral,
+            ZoneKind::Support => ZoneKind::BreakRoom,
+            ZoneKind::BreakRoom => ZoneKind::Vending,
+            ZoneKind::Vending => ZoneKind::Neutral,
         };
         self.floor_paint_mode = false;
         self.zone_paint_mode = true;
@@ -1812,26 +3379,175 @@ impl FactorySim {
         &self.stock
     }
 
+    pub fn lots(&self) -> &LotBook {
+        &self.lots
+    }
+
     pub fn sales_state(&self) -> &SalesState {
         &self.sales
     }
 
+    pub fn market(&self) -> &MarketState {
+        &self.market
+    }
+
+    pub fn market_book(&self) -> &MarketBook {
+        &self.market_book
+    }
+
+    pub fn suppliers(&self) -> &SupplierCatalogue {
+        &self.suppliers
+    }
+
+    pub fn finance(&self) -> &FinanceState {
+        &self.finance
+    }
+
+    pub fn finance_book(&self) -> &FinanceBook {
+        &self.finance_book
+    }
+
+    pub fn energy(&self) -> &EnergyState {
+        &self.energy
+    }
+
+    pub fn energy_book(&self) -> &EnergyBook {
+        &self.energy_book
+    }
+
+    pub fn quality_book(&self) -> &QualityBook {
+        &self.quality_book
+    }
+
+    pub fn needs_book(&self) -> &NeedsBook {
+        &self.needs_book
+    }
+
+    pub fn morale_book(&self) -> &MoraleBook {
+        &self.morale_book
+    }
+
+    pub fn training_book(&self) -> &TrainingBook {
+        &self.training_book
+    }
+
+    pub fn recruitment_book(&self) -> &RecruitmentBook {
+        &self.recruitment_book
+    }
+
+    pub fn recruitment(&self) -> &RecruitmentBoard {
+        &self.recruitment
+    }
+
+    pub fn hse_book(&self) -> &HseBook {
+        &self.hse_book
+    }
+
+    pub fn hse(&self) -> &HseState {
+        &self.hse
+    }
+
+    pub fn quality_control(&self) -> &QualityControl {
+        &self.quality_control
+    }
+
+    // Vitesse de travail d'un employe selon ses besoins et son moral (1 = nominale).
+    pub fn employee_work_speed_factor(&self, employee_id: EmployeeId) -> Option<f64> {
+        self.personnel
+            .employee(employee_id)
+            .map(|employee| self.employee_work_speed(employee))
+    }
+
+    pub fn primary_agent_needs(&self) -> Option<&EmployeeNeeds> {
+        self.primary_agent()
+            .employee_id
+            .and_then(|employee_id| self.personnel.employee(employee_id))
+            .map(|employee| &employee.needs)
+    }
+
+    pub fn lavabo_tiles(&self) -> &[(i32, i32)] {
+        &self.lavabo_tiles
+    }
+
+    fn set_lavabo_tiles(&mut self, mut tiles: Vec<(i32, i32)>) -> Result<String, String> {
+        tiles.sort_unstable();
+        tiles.dedup();
+        self.lavabo_tiles = tiles;
+        Ok(format!(
+            "Lavabos: {} lieu(x) de pause",
+            self.lavabo_tiles.len()
+        ))
+    }
+
+    // Demande spot par heure du produit actif au prix courant, hors variation du jour.
+    pub fn market_demand_per_hour(&self) -> f64 {
+        self.market_book
+            .product(&self.recipes.active().id)
+            .map_or(0.0, |product| {
+                product.units_per_hour(
+                    self.clock.hour_of_day(),
+                    self.clock.is_weekend(),
+                    self.effective_sale_price_eur(),
+                    1.0,
+                )
+            })
+    }
+
     pub fn main_production_line(&self) -> &ProductionLineState {
         self.main_line_state()
     }
 
+    pub fn production_lines(&self) -> &[ProductionLineState] {
+        &self.production_lines
+    }
+
+    pub fn production_line(&self, line_id: ProductionLineId) -> Option<&ProductionLineState> {
+        self.line_state(line_id)
+    }
+
+    // Masse salariale courante: employes pointes, majorations nuit et week-end comprises.
+    // Majorations de nuit et de week-end pour qui tient son poste a cette heure; le patron
+    // et les absents payes (formation, arret) restent au taux de base.
     pub fn payroll_per_hour(&self) -> f64 {
-        self.personnel.hourly_payroll_eur()
+        let premium = self
+            .shifts
+            .wage_multiplier(self.clock.hour_of_day(), self.clock.is_weekend());
+        self.personnel
+            .employees
+            .iter()
+            .filter(|employee| employee.is_paid())
+            .map(|employee| {
+                let wage = employee.hourly_wage.max(0.0);
+                if self.employee_working_shift(employee) {
+                    wage * premium
+                } else {
+                    wage
+                }
+            })
+            .sum()
+    }
+
+    fn employee_working_shift(&self, employee: &Employee) -> bool {
+        employee.role != EmployeeRole::Patron
+            && employee.is_on_site()
+            && self.employee_on_shift(employee)
+    }
+
+    pub fn shifts(&self) -> &ShiftBook {
+        &self.shifts
     }
 
     pub fn sales_capacity_per_hour(&self) -> f64 {
         crate::gestion::vente::SalesState::capacity_units_per_hour(
-            self.personnel
-                .active_role_count(EmployeeRole::AdministrateurVente),
+            self.personnel.active_count_for_work(WorkType::Vente),
             self.sale_office_count(),
         )
     }
 
+    pub fn ledger(&self) -> &Ledger {
+        &self.economy.ledger
+    }
+
     pub fn revenue_total(&self) -> f64 {
         self.economy.revenue_total
     }
@@ -1856,6 +3572,72 @@ impl FactorySim {
         self.kpi.otif
     }
 
+    pub fn kpi(&self) -> &FactoryKpi {
+        &self.kpi
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    // Demarre le journal de rejeu a partir de l'etat courant (nouvelle partie ou chargement).
+    pub fn start_recording(&mut self, world: Option<&crate::World>) {
+        self.journal = Some(CommandLog::new(
+            self.config.clone(),
+            self.to_save_asset(),
+            world.cloned(),
+        ));
+        self.journal_world_revision = world.map(|world| world.revision);
+    }
+
+    pub fn command_log(&self) -> Option<CommandLog> {
+        self.journal
+            .as_ref()
+            .map(|journal| journal.clone().finished_at(self.tick))
+    }
+
+    fn record_event(&mut self, event: ReplayEvent) {
+        let tick = self.tick;
+        if let Some(journal) = self.journal.as_mut() {
+            journal.record(tick, event);
+        }
+    }
+
+    // Une carte modifiee hors simulation ne se rejoue pas: le journal est marque
+    // interrompu au pas courant et n'enregistre plus rien.
+    fn check_journal_world(&mut self, world: Option<&crate::World>) {
+        let (Some(world), Some(expected)) = (world, self.journal_world_revision) else {
+            return;
+        };
+        if world.revision == expected {
+            return;
+        }
+        self.journal_world_revision = None;
+        if let Some(journal) = self.journal.as_mut() {
+            journal.interrupt(self.tick);
+            self.set_status_line(format!(
+                "Carte modifiee au pas {}: journal de rejeu interrompu",
+                self.tick
+            ));
+        }
+    }
+
+    // Apres une commande journalisee, la carte qu'elle a modifiee devient la reference.
+    fn sync_journal_world(&mut self, world: Option<&crate::World>) {
+        if let (Some(world), Some(expected)) = (world, self.journal_world_revision.as_mut()) {
+            *expected = world.revision;
+        }
+    }
+
+    pub fn rng_seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    // Graine des sous-systemes hors simulation (PNJ, social) derivee de la meme graine.
+    pub fn rng_stream_seed(&self, stream: RngStream) -> u64 {
+        self.rng.stream_seed(stream)
+    }
+
     pub fn blocks(&self) -> &[BlockInstance] {
         &self.blocks
     }
@@ -1981,29 +3763,136 @@ impl FactorySim {
     }
 
     pub fn sales_operational(&self) -> bool {
-        self.sale_office_present
-            && self
-                .personnel
-                .active_role_count(EmployeeRole::AdministrateurVente)
-                > 0
+        self.sale_office_present && self.personnel.active_count_for_work(WorkType::Vente) > 0
     }
 
     pub fn sales_block_reason(&self) -> &'static str {
         if !self.sale_office_present {
             "Bureau de vente manquant dans zone vente"
-        } else if self
-            .personnel
-            .active_role_count(EmployeeRole::AdministrateurVente)
-            == 0
-        {
+        } else if self.personnel.active_count_for_work(WorkType::Vente) == 0 {
             "Aucun administrateur de vente"
+        } else if self.sales.last_block_reason
+            == crate::gestion::vente::SalesBlockReason::DemandeInsuffisante
+        {
+            "Demande client insuffisante"
         } else {
             "Operationnel"
         }
     }
 
     pub fn apply_command(&mut self, command: SimCommand) -> Result<String, String> {
+        self.apply_command_in_world(command, None)
+    }
+
+    // Les commandes de construction verifient murs et sols sur la carte vivante.
+    pub fn apply_command_in_world(
+        &mut self,
+        command: SimCommand,
+        world: Option<&mut crate::World>,
+    ) -> Result<String, String> {
+        self.check_journal_world(world.as_deref());
+        // Seules les commandes acceptees entrent au journal: un refus ne change rien.
+        let mut world = world;
+        let result = self.apply_command_charged(command.clone(), world.as_deref_mut(), true);
+        if result.is_ok() {
+            self.record_event(ReplayEvent::Command(command));
+            self.sync_journal_world(world.as_deref());
+        }
+        result
+    }
+
+    // Poses scriptees (Papa, validation de plan): construction offerte, journalisee a
+    // part pour que le rejeu la rejoue sans l'ouvrir aux commandes du joueur.
+    pub(crate) fn apply_scripted_build(
+        &mut self,
+        command: SimCommand,
+        world: Option<&mut crate::World>,
+    ) -> Result<String, String> {
+        if !matches!(
+            command,
+            SimCommand::PlaceBlock { .. }
+                | SimCommand::PaintZoneRect { .. }
+                | SimCommand::PaintFloorRect { .. }
+        ) {
+            return Err("pose scriptee: commande de construction attendue".to_string());
+        }
+        self.check_journal_world(world.as_deref());
+        let mut world = world;
+        let result = self.apply_command_charged(command.clone(), world.as_deref_mut(), false);
+        if result.is_ok() {
+            self.record_event(ReplayEvent::ScriptedBuild(command));
+            self.sync_journal_world(world.as_deref());
+        }
+        result
+    }
+
+    fn apply_command_charged(
+        &mut self,
+        command: SimCommand,
+        world: Option<&mut crate::World>,
+        charge_capex: bool,
+    ) -> Result<String, String> {
+        if self.finance.is_bankrupt() {
+            return Err("usine en faillite: partie terminee".to_string());
+        }
         match command {
+            SimCommand::PlaceBlock {
+                kind,
+                tile,
+                orientation,
+            } => {
+                let world = Self::command_world(world, "Pose de bloc")?;
+                self.place_block(world, kind, tile, orientation, charge_capex)
+                    .map(|(_, status)| status)
+            }
+            SimCommand::RemoveBlock { block_id } => self.remove_block(block_id),
+            SimCommand::MoveBlock { block_id, tile } => {
+                let world = Self::command_world(world, "Deplacement de bloc")?;
+                self.move_block(world, block_id, tile)
+            }
+            SimCommand::RotateBlock {
+                block_id,
+                orientation,
+            } => {
+                let world = Self::command_world(world, "Rotation de bloc")?;
+                self.rotate_block(world, block_id, orientation)
+            }
+            SimCommand::PaintZoneRect { origin, size, zone } => {
+                self.paint_zone_rect(origin, size, zone, charge_capex)
+            }
+            SimCommand::PaintFloorRect {
+                origin,
+                size,
+                floor,
+            } => {
+                let world = Self::command_world(world, "Pose de sol")?;
+                self.paint_floor_rect(world, origin, size, floor, charge_capex)
+            }
+            SimCommand::ResetFloorRect { origin, size } => {
+                let world = Self::command_world(world, "Pose de sol")?;
+                self.paint_floor_rect(world, origin, size, BuildFloorKind::Standard, false)
+            }
+            SimCommand::SetSalePrice { price_eur } => self.set_sale_price(price_eur),
+            SimCommand::SetLavaboTiles { tiles } => self.set_lavabo_tiles(tiles),
+            SimCommand::AcceptContract { contract_id } => {
+                self.market.accept(contract_id, self.clock.seconds())
+            }
+            SimCommand::DeclineContract { contract_id } => {
+                self.market.decline(contract_id, self.clock.seconds())
+            }
+            SimCommand::TakeLoan { offer_id } => {
+                let loan =
+                    self.finance
+                        .take_loan(&self.finance_book, &offer_id, self.clock.seconds())?;
+                let (id, principal, label) = (loan.id, loan.principal_eur, loan.label.clone());
+                self.economy.borrow(Account::Emprunts, principal);
+                Ok(format!("Pret #{id} obtenu: {label}, {principal:.0} EUR"))
+            }
+            SimCommand::RepayLoan { loan_id } => {
+                let amount = self.finance.repay_loan(loan_id, self.economy.cash)?;
+                self.economy.repay(Account::Emprunts, amount);
+                Ok(format!("Pret #{loan_id} rembourse: {amount:.0} EUR"))
+            }
             SimCommand::HireEmployee { role } => {
                 if !role.can_be_hired_by_player() {
                     return Err(format!(
@@ -2020,18 +3909,17 @@ impl FactorySim {
                         role.label()
                     ));
                 }
-                if cost > 0.0 {
-                    self.economy.spend(cost);
-                }
+                // Les frais ne partent qu'une fois l'embauche complete: une commande
+                // refusee ne laisse ni employe ni depense.
                 let id = self.personnel.hire(role, self.clock.seconds())?;
                 if role == EmployeeRole::ChefEquipe
-                    && self
-                        .personnel
-                        .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
-                        .is_none()
+                    && let Err(err) = self.assign_lead_to_free_line(id)
                 {
-                    self.personnel.assign_to_line(id, MAIN_PRODUCTION_LINE_ID)?;
-                    self.main_line_state_mut().assigned_lead_id = Some(id);
+                    self.personnel.cancel_hire(id);
+                    return Err(err);
+                }
+                if cost > 0.0 {
+                    self.economy.spend(Account::Recrutement, cost);
                 }
                 let name = self
                     .personnel
@@ -2047,12 +3935,13 @@ impl FactorySim {
                     .map(|employee| employee.role)
                     .ok_or_else(|| format!("employe introuvable: {employee_id}"))?;
                 self.personnel.fire(employee_id)?;
-                if role == EmployeeRole::ChefEquipe
-                    && self.main_line_state().assigned_lead_id == Some(employee_id)
-                {
-                    let line = self.main_line_state_mut();
-                    line.assigned_lead_id = None;
-                    line.set_blocked("aucun chef d'equipe assigne");
+                if role == EmployeeRole::ChefEquipe {
+                    for line in &mut self.production_lines {
+                        if line.assigned_lead_id == Some(employee_id) {
+                            line.assigned_lead_id = None;
+                            line.set_blocked("aucun chef d'equipe assigne");
+                        }
+                    }
                 }
                 Ok(format!("Employe licencie: #{employee_id}"))
             }
@@ -2060,11 +3949,29 @@ impl FactorySim {
                 employee_id,
                 line_id,
             } => {
-                if line_id != MAIN_PRODUCTION_LINE_ID {
+                if self.line_state(line_id).is_none() {
                     return Err(format!("ligne inconnue: {line_id}"));
                 }
+                let previous_lead = self
+                    .personnel
+                    .team_lead_for_line(line_id)
+                    .map(|employee| employee.id)
+                    .filter(|lead_id| *lead_id != employee_id);
                 self.personnel.assign_to_line(employee_id, line_id)?;
-                self.main_line_state_mut().assigned_lead_id = Some(employee_id);
+                if let Some(previous_lead) = previous_lead
+                    && let Some(employee) = self.personnel.employee_mut(previous_lead)
+                {
+                    employee.assigned_line = None;
+                    employee.task_label = "Attend une ligne a superviser".to_string();
+                }
+                for line in &mut self.production_lines {
+                    if line.id == line_id {
+                        line.assigned_lead_id = Some(employee_id);
+                    } else if line.assigned_lead_id == Some(employee_id) {
+                        line.assigned_lead_id = None;
+                        line.set_blocked("aucun chef d'equipe assigne");
+                    }
+                }
                 Ok(format!("Chef #{employee_id} assigne a la ligne {line_id}"))
             }
             SimCommand::SetLineTempPolicy {
@@ -2072,7 +3979,7 @@ impl FactorySim {
                 enabled,
                 max_temps,
             } => {
-                if line_id != MAIN_PRODUCTION_LINE_ID {
+                if self.line_state(line_id).is_none() {
                     return Err(format!("ligne inconnue: {line_id}"));
                 }
                 let lead_id = self
@@ -2088,207 +3995,270 @@ impl FactorySim {
                     max_temps.min(3)
                 ))
             }
-            SimCommand::BuyRawStock { qty } => {
-                let (order_id, cost) = self.stock.place_raw_order(qty, self.economy.cash)?;
-                self.economy.spend(cost);
+            SimCommand::BuyRawStock { supplier_id, qty } => {
+                let supplier = self
+                    .suppliers
+                    .supplier(&supplier_id)
+                    .ok_or_else(|| format!("fournisseur inconnu: {supplier_id}"))?;
+                let (order_id, cost) = self.stock.place_raw_order(
+                    supplier,
+                    qty,
+                    self.economy.cash,
+                    self.rng.stream(RngStream::Achats),
+                )?;
+                self.economy.spend(Account::AchatsMatiere, cost);
+                Ok(format!(
+                    "Commande matiere #{order_id} chez {}: {qty} unites ({cost:.0} EUR)",
+                    supplier.label
+                ))
+            }
+            SimCommand::BuySpareParts { qty } => {
+                let (order_id, cost) =
+                    self.stock.place_spare_parts_order(qty, self.economy.cash)?;
+                self.economy.spend(Account::PiecesDetachees, cost);
+                Ok(format!(
+                    "Commande pieces #{order_id}: {qty} pieces ({cost:.0} EUR)"
+                ))
+            }
+            SimCommand::SetReorderPolicy { policy } => {
+                let supplier = self
+                    .suppliers
+                    .supplier(&policy.supplier_id)
+                    .ok_or_else(|| format!("fournisseur inconnu: {}", policy.supplier_id))?;
+                let message = if policy.enabled {
+                    format!(
+                        "Reappro auto ON: point {} / cible {} chez {}, reserve {:.0} EUR",
+                        policy.reorder_point,
+                        policy.order_up_to,
+                        supplier.label,
+                        policy.min_cash_reserve_eur
+                    )
+                } else {
+                    "Reappro auto OFF".to_string()
+                };
+                self.stock.set_reorder_policy(policy)?;
+                Ok(message)
+            }
+            SimCommand::SetWorkPriority {
+                employee_id,
+                work,
+                priority,
+            } => {
+                self.personnel
+                    .set_work_priority(employee_id, work, priority)?;
+                Ok(if priority == WORK_PRIORITY_DISABLED {
+                    format!("#{employee_id}: {} desactivee", work.label())
+                } else {
+                    format!("#{employee_id}: {} priorite {priority}", work.label())
+                })
+            }
+            SimCommand::ResetWorkPriorities { employee_id } => {
+                self.personnel.reset_work_priorities(employee_id)?;
+                Ok(format!("#{employee_id}: priorites du role restaurees"))
+            }
+            SimCommand::SetLineShiftTemplate {
+                line_id,
+                template_id,
+            } => {
+                let label = self
+                    .shifts
+                    .template(&template_id)
+                    .map(|template| template.label.clone())
+                    .ok_or_else(|| format!("modele horaire inconnu: {template_id}"))?;
+                let line = self
+                    .line_state_mut(line_id)
+                    .ok_or_else(|| format!("ligne inconnue: {line_id}"))?;
+                line.shift_template = template_id;
+                Ok(format!("Ligne {line_id}: horaires {label}"))
+            }
+            SimCommand::SetEmployeeShift { employee_id, slot } => {
+                self.personnel.set_shift_slot(employee_id, slot)?;
+                Ok(format!("#{employee_id}: poste {}", slot.saturating_add(1)))
+            }
+            SimCommand::ScheduleTraining {
+                employee_id,
+                course_id,
+            } => {
+                let course = self
+                    .training_book
+                    .course(&course_id)
+                    .cloned()
+                    .ok_or_else(|| format!("formation inconnue: {course_id}"))?;
+                if self.economy.cash < course.cost_eur {
+                    return Err(format!(
+                        "Tresorerie insuffisante: {:.0} EUR requis pour {}",
+                        course.cost_eur, course.label
+                    ));
+                }
+                self.personnel.schedule_training(employee_id, &course)?;
+                if course.cost_eur > 0.0 {
+                    self.economy.spend(Account::Formation, course.cost_eur);
+                }
                 Ok(format!(
-                    "Commande matiere #{order_id}: {qty} unites ({cost:.0} EUR)"
+                    "#{employee_id} en formation {}: {:.0}h, {:.0} EUR",
+                    course.label, course.duration_h, course.cost_eur
                 ))
             }
+            SimCommand::ShortlistCandidate { candidate_id } => {
+                let shortlisted = self.recruitment.toggle_shortlist(candidate_id)?;
+                Ok(if shortlisted {
+                    format!("Candidat #{candidate_id} retenu")
+                } else {
+                    format!("Candidat #{candidate_id} retire de la selection")
+                })
+            }
+            SimCommand::InterviewCandidate { candidate_id } => {
+                let cost = self.recruitment_book.interview_cost_eur;
+                if self.economy.cash < cost {
+                    return Err(format!(
+                        "Tresorerie insuffisante: {cost:.0} EUR requis pour un entretien"
+                    ));
+                }
+                let candidate = self.recruitment.interview(candidate_id)?;
+                let message = format!(
+                    "Entretien avec {}: {}",
+                    candidate.name,
+                    candidate.traits_summary()
+                );
+                if cost > 0.0 {
+                    self.economy.spend(Account::Recrutement, cost);
+                }
+                Ok(message)
+            }
+            SimCommand::OfferCandidate {
+                candidate_id,
+                hourly_wage,
+            } => self.offer_candidate(candidate_id, hourly_wage),
+            SimCommand::ChangeEmployeeRole { employee_id, role } => {
+                self.personnel.change_role(employee_id, role)?;
+                Ok(format!("#{employee_id} devient {}", role.label()))
+            }
+            SimCommand::SetEmployeeWage {
+                employee_id,
+                hourly_wage,
+            } => {
+                self.personnel.set_hourly_wage(employee_id, hourly_wage)?;
+                let market = self
+                    .personnel
+                    .employee(employee_id)
+                    .and_then(|employee| self.morale_book.market_wage(employee.role));
+                Ok(match market {
+                    Some(market) => {
+                        format!("#{employee_id}: {hourly_wage:.0} EUR/h (marche {market:.0} EUR/h)")
+                    }
+                    None => format!("#{employee_id}: {hourly_wage:.0} EUR/h"),
+                })
+            }
         }
     }
 
-    pub fn descente_bleue_beacon_active(&self) -> bool {
-        self.line.descente_bleue_beacon_s > 0.0
+    pub fn descente_bleue_beacon_active(&self, line_id: ProductionLineId) -> bool {
+        self.line
+            .flow(line_id)
+            .is_some_and(|flow| flow.descente_bleue_beacon_s > 0.0)
+    }
+
+    pub fn descente_rouge_beacon_active(&self, line_id: ProductionLineId) -> bool {
+        self.line
+            .flow(line_id)
+            .is_some_and(|flow| flow.descente_rouge_beacon_s > 0.0)
     }
 
-    pub fn descente_rouge_beacon_active(&self) -> bool {
-        self.line.descente_rouge_beacon_s > 0.0
+    pub fn descente_bleue_fill_ratio(&self, line_id: ProductionLineId) -> f32 {
+        let units_per_bag = self.active_recipe().packaging.units_per_bag.max(1);
+        let fill = self.line.flow(line_id).map_or(0, |flow| flow.blue_bag_fill);
+        (fill as f32 / units_per_bag as f32).clamp(0.0, 1.0)
     }
 
-    pub fn descente_bleue_fill_ratio(&self) -> f32 {
-        (self.line.blue_bag_fill as f32 / SAC_CAPACITY_UNITS as f32).clamp(0.0, 1.0)
+    pub fn descente_rouge_fill_ratio(&self, line_id: ProductionLineId) -> f32 {
+        let units_per_bag = self.active_recipe().packaging.units_per_bag.max(1);
+        let fill = self.line.flow(line_id).map_or(0, |flow| flow.red_bag_fill);
+        (fill as f32 / units_per_bag as f32).clamp(0.0, 1.0)
     }
 
-    pub fn descente_rouge_fill_ratio(&self) -> f32 {
-        (self.line.red_bag_fill as f32 / SAC_CAPACITY_UNITS as f32).clamp(0.0, 1.0)
+    pub fn active_recipe(&self) -> &ProductRecipe {
+        self.recipes.active()
+    }
+
+    fn modern_block_of_line(
+        &self,
+        line_id: ProductionLineId,
+        kind: BlockKind,
+    ) -> Option<&BlockInstance> {
+        let group = self.modern_group_for_line(line_id)?;
+        self.blocks
+            .iter()
+            .find(|block| block.kind == kind && group.block_ids.contains(&block.id))
     }
 
-    fn modern_stage_cycle_s(&self, kind: BlockKind, base_cycle_s: f64) -> f64 {
+    fn modern_stage_cycle_s(
+        &self,
+        line_id: ProductionLineId,
+        kind: BlockKind,
+        base_cycle_s: f64,
+    ) -> f64 {
         let speed = self
-            .first_block_by_kind(kind)
+            .modern_block_of_line(line_id, kind)
             .map(|block| zone_rules(self.zones.get(block.origin_tile)).speed_multiplier)
             .unwrap_or(1.0)
             .max(0.1);
-        (base_cycle_s / (speed * self.production_staffing_factor())).max(0.001)
+        (base_cycle_s / (speed * self.production_staffing_factor(line_id))).max(0.001)
     }
 
-    fn modern_sorted_units_total(&self) -> u64 {
-        u64::from(self.line.sacs_bleus_total)
-            .saturating_mul(u64::from(SAC_CAPACITY_UNITS))
-            .saturating_add(
-                u64::from(self.line.sacs_rouges_total)
-                    .saturating_mul(u64::from(SAC_CAPACITY_UNITS)),
-            )
-            .saturating_add(u64::from(self.line.blue_bag_fill))
-            .saturating_add(u64::from(self.line.red_bag_fill))
+    pub fn modern_line_ready(&self) -> bool {
+        self.modern_line_readiness_reason_uncached().is_none()
     }
 
-    fn modern_next_sortex_unit_is_blue(&self) -> bool {
-        let next_unit_index = self.modern_sorted_units_total().saturating_add(1);
-        !next_unit_index.is_multiple_of(5)
+    pub fn modern_line_ready_cached_for_render(&self) -> bool {
+        !self.modern_line_cache.dirty && self.modern_line_cache.reason.is_none()
     }
 
-    pub fn modern_line_ready(&self) -> bool {
-        for kind in MODERN_LINE_REQUIRED_KINDS {
-            if self.first_block_by_kind(kind).is_none() {
-                return false;
-            }
-        }
-
-        let mut frontier = self.block_indices_of_kind(BlockKind::InputHopper);
-        if frontier.is_empty() {
-            return false;
-        }
-        frontier = self.reachable_targets_from_starts(
-            &frontier,
-            BlockKind::FluidityTank,
-            &[BlockKind::Conveyor],
-        );
-        if frontier.is_empty() {
-            return false;
-        }
-        frontier = self.reachable_targets_from_starts(
-            &frontier,
-            BlockKind::Cutter,
-            &[BlockKind::Conveyor],
-        );
-        if frontier.is_empty() {
-            return false;
-        }
-        frontier = self.reachable_targets_from_starts(
-            &frontier,
-            BlockKind::DistributorBelt,
-            &[BlockKind::Conveyor],
-        );
-        if frontier.is_empty() {
-            return false;
-        }
-        frontier = self.reachable_targets_from_starts(&frontier, BlockKind::DryerOven, &[]);
-        if frontier.is_empty() {
-            return false;
-        }
-        frontier = self.reachable_targets_from_starts(&frontier, BlockKind::OvenExitConveyor, &[]);
-        if frontier.is_empty() {
-            return false;
-        }
-        frontier = self.reachable_targets_from_starts(&frontier, BlockKind::Flaker, &[]);
-        if frontier.is_empty() {
-            return false;
-        }
-        let sortex_frontier = self.reachable_targets_from_starts(
-            &frontier,
-            BlockKind::Sortex,
-            &[BlockKind::SuctionPipe],
-        );
-        if sortex_frontier.is_empty() {
-            return false;
-        }
-        if self
-            .reachable_targets_from_starts(
-                &sortex_frontier,
-                BlockKind::BlueBagChute,
-                &[BlockKind::SuctionPipe],
-            )
-            .is_empty()
-        {
-            return false;
-        }
-        if self
-            .reachable_targets_from_starts(
-                &sortex_frontier,
-                BlockKind::RedBagChute,
-                &[BlockKind::SuctionPipe],
-            )
-            .is_empty()
-        {
-            return false;
-        }
-        true
-    }
-
-    pub fn modern_line_ready_cached_for_render(&self) -> bool {
-        !self.modern_line_cache.dirty && self.modern_line_cache.reason.is_none()
-    }
-
-    pub fn modern_lavage_busy(&self) -> bool {
-        self.line.lavage_busy
-    }
-
-    pub fn modern_coupe_busy(&self) -> bool {
-        self.line.coupe_busy
-    }
-
-    pub fn modern_four_busy(&self) -> bool {
-        self.line.four_busy
-    }
-
-    pub fn modern_floc_busy(&self) -> bool {
-        self.line.floc_busy
-    }
-
-    pub fn modern_sortex_busy(&self) -> bool {
-        self.line.sortex_busy
-    }
-
-    pub fn modern_lavage_progress_ratio(&self) -> f32 {
-        if !self.line.lavage_busy {
-            0.0
-        } else {
-            (self.line.lavage_progress_s
-                / self.modern_stage_cycle_s(BlockKind::FluidityTank, MODERN_CYCLE_LAVAGE_S))
-            .clamp(0.0, 1.0) as f32
-        }
-    }
-
-    pub fn modern_coupe_progress_ratio(&self) -> f32 {
-        if !self.line.coupe_busy {
-            0.0
-        } else {
-            (self.line.coupe_progress_s
-                / self.modern_stage_cycle_s(BlockKind::Cutter, MODERN_CYCLE_COUPE_S))
-            .clamp(0.0, 1.0) as f32
+    pub fn modern_block_ready_cached_for_render(&self, block_id: BlockId) -> bool {
+        if self.modern_line_cache.dirty {
+            return false;
         }
+        self.modern_line_cache
+            .groups
+            .iter()
+            .find(|group| group.block_ids.contains(&block_id))
+            .map_or(self.modern_line_cache.reason.is_none(), |group| {
+                group.reason.is_none()
+            })
     }
 
-    pub fn modern_four_progress_ratio(&self) -> f32 {
-        if !self.line.four_busy {
-            0.0
-        } else {
-            (self.line.four_progress_s
-                / self.modern_stage_cycle_s(BlockKind::DryerOven, MODERN_CYCLE_FOUR_S))
-            .clamp(0.0, 1.0) as f32
-        }
+    pub fn modern_line_for_block(&self, block_id: BlockId) -> ProductionLineId {
+        self.modern_line_cache
+            .groups
+            .iter()
+            .find(|group| group.block_ids.contains(&block_id))
+            .map_or(MAIN_PRODUCTION_LINE_ID, |group| group.line_id)
     }
 
-    pub fn modern_floc_progress_ratio(&self) -> f32 {
-        if !self.line.floc_busy {
-            0.0
-        } else {
-            (self.line.floc_progress_s
-                / self.modern_stage_cycle_s(BlockKind::Flaker, MODERN_CYCLE_FLOC_S))
-            .clamp(0.0, 1.0) as f32
-        }
+    pub fn modern_stage_busy(&self, line_id: ProductionLineId, kind: BlockKind) -> bool {
+        let Some(flow) = self.line.flow(line_id) else {
+            return false;
+        };
+        self.active_recipe()
+            .stages
+            .iter()
+            .zip(&flow.stages)
+            .any(|(stage, runtime)| stage.block_kind == kind && runtime.busy)
     }
 
-    pub fn modern_sortex_progress_ratio(&self) -> f32 {
-        if !self.line.sortex_busy {
-            0.0
-        } else {
-            (self.line.sortex_progress_s
-                / self.modern_stage_cycle_s(BlockKind::Sortex, MODERN_CYCLE_SORTEX_S))
-            .clamp(0.0, 1.0) as f32
-        }
+    pub fn modern_stage_progress_ratio(&self, line_id: ProductionLineId, kind: BlockKind) -> f32 {
+        let Some(flow) = self.line.flow(line_id) else {
+            return 0.0;
+        };
+        self.active_recipe()
+            .stages
+            .iter()
+            .zip(&flow.stages)
+            .find(|(stage, runtime)| stage.block_kind == kind && runtime.busy)
+            .map(|(stage, runtime)| {
+                (runtime.progress_s / self.modern_stage_cycle_s(line_id, kind, stage.cycle_s))
+                    .clamp(0.0, 1.0) as f32
+            })
+            .unwrap_or(0.0)
     }
 
     pub fn block_kind_at_tile(&self, tile: (i32, i32)) -> Option<BlockKind> {
@@ -2368,6 +4338,7 @@ impl FactorySim {
         self.can_place_block_at(world, kind, tile, orientation, None)
     }
 
+    #[cfg(test)]
     pub fn poser_bloc_script(
         &mut self,
         world: &crate::World,
@@ -2376,90 +4347,23 @@ impl FactorySim {
         orientation: BlockOrientation,
         facturer_capex: bool,
     ) -> Result<BlockId, String> {
-        let footprint = self.valider_pose_bloc_script(world, kind, tile, orientation)?;
-        let capex = kind.capex();
-        if facturer_capex && self.economy.cash < capex {
-            return Err(format!(
-                "Tresorerie insuffisante: {} EUR requis",
-                format_int_fr(capex.round() as i64)
-            ));
-        }
-
-        let id = self.next_block_id;
-        self.next_block_id = self.next_block_id.saturating_add(1);
-        if facturer_capex && capex > 0.0 {
-            self.economy.spend(capex);
-        }
-
-        let mut block = self.make_block(id, kind, tile, orientation);
-        block.footprint = footprint;
-        self.blocks.push(block);
-        self.mark_modern_line_cache_dirty();
-
-        let (guidance, connected) = if kind.is_modern_line_component() {
-            self.modern_line_placement_guidance(kind, tile, footprint, orientation)
-        } else {
-            (String::new(), true)
-        };
-        let mut status = format!(
-            "Script pose {} #{} [{} {}x{}]",
-            kind.buyable_label(),
-            id,
-            orientation.label(),
-            footprint.0,
-            footprint.1
-        );
-        if !guidance.is_empty() {
-            if connected {
-                status.push_str(" | ");
-            } else {
-                status.push_str(" | Alerte: ");
-            }
-            status.push_str(&guidance);
-        }
+        let (id, status) = self.place_block(world, kind, tile, orientation, facturer_capex)?;
         self.set_status_line(status);
         Ok(id)
     }
 
-    pub fn paint_zone_rect_script(
-        &mut self,
-        origin: (i32, i32),
-        size: (i32, i32),
-        zone: ZoneKind,
-    ) -> Result<usize, String> {
-        if size.0 <= 0 || size.1 <= 0 {
-            return Err(format!(
-                "zone invalide: origine {:?}, taille {:?}",
-                origin, size
-            ));
-        }
-
-        let mut count = 0usize;
-
-        for dy in 0..size.1 {
-            for dx in 0..size.0 {
-                let tile = (origin.0 + dx, origin.1 + dy);
-
-                if tile.0 < 0 || tile.1 < 0 || tile.0 >= self.map_w || tile.1 >= self.map_h {
-                    return Err(format!("zone hors carte en {:?}", tile));
-                }
-            }
-        }
-
-        for dy in 0..size.1 {
-            for dx in 0..size.0 {
-                let tile = (origin.0 + dx, origin.1 + dy);
-                self.zones.set(tile, zone);
-                count += 1;
-            }
+    pub fn bootstrap_functional_factory(&mut self) -> Result<String, String> {
+        // Les commandes internes de l'amorcage ne sont pas journalisees une seconde fois.
+        let journal = self.journal.take();
+        let result = self.bootstrap_functional_factory_unrecorded();
+        self.journal = journal;
+        if result.is_ok() {
+            self.record_event(ReplayEvent::Bootstrap);
         }
-
-        self.refresh_static_capabilities();
-
-        Ok(count)
+        result
     }
 
-    pub fn bootstrap_functional_factory(&mut self) -> Result<String, String> {
+    fn bootstrap_functional_factory_unrecorded(&mut self) -> Result<String, String> {
         let now_s = self.clock.seconds();
         let line_id = MAIN_PRODUCTION_LINE_ID;
 
@@ -2496,8 +4400,14 @@ impl FactorySim {
                 .hire(EmployeeRole::AdministrateurVente, now_s)?;
         }
 
+        while self.personnel.active_role_count(EmployeeRole::Technicien) < 1 {
+            self.personnel.hire(EmployeeRole::Technicien, now_s)?;
+        }
+
         self.personnel.set_temp_policy(lead_id, true, 3)?;
 
+        self.stock.spare_parts = self.stock.spare_parts.max(TEST_FACTORY_SPARE_PARTS);
+
         self.stock.raw_receiving = self
             .stock
             .raw_receiving
@@ -2516,7 +4426,7 @@ impl FactorySim {
         self.main_line_state_mut().set_active(lead_id, active_temps);
 
         self.refresh_static_capabilities();
-        self.refresh_autonomous_employee_ai(0, 0);
+        self.refresh_autonomous_employee_ai(0);
 
         let offices = self.sale_office_count();
 
@@ -2765,6 +4675,8 @@ impl FactorySim {
         }
     }
 
+    // Traduit un clic du mode construction en commande; seuls les coins de zone
+    // et la source de deplacement restent un etat d'outil.
     pub fn apply_build_click(
         &mut self,
         world: &mut crate::World,
@@ -2785,128 +4697,212 @@ impl FactorySim {
             return;
         }
 
-        if self.floor_paint_mode {
-            self.apply_floor_click(world, tile, right_click);
-            return;
-        }
-
-        if right_click {
-            if let Some(index) = self.block_index_at_tile(tile) {
-                let removed = self.blocks.remove(index);
-                self.purge_jobs_referencing_block(removed.id);
-                self.mark_modern_line_cache_dirty();
-                self.economy.earn(removed.kind.capex() * 0.6);
-                self.set_status_line(format!(
-                    "Vendu #{} {}",
-                    removed.id,
-                    removed.kind.buyable_label()
-                ));
+        let result = if self.floor_paint_mode {
+            // Clic droit: retour au sol standard, sans frais.
+            let command = if right_click {
+                SimCommand::ResetFloorRect {
+                    origin: tile,
+                    size: (1, 1),
+                }
             } else {
-                self.set_status_line("Aucun bloc a vendre");
+                SimCommand::PaintFloorRect {
+                    origin: tile,
+                    size: (1, 1),
+                    floor: self.floor_brush,
+                }
+            };
+            self.apply_command_in_world(command, Some(world))
+        } else if right_click {
+            match self.block_at_tile(tile).map(|block| block.id) {
+                Some(block_id) => self.apply_command(SimCommand::RemoveBlock { block_id }),
+                None => Err("Aucun bloc a vendre".to_string()),
             }
-            return;
-        }
-
-        if let Some(move_id) = self.pending_move_block {
-            if let Some(idx) = self.block_index_by_id(move_id) {
-                let kind = self.blocks[idx].kind;
-                let orientation = self.blocks[idx].orientation;
-                if self
-                    .can_place_block_at(&*world, kind, tile, orientation, Some(move_id))
-                    .is_ok()
-                {
-                    self.blocks[idx].origin_tile = tile;
+        } else if let Some(move_id) = self.pending_move_block {
+            if self.block_index_by_id(move_id).is_none() {
+                self.pending_move_block = None;
+                Err("Deplacement annule: source introuvable".to_string())
+            } else {
+                let result = self.apply_command_in_world(
+                    SimCommand::MoveBlock {
+                        block_id: move_id,
+                        tile,
+                    },
+                    Some(world),
+                );
+                if result.is_ok() {
                     self.pending_move_block = None;
-                    self.mark_modern_line_cache_dirty();
-                    self.set_status_line(format!(
-                        "Deplace #{} -> ({}, {})",
-                        move_id, tile.0, tile.1
-                    ));
-                } else {
-                    self.set_status_line("Deplacement impossible: destination occupee");
                 }
-            } else {
-                self.pending_move_block = None;
-                self.set_status_line("Deplacement annule: source introuvable");
+                result
             }
-            return;
-        }
+        } else {
+            self.apply_command_in_world(
+                SimCommand::PlaceBlock {
+                    kind: self.block_brush,
+                    tile,
+                    orientation: self.block_orientation,
+                },
+                Some(world),
+            )
+        };
+        self.report_command(result);
+    }
 
-        if !self.block_brush.is_player_buyable() {
-            self.set_status_line("Bloc non achetable par le joueur");
-            return;
+    fn report_command(&mut self, result: Result<String, String>) {
+        match result {
+            Ok(msg) | Err(msg) => self.set_status_line(msg),
         }
-        let placement = self.can_place_block_at(
-            &*world,
-            self.block_brush,
-            tile,
-            self.block_orientation,
-            None,
-        );
-        let footprint = match placement {
-            Ok(footprint) => footprint,
-            Err(reason) => {
-                self.set_status_line(reason);
-                return;
-            }
-        };
-        let (placement_guidance, placement_connected) =
-            if self.block_brush.is_modern_line_component() {
-                self.modern_line_placement_guidance(
-                    self.block_brush,
-                    tile,
-                    footprint,
-                    self.block_orientation,
-                )
-            } else {
-                (String::new(), true)
-            };
+    }
 
-        let id = self.next_block_id;
-        let capex = self.block_brush.capex();
-        if self.economy.cash < capex {
-            self.set_status_line(format!(
+    fn command_world<'w>(
+        world: Option<&'w mut crate::World>,
+        action: &str,
+    ) -> Result<&'w mut crate::World, String> {
+        world.ok_or_else(|| format!("{action} impossible sans carte"))
+    }
+
+    fn place_block(
+        &mut self,
+        world: &crate::World,
+        kind: BlockKind,
+        tile: (i32, i32),
+        orientation: BlockOrientation,
+        charge_capex: bool,
+    ) -> Result<(BlockId, String), String> {
+        let footprint = self.valider_pose_bloc_script(world, kind, tile, orientation)?;
+        let capex = kind.capex();
+        if charge_capex && self.economy.cash < capex {
+            return Err(format!(
                 "Tresorerie insuffisante: {} EUR requis",
                 format_int_fr(capex.round() as i64)
             ));
-            return;
         }
+
+        let id = self.next_block_id;
         self.next_block_id = self.next_block_id.saturating_add(1);
-        self.economy.spend(capex);
-        let mut block = self.make_block(id, self.block_brush, tile, self.block_orientation);
+        if charge_capex && capex > 0.0 {
+            self.economy.invest(
+                &format!("Equipement {}", kind.label()),
+                Some(id),
+                capex,
+                EQUIPMENT_DEPRECIATION_DAYS,
+            );
+        }
+
+        let mut block = self.make_block(id, kind, tile, orientation);
         block.footprint = footprint;
         self.blocks.push(block);
         self.mark_modern_line_cache_dirty();
+
+        let (guidance, connected) = if kind.is_modern_line_component() {
+            self.modern_line_placement_guidance(kind, tile, footprint, orientation)
+        } else {
+            (String::new(), true)
+        };
         let mut status = format!(
             "Place {} #{} [{} {}x{}]",
-            self.block_brush.buyable_label(),
+            kind.buyable_label(),
             id,
-            self.block_orientation.label(),
+            orientation.label(),
             footprint.0,
             footprint.1
         );
-        if !placement_guidance.is_empty() {
-            if placement_connected {
+        if !guidance.is_empty() {
+            if connected {
                 status.push_str(" | ");
             } else {
                 status.push_str(" | Alerte: ");
             }
-            status.push_str(&placement_guidance);
+            status.push_str(&guidance);
         }
-        self.set_status_line(status);
+        Ok((id, status))
     }
 
-    pub fn save_layout(&mut self) -> Result<(), String> {
-        let layout = FactoryLayoutAsset {
-            schema_version: FACTORY_LAYOUT_SCHEMA_VERSION,
-            map_w: self.map_w,
-            map_h: self.map_h,
-            zones: self.zones.clone(),
-            blocks: self.blocks.clone(),
-            agent_tile: self.agent.tile,
-        };
-        self.save_layout_asset(&layout)?;
-        self.set_status_line(format!("Layout usine sauvegarde: {FACTORY_LAYOUT_PATH}"));
+    fn remove_block(&mut self, block_id: BlockId) -> Result<String, String> {
+        let index = self
+            .block_index_by_id(block_id)
+            .ok_or_else(|| format!("bloc introuvable: #{block_id}"))?;
+        let removed = self.blocks.remove(index);
+        if self.pending_move_block == Some(block_id) {
+            self.pending_move_block = None;
+        }
+        self.purge_jobs_referencing_block(removed.id);
+        self.mark_modern_line_cache_dirty();
+        self.economy
+            .sell_asset(removed.id, removed.kind.capex() * 0.6);
+        Ok(format!(
+            "Vendu #{} {}",
+            removed.id,
+            removed.kind.buyable_label()
+        ))
+    }
+
+    fn move_block(
+        &mut self,
+        world: &crate::World,
+        block_id: BlockId,
+        tile: (i32, i32),
+    ) -> Result<String, String> {
+        let idx = self
+            .block_index_by_id(block_id)
+            .ok_or_else(|| format!("bloc introuvable: #{block_id}"))?;
+        let kind = self.blocks[idx].kind;
+        let orientation = self.blocks[idx].orientation;
+        self.can_place_block_at(world, kind, tile, orientation, Some(block_id))
+            .map_err(|_| "Deplacement impossible: destination occupee".to_string())?;
+        self.blocks[idx].origin_tile = tile;
+        self.mark_modern_line_cache_dirty();
+        Ok(format!("Deplace #{} -> ({}, {})", block_id, tile.0, tile.1))
+    }
+
+    fn rotate_block(
+        &mut self,
+        world: &crate::World,
+        block_id: BlockId,
+        orientation: BlockOrientation,
+    ) -> Result<String, String> {
+        let idx = self
+            .block_index_by_id(block_id)
+            .ok_or_else(|| format!("bloc introuvable: #{block_id}"))?;
+        let kind = self.blocks[idx].kind;
+        let origin = self.blocks[idx].origin_tile;
+        let footprint = self
+            .can_place_block_at(world, kind, origin, orientation, Some(block_id))
+            .map_err(|reason| format!("Rotation impossible: {reason}"))?;
+        self.blocks[idx].orientation = orientation;
+        self.blocks[idx].footprint = footprint;
+        self.mark_modern_line_cache_dirty();
+        Ok(format!(
+            "Rotation #{} -> {} [{}x{}]",
+            block_id,
+            orientation.label(),
+            footprint.0,
+            footprint.1
+        ))
+    }
+
+    fn set_sale_price(&mut self, price_eur: f64) -> Result<String, String> {
+        if !price_eur.is_finite() || !(MIN_SALE_PRICE_EUR..=MAX_SALE_PRICE_EUR).contains(&price_eur)
+        {
+            return Err(format!(
+                "prix de vente invalide: {price_eur} (attendu {MIN_SALE_PRICE_EUR:.0}-{MAX_SALE_PRICE_EUR:.0} EUR)"
+            ));
+        }
+        self.config.sale_price = price_eur;
+        Ok(format!("Prix de vente: {price_eur:.0} EUR/box"))
+    }
+
+    pub fn save_layout(&mut self) -> Result<(), String> {
+        let layout = FactoryLayoutAsset {
+            schema_version: FACTORY_LAYOUT_SCHEMA_VERSION,
+            map_w: self.map_w,
+            map_h: self.map_h,
+            zones: self.zones.clone(),
+            blocks: self.blocks.clone(),
+            agent_tile: self.primary_agent().tile,
+            lavabo_tiles: self.lavabo_tiles.clone(),
+        };
+        self.save_layout_asset(&layout)?;
+        self.set_status_line(format!("Layout usine sauvegarde: {FACTORY_LAYOUT_PATH}"));
         Ok(())
     }
 
@@ -2926,8 +4922,19 @@ impl FactorySim {
                 map_h: self.map_h,
                 zones: self.zones.clone(),
                 blocks: self.blocks.clone(),
-                agent_tile: self.agent.tile,
+                agent_tile: self.primary_agent().tile,
+                lavabo_tiles: self.lavabo_tiles.clone(),
             },
+            lots: self.lots.clone(),
+            rng: Some(self.rng.clone()),
+            tick: self.tick,
+            sale_price: Some(self.config.sale_price),
+            market: self.market.clone(),
+            finance: self.finance.clone(),
+            energy: self.energy.clone(),
+            recruitment: self.recruitment.clone(),
+            hse: self.hse.clone(),
+            quality_control: self.quality_control.clone(),
         }
     }
 
@@ -2943,17 +4950,44 @@ impl FactorySim {
         }
         asset.layout.validate()?;
         let mut sim = Self::from_layout(config, asset.layout);
+        sim.recipes = RecipeBook::load_or_create(RECIPE_BOOK_PATH);
+        sim.shifts = ShiftBook::load_or_create(SHIFT_BOOK_PATH);
+        sim.market_book = MarketBook::load_or_create(MARKET_BOOK_PATH);
+        sim.suppliers = SupplierCatalogue::load_or_create(SUPPLIER_CATALOGUE_PATH);
+        sim.finance_book = FinanceBook::load_or_create(FINANCE_BOOK_PATH);
+        sim.energy_book = EnergyBook::load_or_create(ENERGY_BOOK_PATH);
+        sim.quality_book = QualityBook::load_or_create(QUALITY_BOOK_PATH);
+        sim.needs_book = NeedsBook::load_or_create(NEEDS_BOOK_PATH);
+        sim.morale_book = MoraleBook::load_or_create(MORALE_BOOK_PATH);
+        sim.training_book = TrainingBook::load_or_create(TRAINING_BOOK_PATH);
+        sim.recruitment_book = RecruitmentBook::load_or_create(RECRUITMENT_BOOK_PATH);
+        sim.hse_book = HseBook::load_or_create(HSE_BOOK_PATH);
         sim.clock = asset.clock;
         sim.economy = asset.economy;
+        sim.economy.ensure_ledger(sim.clock.seconds());
         sim.personnel = asset.personnel;
         sim.stock = asset.stock;
         sim.sales = asset.sales;
+        sim.market = asset.market;
+        sim.finance = asset.finance;
+        sim.energy = asset.energy;
+        sim.recruitment = asset.recruitment;
+        sim.hse = asset.hse;
+        sim.quality_control = asset.quality_control;
         sim.production_lines = if asset.production_lines.is_empty() {
             vec![ProductionLineState::main_line()]
         } else {
             asset.production_lines
         };
         sim.line = asset.line;
+        sim.lots = asset.lots;
+        if let Some(rng) = asset.rng {
+            sim.rng = rng;
+        }
+        sim.tick = asset.tick;
+        if let Some(price) = asset.sale_price {
+            sim.config.sale_price = price;
+        }
         sim.line.raw = sim.stock.raw_line_input;
         sim.refresh_static_capabilities();
         sim.mark_modern_line_cache_dirty();
@@ -3018,19 +5052,31 @@ impl FactorySim {
             }
             BlockKind::BlueBagChute => format!(
                 "bleu fill={:.0}% sacs={} boxes={}",
-                self.descente_bleue_fill_ratio() * 100.0,
+                self.descente_bleue_fill_ratio(self.modern_line_for_block(block.id)) * 100.0,
                 self.line.sacs_bleus_total,
                 self.line.boxes_bleues_total
             ),
             BlockKind::RedBagChute => format!(
                 "rouge fill={:.0}% sacs={}",
-                self.descente_rouge_fill_ratio() * 100.0,
+                self.descente_rouge_fill_ratio(self.modern_line_for_block(block.id)) * 100.0,
                 self.line.sacs_rouges_total
             ),
             BlockKind::Sortex => format!(
                 "tri flakes={} bleu={} rouge={}",
-                self.line.flakes, self.line.sacs_bleus_total, self.line.sacs_rouges_total
+                self.line.buffer_qty(ItemKind::Flakes),
+                self.line.sacs_bleus_total,
+                self.line.sacs_rouges_total
             ),
+            _ if block.kind.is_machine() => {
+                let machine = block.machine.unwrap_or_default();
+                format!(
+                    "usure={:.0}%{} pannes={} arret={:.0}min",
+                    machine.wear * 100.0,
+                    if machine.broken { " PANNE" } else { "" },
+                    machine.breakdowns_total,
+                    machine.downtime_minutes
+                )
+            }
             _ => format!(
                 "mat:{} enc:{} fini:{} rebut:{}",
                 block.inventory.total_of(ItemKind::Raw),
@@ -3042,13 +5088,27 @@ impl FactorySim {
     }
 
     pub fn agent_debug_views(&self) -> Vec<AgentDebugView> {
-        vec![AgentDebugView {
-            world_pos: (
-                self.agent.tile.0 as f32 + 0.5,
-                self.agent.tile.1 as f32 + 0.5,
-            ),
-            label: self.agent.decision_debug.clone(),
-        }]
+        self.agents
+            .iter()
+            .map(|agent| AgentDebugView {
+                world_pos: (agent.tile.0 as f32 + 0.5, agent.tile.1 as f32 + 0.5),
+                label: {
+                    let role = agent
+                        .role
+                        .map(|role| format!("{}: ", role.label()))
+                        .unwrap_or_default();
+                    if agent.current_job.is_some() {
+                        format!(
+                            "{role}{} | {}",
+                            agent.decision_debug,
+                            agent.job_phase.label()
+                        )
+                    } else {
+                        format!("{role}{}", agent.decision_debug)
+                    }
+                },
+            })
+            .collect()
     }
 
     pub fn build_hint_line(&self) -> String {
@@ -3110,94 +5170,143 @@ impl FactorySim {
         let max_x = start.0.max(tile.0).min(self.map_w - 1);
         let min_y = start.1.min(tile.1).max(0);
         let max_y = start.1.max(tile.1).min(self.map_h - 1);
+        let result = self.apply_command(SimCommand::PaintZoneRect {
+            origin: (min_x, min_y),
+            size: (max_x - min_x + 1, max_y - min_y + 1),
+            zone: zone_target,
+        });
+        self.report_command(result);
+    }
+
+    fn paint_zone_rect(
+        &mut self,
+        origin: (i32, i32),
+        size: (i32, i32),
+        zone: ZoneKind,
+        charge_capex: bool,
+    ) -> Result<String, String> {
+        if size.0 <= 0 || size.1 <= 0 {
+            return Err(format!(
+                "zone invalide: origine {:?}, taille {:?}",
+                origin, size
+            ));
+        }
+        let max = (origin.0 + size.0 - 1, origin.1 + size.1 - 1);
+        if origin.0 < 0 || origin.1 < 0 || max.0 >= self.map_w || max.1 >= self.map_h {
+            return Err(format!("zone hors carte en {:?}", max));
+        }
 
         let mut changed_tiles = 0usize;
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
-                if self.zones.get((x, y)) != zone_target {
+        for y in origin.1..=max.1 {
+            for x in origin.0..=max.0 {
+                if self.zones.get((x, y)) != zone {
                     changed_tiles += 1;
                 }
             }
         }
 
-        let total_cost = zone_target.capex_par_tuile_eur() * changed_tiles as f64;
+        let total_cost = if charge_capex {
+            zone.capex_par_tuile_eur() * changed_tiles as f64
+        } else {
+            0.0
+        };
         if total_cost > 0.0 && self.economy.cash < total_cost {
-            self.set_status_line(format!(
+            return Err(format!(
                 "Tresorerie insuffisante: {} EUR requis pour zone {} ({} tuiles)",
                 format_int_fr(total_cost.round() as i64),
-                zone_target.label(),
+                zone.label(),
                 changed_tiles
             ));
-            return;
         }
         if total_cost > 0.0 {
-            self.economy.spend(total_cost);
+            self.economy.invest(
+                &format!("Amenagement zone {}", zone.label()),
+                None,
+                total_cost,
+                FITOUT_DEPRECIATION_DAYS,
+            );
         }
 
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
-                self.zones.set((x, y), zone_target);
+        for y in origin.1..=max.1 {
+            for x in origin.0..=max.0 {
+                self.zones.set((x, y), zone);
             }
         }
+        self.refresh_static_capabilities();
 
-        self.set_status_line(format!(
+        Ok(format!(
             "Zone {} appliquee sur rectangle ({}, {}) -> ({}, {}) [{} tuiles]",
-            zone_target.label(),
-            min_x,
-            min_y,
-            max_x,
-            max_y,
+            zone.label(),
+            origin.0,
+            origin.1,
+            max.0,
+            max.1,
             changed_tiles
-        ));
+        ))
     }
 
-    fn apply_floor_click(&mut self, world: &mut crate::World, tile: (i32, i32), right_click: bool) {
-        if tile.0 <= 0 || tile.1 <= 0 || tile.0 >= self.map_w - 1 || tile.1 >= self.map_h - 1 {
-            self.set_status_line("Pose sol impossible sur la bordure");
-            return;
+    fn paint_floor_rect(
+        &mut self,
+        world: &mut crate::World,
+        origin: (i32, i32),
+        size: (i32, i32),
+        floor: BuildFloorKind,
+        charge_capex: bool,
+    ) -> Result<String, String> {
+        if size.0 <= 0 || size.1 <= 0 {
+            return Err(format!(
+                "sol invalide: origine {:?}, taille {:?}",
+                origin, size
+            ));
         }
-        if world.is_solid(tile.0, tile.1) {
-            self.set_status_line("Pose sol impossible: mur present");
-            return;
+        let next_tile = floor.to_tile();
+        let mut changed_tiles = 0usize;
+        for y in origin.1..origin.1 + size.1 {
+            for x in origin.0..origin.0 + size.0 {
+                if x <= 0 || y <= 0 || x >= self.map_w - 1 || y >= self.map_h - 1 {
+                    return Err("Pose sol impossible sur la bordure".to_string());
+                }
+                if !world.in_bounds(x, y) {
+                    return Err(format!("sol hors carte en {:?}", (x, y)));
+                }
+                if world.is_solid(x, y) {
+                    return Err("Pose sol impossible: mur present".to_string());
+                }
+                if world.get(x, y) != next_tile {
+                    changed_tiles += 1;
+                }
+            }
         }
-
-        let next_tile = if right_click {
-            crate::Tile::Floor
-        } else {
-            self.floor_brush.to_tile()
-        };
-        if world.get(tile.0, tile.1) == next_tile {
-            self.set_status_line("Aucun changement de sol");
-            return;
+        if changed_tiles == 0 {
+            return Ok("Aucun changement de sol".to_string());
         }
 
-        let capex = if right_click {
-            0.0
+        let capex = if charge_capex {
+            floor.capex_par_tuile_eur() * changed_tiles as f64
         } else {
-            self.floor_brush.capex_par_tuile_eur()
+            0.0
         };
         if capex > 0.0 && self.economy.cash < capex {
-            self.set_status_line(format!(
+            return Err(format!(
                 "Tresorerie insuffisante: {} EUR requis",
                 format_int_fr(capex.round() as i64)
             ));
-            return;
         }
         if capex > 0.0 {
-            self.economy.spend(capex);
+            self.economy
+                .invest("Amenagement sol", None, capex, FITOUT_DEPRECIATION_DAYS);
         }
 
-        world.set(tile.0, tile.1, next_tile);
-        self.set_status_line(if right_click {
-            format!("Sol reinitialise @ ({}, {})", tile.0, tile.1)
+        for y in origin.1..origin.1 + size.1 {
+            for x in origin.0..origin.0 + size.0 {
+                world.set(x, y, next_tile);
+            }
+        }
+        Ok(if size == (1, 1) {
+            format!("{} pose @ ({}, {})", floor.label(), origin.0, origin.1)
         } else {
-            format!(
-                "{} pose @ ({}, {})",
-                self.floor_brush.label(),
-                tile.0,
-                tile.1
-            )
-        });
+            format!("{} pose sur {} tuiles", floor.label(), changed_tiles)
+        })
     }
 
     fn first_block_by_kind(&self, kind: BlockKind) -> Option<&BlockInstance> {
@@ -3438,6 +5547,7 @@ impl FactorySim {
                 cycle_s: self.config.machine_b_cycle_s.max(1.0),
                 ..MachineState::default()
             }),
+            kind if kind.is_machine() => Some(MachineState::default()),
             _ => None,
         };
         BlockInstance {
@@ -3521,16 +5631,34 @@ impl FactorySim {
     }
 
     fn modern_line_readiness_reason_uncached(&self) -> Option<String> {
-        for kind in MODERN_LINE_REQUIRED_KINDS {
-            if self.first_block_by_kind(kind).is_none() {
-                return Some(format!("Bloc manquant: {}", kind.buyable_label()));
-            }
+        let groups = self.modern_line_groups();
+        let reasons = groups
+            .iter()
+            .map(|group| self.modern_group_readiness_reason(group))
+            .collect::<Vec<_>>();
+        self.overall_modern_readiness_reason(&groups, &reasons)
+    }
+
+    fn missing_modern_block_reason(&self) -> Option<String> {
+        MODERN_LINE_REQUIRED_KINDS
+            .into_iter()
+            .find(|kind| self.first_block_by_kind(*kind).is_none())
+            .map(|kind| format!("Bloc manquant: {}", kind.buyable_label()))
+    }
+
+    fn modern_group_readiness_reason(&self, group: &[usize]) -> Option<String> {
+        if let Some(reason) = self.missing_modern_block_reason() {
+            return Some(reason);
         }
 
-        let mut frontier = self.block_indices_of_kind(BlockKind::InputHopper);
+        let mut frontier = group
+            .iter()
+            .copied()
+            .filter(|&idx| self.blocks[idx].kind == BlockKind::InputHopper)
+            .collect::<Vec<_>>();
         if frontier.is_empty() {
             return Some(format!(
-                "Bloc manquant: {}",
+                "Connexion invalide: aucune {} reliee",
                 BlockKind::InputHopper.buyable_label()
             ));
         }
@@ -3617,196 +5745,419 @@ impl FactorySim {
             .map(|block| zone_rules(self.zones.get(block.origin_tile)).speed_multiplier)
             .unwrap_or(1.0);
 
-        let staffing_speed = self.production_staffing_factor();
+        let machine_a_id = self
+            .first_block_by_kind(BlockKind::MachineA)
+            .map(|block| block.id);
+        let machine_b_id = self
+            .first_block_by_kind(BlockKind::MachineB)
+            .map(|block| block.id);
+        let machine_a_broken = machine_a_id.is_some_and(|id| self.machine_broken(id));
+        let machine_b_broken = machine_b_id.is_some_and(|id| self.machine_broken(id));
+
+        let staffing_speed = self.production_staffing_factor(MAIN_PRODUCTION_LINE_ID);
         let cycle_a = (self.config.machine_a_cycle_s
             / (machine_a_zone_speed.max(0.1) * staffing_speed))
             .max(0.001);
-        if !self.line.machine_a_busy && self.line.raw > 0 {
+        if !machine_a_broken && !self.line.machine_a_busy && self.line.raw > 0 {
             self.line.raw -= 1;
             self.line.machine_a_busy = true;
             self.line.machine_a_progress = 0.0;
-        }
-        if self.line.machine_a_busy {
+            self.line.machine_a_lot = self
+                .stock
+                .raw_line_input_lots
+                .take(1)
+                .first()
+                .map(|portion| portion.lot_id);
+        }
+        if !machine_a_broken && self.line.machine_a_busy {
             self.line.machine_a_progress += dt_sim;
             if self.line.machine_a_progress >= cycle_a {
                 self.line.machine_a_busy = false;
                 self.line.machine_a_progress = 0.0;
                 self.line.wip = self.line.wip.saturating_add(1);
                 self.line.produced_wip_total = self.line.produced_wip_total.saturating_add(1);
+                if let Some(lot_id) = self.line.machine_a_lot.take() {
+                    self.line.legacy_wip_lots.push(lot_id, 1);
+                }
                 if let Some(kpi) = self.zone_kpi.get_mut(&ZoneKind::Processing) {
                     kpi.produced_total = kpi.produced_total.saturating_add(1);
                 }
+                if let Some(block_id) = machine_a_id {
+                    self.wear_machine_block(block_id);
+                }
             }
         }
 
         let cycle_b = (self.config.machine_b_cycle_s
             / (machine_b_zone_speed.max(0.1) * staffing_speed))
             .max(0.001);
-        if !self.line.machine_b_busy && self.line.wip > 0 {
+        if !machine_b_broken && !self.line.machine_b_busy && self.line.wip > 0 {
             self.line.wip -= 1;
             self.line.machine_b_busy = true;
             self.line.machine_b_progress = 0.0;
-        }
-        if self.line.machine_b_busy {
+            self.line.machine_b_lot = self
+                .line
+                .legacy_wip_lots
+                .take(1)
+                .first()
+                .map(|portion| portion.lot_id);
+        }
+        if !machine_b_broken && self.line.machine_b_busy {
             self.line.machine_b_progress += dt_sim;
             if self.line.machine_b_progress >= cycle_b {
                 self.line.machine_b_busy = false;
                 self.line.machine_b_progress = 0.0;
-                self.line.finished = self.line.finished.saturating_add(1);
+                let flow = self.line.flows.entry(MAIN_PRODUCTION_LINE_ID).or_default();
+                flow.boxes_to_ship = flow.boxes_to_ship.saturating_add(1);
                 self.line.produced_finished_total =
                     self.line.produced_finished_total.saturating_add(1);
+                let box_lots = self.line.machine_b_lot.take().into_iter().collect();
+                self.lots.record_boxes(1, &box_lots, self.clock.seconds());
                 if let Some(kpi) = self.zone_kpi.get_mut(&ZoneKind::Shipping) {
                     kpi.produced_total = kpi.produced_total.saturating_add(1);
                 }
+                if let Some(block_id) = machine_b_id {
+                    self.wear_machine_block(block_id);
+                }
             }
         }
 
         let _ = dt_hours;
     }
 
-    fn tick_modern_line(&mut self, dt_sim: f64) {
-        self.line.descente_bleue_beacon_s = (self.line.descente_bleue_beacon_s - dt_sim).max(0.0);
-        self.line.descente_rouge_beacon_s = (self.line.descente_rouge_beacon_s - dt_sim).max(0.0);
+    fn tick_modern_line(&mut self, line_id: ProductionLineId, dt_sim: f64) {
+        let recipe = self.recipes.active();
+        let cycles_s = recipe
+            .stages
+            .iter()
+            .map(|stage| self.modern_stage_cycle_s(line_id, stage.block_kind, stage.cycle_s))
+            .collect::<Vec<_>>();
+        let stage_blocks = recipe
+            .stages
+            .iter()
+            .map(|stage| {
+                self.modern_block_of_line(line_id, stage.block_kind)
+                    .map(|block| {
+                        let machine = block.machine.unwrap_or_default();
+                        (block.id, machine.broken, machine.wear)
+                    })
+            })
+            .collect::<Vec<_>>();
+        // Usure et operateurs degradent chaque unite au prorata des etapes traversees.
+        let stage_share = 1.0 / recipe.stages.len().max(1) as f64;
+        let operator_defect = self.line_operator_defect(line_id) * stage_share;
+        // Un controleur au poste preleve des unites et fiabilise le tri.
+        let controllers = self.personnel.active_count_for_work(WorkType::Controle);
+        let sorting_noise = if controllers > 0 {
+            self.quality_book.sorting_noise * self.quality_book.controlled_noise_factor
+        } else {
+            self.quality_book.sorting_noise
+        };
+        let mut outcomes = Vec::new();
+        let mut completed_cycles = Vec::new();
+        let now_s = self.clock.seconds();
+        let flow = self.line.flows.entry(line_id).or_default();
+        flow.tick_beacons(dt_sim);
+        flow.sync_stage_runtimes(recipe);
 
-        let lavage_cycle_s =
-            self.modern_stage_cycle_s(BlockKind::FluidityTank, MODERN_CYCLE_LAVAGE_S);
-        let coupe_cycle_s = self.modern_stage_cycle_s(BlockKind::Cutter, MODERN_CYCLE_COUPE_S);
-        let four_cycle_s = self.modern_stage_cycle_s(BlockKind::DryerOven, MODERN_CYCLE_FOUR_S);
-        let floc_cycle_s = self.modern_stage_cycle_s(BlockKind::Flaker, MODERN_CYCLE_FLOC_S);
-        let sortex_cycle_s = self.modern_stage_cycle_s(BlockKind::Sortex, MODERN_CYCLE_SORTEX_S);
+        for (index, stage) in recipe.stages.iter().enumerate() {
+            // Une machine en panne fige son etape jusqu'a la reparation.
+            if stage_blocks[index].is_some_and(|(_, broken, _)| broken) {
+                continue;
+            }
+            if !flow.stages[index].busy && flow.has_items(&stage.inputs) {
+                let (parents, defect) = flow.take_items(&stage.inputs, |lot_id| {
+                    self.lots
+                        .reception_grade(lot_id)
+                        .map_or(0.0, |grade| self.quality_book.raw_defect(grade))
+                });
+                let runtime = &mut flow.stages[index];
+                runtime.busy = true;
+                runtime.progress_s = 0.0;
+                runtime.defect = defect;
+                runtime.lot_id = self.lots.merge(&parents, &stage.id, now_s);
+            }
+            let runtime = &mut flow.stages[index];
+            if !runtime.busy {
+                continue;
+            }
+            runtime.progress_s += dt_sim;
+            if runtime.progress_s < cycles_s[index] {
+                continue;
+            }
+            runtime.busy = false;
+            runtime.progress_s = 0.0;
+            let lot_id = runtime.lot_id.take();
+            let mut defect = std::mem::take(&mut runtime.defect);
+            let wear = stage_blocks[index].map_or(0.0, |(_, _, wear)| wear);
+            if let Some((block_id, _, _)) = stage_blocks[index] {
+                completed_cycles.push(block_id);
+            }
+            defect.usure += self.quality_book.wear_defect * wear * stage_share;
+            defect.operateur += operator_defect;
+            if stage.cycle_accuracy_defect {
+                // Cuisson hors consigne: cycle etire ou raccourci, plus un alea du four use.
+                let deviation = (cycles_s[index] / stage.cycle_s - 1.0).abs().min(1.0)
+                    + self.quality_book.oven_jitter
+                        * (1.0 + wear)
+                        * self.rng.stream(RngStream::Qualite).next_f64();
+                defect.four += self.quality_book.oven_deviation_defect * deviation;
+            }
 
-        if !self.line.lavage_busy && self.line.raw > 0 {
-            self.line.raw -= 1;
-            self.line.lavage_busy = true;
-            self.line.lavage_progress_s = 0.0;
-        }
-        if self.line.lavage_busy {
-            self.line.lavage_progress_s += dt_sim;
-            if self.line.lavage_progress_s >= lavage_cycle_s {
-                self.line.lavage_busy = false;
-                self.line.lavage_progress_s = 0.0;
-                self.line.washed = self.line.washed.saturating_add(1);
-                self.line.produced_wip_total = self.line.produced_wip_total.saturating_add(1);
-                if let Some(kpi) = self.zone_kpi.get_mut(&ZoneKind::Processing) {
-                    kpi.produced_total = kpi.produced_total.saturating_add(1);
+            let rejected = if let Some(min_grade) = stage.sort_min_grade {
+                let noise =
+                    sorting_noise * (self.rng.stream(RngStream::Qualite).next_f64() * 2.0 - 1.0);
+                let outcome = self.quality_book.grade(&defect, noise, min_grade);
+                if controllers > 0 && self.quality_control.due(now_s) {
+                    self.quality_control.record(
+                        &self.quality_book,
+                        controllers,
+                        LotSample::new(lot_id, &defect, outcome, now_s),
+                    );
+                }
+                outcomes.push(outcome);
+                matches!(outcome, GradeOutcome::Rejected(_))
+            } else {
+                // Rendement deterministe: la part non conforme s'accumule et sort en rebut
+                // des qu'elle represente une unite entiere.
+                runtime.reject_accum += 1.0 - stage.yield_ratio;
+                let rejected = runtime.reject_accum >= 1.0 - 1e-9;
+                if rejected {
+                    runtime.reject_accum = (runtime.reject_accum - 1.0).max(0.0);
+                }
+                rejected
+            };
+            match (rejected, stage.reject) {
+                (true, Some(reject)) => {
+                    self.kpi.scrap_total = self.kpi.scrap_total.saturating_add(reject.qty);
+                    flow.put_items(&[reject], lot_id, &defect);
+                }
+                (true, None) => {
+                    self.kpi.scrap_total = self.kpi.scrap_total.saturating_add(1);
                 }
+                (false, _) => flow.put_items(&stage.outputs, lot_id, &defect),
+            }
+            if stage.counts_as_wip {
+                let units = stage.outputs.iter().map(|output| output.qty).sum::<u32>();
+                self.line.produced_wip_total = self.line.produced_wip_total.saturating_add(units);
+            }
+            if let Some(zone) = stage.kpi_zone
+                && let Some(kpi) = self.zone_kpi.get_mut(&zone)
+            {
+                kpi.produced_total = kpi.produced_total.saturating_add(1);
             }
         }
 
-        if !self.line.coupe_busy && self.line.washed > 0 {
-            self.line.washed -= 1;
-            self.line.coupe_busy = true;
-            self.line.coupe_progress_s = 0.0;
+        let packaging = &recipe.packaging;
+        let units_per_bag = packaging.units_per_bag.max(1);
+        let (good, good_lots) = flow.drain_buffer(packaging.good_item);
+        for portion in good_lots {
+            self.line.packing_lots.insert(portion.lot_id);
+            self.line.packing_last_lot = Some(portion.lot_id);
+        }
+        if good > 0 {
+            let fill = flow.blue_bag_fill.saturating_add(good);
+            let bags = fill / units_per_bag;
+            flow.blue_bag_fill = fill % units_per_bag;
+            if bags > 0 {
+                self.line.sacs_bleus_total = self.line.sacs_bleus_total.saturating_add(bags);
+                flow.descente_bleue_beacon_s = DESCENTE_BEACON_SECONDS;
+            }
         }
-        if self.line.coupe_busy {
-            self.line.coupe_progress_s += dt_sim;
-            if self.line.coupe_progress_s >= coupe_cycle_s {
-                self.line.coupe_busy = false;
-                self.line.coupe_progress_s = 0.0;
-                self.line.sliced = self.line.sliced.saturating_add(1);
-                self.line.produced_wip_total = self.line.produced_wip_total.saturating_add(1);
+        let (rejects, _) = flow.drain_buffer(packaging.reject_item);
+        if rejects > 0 {
+            let fill = flow.red_bag_fill.saturating_add(rejects);
+            let bags = fill / units_per_bag;
+            flow.red_bag_fill = fill % units_per_bag;
+            if bags > 0 {
+                self.line.sacs_rouges_total = self.line.sacs_rouges_total.saturating_add(bags);
+                flow.descente_rouge_beacon_s = DESCENTE_BEACON_SECONDS;
             }
         }
 
-        if !self.line.four_busy && self.line.sliced > 0 {
-            self.line.sliced -= 1;
-            self.line.four_busy = true;
-            self.line.four_progress_s = 0.0;
-        }
-        if self.line.four_busy {
-            self.line.four_progress_s += dt_sim;
-            if self.line.four_progress_s >= four_cycle_s {
-                self.line.four_busy = false;
-                self.line.four_progress_s = 0.0;
-                self.line.dehydrated = self.line.dehydrated.saturating_add(1);
-                if let Some(kpi) = self.zone_kpi.get_mut(&ZoneKind::Shipping) {
-                    kpi.produced_total = kpi.produced_total.saturating_add(1);
-                }
+        let buffered = flow
+            .buffers
+            .values()
+            .fold(0u32, |acc, qty| acc.saturating_add(*qty));
+        let busy_stages = flow.stages.iter().filter(|stage| stage.busy).count() as u32;
+        flow.wip = buffered.saturating_add(busy_stages);
+
+        let units_per_box = units_per_bag.saturating_mul(packaging.bags_per_box.max(1));
+        if let Some(line) = self
+            .production_lines
+            .iter_mut()
+            .find(|line| line.id == line_id)
+        {
+            line.record_output(good, rejects, units_per_box, dt_sim / 3600.0);
+            for outcome in outcomes {
+                line.quality.record(outcome);
             }
         }
 
-        if !self.line.floc_busy && self.line.dehydrated > 0 {
-            self.line.dehydrated -= 1;
-            self.line.floc_busy = true;
-            self.line.floc_progress_s = 0.0;
+        for block_id in completed_cycles {
+            self.wear_machine_block(block_id);
         }
-        if self.line.floc_busy {
-            self.line.floc_progress_s += dt_sim;
-            if self.line.floc_progress_s >= floc_cycle_s {
-                self.line.floc_busy = false;
-                self.line.floc_progress_s = 0.0;
-                self.line.flakes = self.line.flakes.saturating_add(1);
-            }
+        self.sync_modern_finished_boxes(line_id);
+        self.line.refresh_aggregates();
+    }
+
+    // Fatigue, besoins et competence technique moyens des operateurs, plus l'hygiene
+    // de la zone la plus sale de la ligne, en defaut par unite.
+    fn line_operator_defect(&self, line_id: ProductionLineId) -> f64 {
+        let (count, fatigue, deficit, skill) = self.personnel.line_operators(line_id).fold(
+            (0usize, 0.0, 0.0, 0.0),
+            |(count, fatigue, deficit, skill), employee| {
+                (
+                    count + 1,
+                    fatigue + employee.fatigue as f64,
+                    deficit + employee.needs.deficit(&self.needs_book),
+                    skill + WorkType::Production.skill(&employee.skills) as f64,
+                )
+            },
+        );
+        if count == 0 {
+            return 0.0;
         }
+        let hygiene = self
+            .line_zones(line_id)
+            .into_iter()
+            .map(|zone| self.hse.hygiene(zone))
+            .fold(100.0, f64::min);
+        let book = &self.quality_book;
+        let fatigue = fatigue / count as f64;
+        let skill = skill / count as f64;
+        book.fatigue_defect * (fatigue / 100.0).clamp(0.0, 1.0)
+            + self.needs_book.defect(deficit / count as f64)
+            + book.skill_defect * (book.skill_reference - skill)
+            + self.hse_book.hygiene_defect(hygiene)
+    }
+
+    fn line_zones(&self, line_id: ProductionLineId) -> BTreeSet<ZoneKind> {
+        let Some(group) = self.modern_group_for_line(line_id) else {
+            return BTreeSet::new();
+        };
+        self.blocks
+            .iter()
+            .filter(|block| group.block_ids.contains(&block.id))
+            .map(|block| self.zones.get(block.origin_tile))
+            .collect()
+    }
 
-        if !self.line.sortex_busy && self.line.flakes > 0 {
-            self.line.flakes -= 1;
-            self.line.sortex_busy = true;
-            self.line.sortex_progress_s = 0.0;
+    // Hygiene des zones de travail, nettoyage, prevention et accidents du travail.
+    fn tick_hse(&mut self, dt_sim: f64) {
+        let dt_h = dt_sim / 3600.0;
+        if !dt_h.is_finite() || dt_h <= 0.0 {
+            return;
         }
-        if self.line.sortex_busy {
-            self.line.sortex_progress_s += dt_sim;
-            if self.line.sortex_progress_s >= sortex_cycle_s {
-                self.line.sortex_busy = false;
-                self.line.sortex_progress_s = 0.0;
-                if self.modern_next_sortex_unit_is_blue() {
-                    self.line.blue_bag_fill = self.line.blue_bag_fill.saturating_add(1);
-                    if self.line.blue_bag_fill >= SAC_CAPACITY_UNITS {
-                        self.line.blue_bag_fill = 0;
-                        self.line.sacs_bleus_total = self.line.sacs_bleus_total.saturating_add(1);
-                        self.line.descente_bleue_beacon_s = 7.0;
-                    }
-                } else {
-                    self.kpi.scrap_total = self.kpi.scrap_total.saturating_add(1);
-                    self.line.red_bag_fill = self.line.red_bag_fill.saturating_add(1);
-                    if self.line.red_bag_fill >= SAC_CAPACITY_UNITS {
-                        self.line.red_bag_fill = 0;
-                        self.line.sacs_rouges_total = self.line.sacs_rouges_total.saturating_add(1);
-                        self.line.descente_rouge_beacon_s = 7.0;
-                    }
-                }
+        let now_s = self.clock.seconds();
+        for employee in &mut self.personnel.employees {
+            if employee
+                .injured_until_s
+                .is_some_and(|until_s| now_s >= until_s)
+            {
+                employee.recover();
+            }
+        }
+
+        // Lignes en marche et agents au travail salissent leur zone.
+        let mut worked = self
+            .agents
+            .iter()
+            .filter(|agent| agent.employee_id.is_some() && agent.current_job.is_some())
+            .map(|agent| self.zones.get(agent.tile))
+            .collect::<BTreeSet<_>>();
+        for line in &self.production_lines {
+            if line.status == LineOperationalState::Active {
+                worked.extend(self.line_zones(line.id));
             }
         }
+        let cleaners = self.personnel.active_count_for_work(WorkType::Nettoyage);
+        let cleaned = self
+            .hse
+            .tick_hygiene(&self.hse_book, &worked, cleaners, dt_h);
+        self.personnel.set_role_activity(
+            EmployeeRole::AgentEntretien,
+            if cleaned.is_some() { cleaners } else { 0 },
+            now_s,
+            format!(
+                "Nettoie la zone {}",
+                cleaned.map_or("-", |zone| zone.label())
+            ),
+            "Zones propres: ronde de proprete",
+            70.0,
+        );
 
-        self.sync_modern_finished_boxes();
+        let managers = self.personnel.active_count_for_work(WorkType::Prevention);
+        let prevention = managers > 0;
+        self.personnel.set_role_activity(
+            EmployeeRole::ResponsableHse,
+            managers,
+            now_s,
+            format!(
+                "Audite les postes: risque accident x{:.1}",
+                self.hse_book.prevention_factor
+            ),
+            "Prepare l'audit securite",
+            76.0,
+        );
 
-        self.line.wip = self
-            .line
-            .washed
-            .saturating_add(self.line.sliced)
-            .saturating_add(self.line.dehydrated)
-            .saturating_add(self.line.flakes)
-            .saturating_add(self.line.lavage_busy as u32)
-            .saturating_add(self.line.coupe_busy as u32)
-            .saturating_add(self.line.four_busy as u32)
-            .saturating_add(self.line.floc_busy as u32)
-            .saturating_add(self.line.sortex_busy as u32);
-        self.line.machine_a_busy = self.line.lavage_busy || self.line.coupe_busy;
-        self.line.machine_b_busy =
-            self.line.four_busy || self.line.floc_busy || self.line.sortex_busy;
-        self.line.machine_a_progress = if self.line.coupe_busy {
-            self.line.coupe_progress_s
-        } else {
-            self.line.lavage_progress_s
-        };
-        self.line.machine_b_progress = if self.line.sortex_busy {
-            self.line.sortex_progress_s
-        } else if self.line.floc_busy {
-            self.line.floc_progress_s
-        } else {
-            self.line.four_progress_s
-        };
+        let exposures = self
+            .personnel
+            .employees
+            .iter()
+            .filter(|employee| {
+                employee.role != EmployeeRole::Patron && employee.is_active_for_business()
+            })
+            .map(|employee| {
+                // Sans agent sur la carte: poste de bureau.
+                let zone = self
+                    .agent_for_employee(employee.id)
+                    .map_or(ZoneKind::Support, |agent| self.zones.get(agent.tile));
+                (employee.id, employee.fatigue as f64, self.hse.hygiene(zone))
+            })
+            .collect::<Vec<_>>();
+        for (employee_id, fatigue, hygiene) in exposures {
+            let chance = self
+                .hse_book
+                .accident_chance(fatigue, hygiene, prevention, dt_h);
+            if !self.rng.stream(RngStream::Personnel).chance(chance) {
+                continue;
+            }
+            let until_s = now_s + self.hse_book.absence_h * 3600.0;
+            let Some(employee) = self.personnel.employee_mut(employee_id) else {
+                continue;
+            };
+            employee.injure(until_s);
+            let (name, role) = (employee.name.clone(), employee.role);
+            self.hse.record_accident(&name, role.label(), now_s);
+            if self.hse_book.accident_cost_eur > 0.0 {
+                self.economy
+                    .spend(Account::Accidents, self.hse_book.accident_cost_eur);
+            }
+            self.set_action_status(format!(
+                "Accident du travail: {name} ({}) en arret {:.0}h",
+                role.label(),
+                self.hse_book.absence_h
+            ));
+        }
     }
 
-    fn sync_modern_finished_boxes(&mut self) {
-        let expected_boxes = self.line.sacs_bleus_total / SACS_PAR_BOX;
+    // Les boxes fermees attendent au bout de la ligne qui a rempli le dernier sac.
+    fn sync_modern_finished_boxes(&mut self, line_id: ProductionLineId) {
+        let bags_per_box = self.recipes.active().packaging.bags_per_box.max(1);
+        let expected_boxes = self.line.sacs_bleus_total / bags_per_box;
         if expected_boxes <= self.line.boxes_bleues_total {
             return;
         }
 
         let new_boxes = expected_boxes - self.line.boxes_bleues_total;
         self.line.boxes_bleues_total = expected_boxes;
-        self.line.finished = self.line.finished.saturating_add(new_boxes);
+        self.lots
+            .record_boxes(new_boxes, &self.line.packing_lots, self.clock.seconds());
+        // Le sac en cours de remplissage continue le dernier lot emballe.
+        self.line.packing_lots = self.line.packing_last_lot.into_iter().collect();
+        let flow = self.line.flows.entry(line_id).or_default();
+        flow.boxes_to_ship = flow.boxes_to_ship.saturating_add(new_boxes);
         self.line.produced_finished_total =
             self.line.produced_finished_total.saturating_add(new_boxes);
     }
@@ -3818,13 +6169,12 @@ impl FactorySim {
             .find(|block| block.kind == BlockKind::Storage)
         {
             storage.inventory.stacks.clear();
-            if self.line.raw > 0 {
-                storage.inventory.add_stack(ItemStack {
-                    kind: ItemKind::Raw,
-                    qty: self.line.raw,
-                    lot_id: 1,
-                });
-            }
+            add_lot_stacks(
+                &mut storage.inventory,
+                ItemKind::Raw,
+                self.line.raw,
+                &self.stock.raw_line_input_lots,
+            );
         }
         if let Some(machine_b) = self
             .blocks
@@ -3832,31 +6182,25 @@ impl FactorySim {
             .find(|block| block.kind == BlockKind::MachineB)
         {
             machine_b.inventory.stacks.clear();
-            if self.line.wip > 0 {
-                machine_b.inventory.add_stack(ItemStack {
-                    kind: ItemKind::Wip,
-                    qty: self.line.wip,
-                    lot_id: 2,
-                });
-            }
+            add_lot_stacks(
+                &mut machine_b.inventory,
+                ItemKind::Wip,
+                self.line.wip,
+                &self.line.legacy_wip_lots,
+            );
         }
     }
 
     fn reservation_keys_for_job(&self, kind: &JobKind) -> Vec<ReservationKey> {
         match kind {
-            JobKind::Haul {
-                from_block,
-                to_block,
-                ..
-            } => vec![
-                ReservationKey::BlockOutput(*from_block),
-                ReservationKey::BlockInput(*to_block),
-                ReservationKey::InventorySlot(*from_block, 0),
-            ],
+            // Les trajets paralleles ne se bloquent pas: la depose est bornee par
+            // le stock restant a la source et la place a la destination.
+            JobKind::Haul { .. } => Vec::new(),
             JobKind::OperateMachine { block_id } => vec![
                 ReservationKey::BlockInput(*block_id),
                 ReservationKey::BlockOutput(*block_id),
             ],
+            JobKind::Repair { .. } => Vec::new(),
         }
     }
 
@@ -3900,6 +6244,9 @@ impl FactorySim {
             } => *from_block == block_id || *to_block == block_id,
             JobKind::OperateMachine {
                 block_id: job_block,
+            }
+            | JobKind::Repair {
+                block_id: job_block,
             } => *job_block == block_id,
         }
     }
@@ -3908,14 +6255,14 @@ impl FactorySim {
         if removed_job_ids.is_empty() {
             return;
         }
-        if self
-            .agent
-            .current_job
-            .is_some_and(|job_id| removed_job_ids.contains(&job_id))
-        {
-            self.agent.current_job = None;
-            self.agent.job_progress_s = 0.0;
-            self.agent.decision_debug = "job annule: cible invalide".to_string();
+        for agent in &mut self.agents {
+            if agent
+                .current_job
+                .is_some_and(|job_id| removed_job_ids.contains(&job_id))
+            {
+                agent.clear_job();
+                agent.decision_debug = "job annule: cible invalide".to_string();
+            }
         }
         self.jobs.retain(|job| !removed_job_ids.contains(&job.id));
         self.reservations
@@ -3947,7 +6294,9 @@ impl FactorySim {
                     to_block,
                     ..
                 } => !block_ids.contains(from_block) || !block_ids.contains(to_block),
-                JobKind::OperateMachine { block_id } => !block_ids.contains(block_id),
+                JobKind::OperateMachine { block_id } | JobKind::Repair { block_id } => {
+                    !block_ids.contains(block_id)
+                }
             })
             .map(|job| job.id)
             .collect::<Vec<_>>();
@@ -3965,10 +6314,25 @@ impl FactorySim {
         self.purge_jobs_with_missing_blocks();
         self.jobs.retain(|job| !matches!(job.state, JobState::Done));
 
-        let storage_id = self
+        let broken_blocks = self
             .blocks
             .iter()
-            .find(|b| b.kind == BlockKind::Storage)
+            .filter(|block| block.machine.is_some_and(|machine| machine.broken))
+            .map(|block| block.id)
+            .collect::<Vec<_>>();
+        for block_id in broken_blocks {
+            self.ensure_job(JobKind::Repair { block_id }, 90, "reparer panne");
+        }
+
+        let storage_id = self
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::Storage)
+            .map(|b| b.id);
+        let seller_id = self
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::Seller)
             .map(|b| b.id);
         let machine_a_id = self
             .blocks
@@ -3980,25 +6344,78 @@ impl FactorySim {
             .iter()
             .find(|b| b.kind == BlockKind::MachineB)
             .map(|b| b.id);
-        let seller_id = self
-            .blocks
+
+        // Les manutentions portent la matiere: le stock ne bouge qu'a la depose.
+        // Le nombre de trajets ouverts suit la quantite a deplacer, ligne par ligne.
+        let endpoints = self.line_haul_endpoints();
+        let raw_to_feed = self
+            .stock
+            .raw_receiving
+            .min(self.stock.raw_line_input_room())
+            .div_ceil(endpoints.len().max(1) as u32);
+        let mut wanted_hauls = Vec::new();
+        for (line_id, input_block, output_block) in endpoints {
+            if let (Some(storage), Some(input)) = (storage_id, input_block) {
+                let trips = raw_to_feed
+                    .div_ceil(HAUL_RAW_UNITS_PER_TRIP)
+                    .min(MAX_HAUL_TRIPS_PER_LINE);
+                wanted_hauls.extend((0..trips).map(|trip| {
+                    (
+                        JobKind::Haul {
+                            from_block: storage,
+                            to_block: input,
+                            item_kind: ItemKind::Raw,
+                            qty: HAUL_RAW_UNITS_PER_TRIP,
+                            line_id,
+                            trip,
+                        },
+                        50,
+                    )
+                }));
+            }
+            let boxes = self
+                .line
+                .flow(line_id)
+                .map_or(0, LineFlowState::boxes_to_ship);
+            if let (Some(output), Some(shipping)) = (output_block, seller_id.or(storage_id)) {
+                let trips = boxes
+                    .div_ceil(HAUL_BOXES_PER_TRIP)
+                    .min(MAX_HAUL_TRIPS_PER_LINE);
+                wanted_hauls.extend((0..trips).map(|trip| {
+                    (
+                        JobKind::Haul {
+                            from_block: output,
+                            to_block: shipping,
+                            item_kind: ItemKind::Finished,
+                            qty: HAUL_BOXES_PER_TRIP,
+                            line_id,
+                            trip,
+                        },
+                        80,
+                    )
+                }));
+            }
+        }
+        // Un trajet devenu inutile (entree pleine, bout de ligne vide) sort de la file
+        // tant qu'aucun agent ne l'a pris.
+        let stale_hauls = self
+            .jobs
             .iter()
-            .find(|b| b.kind == BlockKind::Seller)
-            .map(|b| b.id);
+            .filter(|job| {
+                matches!(job.kind, JobKind::Haul { .. })
+                    && job.assigned_agent.is_none()
+                    && !wanted_hauls.iter().any(|(kind, _)| *kind == job.kind)
+            })
+            .map(|job| job.id)
+            .collect::<Vec<_>>();
+        self.purge_jobs_by_ids(&stale_hauls);
+        for (kind, priority) in wanted_hauls {
+            self.ensure_job(kind, priority, "manutention ligne");
+        }
 
-        if let (Some(storage), Some(machine_a)) = (storage_id, machine_a_id)
+        if let Some(machine_a) = machine_a_id
             && self.line.raw > 0
         {
-            self.ensure_job(
-                JobKind::Haul {
-                    from_block: storage,
-                    to_block: machine_a,
-                    item_kind: ItemKind::Raw,
-                    qty: 1,
-                },
-                50,
-                "alimenter machine A",
-            );
             self.ensure_job(
                 JobKind::OperateMachine {
                     block_id: machine_a,
@@ -4018,19 +6435,53 @@ impl FactorySim {
                 "operer B",
             );
         }
-        if let (Some(machine_b), Some(seller)) = (machine_b_id, seller_id)
-            && self.line.produced_finished_total > self.line.sold_total
-        {
-            self.ensure_job(
-                JobKind::Haul {
-                    from_block: machine_b,
-                    to_block: seller,
-                    item_kind: ItemKind::Finished,
-                    qty: 1,
-                },
-                80,
-                "expedier produits finis",
-            );
+    }
+
+    // (ligne, bloc d'entree matiere, bloc de sortie des boxes) de chaque ligne:
+    // tremie et descente sac bleu en moderne, machines A et B en legacy.
+    fn line_haul_endpoints(&self) -> Vec<(ProductionLineId, Option<BlockId>, Option<BlockId>)> {
+        if self.modern_line_present() {
+            return self
+                .production_line_ids()
+                .into_iter()
+                .map(|line_id| {
+                    (
+                        line_id,
+                        self.modern_block_of_line(line_id, BlockKind::InputHopper)
+                            .map(|block| block.id),
+                        self.modern_block_of_line(line_id, BlockKind::BlueBagChute)
+                            .map(|block| block.id),
+                    )
+                })
+                .collect();
+        }
+        vec![(
+            MAIN_PRODUCTION_LINE_ID,
+            self.first_block_by_kind(BlockKind::MachineA)
+                .map(|block| block.id),
+            self.first_block_by_kind(BlockKind::MachineB)
+                .map(|block| block.id),
+        )]
+    }
+
+    // Depose d'une manutention: matiere vers l'entree ligne, boxes vers le stock vendable.
+    fn deliver_haul(&mut self, line_id: ProductionLineId, item_kind: ItemKind, qty: u32) -> u32 {
+        match item_kind {
+            ItemKind::Raw => {
+                let moved = self.stock.transfer_raw_to_line(qty);
+                self.sync_line_raw_from_stock();
+                moved
+            }
+            ItemKind::Finished => {
+                let Some(flow) = self.line.flows.get_mut(&line_id) else {
+                    return 0;
+                };
+                let moved = qty.min(flow.boxes_to_ship);
+                flow.boxes_to_ship -= moved;
+                self.line.finished = self.line.finished.saturating_add(moved);
+                moved
+            }
+            _ => 0,
         }
     }
 
@@ -4059,1094 +6510,3603 @@ impl FactorySim {
         self.next_job_id = self.next_job_id.saturating_add(1);
     }
 
-    fn tick_agent(&mut self, dt_sim: f64) {
-        if let Some(job_id) = self.agent.current_job {
-            if let Some(job_idx) = self.jobs.iter().position(|job| job.id == job_id) {
-                self.jobs[job_idx].state = JobState::InProgress;
-                self.agent.job_progress_s += dt_sim;
-                self.touch_reservations(job_id);
-                let zone = self.zones.get(self.agent.tile);
-                let rules = zone_rules(zone);
-                self.agent.fatigue =
-                    (self.agent.fatigue + dt_sim / 3600.0 * rules.fatigue_factor).clamp(0.0, 100.0);
-                self.agent.stress =
-                    (self.agent.stress + dt_sim / 3600.0 * rules.risk_factor).clamp(0.0, 100.0);
-                let required_time = 4.0 / self.agent.speed_tiles_per_s.max(0.1);
-                if self.agent.job_progress_s >= required_time {
-                    self.jobs[job_idx].state = JobState::Done;
-                    self.agent.current_job = None;
-                    self.agent.job_progress_s = 0.0;
-                    self.release_reservations(job_id);
-                    if self.agent.decision_debug != "tache terminee" {
-                        self.agent.decision_debug.clear();
-                        self.agent.decision_debug.push_str("tache terminee");
+    fn repair_parts_ready(&self, block_id: BlockId) -> bool {
+        self.blocks
+            .iter()
+            .find(|block| block.id == block_id)
+            .and_then(|block| block.machine)
+            .is_some_and(|machine| {
+                machine.repair_parts_taken || self.stock.spare_parts >= SPARE_PARTS_PER_REPAIR
+            })
+    }
+
+    fn machine_broken(&self, block_id: BlockId) -> bool {
+        self.blocks
+            .iter()
+            .find(|block| block.id == block_id)
+            .and_then(|block| block.machine)
+            .is_some_and(|machine| machine.broken)
+    }
+
+    pub fn block_is_broken(&self, block_id: BlockId) -> bool {
+        self.machine_broken(block_id)
+    }
+
+    pub fn broken_machine_count(&self) -> usize {
+        self.blocks
+            .iter()
+            .filter(|block| block.machine.is_some_and(|machine| machine.broken))
+            .count()
+    }
+
+    fn wear_machine_block(&mut self, block_id: BlockId) {
+        let Some(block) = self.blocks.iter_mut().find(|block| block.id == block_id) else {
+            return;
+        };
+        let machine = block.machine.get_or_insert_with(MachineState::default);
+        if machine.record_cycle(self.rng.stream(RngStream::Pannes)) {
+            self.set_action_status(format!("Panne machine B{block_id}: reparation requise"));
+        }
+    }
+
+    fn tick_maintenance(&mut self, dt_sim: f64) {
+        let downtime_minutes = dt_sim / 60.0;
+        for block in &mut self.blocks {
+            if let Some(machine) = block.machine.as_mut()
+                && machine.broken
+            {
+                machine.downtime_minutes += downtime_minutes;
+                self.kpi.downtime_minutes += downtime_minutes;
+                *self.kpi.downtime_by_block.entry(block.id).or_default() += downtime_minutes;
+            }
+        }
+
+        let technicians = self.personnel.active_count_for_work(WorkType::Maintenance);
+        let mut busy = 0usize;
+        let mut repaired = Vec::new();
+        let mut released = Vec::new();
+        for idx in 0..self.jobs.len() {
+            let JobKind::Repair { block_id } = self.jobs[idx].kind else {
+                continue;
+            };
+            if self.jobs[idx].state == JobState::Done {
+                continue;
+            }
+            let job_id = self.jobs[idx].id;
+            // Seul le technicien affecte, arrive au poste, fait avancer la reparation.
+            let technician = self.agents.iter().position(|agent| {
+                agent.current_job == Some(job_id) && agent.job_phase == JobPhase::Work
+            });
+            let Some(machine) = self
+                .blocks
+                .iter_mut()
+                .find(|block| block.id == block_id)
+                .and_then(|block| block.machine.as_mut())
+            else {
+                continue;
+            };
+            let parts_ready =
+                machine.repair_parts_taken || self.stock.spare_parts >= SPARE_PARTS_PER_REPAIR;
+            let Some(agent_idx) = technician else {
+                if self.jobs[idx].assigned_agent.is_none() {
+                    if technicians == 0 {
+                        self.jobs[idx].state =
+                            JobState::Blocked("aucun technicien disponible".to_string());
+                    } else if !parts_ready {
+                        self.jobs[idx].state =
+                            JobState::Blocked("pieces detachees manquantes".to_string());
                     }
                 }
-                return;
+                continue;
+            };
+            // Une reparation interrompue reprend sans consommer de nouvelles pieces.
+            if !machine.repair_parts_taken {
+                if !self.stock.take_spare_parts(SPARE_PARTS_PER_REPAIR) {
+                    self.jobs[idx].state =
+                        JobState::Blocked("pieces detachees manquantes".to_string());
+                    self.jobs[idx].assigned_agent = None;
+                    self.agents[agent_idx].clear_job();
+                    released.push(job_id);
+                    continue;
+                }
+                machine.repair_parts_taken = true;
+            }
+            busy += 1;
+            machine.repair_progress_s += dt_sim;
+            if machine.repair_progress_s >= MACHINE_REPAIR_SECONDS {
+                machine.repair();
+                self.jobs[idx].state = JobState::Done;
+                let agent = &mut self.agents[agent_idx];
+                agent.clear_job();
+                agent.decision_debug = "tache terminee".to_string();
+                released.push(job_id);
+                repaired.push(block_id);
             }
-            self.agent.current_job = None;
-            self.agent.job_progress_s = 0.0;
+        }
+        for job_id in released {
+            self.release_reservations(job_id);
         }
 
-        if let Some(job_idx) = self
-            .jobs
-            .iter()
-            .enumerate()
-            .filter(|(_, job)| matches!(job.state, JobState::Pending | JobState::Blocked(_)))
-            .max_by_key(|(_, job)| job.priority)
-            .map(|(idx, _)| idx)
-        {
-            let job_id = self.jobs[job_idx].id;
-            let keys = self.reservation_keys_for_job(&self.jobs[job_idx].kind);
-            if self.try_reserve_all(keys, job_id).is_ok() {
-                let job_kind = self.jobs[job_idx].kind.clone();
-                let job_priority = self.jobs[job_idx].priority;
-                let job_label = self.job_kind_label(&job_kind);
-                let job_target = self.job_target_label(&job_kind);
-                let agent_id = self.agent.id;
-                self.jobs[job_idx].state = JobState::Claimed;
-                self.jobs[job_idx].assigned_agent = Some(agent_id);
-                self.jobs[job_idx].score_debug = format!(
-                    "priorite={} fatigue={:.1} stress={:.1}",
-                    job_priority, self.agent.fatigue, self.agent.stress
-                );
-                self.agent.current_job = Some(job_id);
-                self.agent.job_progress_s = 0.0;
-                self.agent.decision_debug = format!(
-                    "job=#{job_id} {job_label} cible={job_target} priorite={job_priority} score:{}",
-                    self.jobs[job_idx].score_debug
-                );
+        let now_s = self.clock.seconds();
+        self.personnel.set_role_activity(
+            EmployeeRole::Technicien,
+            busy,
+            now_s,
+            "Repare une machine en panne",
+            if self.stock.spare_parts == 0 {
+                "Attend des pieces detachees"
             } else {
-                let job_kind = self.jobs[job_idx].kind.clone();
-                let job_priority = self.jobs[job_idx].priority;
-                let job_target = self.job_target_label(&job_kind);
-                if !matches!(
-                    self.jobs[job_idx].state,
-                    JobState::Blocked(ref reason) if reason == "conflit reservation"
-                ) {
-                    self.jobs[job_idx].state = JobState::Blocked("conflit reservation".to_string());
+                "Attend une panne a reparer"
+            },
+            84.0,
+        );
+        if let Some(block_id) = repaired.last() {
+            self.set_action_status(format!("Machine B{block_id} reparee"));
+        }
+    }
+
+    // Besoins: usure au poste, recuperation hors poste, depart en pause sous le seuil.
+    fn tick_needs(&mut self, dt_sim: f64, world: Option<&crate::World>) {
+        let dt_h = dt_sim / 3600.0;
+        if !dt_h.is_finite() || dt_h <= 0.0 {
+            return;
+        }
+        let mut departures = Vec::new();
+        for employee in &mut self.personnel.employees {
+            match employee.status {
+                EmployeeStatus::Termine | EmployeeStatus::EnPause => {}
+                EmployeeStatus::Absent | EmployeeStatus::EnFormation | EmployeeStatus::Blesse => {
+                    employee.needs.break_need = None;
+                    employee.needs.recover(&self.needs_book, dt_h);
+                }
+                EmployeeStatus::Disponible | EmployeeStatus::Occupe => {
+                    employee.needs.decay(&self.needs_book, dt_h);
+                    if let Some(need) = employee.needs.most_urgent(&self.needs_book) {
+                        departures.push((employee.id, need));
+                    }
                 }
-                self.agent.decision_debug = format!(
-                    "bloque job=#{job_id} cible={job_target} priorite={job_priority} raison=conflit reservation"
-                );
-            }
-        } else {
-            if self.agent.decision_debug != "inactif(aucune tache en attente)" {
-                self.agent.decision_debug.clear();
-                self.agent
-                    .decision_debug
-                    .push_str("inactif(aucune tache en attente)");
             }
-            self.agent.fatigue = (self.agent.fatigue - dt_sim / 3600.0).clamp(0.0, 100.0);
-            self.agent.stress = (self.agent.stress - dt_sim / 3600.0 * 0.8).clamp(0.0, 100.0);
         }
+        for (employee_id, need) in departures {
+            self.send_on_break(employee_id, need, world);
+        }
+        self.tick_breaks(dt_sim);
     }
 
-    fn refresh_kpi(&mut self, dt_hours: f64) {
-        self.kpi.throughput_per_hour = self.line.sold_total as f64 / self.clock.hours().max(0.01);
-        self.kpi.downtime_minutes += if self.line.machine_a_busy || self.line.machine_b_busy {
-            0.0
-        } else {
-            dt_hours * 60.0
+    // L'employe lache son poste (le job retourne a la file) et rejoint le lieu de pause le plus proche.
+    fn send_on_break(
+        &mut self,
+        employee_id: EmployeeId,
+        need: NeedKind,
+        world: Option<&crate::World>,
+    ) {
+        let Some(venue) = self.needs_book.venue(need) else {
+            return;
         };
-        self.kpi.otif = if self.jobs.is_empty() {
-            1.0
+        let agent_idx = self
+            .agents
+            .iter()
+            .position(|agent| agent.employee_id == Some(employee_id));
+        let from = agent_idx.map_or(self.agent_spawn_tile, |idx| self.agents[idx].tile);
+        let path = self
+            .nearest_break_tile(venue, from)
+            .and_then(|to| Self::plan_agent_path(world, from, to));
+        let place = if path.is_some() {
+            venue.label().to_string()
         } else {
-            let blocked = self
-                .jobs
-                .iter()
-                .filter(|job| matches!(job.state, JobState::Blocked(_)))
-                .count();
-            (1.0 - blocked as f64 / self.jobs.len() as f64).clamp(0.0, 1.0)
+            format!("sur place, aucun {}", venue.label())
         };
-        let stress_scrap = (self.agent.stress / 100.0 * dt_hours * 4.0).max(0.0) as u32;
-        self.kpi.scrap_total = self.kpi.scrap_total.saturating_add(stress_scrap);
+
+        if let Some(agent_idx) = agent_idx {
+            if let Some(job_id) = self.agents[agent_idx].current_job {
+                self.release_reservations(job_id);
+                if let Some(job) = self.jobs.iter_mut().find(|job| job.id == job_id) {
+                    job.state = JobState::Pending;
+                    job.assigned_agent = None;
+                }
+            }
+            let agent = &mut self.agents[agent_idx];
+            agent.clear_job();
+            agent.path = path.clone().unwrap_or_default();
+            agent.decision_debug = format!("pause {} ({place})", need.label());
+        }
+        if let Some(employee) = self.personnel.employee_mut(employee_id) {
+            employee.start_break(need, &place);
+            employee.needs.break_venue = path.is_some().then_some(venue);
+        }
     }
 
-    fn default_layout(map_w: i32, map_h: i32, config: &StarterSimConfig) -> FactoryLayoutAsset {
-        let mut zones = ZoneLayer::new(map_w, map_h, ZoneKind::Neutral);
-        let (fx0, fx1, fy0, fy1) = crate::utilitaires::starter_factory_bounds(map_w, map_h);
-        let width = (fx1 - fx0).max(6);
-        let span_y = (fy1 - fy0).max(6);
-        let recv_x1 = fx0 + width / 3;
-        let proc_x1 = fx0 + (width * 2) / 3;
-        let support_wall_y = (fy1 - 6).max(fy0 + 3);
-        let support_y0 = (support_wall_y + 1).min(fy1 - 1);
+    fn nearest_break_tile(&self, venue: BreakVenue, from: (i32, i32)) -> Option<(i32, i32)> {
+        let distance = |tile: &(i32, i32)| (tile.0 - from.0).abs() + (tile.1 - from.1).abs();
+        match venue {
+            BreakVenue::Lavabo => self.lavabo_tiles.iter().copied().min_by_key(distance),
+            BreakVenue::SallePause => self
+                .zones
+                .tiles_of(ZoneKind::BreakRoom)
+                .min_by_key(distance),
+            BreakVenue::Distributeur => self.zones.tiles_of(ZoneKind::Vending).min_by_key(distance),
+        }
+    }
 
-        for y in fy0 + 1..fy1 {
-            for x in fx0 + 1..=recv_x1 {
-                zones.set((x, y), ZoneKind::Receiving);
+    // Trajet jusqu'au lieu de pause, puis remplissage jusqu'au niveau de retour au poste.
+    fn tick_breaks(&mut self, dt_sim: f64) {
+        let dt_h = dt_sim / 3600.0;
+        for employee in &mut self.personnel.employees {
+            if employee.status != EmployeeStatus::EnPause {
+                continue;
             }
-            for x in recv_x1 + 1..=proc_x1 {
-                zones.set((x, y), ZoneKind::Processing);
+            let mut agent = self
+                .agents
+                .iter_mut()
+                .find(|agent| agent.employee_id == Some(employee.id));
+            if let Some(agent) = agent.as_mut()
+                && !agent.follow_path(dt_sim, agent.speed_tiles_per_s)
+            {
+                continue;
             }
-            for x in proc_x1 + 1..fx1 {
-                zones.set((x, y), ZoneKind::Shipping);
+            let venue = employee.needs.break_venue;
+            employee.needs.refill_at(&self.needs_book, venue, dt_h);
+            let done = employee
+                .needs
+                .break_need
+                .is_none_or(|need| employee.needs.level(need) >= self.needs_book.break_target);
+            if done {
+                employee.end_break();
+                if let Some(agent) = agent {
+                    agent.decision_debug = "fin de pause".to_string();
+                }
             }
         }
+    }
 
-        for y in support_y0..fy1 {
-            for x in fx0 + 1..fx1 {
-                zones.set((x, y), ZoneKind::Support);
+    // Collegues arrives au meme lieu de pause: chaque echange noue ou abime leur
+    // relation, que le moral lit ensuite.
+    fn tick_break_exchanges(&mut self, dt_sim: f64) {
+        let dt_h = dt_sim / 3600.0;
+        let rate = self.morale_book.break_exchanges_per_h;
+        if !(dt_h > 0.0 && rate > 0.0) {
+            return;
+        }
+        let resting = self
+            .personnel
+            .employees
+            .iter()
+            .filter(|employee| employee.status == EmployeeStatus::EnPause)
+            .filter_map(|employee| {
+                let venue = employee.needs.break_venue?;
+                let agent = self
+                    .agents
+                    .iter()
+                    .find(|agent| agent.employee_id == Some(employee.id))?;
+                agent.path.is_empty().then_some((
+                    employee.id,
+                    venue,
+                    agent.tile,
+                    employee.stress as f64,
+                ))
+            })
+            .collect::<Vec<_>>();
+        if resting.len() < 2 {
+            return;
+        }
+
+        let chance = 1.0 - (-rate * dt_h).exp();
+        let hostile_share = self.morale_book.hostile_exchange_share;
+        let mut exchanged = false;
+        for (i, &(a, venue, tile, stress)) in resting.iter().enumerate() {
+            for &(b, other_venue, other_tile, other_stress) in &resting[i + 1..] {
+                let distance = (tile.0 - other_tile.0).abs() + (tile.1 - other_tile.1).abs();
+                if venue != other_venue || distance > BREAK_EXCHANGE_RANGE_TILES {
+                    continue;
+                }
+                let rng = self.rng.stream(RngStream::Relations);
+                if !rng.chance(chance) {
+                    continue;
+                }
+                let kind = pick_exchange(rng, (stress + other_stress) / 200.0, hostile_share);
+                self.personnel.relations.record(a, b, kind);
+                exchanged = true;
             }
         }
+        if exchanged {
+            self.personnel.refresh_relationships();
+        }
+    }
 
-        let flow_y = (fy0 + span_y / 2).clamp(fy0 + 2, fy1 - 2);
-        let ship_y_hi = (support_y0 - 1).max(fy0 + 2);
-        let ship_y = (fy0 + (span_y * 3) / 4).clamp(fy0 + 2, ship_y_hi);
-        let clamp_tile =
-            |x: i32, y: i32| (x.clamp(1, map_w - 2).max(1), y.clamp(1, map_h - 2).max(1));
-        let storage_x = (fx0 + 2).min(recv_x1);
-        let machine_a_x = (recv_x1 + 1).min(proc_x1);
-        let machine_b_x = ((machine_a_x + proc_x1) / 2).max(machine_a_x);
-        let buffer_x = (proc_x1 + 1).max(machine_b_x).min(fx1 - 2);
-        let seller_x = (fx1 - 2).max(buffer_x);
-        let storage_tile = clamp_tile(storage_x, flow_y);
-        let machine_a_tile = clamp_tile(machine_a_x, flow_y);
-        let machine_b_tile = clamp_tile(machine_b_x, flow_y);
-        let buffer_tile = clamp_tile(buffer_x, ship_y);
-        let seller_tile = clamp_tile(seller_x, support_y0.max(fy0 + 2));
-        let agent_tile = clamp_tile((fx0 + fx1) / 2, (fy1 - 2).max(fy0 + 2));
+    fn agent_on_break(&self, agent: &SimAgent) -> bool {
+        agent
+            .employee_id
+            .and_then(|employee_id| self.personnel.employee(employee_id))
+            .is_some_and(|employee| employee.status == EmployeeStatus::EnPause)
+    }
 
-        let blocks = vec![
-            BlockInstance {
-                id: 1,
-                kind: BlockKind::Storage,
-                origin_tile: storage_tile,
-                footprint: (1, 1),
-                orientation: BlockOrientation::East,
-                inventory: BlockInventory::default(),
-                machine: None,
-                rack_palettes: [false; RACK_NIVEAU_COUNT],
-            },
-            BlockInstance {
-                id: 2,
-                kind: BlockKind::MachineA,
-                origin_tile: machine_a_tile,
-                footprint: (1, 1),
-                orientation: BlockOrientation::East,
-                inventory: BlockInventory::default(),
-                machine: Some(MachineState {
-                    recipe_id: RecipeId::RawToWip,
-                    cycle_s: config.machine_a_cycle_s.max(1.0),
-                    ..MachineState::default()
-                }),
-                rack_palettes: [false; RACK_NIVEAU_COUNT],
-            },
-            BlockInstance {
-                id: 3,
-                kind: BlockKind::MachineB,
-                origin_tile: machine_b_tile,
-                footprint: (1, 1),
-                orientation: BlockOrientation::East,
-                inventory: BlockInventory::default(),
-                machine: Some(MachineState {
-                    recipe_id: RecipeId::WipToFinished,
-                    cycle_s: config.machine_b_cycle_s.max(1.0),
-                    ..MachineState::default()
-                }),
-                rack_palettes: [false; RACK_NIVEAU_COUNT],
-            },
-            BlockInstance {
-                id: 4,
-                kind: BlockKind::Buffer,
-                origin_tile: buffer_tile,
-                footprint: (1, 1),
-                orientation: BlockOrientation::East,
-                inventory: BlockInventory::default(),
-                machine: None,
-                rack_palettes: [false; RACK_NIVEAU_COUNT],
-            },
-            BlockInstance {
-                id: 5,
-                kind: BlockKind::Seller,
-                origin_tile: seller_tile,
-                footprint: (1, 1),
-                orientation: BlockOrientation::East,
-                inventory: BlockInventory::default(),
-                machine: None,
-                rack_palettes: [false; RACK_NIVEAU_COUNT],
-            },
-        ];
-
-        FactoryLayoutAsset {
-            schema_version: FACTORY_LAYOUT_SCHEMA_VERSION,
-            map_w,
-            map_h,
-            zones,
-            blocks,
-            agent_tile,
-        }
+    fn agent_work_speed(&self, agent: &SimAgent) -> f64 {
+        agent
+            .employee_id
+            .and_then(|employee_id| self.personnel.employee(employee_id))
+            .map_or(1.0, |employee| self.employee_work_speed(employee))
     }
 
-    fn load_or_create_layout(
-        path: &str,
-        map_w: i32,
-        map_h: i32,
-        config: &StarterSimConfig,
-    ) -> (FactoryLayoutAsset, Option<String>) {
-        match Self::load_layout_asset(path) {
-            Ok(layout) => {
-                if layout.map_w != map_w || layout.map_h != map_h {
-                    return (
-                        Self::default_layout(map_w, map_h, config),
-                        Some(format!(
-                            "layout usine ignore car dimensions {}x{} != carte {}x{}",
-                            layout.map_w, layout.map_h, map_w, map_h
-                        )),
-                    );
+    // Moral du personnel: plaintes et demissions au fil de l'eau, soldes passes en charge au depart.
+    fn tick_morale(&mut self, dt_hours: f64) {
+        let events = self.morale_book.tick(
+            &mut self.personnel,
+            &self.needs_book,
+            self.clock.seconds(),
+            dt_hours,
+        );
+        for event in events {
+            if let MoraleEvent::Departure {
+                employee_id,
+                role,
+                exit_cost_eur,
+                ..
+            } = &event
+            {
+                if *exit_cost_eur > 0.0 {
+                    self.economy.spend(Account::Departs, *exit_cost_eur);
                 }
-                if layout.blocks.is_empty() {
-                    return (
-                        Self::default_layout(map_w, map_h, config),
-                        Some("layout usine vide, defaut actif".to_string()),
-                    );
+                if *role == EmployeeRole::ChefEquipe {
+                    for line in &mut self.production_lines {
+                        if line.assigned_lead_id == Some(*employee_id) {
+                            line.assigned_lead_id = None;
+                            line.set_blocked("aucun chef d'equipe assigne");
+                        }
+                    }
                 }
-                (layout, None)
-            }
-            Err(err) if err.contains("echec lecture layout usine") => {
-                let layout = Self::default_layout(map_w, map_h, config);
-                let warning =
-                    Self::save_layout_static_to_path(path, &layout)
-                        .err()
-                        .map(|save_err| {
-                            format!(
-                                "layout usine par defaut actif, ecriture impossible: {save_err}"
-                            )
-                        });
-                (layout, warning)
-            }
-            Err(err) => {
-                let layout = Self::default_layout(map_w, map_h, config);
-                (
-                    layout,
-                    Some(format!("layout usine invalide, defaut non persiste: {err}")),
-                )
             }
+            self.set_action_status(event.summary());
         }
     }
 
-    fn load_layout_asset(path: &str) -> Result<FactoryLayoutAsset, String> {
-        let raw = fs::read_to_string(path).map_err(|e| {
-            if e.kind() == ErrorKind::NotFound {
-                format!("echec lecture layout usine: {e}")
-            } else {
-                format!("layout usine illisible: {e}")
+    // Competences: pratique du travail en cours, oubli, retours de formation.
+    fn tick_training(&mut self, dt_hours: f64) {
+        let practiced = self
+            .personnel
+            .employees
+            .iter()
+            .filter(|employee| employee.status == EmployeeStatus::Occupe)
+            .filter_map(|employee| {
+                let agent_work = self
+                    .agents
+                    .iter()
+                    .find(|agent| agent.employee_id == Some(employee.id))
+                    .and_then(|agent| agent.current_job)
+                    .and_then(|job_id| self.jobs.iter().find(|job| job.id == job_id))
+                    .map(|job| job_work_type(&job.kind));
+                agent_work
+                    .or_else(|| employee.role.primary_work())
+                    .map(|work| (employee.id, work))
+            })
+            .collect::<HashMap<_, _>>();
+        let completions = self
+            .training_book
+            .tick(&mut self.personnel, &practiced, dt_hours);
+        for completion in completions {
+            self.set_action_status(completion.summary());
+        }
+    }
+
+    // Tableau de recrutement: candidats qui vont et viennent, embauches qui prennent leur poste.
+    fn tick_recruitment(&mut self) {
+        let events = self.recruitment.tick(
+            &self.recruitment_book,
+            &mut self.personnel,
+            &self.character_catalog,
+            &self.morale_book,
+            self.clock.seconds(),
+            self.rng.stream(RngStream::Personnel),
+        );
+        for event in events {
+            if let RecruitmentEvent::Arrived {
+                employee_id,
+                role: EmployeeRole::ChefEquipe,
+                ..
+            } = &event
+            {
+                let _ = self.assign_lead_to_free_line(*employee_id);
             }
-        })?;
-        let layout: FactoryLayoutAsset =
-            ron_from_str(&raw).map_err(|e| format!("echec lecture RON layout usine: {e}"))?;
-        layout.validate()?;
-        Ok(layout)
-    }
-
-    fn save_layout_asset(&self, layout: &FactoryLayoutAsset) -> Result<(), String> {
-        Self::save_layout_static(layout)
+            self.set_action_status(event.summary());
+        }
     }
 
-    fn save_layout_static(layout: &FactoryLayoutAsset) -> Result<(), String> {
-        Self::save_layout_static_to_path(FACTORY_LAYOUT_PATH, layout)
+    // Les frais d'embauche partent a l'acceptation; l'employe arrive apres son preavis.
+    fn offer_candidate(
+        &mut self,
+        candidate_id: CandidateId,
+        hourly_wage: f64,
+    ) -> Result<String, String> {
+        let candidate = self
+            .recruitment
+            .candidate(candidate_id)
+            .ok_or_else(|| format!("candidat introuvable: {candidate_id}"))?;
+        let (name, role) = (candidate.name.clone(), candidate.role);
+        let cost = role.hiring_cost_eur();
+        if self.economy.cash < cost {
+            return Err(format!(
+                "Tresorerie insuffisante: {:.0} EUR requis pour recruter {}",
+                cost,
+                role.label()
+            ));
+        }
+        let now_s = self.clock.seconds();
+        let accepted = self
+            .recruitment
+            .make_offer(
+                &self.recruitment_book,
+                candidate_id,
+                hourly_wage,
+                now_s,
+                self.rng.stream(RngStream::Personnel),
+            )?
+            .map(|pending| pending.starts_at_s);
+        let Some(starts_at_s) = accepted else {
+            let withdrawn = self.recruitment.candidate(candidate_id).is_none();
+            return Ok(if withdrawn {
+                format!("{name} refuse {hourly_wage:.0} EUR/h et se retire")
+            } else {
+                format!("{name} refuse {hourly_wage:.0} EUR/h")
+            });
+        };
+        if cost > 0.0 {
+            self.economy.spend(Account::Recrutement, cost);
+        }
+        Ok(format!(
+            "{name} accepte {hourly_wage:.0} EUR/h: {} a partir de J{}",
+            role.label(),
+            (starts_at_s / 86_400.0) as u64
+        ))
     }
 
-    fn save_layout_static_to_path(path: &str, layout: &FactoryLayoutAsset) -> Result<(), String> {
-        layout.validate()?;
-        let payload = ron_to_string_pretty(layout, PrettyConfig::new().depth_limit(4))
-            .map_err(|e| format!("echec serialisation layout usine: {e}"))?;
-        if let Some(parent) = Path::new(path).parent()
-            && !parent.as_os_str().is_empty()
-        {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("echec creation dossier layout usine: {e}"))?;
+    // Un chef fraichement embauche prend la premiere ligne sans chef.
+    fn assign_lead_to_free_line(&mut self, lead_id: EmployeeId) -> Result<(), String> {
+        let Some(line_id) = self
+            .production_line_ids()
+            .into_iter()
+            .find(|line_id| self.personnel.team_lead_for_line(*line_id).is_none())
+        else {
+            return Ok(());
+        };
+        self.personnel.assign_to_line(lead_id, line_id)?;
+        if let Some(line) = self.line_state_mut(line_id) {
+            line.assigned_lead_id = Some(lead_id);
         }
-        fs::write(path, payload).map_err(|e| format!("echec ecriture layout usine: {e}"))
+        Ok(())
     }
 
-    pub fn debug_hud(&self) -> String {
-        let day = self.clock.day_index();
-        let machine_a = if self.line.machine_a_busy {
-            "actif"
-        } else {
-            "inactif"
-        };
-        let machine_b = if self.line.machine_b_busy {
-            "actif"
-        } else {
-            "inactif"
-        };
-        let pending_jobs = self
-            .jobs
-            .iter()
-            .filter(|job| matches!(job.state, JobState::Pending))
-            .count();
-        let blocked_jobs = self
-            .jobs
+    // Chaque employe actif a son agent; les departs rendent leur job a la file.
+    // Le patron garde toujours un seul agent, lie a sa fiche quand elle existe.
+    fn sync_agents_with_personnel(&mut self) {
+        let patron = self
+            .personnel
+            .employees
             .iter()
-            .filter_map(|job| match &job.state {
-                JobState::Blocked(reason) => Some(reason.as_str()),
-                _ => None,
-            })
-            .collect::<Vec<_>>()
-            .join(" | ");
-        let blocked_label = if blocked_jobs.is_empty() {
-            "aucun"
-        } else {
-            blocked_jobs.as_str()
-        };
-        let zone_summary = self
-            .zone_kpi
+            .find(|employee| employee.role == EmployeeRole::Patron)
+            .map(|employee| (employee.id, employee.fatigue as f64, employee.stress as f64));
+        let active = self
+            .personnel
+            .employees
             .iter()
-            .map(|(zone, kpi)| {
-                format!(
-                    "{}={}/obj{:.0}",
-                    zone.label(),
-                    kpi.produced_total,
-                    kpi.target_per_hour
+            .filter(|employee| employee.role != EmployeeRole::Patron && employee.is_on_site())
+            .map(|employee| {
+                (
+                    employee.id,
+                    employee.role,
+                    employee.fatigue as f64,
+                    employee.stress as f64,
                 )
             })
-            .collect::<Vec<_>>()
-            .join(" ");
-        format!(
-            "Simulation J{day} {} ({:.2} h)\nFinances: tresorerie={:.2} | revenu={:.2} | cout={:.2} | profit={:.2}\nFlux ligne: matieres={} en-cours={} finis={} ventes_totales={}\nMachines: A={} | B={}\nJobs: en_attente={} | bloques={} | reservations={}\nAgent: tuile=({}, {}) fatigue={:.1} stress={:.1} job_actuel={:?}\nKPI: cadence={:.1}/h rebut={} arret={:.1}m service={:.0}%\nZones KPI: {}\nConstruction: {}\nStatut: {}",
-            self.clock.format_hhmm(),
-            self.clock.hours(),
-            self.economy.cash,
-            self.economy.revenue_total,
-            self.economy.cost_total,
-            self.economy.profit(),
-            self.line.raw,
-            self.line.wip,
-            self.line.finished,
-            self.line.sold_total,
-            machine_a,
-            machine_b,
-            pending_jobs,
-            blocked_label,
-            self.reservations.len(),
-            self.agent.tile.0,
-            self.agent.tile.1,
-            self.agent.fatigue,
-            self.agent.stress,
-            self.agent.current_job,
-            self.kpi.throughput_per_hour,
-            self.kpi.scrap_total,
-            self.kpi.downtime_minutes,
-            self.kpi.otif * 100.0,
-            zone_summary,
-            self.build_hint_line(),
-            self.status_line()
-        )
-    }
-}
+            .collect::<Vec<_>>();
 
-fn format_int_fr(v: i64) -> String {
-    let sign = if v < 0 { "-" } else { "" };
-    let mut n = v.unsigned_abs();
-    let mut parts: Vec<String> = Vec::new();
-    while n >= 1000 {
-        let chunk = (n % 1000) as u32;
-        parts.push(format!("{:03}", chunk));
-        n /= 1000;
-    }
-    parts.push(format!("{}", n));
-    parts.reverse();
-    format!("{}{}", sign, parts.join(" "))
-}
+        let mut released_jobs = Vec::new();
+        self.agents.retain(|agent| {
+            let keep = agent.role == Some(EmployeeRole::Patron)
+                || agent
+                    .employee_id
+                    .is_some_and(|id| active.iter().any(|(active_id, ..)| *active_id == id));
+            if !keep && let Some(job_id) = agent.current_job {
+                released_jobs.push(job_id);
+            }
+            keep
+        });
+        for job_id in released_jobs {
+            self.release_reservations(job_id);
+            if let Some(job) = self.jobs.iter_mut().find(|job| job.id == job_id) {
+                job.state = JobState::Pending;
+                job.assigned_agent = None;
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let patron_id = patron.map(|(id, ..)| id);
+        match self
+            .agents
+            .iter_mut()
+            .find(|agent| agent.role == Some(EmployeeRole::Patron))
+        {
+            Some(agent) if agent.employee_id != patron_id => {
+                agent.employee_id = patron_id;
+                if let Some((_, fatigue, stress)) = patron {
+                    agent.fatigue = fatigue;
+                    agent.stress = stress;
+                }
+            }
+            Some(_) => {}
+            None => {
+                let id = self.next_agent_id;
+                self.next_agent_id = self.next_agent_id.saturating_add(1);
+                let agent = SimAgent::new(
+                    id,
+                    patron_id,
+                    Some(EmployeeRole::Patron),
+                    self.agent_spawn_tile,
+                );
+                self.agents.insert(0, agent);
+            }
+        }
 
-    fn temp_ron_path(name: &str) -> std::path::PathBuf {
-        let path =
-            std::env::temp_dir().join(format!("rxchixs_sim_{}_{}.ron", name, std::process::id()));
-        let _ = fs::remove_file(&path);
-        path
-    }
+        // Un agent qui (re)prend son poste repart de la fatigue et du stress de la fiche.
+        for (employee_id, role, fatigue, stress) in active {
+            if self
+                .agents
+                .iter()
+                .any(|agent| agent.employee_id == Some(employee_id))
+            {
+                continue;
+            }
+            let id = self.next_agent_id;
+            self.next_agent_id = self.next_agent_id.saturating_add(1);
+            let mut agent = SimAgent::new(id, Some(employee_id), Some(role), self.agent_spawn_tile);
+            agent.fatigue = fatigue;
+            agent.stress = stress;
+            self.agents.push(agent);
+        }
 
-    #[test]
-    fn sim_clock_formats_hhmm() {
-        let mut clock = SimClock::new();
-        assert_eq!(clock.format_hhmm(), "00:00");
-        clock.advance(60.0 * 60.0 * 25.0);
-        assert_eq!(clock.day_index(), 1);
-        assert_eq!(clock.format_hhmm(), "01:00");
+        for agent in &self.agents {
+            if let Some(employee_id) = agent.employee_id
+                && let Some(employee) = self.personnel.employee_mut(employee_id)
+            {
+                employee.fatigue = agent.fatigue as f32;
+                employee.stress = agent.stress as f32;
+            }
+        }
     }
 
-    #[test]
-    fn invalid_sim_config_falls_back_without_overwriting_file() {
-        let path = temp_ron_path("bad_config");
-        let raw = "(schema_version: 99, time_scale: -1.0)";
-        fs::write(&path, raw).expect("config invalide ecrite");
-
-        let (cfg, warning) =
-            StarterSimConfig::load_or_create_with_warning(path.to_str().expect("path utf8"));
+    fn job_target_tile(&self, kind: &JobKind) -> Option<(i32, i32)> {
+        let block_id = match *kind {
+            JobKind::Haul { from_block, .. } => from_block,
+            JobKind::OperateMachine { block_id } | JobKind::Repair { block_id } => block_id,
+        };
+        self.block_access_tile(block_id)
+    }
 
-        assert_eq!(cfg.schema_version, STARTER_SIM_CONFIG_SCHEMA_VERSION);
-        assert!(
-            warning
-                .as_deref()
-                .is_some_and(|msg| msg.contains("defaut non persiste"))
-        );
-        assert_eq!(fs::read_to_string(&path).expect("lecture config"), raw);
+    fn job_destination_tile(&self, kind: &JobKind) -> Option<(i32, i32)> {
+        match *kind {
+            JobKind::Haul { to_block, .. } => self.block_access_tile(to_block),
+            _ => None,
+        }
+    }
 
-        let _ = fs::remove_file(path);
+    fn block_access_tile(&self, block_id: BlockId) -> Option<(i32, i32)> {
+        self.block_index_by_id(block_id)
+            .map(|idx| self.blocks[idx].origin_tile)
     }
 
-    #[test]
-    fn invalid_factory_layout_falls_back_without_overwriting_file() {
-        let path = temp_ron_path("bad_layout");
-        let raw = "(schema_version: 999, map_w: 25, map_h: 15)";
-        fs::write(&path, raw).expect("layout invalide ecrit");
+    fn job_leg_reachable(
+        &mut self,
+        world: Option<&crate::World>,
+        job_id: JobId,
+        from: (i32, i32),
+        to: (i32, i32),
+    ) -> bool {
+        let Some(world) = world else {
+            return true;
+        };
+        let leg = ReachableLeg {
+            world_revision: world.revision,
+            from,
+            to,
+        };
+        if self.reachable_legs.get(&job_id) == Some(&leg) {
+            return true;
+        }
+        if crate::deplacement::a_star_path(world, from, to).is_none() {
+            self.reachable_legs.remove(&job_id);
+            return false;
+        }
+        self.reachable_legs.insert(job_id, leg);
+        true
+    }
+
+    fn plan_agent_path(
+        world: Option<&crate::World>,
+        from: (i32, i32),
+        to: (i32, i32),
+    ) -> Option<VecDeque<(i32, i32)>> {
+        let Some(world) = world else {
+            return Some(straight_path(from, to));
+        };
+        let path = crate::deplacement::a_star_path(world, from, to)?;
+        Some(path.into_iter().skip(1).collect())
+    }
+
+    // (priorite matrice, competence); priorite 0 = l'agent ne prend pas ce travail.
+    fn agent_work_profile(&self, agent: &SimAgent, work: WorkType) -> (u8, u8) {
+        match agent
+            .employee_id
+            .and_then(|employee_id| self.personnel.employee(employee_id))
+        {
+            Some(employee) => (employee.work_priority(work), work.skill(&employee.skills)),
+            None => (
+                agent.role.map_or(WORK_PRIORITY_DISABLED, |role| {
+                    role.default_work_priority(work)
+                }),
+                0,
+            ),
+        }
+    }
+
+    // Score = matrice de travail dominante, puis priorite du job, competence, distance et usure.
+    fn agent_job_score(&self, agent: &SimAgent, job: &Job) -> Option<(f64, i32)> {
+        // Sans piece detachee, la panne attend la livraison au lieu d'occuper un technicien.
+        if let JobKind::Repair { block_id } = job.kind
+            && !self.repair_parts_ready(block_id)
+        {
+            return None;
+        }
+        let (work_priority, skill) = self.agent_work_profile(agent, job_work_type(&job.kind));
+        if work_priority == WORK_PRIORITY_DISABLED {
+            return None;
+        }
+        let distance = self
+            .job_target_tile(&job.kind)
+            .map(|(x, y)| (x - agent.tile.0).abs() + (y - agent.tile.1).abs())
+            .unwrap_or(0);
+        let score = (WORK_PRIORITY_LOWEST + 1 - work_priority) as f64 * 1000.0
+            + job.priority as f64 * 10.0
+            + skill as f64 * 0.2
+            - distance as f64 * 1.5
+            - agent.fatigue * 0.5
+            - agent.stress * 0.25;
+        Some((score, distance))
+    }
+
+    fn tick_agents(&mut self, dt_sim: f64, world: Option<&crate::World>) {
+        for agent_idx in 0..self.agents.len() {
+            self.tick_agent_job(agent_idx, dt_sim, world);
+        }
+        self.assign_jobs_to_idle_agents(dt_sim, world);
+    }
+
+    fn tick_agent_job(&mut self, agent_idx: usize, dt_sim: f64, world: Option<&crate::World>) {
+        let Some(job_id) = self.agents[agent_idx].current_job else {
+            return;
+        };
+        let Some(job_idx) = self.jobs.iter().position(|job| job.id == job_id) else {
+            self.agents[agent_idx].clear_job();
+            return;
+        };
+        self.jobs[job_idx].state = JobState::InProgress;
+        self.touch_reservations(job_id);
+        let rules = zone_rules(self.zones.get(self.agents[agent_idx].tile));
+        let job_kind = self.jobs[job_idx].kind.clone();
+        let destination = self.job_destination_tile(&job_kind);
+        let now_s = self.clock.seconds();
+        let work_speed = self.agent_work_speed(&self.agents[agent_idx]);
+        let agent = &mut self.agents[agent_idx];
+        agent.fatigue = (agent.fatigue + dt_sim / 3600.0 * rules.fatigue_factor).clamp(0.0, 100.0);
+        agent.stress = (agent.stress + dt_sim / 3600.0 * rules.risk_factor).clamp(0.0, 100.0);
+
+        let speed = (agent.speed_tiles_per_s * work_speed).max(0.1);
+        if agent.job_phase.is_travel() {
+            agent.move_progress_s += dt_sim;
+            while agent.move_progress_s >= 1.0 / speed {
+                let Some(next) = agent.path.pop_front() else {
+                    break;
+                };
+                agent.tile = next;
+                agent.move_progress_s -= 1.0 / speed;
+                if matches!(job_kind, JobKind::Haul { .. }) {
+                    self.kpi.haul_tiles_total = self.kpi.haul_tiles_total.saturating_add(1);
+                }
+            }
+            if !agent.path.is_empty() {
+                return;
+            }
+            agent.move_progress_s = 0.0;
+            agent.job_phase = match (agent.job_phase, &job_kind) {
+                (JobPhase::GoToSource, JobKind::Haul { .. }) => JobPhase::Pick,
+                (JobPhase::GoToDestination, _) => JobPhase::Drop,
+                _ => JobPhase::Work,
+            };
+            agent.job_progress_s = 0.0;
+            return;
+        }
+        // Le technicien reste au poste: tick_maintenance fait avancer puis clot la reparation.
+        if matches!(job_kind, JobKind::Repair { .. }) {
+            return;
+        }
+
+        agent.job_progress_s += dt_sim;
+        let phase_duration = match agent.job_phase {
+            JobPhase::Pick | JobPhase::Drop => HAUL_HANDLING_SECONDS,
+            _ => 4.0 / speed,
+        };
+        if agent.job_progress_s < phase_duration {
+            return;
+        }
+
+        if agent.job_phase == JobPhase::Pick {
+            let Some(path) =
+                destination.and_then(|to| Self::plan_agent_path(world, agent.tile, to))
+            else {
+                agent.clear_job();
+                agent.decision_debug = format!("bloque job=#{job_id} raison=chemin introuvable");
+                self.jobs[job_idx].state = JobState::Blocked("chemin introuvable".to_string());
+                self.jobs[job_idx].assigned_agent = None;
+                self.release_reservations(job_id);
+                return;
+            };
+            agent.path = path;
+            agent.job_phase = JobPhase::GoToDestination;
+            agent.job_progress_s = 0.0;
+            return;
+        }
+
+        let started_s = agent.job_started_s;
+        agent.clear_job();
+        if agent.decision_debug != "tache terminee" {
+            agent.decision_debug.clear();
+            agent.decision_debug.push_str("tache terminee");
+        }
+        if let JobKind::Haul {
+            item_kind,
+            qty,
+            line_id,
+            ..
+        } = job_kind
+        {
+            let moved = self.deliver_haul(line_id, item_kind, qty);
+            if item_kind == ItemKind::Raw {
+                self.kpi.raw_hauled_total = self.kpi.raw_hauled_total.saturating_add(moved);
+            }
+            self.kpi.hauls_completed = self.kpi.hauls_completed.saturating_add(1);
+            self.kpi.haul_seconds_total += (now_s - started_s).max(0.0);
+        }
+        self.jobs[job_idx].state = JobState::Done;
+        self.release_reservations(job_id);
+    }
+
+    // Appariement glouton agents libres x jobs ouverts, du meilleur score au moins bon.
+    fn assign_jobs_to_idle_agents(&mut self, dt_sim: f64, world: Option<&crate::World>) {
+        let mut candidates = Vec::new();
+        let on_break = self
+            .agents
+            .iter()
+            .map(|agent| self.agent_on_break(agent))
+            .collect::<Vec<_>>();
+        for (agent_idx, agent) in self.agents.iter().enumerate() {
+            if agent.current_job.is_some() || on_break[agent_idx] {
+                continue;
+            }
+            for (job_idx, job) in self.jobs.iter().enumerate() {
+                if !matches!(job.state, JobState::Pending | JobState::Blocked(_)) {
+                    continue;
+                }
+                let Some((score, distance)) = self.agent_job_score(agent, job) else {
+                    continue;
+                };
+                candidates.push((score, distance, agent_idx, job_idx));
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.2.cmp(&b.2)).then(a.3.cmp(&b.3)));
+        let jobs = &self.jobs;
+        self.unreachable_paths
+            .retain(|job_id, _| jobs.iter().any(|job| job.id == *job_id));
+        self.reachable_legs
+            .retain(|job_id, _| jobs.iter().any(|job| job.id == *job_id));
+
+        let mut agent_done = vec![false; self.agents.len()];
+        let mut job_done = vec![false; self.jobs.len()];
+        for (score, distance, agent_idx, job_idx) in candidates {
+            if agent_done[agent_idx] || job_done[job_idx] {
+                continue;
+            }
+            job_done[job_idx] = true;
+            let job_id = self.jobs[job_idx].id;
+            let job_kind = self.jobs[job_idx].kind.clone();
+            let job_priority = self.jobs[job_idx].priority;
+            let job_target = self.job_target_label(&job_kind);
+            let agent_tile = self.agents[agent_idx].tile;
+            // Une manutention n'est prise que si les deux trajets existent.
+            let target = self.job_target_tile(&job_kind);
+            let destination = self.job_destination_tile(&job_kind);
+            let probe = world.map(|world| UnreachablePath {
+                world_revision: world.revision,
+                from: agent_tile,
+                target,
+                destination,
+            });
+            if probe.is_some() && self.unreachable_paths.get(&job_id) == probe.as_ref() {
+                continue;
+            }
+            // Les reservations se verifient avant tout A*: un job perdu d'avance ne coute rien.
+            let keys = self.reservation_keys_for_job(&job_kind);
+            if self.try_reserve_all(keys, job_id).is_err() {
+                if !matches!(
+                    self.jobs[job_idx].state,
+                    JobState::Blocked(ref reason) if reason == "conflit reservation"
+                ) {
+                    self.jobs[job_idx].state = JobState::Blocked("conflit reservation".to_string());
+                }
+                agent_done[agent_idx] = true;
+                self.agents[agent_idx].decision_debug = format!(
+                    "bloque job=#{job_id} cible={job_target} priorite={job_priority} raison=conflit reservation"
+                );
+                continue;
+            }
+            let path = match target {
+                Some(target) => Self::plan_agent_path(world, agent_tile, target),
+                None => Some(VecDeque::new()),
+            }
+            .filter(|_| match (target, destination) {
+                (Some(from), Some(to)) => self.job_leg_reachable(world, job_id, from, to),
+                _ => true,
+            });
+            let Some(path) = path else {
+                self.release_reservations(job_id);
+                if let Some(probe) = probe {
+                    self.unreachable_paths.insert(job_id, probe);
+                }
+                self.jobs[job_idx].state = JobState::Blocked("chemin introuvable".to_string());
+                continue;
+            };
+            self.unreachable_paths.remove(&job_id);
+
+            agent_done[agent_idx] = true;
+            let job_label = self.job_kind_label(&job_kind);
+            let now_s = self.clock.seconds();
+            let agent = &mut self.agents[agent_idx];
+            let score_debug = format!(
+                "score={score:.0} priorite={job_priority} distance={distance} fatigue={:.1} stress={:.1}",
+                agent.fatigue, agent.stress
+            );
+            agent.clear_job();
+            agent.current_job = Some(job_id);
+            agent.path = path;
+            agent.job_started_s = now_s;
+            agent.decision_debug = format!(
+                "job=#{job_id} {job_label} cible={job_target} priorite={job_priority} {score_debug}"
+            );
+            let job = &mut self.jobs[job_idx];
+            job.state = JobState::Claimed;
+            job.assigned_agent = Some(agent.id);
+            job.score_debug = score_debug;
+        }
+
+        for (agent_idx, agent) in self.agents.iter_mut().enumerate() {
+            if agent.current_job.is_some() {
+                continue;
+            }
+            if !agent_done[agent_idx]
+                && !on_break[agent_idx]
+                && agent.decision_debug != "inactif(aucune tache en attente)"
+            {
+                agent.decision_debug.clear();
+                agent
+                    .decision_debug
+                    .push_str("inactif(aucune tache en attente)");
+            }
+            agent.fatigue = (agent.fatigue - dt_sim / 3600.0).clamp(0.0, 100.0);
+            agent.stress = (agent.stress - dt_sim / 3600.0 * 0.8).clamp(0.0, 100.0);
+        }
+    }
+
+    fn refresh_kpi(&mut self, dt_hours: f64) {
+        self.kpi.throughput_per_hour = self.line.sold_total as f64 / self.clock.hours().max(0.01);
+        self.kpi.otif = self
+            .market
+            .orders
+            .stats(self.clock.seconds(), KPI_FULFILMENT_WINDOW_H)
+            .otif();
+        let mut quality = QualityStats::default();
+        for line in &self.production_lines {
+            quality.merge(&line.quality);
+        }
+        self.kpi.quality = quality;
+        self.kpi.energy_kwh_per_box = self
+            .energy
+            .kwh_per_box(self.line.produced_finished_total)
+            .unwrap_or(0.0);
+        let mean_stress = self.agents.iter().map(|agent| agent.stress).sum::<f64>()
+            / self.agents.len().max(1) as f64;
+        let stress_scrap = (mean_stress / 100.0 * dt_hours * 4.0).max(0.0) as u32;
+        self.kpi.scrap_total = self.kpi.scrap_total.saturating_add(stress_scrap);
+    }
+
+    fn default_layout(map_w: i32, map_h: i32, config: &StarterSimConfig) -> FactoryLayoutAsset {
+        let mut zones = ZoneLayer::new(map_w, map_h, ZoneKind::Neutral);
+        let (fx0, fx1, fy0, fy1) = crate::utilitaires::starter_factory_bounds(map_w, map_h);
+        let width = (fx1 - fx0).max(6);
+        let span_y = (fy1 - fy0).max(6);
+        let recv_x1 = fx0 + width / 3;
+        let proc_x1 = fx0 + (width * 2) / 3;
+        let support_wall_y = (fy1 - 6).max(fy0 + 3);
+        let support_y0 = (support_wall_y + 1).min(fy1 - 1);
+
+        for y in fy0 + 1..fy1 {
+            for x in fx0 + 1..=recv_x1 {
+                zones.set((x, y), ZoneKind::Receiving);
+            }
+            for x in recv_x1 + 1..=proc_x1 {
+                zones.set((x, y), ZoneKind::Processing);
+            }
+            for x in proc_x1 + 1..fx1 {
+                zones.set((x, y), ZoneKind::Shipping);
+            }
+        }
+
+        for y in support_y0..fy1 {
+            for x in fx0 + 1..fx1 {
+                zones.set((x, y), ZoneKind::Support);
+            }
+        }
+
+        let flow_y = (fy0 + span_y / 2).clamp(fy0 + 2, fy1 - 2);
+        let ship_y_hi = (support_y0 - 1).max(fy0 + 2);
+        let ship_y = (fy0 + (span_y * 3) / 4).clamp(fy0 + 2, ship_y_hi);
+        let clamp_tile =
+            |x: i32, y: i32| (x.clamp(1, map_w - 2).max(1), y.clamp(1, map_h - 2).max(1));
+        let storage_x = (fx0 + 2).min(recv_x1);
+        let machine_a_x = (recv_x1 + 1).min(proc_x1);
+        let machine_b_x = ((machine_a_x + proc_x1) / 2).max(machine_a_x);
+        let buffer_x = (proc_x1 + 1).max(machine_b_x).min(fx1 - 2);
+        let seller_x = (fx1 - 2).max(buffer_x);
+        let storage_tile = clamp_tile(storage_x, flow_y);
+        let machine_a_tile = clamp_tile(machine_a_x, flow_y);
+        let machine_b_tile = clamp_tile(machine_b_x, flow_y);
+        let buffer_tile = clamp_tile(buffer_x, ship_y);
+        let seller_tile = clamp_tile(seller_x, support_y0.max(fy0 + 2));
+        let agent_tile = clamp_tile((fx0 + fx1) / 2, (fy1 - 2).max(fy0 + 2));
+
+        let blocks = vec![
+            BlockInstance {
+                id: 1,
+                kind: BlockKind::Storage,
+                origin_tile: storage_tile,
+                footprint: (1, 1),
+                orientation: BlockOrientation::East,
+                inventory: BlockInventory::default(),
+                machine: None,
+                rack_palettes: [false; RACK_NIVEAU_COUNT],
+            },
+            BlockInstance {
+                id: 2,
+                kind: BlockKind::MachineA,
+                origin_tile: machine_a_tile,
+                footprint: (1, 1),
+                orientation: BlockOrientation::East,
+                inventory: BlockInventory::default(),
+                machine: Some(MachineState {
+                    recipe_id: RecipeId::RawToWip,
+                    cycle_s: config.machine_a_cycle_s.max(1.0),
+                    ..MachineState::default()
+                }),
+                rack_palettes: [false; RACK_NIVEAU_COUNT],
+            },
+            BlockInstance {
+                id: 3,
+                kind: BlockKind::MachineB,
+                origin_tile: machine_b_tile,
+                footprint: (1, 1),
+                orientation: BlockOrientation::East,
+                inventory: BlockInventory::default(),
+                machine: Some(MachineState {
+                    recipe_id: RecipeId::WipToFinished,
+                    cycle_s: config.machine_b_cycle_s.max(1.0),
+                    ..MachineState::default()
+                }),
+                rack_palettes: [false; RACK_NIVEAU_COUNT],
+            },
+            BlockInstance {
+                id: 4,
+                kind: BlockKind::Buffer,
+                origin_tile: buffer_tile,
+                footprint: (1, 1),
+                orientation: BlockOrientation::East,
+                inventory: BlockInventory::default(),
+                machine: None,
+                rack_palettes: [false; RACK_NIVEAU_COUNT],
+            },
+            BlockInstance {
+                id: 5,
+                kind: BlockKind::Seller,
+                origin_tile: seller_tile,
+                footprint: (1, 1),
+                orientation: BlockOrientation::East,
+                inventory: BlockInventory::default(),
+                machine: None,
+                rack_palettes: [false; RACK_NIVEAU_COUNT],
+            },
+        ];
+
+        FactoryLayoutAsset {
+            schema_version: FACTORY_LAYOUT_SCHEMA_VERSION,
+            map_w,
+            map_h,
+            zones,
+            blocks,
+            agent_tile,
+            lavabo_tiles: Vec::new(),
+        }
+    }
+
+    fn load_or_create_layout(
+        path: &str,
+        map_w: i32,
+        map_h: i32,
+        config: &StarterSimConfig,
+    ) -> (FactoryLayoutAsset, Option<String>) {
+        match Self::load_layout_asset(path) {
+            Ok(layout) => {
+                if layout.map_w != map_w || layout.map_h != map_h {
+                    return (
+                        Self::default_layout(map_w, map_h, config),
+                        Some(format!(
+                            "layout usine ignore car dimensions {}x{} != carte {}x{}",
+                            layout.map_w, layout.map_h, map_w, map_h
+                        )),
+                    );
+                }
+                if layout.blocks.is_empty() {
+                    return (
+                        Self::default_layout(map_w, map_h, config),
+                        Some("layout usine vide, defaut actif".to_string()),
+                    );
+                }
+                (layout, None)
+            }
+            Err(err) if err.contains("echec lecture layout usine") => {
+                let layout = Self::default_layout(map_w, map_h, config);
+                let warning =
+                    Self::save_layout_static_to_path(path, &layout)
+                        .err()
+                        .map(|save_err| {
+                            format!(
+                                "layout usine par defaut actif, ecriture impossible: {save_err}"
+                            )
+                        });
+                (layout, warning)
+            }
+            Err(err) => {
+                let layout = Self::default_layout(map_w, map_h, config);
+                (
+                    layout,
+                    Some(format!("layout usine invalide, defaut non persiste: {err}")),
+                )
+            }
+        }
+    }
+
+    fn load_layout_asset(path: &str) -> Result<FactoryLayoutAsset, String> {
+        let raw = fs::read_to_string(path).map_err(|e| {
+            if e.kind() == ErrorKind::NotFound {
+                format!("echec lecture layout usine: {e}")
+            } else {
+                format!("layout usine illisible: {e}")
+            }
+        })?;
+        let layout: FactoryLayoutAsset =
+            ron_from_str(&raw).map_err(|e| format!("echec lecture RON layout usine: {e}"))?;
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    fn save_layout_asset(&self, layout: &FactoryLayoutAsset) -> Result<(), String> {
+        Self::save_layout_static(layout)
+    }
+
+    fn save_layout_static(layout: &FactoryLayoutAsset) -> Result<(), String> {
+        Self::save_layout_static_to_path(FACTORY_LAYOUT_PATH, layout)
+    }
+
+    fn save_layout_static_to_path(path: &str, layout: &FactoryLayoutAsset) -> Result<(), String> {
+        layout.validate()?;
+        let payload = ron_to_string_pretty(layout, PrettyConfig::new().depth_limit(4))
+            .map_err(|e| format!("echec serialisation layout usine: {e}"))?;
+        if let Some(parent) = Path::new(path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("echec creation dossier layout usine: {e}"))?;
+        }
+        fs::write(path, payload).map_err(|e| format!("echec ecriture layout usine: {e}"))
+    }
+
+    pub fn debug_hud(&self) -> String {
+        let day = self.clock.day_index();
+        let machine_a = if self.line.machine_a_busy {
+            "actif"
+        } else {
+            "inactif"
+        };
+        let machine_b = if self.line.machine_b_busy {
+            "actif"
+        } else {
+            "inactif"
+        };
+        let pending_jobs = self
+            .jobs
+            .iter()
+            .filter(|job| matches!(job.state, JobState::Pending))
+            .count();
+        let blocked_jobs = self
+            .jobs
+            .iter()
+            .filter_map(|job| match &job.state {
+                JobState::Blocked(reason) => Some(reason.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let blocked_label = if blocked_jobs.is_empty() {
+            "aucun"
+        } else {
+            blocked_jobs.as_str()
+        };
+        let zone_summary = self
+            .zone_kpi
+            .iter()
+            .map(|(zone, kpi)| {
+                format!(
+                    "{}={}/obj{:.0}",
+                    zone.label(),
+                    kpi.produced_total,
+                    kpi.target_per_hour
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let busy_agents = self
+            .agents
+            .iter()
+            .filter(|agent| agent.current_job.is_some())
+            .count();
+        format!(
+            "Simulation J{day} {} ({:.2} h)\nFinances: tresorerie={:.2} | revenu={:.2} | cout={:.2} | profit={:.2}\nFlux ligne: matieres={} en-cours={} finis={} ventes_totales={}\nMachines: A={} | B={}\nJobs: en_attente={} | bloques={} | reservations={}\nAgents: {} actifs={} | principal tuile=({}, {}) fatigue={:.1} stress={:.1} job_actuel={:?}\nKPI: cadence={:.1}/h rebut={} (tri {:.1}%) arret={:.1}m service={:.0}% manutentions={} moy={:.0}s\nZones KPI: {}\nConstruction: {}\nStatut: {}",
+            self.clock.format_hhmm(),
+            self.clock.hours(),
+            self.economy.cash,
+            self.economy.revenue_total,
+            self.economy.cost_total,
+            self.economy.profit(),
+            self.line.raw,
+            self.line.wip,
+            self.line.finished,
+            self.line.sold_total,
+            machine_a,
+            machine_b,
+            pending_jobs,
+            blocked_label,
+            self.reservations.len(),
+            self.agents.len(),
+            busy_agents,
+            self.primary_agent().tile.0,
+            self.primary_agent().tile.1,
+            self.primary_agent().fatigue,
+            self.primary_agent().stress,
+            self.primary_agent().current_job,
+            self.kpi.throughput_per_hour,
+            self.kpi.scrap_total,
+            self.kpi.quality.scrap_rate() * 100.0,
+            self.kpi.downtime_minutes,
+            self.kpi.otif * 100.0,
+            self.kpi.hauls_completed,
+            self.kpi.mean_haul_seconds(),
+            zone_summary,
+            self.build_hint_line(),
+            self.status_line()
+        )
+    }
+}
+
+fn format_int_fr(v: i64) -> String {
+    let sign = if v < 0 { "-" } else { "" };
+    let mut n = v.unsigned_abs();
+    let mut parts: Vec<String> = Vec::new();
+    while n >= 1000 {
+        let chunk = (n % 1000) as u32;
+        parts.push(format!("{:03}", chunk));
+        n /= 1000;
+    }
+    parts.push(format!("{}", n));
+    parts.reverse();
+    format!("{}{}", sign, parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_ron_path(name: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rxchixs_sim_{}_{}.ron", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn sim_clock_formats_hhmm() {
+        let mut clock = SimClock::new();
+        assert_eq!(clock.format_hhmm(), "00:00");
+        clock.advance(60.0 * 60.0 * 25.0);
+        assert_eq!(clock.day_index(), 1);
+        assert_eq!(clock.format_hhmm(), "01:00");
+    }
+
+    #[test]
+    fn invalid_sim_config_falls_back_without_overwriting_file() {
+        let path = temp_ron_path("bad_config");
+        let raw = "(schema_version: 99, time_scale: -1.0)";
+        fs::write(&path, raw).expect("config invalide ecrite");
+
+        let (cfg, warning) =
+            StarterSimConfig::load_or_create_with_warning(path.to_str().expect("path utf8"));
+
+        assert_eq!(cfg.schema_version, STARTER_SIM_CONFIG_SCHEMA_VERSION);
+        assert!(
+            warning
+                .as_deref()
+                .is_some_and(|msg| msg.contains("defaut non persiste"))
+        );
+        assert_eq!(fs::read_to_string(&path).expect("lecture config"), raw);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn invalid_factory_layout_falls_back_without_overwriting_file() {
+        let path = temp_ron_path("bad_layout");
+        let raw = "(schema_version: 999, map_w: 25, map_h: 15)";
+        fs::write(&path, raw).expect("layout invalide ecrit");
+
+        let (layout, warning) = FactorySim::load_or_create_layout(
+            path.to_str().expect("path utf8"),
+            25,
+            15,
+            &StarterSimConfig::default(),
+        );
+
+        assert_eq!(layout.schema_version, FACTORY_LAYOUT_SCHEMA_VERSION);
+        assert!(
+            warning
+                .as_deref()
+                .is_some_and(|msg| msg.contains("defaut non persiste"))
+        );
+        assert_eq!(fs::read_to_string(&path).expect("lecture layout"), raw);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn factory_sim_generates_sales_and_costs() {
+        let cfg = StarterSimConfig {
+            schema_version: 1,
+            time_scale: 60.0,
+            starting_cash: 0.0,
+            worker_count: 1,
+            wage_per_hour: 10.0,
+            raw_delivery_per_hour: 3600.0,
+            machine_a_cycle_s: 5.0,
+            machine_b_cycle_s: 5.0,
+            sale_price: 2.0,
+            seed: DEFAULT_SIM_SEED,
+        };
+
+        let mut sim = FactorySim::new(cfg, 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        for _ in 0..4_000 {
+            sim.step(1.0 / 60.0);
+        }
+
+        assert!(sim.line.sold_total > 0);
+        assert!(sim.economy.revenue_total > 0.0);
+        assert!(sim.economy.cost_total > 0.0);
+    }
+
+    #[test]
+    fn new_factory_starts_without_test_bootstrap_resources() {
+        let sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+
+        assert_eq!(sim.personnel.employees.len(), 0);
+        assert_eq!(sim.stock.raw_receiving, 0);
+        assert_eq!(sim.stock.raw_line_input, 0);
+        assert_eq!(sim.main_line_state().assigned_lead_id, None);
+    }
+
+    #[test]
+    fn bootstrap_functional_factory_creates_autonomous_staff_and_stock() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+
+        let message = sim
+            .bootstrap_functional_factory()
+            .expect("bootstrap should succeed");
+
+        assert!(message.contains("Usine test amorcee"));
+        assert_eq!(sim.personnel.active_role_count(EmployeeRole::ChefEquipe), 1);
+        assert_eq!(sim.personnel.active_role_count(EmployeeRole::Cariste), 1);
+        assert_eq!(
+            sim.personnel
+                .active_role_count(EmployeeRole::AdministrateurVente),
+            1
+        );
+        assert_eq!(
+            sim.stock.raw_line_input,
+            crate::gestion::RAW_LINE_INPUT_CAPACITY
+        );
+        assert!(sim.stock.raw_receiving >= TEST_FACTORY_RAW_RECEIVING_UNITS);
+
+        let lead = sim
+            .personnel
+            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
+            .expect("bootstrap should assign a lead");
+        assert_eq!(lead.status, crate::gestion::EmployeeStatus::Occupe);
+        assert!(lead.task_label.contains("Supervise"));
+        assert!(lead.ai_score > 0.0);
+    }
+
+    #[test]
+    fn legacy_low_sale_price_uses_profitable_box_price() {
+        let low_cfg = StarterSimConfig {
+            sale_price: 45.0,
+            ..StarterSimConfig::default()
+        };
+        let high_cfg = StarterSimConfig {
+            sale_price: 140.0,
+            ..StarterSimConfig::default()
+        };
+
+        let low = FactorySim::new(low_cfg, 25, 15);
+        let high = FactorySim::new(high_cfg, 25, 15);
+
+        assert_eq!(
+            low.effective_sale_price_eur(),
+            DEFAULT_FINISHED_BOX_PRICE_EUR
+        );
+        assert_eq!(high.effective_sale_price_eur(), 140.0);
+    }
+
+    #[test]
+    fn build_mode_can_place_and_sell_block() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let mut world = crate::World::new_room(25, 15);
+        let cash0 = sim.economy.cash;
+        let tile = (6, 11);
+
+        sim.toggle_build_mode();
+        sim.apply_build_click(&mut world, tile, false);
+        assert!(sim.block_at_tile(tile).is_some());
+        assert!(sim.economy.cash < cash0);
+
+        sim.apply_build_click(&mut world, tile, true);
+        assert!(sim.block_at_tile(tile).is_none());
+    }
+
+    #[test]
+    fn action_status_survives_production_tick_until_deterministic_expiry() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let mut world = crate::World::new_room(25, 15);
+        sim.toggle_build_mode();
+
+        sim.apply_build_click(&mut world, (6, 11), false);
+        assert!(sim.status_line().contains("Place"));
+
+        sim.step(1.0 / 60.0);
+        assert!(sim.status_line().contains("Place"));
+
+        for _ in 0..ACTION_STATUS_TTL_SIM_SECONDS as usize {
+            sim.step(1.0 / 60.0);
+        }
+        assert!(!sim.status_line().contains("Place"));
+    }
+
+    #[test]
+    fn sales_blocked_status_is_visible_after_tick() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let seller_tile = sim
+            .blocks
+            .iter()
+            .find(|block| block.kind == BlockKind::Seller)
+            .map(|block| block.origin_tile)
+            .expect("default seller should exist");
+        sim.zones.set(seller_tile, ZoneKind::Support);
+        sim.line.finished = 2;
+
+        sim.step(1.0 / 60.0);
+
+        assert!(sim.status_line().contains("Vente en attente"));
+        assert!(sim.status_line().contains("administrateur"));
+        assert_eq!(sim.line.finished, 2);
+    }
+
+    #[test]
+    fn production_requires_assigned_team_lead() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.stock.raw_line_input = 20;
+        sim.line.raw = 20;
+
+        for _ in 0..300 {
+            sim.step(1.0 / 60.0);
+        }
+
+        assert_eq!(sim.line.produced_wip_total, 0);
+        assert!(sim.status_line().contains("chef"));
+    }
+
+    #[test]
+    fn cariste_is_required_to_feed_line_from_receiving_stock() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let cariste_id = sim
+            .personnel
+            .employees
+            .iter()
+            .find(|employee| employee.role == EmployeeRole::Cariste)
+            .map(|employee| employee.id)
+            .expect("sandbox cariste should exist");
+        sim.apply_command(SimCommand::FireEmployee {
+            employee_id: cariste_id,
+        })
+        .expect("cariste should be fireable");
+        sim.stock.raw_receiving = 500;
+        sim.stock.raw_line_input = 0;
+        sim.line.raw = 0;
+
+        sim.step(60.0);
+
+        assert_eq!(sim.stock.raw_line_input, 0);
+        assert!(sim.production_status.contains("caristes"));
+    }
+
+    #[test]
+    fn raw_feed_arrives_by_haul_trips_and_a_far_storage_slows_it() {
+        fn run(storage_dx: i32) -> FactorySim {
+            let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 30);
+            sim.bootstrap_functional_factory()
+                .expect("test factory should be bootstrapped");
+            sim.stock.raw_receiving = 1_000;
+            sim.stock.raw_line_input = 0;
+            sim.line.raw = 0;
+            let machine_a = sim
+                .first_block_by_kind(BlockKind::MachineA)
+                .expect("machine A block should exist")
+                .origin_tile;
+            let storage = sim
+                .blocks
+                .iter()
+                .position(|b| b.kind == BlockKind::Storage)
+                .expect("storage block should exist");
+            sim.blocks[storage].origin_tile = (machine_a.0 - storage_dx, machine_a.1);
+            for _ in 0..90 {
+                sim.step(1.0 / 60.0);
+            }
+            sim
+        }
+
+        let near = run(2);
+        let far = run(40);
+        assert!(near.kpi.hauls_completed > 0);
+        // Rien ne quitte la reception hors des deposes de manutention.
+        assert_eq!(near.kpi.raw_hauled_total, 1_000 - near.stock.raw_receiving);
+        assert!(far.kpi.raw_hauled_total < near.kpi.raw_hauled_total);
+        assert!(far.kpi.mean_haul_seconds() > near.kpi.mean_haul_seconds());
+    }
+
+    #[test]
+    fn bought_raw_stock_is_paid_delivered_and_moved_by_cariste() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        sim.stock.raw_receiving = 0;
+        sim.stock.raw_line_input = 0;
+        sim.line.raw = 0;
+        let cash_before = sim.cash();
+
+        sim.apply_command(SimCommand::BuyRawStock {
+            supplier_id: crate::gestion::fournisseurs::default_raw_supplier_id(),
+            qty: 500,
+        })
+        .expect("raw stock purchase should succeed");
+        assert!(sim.cash() < cash_before);
+        assert_eq!(sim.stock.pending_raw_qty(), 500);
+
+        let delay_s = sim.suppliers().default_supplier().lead_time_h * 3600.0;
+        sim.step((delay_s / sim.config.time_scale) as f32);
+
+        assert_eq!(sim.stock.pending_raw_qty(), 0);
+        assert!(sim.stock.raw_receiving > 0 || sim.stock.raw_line_input > 0);
+        assert!(sim.stock.raw_line_input <= crate::gestion::stock::RAW_LINE_INPUT_CAPACITY);
+    }
+
+    #[test]
+    fn reorder_policy_places_audited_order_when_stock_runs_low() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        sim.stock.raw_receiving = 0;
+        sim.stock.raw_line_input = 0;
+        sim.line.raw = 0;
+        let policy = crate::gestion::stock::ReorderPolicy::raw_default(
+            crate::gestion::fournisseurs::DEFAULT_RAW_SUPPLIER_ID,
+        );
+        assert!(
+            sim.apply_command(SimCommand::SetReorderPolicy {
+                policy: crate::gestion::stock::ReorderPolicy {
+                    supplier_id: "inconnu".to_string(),
+                    ..policy.clone()
+                },
+            })
+            .is_err()
+        );
+        sim.apply_command(SimCommand::SetReorderPolicy {
+            policy: policy.clone(),
+        })
+        .expect("reorder policy should be accepted");
+        let cash_before = sim.cash();
+
+        sim.step(1.0 / 60.0);
+
+        assert_eq!(sim.stock.pending_raw_qty(), policy.order_up_to);
+        assert_eq!(sim.stock.reorder_log.len(), 1);
+        let cost = sim.stock.reorder_log[0].cost_eur();
+        assert!(cost > 0.0);
+        assert!(sim.cash() <= cash_before - cost);
+        assert!(sim.status_line().contains("Reappro auto"));
+    }
+
+    #[test]
+    fn delivered_raw_lot_is_traced_to_packed_boxes() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        sim.stock.raw_receiving = 0;
+        sim.stock.raw_line_input = 0;
+        sim.line.raw = 0;
+
+        sim.apply_command(SimCommand::BuyRawStock {
+            supplier_id: crate::gestion::fournisseurs::default_raw_supplier_id(),
+            qty: 500,
+        })
+        .expect("raw stock purchase should succeed");
+        let delay_s = sim.suppliers().default_supplier().lead_time_h * 3600.0;
+        sim.step((delay_s / sim.config.time_scale) as f32);
+        let delivered = sim
+            .lots
+            .lots
+            .values()
+            .find(|lot| {
+                matches!(
+                    &lot.origin,
+                    crate::gestion::lots::LotOrigin::Reception { supplier, qty: 500, .. }
+                        if supplier == crate::gestion::stock::DEFAULT_RAW_SUPPLIER
+                )
+            })
+            .map(|lot| lot.id)
+            .expect("delivery should be registered as a raw lot");
+
+        let boxes_before = sim.lots.boxes.len();
+        for _ in 0..3000 {
+            sim.step(1.0 / 60.0);
+        }
+
+        let traced = sim.lots.boxes_from_raw_lot(delivered);
+        assert!(sim.lots.boxes.len() > boxes_before);
+        assert!(!traced.is_empty());
+        let raw = sim.lots.raw_lots_of_box(traced[0]);
+        assert_eq!(
+            raw.iter().map(|lot| lot.id).collect::<Vec<_>>(),
+            vec![delivered]
+        );
+    }
+
+    #[test]
+    fn finished_goods_are_sold_progressively_by_admins() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let seller_tile = sim
+            .blocks
+            .iter()
+            .find(|block| block.kind == BlockKind::Seller)
+            .map(|block| block.origin_tile)
+            .expect("default seller should exist");
+        sim.zones.set(seller_tile, ZoneKind::Support);
+        sim.line.finished = 10;
+
+        sim.step(1.0 / 60.0);
+        assert_eq!(
+            sim.line.finished, 10,
+            "one second should not sell a full box"
+        );
+
+        for _ in 0..1_200 {
+            sim.step(1.0 / 60.0);
+        }
+        assert!(sim.line.sold_total > 0);
+    }
+
+    #[test]
+    fn team_lead_never_creates_more_than_three_temps() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let lead_id = sim
+            .personnel
+            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
+            .map(|lead| lead.id)
+            .expect("sandbox lead should exist");
+        sim.personnel
+            .set_temp_policy(lead_id, true, 99)
+            .expect("temp policy should clamp");
+        sim.stock.raw_receiving = 2_000;
+        sim.stock.raw_line_input = 120;
+
+        for _ in 0..60 {
+            sim.step(1.0 / 60.0);
+        }
+
+        assert!(sim.personnel.active_temps_for_lead(lead_id) <= 3);
+    }
+
+    #[test]
+    fn factory_layout_validation_rejects_overlapping_block_footprints() {
+        let layout = FactoryLayoutAsset {
+            schema_version: FACTORY_LAYOUT_SCHEMA_VERSION,
+            map_w: 12,
+            map_h: 12,
+            zones: ZoneLayer::new(12, 12, ZoneKind::Neutral),
+            blocks: vec![
+                BlockInstance {
+                    id: 1,
+                    kind: BlockKind::Storage,
+                    origin_tile: (3, 3),
+                    footprint: (1, 1),
+                    ..BlockInstance::default()
+                },
+                BlockInstance {
+                    id: 2,
+                    kind: BlockKind::Buffer,
+                    origin_tile: (3, 3),
+                    footprint: (1, 1),
+                    ..BlockInstance::default()
+                },
+            ],
+            agent_tile: (4, 4),
+            lavabo_tiles: Vec::new(),
+        };
+
+        let err = layout.validate().expect_err("layout superpose refuse");
+        assert!(err.contains("superposes"));
+    }
+
+    #[test]
+    fn lavabo_tiles_follow_the_save_and_match_the_starter_map() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.apply_command(SimCommand::SetLavaboTiles {
+            tiles: vec![(7, 3), (2, 2), (7, 3)],
+        })
+        .expect("lavabo tiles should be accepted");
+        let restored = FactorySim::from_save_asset(sim.config.clone(), sim.to_save_asset())
+            .expect("save should reload");
+        assert_eq!(restored.lavabo_tiles(), &[(2, 2), (7, 3)]);
+
+        // Les runs sans editeur (headless, rejeu) partent du layout de depart.
+        let world = crate::generate_starter_factory_world(crate::MAP_W, crate::MAP_H);
+        let mut props = crate::default_props(&world)
+            .into_iter()
+            .filter(|prop| prop.kind == crate::PropKind::Lavabo)
+            .map(|prop| (prop.tile_x, prop.tile_y))
+            .collect::<Vec<_>>();
+        props.sort_unstable();
+        let layout =
+            FactorySim::load_layout_asset(FACTORY_LAYOUT_PATH).expect("starter layout should load");
+        assert!(!props.is_empty());
+        assert_eq!(layout.lavabo_tiles, props);
+    }
+
+    #[test]
+    fn modern_line_cache_is_invalidated_by_placement_and_sale() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
+        let mut world = crate::World::new_room(80, 60);
+        sim.blocks.clear();
+        sim.mark_modern_line_cache_dirty();
+        assert!(sim.cached_modern_line_readiness_reason().is_some());
+        assert!(!sim.modern_line_cache.dirty);
+
+        sim.toggle_build_mode();
+        sim.set_block_brush(BlockKind::InputHopper);
+        sim.apply_build_click(&mut world, (20, 20), false);
+        assert!(sim.modern_line_cache.dirty);
+
+        assert!(sim.cached_modern_line_readiness_reason().is_some());
+        assert!(!sim.modern_line_cache.dirty);
+
+        sim.apply_build_click(&mut world, (20, 20), true);
+        assert!(sim.modern_line_cache.dirty);
+    }
+
+    #[test]
+    fn render_ready_read_uses_cached_modern_line_state_only() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
+        sim.blocks.clear();
+        sim.mark_modern_line_cache_dirty();
+
+        assert!(!sim.modern_line_ready_cached_for_render());
+
+        let reason = sim.cached_modern_line_readiness_reason();
+        assert_eq!(sim.modern_line_ready_cached_for_render(), reason.is_none());
+
+        sim.mark_modern_line_cache_dirty();
+        assert!(!sim.modern_line_ready_cached_for_render());
+    }
+
+    #[test]
+    fn agent_debug_label_exposes_blocked_job_priority_target_and_reason() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let job_id = 99;
+        sim.jobs.push(Job {
+            id: job_id,
+            kind: JobKind::OperateMachine { block_id: 1 },
+            state: JobState::Pending,
+            priority: 42,
+            score_debug: String::new(),
+            assigned_agent: None,
+        });
+        sim.reservations.insert(
+            ReservationKey::BlockInput(1),
+            Reservation {
+                job_id: 777,
+                ttl_s: RESERVATION_TTL_SECONDS,
+            },
+        );
+        sim.personnel
+            .hire(EmployeeRole::ChefEquipe, 0.0)
+            .expect("lead should be hired");
+        sim.sync_agents_with_personnel();
+
+        sim.tick_agents(1.0 / 60.0, None);
+
+        let label = &sim.agent_debug_views()[1].label;
+        assert!(label.contains("job=#99"));
+        assert!(label.contains("cible=B1"));
+        assert!(label.contains("priorite=42"));
+        assert!(label.contains("raison=conflit reservation"));
+    }
+
+    #[test]
+    fn selling_block_purges_related_jobs_and_reservations() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let mut world = crate::World::new_room(25, 15);
+        let machine = sim
+            .blocks
+            .iter()
+            .find(|block| block.kind == BlockKind::MachineA)
+            .expect("machine A attendue");
+        let machine_id = machine.id;
+        let machine_tile = machine.origin_tile;
+        let job_id = 44;
+        sim.jobs.push(Job {
+            id: job_id,
+            kind: JobKind::OperateMachine {
+                block_id: machine_id,
+            },
+            state: JobState::InProgress,
+            priority: 60,
+            score_debug: "test".to_string(),
+            assigned_agent: Some(sim.agents[0].id),
+        });
+        sim.agents[0].current_job = Some(job_id);
+        sim.agents[0].job_progress_s = 2.0;
+        sim.reservations.insert(
+            ReservationKey::BlockInput(machine_id),
+            Reservation {
+                job_id,
+                ttl_s: RESERVATION_TTL_SECONDS,
+            },
+        );
+
+        sim.toggle_build_mode();
+        sim.apply_build_click(&mut world, machine_tile, true);
+
+        assert!(sim.block_index_by_id(machine_id).is_none());
+        assert!(sim.jobs.iter().all(|job| job.id != job_id));
+        assert_eq!(sim.agents[0].current_job, None);
+        assert!(sim.reservations.is_empty());
+    }
+
+    #[test]
+    fn storage_debug_view_exposes_raw_quantity() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.line.raw = 12;
+        sim.sync_blocks_from_line();
+
+        let storage = sim
+            .block_debug_views()
+            .into_iter()
+            .find(|block| block.kind == BlockKind::Storage)
+            .expect("storage block should exist");
+
+        assert_eq!(storage.raw_qty, 12);
+    }
+
+    #[test]
+    fn minimal_block_debug_views_keep_structure_without_inventory_strings() {
+        let sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let full = sim.block_debug_views();
+        let minimal = sim.block_debug_views_with_options(false);
+
+        assert_eq!(full.len(), minimal.len());
+        for (lhs, rhs) in full.iter().zip(minimal.iter()) {
+            assert_eq!(lhs.id, rhs.id);
+            assert_eq!(lhs.kind, rhs.kind);
+            assert_eq!(lhs.tile, rhs.tile);
+            assert_eq!(lhs.footprint, rhs.footprint);
+            assert_eq!(lhs.orientation, rhs.orientation);
+            assert_eq!(lhs.raw_qty, rhs.raw_qty);
+            assert_eq!(lhs.rack_levels, rhs.rack_levels);
+            assert!(rhs.inventory_summary.is_empty());
+        }
+    }
+
+    #[test]
+    fn block_render_views_match_debug_structure_without_inventory_strings() {
+        let sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let full = sim.block_debug_views();
+        let render: Vec<BlockRenderView> = sim.block_render_views().collect();
+
+        assert_eq!(full.len(), render.len());
+        for (lhs, rhs) in full.iter().zip(render.iter()) {
+            assert_eq!(lhs.render_view(), *rhs);
+        }
+    }
+
+    #[test]
+    fn refresh_jobs_does_not_duplicate_existing_blocked_kind() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.jobs.clear();
+
+        let storage_id = sim
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::Storage)
+            .expect("storage block should exist")
+            .id;
+        let machine_a_id = sim
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::MachineA)
+            .expect("machine A block should exist")
+            .id;
+        let blocked_kind = JobKind::Haul {
+            from_block: storage_id,
+            to_block: machine_a_id,
+            item_kind: ItemKind::Raw,
+            qty: HAUL_RAW_UNITS_PER_TRIP,
+            line_id: MAIN_PRODUCTION_LINE_ID,
+            trip: 0,
+        };
+        sim.jobs.push(Job {
+            id: 9000,
+            kind: blocked_kind.clone(),
+            state: JobState::Blocked("conflit reservation".to_string()),
+            priority: 50,
+            score_debug: String::new(),
+            assigned_agent: None,
+        });
+        sim.stock.raw_receiving = 200;
+        sim.stock.raw_line_input = 0;
+
+        for _ in 0..20 {
+            sim.refresh_jobs();
+        }
+
+        let same_kind = sim
+            .jobs
+            .iter()
+            .filter(|job| job.kind == blocked_kind)
+            .count();
+        assert_eq!(
+            same_kind, 1,
+            "refresh_jobs should not append duplicates for an already blocked kind"
+        );
+    }
+
+    #[test]
+    fn agents_resume_employee_fatigue_and_the_patron_takes_no_jobs() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.jobs.clear();
+        sim.reservations.clear();
+        let storage = sim
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::Storage)
+            .expect("storage block should exist")
+            .id;
+        let seller = sim
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::Seller)
+            .expect("seller block should exist")
+            .id;
+        sim.jobs.push(Job {
+            id: 1,
+            kind: JobKind::Haul {
+                from_block: storage,
+                to_block: seller,
+                item_kind: ItemKind::Finished,
+                qty: 1,
+                line_id: MAIN_PRODUCTION_LINE_ID,
+                trip: 0,
+            },
+            state: JobState::Pending,
+            priority: 80,
+            score_debug: String::new(),
+            assigned_agent: None,
+        });
+        sim.tick_agents(1.0, None);
+        assert_eq!(sim.agents[0].role, Some(EmployeeRole::Patron));
+        assert_eq!(sim.agents[0].current_job, None);
+        assert_eq!(sim.jobs[0].state, JobState::Pending);
+
+        let cariste = sim
+            .personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("cariste should be hired");
+        if let Some(employee) = sim.personnel.employee_mut(cariste) {
+            employee.fatigue = 63.0;
+            employee.stress = 41.0;
+        }
+        sim.sync_agents_with_personnel();
+        let agent = sim.agent_for_employee(cariste).expect("cariste agent");
+        assert_eq!((agent.fatigue, agent.stress), (63.0, 41.0));
+        let employee = sim.personnel.employee(cariste).expect("cariste");
+        assert_eq!((employee.fatigue, employee.stress), (63.0, 41.0));
+    }
+
+    #[test]
+    fn patron_employee_is_linked_to_the_single_patron_agent() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let patron = sim
+            .personnel
+            .hire(EmployeeRole::Patron, 0.0)
+            .expect("patron should be hired");
+        sim.personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("cariste should be hired");
+        sim.step(1.0 / 60.0);
+        sim.step(1.0 / 60.0);
+
+        let patrons = sim
+            .agents
+            .iter()
+            .filter(|agent| agent.role == Some(EmployeeRole::Patron))
+            .collect::<Vec<_>>();
+        assert_eq!(patrons.len(), 1);
+        assert_eq!(patrons[0].employee_id, Some(patron));
+        assert_eq!(sim.primary_agent().employee_id, Some(patron));
+        assert_eq!(sim.agents.len(), 2);
+    }
 
-        let (layout, warning) = FactorySim::load_or_create_layout(
-            path.to_str().expect("path utf8"),
-            25,
-            15,
-            &StarterSimConfig::default(),
-        );
+    #[test]
+    fn hired_caristes_get_agents_and_haul_in_parallel() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.jobs.clear();
+        sim.reservations.clear();
+        let block_of = |sim: &FactorySim, kind: BlockKind| {
+            sim.blocks
+                .iter()
+                .find(|b| b.kind == kind)
+                .expect("legacy block should exist")
+                .id
+        };
+        let storage = block_of(&sim, BlockKind::Storage);
+        let machine_a = block_of(&sim, BlockKind::MachineA);
+        let machine_b = block_of(&sim, BlockKind::MachineB);
+        let seller = block_of(&sim, BlockKind::Seller);
+        let first = sim
+            .personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("cariste should be hired");
+        let second = sim
+            .personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("cariste should be hired");
+        sim.sync_agents_with_personnel();
+        assert!(sim.agent_for_employee(first).is_some());
+        assert!(sim.agent_for_employee(second).is_some());
+
+        for (id, from_block, to_block) in [(1, storage, machine_a), (2, machine_b, seller)] {
+            sim.jobs.push(Job {
+                id,
+                kind: JobKind::Haul {
+                    from_block,
+                    to_block,
+                    item_kind: ItemKind::Raw,
+                    qty: 1,
+                    line_id: MAIN_PRODUCTION_LINE_ID,
+                    trip: 0,
+                },
+                state: JobState::Pending,
+                priority: 50,
+                score_debug: String::new(),
+                assigned_agent: None,
+            });
+        }
+        sim.tick_agents(1.0, None);
 
-        assert_eq!(layout.schema_version, FACTORY_LAYOUT_SCHEMA_VERSION);
+        let haulers = sim
+            .jobs
+            .iter()
+            .map(|job| {
+                let agent_id = job.assigned_agent.expect("haul job should be claimed");
+                sim.agents
+                    .iter()
+                    .find(|agent| agent.id == agent_id)
+                    .expect("claiming agent should exist")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(haulers.len(), 2);
+        assert_ne!(haulers[0].id, haulers[1].id);
         assert!(
-            warning
-                .as_deref()
-                .is_some_and(|msg| msg.contains("defaut non persiste"))
+            haulers
+                .iter()
+                .all(|agent| agent.role == Some(EmployeeRole::Cariste))
         );
-        assert_eq!(fs::read_to_string(&path).expect("lecture layout"), raw);
 
-        let _ = fs::remove_file(path);
+        let fired = haulers[0]
+            .employee_id
+            .expect("cariste agent has an employee");
+        let fired_job = haulers[0].current_job;
+        sim.personnel.fire(fired).expect("cariste should be fired");
+        sim.sync_agents_with_personnel();
+        assert!(sim.agent_for_employee(fired).is_none());
+        let job = sim
+            .jobs
+            .iter()
+            .find(|job| Some(job.id) == fired_job)
+            .expect("released job should stay queued");
+        assert_eq!(job.state, JobState::Pending);
+        assert_eq!(job.assigned_agent, None);
     }
 
     #[test]
-    fn factory_sim_generates_sales_and_costs() {
-        let cfg = StarterSimConfig {
-            schema_version: 1,
-            time_scale: 60.0,
-            starting_cash: 0.0,
-            worker_count: 1,
-            wage_per_hour: 10.0,
-            raw_delivery_per_hour: 3600.0,
-            machine_a_cycle_s: 5.0,
-            machine_b_cycle_s: 5.0,
-            sale_price: 2.0,
-        };
-
-        let mut sim = FactorySim::new(cfg, 25, 15);
+    fn raw_feed_opens_parallel_trips_in_proportion_to_the_missing_quantity() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 30);
         sim.bootstrap_functional_factory()
             .expect("test factory should be bootstrapped");
-        for _ in 0..4_000 {
-            sim.step(1.0 / 60.0);
-        }
+        sim.personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("second cariste should be hired");
+        sim.stock.raw_receiving = 1_000;
+        sim.stock.raw_line_input = 0;
+        sim.line.raw = 0;
+        sim.step(1.0 / 60.0);
 
-        assert!(sim.line.sold_total > 0);
-        assert!(sim.economy.revenue_total > 0.0);
-        assert!(sim.economy.cost_total > 0.0);
+        let raw_trips = sim
+            .jobs
+            .iter()
+            .filter_map(|job| match job.kind {
+                JobKind::Haul {
+                    item_kind: ItemKind::Raw,
+                    trip,
+                    ..
+                } => Some((job.id, trip)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let expected = crate::gestion::RAW_LINE_INPUT_CAPACITY.div_ceil(HAUL_RAW_UNITS_PER_TRIP);
+        assert_eq!(raw_trips.len(), expected as usize);
+        let caristes_on_trips = sim
+            .agents
+            .iter()
+            .filter(|agent| agent.role == Some(EmployeeRole::Cariste))
+            .filter(|agent| {
+                agent
+                    .current_job
+                    .is_some_and(|job_id| raw_trips.iter().any(|(id, _)| *id == job_id))
+            })
+            .count();
+        assert_eq!(caristes_on_trips, 2);
+
+        // Entree pleine: les trajets non pris sortent de la file.
+        sim.stock.raw_line_input = crate::gestion::RAW_LINE_INPUT_CAPACITY;
+        sim.refresh_jobs();
+        assert!(sim.jobs.iter().all(|job| {
+            !matches!(
+                job.kind,
+                JobKind::Haul {
+                    item_kind: ItemKind::Raw,
+                    ..
+                }
+            ) || job.assigned_agent.is_some()
+        }));
     }
 
     #[test]
-    fn new_factory_starts_without_test_bootstrap_resources() {
-        let sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+    fn work_priority_matrix_steers_job_matching() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.jobs.clear();
+        sim.reservations.clear();
+        let machine_a = sim
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::MachineA)
+            .expect("machine A block should exist")
+            .id;
+        let cariste = sim
+            .personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("cariste should be hired");
+        sim.sync_agents_with_personnel();
+        sim.jobs.push(Job {
+            id: 1,
+            kind: JobKind::OperateMachine {
+                block_id: machine_a,
+            },
+            state: JobState::Pending,
+            priority: 60,
+            score_debug: String::new(),
+            assigned_agent: None,
+        });
 
-        assert_eq!(sim.personnel.employees.len(), 0);
-        assert_eq!(sim.stock.raw_receiving, 0);
-        assert_eq!(sim.stock.raw_line_input, 0);
-        assert_eq!(sim.main_line_state().assigned_lead_id, None);
+        sim.apply_command(SimCommand::SetWorkPriority {
+            employee_id: cariste,
+            work: WorkType::Production,
+            priority: 1,
+        })
+        .expect("priority should be accepted");
+        sim.tick_agents(1.0 / 60.0, None);
+        let cariste_agent = sim
+            .agent_for_employee(cariste)
+            .expect("cariste has an agent")
+            .id;
+        assert_eq!(sim.jobs[0].assigned_agent, Some(cariste_agent));
+
+        assert!(
+            sim.apply_command(SimCommand::SetWorkPriority {
+                employee_id: cariste,
+                work: WorkType::Manutention,
+                priority: 5,
+            })
+            .is_err()
+        );
+        sim.apply_command(SimCommand::SetWorkPriority {
+            employee_id: cariste,
+            work: WorkType::Manutention,
+            priority: 0,
+        })
+        .expect("disabling a work type should be accepted");
+        assert_eq!(
+            sim.personnel.active_count_for_work(WorkType::Manutention),
+            0
+        );
+
+        sim.apply_command(SimCommand::ResetWorkPriorities {
+            employee_id: cariste,
+        })
+        .expect("reset should be accepted");
+        let employee = sim.personnel.employee(cariste).expect("cariste exists");
+        assert!(employee.work_priorities.is_empty());
+        assert_eq!(employee.work_priority(WorkType::Manutention), 1);
+        assert_eq!(employee.work_priority(WorkType::Production), 0);
     }
 
     #[test]
-    fn bootstrap_functional_factory_creates_autonomous_staff_and_stock() {
+    fn two_shift_line_idles_at_night_and_pays_premiums() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-
-        let message = sim
-            .bootstrap_functional_factory()
-            .expect("bootstrap should succeed");
-
-        assert!(message.contains("Usine test amorcee"));
-        assert_eq!(sim.personnel.active_role_count(EmployeeRole::ChefEquipe), 1);
-        assert_eq!(sim.personnel.active_role_count(EmployeeRole::Cariste), 1);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let second_cariste = sim
+            .personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("cariste should be hired");
         assert_eq!(
             sim.personnel
-                .active_role_count(EmployeeRole::AdministrateurVente),
-            1
+                .employee(second_cariste)
+                .map(|employee| employee.shift_slot),
+            Some(1)
         );
-        assert_eq!(
-            sim.stock.raw_line_input,
-            crate::gestion::RAW_LINE_INPUT_CAPACITY
+        assert!(
+            sim.apply_command(SimCommand::SetLineShiftTemplate {
+                line_id: MAIN_PRODUCTION_LINE_ID,
+                template_id: "4x6".to_string(),
+            })
+            .is_err()
         );
-        assert!(sim.stock.raw_receiving >= TEST_FACTORY_RAW_RECEIVING_UNITS);
+        sim.apply_command(SimCommand::SetLineShiftTemplate {
+            line_id: MAIN_PRODUCTION_LINE_ID,
+            template_id: "2x8".to_string(),
+        })
+        .expect("2x8 should be accepted");
 
+        // Lundi 23h: ligne fermee, seuls les non postes restent pointes.
+        sim.clock.advance(23.0 * 3600.0);
+        sim.step(1.0 / 60.0);
+        assert_eq!(sim.main_line_state().status, LineOperationalState::Bloquee);
+        assert!(sim.main_line_state().block_reason.contains("hors horaires"));
         let lead = sim
             .personnel
             .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
-            .expect("bootstrap should assign a lead");
-        assert_eq!(lead.status, crate::gestion::EmployeeStatus::Occupe);
-        assert!(lead.task_label.contains("Supervise"));
-        assert!(lead.ai_score > 0.0);
+            .expect("lead stays assigned");
+        assert_eq!(lead.status, EmployeeStatus::Absent);
+        assert_eq!(sim.personnel.active_role_count(EmployeeRole::Cariste), 0);
+        // Le patron, hors poste, reste au taux de base meme la nuit.
+        let night_base = sim.personnel.hourly_payroll_eur();
+        assert!((sim.payroll_per_hour() - night_base).abs() < 1e-9);
+
+        // Mardi 10h: poste du matin, le second cariste est d'apres-midi.
+        sim.clock.advance(11.0 * 3600.0);
+        sim.step(1.0 / 60.0);
+        assert_eq!(sim.main_line_state().status, LineOperationalState::Active);
+        assert_eq!(sim.personnel.active_role_count(EmployeeRole::Cariste), 1);
+        assert_eq!(
+            sim.personnel
+                .employee(second_cariste)
+                .map(|employee| employee.status),
+            Some(EmployeeStatus::Absent)
+        );
+        assert!(sim.personnel.hourly_payroll_eur() > night_base);
+        assert!((sim.payroll_per_hour() - sim.personnel.hourly_payroll_eur()).abs() < 1e-9);
+
+        // Mardi 23h en 3x8: toute l'equipe de nuit est majoree, le cariste blesse
+        // est paye au taux de base.
+        sim.apply_command(SimCommand::SetLineShiftTemplate {
+            line_id: MAIN_PRODUCTION_LINE_ID,
+            template_id: "3x8".to_string(),
+        })
+        .expect("3x8 should be accepted");
+        sim.apply_command(SimCommand::SetEmployeeShift {
+            employee_id: second_cariste,
+            slot: 2,
+        })
+        .expect("night slot should be accepted");
+        let injured = sim
+            .personnel
+            .employees
+            .iter()
+            .find(|employee| {
+                employee.role == EmployeeRole::Cariste && employee.id != second_cariste
+            })
+            .map(|employee| employee.id)
+            .expect("first cariste exists");
+        let injured_until_s = sim.clock.seconds() + 3.0 * 86_400.0;
+        sim.personnel
+            .employee_mut(injured)
+            .expect("first cariste exists")
+            .injure(injured_until_s);
+        sim.clock.advance(13.0 * 3600.0);
+        sim.step(1.0 / 60.0);
+        let wage_of = |id| {
+            sim.personnel
+                .employee(id)
+                .map(|employee| employee.hourly_wage)
+                .expect("employee exists")
+        };
+        assert_eq!(
+            sim.personnel
+                .employee(injured)
+                .map(|employee| employee.status),
+            Some(EmployeeStatus::Blesse)
+        );
+        assert_eq!(
+            sim.personnel
+                .employee(second_cariste)
+                .map(|employee| employee.status),
+            Some(EmployeeStatus::Occupe)
+        );
+        let paid = sim.personnel.hourly_payroll_eur();
+        let premiums = (paid - wage_of(injured)) * 0.25;
+        assert!((sim.payroll_per_hour() - paid - premiums).abs() < 1e-9);
     }
 
     #[test]
-    fn legacy_low_sale_price_uses_profitable_box_price() {
-        let low_cfg = StarterSimConfig {
-            sale_price: 45.0,
-            ..StarterSimConfig::default()
+    fn haul_walks_the_world_and_detours_around_walls() {
+        fn run_haul(world: &crate::World) -> (u32, FactorySim) {
+            let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+            sim.jobs.clear();
+            sim.reservations.clear();
+            let storage = sim
+                .blocks
+                .iter()
+                .find(|b| b.kind == BlockKind::Storage)
+                .expect("storage block should exist")
+                .id;
+            let seller = sim
+                .blocks
+                .iter()
+                .find(|b| b.kind == BlockKind::Seller)
+                .expect("seller block should exist")
+                .id;
+            sim.jobs.push(Job {
+                id: 1,
+                kind: JobKind::Haul {
+                    from_block: storage,
+                    to_block: seller,
+                    item_kind: ItemKind::Finished,
+                    qty: 1,
+                    line_id: MAIN_PRODUCTION_LINE_ID,
+                    trip: 0,
+                },
+                state: JobState::Pending,
+                priority: 80,
+                score_debug: String::new(),
+                assigned_agent: None,
+            });
+            sim.personnel
+                .hire(EmployeeRole::Cariste, 0.0)
+                .expect("cariste should be hired");
+            sim.sync_agents_with_personnel();
+            let mut ticks = 0;
+            while ticks < 600 && !matches!(sim.jobs[0].state, JobState::Done | JobState::Blocked(_))
+            {
+                sim.tick_agents(1.0, Some(world));
+                ticks += 1;
+            }
+            (ticks, sim)
+        }
+
+        let open_world = crate::World::new_room(25, 15);
+        let (open_ticks, open_sim) = run_haul(&open_world);
+        assert_eq!(open_sim.jobs[0].state, JobState::Done);
+        assert_eq!(open_sim.kpi.hauls_completed, 1);
+        // Agent (14,9) -> stockage (11,8) -> vente (18,9): 12 tuiles a vol d'oiseau,
+        // mais le mur x=12 de la salle de test impose un detour.
+        let walked = open_sim.kpi.haul_tiles_total;
+        assert!(walked > 12);
+        let speed = agent_speed_for_role(Some(EmployeeRole::Cariste));
+        assert!(open_ticks as f64 >= walked as f64 / speed + 2.0 * HAUL_HANDLING_SECONDS);
+
+        let mut walled = crate::World::new_room(25, 15);
+        for y in 2..14 {
+            walled.set(16, y, crate::Tile::Wall);
+        }
+        let (walled_ticks, walled_sim) = run_haul(&walled);
+        assert_eq!(walled_sim.jobs[0].state, JobState::Done);
+        assert!(walled_sim.kpi.haul_tiles_total > open_sim.kpi.haul_tiles_total);
+        assert!(walled_ticks > open_ticks);
+
+        walled.set(16, 1, crate::Tile::Wall);
+        let (_, blocked_sim) = run_haul(&walled);
+        assert_eq!(
+            blocked_sim.jobs[0].state,
+            JobState::Blocked("chemin introuvable".to_string())
+        );
+        assert!(blocked_sim.reservations.is_empty());
+    }
+
+    #[test]
+    fn unreachable_haul_skips_path_search_until_the_world_changes() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.jobs.clear();
+        sim.reservations.clear();
+        let block_of = |sim: &FactorySim, kind: BlockKind| {
+            sim.blocks
+                .iter()
+                .find(|b| b.kind == kind)
+                .expect("legacy block should exist")
+                .id
         };
-        let high_cfg = StarterSimConfig {
-            sale_price: 140.0,
-            ..StarterSimConfig::default()
+        let (storage, seller) = (
+            block_of(&sim, BlockKind::Storage),
+            block_of(&sim, BlockKind::Seller),
+        );
+        sim.jobs.push(Job {
+            id: 1,
+            kind: JobKind::Haul {
+                from_block: storage,
+                to_block: seller,
+                item_kind: ItemKind::Finished,
+                qty: 1,
+                line_id: MAIN_PRODUCTION_LINE_ID,
+                trip: 0,
+            },
+            state: JobState::Pending,
+            priority: 80,
+            score_debug: String::new(),
+            assigned_agent: None,
+        });
+        sim.personnel
+            .hire(EmployeeRole::Cariste, 0.0)
+            .expect("cariste should be hired");
+        sim.sync_agents_with_personnel();
+
+        let mut world = crate::World::new_room(25, 15);
+        for y in 1..14 {
+            world.set(16, y, crate::Tile::Wall);
+        }
+        sim.tick_agents(1.0, Some(&world));
+        assert_eq!(
+            sim.jobs[0].state,
+            JobState::Blocked("chemin introuvable".to_string())
+        );
+        let probe = sim.unreachable_paths.get(&1).copied().expect("cached miss");
+        assert_eq!(probe.world_revision, world.revision);
+
+        sim.tick_agents(1.0, Some(&world));
+        assert_eq!(sim.unreachable_paths.get(&1), Some(&probe));
+        assert_eq!(sim.agents[1].current_job, None);
+
+        world.set(16, 5, crate::Tile::Floor);
+        sim.tick_agents(1.0, Some(&world));
+        assert_eq!(sim.agents[1].current_job, Some(1));
+        assert!(sim.unreachable_paths.is_empty());
+        // Le trajet source -> destination trouve reste en cache pour cette revision.
+        assert_eq!(
+            sim.reachable_legs.get(&1).map(|leg| leg.world_revision),
+            Some(world.revision)
+        );
+    }
+
+    #[test]
+    fn tick_agent_retries_blocked_job_when_reservations_are_available() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.jobs.clear();
+        sim.reservations.clear();
+
+        let machine_a_id = sim
+            .blocks
+            .iter()
+            .find(|b| b.kind == BlockKind::MachineA)
+            .expect("machine A block should exist")
+            .id;
+        let blocked_job_id = 77;
+        sim.jobs.push(Job {
+            id: blocked_job_id,
+            kind: JobKind::OperateMachine {
+                block_id: machine_a_id,
+            },
+            state: JobState::Blocked("conflit reservation".to_string()),
+            priority: 60,
+            score_debug: String::new(),
+            assigned_agent: None,
+        });
+
+        sim.personnel
+            .hire(EmployeeRole::ChefEquipe, 0.0)
+            .expect("lead should be hired");
+        sim.sync_agents_with_personnel();
+
+        // Tant qu'un autre job tient la machine, le job reste bloque sans recherche de chemin.
+        sim.reservations.insert(
+            ReservationKey::BlockInput(machine_a_id),
+            Reservation {
+                job_id: 5,
+                ttl_s: RESERVATION_TTL_SECONDS,
+            },
+        );
+        let world = crate::World::new_room(25, 15);
+        sim.tick_agents(1.0 / 60.0, Some(&world));
+        assert_eq!(sim.agents[1].current_job, None);
+        assert!(sim.unreachable_paths.is_empty());
+        sim.reservations.clear();
+
+        sim.tick_agents(1.0 / 60.0, None);
+
+        assert_eq!(sim.agents[0].current_job, None);
+        assert_eq!(sim.agents[1].current_job, Some(blocked_job_id));
+        let job = sim
+            .jobs
+            .iter()
+            .find(|j| j.id == blocked_job_id)
+            .expect("blocked test job should still exist");
+        assert!(matches!(job.state, JobState::Claimed));
+    }
+
+    #[test]
+    fn default_layout_flow_is_coherent_with_zones() {
+        let sim = FactorySim::new(StarterSimConfig::default(), 168, 108);
+        let views = sim.block_debug_views();
+
+        let find_tile = |kind: BlockKind| {
+            views
+                .iter()
+                .find(|b| b.kind == kind)
+                .map(|b| b.tile)
+                .expect("required block should exist")
         };
 
-        let low = FactorySim::new(low_cfg, 25, 15);
-        let high = FactorySim::new(high_cfg, 25, 15);
+        let storage = find_tile(BlockKind::Storage);
+        let machine_a = find_tile(BlockKind::MachineA);
+        let machine_b = find_tile(BlockKind::MachineB);
+        let buffer = find_tile(BlockKind::Buffer);
+        let seller = find_tile(BlockKind::Seller);
 
-        assert_eq!(
-            low.effective_sale_price_eur(),
-            DEFAULT_FINISHED_BOX_PRICE_EUR
-        );
-        assert_eq!(high.effective_sale_price_eur(), 140.0);
+        // Flux global orienté réception -> production -> expédition.
+        assert!(storage.0 < machine_a.0);
+        assert!(machine_a.0 <= machine_b.0);
+        assert!(machine_b.0 <= buffer.0);
+        assert!(buffer.0 <= seller.0);
+
+        assert_eq!(sim.zone_kind_at_tile(storage), ZoneKind::Receiving);
+        assert_eq!(sim.zone_kind_at_tile(machine_a), ZoneKind::Processing);
+        assert_eq!(sim.zone_kind_at_tile(machine_b), ZoneKind::Processing);
+        assert_eq!(sim.zone_kind_at_tile(seller), ZoneKind::Support);
     }
 
     #[test]
-    fn build_mode_can_place_and_sell_block() {
+    fn zone_paint_rectangle_spends_cash_and_applies_zone() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
         let mut world = crate::World::new_room(25, 15);
-        let cash0 = sim.economy.cash;
-        let tile = (6, 11);
-
+        let cash0 = sim.cash();
         sim.toggle_build_mode();
-        sim.apply_build_click(&mut world, tile, false);
-        assert!(sim.block_at_tile(tile).is_some());
-        assert!(sim.economy.cash < cash0);
+        sim.set_zone_brush(ZoneKind::Receiving);
+        sim.set_zone_paint_mode(true);
 
-        sim.apply_build_click(&mut world, tile, true);
-        assert!(sim.block_at_tile(tile).is_none());
+        sim.apply_build_click(&mut world, (3, 3), false);
+        sim.apply_build_click(&mut world, (5, 4), false);
+
+        assert_eq!(sim.zone_kind_at_tile((3, 3)), ZoneKind::Receiving);
+        assert_eq!(sim.zone_kind_at_tile((5, 4)), ZoneKind::Receiving);
+        assert!(sim.cash() < cash0);
     }
 
     #[test]
-    fn action_status_survives_production_tick_until_deterministic_expiry() {
+    fn missed_contract_charges_penalty_and_drives_otif() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let mut world = crate::World::new_room(25, 15);
-        sim.toggle_build_mode();
+        let demand = sim.market_demand_per_hour();
+        sim.apply_command(SimCommand::SetSalePrice { price_eur: 1_200.0 })
+            .expect("price should be accepted");
+        assert!(sim.market_demand_per_hour() < demand);
+
+        sim.step(1.0);
+        let contract_id = sim
+            .market()
+            .offers()
+            .next()
+            .map(|offer| offer.id)
+            .expect("an offer should be published at start");
+        sim.apply_command(SimCommand::AcceptContract { contract_id })
+            .expect("offer should be accepted");
+        assert!(
+            sim.apply_command(SimCommand::DeclineContract { contract_id })
+                .is_err()
+        );
+        assert_eq!(sim.kpi.otif, 1.0);
+
+        // Sans bureau ni administrateur de vente, rien ne part avant l'echeance.
+        let due_s = sim.market().contract(contract_id).expect("contract").due_s;
+        let cost_before = sim.economy.cost_total;
+        while sim.clock.seconds() < due_s + 60.0 {
+            sim.step(60.0);
+        }
+        let contract = sim.market().contract(contract_id).expect("contract kept");
+        assert_eq!(contract.status, crate::gestion::ContractStatus::EnRetard);
+        assert!(sim.market().penalties_total_eur > 0.0);
+        assert!(sim.economy.cost_total - cost_before >= sim.market().penalties_total_eur);
+        // La commande echue non servie compte dans la fenetre glissante du KPI.
+        assert_eq!(sim.kpi.otif, 0.0);
+        let stats = sim
+            .market()
+            .orders
+            .stats(sim.clock.seconds(), KPI_FULFILMENT_WINDOW_H);
+        assert_eq!(stats.fill_rate(), 0.0);
+        assert!(stats.mean_lateness_h() > 0.0);
+
+        let restored = FactorySim::from_save_asset(sim.config.clone(), sim.to_save_asset())
+            .expect("save should reload");
+        assert_eq!(restored.market(), sim.market());
+    }
 
-        sim.apply_build_click(&mut world, (6, 11), false);
-        assert!(sim.status_line().contains("Place"));
+    #[test]
+    fn ledger_capitalises_capex_and_closes_daily_reports() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let mut world = crate::World::new_room(25, 15);
+        sim.apply_command_in_world(
+            SimCommand::PlaceBlock {
+                kind: BlockKind::Buffer,
+                tile: (6, 11),
+                orientation: BlockOrientation::East,
+            },
+            Some(&mut world),
+        )
+        .expect("buffer should be placeable");
+        let capex = BlockKind::Buffer.capex();
+        assert_eq!(sim.cost_total(), 0.0);
+        assert_eq!(sim.ledger().balance(Account::Immobilisations), capex);
 
-        sim.step(1.0 / 60.0);
-        assert!(sim.status_line().contains("Place"));
+        sim.step((30.0 * 3600.0 / sim.config.time_scale) as f32);
 
-        for _ in 0..ACTION_STATUS_TTL_SIM_SECONDS as usize {
-            sim.step(1.0 / 60.0);
-        }
-        assert!(!sim.status_line().contains("Place"));
+        let ledger = sim.ledger();
+        let day0 = ledger.last_daily_report();
+        assert_eq!(day0.index, 0);
+        assert!(day0.amount(Account::Amortissements) > 0.0);
+        assert!(
+            (day0.cash_flow(crate::gestion::compta::CashActivity::Investissement) + capex).abs()
+                < 1e-6
+        );
+        assert!((ledger.balance(Account::Tresorerie) - sim.cash()).abs() < 1e-6);
+        let sheet = ledger.balance_sheet();
+        assert!((sheet.total_assets_eur() - sheet.total_equity_and_liabilities_eur()).abs() < 1e-6);
+        assert!((sheet.retained_earnings_eur - sim.profit_total()).abs() < 1e-6);
     }
 
     #[test]
-    fn sales_blocked_status_is_visible_after_tick() {
+    fn energy_meter_posts_daily_bill_and_tracks_kwh_per_box() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let seller_tile = sim
-            .blocks
-            .iter()
-            .find(|block| block.kind == BlockKind::Seller)
-            .map(|block| block.origin_tile)
-            .expect("default seller should exist");
-        sim.zones.set(seller_tile, ZoneKind::Support);
-        sim.line.finished = 2;
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let idle_kw = sim.factory_load_kw();
+        assert!(idle_kw >= sim.energy_book().base_load_kw);
 
-        sim.step(1.0 / 60.0);
+        let minute_step = (60.0 / sim.config.time_scale) as f32;
+        let mut peak_kw = idle_kw;
+        for _ in 0..(25 * 60) {
+            sim.step(minute_step);
+            peak_kw = peak_kw.max(sim.energy().load_kw);
+        }
+        assert!(peak_kw > idle_kw);
 
-        assert!(sim.status_line().contains("Vente en attente"));
-        assert!(sim.status_line().contains("administrateur"));
-        assert_eq!(sim.line.finished, 2);
+        let bill = sim.energy().last_bill().expect("day 0 should be billed");
+        assert_eq!(bill.day_index, 0);
+        assert!(bill.kwh >= sim.energy_book().base_load_kw * 24.0 - 1e-6);
+        let day0 = sim.ledger().last_daily_report();
+        assert_eq!(day0.index, 0);
+        assert!((day0.amount(Account::Energie) - bill.cost_eur).abs() < 1e-6);
+        assert!(sim.energy().day_kwh > 0.0);
+        if sim.line.produced_finished_total > 0 {
+            assert!(sim.kpi().energy_kwh_per_box > 0.0);
+        }
     }
 
     #[test]
-    fn production_requires_assigned_team_lead() {
+    fn unmet_needs_slow_the_line_and_send_employees_to_the_lavabo() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        sim.stock.raw_line_input = 20;
-        sim.line.raw = 20;
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let minute_step = (60.0 / sim.config.time_scale) as f32;
+        sim.step(minute_step);
 
-        for _ in 0..300 {
-            sim.step(1.0 / 60.0);
+        let line_id = MAIN_PRODUCTION_LINE_ID;
+        let operator_id = sim
+            .personnel
+            .line_operators(line_id)
+            .next()
+            .map(|employee| employee.id)
+            .expect("line should have an operator on shift");
+        let speed_before = sim.production_staffing_factor(line_id);
+        let defect_before = sim.line_operator_defect(line_id);
+        for employee in &mut sim.personnel.employees {
+            for need in NeedKind::ALL {
+                employee.needs.levels.insert(need, 40.0);
+            }
         }
+        assert!(sim.production_staffing_factor(line_id) < speed_before);
+        assert!(sim.line_operator_defect(line_id) > defect_before);
+        assert!(sim.employee_work_speed_factor(operator_id).unwrap() < 1.0);
 
-        assert_eq!(sim.line.produced_wip_total, 0);
-        assert!(sim.status_line().contains("chef"));
+        let lavabo = (2, 2);
+        sim.apply_command(SimCommand::SetLavaboTiles {
+            tiles: vec![lavabo],
+        })
+        .expect("lavabo tiles should be accepted");
+        sim.personnel
+            .employee_mut(operator_id)
+            .unwrap()
+            .needs
+            .levels
+            .insert(NeedKind::Toilettes, 20.0);
+        sim.step(minute_step);
+        let employee = sim.personnel.employee(operator_id).unwrap();
+        assert_eq!(employee.status, EmployeeStatus::EnPause);
+        assert_eq!(employee.needs.break_need, Some(NeedKind::Toilettes));
+        assert!(employee.task_label.contains("lavabo"));
+        assert!(
+            sim.personnel
+                .line_operators(line_id)
+                .all(|e| e.id != operator_id)
+        );
+        let agent = sim
+            .agent_for_employee(operator_id)
+            .expect("agent stays on site");
+        assert!(agent.current_job.is_none());
+
+        let mut minutes = 0;
+        while sim.personnel.employee(operator_id).unwrap().status == EmployeeStatus::EnPause {
+            sim.step(minute_step);
+            minutes += 1;
+            assert!(minutes < 60, "break should end within the hour");
+        }
+        let employee = sim.personnel.employee(operator_id).unwrap();
+        assert!(employee.needs.level(NeedKind::Toilettes) >= sim.needs_book().break_target);
+        assert_eq!(employee.needs.breaks_total, 1);
+        assert_eq!(sim.agent_for_employee(operator_id).unwrap().tile, lavabo);
     }
 
     #[test]
-    fn cariste_is_required_to_feed_line_from_receiving_stock() {
+    fn low_morale_slows_work_and_resignation_posts_exit_cost() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
         sim.bootstrap_functional_factory()
             .expect("test factory should be bootstrapped");
+        let minute_step = (60.0 / sim.config.time_scale) as f32;
+        sim.step(minute_step);
+
         let cariste_id = sim
             .personnel
             .employees
             .iter()
             .find(|employee| employee.role == EmployeeRole::Cariste)
             .map(|employee| employee.id)
-            .expect("sandbox cariste should exist");
-        sim.apply_command(SimCommand::FireEmployee {
+            .expect("test factory has a cariste");
+        let speed_before = sim.employee_work_speed_factor(cariste_id).unwrap();
+        sim.personnel.employee_mut(cariste_id).unwrap().morale = 10.0;
+        assert!(sim.employee_work_speed_factor(cariste_id).unwrap() < speed_before);
+
+        assert!(
+            sim.apply_command(SimCommand::SetEmployeeWage {
+                employee_id: cariste_id,
+                hourly_wage: -1.0,
+            })
+            .is_err()
+        );
+        sim.apply_command(SimCommand::SetEmployeeWage {
             employee_id: cariste_id,
+            hourly_wage: 30.0,
         })
-        .expect("cariste should be fireable");
-        sim.stock.raw_receiving = 500;
-        sim.stock.raw_line_input = 0;
-        sim.line.raw = 0;
-
-        sim.step(60.0);
+        .expect("wage should be set");
+        sim.step(minute_step);
+        let track = &sim.personnel.employee(cariste_id).unwrap().morale_track;
+        assert!(track.factors.wage > 0.0);
 
-        assert_eq!(sim.stock.raw_line_input, 0);
-        assert!(sim.production_status.contains("caristes"));
+        // Preavis qui s'acheve: le cariste part et son solde passe en charge.
+        let now_s = sim.clock.seconds();
+        sim.personnel
+            .employee_mut(cariste_id)
+            .unwrap()
+            .morale_track
+            .notice_until_s = Some(now_s + 30.0);
+        sim.step(minute_step);
+        assert!(sim.personnel.employee(cariste_id).is_none());
+        assert_eq!(sim.personnel.turnover.departures_total, 1);
+        let exit_cost = sim.morale_book().exit_cost_hours * 30.0;
+        assert!((sim.ledger().balance(Account::Departs) - exit_cost).abs() < 1e-6);
+        assert!(sim.status_line().contains("Depart de"));
     }
 
     #[test]
-    fn bought_raw_stock_is_paid_delivered_and_moved_by_cariste() {
+    fn paid_training_takes_employee_off_the_floor_and_certifies_a_role_change() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
         sim.bootstrap_functional_factory()
             .expect("test factory should be bootstrapped");
-        sim.stock.raw_receiving = 0;
-        sim.stock.raw_line_input = 0;
-        sim.line.raw = 0;
-        let cash_before = sim.cash();
+        let minute_step = (60.0 / sim.config.time_scale) as f32;
+        sim.step(minute_step);
 
-        sim.apply_command(SimCommand::BuyRawStock { qty: 500 })
-            .expect("raw stock purchase should succeed");
-        assert!(sim.cash() < cash_before);
-        assert_eq!(sim.stock.pending_raw_qty(), 500);
+        let cariste_id = sim
+            .personnel
+            .employees
+            .iter()
+            .find(|employee| employee.role == EmployeeRole::Cariste)
+            .map(|employee| employee.id)
+            .expect("test factory has a cariste");
+        assert!(
+            sim.apply_command(SimCommand::ChangeEmployeeRole {
+                employee_id: cariste_id,
+                role: EmployeeRole::Technicien,
+            })
+            .is_err()
+        );
+        assert!(
+            sim.apply_command(SimCommand::ScheduleTraining {
+                employee_id: cariste_id,
+                course_id: "inconnue".to_string(),
+            })
+            .is_err()
+        );
+
+        let course = sim
+            .training_book()
+            .course("habilitation_maintenance")
+            .cloned()
+            .unwrap();
+        let technique_before = sim.personnel.employee(cariste_id).unwrap().skills.technique;
+        sim.apply_command(SimCommand::ScheduleTraining {
+            employee_id: cariste_id,
+            course_id: course.id.clone(),
+        })
+        .expect("training should be scheduled");
+        assert!(
+            (sim.ledger().balance(Account::Formation) - course.cost_eur).abs() < 1e-6,
+            "la formation passe en charge"
+        );
 
-        sim.step((crate::gestion::stock::RAW_DELIVERY_DELAY_S / sim.config.time_scale) as f32);
+        sim.step(minute_step);
+        let employee = sim.personnel.employee(cariste_id).unwrap();
+        assert_eq!(employee.status, EmployeeStatus::EnFormation);
+        assert!(sim.agent_for_employee(cariste_id).is_none());
+        assert!(sim.personnel.hourly_payroll_eur() >= employee.hourly_wage);
 
-        assert_eq!(sim.stock.pending_raw_qty(), 0);
-        assert!(sim.stock.raw_receiving > 0 || sim.stock.raw_line_input > 0);
-        assert!(sim.stock.raw_line_input <= crate::gestion::stock::RAW_LINE_INPUT_CAPACITY);
+        for _ in 0..(course.duration_h as usize * 60 + 1) {
+            sim.step(minute_step);
+        }
+        let employee = sim.personnel.employee(cariste_id).unwrap();
+        assert!(employee.training.is_none());
+        assert_ne!(employee.status, EmployeeStatus::EnFormation);
+        assert!(employee.skills.technique >= technique_before + 12);
+        sim.apply_command(SimCommand::ChangeEmployeeRole {
+            employee_id: cariste_id,
+            role: EmployeeRole::Technicien,
+        })
+        .expect("certified cariste can become a technician");
+        assert_eq!(
+            sim.personnel.employee(cariste_id).unwrap().role,
+            EmployeeRole::Technicien
+        );
     }
 
     #[test]
-    fn finished_goods_are_sold_progressively_by_admins() {
+    fn recruitment_board_interviews_and_accepted_offer_adds_employee_on_arrival() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
         sim.bootstrap_functional_factory()
             .expect("test factory should be bootstrapped");
-        let seller_tile = sim
-            .blocks
-            .iter()
-            .find(|block| block.kind == BlockKind::Seller)
-            .map(|block| block.origin_tile)
-            .expect("default seller should exist");
-        sim.zones.set(seller_tile, ZoneKind::Support);
-        sim.line.finished = 10;
-
-        sim.step(1.0 / 60.0);
+        let minute_step = (60.0 / sim.config.time_scale) as f32;
+        sim.step(minute_step);
         assert_eq!(
-            sim.line.finished, 10,
-            "one second should not sell a full box"
+            sim.recruitment().candidates.len(),
+            sim.recruitment_book().board_size
         );
 
-        for _ in 0..1_200 {
-            sim.step(1.0 / 60.0);
+        let candidate = sim.recruitment().candidates[0].clone();
+        assert!(
+            sim.apply_command(SimCommand::InterviewCandidate {
+                candidate_id: candidate.id,
+            })
+            .is_err(),
+            "entretien reserve aux candidats retenus"
+        );
+        sim.apply_command(SimCommand::ShortlistCandidate {
+            candidate_id: candidate.id,
+        })
+        .expect("candidate should be shortlisted");
+        sim.apply_command(SimCommand::InterviewCandidate {
+            candidate_id: candidate.id,
+        })
+        .expect("shortlisted candidate can be interviewed");
+        let interview_cost = sim.recruitment_book().interview_cost_eur;
+        assert!((sim.ledger().balance(Account::Recrutement) - interview_cost).abs() < 1e-6);
+        assert!(
+            sim.recruitment()
+                .candidate(candidate.id)
+                .unwrap()
+                .interviewed
+        );
+
+        let headcount = sim.personnel.employees.len();
+        sim.apply_command(SimCommand::OfferCandidate {
+            candidate_id: candidate.id,
+            hourly_wage: candidate.asking_wage * 1.1,
+        })
+        .expect("generous offer should be accepted");
+        assert!(
+            (sim.ledger().balance(Account::Recrutement)
+                - interview_cost
+                - candidate.role.hiring_cost_eur())
+            .abs()
+                < 1e-6
+        );
+        assert!(sim.recruitment().candidate(candidate.id).is_none());
+        let starts_at_s = sim.recruitment().pending[0].starts_at_s;
+        assert_eq!(sim.personnel.employees.len(), headcount);
+
+        while sim.clock.seconds() < starts_at_s + 60.0 {
+            sim.step(minute_step * 60.0);
         }
-        assert!(sim.line.sold_total > 0);
+        assert!(sim.recruitment().pending.is_empty());
+        assert_eq!(sim.personnel.employees.len(), headcount + 1);
+        let hired = sim.personnel.employees.last().unwrap();
+        assert_eq!(hired.name, candidate.name);
+        assert_eq!(hired.role, candidate.role);
+        assert_eq!(hired.skills, candidate.skills);
     }
 
     #[test]
-    fn team_lead_never_creates_more_than_three_temps() {
+    fn colleagues_sharing_a_break_build_relationships_that_feed_morale() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let ids = [
+            EmployeeRole::Cariste,
+            EmployeeRole::Technicien,
+            EmployeeRole::AgentEntretien,
+        ]
+        .map(|role| {
+            sim.personnel
+                .hire(role, 0.0)
+                .expect("role should be hireable")
+        });
+        sim.sync_agents_with_personnel();
+        // Les deux premiers boivent un cafe cote a cote, le troisieme est a l'autre bout.
+        for (id, tile) in ids.into_iter().zip([(3, 3), (4, 3), (20, 12)]) {
+            let employee = sim.personnel.employee_mut(id).expect("employee exists");
+            employee.start_break(NeedKind::Boire, "Distributeur");
+            employee.needs.break_venue = Some(BreakVenue::Distributeur);
+            employee.stress = 0.0;
+            let agent = sim
+                .agents
+                .iter_mut()
+                .find(|agent| agent.employee_id == Some(id))
+                .expect("agent exists");
+            agent.tile = tile;
+            agent.path.clear();
+        }
+
+        for _ in 0..4 {
+            sim.tick_break_exchanges(3600.0);
+        }
+        let relations = &sim.personnel.relations;
+        assert!(relations.affinity(ids[0], ids[1]).is_some_and(|a| a > 0.0));
+        assert_eq!(relations.affinity(ids[0], ids[2]), None);
+        let employee = sim.personnel.employee(ids[0]).expect("employee exists");
+        assert!(employee.relationship > 0.0);
+        assert_eq!(sim.personnel.employee(ids[2]).unwrap().relationship, 0.0);
+        let factors = sim.morale_book.factors(employee, 0.0, &sim.needs_book);
+        assert!(factors.relationship > 0.0);
+        let saved = ron_to_string_pretty(&sim.to_save_asset(), PrettyConfig::new())
+            .expect("save asset should serialize");
+        let restored: FactorySimSaveAsset = ron_from_str(&saved).expect("save asset should parse");
+        assert_eq!(restored.personnel.relations, sim.personnel.relations);
+
+        // Un depart efface ses relations: l'autre garde sa derniere moyenne connue.
+        sim.personnel.fire(ids[1]).expect("fire should succeed");
+        assert_eq!(sim.personnel.relations.affinity(ids[0], ids[1]), None);
+    }
+
+    #[test]
+    fn support_roles_sample_lots_clean_zones_and_injuries_take_staff_off_the_floor() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
         sim.bootstrap_functional_factory()
             .expect("test factory should be bootstrapped");
+        let minute_step = (60.0 / sim.config.time_scale) as f32;
+        sim.hse_book.accident_rate_per_h = 0.0;
+        for role in [
+            EmployeeRole::ControleurQualite,
+            EmployeeRole::AgentEntretien,
+            EmployeeRole::ResponsableHse,
+        ] {
+            sim.apply_command(SimCommand::HireEmployee { role })
+                .expect("support role should be hireable");
+        }
+        sim.step(minute_step);
+        for (work, expected) in [
+            (WorkType::Controle, 1),
+            (WorkType::Nettoyage, 1),
+            (WorkType::Prevention, 1),
+        ] {
+            assert_eq!(sim.personnel.active_count_for_work(work), expected);
+        }
+
+        // Le controleur preleve la premiere unite triee, puis attend l'intervalle suivant.
         let lead_id = sim
             .personnel
             .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
             .map(|lead| lead.id)
             .expect("sandbox lead should exist");
-        sim.personnel
-            .set_temp_policy(lead_id, true, 99)
-            .expect("temp policy should clamp");
-        sim.stock.raw_receiving = 2_000;
-        sim.stock.raw_line_input = 120;
+        sim.main_line_state_mut().set_active(lead_id, 2);
+        let sortex_cycle_s = sim
+            .active_recipe()
+            .stages
+            .iter()
+            .find(|stage| stage.block_kind == BlockKind::Sortex)
+            .map(|stage| stage.cycle_s)
+            .expect("sortex stage should exist");
+        sim.line
+            .flows
+            .entry(MAIN_PRODUCTION_LINE_ID)
+            .or_default()
+            .put_items(
+                &[ItemQty::new(ItemKind::Flakes, 3)],
+                None,
+                &DefectProfile::default(),
+            );
+        for _ in 0..3 {
+            sim.tick_modern_line(MAIN_PRODUCTION_LINE_ID, sortex_cycle_s);
+        }
+        let control = sim.quality_control();
+        assert_eq!(control.samples_total, 1);
+        assert_eq!(
+            control.last_sample().and_then(|sample| sample.grade),
+            Some(QualityGrade::A)
+        );
+        assert!(control.next_sample_s > sim.clock.seconds());
 
+        sim.hse.zone_hygiene.insert(ZoneKind::Processing, 10.0);
         for _ in 0..60 {
-            sim.step(1.0 / 60.0);
+            sim.step(minute_step);
         }
+        assert!(sim.hse().hygiene(ZoneKind::Processing) > 30.0);
 
-        assert!(sim.personnel.active_temps_for_lead(lead_id) <= 3);
+        // Risque force: tout le personnel au poste se blesse.
+        sim.hse_book.accident_rate_per_h = 1_000.0;
+        sim.step(minute_step);
+        sim.hse_book.accident_rate_per_h = 0.0;
+        let injured = sim
+            .personnel
+            .employees
+            .iter()
+            .filter(|employee| employee.status == EmployeeStatus::Blesse)
+            .map(|employee| employee.id)
+            .collect::<Vec<_>>();
+        assert!(!injured.is_empty());
+        assert_eq!(sim.hse().accidents_total as usize, injured.len());
+        assert!(
+            (sim.ledger().balance(Account::Accidents)
+                - sim.hse_book().accident_cost_eur * injured.len() as f64)
+                .abs()
+                < 1e-6
+        );
+        sim.step(minute_step);
+        let victim = sim.personnel.employee(injured[0]).unwrap();
+        assert!(victim.is_paid());
+        assert!(sim.agent_for_employee(victim.id).is_none());
+
+        let until_s = victim.injured_until_s.unwrap();
+        while sim.clock.seconds() < until_s + 60.0 {
+            sim.step(minute_step * 60.0);
+        }
+        let victim = sim.personnel.employee(injured[0]).unwrap();
+        assert!(victim.injured_until_s.is_none());
+        assert_ne!(victim.status, EmployeeStatus::Blesse);
     }
 
     #[test]
-    fn factory_layout_validation_rejects_overlapping_block_footprints() {
-        let layout = FactoryLayoutAsset {
-            schema_version: FACTORY_LAYOUT_SCHEMA_VERSION,
-            map_w: 12,
-            map_h: 12,
-            zones: ZoneLayer::new(12, 12, ZoneKind::Neutral),
-            blocks: vec![
-                BlockInstance {
-                    id: 1,
-                    kind: BlockKind::Storage,
-                    origin_tile: (3, 3),
-                    footprint: (1, 1),
-                    ..BlockInstance::default()
-                },
-                BlockInstance {
-                    id: 2,
-                    kind: BlockKind::Buffer,
-                    origin_tile: (3, 3),
-                    footprint: (1, 1),
-                    ..BlockInstance::default()
-                },
-            ],
-            agent_tile: (4, 4),
+    fn loans_post_to_ledger_and_insolvency_ends_in_bankruptcy() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let cash0 = sim.cash();
+        sim.apply_command(SimCommand::TakeLoan {
+            offer_id: "pret_court".to_string(),
+        })
+        .expect("loan should be granted");
+        assert_eq!(sim.cash(), cash0 + 15_000.0);
+        assert_eq!(sim.ledger().balance_sheet().loans_eur, 15_000.0);
+        assert!(
+            sim.apply_command(SimCommand::TakeLoan {
+                offer_id: "inconnu".to_string(),
+            })
+            .is_err()
+        );
+        let loan_id = sim.finance().loans[0].id;
+        sim.apply_command(SimCommand::RepayLoan { loan_id })
+            .expect("early repayment should succeed");
+        assert!(sim.finance().loans.is_empty());
+        assert!((sim.cash() - cash0).abs() < 1e-6);
+
+        // Decouvert bien au-dela de la limite: la faillite tombe apres le delai de grace.
+        let limit = sim.finance_book().credit_line_limit_eur;
+        sim.economy.repay(Account::Capital, cash0 + limit * 3.0);
+        let day_steps = (86_400.0 / sim.config.time_scale) as f32;
+        sim.step(day_steps * 0.25);
+        assert!(sim.status_line().contains("decouvert autorise depasse"));
+        for _ in 0..4 {
+            sim.step(day_steps);
+        }
+        assert!(sim.finance().is_bankrupt());
+        assert!(sim.status_line().contains("FAILLITE"));
+        assert!(sim.ledger().balance(Account::Interets) > 0.0);
+        let frozen_s = sim.clock.seconds();
+        sim.step(day_steps);
+        assert_eq!(sim.clock.seconds(), frozen_s);
+        assert!(
+            sim.apply_command(SimCommand::BuySpareParts { qty: 1 })
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn build_commands_validate_and_mutate_layout() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let mut world = crate::World::new_room(25, 15);
+        let cash0 = sim.cash();
+        let place = SimCommand::PlaceBlock {
+            kind: BlockKind::Buffer,
+            tile: (6, 11),
+            orientation: BlockOrientation::East,
         };
 
-        let err = layout.validate().expect_err("layout superpose refuse");
-        assert!(err.contains("superposes"));
-    }
+        let err = sim
+            .apply_command(place.clone())
+            .expect_err("placement needs the live map");
+        assert!(err.contains("sans carte"));
+        sim.apply_command_in_world(place.clone(), Some(&mut world))
+            .expect("buffer should be placeable");
+        let block_id = sim
+            .block_at_tile((6, 11))
+            .map(|block| block.id)
+            .expect("placed block should exist");
+        assert!(sim.cash() < cash0);
+        assert!(sim.apply_command_in_world(place, Some(&mut world)).is_err());
 
-    #[test]
-    fn modern_line_cache_is_invalidated_by_placement_and_sale() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
-        let mut world = crate::World::new_room(80, 60);
-        sim.blocks.clear();
-        sim.mark_modern_line_cache_dirty();
-        assert!(sim.cached_modern_line_readiness_reason().is_some());
-        assert!(!sim.modern_line_cache.dirty);
+        sim.apply_command_in_world(
+            SimCommand::RotateBlock {
+                block_id,
+                orientation: BlockOrientation::South,
+            },
+            Some(&mut world),
+        )
+        .expect("rotation should fit");
+        sim.apply_command_in_world(
+            SimCommand::MoveBlock {
+                block_id,
+                tile: (4, 11),
+            },
+            Some(&mut world),
+        )
+        .expect("move should fit");
+        let block = sim.block_at_tile((4, 11)).expect("block should have moved");
+        assert_eq!(block.orientation, BlockOrientation::South);
+        assert!(
+            sim.apply_command_in_world(
+                SimCommand::MoveBlock {
+                    block_id,
+                    tile: (0, 0),
+                },
+                Some(&mut world),
+            )
+            .is_err()
+        );
 
-        sim.toggle_build_mode();
-        sim.set_block_brush(BlockKind::InputHopper);
-        sim.apply_build_click(&mut world, (20, 20), false);
-        assert!(sim.modern_line_cache.dirty);
+        let cash_before_sale = sim.cash();
+        sim.apply_command(SimCommand::RemoveBlock { block_id })
+            .expect("block should be sold");
+        assert!(sim.cash() > cash_before_sale);
+        let err = sim
+            .apply_command(SimCommand::RemoveBlock { block_id })
+            .expect_err("sold block is gone");
+        assert!(err.contains("bloc introuvable"));
 
-        assert!(sim.cached_modern_line_readiness_reason().is_some());
-        assert!(!sim.modern_line_cache.dirty);
+        assert!(
+            sim.apply_scripted_build(
+                SimCommand::PaintZoneRect {
+                    origin: (20, 10),
+                    size: (10, 2),
+                    zone: ZoneKind::Receiving,
+                },
+                None,
+            )
+            .is_err()
+        );
+        let cash_before_zone = sim.cash();
+        sim.apply_scripted_build(
+            SimCommand::PaintZoneRect {
+                origin: (2, 2),
+                size: (2, 2),
+                zone: ZoneKind::Receiving,
+            },
+            None,
+        )
+        .expect("scripted zone should paint for free");
+        assert!(
+            sim.apply_scripted_build(SimCommand::SetSalePrice { price_eur: 900.0 }, None)
+                .is_err()
+        );
+        assert_eq!(sim.zone_kind_at_tile((3, 3)), ZoneKind::Receiving);
+        assert_eq!(sim.cash(), cash_before_zone);
 
-        sim.apply_build_click(&mut world, (20, 20), true);
-        assert!(sim.modern_line_cache.dirty);
+        sim.apply_command_in_world(
+            SimCommand::PaintFloorRect {
+                origin: (2, 2),
+                size: (2, 2),
+                floor: BuildFloorKind::Metal,
+            },
+            Some(&mut world),
+        )
+        .expect("floor should paint");
+        assert_eq!(world.get(3, 3), crate::Tile::FloorMetal);
+        assert!((cash_before_zone - sim.cash() - 4.0 * 14.0).abs() < 1e-9);
+        let cash_before_reset = sim.cash();
+        sim.apply_command_in_world(
+            SimCommand::ResetFloorRect {
+                origin: (2, 2),
+                size: (2, 2),
+            },
+            Some(&mut world),
+        )
+        .expect("floor should reset");
+        assert_eq!(world.get(3, 3), crate::Tile::Floor);
+        assert_eq!(sim.cash(), cash_before_reset);
+        assert!(
+            sim.apply_command_in_world(
+                SimCommand::PaintFloorRect {
+                    origin: (0, 0),
+                    size: (1, 1),
+                    floor: BuildFloorKind::Metal,
+                },
+                Some(&mut world),
+            )
+            .is_err()
+        );
+
+        assert!(
+            sim.apply_command(SimCommand::SetSalePrice { price_eur: 50.0 })
+                .is_err()
+        );
+        sim.apply_command(SimCommand::SetSalePrice { price_eur: 900.0 })
+            .expect("sale price should be accepted");
+        let reloaded =
+            FactorySim::from_save_asset(StarterSimConfig::default(), sim.to_save_asset())
+                .expect("save should reload");
+        assert_eq!(reloaded.sale_price_eur(), 900.0);
     }
 
     #[test]
-    fn render_ready_read_uses_cached_modern_line_state_only() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
-        sim.blocks.clear();
-        sim.mark_modern_line_cache_dirty();
-
-        assert!(!sim.modern_line_ready_cached_for_render());
+    fn floor_paint_spends_cash_and_changes_tile() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        let mut world = crate::World::new_room(25, 15);
+        let tile = (4, 4);
+        sim.toggle_build_mode();
+        sim.set_floor_brush(BuildFloorKind::Metal);
+        let cash0 = sim.cash();
 
-        let reason = sim.cached_modern_line_readiness_reason();
-        assert_eq!(sim.modern_line_ready_cached_for_render(), reason.is_none());
+        sim.apply_build_click(&mut world, tile, false);
 
-        sim.mark_modern_line_cache_dirty();
-        assert!(!sim.modern_line_ready_cached_for_render());
+        assert_eq!(world.get(tile.0, tile.1), crate::Tile::FloorMetal);
+        assert!(sim.cash() < cash0);
     }
 
     #[test]
-    fn agent_debug_label_exposes_blocked_job_priority_target_and_reason() {
+    fn rack_store_and_take_palette_by_level() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let job_id = 99;
-        sim.jobs.push(Job {
-            id: job_id,
-            kind: JobKind::OperateMachine { block_id: 1 },
-            state: JobState::Pending,
-            priority: 42,
-            score_debug: String::new(),
-            assigned_agent: None,
-        });
-        sim.reservations.insert(
-            ReservationKey::BlockInput(1),
-            Reservation {
-                job_id: 777,
-                ttl_s: RESERVATION_TTL_SECONDS,
-            },
-        );
-
-        sim.tick_agent(1.0 / 60.0);
+        let rack_tile = sim
+            .block_debug_views()
+            .into_iter()
+            .find(|block| block.kind == BlockKind::Buffer)
+            .expect("default layout must include one rack")
+            .tile;
 
-        let label = &sim.agent_debug_views()[0].label;
-        assert!(label.contains("job=#99"));
-        assert!(label.contains("cible=B1"));
-        assert!(label.contains("priorite=42"));
-        assert!(label.contains("raison=conflit reservation"));
+        assert!(sim.rack_store_palette(rack_tile, 3).is_ok());
+        assert!(sim.rack_store_palette(rack_tile, 3).is_err());
+        assert!(sim.rack_take_palette(rack_tile, 3).is_ok());
+        assert!(sim.rack_take_palette(rack_tile, 3).is_err());
     }
 
     #[test]
-    fn selling_block_purges_related_jobs_and_reservations() {
+    fn sales_requires_office_and_manager() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let mut world = crate::World::new_room(25, 15);
-        let machine = sim
-            .blocks
-            .iter()
-            .find(|block| block.kind == BlockKind::MachineA)
-            .expect("machine A attendue");
-        let machine_id = machine.id;
-        let machine_tile = machine.origin_tile;
-        let job_id = 44;
-        sim.jobs.push(Job {
-            id: job_id,
-            kind: JobKind::OperateMachine {
-                block_id: machine_id,
-            },
-            state: JobState::InProgress,
-            priority: 60,
-            score_debug: "test".to_string(),
-            assigned_agent: Some(sim.agent.id),
-        });
-        sim.agent.current_job = Some(job_id);
-        sim.agent.job_progress_s = 2.0;
-        sim.reservations.insert(
-            ReservationKey::BlockInput(machine_id),
-            Reservation {
-                job_id,
-                ttl_s: RESERVATION_TTL_SECONDS,
-            },
-        );
+        sim.set_sales_office_present_for_test(true);
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::AdministrateurVente,
+        })
+        .expect("sales manager should be hireable");
+        assert!(sim.sales_operational());
+        sim.toggle_sales_manager_assigned();
+        assert!(!sim.sales_operational());
+        assert!(sim.sales_block_reason().contains("administrateur"));
+    }
 
-        sim.toggle_build_mode();
-        sim.apply_build_click(&mut world, machine_tile, true);
+    #[test]
+    fn dryer_oven_footprint_rotates_with_orientation() {
+        let horizontal = BlockKind::DryerOven.footprint_for_orientation(BlockOrientation::East);
+        let vertical = BlockKind::DryerOven.footprint_for_orientation(BlockOrientation::South);
+        assert_eq!(horizontal, (20, 10));
+        assert_eq!(vertical, (10, 20));
+    }
 
-        assert!(sim.block_index_by_id(machine_id).is_none());
-        assert!(sim.jobs.iter().all(|job| job.id != job_id));
-        assert_eq!(sim.agent.current_job, None);
-        assert!(sim.reservations.is_empty());
+    #[test]
+    fn input_hopper_footprint_rotates_with_orientation() {
+        let horizontal = BlockKind::InputHopper.footprint_for_orientation(BlockOrientation::East);
+        let vertical = BlockKind::InputHopper.footprint_for_orientation(BlockOrientation::South);
+        assert_eq!(horizontal, (8, 3));
+        assert_eq!(vertical, (3, 8));
     }
 
     #[test]
-    fn storage_debug_view_exposes_raw_quantity() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        sim.line.raw = 12;
-        sim.sync_blocks_from_line();
+    fn build_mode_rejects_overlapping_large_footprints() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let mut world = crate::World::new_room(120, 90);
+        sim.toggle_build_mode();
 
-        let storage = sim
+        let existing_tiles = sim
             .block_debug_views()
             .into_iter()
-            .find(|block| block.kind == BlockKind::Storage)
-            .expect("storage block should exist");
+            .map(|b| b.tile)
+            .collect::<Vec<_>>();
+        for tile in existing_tiles {
+            sim.apply_build_click(&mut world, tile, true);
+        }
 
-        assert_eq!(storage.raw_qty, 12);
+        sim.set_block_brush(BlockKind::InputHopper);
+        sim.set_block_orientation(BlockOrientation::East);
+        sim.apply_build_click(&mut world, (8, 72), false);
+        let cash_after_first = sim.cash();
+        let count_after_first = sim
+            .block_debug_views()
+            .into_iter()
+            .filter(|b| b.kind == BlockKind::InputHopper)
+            .count();
+
+        sim.set_block_brush(BlockKind::FluidityTank);
+        sim.apply_build_click(&mut world, (9, 73), false);
+        let count_after_second = sim
+            .block_debug_views()
+            .into_iter()
+            .filter(|b| b.kind == BlockKind::FluidityTank)
+            .count();
+
+        assert_eq!(count_after_first, 1);
+        assert_eq!(count_after_second, 0);
+        assert_eq!(sim.cash(), cash_after_first);
+        assert!(sim.status_line().contains("destination occupee"));
     }
 
     #[test]
-    fn minimal_block_debug_views_keep_structure_without_inventory_strings() {
-        let sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let full = sim.block_debug_views();
-        let minimal = sim.block_debug_views_with_options(false);
+    fn build_preview_advises_start_of_line_when_no_modern_component() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let mut world = crate::World::new_room(120, 90);
 
-        assert_eq!(full.len(), minimal.len());
-        for (lhs, rhs) in full.iter().zip(minimal.iter()) {
-            assert_eq!(lhs.id, rhs.id);
-            assert_eq!(lhs.kind, rhs.kind);
-            assert_eq!(lhs.tile, rhs.tile);
-            assert_eq!(lhs.footprint, rhs.footprint);
-            assert_eq!(lhs.orientation, rhs.orientation);
-            assert_eq!(lhs.raw_qty, rhs.raw_qty);
-            assert_eq!(lhs.rack_levels, rhs.rack_levels);
-            assert!(rhs.inventory_summary.is_empty());
+        sim.toggle_build_mode();
+        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
+            sim.apply_build_click(&mut world, tile, true);
         }
+
+        sim.set_block_brush(BlockKind::FluidityTank);
+        sim.set_block_orientation(BlockOrientation::East);
+        let Some(preview) = sim.build_block_preview(&world, (20, 20)) else {
+            panic!("preview should exist");
+        };
+
+        assert!(preview.can_place, "preview doit etre placable ici");
+        assert!(!preview.connects_to_line);
+        assert!(preview.guidance.contains("Aucune ligne en cours"));
     }
 
     #[test]
-    fn block_render_views_match_debug_structure_without_inventory_strings() {
-        let sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let full = sim.block_debug_views();
-        let render: Vec<BlockRenderView> = sim.block_render_views().collect();
+    fn build_preview_guides_modern_line_order() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let mut world = crate::World::new_room(120, 90);
+
+        sim.toggle_build_mode();
+        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
+            sim.apply_build_click(&mut world, tile, true);
+        }
+
+        assert!(sim.build_mode_enabled());
+        sim.set_block_brush(BlockKind::InputHopper);
+        sim.set_block_orientation(BlockOrientation::East);
+        sim.apply_build_click(&mut world, (10, 20), false);
+
+        sim.set_block_brush(BlockKind::FluidityTank);
+        sim.set_block_orientation(BlockOrientation::East);
+        let Some(preview) = sim.build_block_preview(&world, (18, 20)) else {
+            panic!("preview should exist");
+        };
 
-        assert_eq!(full.len(), render.len());
-        for (lhs, rhs) in full.iter().zip(render.iter()) {
-            assert_eq!(lhs.render_view(), *rhs);
-        }
+        assert!(preview.can_place);
+        assert!(preview.connects_to_line);
+        assert!(preview.guidance.contains("Etape conseillee"));
     }
 
     #[test]
-    fn refresh_jobs_does_not_duplicate_existing_blocked_kind() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        sim.jobs.clear();
+    fn modern_line_next_step_is_connectivity_driven() {
+        fn place(
+            sim: &mut FactorySim,
+            world: &mut crate::World,
+            kind: BlockKind,
+            tile: (i32, i32),
+            orientation: BlockOrientation,
+        ) {
+            let before = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            sim.set_block_brush(kind);
+            sim.set_block_orientation(orientation);
+            sim.apply_build_click(world, tile, false);
+            let after = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            assert!(
+                after > before,
+                "placement failed for {:?} at {:?}: {}",
+                kind,
+                tile,
+                sim.status_line()
+            );
+        }
 
-        let storage_id = sim
-            .blocks
-            .iter()
-            .find(|b| b.kind == BlockKind::Storage)
-            .expect("storage block should exist")
-            .id;
-        let machine_a_id = sim
-            .blocks
-            .iter()
-            .find(|b| b.kind == BlockKind::MachineA)
-            .expect("machine A block should exist")
-            .id;
-        let blocked_kind = JobKind::Haul {
-            from_block: storage_id,
-            to_block: machine_a_id,
-            item_kind: ItemKind::Raw,
-            qty: 1,
-        };
-        sim.jobs.push(Job {
-            id: 9000,
-            kind: blocked_kind.clone(),
-            state: JobState::Blocked("conflit reservation".to_string()),
-            priority: 50,
-            score_debug: String::new(),
-            assigned_agent: None,
-        });
-        sim.line.raw = 24;
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let mut world = crate::World::new_room(120, 90);
 
-        for _ in 0..20 {
-            sim.refresh_jobs();
+        sim.toggle_build_mode();
+        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
+            sim.apply_build_click(&mut world, tile, true);
         }
 
-        let same_kind = sim
-            .jobs
-            .iter()
-            .filter(|job| job.kind == blocked_kind)
-            .count();
         assert_eq!(
-            same_kind, 1,
-            "refresh_jobs should not append duplicates for an already blocked kind"
+            sim.next_modern_line_step(),
+            Some(BlockKind::InputHopper),
+            "premier bloc attendu"
         );
-    }
 
-    #[test]
-    fn tick_agent_retries_blocked_job_when_reservations_are_available() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        sim.jobs.clear();
-        sim.reservations.clear();
+        place(
+            &mut sim,
+            &mut world,
+            BlockKind::InputHopper,
+            (10, 20),
+            BlockOrientation::East,
+        );
 
-        let machine_a_id = sim
-            .blocks
-            .iter()
-            .find(|b| b.kind == BlockKind::MachineA)
-            .expect("machine A block should exist")
-            .id;
-        let blocked_job_id = 77;
-        sim.jobs.push(Job {
-            id: blocked_job_id,
-            kind: JobKind::OperateMachine {
-                block_id: machine_a_id,
-            },
-            state: JobState::Blocked("conflit reservation".to_string()),
-            priority: 60,
-            score_debug: String::new(),
-            assigned_agent: None,
-        });
+        assert_eq!(
+            sim.next_modern_line_step(),
+            Some(BlockKind::FluidityTank),
+            "apres entree ligne, la prochaine etape est bac fluidite"
+        );
 
-        sim.agent.current_job = None;
-        sim.agent.job_progress_s = 0.0;
-        sim.tick_agent(1.0 / 60.0);
+        place(
+            &mut sim,
+            &mut world,
+            BlockKind::Conveyor,
+            (18, 22),
+            BlockOrientation::East,
+        );
+        place(
+            &mut sim,
+            &mut world,
+            BlockKind::FluidityTank,
+            (19, 20),
+            BlockOrientation::East,
+        );
 
-        assert_eq!(sim.agent.current_job, Some(blocked_job_id));
-        let job = sim
-            .jobs
-            .iter()
-            .find(|j| j.id == blocked_job_id)
-            .expect("blocked test job should still exist");
-        assert!(matches!(job.state, JobState::Claimed));
+        assert_eq!(
+            sim.next_modern_line_step(),
+            Some(BlockKind::Cutter),
+            "apres raccord flux, la suivante etape doit etre la coupeuse"
+        );
     }
 
     #[test]
-    fn default_layout_flow_is_coherent_with_zones() {
-        let sim = FactorySim::new(StarterSimConfig::default(), 168, 108);
-        let views = sim.block_debug_views();
+    fn modern_line_rejects_side_connection_from_input_hopper() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let mut world = crate::World::new_room(120, 90);
+        sim.toggle_build_mode();
 
-        let find_tile = |kind: BlockKind| {
-            views
-                .iter()
-                .find(|b| b.kind == kind)
-                .map(|b| b.tile)
-                .expect("required block should exist")
-        };
+        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
+            sim.apply_build_click(&mut world, tile, true);
+        }
 
-        let storage = find_tile(BlockKind::Storage);
-        let machine_a = find_tile(BlockKind::MachineA);
-        let machine_b = find_tile(BlockKind::MachineB);
-        let buffer = find_tile(BlockKind::Buffer);
-        let seller = find_tile(BlockKind::Seller);
+        let mut place = |kind: BlockKind, tile: (i32, i32)| {
+            let before = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            sim.set_block_brush(kind);
+            sim.set_block_orientation(BlockOrientation::East);
+            sim.apply_build_click(&mut world, tile, false);
+            let after = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            assert!(
+                after > before,
+                "placement failed for {:?} at {:?}: {}",
+                kind,
+                tile,
+                sim.status_line()
+            );
+        };
 
-        // Flux global orienté réception -> production -> expédition.
-        assert!(storage.0 < machine_a.0);
-        assert!(machine_a.0 <= machine_b.0);
-        assert!(machine_b.0 <= buffer.0);
-        assert!(buffer.0 <= seller.0);
+        place(BlockKind::InputHopper, (10, 20));
+        place(BlockKind::Conveyor, (13, 23));
+        place(BlockKind::FluidityTank, (14, 23));
 
-        assert_eq!(sim.zone_kind_at_tile(storage), ZoneKind::Receiving);
-        assert_eq!(sim.zone_kind_at_tile(machine_a), ZoneKind::Processing);
-        assert_eq!(sim.zone_kind_at_tile(machine_b), ZoneKind::Processing);
-        assert_eq!(sim.zone_kind_at_tile(seller), ZoneKind::Support);
+        assert_eq!(
+            sim.next_modern_line_step(),
+            Some(BlockKind::FluidityTank),
+            "connexion laterale de l'entree ligne ne doit pas valider le flux vers le bac"
+        );
     }
 
     #[test]
-    fn zone_paint_rectangle_spends_cash_and_applies_zone() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let mut world = crate::World::new_room(25, 15);
-        let cash0 = sim.cash();
+    fn modern_line_rejects_reverse_conveyor_direction_after_input_hopper() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let mut world = crate::World::new_room(120, 90);
         sim.toggle_build_mode();
-        sim.set_zone_brush(ZoneKind::Receiving);
-        sim.set_zone_paint_mode(true);
 
-        sim.apply_build_click(&mut world, (3, 3), false);
-        sim.apply_build_click(&mut world, (5, 4), false);
-
-        assert_eq!(sim.zone_kind_at_tile((3, 3)), ZoneKind::Receiving);
-        assert_eq!(sim.zone_kind_at_tile((5, 4)), ZoneKind::Receiving);
-        assert!(sim.cash() < cash0);
-    }
+        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
+            sim.apply_build_click(&mut world, tile, true);
+        }
 
-    #[test]
-    fn floor_paint_spends_cash_and_changes_tile() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let mut world = crate::World::new_room(25, 15);
-        let tile = (4, 4);
-        sim.toggle_build_mode();
-        sim.set_floor_brush(BuildFloorKind::Metal);
-        let cash0 = sim.cash();
+        let mut place = |kind: BlockKind, tile: (i32, i32), orientation: BlockOrientation| {
+            let before = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            sim.set_block_brush(kind);
+            sim.set_block_orientation(orientation);
+            sim.apply_build_click(&mut world, tile, false);
+            let after = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            assert!(
+                after > before,
+                "placement failed for {:?} at {:?}: {}",
+                kind,
+                tile,
+                sim.status_line()
+            );
+        };
 
-        sim.apply_build_click(&mut world, tile, false);
+        place(BlockKind::InputHopper, (10, 20), BlockOrientation::East);
+        place(BlockKind::Conveyor, (18, 22), BlockOrientation::West);
+        place(BlockKind::FluidityTank, (19, 20), BlockOrientation::East);
 
-        assert_eq!(world.get(tile.0, tile.1), crate::Tile::FloorMetal);
-        assert!(sim.cash() < cash0);
+        assert_eq!(
+            sim.next_modern_line_step(),
+            Some(BlockKind::FluidityTank),
+            "convoyeur inverse apres entree ligne ne doit pas valider la connexion vers le bac"
+        );
     }
 
     #[test]
-    fn rack_store_and_take_palette_by_level() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        let rack_tile = sim
-            .block_debug_views()
-            .into_iter()
-            .find(|block| block.kind == BlockKind::Buffer)
-            .expect("default layout must include one rack")
-            .tile;
+    fn modern_line_rejects_side_connection_from_dryer_oven() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 140, 100);
+        let mut world = crate::World::new_room(140, 100);
+        sim.toggle_build_mode();
 
-        assert!(sim.rack_store_palette(rack_tile, 3).is_ok());
-        assert!(sim.rack_store_palette(rack_tile, 3).is_err());
-        assert!(sim.rack_take_palette(rack_tile, 3).is_ok());
-        assert!(sim.rack_take_palette(rack_tile, 3).is_err());
-    }
+        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
+            sim.apply_build_click(&mut world, tile, true);
+        }
 
-    #[test]
-    fn sales_requires_office_and_manager() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
-        sim.set_sales_office_present_for_test(true);
-        sim.apply_command(SimCommand::HireEmployee {
-            role: EmployeeRole::AdministrateurVente,
-        })
-        .expect("sales manager should be hireable");
-        assert!(sim.sales_operational());
-        sim.toggle_sales_manager_assigned();
-        assert!(!sim.sales_operational());
-        assert!(sim.sales_block_reason().contains("administrateur"));
-    }
+        let mut place = |kind: BlockKind, tile: (i32, i32)| {
+            let before = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            sim.set_block_brush(kind);
+            sim.set_block_orientation(BlockOrientation::East);
+            sim.apply_build_click(&mut world, tile, false);
+            let after = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            assert!(
+                after > before,
+                "placement failed for {:?} at {:?}: {}",
+                kind,
+                tile,
+                sim.status_line()
+            );
+        };
 
-    #[test]
-    fn dryer_oven_footprint_rotates_with_orientation() {
-        let horizontal = BlockKind::DryerOven.footprint_for_orientation(BlockOrientation::East);
-        let vertical = BlockKind::DryerOven.footprint_for_orientation(BlockOrientation::South);
-        assert_eq!(horizontal, (20, 10));
-        assert_eq!(vertical, (10, 20));
-    }
+        place(BlockKind::InputHopper, (10, 20));
+        place(BlockKind::Conveyor, (18, 22));
+        place(BlockKind::FluidityTank, (19, 20));
+        place(BlockKind::Conveyor, (24, 22));
+        place(BlockKind::Cutter, (25, 21));
+        place(BlockKind::DistributorBelt, (28, 22));
+        place(BlockKind::DryerOven, (35, 17));
+        place(BlockKind::OvenExitConveyor, (40, 27));
 
-    #[test]
-    fn input_hopper_footprint_rotates_with_orientation() {
-        let horizontal = BlockKind::InputHopper.footprint_for_orientation(BlockOrientation::East);
-        let vertical = BlockKind::InputHopper.footprint_for_orientation(BlockOrientation::South);
-        assert_eq!(horizontal, (8, 3));
-        assert_eq!(vertical, (3, 8));
+        assert_eq!(
+            sim.next_modern_line_step(),
+            Some(BlockKind::OvenExitConveyor),
+            "connexion laterale du four ne doit pas valider le tapis sortie four"
+        );
     }
 
     #[test]
-    fn build_mode_rejects_overlapping_large_footprints() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+    fn modern_line_reports_connectivity_issue_when_chain_is_broken() {
+        let cfg = StarterSimConfig {
+            starting_cash: 500_000.0,
+            raw_delivery_per_hour: 3600.0,
+            ..StarterSimConfig::default()
+        };
+        let mut sim = FactorySim::new(cfg, 120, 90);
         let mut world = crate::World::new_room(120, 90);
         sim.toggle_build_mode();
 
@@ -5159,483 +10119,838 @@ mod tests {
             sim.apply_build_click(&mut world, tile, true);
         }
 
-        sim.set_block_brush(BlockKind::InputHopper);
-        sim.set_block_orientation(BlockOrientation::East);
-        sim.apply_build_click(&mut world, (8, 72), false);
-        let cash_after_first = sim.cash();
-        let count_after_first = sim
-            .block_debug_views()
-            .into_iter()
-            .filter(|b| b.kind == BlockKind::InputHopper)
-            .count();
+        let mut place = |kind: BlockKind, tile: (i32, i32), orientation: BlockOrientation| {
+            let before = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            sim.set_block_brush(kind);
+            sim.set_block_orientation(orientation);
+            sim.apply_build_click(&mut world, tile, false);
+            let after = sim
+                .block_debug_views()
+                .into_iter()
+                .filter(|b| b.kind == kind)
+                .count();
+            assert!(
+                after > before,
+                "placement failed for {:?} at {:?}: {}",
+                kind,
+                tile,
+                sim.status_line()
+            );
+        };
 
-        sim.set_block_brush(BlockKind::FluidityTank);
-        sim.apply_build_click(&mut world, (9, 73), false);
-        let count_after_second = sim
-            .block_debug_views()
-            .into_iter()
-            .filter(|b| b.kind == BlockKind::FluidityTank)
-            .count();
+        place(BlockKind::InputHopper, (10, 20), BlockOrientation::East);
+        place(BlockKind::Conveyor, (18, 22), BlockOrientation::East);
+        place(BlockKind::FluidityTank, (19, 20), BlockOrientation::East);
+        place(BlockKind::Cutter, (70, 50), BlockOrientation::East);
+        place(BlockKind::DistributorBelt, (73, 51), BlockOrientation::East);
+        place(BlockKind::DryerOven, (80, 42), BlockOrientation::East);
+        place(
+            BlockKind::OvenExitConveyor,
+            (100, 51),
+            BlockOrientation::East,
+        );
+        place(BlockKind::Flaker, (107, 50), BlockOrientation::East);
+        place(BlockKind::SuctionPipe, (110, 51), BlockOrientation::East);
+        place(BlockKind::Sortex, (111, 49), BlockOrientation::East);
+        place(BlockKind::BlueBagChute, (115, 49), BlockOrientation::East);
+        place(BlockKind::RedBagChute, (115, 52), BlockOrientation::East);
 
-        assert_eq!(count_after_first, 1);
-        assert_eq!(count_after_second, 0);
-        assert_eq!(sim.cash(), cash_after_first);
-        assert!(sim.status_line().contains("destination occupee"));
+        sim.build_status.clear();
+        sim.build_status_ttl_s = 0.0;
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        sim.step(1.0 / 60.0);
+
+        assert!(sim.production_status.contains("Connexion invalide"));
     }
 
     #[test]
-    fn build_preview_advises_start_of_line_when_no_modern_component() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+    fn modern_line_processes_material_when_chain_is_complete() {
+        let cfg = StarterSimConfig {
+            starting_cash: 500_000.0,
+            raw_delivery_per_hour: 3600.0,
+            ..StarterSimConfig::default()
+        };
+        let mut sim = FactorySim::new(cfg, 120, 90);
         let mut world = crate::World::new_room(120, 90);
-
         sim.toggle_build_mode();
-        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
-            sim.apply_build_click(&mut world, tile, true);
+
+        let mut place = |kind: BlockKind, tile: (i32, i32), orientation: BlockOrientation| {
+            sim.set_block_brush(kind);
+            sim.set_block_orientation(orientation);
+            sim.apply_build_click(&mut world, tile, false);
+        };
+
+        place(BlockKind::InputHopper, (10, 20), BlockOrientation::East);
+        place(BlockKind::Conveyor, (18, 22), BlockOrientation::East);
+        place(BlockKind::FluidityTank, (19, 20), BlockOrientation::East);
+        place(BlockKind::Conveyor, (24, 22), BlockOrientation::East);
+        place(BlockKind::Cutter, (25, 21), BlockOrientation::East);
+        place(BlockKind::DistributorBelt, (28, 22), BlockOrientation::East);
+        place(BlockKind::DryerOven, (35, 17), BlockOrientation::East);
+        place(
+            BlockKind::OvenExitConveyor,
+            (55, 22),
+            BlockOrientation::East,
+        );
+        place(BlockKind::Flaker, (62, 21), BlockOrientation::East);
+        place(BlockKind::SuctionPipe, (65, 22), BlockOrientation::East);
+        place(BlockKind::SuctionPipe, (66, 22), BlockOrientation::East);
+        place(BlockKind::Sortex, (67, 20), BlockOrientation::East);
+        place(BlockKind::BlueBagChute, (71, 20), BlockOrientation::East);
+        place(BlockKind::RedBagChute, (71, 23), BlockOrientation::East);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+
+        for _ in 0..1000 {
+            sim.step(1.0 / 60.0);
+        }
+
+        assert!(!sim.status_line().contains("non operationnelle"));
+        assert!(sim.line.produced_wip_total > 0);
+        assert!(sim.line.sacs_bleus_total + sim.line.sacs_rouges_total > 0);
+    }
+
+    #[test]
+    fn modern_sortex_sorts_units_by_quality_grade() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let lead_id = sim
+            .personnel
+            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
+            .map(|lead| lead.id)
+            .expect("sandbox lead should exist");
+        sim.main_line_state_mut().set_active(lead_id, 2);
+        let sortex_cycle_s = sim
+            .active_recipe()
+            .stages
+            .iter()
+            .find(|stage| stage.block_kind == BlockKind::Sortex)
+            .map(|stage| stage.cycle_s)
+            .expect("sortex stage should exist");
+
+        // Flocons issus de machines tres usees: grade C ou hors classe, refuses au tri B.
+        let worn = DefectProfile {
+            usure: 0.22,
+            ..DefectProfile::default()
+        };
+        sim.line
+            .flows
+            .entry(MAIN_PRODUCTION_LINE_ID)
+            .or_default()
+            .put_items(&[ItemQty::new(ItemKind::Flakes, 3)], None, &worn);
+        for _ in 0..3 {
+            sim.tick_modern_line(MAIN_PRODUCTION_LINE_ID, sortex_cycle_s);
+        }
+        // Flocons sains: grade A, descente bleue.
+        sim.line
+            .flows
+            .entry(MAIN_PRODUCTION_LINE_ID)
+            .or_default()
+            .put_items(
+                &[ItemQty::new(ItemKind::Flakes, 4)],
+                None,
+                &DefectProfile::default(),
+            );
+        for _ in 0..4 {
+            sim.tick_modern_line(MAIN_PRODUCTION_LINE_ID, sortex_cycle_s);
         }
 
-        sim.set_block_brush(BlockKind::FluidityTank);
-        sim.set_block_orientation(BlockOrientation::East);
-        let Some(preview) = sim.build_block_preview(&world, (20, 20)) else {
-            panic!("preview should exist");
+        let flow = sim
+            .line
+            .flow(MAIN_PRODUCTION_LINE_ID)
+            .expect("main line flow should exist");
+        assert_eq!(flow.blue_bag_fill, 4);
+        assert_eq!(flow.red_bag_fill, 3);
+        assert_eq!(sim.kpi.scrap_total, 3);
+        assert_eq!(sim.line.buffer_qty(ItemKind::Flakes), 0);
+        let quality = &sim.main_line_state().quality;
+        assert_eq!(quality.grades.get(&QualityGrade::A), Some(&4));
+        assert_eq!(
+            quality
+                .rejects_by_cause
+                .get(&crate::gestion::qualite::RejectCause::Usure),
+            Some(&3)
+        );
+        assert!((quality.scrap_rate() - 3.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn modern_stage_cycles_use_block_zone_speed() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let world = crate::World::new_room(120, 90);
+        sim.blocks.clear();
+        place_complete_modern_chain(&mut sim, &world, 0);
+        sim.mark_modern_line_cache_dirty();
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        assert_eq!(sim.cached_modern_line_readiness_reason(), None);
+        let cutter_tile = sim
+            .modern_block_of_line(MAIN_PRODUCTION_LINE_ID, BlockKind::Cutter)
+            .map(|block| block.origin_tile)
+            .expect("main line cutter should exist");
+        sim.zones.set(cutter_tile, ZoneKind::Processing);
+        sim.line.raw = 0;
+        sim.line
+            .flows
+            .entry(MAIN_PRODUCTION_LINE_ID)
+            .or_default()
+            .put_items(
+                &[ItemQty::new(ItemKind::Washed, 1)],
+                None,
+                &DefectProfile::default(),
+            );
+        let coupe_cycle_s = sim.active_recipe().stages[1].cycle_s;
+
+        let lead_id = sim
+            .personnel
+            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
+            .map(|lead| lead.id)
+            .expect("sandbox lead should exist");
+        sim.main_line_state_mut().set_active(lead_id, 2);
+        let boosted_cycle_s = coupe_cycle_s
+            / (zone_rules(ZoneKind::Processing).speed_multiplier
+                * sim.production_staffing_factor(MAIN_PRODUCTION_LINE_ID));
+        assert!(boosted_cycle_s < coupe_cycle_s);
+        sim.tick_modern_line(MAIN_PRODUCTION_LINE_ID, boosted_cycle_s + 0.01);
+
+        assert!(!sim.modern_stage_busy(MAIN_PRODUCTION_LINE_ID, BlockKind::Cutter));
+        assert!(sim.modern_stage_busy(MAIN_PRODUCTION_LINE_ID, BlockKind::DryerOven));
+    }
+
+    #[test]
+    fn modern_line_walks_second_product_recipe_from_data() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        let mut recipes = RecipeBook::load(RECIPE_BOOK_PATH).expect("recipes should load");
+        recipes.active_product = "oignon".to_string();
+        sim.recipes = recipes;
+        sim.line.raw = 0;
+        sim.line
+            .flows
+            .entry(MAIN_PRODUCTION_LINE_ID)
+            .or_default()
+            .put_items(
+                &[ItemQty::new(ItemKind::Washed, 1)],
+                None,
+                &DefectProfile::default(),
+            );
+
+        // L'emincage double les unites, le four en consomme deux par cycle.
+        sim.tick_modern_line(MAIN_PRODUCTION_LINE_ID, 60.0);
+        assert!(sim.modern_stage_busy(MAIN_PRODUCTION_LINE_ID, BlockKind::DryerOven));
+        assert_eq!(sim.line.buffer_qty(ItemKind::Sliced), 0);
+        assert_eq!(
+            sim.line
+                .flow(MAIN_PRODUCTION_LINE_ID)
+                .map(|flow| flow.stages.len()),
+            Some(sim.active_recipe().stages.len())
+        );
+    }
+
+    fn place_complete_modern_chain(sim: &mut FactorySim, world: &crate::World, dy: i32) {
+        let mut place = |kind: BlockKind, tile: (i32, i32)| {
+            sim.poser_bloc_script(
+                world,
+                kind,
+                (tile.0, tile.1 + dy),
+                BlockOrientation::East,
+                false,
+            )
+            .unwrap_or_else(|err| panic!("placement failed for {:?} at {:?}: {err}", kind, tile));
         };
 
-        assert!(preview.can_place, "preview doit etre placable ici");
-        assert!(!preview.connects_to_line);
-        assert!(preview.guidance.contains("Aucune ligne en cours"));
+        place(BlockKind::InputHopper, (10, 20));
+        place(BlockKind::Conveyor, (18, 22));
+        place(BlockKind::FluidityTank, (19, 20));
+        place(BlockKind::Conveyor, (24, 22));
+        place(BlockKind::Cutter, (25, 21));
+        place(BlockKind::DistributorBelt, (28, 22));
+        place(BlockKind::DryerOven, (35, 17));
+        place(BlockKind::OvenExitConveyor, (55, 22));
+        place(BlockKind::Flaker, (62, 21));
+        place(BlockKind::SuctionPipe, (65, 22));
+        place(BlockKind::SuctionPipe, (66, 22));
+        place(BlockKind::Sortex, (67, 20));
+        place(BlockKind::BlueBagChute, (71, 20));
+        place(BlockKind::RedBagChute, (71, 23));
     }
 
     #[test]
-    fn build_preview_guides_modern_line_order() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
-        let mut world = crate::World::new_room(120, 90);
+    fn disjoint_modern_chains_become_independent_production_lines() {
+        let cfg = StarterSimConfig {
+            starting_cash: 500_000.0,
+            raw_delivery_per_hour: 3600.0,
+            ..StarterSimConfig::default()
+        };
+        let mut sim = FactorySim::new(cfg, 120, 90);
+        let world = crate::World::new_room(120, 90);
+        sim.blocks.clear();
+        place_complete_modern_chain(&mut sim, &world, 0);
+        place_complete_modern_chain(&mut sim, &world, 30);
+        sim.mark_modern_line_cache_dirty();
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
 
-        sim.toggle_build_mode();
-        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
-            sim.apply_build_click(&mut world, tile, true);
+        assert_eq!(sim.cached_modern_line_readiness_reason(), None);
+        assert_eq!(sim.production_line_ids(), vec![1, 2]);
+        let first_block = sim.blocks[0].id;
+        let last_block = sim.blocks[sim.blocks.len() - 1].id;
+        assert_eq!(sim.modern_line_for_block(first_block), 1);
+        assert_eq!(sim.modern_line_for_block(last_block), 2);
+
+        // Le nouveau chef prend la ligne sans superviseur.
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::ChefEquipe,
+        })
+        .expect("second lead should be hired");
+        assert!(sim.personnel.team_lead_for_line(2).is_some());
+
+        for _ in 0..1000 {
+            sim.step(1.0 / 60.0);
         }
 
-        assert!(sim.build_mode_enabled());
-        sim.set_block_brush(BlockKind::InputHopper);
-        sim.set_block_orientation(BlockOrientation::East);
-        sim.apply_build_click(&mut world, (10, 20), false);
+        for line_id in [1, 2] {
+            let flow = sim.line.flow(line_id).expect("line flow should exist");
+            assert!(flow.stages.iter().any(|stage| stage.busy) || flow.wip() > 0);
+            assert!(
+                sim.production_line(line_id)
+                    .is_some_and(|line| line.active_hours > 0.0)
+            );
+        }
+    }
 
-        sim.set_block_brush(BlockKind::FluidityTank);
-        sim.set_block_orientation(BlockOrientation::East);
-        let Some(preview) = sim.build_block_preview(&world, (18, 20)) else {
-            panic!("preview should exist");
+    fn two_line_sim() -> FactorySim {
+        let cfg = StarterSimConfig {
+            starting_cash: 500_000.0,
+            ..StarterSimConfig::default()
         };
-
-        assert!(preview.can_place);
-        assert!(preview.connects_to_line);
-        assert!(preview.guidance.contains("Etape conseillee"));
+        let mut sim = FactorySim::new(cfg, 120, 90);
+        let world = crate::World::new_room(120, 90);
+        sim.blocks.clear();
+        place_complete_modern_chain(&mut sim, &world, 0);
+        place_complete_modern_chain(&mut sim, &world, 30);
+        sim.mark_modern_line_cache_dirty();
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        assert_eq!(sim.cached_modern_line_readiness_reason(), None);
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::ChefEquipe,
+        })
+        .expect("second lead should be hired");
+        sim
     }
 
     #[test]
-    fn modern_line_next_step_is_connectivity_driven() {
-        fn place(
-            sim: &mut FactorySim,
-            world: &mut crate::World,
-            kind: BlockKind,
-            tile: (i32, i32),
-            orientation: BlockOrientation,
-        ) {
-            let before = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            sim.set_block_brush(kind);
-            sim.set_block_orientation(orientation);
-            sim.apply_build_click(world, tile, false);
-            let after = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            assert!(
-                after > before,
-                "placement failed for {:?} at {:?}: {}",
-                kind,
-                tile,
-                sim.status_line()
-            );
-        }
+    fn scarce_raw_is_split_between_line_buffers() {
+        let mut sim = two_line_sim();
+        sim.stock.raw_receiving = 0;
+        sim.stock.raw_line_input = 6;
 
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
-        let mut world = crate::World::new_room(120, 90);
+        sim.step(1.0 / 60.0);
 
-        sim.toggle_build_mode();
-        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
-            sim.apply_build_click(&mut world, tile, true);
+        for line_id in [1, 2] {
+            let flow = sim.line.flow(line_id).expect("line flow should exist");
+            assert!(flow.stages[0].busy, "line {line_id} should start a unit");
+            assert_eq!(flow.raw_qty(), 2);
         }
+        assert_eq!(sim.stock.raw_line_input, 0);
+    }
 
-        assert_eq!(
-            sim.next_modern_line_step(),
-            Some(BlockKind::InputHopper),
-            "premier bloc attendu"
-        );
+    #[test]
+    fn a_stalled_line_keeps_only_its_buffer_while_the_other_runs() {
+        let mut sim = two_line_sim();
+        sim.stock.spare_parts = 0;
+        let tank = sim
+            .modern_block_of_line(1, BlockKind::FluidityTank)
+            .map(|block| block.id)
+            .expect("first line tank should exist");
+        let index = sim.block_index_by_id(tank).expect("tank index");
+        sim.blocks[index]
+            .machine
+            .get_or_insert_with(MachineState::default)
+            .broken = true;
 
-        place(
-            &mut sim,
-            &mut world,
-            BlockKind::InputHopper,
-            (10, 20),
-            BlockOrientation::East,
-        );
+        for _ in 0..1000 {
+            sim.step(1.0 / 60.0);
+        }
 
-        assert_eq!(
-            sim.next_modern_line_step(),
-            Some(BlockKind::FluidityTank),
-            "apres entree ligne, la prochaine etape est bac fluidite"
-        );
+        let stalled = sim.line.flow(1).expect("first line flow should exist");
+        assert_eq!(stalled.raw_qty(), LINE_RAW_BUFFER_UNITS);
+        assert!(!stalled.stages[0].busy);
+        let running = sim.line.flow(2).expect("second line flow should exist");
+        assert!(running.stages.iter().skip(1).any(|stage| stage.busy) || running.wip() > 1);
+        assert!(sim.line.raw > 0);
+    }
 
-        place(
-            &mut sim,
-            &mut world,
-            BlockKind::Conveyor,
-            (18, 22),
-            BlockOrientation::East,
-        );
-        place(
-            &mut sim,
-            &mut world,
-            BlockKind::FluidityTank,
-            (19, 20),
-            BlockOrientation::East,
-        );
+    #[test]
+    fn modern_line_is_fed_and_shipped_by_haul_jobs() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
+        let world = crate::World::new_room(120, 90);
+        place_complete_modern_chain(&mut sim, &world, 0);
+        sim.mark_modern_line_cache_dirty();
+        sim.bootstrap_functional_factory()
+            .expect("test factory should be bootstrapped");
+        sim.stock.raw_line_input = 0;
+        sim.line.raw = 0;
+        sim.step(1.0 / 60.0);
 
+        let block_of = |sim: &FactorySim, kind: BlockKind| {
+            sim.blocks
+                .iter()
+                .find(|b| b.kind == kind)
+                .map(|b| b.id)
+                .expect("block should exist")
+        };
+        let storage = block_of(&sim, BlockKind::Storage);
+        let hopper = block_of(&sim, BlockKind::InputHopper);
+        let chute = block_of(&sim, BlockKind::BlueBagChute);
+        let seller = block_of(&sim, BlockKind::Seller);
+        let haul_between = |sim: &FactorySim, from: BlockId, to: BlockId| {
+            sim.jobs.iter().any(|job| {
+                matches!(job.kind, JobKind::Haul { from_block, to_block, .. }
+                    if from_block == from && to_block == to)
+            })
+        };
+        assert!(haul_between(&sim, storage, hopper));
+        assert!(!haul_between(&sim, chute, seller));
+
+        sim.line
+            .flows
+            .entry(MAIN_PRODUCTION_LINE_ID)
+            .or_default()
+            .boxes_to_ship = 3;
+        sim.refresh_jobs();
+        assert!(haul_between(&sim, chute, seller));
+
+        for _ in 0..600 {
+            sim.step(1.0 / 60.0);
+        }
+        assert!(sim.kpi.raw_hauled_total > 0);
         assert_eq!(
-            sim.next_modern_line_step(),
-            Some(BlockKind::Cutter),
-            "apres raccord flux, la suivante etape doit etre la coupeuse"
+            sim.line
+                .flow(MAIN_PRODUCTION_LINE_ID)
+                .map(LineFlowState::boxes_to_ship),
+            Some(0)
         );
+        assert_eq!(sim.line.finished + sim.line.sold_total, 3);
     }
 
     #[test]
-    fn modern_line_rejects_side_connection_from_input_hopper() {
+    fn removing_a_modern_chain_drops_its_orphan_line() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
-        let mut world = crate::World::new_room(120, 90);
-        sim.toggle_build_mode();
-
-        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
-            sim.apply_build_click(&mut world, tile, true);
-        }
-
-        let mut place = |kind: BlockKind, tile: (i32, i32)| {
-            let before = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            sim.set_block_brush(kind);
-            sim.set_block_orientation(BlockOrientation::East);
-            sim.apply_build_click(&mut world, tile, false);
-            let after = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            assert!(
-                after > before,
-                "placement failed for {:?} at {:?}: {}",
-                kind,
-                tile,
-                sim.status_line()
-            );
-        };
+        let world = crate::World::new_room(120, 90);
+        sim.blocks.clear();
+        place_complete_modern_chain(&mut sim, &world, 0);
+        place_complete_modern_chain(&mut sim, &world, 30);
+        sim.mark_modern_line_cache_dirty();
+        sim.cached_modern_line_readiness_reason();
+        assert_eq!(sim.production_line_ids(), vec![1, 2]);
+
+        let second_line_blocks = sim
+            .modern_group_for_line(2)
+            .map(|group| group.block_ids.clone())
+            .expect("second line group should exist");
+        // En-cours de la ligne demontee: tampon, cycle en cours et matiere en trop.
+        let recipe = sim.recipes.active().clone();
+        let cut_stage = recipe.stages[1].id.clone();
+        let first = sim.line.flows.entry(1).or_default();
+        first.sync_stage_runtimes(&recipe);
+        first.buffers.insert(ItemKind::Washed, 2);
+        let second = sim.line.flows.entry(2).or_default();
+        second.sync_stage_runtimes(&recipe);
+        second.buffers.insert(ItemKind::Washed, 5);
+        second.stages[1].busy = true;
+        second.raw = 10;
+        sim.line.raw = crate::gestion::RAW_LINE_INPUT_CAPACITY - 4;
+        sim.stock.raw_line_input = sim.line.raw;
+        sim.stock.raw_receiving = 0;
+        let scrap_before = sim.kpi.scrap_total;
 
-        place(BlockKind::InputHopper, (10, 20));
-        place(BlockKind::Conveyor, (13, 23));
-        place(BlockKind::FluidityTank, (14, 23));
+        sim.blocks
+            .retain(|block| !second_line_blocks.contains(&block.id));
+        sim.mark_modern_line_cache_dirty();
+        sim.cached_modern_line_readiness_reason();
 
+        assert_eq!(sim.production_line_ids(), vec![MAIN_PRODUCTION_LINE_ID]);
+        assert!(sim.line.flow(2).is_none());
+        let heir = sim.line.flow(1).expect("first line flow stays");
+        assert_eq!(heir.buffer_qty(ItemKind::Washed), 7);
+        assert!(
+            heir.stages
+                .iter()
+                .any(|runtime| runtime.stage_id == cut_stage && runtime.busy)
+        );
+        assert_eq!(sim.kpi.scrap_total, scrap_before);
         assert_eq!(
-            sim.next_modern_line_step(),
-            Some(BlockKind::FluidityTank),
-            "connexion laterale de l'entree ligne ne doit pas valider le flux vers le bac"
+            sim.stock.raw_line_input,
+            crate::gestion::RAW_LINE_INPUT_CAPACITY
+        );
+        assert_eq!(sim.stock.raw_receiving, 6);
+        assert!(
+            sim.apply_command(SimCommand::SetLineTempPolicy {
+                line_id: 2,
+                enabled: true,
+                max_temps: 2,
+            })
+            .is_err()
         );
     }
 
     #[test]
-    fn modern_line_rejects_reverse_conveyor_direction_after_input_hopper() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 120, 90);
-        let mut world = crate::World::new_room(120, 90);
-        sim.toggle_build_mode();
-
-        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
-            sim.apply_build_click(&mut world, tile, true);
-        }
-
-        let mut place = |kind: BlockKind, tile: (i32, i32), orientation: BlockOrientation| {
-            let before = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            sim.set_block_brush(kind);
-            sim.set_block_orientation(orientation);
-            sim.apply_build_click(&mut world, tile, false);
-            let after = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            assert!(
-                after > before,
-                "placement failed for {:?} at {:?}: {}",
-                kind,
-                tile,
-                sim.status_line()
+    fn machine_wear_raises_seeded_breakdowns() {
+        let mut first = MachineState::default();
+        let mut second = MachineState::default();
+        let mut first_rng = RngStreams::new(DEFAULT_SIM_SEED);
+        let mut second_rng = RngStreams::new(DEFAULT_SIM_SEED);
+        let mut broke_at = None;
+        for cycle in 1..=2_000u64 {
+            let broken = first.record_cycle(first_rng.stream(RngStream::Pannes));
+            assert_eq!(
+                second.record_cycle(second_rng.stream(RngStream::Pannes)),
+                broken
             );
-        };
-
-        place(BlockKind::InputHopper, (10, 20), BlockOrientation::East);
-        place(BlockKind::Conveyor, (18, 22), BlockOrientation::West);
-        place(BlockKind::FluidityTank, (19, 20), BlockOrientation::East);
+            if broken {
+                broke_at = Some(cycle);
+                break;
+            }
+        }
 
-        assert_eq!(
-            sim.next_modern_line_step(),
-            Some(BlockKind::FluidityTank),
-            "convoyeur inverse apres entree ligne ne doit pas valider la connexion vers le bac"
-        );
+        let broke_at = broke_at.expect("worn machine should eventually break");
+        assert!(first.wear > 0.0);
+        assert_eq!(first.breakdowns_total, 1);
+        first.repair();
+        assert!(!first.broken);
+        assert_eq!(first.wear, 0.0);
+        assert_eq!(first.cycles_total, broke_at);
     }
 
     #[test]
-    fn modern_line_rejects_side_connection_from_dryer_oven() {
-        let mut sim = FactorySim::new(StarterSimConfig::default(), 140, 100);
-        let mut world = crate::World::new_room(140, 100);
-        sim.toggle_build_mode();
-
-        for tile in sim.block_debug_views().into_iter().map(|b| b.tile) {
-            sim.apply_build_click(&mut world, tile, true);
+    fn needs_breaks_and_morale_replay_identically() {
+        let world = crate::World::new_room(25, 15);
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 25, 15);
+        sim.bootstrap_functional_factory()
+            .expect("bootstrap should succeed");
+        for employee in &mut sim.personnel.employees {
+            employee.needs.levels.insert(NeedKind::Toilettes, 5.0);
         }
+        let cariste = sim
+            .personnel
+            .employees
+            .iter()
+            .find(|employee| employee.role == EmployeeRole::Cariste)
+            .map(|employee| employee.id)
+            .expect("sandbox cariste should exist");
+        sim.start_recording(Some(&world));
 
-        let mut place = |kind: BlockKind, tile: (i32, i32)| {
-            let before = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            sim.set_block_brush(kind);
-            sim.set_block_orientation(BlockOrientation::East);
-            sim.apply_build_click(&mut world, tile, false);
-            let after = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            assert!(
-                after > before,
-                "placement failed for {:?} at {:?}: {}",
-                kind,
-                tile,
-                sim.status_line()
-            );
+        sim.apply_command(SimCommand::SetLavaboTiles {
+            tiles: vec![(3, 3)],
+        })
+        .expect("lavabo should be accepted");
+        sim.apply_command(SimCommand::SetEmployeeWage {
+            employee_id: cariste,
+            hourly_wage: 12.0,
+        })
+        .expect("wage should be accepted");
+        for _ in 0..600 {
+            sim.step_in_world(crate::FIXED_DT, Some(&world));
+        }
+        let employee = sim.personnel.employee(cariste).expect("cariste stays");
+        assert!(employee.needs.breaks_total > 0);
+        assert!(employee.morale < 72.0);
+
+        let log = sim.command_log().expect("recording should be active");
+        let snapshot = |sim: &FactorySim| {
+            ron_to_string_pretty(&sim.to_save_asset(), PrettyConfig::new())
+                .expect("save asset should serialize")
         };
-
-        place(BlockKind::InputHopper, (10, 20));
-        place(BlockKind::Conveyor, (18, 22));
-        place(BlockKind::FluidityTank, (19, 20));
-        place(BlockKind::Conveyor, (24, 22));
-        place(BlockKind::Cutter, (25, 21));
-        place(BlockKind::DistributorBelt, (28, 22));
-        place(BlockKind::DryerOven, (35, 17));
-        place(BlockKind::OvenExitConveyor, (40, 27));
-
-        assert_eq!(
-            sim.next_modern_line_step(),
-            Some(BlockKind::OvenExitConveyor),
-            "connexion laterale du four ne doit pas valider le tapis sortie four"
-        );
+        let replayed = crate::rejeu::Replay::new(log)
+            .expect("replay should start")
+            .run_to_end();
+        assert_eq!(replayed.lavabo_tiles(), &[(3, 3)]);
+        assert_eq!(snapshot(&replayed), snapshot(&sim));
     }
 
     #[test]
-    fn modern_line_reports_connectivity_issue_when_chain_is_broken() {
-        let cfg = StarterSimConfig {
-            starting_cash: 500_000.0,
-            raw_delivery_per_hour: 3600.0,
-            ..StarterSimConfig::default()
+    fn same_seed_and_commands_replay_bit_for_bit() {
+        let run = |seed: u64| {
+            let config = StarterSimConfig {
+                seed,
+                ..StarterSimConfig::default()
+            };
+            let mut sim = FactorySim::new(config, 25, 15);
+            sim.bootstrap_functional_factory()
+                .expect("bootstrap should succeed");
+            // Usure forcee pour que le flux des pannes soit sollicite.
+            for machine in sim.blocks.iter_mut().filter_map(|b| b.machine.as_mut()) {
+                machine.wear = 0.9;
+            }
+            let mut world = crate::World::new_room(25, 15);
+            for _ in 0..600 {
+                sim.step(1.0 / 60.0);
+            }
+            // Embauche, achat, pose et prix en cours de partie, identiques d'un run a l'autre.
+            for command in [
+                SimCommand::HireEmployee {
+                    role: EmployeeRole::Technicien,
+                },
+                SimCommand::BuyRawStock {
+                    supplier_id: crate::gestion::fournisseurs::default_raw_supplier_id(),
+                    qty: 120,
+                },
+                SimCommand::PlaceBlock {
+                    kind: BlockKind::Buffer,
+                    tile: (6, 11),
+                    orientation: BlockOrientation::East,
+                },
+                SimCommand::SetSalePrice { price_eur: 1_150.0 },
+            ] {
+                sim.apply_command_in_world(command, Some(&mut world))
+                    .expect("replayed command should apply");
+            }
+            for _ in 0..1_200 {
+                sim.step(1.0 / 60.0);
+            }
+            sim
+        };
+        let snapshot = |sim: &FactorySim| {
+            ron_to_string_pretty(&sim.to_save_asset(), PrettyConfig::new())
+                .expect("save asset should serialize")
         };
-        let mut sim = FactorySim::new(cfg, 120, 90);
-        let mut world = crate::World::new_room(120, 90);
-        sim.toggle_build_mode();
 
-        let existing_tiles = sim
-            .block_debug_views()
-            .into_iter()
-            .map(|b| b.tile)
-            .collect::<Vec<_>>();
-        for tile in existing_tiles {
-            sim.apply_build_click(&mut world, tile, true);
+        let first = run(DEFAULT_SIM_SEED);
+        let second = run(DEFAULT_SIM_SEED);
+        assert_eq!(snapshot(&first), snapshot(&second));
+        assert_ne!(first.rng, RngStreams::new(DEFAULT_SIM_SEED));
+
+        let other = run(DEFAULT_SIM_SEED ^ 1);
+        assert_ne!(first.rng, other.rng);
+
+        // La sauvegarde conserve l'etat des flux: la suite des tirages reprend a l'identique.
+        let mut restored =
+            FactorySim::from_save_asset(StarterSimConfig::default(), first.to_save_asset())
+                .expect("save asset should reload");
+        let mut original = first.rng.clone();
+        assert_eq!(restored.rng_seed(), DEFAULT_SIM_SEED);
+        for _ in 0..16 {
+            assert_eq!(
+                restored.rng.stream(RngStream::Pannes).next_u64(),
+                original.stream(RngStream::Pannes).next_u64()
+            );
         }
+    }
 
-        let mut place = |kind: BlockKind, tile: (i32, i32), orientation: BlockOrientation| {
-            let before = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            sim.set_block_brush(kind);
-            sim.set_block_orientation(orientation);
-            sim.apply_build_click(&mut world, tile, false);
-            let after = sim
-                .block_debug_views()
-                .into_iter()
-                .filter(|b| b.kind == kind)
-                .count();
-            assert!(
-                after > before,
-                "placement failed for {:?} at {:?}: {}",
-                kind,
-                tile,
-                sim.status_line()
-            );
-        };
+    #[test]
+    fn broken_machine_needs_technician_and_spare_part() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
+        let block_id = sim
+            .blocks
+            .iter()
+            .find(|block| block.kind.is_machine())
+            .map(|block| block.id)
+            .expect("starter layout should contain a machine");
+        let machine = sim
+            .blocks
+            .iter_mut()
+            .find(|block| block.id == block_id)
+            .and_then(|block| block.machine.as_mut())
+            .expect("machine block should carry a machine state");
+        machine.broken = true;
 
-        place(BlockKind::InputHopper, (10, 20), BlockOrientation::East);
-        place(BlockKind::Conveyor, (18, 22), BlockOrientation::East);
-        place(BlockKind::FluidityTank, (19, 20), BlockOrientation::East);
-        place(BlockKind::Cutter, (70, 50), BlockOrientation::East);
-        place(BlockKind::DistributorBelt, (73, 51), BlockOrientation::East);
-        place(BlockKind::DryerOven, (80, 42), BlockOrientation::East);
-        place(
-            BlockKind::OvenExitConveyor,
-            (100, 51),
-            BlockOrientation::East,
+        sim.step(1.0 / 60.0);
+        let repair = sim
+            .jobs
+            .iter()
+            .find(|job| job.kind == JobKind::Repair { block_id })
+            .expect("broken machine should emit a repair job");
+        assert_eq!(
+            repair.state,
+            JobState::Blocked("aucun technicien disponible".to_string())
+        );
+        assert!(
+            sim.kpi
+                .downtime_by_block
+                .get(&block_id)
+                .copied()
+                .unwrap_or(0.0)
+                > 0.0
         );
-        place(BlockKind::Flaker, (107, 50), BlockOrientation::East);
-        place(BlockKind::SuctionPipe, (110, 51), BlockOrientation::East);
-        place(BlockKind::Sortex, (111, 49), BlockOrientation::East);
-        place(BlockKind::BlueBagChute, (115, 49), BlockOrientation::East);
-        place(BlockKind::RedBagChute, (115, 52), BlockOrientation::East);
 
-        sim.build_status.clear();
-        sim.build_status_ttl_s = 0.0;
-        sim.bootstrap_functional_factory()
-            .expect("test factory should be bootstrapped");
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::Technicien,
+        })
+        .expect("technician should be hireable");
         sim.step(1.0 / 60.0);
+        assert!(
+            sim.jobs
+                .iter()
+                .any(|job| job.kind == JobKind::Repair { block_id }
+                    && job.state == JobState::Blocked("pieces detachees manquantes".to_string()))
+        );
 
-        assert!(sim.production_status.contains("Connexion invalide"));
+        sim.stock.spare_parts = 1;
+        for _ in 0..(MACHINE_REPAIR_SECONDS as usize + 60) {
+            sim.step(1.0 / 60.0);
+        }
+        assert!(!sim.block_is_broken(block_id));
+        assert_eq!(sim.stock.spare_parts, 0);
+        assert!(sim.kpi.downtime_minutes >= MACHINE_REPAIR_SECONDS / 60.0);
     }
 
     #[test]
-    fn modern_line_processes_material_when_chain_is_complete() {
-        let cfg = StarterSimConfig {
-            starting_cash: 500_000.0,
-            raw_delivery_per_hour: 3600.0,
-            ..StarterSimConfig::default()
-        };
-        let mut sim = FactorySim::new(cfg, 120, 90);
-        let mut world = crate::World::new_room(120, 90);
-        sim.toggle_build_mode();
-
-        let mut place = |kind: BlockKind, tile: (i32, i32), orientation: BlockOrientation| {
-            sim.set_block_brush(kind);
-            sim.set_block_orientation(orientation);
-            sim.apply_build_click(&mut world, tile, false);
-        };
+    fn technician_walks_to_the_broken_machine_before_the_repair_progresses() {
+        let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
+        let block_id = sim
+            .blocks
+            .iter()
+            .find(|block| block.kind.is_machine())
+            .map(|block| block.id)
+            .expect("starter layout should contain a machine");
+        let machine_tile = sim.block_access_tile(block_id).expect("machine tile");
+        sim.blocks
+            .iter_mut()
+            .find(|block| block.id == block_id)
+            .and_then(|block| block.machine.as_mut())
+            .expect("machine block should carry a machine state")
+            .broken = true;
+        sim.stock.spare_parts = 1;
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::Technicien,
+        })
+        .expect("technician should be hireable");
+        sim.step(1.0 / 60.0);
 
-        place(BlockKind::InputHopper, (10, 20), BlockOrientation::East);
-        place(BlockKind::Conveyor, (18, 22), BlockOrientation::East);
-        place(BlockKind::FluidityTank, (19, 20), BlockOrientation::East);
-        place(BlockKind::Conveyor, (24, 22), BlockOrientation::East);
-        place(BlockKind::Cutter, (25, 21), BlockOrientation::East);
-        place(BlockKind::DistributorBelt, (28, 22), BlockOrientation::East);
-        place(BlockKind::DryerOven, (35, 17), BlockOrientation::East);
-        place(
-            BlockKind::OvenExitConveyor,
-            (55, 22),
-            BlockOrientation::East,
+        let technician = sim
+            .agents
+            .iter()
+            .find(|agent| agent.role == Some(EmployeeRole::Technicien))
+            .map(|agent| agent.id)
+            .expect("technician should have an agent");
+        let repair = sim
+            .jobs
+            .iter()
+            .find(|job| job.kind == JobKind::Repair { block_id })
+            .expect("broken machine should emit a repair job");
+        assert_eq!(repair.assigned_agent, Some(technician));
+        assert_ne!(
+            sim.agents.iter().find(|a| a.id == technician).unwrap().tile,
+            machine_tile
         );
-        place(BlockKind::Flaker, (62, 21), BlockOrientation::East);
-        place(BlockKind::SuctionPipe, (65, 22), BlockOrientation::East);
-        place(BlockKind::SuctionPipe, (66, 22), BlockOrientation::East);
-        place(BlockKind::Sortex, (67, 20), BlockOrientation::East);
-        place(BlockKind::BlueBagChute, (71, 20), BlockOrientation::East);
-        place(BlockKind::RedBagChute, (71, 23), BlockOrientation::East);
-        sim.bootstrap_functional_factory()
-            .expect("test factory should be bootstrapped");
 
-        for _ in 0..1000 {
+        let progress = |sim: &FactorySim| {
+            sim.blocks
+                .iter()
+                .find(|block| block.id == block_id)
+                .and_then(|block| block.machine)
+                .map_or(0.0, |machine| machine.repair_progress_s)
+        };
+        for _ in 0..600 {
+            let agent = sim.agents.iter().find(|a| a.id == technician).unwrap();
+            if agent.job_phase == JobPhase::Work {
+                break;
+            }
+            assert_eq!(progress(&sim), 0.0);
             sim.step(1.0 / 60.0);
         }
+        let agent = sim.agents.iter().find(|a| a.id == technician).unwrap();
+        assert_eq!(agent.job_phase, JobPhase::Work);
+        assert_eq!(agent.tile, machine_tile);
 
-        assert!(!sim.status_line().contains("non operationnelle"));
-        assert!(sim.line.produced_wip_total > 0);
-        assert!(sim.line.sacs_bleus_total + sim.line.sacs_rouges_total > 0);
+        sim.step(1.0 / 60.0);
+        assert!(progress(&sim) > 0.0);
+        assert_eq!(sim.stock.spare_parts, 0);
     }
 
     #[test]
-    fn modern_sortex_counts_red_outputs_as_scrap() {
+    fn downtime_only_counts_broken_machines() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
-        sim.bootstrap_functional_factory()
-            .expect("test factory should be bootstrapped");
-        let lead_id = sim
-            .personnel
-            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
-            .map(|lead| lead.id)
-            .expect("sandbox lead should exist");
-        sim.main_line_state_mut().set_active(lead_id, 2);
-        sim.line.flakes = 5;
-
-        for _ in 0..5 {
-            sim.tick_modern_line(MODERN_CYCLE_SORTEX_S);
+        for _ in 0..300 {
+            sim.step(1.0 / 60.0);
         }
-
-        assert_eq!(sim.line.blue_bag_fill, 4);
-        assert_eq!(sim.line.red_bag_fill, 1);
-        assert_eq!(sim.kpi.scrap_total, 1);
-        assert_eq!(sim.modern_sorted_units_total(), 5);
+        assert_eq!(sim.broken_machine_count(), 0);
+        assert_eq!(sim.kpi.downtime_minutes, 0.0);
     }
 
     #[test]
-    fn modern_stage_cycles_use_block_zone_speed() {
+    fn interrupted_repair_resumes_without_taking_a_second_spare_part() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
-        sim.bootstrap_functional_factory()
-            .expect("test factory should be bootstrapped");
-        let world = crate::World::new_room(80, 60);
-        sim.blocks.clear();
-        for y in 20..23 {
-            for x in 20..23 {
-                sim.zones.set((x, y), ZoneKind::Processing);
-            }
+        let block_id = sim
+            .blocks
+            .iter()
+            .find(|block| block.kind.is_machine())
+            .map(|block| block.id)
+            .expect("starter layout should contain a machine");
+        sim.blocks
+            .iter_mut()
+            .find(|block| block.id == block_id)
+            .and_then(|block| block.machine.as_mut())
+            .expect("machine block should carry a machine state")
+            .broken = true;
+        sim.stock.spare_parts = 2;
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::Technicien,
+        })
+        .expect("technician should be hireable");
+        for _ in 0..60 {
+            sim.step(1.0 / 60.0);
         }
-        sim.poser_bloc_script(
-            &world,
-            BlockKind::Cutter,
-            (20, 20),
-            BlockOrientation::East,
-            false,
-        )
-        .expect("cutter should be placeable");
-        sim.line.washed = 1;
+        assert_eq!(sim.stock.spare_parts, 1);
 
-        let lead_id = sim
+        // Technicien parti en pleine reparation: la panne attend sans reprendre de pieces.
+        let technician = sim
             .personnel
-            .team_lead_for_line(MAIN_PRODUCTION_LINE_ID)
-            .map(|lead| lead.id)
-            .expect("sandbox lead should exist");
-        sim.main_line_state_mut().set_active(lead_id, 2);
-        let boosted_cycle_s = MODERN_CYCLE_COUPE_S
-            / (zone_rules(ZoneKind::Processing).speed_multiplier
-                * sim.production_staffing_factor());
-        assert!(boosted_cycle_s < MODERN_CYCLE_COUPE_S);
-        sim.tick_modern_line(boosted_cycle_s + 0.01);
+            .employees
+            .iter()
+            .find(|employee| employee.role == EmployeeRole::Technicien)
+            .map(|employee| employee.id)
+            .unwrap();
+        sim.apply_command(SimCommand::FireEmployee {
+            employee_id: technician,
+        })
+        .expect("technician should be fired");
+        sim.step(1.0 / 60.0);
+        assert!(
+            sim.jobs
+                .iter()
+                .any(|job| job.kind == JobKind::Repair { block_id }
+                    && matches!(job.state, JobState::Blocked(_)))
+        );
 
-        assert!(!sim.line.coupe_busy);
-        assert!(sim.line.four_busy);
+        sim.apply_command(SimCommand::HireEmployee {
+            role: EmployeeRole::Technicien,
+        })
+        .expect("technician should be hireable");
+        for _ in 0..(MACHINE_REPAIR_SECONDS as usize + 60) {
+            sim.step(1.0 / 60.0);
+        }
+        assert!(!sim.block_is_broken(block_id));
+        assert_eq!(sim.stock.spare_parts, 1);
     }
 
     #[test]
     fn modern_box_sync_batches_blue_bags_without_iterating_each_box() {
         let mut sim = FactorySim::new(StarterSimConfig::default(), 80, 60);
-        sim.line.sacs_bleus_total = SACS_PAR_BOX * 3;
+        sim.line.sacs_bleus_total = sim.active_recipe().packaging.bags_per_box * 3;
         sim.line.boxes_bleues_total = 1;
 
-        sim.sync_modern_finished_boxes();
+        sim.sync_modern_finished_boxes(MAIN_PRODUCTION_LINE_ID);
 
         assert_eq!(sim.line.boxes_bleues_total, 3);
-        assert_eq!(sim.line.finished, 2);
+        assert_eq!(sim.line.finished, 0);
+        assert_eq!(
+            sim.line
+                .flow(MAIN_PRODUCTION_LINE_ID)
+                .map(LineFlowState::boxes_to_ship),
+            Some(2)
+        );
         assert_eq!(sim.line.produced_finished_total, 2);
+        assert_eq!(sim.lots.boxes.len(), 2);
     }
 
     #[test]
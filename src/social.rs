@@ -1,5 +1,6 @@
 use super::*;
 
+use crate::aleatoire::SimRng;
 use crate::historique::LogCategorie;
 use crate::interactions::{SocialActionKind, SocialEmoteIcon, SocialGesture};
 use std::collections::HashMap;
@@ -41,8 +42,7 @@ pub struct Relation {
 impl Relation {
     fn new(seed: u64) -> Self {
         // bruit initial léger, pour éviter une matrice trop plate
-        let x = (seed ^ 0x9E37_79B9_7F4A_7C15).wrapping_mul(0xBF58_476D_1CE4_E5B9);
-        let n = ((x >> 33) as i32 % 11) as f32; // ~[-10..10]
+        let n = SimRng::from_seed(seed).roll_range(21) as f32 - 10.0; // [-10..10]
         let affinity = (n / 100.0).clamp(-0.1, 0.1);
         Self { affinity }
     }
@@ -189,11 +189,11 @@ pub struct SocialState {
     next_encounter_id: u64,
 
     tick_accum: f32,
-    rng_state: u64,
+    rng: SimRng,
 }
 
 impl SocialState {
-    pub fn new(pawns: &[PawnCard], lineage_seed: u64) -> Self {
+    pub fn new(pawns: &[PawnCard], seed: u64) -> Self {
         let keys: Vec<PawnKey> = pawns.iter().map(|p| p.key).collect();
         let mut idx = HashMap::new();
         for (i, k) in keys.iter().copied().enumerate() {
@@ -207,7 +207,7 @@ impl SocialState {
                 if i == j {
                     *cell = Relation { affinity: 1.0 };
                 } else {
-                    let s = lineage_seed ^ ((i as u64) << 32) ^ (j as u64);
+                    let s = seed ^ ((i as u64) << 32) ^ (j as u64);
                     *cell = Relation::new(s);
                 }
             }
@@ -216,7 +216,7 @@ impl SocialState {
         let runtime = vec![SocialRuntime::default(); n];
         let pair_cooldown = vec![vec![0.0; n]; n];
 
-        let rng_state = lineage_seed ^ 0xD1B5_4A32_D192_ED03;
+        let rng = SimRng::from_seed(seed ^ 0xD1B5_4A32_D192_ED03);
 
         Self {
             keys,
@@ -227,7 +227,7 @@ impl SocialState {
             encounters: Vec::new(),
             next_encounter_id: 1,
             tick_accum: 0.0,
-            rng_state,
+            rng,
         }
     }
 
@@ -1112,18 +1112,7 @@ impl SocialState {
         let Some(ai) = self.idx_of(a) else { return };
         let Some(bi) = self.idx_of(b) else { return };
 
-        let base_delta = match kind {
-            SocialActionKind::DireBonjour => 0.04,
-            SocialActionKind::SmallTalk => 0.06,
-            SocialActionKind::Compliment => 0.10,
-            SocialActionKind::DemanderAide => 0.05,
-            SocialActionKind::Blague => 0.07,
-            SocialActionKind::Ragot => -0.02,
-            SocialActionKind::SExcuser => 0.09,
-            SocialActionKind::Menacer => -0.15,
-            SocialActionKind::Insulter => -0.18,
-            SocialActionKind::SEngueuler => -0.22,
-        };
+        let base_delta = kind.affinity_delta();
 
         let soc = get_skill01(pawns, a, SkillBar::Sociabilite);
         let emp = get_trait01(pawns, a, TraitBar::Empathie);
@@ -1212,18 +1201,8 @@ impl SocialState {
         self.idx.get(&key).copied()
     }
 
-    fn rng_next_u64(&mut self) -> u64 {
-        let mut x = self.rng_state;
-        x ^= x << 13;
-        x ^= x >> 7;
-        x ^= x << 17;
-        self.rng_state = x;
-        x
-    }
-
     fn rand01(&mut self) -> f32 {
-        let v = (self.rng_next_u64() >> 40) as u32;
-        (v as f32) / (u32::MAX as f32 + 1.0)
+        self.rng.next_f32()
     }
 
     fn roll(&mut self, chance: f32) -> bool {
@@ -1235,8 +1214,7 @@ impl SocialState {
             return min_incl;
         }
         let span = (max_excl - min_incl) as u32;
-        let v = (self.rng_next_u64() as u32) % span;
-        min_incl + v as usize
+        min_incl + self.rng.roll_range(span) as usize
     }
 
     fn choose_weighted<const N: usize>(
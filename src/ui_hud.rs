@@ -1,5 +1,14 @@
 use super::*;
-use crate::gestion::{EmployeeId, EmployeeRole, SimCommand};
+use crate::gestion::carnet::FULFILMENT_WINDOWS_H;
+use crate::gestion::compta::{CashActivity, PeriodReport};
+use crate::gestion::financement::LoanId;
+use crate::gestion::personnel::next_work_priority;
+use crate::gestion::recrutement::CandidateId;
+use crate::gestion::stock::{ReorderPolicy, StockItemKind};
+use crate::gestion::{
+    ContractId, EmployeeId, EmployeeRole, ProductionLineId, ProductionLineState, SimCommand,
+    Supplier, WorkType,
+};
 use crate::rendu::theme::{feedback_theme, ui_theme};
 use crate::sim::{BlockKind, BuildFloorKind, ZoneKind};
 use std::cell::RefCell;
@@ -138,7 +147,7 @@ const BUILD_MENU_BLOCKS: [BuildMenuEntry; 14] = [
     },
 ];
 
-const BUILD_MENU_ZONES: [BuildMenuEntry; 4] = [
+const BUILD_MENU_ZONES: [BuildMenuEntry; 6] = [
     BuildMenuEntry {
         selection: BuildMenuSelection::Zone(ZoneKind::Receiving),
         label: "Zone stockage",
@@ -163,6 +172,18 @@ const BUILD_MENU_ZONES: [BuildMenuEntry; 4] = [
         description: "Zone vente activee avec bureau + responsable.",
         hint: "Zone",
     },
+    BuildMenuEntry {
+        selection: BuildMenuSelection::Zone(ZoneKind::BreakRoom),
+        label: "Salle de pause",
+        description: "Le personnel y mange et discute pendant ses pauses.",
+        hint: "Pause",
+    },
+    BuildMenuEntry {
+        selection: BuildMenuSelection::Zone(ZoneKind::Vending),
+        label: "Coin distributeurs",
+        description: "Boissons pour le personnel en pause.",
+        hint: "Pause",
+    },
 ];
 
 const BUILD_MENU_FLOORS: [BuildMenuEntry; 5] = [
@@ -275,18 +296,22 @@ impl HudInfoTab {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GestionTab {
     Personnel,
+    Recrutement,
     Stock,
     Ligne,
     Ventes,
+    Finances,
 }
 
 impl GestionTab {
     fn label(self) -> &'static str {
         match self {
             GestionTab::Personnel => "PERSONNEL",
+            GestionTab::Recrutement => "RECRUTEMENT",
             GestionTab::Stock => "STOCKS",
             GestionTab::Ligne => "LIGNE",
             GestionTab::Ventes => "VENTES",
+            GestionTab::Finances => "FINANCES",
         }
     }
 }
@@ -332,6 +357,11 @@ pub struct HudUiState {
     pub info_window_open: bool,
     pub gestion_window_open: bool,
     pub gestion_tab: GestionTab,
+    pub gestion_selected_line: ProductionLineId,
+    // Index dans le catalogue fournisseurs du panneau Stocks.
+    pub gestion_selected_supplier: usize,
+    // Premiere ligne affichee dans le tableau de comparaison (molette).
+    pub gestion_line_scroll: usize,
 }
 
 impl Default for HudUiState {
@@ -348,6 +378,9 @@ impl Default for HudUiState {
             info_window_open: false,
             gestion_window_open: false,
             gestion_tab: GestionTab::Personnel,
+            gestion_selected_line: sim::MAIN_PRODUCTION_LINE_ID,
+            gestion_selected_supplier: 0,
+            gestion_line_scroll: 0,
         }
     }
 }
@@ -514,6 +547,9 @@ pub fn process_hud_input(
 
         if wheel_y.abs() > f32::EPSILON {
             out.consumed_wheel = true;
+            if over_modal && state.hud_ui.gestion_tab == GestionTab::Ligne {
+                process_gestion_line_table_wheel(state, wheel_y);
+            }
         }
 
         if left_click {
@@ -688,6 +724,7 @@ pub fn draw_hud(
     telephone::draw_telephone_panel(state, layout.telephone_panel, mouse, time);
     draw_minimap_panel(state, layout.minimap_panel, mouse, map_view, world_camera);
     draw_footer_strip(state, layout.footer_strip_rect, mouse);
+    draw_bankruptcy_banner(state);
     draw_info_window(state, mouse);
     draw_gestion_window(state, mouse);
     draw_build_menu(state, mouse);
@@ -700,6 +737,32 @@ pub fn draw_hud(
     }
 }
 
+// Fin de partie: bandeau fixe tant que l'usine est en faillite.
+fn draw_bankruptcy_banner(state: &GameState) {
+    if !state.sim.finance().is_bankrupt() {
+        return;
+    }
+    let w = (screen_width() * 0.5).clamp(420.0, 720.0);
+    let rect = Rect::new((screen_width() - w) * 0.5, screen_height() * 0.32, w, 86.0);
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, rgba(54, 10, 12, 232));
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, rgba(236, 96, 88, 220));
+    draw_text_shadowed(
+        "FAILLITE",
+        rect.x + 20.0,
+        rect.y + 36.0,
+        28.0,
+        rgba(255, 214, 206, 255),
+        rgba(0, 0, 0, 180),
+        ui_shadow_offset(28.0),
+    );
+    draw_gestion_small_line(
+        "Tresorerie restee hors decouvert autorise: la banque a mis fin a l'activite.",
+        rect.x + 20.0,
+        rect.y + 66.0,
+        true,
+    );
+}
+
 fn draw_bar_background(bar: Rect, _time: f32) {
     let ui = ui_theme();
 
@@ -1122,8 +1185,9 @@ fn draw_top_strip(state: &GameState, rect: Rect, mouse: Vec2) {
     let sold = state.sim.sold_total();
     let cadence = state.sim.throughput_per_hour();
     let otif = state.sim.otif();
+    let kwh_per_box = state.sim.kpi().energy_kwh_per_box;
 
-    let metrics = [
+    let metrics: [_; TOP_STRIP_METRIC_COUNT] = [
         (
             "TRÉSORERIE",
             format_money(cash),
@@ -1143,6 +1207,16 @@ fn draw_top_strip(state: &GameState, rect: Rect, mouse: Vec2) {
             HeaderIcon::Shield,
             ui.accent_green,
         ),
+        (
+            "ÉNERGIE",
+            if kwh_per_box > 0.0 {
+                format!("{kwh_per_box:.1} kWh/c")
+            } else {
+                format!("{:.0} kW", state.sim.energy().load_kw)
+            },
+            HeaderIcon::Pulse,
+            ui.accent_amber,
+        ),
     ];
 
     let layout = compute_top_strip_layout(rect, scale, metrics.len());
@@ -1156,6 +1230,8 @@ fn draw_top_strip(state: &GameState, rect: Rect, mouse: Vec2) {
     }
 }
 
+const TOP_STRIP_METRIC_COUNT: usize = 5;
+
 #[derive(Clone, Debug)]
 struct TopStripLayout {
     brand: Rect,
@@ -1446,7 +1522,7 @@ fn header_nav_cells(rect: Rect) -> [(HeaderNavAction, &'static str, &'static str
 
 fn process_top_strip_input(state: &mut GameState, rect: Rect, mouse: Vec2) -> bool {
     let scale = (rect.h / 64.0).clamp(0.84, 1.16);
-    let layout = compute_top_strip_layout(rect, scale, 4);
+    let layout = compute_top_strip_layout(rect, scale, TOP_STRIP_METRIC_COUNT);
 
     for (action, _, _, cell) in header_nav_cells(layout.nav) {
         if point_in_rect(mouse, cell) && action == HeaderNavAction::OpenGestion {
@@ -2632,12 +2708,20 @@ fn process_build_panel_input(state: &mut GameState, panel: Rect, mouse: Vec2) ->
                         role: EmployeeRole::AdministrateurVente,
                     })
                 }
-                GestionQuickAction::BuyRaw500 => state
-                    .sim
-                    .apply_command(SimCommand::BuyRawStock { qty: 500 }),
-                GestionQuickAction::BuyRaw1000 => state
-                    .sim
-                    .apply_command(SimCommand::BuyRawStock { qty: 1000 }),
+                GestionQuickAction::BuyRaw500 => {
+                    let supplier_id = state.sim.suppliers().default_supplier().id.clone();
+                    state.sim.apply_command(SimCommand::BuyRawStock {
+                        supplier_id,
+                        qty: 500,
+                    })
+                }
+                GestionQuickAction::BuyRaw1000 => {
+                    let supplier_id = state.sim.suppliers().default_supplier().id.clone();
+                    state.sim.apply_command(SimCommand::BuyRawStock {
+                        supplier_id,
+                        qty: 1000,
+                    })
+                }
                 GestionQuickAction::ToggleInterim => {
                     let (line_id, enabled) = {
                         let line = state.sim.main_production_line();
@@ -3815,7 +3899,44 @@ enum GestionAction {
     Fire(EmployeeId),
     AssignLead(EmployeeId),
     BuyRaw(u32),
-    SetInterim { enabled: bool, max_temps: u8 },
+    CycleSupplier,
+    SetRawReorder(bool),
+    BuySpareParts(u32),
+    SetInterim {
+        enabled: bool,
+        max_temps: u8,
+    },
+    SelectLine(ProductionLineId),
+    CycleWorkPriority {
+        employee_id: EmployeeId,
+        work: WorkType,
+        current: u8,
+    },
+    CycleEmployeeShift {
+        employee_id: EmployeeId,
+        next_slot: u8,
+    },
+    CycleLineShift(ProductionLineId),
+    RaiseWage(EmployeeId),
+    Train(EmployeeId),
+    ChangeRole {
+        employee_id: EmployeeId,
+        role: EmployeeRole,
+    },
+    ToggleShortlist {
+        candidate_id: CandidateId,
+        shortlisted: bool,
+    },
+    Interview(CandidateId),
+    OfferCandidate {
+        candidate_id: CandidateId,
+        wage_eur: u32,
+    },
+    AdjustSalePrice(i32),
+    AcceptContract(ContractId),
+    DeclineContract(ContractId),
+    TakeLoan(usize),
+    RepayLoan(LoanId),
     BootstrapFactory,
 }
 
@@ -3837,13 +3958,15 @@ fn gestion_tab_rects(panel: Rect) -> Vec<(GestionTab, Rect)> {
     let y = panel.y + 34.0;
     let h = 28.0;
     let gap = 8.0;
-    let w = ((panel.w - pad * 2.0 - gap * 3.0) / 4.0).max(90.0);
+    let w = ((panel.w - pad * 2.0 - gap * 5.0) / 6.0).max(90.0);
 
     [
         GestionTab::Personnel,
+        GestionTab::Recrutement,
         GestionTab::Stock,
         GestionTab::Ligne,
         GestionTab::Ventes,
+        GestionTab::Finances,
     ]
     .into_iter()
     .enumerate()
@@ -3887,21 +4010,78 @@ fn process_gestion_window_input(state: &mut GameState, panel: Rect, mouse: Vec2)
         }
 
         let result = match action {
-            GestionAction::Hire(role) => state.sim.apply_command(SimCommand::HireEmployee { role }),
+            // Les embauches passent par le tableau de recrutement.
+            GestionAction::Hire(role) => {
+                state.hud_ui.gestion_tab = GestionTab::Recrutement;
+                let count = state
+                    .sim
+                    .recruitment()
+                    .candidates
+                    .iter()
+                    .filter(|candidate| candidate.role == role)
+                    .count();
+                Ok(format!(
+                    "Tableau de recrutement: {count} candidat(s) {}",
+                    role.label()
+                ))
+            }
             GestionAction::Fire(employee_id) => state
                 .sim
                 .apply_command(SimCommand::FireEmployee { employee_id }),
             GestionAction::AssignLead(employee_id) => {
-                let line_id = state.sim.main_production_line().id;
+                let line_id = gestion_selected_line(state).id;
 
                 state.sim.apply_command(SimCommand::AssignEmployeeToLine {
                     employee_id,
                     line_id,
                 })
             }
-            GestionAction::BuyRaw(qty) => state.sim.apply_command(SimCommand::BuyRawStock { qty }),
+            GestionAction::BuyRaw(qty) => {
+                let supplier_id = gestion_selected_supplier(state).id.clone();
+                state
+                    .sim
+                    .apply_command(SimCommand::BuyRawStock { supplier_id, qty })
+            }
+            GestionAction::CycleSupplier => {
+                let count = state.sim.suppliers().suppliers.len().max(1);
+                state.hud_ui.gestion_selected_supplier =
+                    (state.hud_ui.gestion_selected_supplier + 1) % count;
+                Ok(format!(
+                    "Fournisseur: {}",
+                    gestion_selected_supplier(state).label
+                ))
+            }
+            GestionAction::SetRawReorder(enabled) => {
+                // Activation avec le fournisseur selectionne; la desactivation garde les seuils.
+                let selected_id = gestion_selected_supplier(state).id.clone();
+                let policy = match state
+                    .sim
+                    .stock()
+                    .reorder_policy(StockItemKind::MatierePremiere)
+                {
+                    Some(policy) => ReorderPolicy {
+                        enabled,
+                        supplier_id: if enabled {
+                            selected_id
+                        } else {
+                            policy.supplier_id.clone()
+                        },
+                        ..policy.clone()
+                    },
+                    None => ReorderPolicy {
+                        enabled,
+                        ..ReorderPolicy::raw_default(&selected_id)
+                    },
+                };
+                state
+                    .sim
+                    .apply_command(SimCommand::SetReorderPolicy { policy })
+            }
+            GestionAction::BuySpareParts(qty) => {
+                state.sim.apply_command(SimCommand::BuySpareParts { qty })
+            }
             GestionAction::SetInterim { enabled, max_temps } => {
-                let line_id = state.sim.main_production_line().id;
+                let line_id = gestion_selected_line(state).id;
 
                 state.sim.apply_command(SimCommand::SetLineTempPolicy {
                     line_id,
@@ -3909,6 +4089,108 @@ fn process_gestion_window_input(state: &mut GameState, panel: Rect, mouse: Vec2)
                     max_temps,
                 })
             }
+            GestionAction::SelectLine(line_id) => {
+                state.hud_ui.gestion_selected_line = line_id;
+                Ok(format!("Ligne {line_id} selectionnee"))
+            }
+            GestionAction::CycleWorkPriority {
+                employee_id,
+                work,
+                current,
+            } => state.sim.apply_command(SimCommand::SetWorkPriority {
+                employee_id,
+                work,
+                priority: next_work_priority(current),
+            }),
+            GestionAction::CycleEmployeeShift {
+                employee_id,
+                next_slot,
+            } => state.sim.apply_command(SimCommand::SetEmployeeShift {
+                employee_id,
+                slot: next_slot,
+            }),
+            GestionAction::RaiseWage(employee_id) => {
+                let hourly_wage = state
+                    .sim
+                    .personnel()
+                    .employee(employee_id)
+                    .map_or(0.0, |employee| employee.hourly_wage + 1.0);
+
+                state.sim.apply_command(SimCommand::SetEmployeeWage {
+                    employee_id,
+                    hourly_wage,
+                })
+            }
+            GestionAction::Train(employee_id) => {
+                let course_id = state
+                    .sim
+                    .personnel()
+                    .employee(employee_id)
+                    .and_then(|employee| state.sim.training_book().suggested_course(employee))
+                    .map(|course| course.id.clone());
+
+                match course_id {
+                    Some(course_id) => state.sim.apply_command(SimCommand::ScheduleTraining {
+                        employee_id,
+                        course_id,
+                    }),
+                    None => Err(format!("aucune formation au catalogue pour #{employee_id}")),
+                }
+            }
+            GestionAction::ToggleShortlist { candidate_id, .. } => state
+                .sim
+                .apply_command(SimCommand::ShortlistCandidate { candidate_id }),
+            GestionAction::Interview(candidate_id) => state
+                .sim
+                .apply_command(SimCommand::InterviewCandidate { candidate_id }),
+            GestionAction::OfferCandidate {
+                candidate_id,
+                wage_eur,
+            } => state.sim.apply_command(SimCommand::OfferCandidate {
+                candidate_id,
+                hourly_wage: f64::from(wage_eur),
+            }),
+            GestionAction::ChangeRole { employee_id, role } => state
+                .sim
+                .apply_command(SimCommand::ChangeEmployeeRole { employee_id, role }),
+            GestionAction::CycleLineShift(line_id) => {
+                let current = state
+                    .sim
+                    .production_line(line_id)
+                    .map(|line| line.shift_template.clone())
+                    .unwrap_or_default();
+                let template_id = state.sim.shifts().next_template_id(&current);
+
+                state.sim.apply_command(SimCommand::SetLineShiftTemplate {
+                    line_id,
+                    template_id,
+                })
+            }
+            GestionAction::AdjustSalePrice(delta_eur) => {
+                let price_eur = state.sim.sale_price_eur() + f64::from(delta_eur);
+
+                state
+                    .sim
+                    .apply_command(SimCommand::SetSalePrice { price_eur })
+            }
+            GestionAction::AcceptContract(contract_id) => state
+                .sim
+                .apply_command(SimCommand::AcceptContract { contract_id }),
+            GestionAction::DeclineContract(contract_id) => state
+                .sim
+                .apply_command(SimCommand::DeclineContract { contract_id }),
+            GestionAction::TakeLoan(offer_index) => {
+                match state.sim.finance_book().loan_offers.get(offer_index) {
+                    Some(offer) => {
+                        let offer_id = offer.id.clone();
+                        state.sim.apply_command(SimCommand::TakeLoan { offer_id })
+                    }
+                    None => Err(format!("offre de pret inconnue: #{offer_index}")),
+                }
+            }
+            GestionAction::RepayLoan(loan_id) => {
+                state.sim.apply_command(SimCommand::RepayLoan { loan_id })
+            }
             GestionAction::BootstrapFactory => state.sim.bootstrap_functional_factory(),
         };
 
@@ -3935,28 +4217,61 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
         GestionTab::Personnel => {
             let mut y = body.y + 12.0;
 
-            for role in [
-                EmployeeRole::ChefEquipe,
-                EmployeeRole::Cariste,
-                EmployeeRole::AdministrateurVente,
+            for roles in [
+                [EmployeeRole::ChefEquipe, EmployeeRole::Cariste],
+                [
+                    EmployeeRole::AdministrateurVente,
+                    EmployeeRole::ControleurQualite,
+                ],
+                [EmployeeRole::AgentEntretien, EmployeeRole::ResponsableHse],
             ] {
-                out.push((
-                    GestionAction::Hire(role),
-                    Rect::new(x_right, y, button_w, button_h),
-                ));
+                for (col, role) in roles.into_iter().enumerate() {
+                    out.push((
+                        GestionAction::Hire(role),
+                        Rect::new(
+                            x_right - (1 - col) as f32 * (button_w + gap),
+                            y,
+                            button_w,
+                            button_h,
+                        ),
+                    ));
+                }
 
                 y += button_h + gap;
             }
 
-            y += 14.0;
+            y += 44.0;
+
+            let slot_count = state
+                .sim
+                .shifts()
+                .template_or_default(&state.sim.main_production_line().shift_template)
+                .slots
+                .len();
 
             for employee in &state.sim.personnel().employees {
                 let row_y = y;
 
+                let x_left = x_right - button_w - gap;
                 if employee.role == EmployeeRole::ChefEquipe {
                     out.push((
                         GestionAction::AssignLead(employee.id),
-                        Rect::new(x_right - button_w - gap, row_y, button_w, button_h),
+                        Rect::new(x_left, row_y, button_w, button_h),
+                    ));
+                } else if let Some(role) = employee
+                    .certifications
+                    .iter()
+                    .map(|certification| certification.unlocks())
+                    .find(|role| *role != employee.role)
+                    && employee.role != EmployeeRole::Patron
+                    && employee.role != EmployeeRole::Interimaire
+                {
+                    out.push((
+                        GestionAction::ChangeRole {
+                            employee_id: employee.id,
+                            role,
+                        },
+                        Rect::new(x_left, row_y, button_w, button_h),
                     ));
                 }
 
@@ -3967,20 +4282,102 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
                         GestionAction::Fire(employee.id),
                         Rect::new(x_right, row_y, button_w, button_h),
                     ));
+                    out.push((
+                        GestionAction::RaiseWage(employee.id),
+                        Rect::new(x_right, row_y + button_h + 4.0, button_w, button_h),
+                    ));
+                    if employee.training.is_none() {
+                        out.push((
+                            GestionAction::Train(employee.id),
+                            Rect::new(x_left, row_y + button_h + 4.0, button_w, button_h),
+                        ));
+                    }
                 }
 
-                y += 42.0;
+                let mut work_x = body.x + 30.0;
+                for work in WorkType::ALL {
+                    out.push((
+                        GestionAction::CycleWorkPriority {
+                            employee_id: employee.id,
+                            work,
+                            current: employee.work_priority(work),
+                        },
+                        Rect::new(work_x, row_y + 6.0, GESTION_WORK_BUTTON_W, 18.0),
+                    ));
+                    work_x += GESTION_WORK_BUTTON_W + 4.0;
+                }
+
+                if employee.follows_own_shift() && slot_count > 1 {
+                    out.push((
+                        GestionAction::CycleEmployeeShift {
+                            employee_id: employee.id,
+                            next_slot: ((employee.shift_slot as usize + 1) % slot_count) as u8,
+                        },
+                        Rect::new(work_x, row_y + 6.0, GESTION_WORK_BUTTON_W, 18.0),
+                    ));
+                }
+
+                y += GESTION_EMPLOYEE_ROW_H;
 
                 if y > body.y + body.h - button_h - 4.0 {
                     break;
                 }
             }
         }
+        GestionTab::Recrutement => {
+            let offer_w = ((button_w - gap) * 0.5).floor();
+            for (idx, candidate) in state.sim.recruitment().candidates.iter().enumerate() {
+                let row_y = gestion_candidate_row_top(body, idx);
+                if row_y + GESTION_CANDIDATE_ROW_H > body.y + body.h {
+                    break;
+                }
+                out.push((
+                    GestionAction::ToggleShortlist {
+                        candidate_id: candidate.id,
+                        shortlisted: candidate.shortlisted,
+                    },
+                    Rect::new(x_right, row_y + 2.0, button_w, button_h),
+                ));
+                if !candidate.shortlisted {
+                    continue;
+                }
+                if !candidate.interviewed {
+                    out.push((
+                        GestionAction::Interview(candidate.id),
+                        Rect::new(x_right, row_y + button_h + 8.0, button_w, button_h),
+                    ));
+                }
+                let asking = candidate.asking_wage.round() as u32;
+                for (col, wage_eur) in [asking, (candidate.asking_wage * 1.1).round() as u32]
+                    .into_iter()
+                    .enumerate()
+                {
+                    out.push((
+                        GestionAction::OfferCandidate {
+                            candidate_id: candidate.id,
+                            wage_eur,
+                        },
+                        Rect::new(
+                            x_right - button_w - gap + col as f32 * (offer_w + gap),
+                            row_y + 2.0,
+                            offer_w,
+                            button_h,
+                        ),
+                    ));
+                }
+            }
+        }
         GestionTab::Stock => {
             let y = body.y + 38.0;
+            let supplier = gestion_selected_supplier(state);
+
+            out.push((
+                GestionAction::CycleSupplier,
+                Rect::new(x_right, y - button_h - gap, button_w, button_h),
+            ));
 
             out.push((
-                GestionAction::BuyRaw(100),
+                GestionAction::BuyRaw(supplier.moq.min(500)),
                 Rect::new(x_right, y, button_w, button_h),
             ));
 
@@ -3994,6 +4391,26 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
                 Rect::new(x_right, y + (button_h + gap) * 2.0, button_w, button_h),
             ));
 
+            out.push((
+                GestionAction::BuySpareParts(4),
+                Rect::new(x_right, y + (button_h + gap) * 3.0, button_w, button_h),
+            ));
+
+            out.push((
+                GestionAction::Hire(EmployeeRole::Technicien),
+                Rect::new(x_right, y + (button_h + gap) * 4.0, button_w, button_h),
+            ));
+
+            let reorder_enabled = state
+                .sim
+                .stock()
+                .reorder_policy(StockItemKind::MatierePremiere)
+                .is_some_and(|policy| policy.enabled);
+            out.push((
+                GestionAction::SetRawReorder(!reorder_enabled),
+                Rect::new(x_right, y + (button_h + gap) * 5.0, button_w, button_h),
+            ));
+
             out.push((
                 GestionAction::BootstrapFactory,
                 Rect::new(
@@ -4005,7 +4422,18 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
             ));
         }
         GestionTab::Ligne => {
-            let mut y = body.y + 86.0;
+            let mut y = body.y + GESTION_LINE_TABLE_TOP;
+
+            for line in gestion_visible_lines(state) {
+                out.push((
+                    GestionAction::SelectLine(line.id),
+                    Rect::new(x_right, y - 18.0, button_w, button_h - 4.0),
+                ));
+
+                y += GESTION_LINE_TABLE_ROW_H;
+            }
+
+            let mut y = gestion_ligne_detail_top(state, body) + 56.0;
 
             for employee in &state.sim.personnel().employees {
                 if employee.role == EmployeeRole::ChefEquipe {
@@ -4018,7 +4446,7 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
                 }
             }
 
-            let line = state.sim.main_production_line();
+            let line = gestion_selected_line(state);
 
             let policy_enabled = line
                 .assigned_lead_id
@@ -4035,6 +4463,11 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
 
             let y2 = body.y + body.h - button_h * 2.0 - gap - 12.0;
 
+            out.push((
+                GestionAction::CycleLineShift(line.id),
+                Rect::new(x_right, y2 - button_h - gap, button_w, button_h),
+            ));
+
             out.push((
                 GestionAction::SetInterim {
                     enabled: !policy_enabled,
@@ -4066,6 +4499,34 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
                 Rect::new(x_right, y, button_w, button_h),
             ));
 
+            out.push((
+                GestionAction::AdjustSalePrice(20),
+                Rect::new(x_right, y + button_h + gap, button_w, button_h),
+            ));
+
+            out.push((
+                GestionAction::AdjustSalePrice(-20),
+                Rect::new(x_right, y + (button_h + gap) * 2.0, button_w, button_h),
+            ));
+
+            let accept_w = ((button_w - gap) * 0.6).floor();
+            for (idx, offer) in state.sim.market().offers().enumerate() {
+                let offer_y = y + (button_h + gap) * (3.5 + idx as f32);
+                out.push((
+                    GestionAction::AcceptContract(offer.id),
+                    Rect::new(x_right, offer_y, accept_w, button_h),
+                ));
+                out.push((
+                    GestionAction::DeclineContract(offer.id),
+                    Rect::new(
+                        x_right + accept_w + gap,
+                        offer_y,
+                        button_w - accept_w - gap,
+                        button_h,
+                    ),
+                ));
+            }
+
             out.push((
                 GestionAction::BootstrapFactory,
                 Rect::new(
@@ -4076,6 +4537,21 @@ fn gestion_action_rects(state: &GameState, panel: Rect) -> Vec<(GestionAction, R
                 ),
             ));
         }
+        GestionTab::Finances => {
+            let offers = state.sim.finance_book().loan_offers.len();
+            for offer_index in 0..offers {
+                out.push((
+                    GestionAction::TakeLoan(offer_index),
+                    gestion_loan_button_rect(body, offer_index),
+                ));
+            }
+            for (idx, loan) in state.sim.finance().loans.iter().enumerate() {
+                out.push((
+                    GestionAction::RepayLoan(loan.id),
+                    gestion_loan_button_rect(body, offers + idx),
+                ));
+            }
+        }
     }
 
     out
@@ -4087,6 +4563,11 @@ fn gestion_action_label(action: GestionAction) -> String {
         GestionAction::Fire(id) => format!("Licencier #{id}"),
         GestionAction::AssignLead(id) => format!("Assigner chef #{id}"),
         GestionAction::BuyRaw(qty) => format!("Acheter {qty}"),
+        GestionAction::CycleSupplier => "Fournisseur suivant".to_string(),
+        GestionAction::SetRawReorder(enabled) => {
+            format!("Reappro auto {}", if enabled { "ON" } else { "OFF" })
+        }
+        GestionAction::BuySpareParts(qty) => format!("Pieces x{qty}"),
         GestionAction::SetInterim { enabled, max_temps } => {
             if enabled {
                 format!("Interim ON max {max_temps}")
@@ -4094,6 +4575,35 @@ fn gestion_action_label(action: GestionAction) -> String {
                 "Interim OFF".to_string()
             }
         }
+        GestionAction::SelectLine(id) => format!("Piloter L{id}"),
+        GestionAction::CycleWorkPriority { work, current, .. } => {
+            if current == 0 {
+                format!("{} -", work.short_label())
+            } else {
+                format!("{} {current}", work.short_label())
+            }
+        }
+        GestionAction::CycleEmployeeShift { next_slot, .. } => {
+            format!("Poste > {}", next_slot.saturating_add(1))
+        }
+        GestionAction::CycleLineShift(_) => "Changer horaires".to_string(),
+        GestionAction::RaiseWage(id) => format!("Augmenter #{id} +1 EUR/h"),
+        GestionAction::Train(id) => format!("Former #{id}"),
+        GestionAction::ChangeRole { role, .. } => format!("Passer {}", role.label()),
+        GestionAction::ToggleShortlist { shortlisted, .. } => {
+            if shortlisted {
+                "Ecarter".to_string()
+            } else {
+                "Retenir".to_string()
+            }
+        }
+        GestionAction::Interview(_) => "Entretien".to_string(),
+        GestionAction::OfferCandidate { wage_eur, .. } => format!("Offre {wage_eur}/h"),
+        GestionAction::AdjustSalePrice(delta_eur) => format!("Prix {delta_eur:+} EUR"),
+        GestionAction::AcceptContract(id) => format!("Accepter #{id}"),
+        GestionAction::TakeLoan(_) => "Emprunter".to_string(),
+        GestionAction::RepayLoan(id) => format!("Rembourser #{id}"),
+        GestionAction::DeclineContract(_) => "Refuser".to_string(),
         GestionAction::BootstrapFactory => "Bootstrap test".to_string(),
     }
 }
@@ -4141,9 +4651,11 @@ fn draw_gestion_window(state: &GameState, mouse: Vec2) {
 
     match state.hud_ui.gestion_tab {
         GestionTab::Personnel => draw_gestion_personnel_tab(state, body),
+        GestionTab::Recrutement => draw_gestion_recrutement_tab(state, body),
         GestionTab::Stock => draw_gestion_stock_tab(state, body),
         GestionTab::Ligne => draw_gestion_ligne_tab(state, body),
         GestionTab::Ventes => draw_gestion_ventes_tab(state, body),
+        GestionTab::Finances => draw_gestion_finances_tab(state, body),
     }
 
     for (action, rect) in gestion_action_rects(state, panel) {
@@ -4180,6 +4692,10 @@ fn draw_gestion_small_line(text: &str, x: f32, y: f32, strong: bool) {
     );
 }
 
+// Chaque ligne employe: identite, activite IA, matrice de travail cliquable, besoins et competences.
+const GESTION_EMPLOYEE_ROW_H: f32 = 90.0;
+const GESTION_WORK_BUTTON_W: f32 = 62.0;
+
 fn draw_gestion_personnel_tab(state: &GameState, body: Rect) {
     let personnel = state.sim.personnel();
     let line = state.sim.main_production_line();
@@ -4191,26 +4707,82 @@ fn draw_gestion_personnel_tab(state: &GameState, body: Rect) {
     y += 26.0;
 
     let summary = format!(
-        "Chefs {} | Caristes {} | Admins vente {} | Interimaires {} | Paie {}/h",
+        "Chefs {} | Caristes {} | Techniciens {} | Interimaires {} | Paie {}/h",
         personnel.count_role(EmployeeRole::ChefEquipe),
         personnel.count_role(EmployeeRole::Cariste),
-        personnel.count_role(EmployeeRole::AdministrateurVente),
+        personnel.count_role(EmployeeRole::Technicien),
         personnel.count_role(EmployeeRole::Interimaire),
         format_money(state.sim.payroll_per_hour())
     );
 
     draw_gestion_small_line(&summary, x, y, true);
 
+    y += 18.0;
+
+    draw_gestion_small_line(
+        &format!(
+            "Admins vente {} | Controleurs qualite {} | Entretien {} | HSE {}",
+            personnel.count_role(EmployeeRole::AdministrateurVente),
+            personnel.count_role(EmployeeRole::ControleurQualite),
+            personnel.count_role(EmployeeRole::AgentEntretien),
+            personnel.count_role(EmployeeRole::ResponsableHse),
+        ),
+        x,
+        y,
+        true,
+    );
+
     y += 22.0;
 
+    let staff = personnel
+        .employees
+        .iter()
+        .filter(|employee| employee.role != EmployeeRole::Patron)
+        .map(|employee| employee.morale as f64)
+        .collect::<Vec<_>>();
+    let mean_morale = staff.iter().sum::<f64>() / staff.len().max(1) as f64;
+    draw_gestion_small_line(
+        &format!(
+            "Moral moyen {mean_morale:.0} | {}{}",
+            personnel.turnover.summary(),
+            personnel
+                .turnover
+                .recent
+                .last()
+                .map(|event| format!(" | {event}"))
+                .unwrap_or_default()
+        ),
+        x,
+        y,
+        false,
+    );
+
+    y += 16.0;
+
+    let hse = state.sim.hse();
+    let prevention = if personnel.active_role_count(EmployeeRole::ResponsableHse) > 0 {
+        format!(
+            "prevention HSE x{:.1}",
+            state.sim.hse_book().prevention_factor
+        )
+    } else {
+        "sans prevention HSE".to_string()
+    };
     draw_gestion_small_line(
-        "Les employes travaillent seuls: chefs supervisent, caristes alimentent, admins vendent.",
+        &format!(
+            "{} | {prevention}{}",
+            hse.summary(),
+            hse.recent
+                .last()
+                .map(|event| format!(" | {event}"))
+                .unwrap_or_default()
+        ),
         x,
         y,
         false,
     );
 
-    y += 36.0;
+    y += 32.0;
 
     for employee in &personnel.employees {
         if y > body.y + body.h - 34.0 {
@@ -4237,31 +4809,135 @@ fn draw_gestion_personnel_tab(state: &GameState, body: Rect) {
             String::new()
         };
 
+        let shift = if employee.role == EmployeeRole::Patron {
+            "libre".to_string()
+        } else if employee.follows_own_shift() {
+            state
+                .sim
+                .shifts()
+                .template_or_default(&line.shift_template)
+                .slot(employee.shift_slot)
+                .span_label()
+        } else {
+            "horaires ligne".to_string()
+        };
+
+        let morale = if employee.role == EmployeeRole::Patron {
+            String::new()
+        } else {
+            let track = &employee.morale_track;
+            let grievance = track
+                .factors
+                .main_grievance()
+                .map(|grievance| format!(", grief {grievance}"))
+                .unwrap_or_default();
+            let notice = track
+                .notice_until_s
+                .map(|until_s| format!(" | PREAVIS jusqu'a J{}", (until_s / 86_400.0) as u64))
+                .unwrap_or_default();
+            format!(" | moral {:.0}{grievance}{notice}", employee.morale)
+        };
+
         let row = format!(
-            "#{} {} | {} | {} | {} EUR/h{}",
+            "#{} {} | {} | {} | {} | {} EUR/h{}{}",
             employee.id,
             employee.name,
             employee.role.label(),
             assigned,
+            shift,
             employee.hourly_wage.round(),
-            contract
+            contract,
+            morale
         );
 
         draw_gestion_small_line(&row, x, y, true);
 
+        let agent = state
+            .sim
+            .agent_for_employee(employee.id)
+            .map(|agent| match agent.current_job {
+                Some(job_id) => format!(
+                    " | tuile ({}, {}) job #{job_id} | fatigue {:.0}",
+                    agent.tile.0, agent.tile.1, agent.fatigue
+                ),
+                None => format!(
+                    " | tuile ({}, {}) libre | fatigue {:.0}",
+                    agent.tile.0, agent.tile.1, agent.fatigue
+                ),
+            })
+            .unwrap_or_default();
+
         draw_gestion_small_line(
             &format!(
-                "IA: {} | etat {} | score {:.0}",
+                "IA: {} | etat {} | score {:.0}{}",
                 employee.task_label,
                 employee.status.label(),
-                employee.ai_score
+                employee.ai_score,
+                agent
             ),
             x + 14.0,
             y + 16.0,
             false,
         );
 
-        y += 42.0;
+        let needs_book = state.sim.needs_book();
+        let deficit = employee.needs.deficit(needs_book);
+        draw_gestion_small_line(
+            &format!(
+                "Besoins: {} | rendement {:.0}% | erreurs +{:.1}% | pauses {}",
+                employee.needs.summary(),
+                needs_book.speed_factor(deficit) * 100.0,
+                needs_book.defect(deficit) * 100.0,
+                employee.needs.breaks_total
+            ),
+            x + 14.0,
+            y + 56.0,
+            false,
+        );
+
+        let certifications = employee
+            .certifications
+            .iter()
+            .map(|certification| certification.label())
+            .collect::<Vec<_>>();
+        let training = match &employee.training {
+            Some(session) => format!(" | en formation, reste {:.1}h", session.remaining_h),
+            None if matches!(
+                employee.role,
+                EmployeeRole::Patron | EmployeeRole::Interimaire
+            ) =>
+            {
+                String::new()
+            }
+            None => state
+                .sim
+                .training_book()
+                .suggested_course(employee)
+                .map(|course| {
+                    format!(
+                        " | conseil: {} ({:.0}h, {:.0} EUR)",
+                        course.label, course.duration_h, course.cost_eur
+                    )
+                })
+                .unwrap_or_default(),
+        };
+        draw_gestion_small_line(
+            &format!(
+                "Competences: {} | certifs {}{}",
+                employee.skills.summary(),
+                if certifications.is_empty() {
+                    "-".to_string()
+                } else {
+                    certifications.join(", ")
+                },
+                training
+            ),
+            x + 14.0,
+            y + 72.0,
+            false,
+        );
+
+        y += GESTION_EMPLOYEE_ROW_H;
     }
 
     if line.assigned_lead_id.is_none() {
@@ -4274,6 +4950,153 @@ fn draw_gestion_personnel_tab(state: &GameState, body: Rect) {
     }
 }
 
+// Chaque candidat: allure, demande et dispo, competences du CV, traits (caches avant entretien).
+const GESTION_CANDIDATE_ROW_H: f32 = 74.0;
+
+fn gestion_candidate_row_top(body: Rect, idx: usize) -> f32 {
+    body.y + 68.0 + idx as f32 * GESTION_CANDIDATE_ROW_H
+}
+
+fn draw_gestion_recrutement_tab(state: &GameState, body: Rect) {
+    let board = state.sim.recruitment();
+    let book = state.sim.recruitment_book();
+    let now_s = state.sim.clock.seconds();
+
+    let x = body.x + 16.0;
+    draw_gestion_section_title("Tableau de recrutement", x, body.y + 28.0);
+
+    let pending = board
+        .pending
+        .iter()
+        .map(|pending| {
+            format!(
+                "{} ({}) J{}",
+                pending.candidate.name,
+                pending.candidate.role.label(),
+                (pending.starts_at_s / 86_400.0) as u64
+            )
+        })
+        .collect::<Vec<_>>();
+    draw_gestion_small_line(
+        &format!(
+            "Candidats {}/{} | prochain profil dans {:.0}h | entretien {} | arrivees: {}",
+            board.candidates.len(),
+            book.board_size,
+            ((board.next_arrival_s - now_s) / 3600.0).max(0.0),
+            format_money(book.interview_cost_eur),
+            if pending.is_empty() {
+                "-".to_string()
+            } else {
+                pending.join(", ")
+            }
+        ),
+        x,
+        body.y + 50.0,
+        true,
+    );
+
+    if board.candidates.is_empty() {
+        draw_gestion_small_line(
+            "Aucun candidat pour le moment: de nouveaux profils arrivent au fil des heures.",
+            x,
+            body.y + 84.0,
+            false,
+        );
+    }
+
+    let text_x = x + 54.0;
+    for (idx, candidate) in board.candidates.iter().enumerate() {
+        let top = gestion_candidate_row_top(body, idx);
+        if top + GESTION_CANDIDATE_ROW_H > body.y + body.h {
+            draw_gestion_small_line(
+                "Liste tronquee: agrandis la fenetre pour voir tous les candidats.",
+                x,
+                top + 14.0,
+                false,
+            );
+            break;
+        }
+
+        let record = state
+            .character_catalog
+            .spawn_founder(&candidate.name, candidate.seed);
+        draw_character(
+            &record,
+            CharacterRenderParams {
+                center: vec2(x + 20.0, top + 44.0),
+                scale: 0.62,
+                presentation: crate::character::CharacterPresentation::Portrait,
+                facing: CharacterFacing::Front,
+                facing_left: false,
+                is_walking: false,
+                walk_cycle: 0.0,
+                gesture: CharacterGesture::None,
+                time: 0.0,
+                debug: false,
+            },
+        );
+
+        let status = match (candidate.shortlisted, candidate.interviewed) {
+            (true, true) => " | retenu, vu en entretien",
+            (true, false) => " | retenu",
+            _ => "",
+        };
+        draw_gestion_small_line(
+            &format!(
+                "#{} {} | {} | demande {:.0} EUR/h | dispo J+{} | part dans {:.0}h{}",
+                candidate.id,
+                candidate.name,
+                candidate.role.label(),
+                candidate.asking_wage,
+                candidate.availability_days,
+                ((candidate.expires_at_s - now_s) / 3600.0).max(0.0),
+                status
+            ),
+            text_x,
+            top + 14.0,
+            true,
+        );
+        draw_gestion_small_line(&candidate.portrait, text_x, top + 30.0, false);
+        draw_gestion_small_line(
+            &format!("CV: {}", candidate.skills_summary()),
+            text_x,
+            top + 46.0,
+            false,
+        );
+        draw_gestion_small_line(
+            &format!("Traits: {}", candidate.traits_summary()),
+            text_x,
+            top + 62.0,
+            false,
+        );
+    }
+}
+
+fn gestion_last_box_trace_row(lots: &crate::gestion::LotBook) -> String {
+    let Some(trace) = lots.last_box() else {
+        return format!("Tracabilite: {} lots, aucune box emballee", lots.lots.len());
+    };
+    let raw_lots = lots.raw_lots_of_box(trace.id);
+    let Some(first) = raw_lots.first() else {
+        return format!("Derniere box {}: lots bruts non traces", trace.id);
+    };
+    let labels = raw_lots.iter().map(|lot| lot.label()).collect::<Vec<_>>();
+    format!(
+        "Derniere box {}: lots bruts {} | rappel #{}: {} boxes",
+        trace.id,
+        labels.join(", "),
+        first.id,
+        lots.boxes_from_raw_lot(first.id).len()
+    )
+}
+
+fn gestion_selected_supplier(state: &GameState) -> &Supplier {
+    let suppliers = &state.sim.suppliers().suppliers;
+    suppliers
+        .get(state.hud_ui.gestion_selected_supplier)
+        .unwrap_or_else(|| state.sim.suppliers().default_supplier())
+}
+
 fn draw_gestion_stock_tab(state: &GameState, body: Rect) {
     let stock = state.sim.stock();
 
@@ -4291,7 +5114,23 @@ fn draw_gestion_stock_tab(state: &GameState, body: Rect) {
             crate::gestion::RAW_LINE_INPUT_CAPACITY
         ),
         format!("Commandes en attente: {} unites", stock.pending_raw_qty()),
+        format!(
+            "Fournisseur: {}",
+            gestion_selected_supplier(state).summary()
+        ),
+        format!(
+            "Pieces detachees: {} / {} (+{} en commande)",
+            stock.spare_parts,
+            crate::gestion::SPARE_PARTS_CAPACITY,
+            stock.pending_spare_parts_qty()
+        ),
+        format!(
+            "Pannes en cours: {} | techniciens {}",
+            state.sim.broken_machine_count(),
+            state.sim.personnel().count_role(EmployeeRole::Technicien)
+        ),
         format!("Cash disponible: {}", format_money(state.sim.cash())),
+        gestion_last_box_trace_row(state.sim.lots()),
     ];
 
     for row in rows {
@@ -4299,6 +5138,56 @@ fn draw_gestion_stock_tab(state: &GameState, body: Rect) {
         y += 24.0;
     }
 
+    for order in stock
+        .pending_orders
+        .iter()
+        .filter(|order| order.item_kind == StockItemKind::MatierePremiere)
+        .take(4)
+    {
+        let eta = if order.is_late() {
+            "en retard".to_string()
+        } else {
+            format!("arrivee dans {:.1}h", order.promised_remaining_s / 3600.0)
+        };
+        draw_gestion_small_line(
+            &format!(
+                "Commande #{} {}: {} unites ({}), {eta}",
+                order.id,
+                order.supplier,
+                order.qty,
+                order.grade.label()
+            ),
+            x,
+            y,
+            false,
+        );
+        y += 22.0;
+    }
+
+    let policy_row = match stock.reorder_policy(StockItemKind::MatierePremiere) {
+        Some(policy) => format!(
+            "Reappro auto {}: point {} | cible {} | {} | reserve {}",
+            if policy.enabled { "ON" } else { "OFF" },
+            policy.reorder_point,
+            policy.order_up_to,
+            state
+                .sim
+                .suppliers()
+                .supplier(&policy.supplier_id)
+                .map(|supplier| supplier.label.as_str())
+                .unwrap_or(policy.supplier_id.as_str()),
+            format_money(policy.min_cash_reserve_eur)
+        ),
+        None => "Reappro auto: aucune politique".to_string(),
+    };
+    draw_gestion_small_line(&policy_row, x, y, true);
+    y += 22.0;
+
+    for decision in stock.reorder_log.iter().rev().take(3) {
+        draw_gestion_small_line(&decision.summary(), x, y, false);
+        y += 22.0;
+    }
+
     y += 14.0;
 
     draw_gestion_small_line(
@@ -4316,13 +5205,144 @@ fn draw_gestion_stock_tab(state: &GameState, body: Rect) {
         y,
         false,
     );
+
+    y += 22.0;
+
+    draw_gestion_small_line(
+        "Maintenance: chaque panne demande un technicien libre et une piece detachee.",
+        x,
+        y,
+        false,
+    );
 }
 
-fn draw_gestion_ligne_tab(state: &GameState, body: Rect) {
-    let line = state.sim.main_production_line();
+const GESTION_LINE_TABLE_TOP: f32 = 62.0;
+const GESTION_LINE_TABLE_ROW_H: f32 = 24.0;
+const GESTION_LINE_TABLE_MAX_ROWS: usize = 4;
+
+fn gestion_line_scroll_max(state: &GameState) -> usize {
+    state
+        .sim
+        .production_lines()
+        .len()
+        .saturating_sub(GESTION_LINE_TABLE_MAX_ROWS)
+}
+
+fn gestion_visible_lines(state: &GameState) -> impl Iterator<Item = &ProductionLineState> {
+    state
+        .sim
+        .production_lines()
+        .iter()
+        .skip(
+            state
+                .hud_ui
+                .gestion_line_scroll
+                .min(gestion_line_scroll_max(state)),
+        )
+        .take(GESTION_LINE_TABLE_MAX_ROWS)
+}
+
+fn process_gestion_line_table_wheel(state: &mut GameState, wheel_y: f32) {
+    let max = gestion_line_scroll_max(state);
+    let scroll = state.hud_ui.gestion_line_scroll.min(max);
+    state.hud_ui.gestion_line_scroll = if wheel_y > 0.0 {
+        scroll.saturating_sub(1)
+    } else {
+        (scroll + 1).min(max)
+    };
+}
 
+fn gestion_selected_line(state: &GameState) -> &ProductionLineState {
+    state
+        .sim
+        .production_line(state.hud_ui.gestion_selected_line)
+        .unwrap_or_else(|| state.sim.main_production_line())
+}
+
+fn gestion_ligne_detail_top(state: &GameState, body: Rect) -> f32 {
+    let rows = state
+        .sim
+        .production_lines()
+        .len()
+        .min(GESTION_LINE_TABLE_MAX_ROWS);
+
+    body.y + GESTION_LINE_TABLE_TOP + rows as f32 * GESTION_LINE_TABLE_ROW_H + 6.0
+}
+
+fn gestion_line_comparison_row(state: &GameState, line: &ProductionLineState) -> String {
+    let lead_name = line
+        .assigned_lead_id
+        .and_then(|id| state.sim.personnel().employee(id))
+        .map(|employee| employee.name.clone())
+        .unwrap_or_else(|| "aucun".to_string());
+    let reason = if line.block_reason.is_empty() {
+        "-"
+    } else {
+        line.block_reason.as_str()
+    };
+    let raw_buffer = state
+        .sim
+        .line
+        .flow(line.id)
+        .map_or(0, |flow| flow.raw_qty());
+
+    format!(
+        "L{} {} | {:?} | chef {} | mat {} | int {} | x{:.2} | {:.1}/{:.1} box/h | rebut {:.0}% | {}",
+        line.id,
+        line.label,
+        line.status,
+        lead_name,
+        raw_buffer,
+        line.active_temps,
+        line.staffing_factor,
+        line.throughput_per_hour,
+        line.target_boxes_per_hour,
+        line.scrap_ratio() * 100.0,
+        reason
+    )
+}
+
+fn draw_gestion_ligne_tab(state: &GameState, body: Rect) {
     let x = body.x + 16.0;
-    let mut y = body.y + 30.0;
+
+    draw_gestion_section_title("Lignes de production - comparaison", x, body.y + 30.0);
+    let line_count = state.sim.production_lines().len();
+    let first = state
+        .hud_ui
+        .gestion_line_scroll
+        .min(gestion_line_scroll_max(state));
+    draw_gestion_small_line(
+        &format!(
+            "Matiere premiere: stock entree {} reparti entre les tampons de ligne{}",
+            state.sim.line.raw,
+            if line_count > GESTION_LINE_TABLE_MAX_ROWS {
+                format!(
+                    " | lignes {}-{} / {line_count} (molette)",
+                    first + 1,
+                    first + GESTION_LINE_TABLE_MAX_ROWS
+                )
+            } else {
+                String::new()
+            }
+        ),
+        x,
+        body.y + 46.0,
+        false,
+    );
+    let mut y = body.y + GESTION_LINE_TABLE_TOP;
+
+    for line in gestion_visible_lines(state) {
+        draw_gestion_small_line(
+            &gestion_line_comparison_row(state, line),
+            x,
+            y,
+            line.id == gestion_selected_line(state).id,
+        );
+        y += GESTION_LINE_TABLE_ROW_H;
+    }
+
+    let line = gestion_selected_line(state);
+    y = gestion_ligne_detail_top(state, body);
 
     draw_gestion_section_title(&format!("{} - pilotage production", line.label), x, y);
     y += 32.0;
@@ -4338,12 +5358,27 @@ fn draw_gestion_ligne_tab(state: &GameState, body: Rect) {
         .and_then(|id| state.sim.personnel().employee(id))
         .and_then(|employee| employee.temp_policy.as_ref());
 
+    let shifts = state.sim.shifts().template_or_default(&line.shift_template);
+    let open_slot = shifts
+        .open_slot(state.sim.clock.hour_of_day(), state.sim.clock.is_weekend())
+        .map(|slot| slot.span_label())
+        .unwrap_or_else(|| "ferme".to_string());
+
     let rows = [
         format!("Etat: {:?} | raison: {}", line.status, line.block_reason),
         format!("Chef assigne: {}", lead_name),
+        format!(
+            "Horaires: {} ({} postes{}) | en cours: {}",
+            shifts.label,
+            shifts.slots.len(),
+            if shifts.weekend { ", week-end" } else { "" },
+            open_slot
+        ),
         format!("Interimaires actifs: {} / 3", line.active_temps),
         format!("Facteur effectifs: x{:.2}", line.staffing_factor),
         format!("Objectif: {:.1} boxes/h", line.target_boxes_per_hour),
+        line.quality.summary(),
+        state.sim.quality_control().summary(),
         format!(
             "Politique interim: {} max {}",
             if policy.is_some_and(|p| p.enabled) {
@@ -4381,8 +5416,9 @@ fn draw_gestion_ventes_tab(state: &GameState, body: Rect) {
 
     let rows = [
         format!(
-            "Produits finis en attente: {} boxes",
-            state.sim.line.finished
+            "Produits finis en attente: {} boxes (+{} en bout de ligne)",
+            state.sim.line.finished,
+            state.sim.line.boxes_to_ship_total()
         ),
         format!(
             "Administrateurs vente: {}",
@@ -4395,6 +5431,10 @@ fn draw_gestion_ventes_tab(state: &GameState, body: Rect) {
             "Capacite vente: {:.1} boxes/h",
             state.sim.sales_capacity_per_hour()
         ),
+        format!(
+            "Prix de vente: {}/box",
+            format_money(state.sim.sale_price_eur())
+        ),
         format!(
             "Revenu potentiel: {}/h",
             format_money(sales.last_revenue_per_hour)
@@ -4407,7 +5447,87 @@ fn draw_gestion_ventes_tab(state: &GameState, body: Rect) {
         y += 24.0;
     }
 
-    y += 14.0;
+    y += 8.0;
+    draw_gestion_section_title("Marche et contrats", x, y);
+    y += 30.0;
+
+    let market = state.sim.market();
+    let now_s = state.sim.clock.seconds();
+    let market_rows = [
+        format!(
+            "Demande clients: {:.1} boxes/h | en attente: {:.0}",
+            state.sim.market_demand_per_hour(),
+            market.spot_waiting(&state.sim.active_recipe().id)
+        ),
+        format!(
+            "Penalites de retard cumulees: {}",
+            format_money(market.penalties_total_eur)
+        ),
+    ];
+    for row in market_rows {
+        draw_gestion_small_line(&row, x, y, true);
+        y += 24.0;
+    }
+
+    // Carnet de commandes: commandes dont la date demandee tombe dans la fenetre.
+    for (label, window_h) in FULFILMENT_WINDOWS_H {
+        let stats = market.orders.stats(now_s, window_h);
+        draw_gestion_small_line(
+            &format!(
+                "{label}: OTIF {:.0}% | service {:.0}% | retard moyen {:.1}h ({} cmd)",
+                stats.otif() * 100.0,
+                stats.fill_rate() * 100.0,
+                stats.mean_lateness_h(),
+                stats.orders
+            ),
+            x,
+            y,
+            true,
+        );
+        y += 22.0;
+    }
+
+    let contract_rows = market
+        .offers()
+        .map(|offer| {
+            format!(
+                "Offre #{} {}: {} x {} a {}, echeance {:.0}h",
+                offer.id,
+                offer.customer,
+                offer.volume,
+                offer.product_id,
+                format_money(offer.unit_price_eur),
+                ((offer.due_s - now_s) / 3600.0).max(0.0)
+            )
+        })
+        .chain(market.active_contracts().map(|contract| {
+            let due_h = (contract.due_s - now_s) / 3600.0;
+            let due = if due_h >= 0.0 {
+                format!("reste {due_h:.0}h")
+            } else {
+                format!("{} {:.0}h", contract.status.label().to_lowercase(), -due_h)
+            };
+            format!(
+                "Contrat #{} {}: {}/{} {}, {due}",
+                contract.id,
+                contract.customer,
+                contract.delivered,
+                contract.volume,
+                contract.product_id
+            )
+        }))
+        .take(6)
+        .collect::<Vec<_>>();
+    if contract_rows.is_empty() {
+        draw_gestion_small_line("Aucune offre client pour le moment.", x, y, false);
+        y += 22.0;
+    }
+    for row in contract_rows {
+        draw_gestion_small_line(&row, x, y, false);
+        y += 22.0;
+    }
+
+    y += 10.0;
 
     draw_gestion_small_line(
         "Regle: les produits finis ne donnent de l'argent qu'une fois vendus par un administrateur avec bureau en zone vente.",
@@ -4417,6 +5537,209 @@ fn draw_gestion_ventes_tab(state: &GameState, body: Rect) {
     );
 }
 
+const GESTION_FINANCE_DETAIL_ROWS: usize = 4;
+const GESTION_FINANCE_TOP: f32 = 250.0;
+const GESTION_FINANCE_ROW_H: f32 = 28.0;
+const GESTION_LOAN_BUTTON_W: f32 = 118.0;
+
+// Lignes d'offres puis de prets en cours, chacune avec son bouton en bout de colonne gauche.
+fn gestion_financing_rows_top(body: Rect) -> f32 {
+    body.y + GESTION_FINANCE_TOP + 30.0 + 22.0 * 2.0 + 8.0
+}
+
+fn gestion_loan_button_rect(body: Rect, row: usize) -> Rect {
+    let row_y = gestion_financing_rows_top(body) + row as f32 * GESTION_FINANCE_ROW_H;
+    Rect::new(
+        body.x + body.w * 0.5 - GESTION_LOAN_BUTTON_W - 8.0,
+        row_y - 17.0,
+        GESTION_LOAN_BUTTON_W,
+        22.0,
+    )
+}
+
+fn draw_gestion_financing_section(state: &GameState, body: Rect) {
+    let finance = state.sim.finance();
+    let book = state.sim.finance_book();
+    let x = body.x + 16.0;
+    let mut y = body.y + GESTION_FINANCE_TOP;
+
+    draw_gestion_section_title("Financement", x, y);
+    y += 30.0;
+
+    let cash = state.sim.cash();
+    let overdraft_row = format!(
+        "Decouvert: {} / {} autorises | agios courus {}",
+        format_money((-cash).max(0.0)),
+        format_money(book.credit_line_limit_eur),
+        format_money(finance.overdraft_interest_accrued_eur)
+    );
+    draw_gestion_small_line(&overdraft_row, x, y, true);
+    y += 22.0;
+
+    let risk_row = if finance.is_bankrupt() {
+        "FAILLITE: la banque a coupe les credits, partie terminee.".to_string()
+    } else if let Some(days) = finance.days_before_bankruptcy(book, state.sim.clock.seconds()) {
+        format!("Hors decouvert autorise: faillite dans {days:.1} j")
+    } else {
+        format!(
+            "Emprunts restant dus: {} | interets payes {}",
+            format_money(finance.loans_outstanding_eur()),
+            format_money(finance.interest_paid_eur)
+        )
+    };
+    draw_gestion_small_line(&risk_row, x, y, true);
+
+    let mut y = gestion_financing_rows_top(body);
+    for offer in &book.loan_offers {
+        draw_gestion_small_line(&offer.summary(), x, y, false);
+        y += GESTION_FINANCE_ROW_H;
+    }
+    for loan in &finance.loans {
+        draw_gestion_small_line(
+            &format!(
+                "Pret #{} {}: reste {} ({} /j)",
+                loan.id,
+                loan.label,
+                format_money(loan.outstanding_eur),
+                format_money(loan.daily_principal_eur)
+            ),
+            x,
+            y,
+            false,
+        );
+        y += GESTION_FINANCE_ROW_H;
+    }
+}
+
+fn gestion_period_pnl_row(label: &str, report: &PeriodReport) -> String {
+    format!(
+        "{label}: produits {} | charges {} | resultat {}",
+        format_money(report.revenue()),
+        format_money(report.expenses()),
+        format_money(report.net_income())
+    )
+}
+
+fn draw_gestion_finances_tab(state: &GameState, body: Rect) {
+    let ledger = state.sim.ledger();
+
+    let x = body.x + 16.0;
+    let col_x = body.x + body.w * 0.5 + 8.0;
+    let mut y = body.y + 30.0;
+
+    draw_gestion_section_title("Compte de resultat", x, y);
+    y += 30.0;
+
+    let day_label = format!("Jour {} (en cours)", ledger.day.index + 1);
+    let last_day = ledger.last_daily_report();
+    let last_day_label = format!("Jour {}", last_day.index + 1);
+    let week_label = format!("Semaine {} (en cours)", ledger.week.index + 1);
+    let last_week = ledger.last_weekly_report();
+    let last_week_label = format!("Semaine {}", last_week.index + 1);
+    let pnl_rows = [
+        gestion_period_pnl_row(&day_label, &ledger.day),
+        gestion_period_pnl_row(&last_day_label, last_day),
+        gestion_period_pnl_row(&week_label, &ledger.week),
+        gestion_period_pnl_row(&last_week_label, last_week),
+    ];
+    for row in pnl_rows {
+        draw_gestion_small_line(&row, x, y, true);
+        y += 22.0;
+    }
+
+    // Principaux comptes de la semaine en cours.
+    let detail_top = y + 6.0;
+    y = detail_top;
+    let mut lines = ledger.week.income_lines().collect::<Vec<_>>();
+    lines.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+    for (account, amount) in lines.into_iter().take(GESTION_FINANCE_DETAIL_ROWS) {
+        draw_gestion_small_line(
+            &format!("{}: {}", account.label(), format_money(amount)),
+            x,
+            y,
+            false,
+        );
+        y += 20.0;
+    }
+
+    draw_gestion_financing_section(state, body);
+
+    let mut y = detail_top;
+    draw_gestion_section_title("Bilan", col_x, y);
+    y += 30.0;
+
+    let sheet = ledger.balance_sheet();
+    let sheet_rows = [
+        format!(
+            "Tresorerie: {} | decouvert {}",
+            format_money(sheet.cash_eur.max(0.0)),
+            format_money(sheet.bank_overdraft_eur())
+        ),
+        format!(
+            "Immobilisations: {} brut - {} amort. = {}",
+            format_money(sheet.fixed_assets_gross_eur),
+            format_money(sheet.accumulated_depreciation_eur),
+            format_money(sheet.fixed_assets_net_eur())
+        ),
+        format!("Total actif: {}", format_money(sheet.total_assets_eur())),
+        format!(
+            "Capital {} + resultat cumule {} = {}",
+            format_money(sheet.capital_eur),
+            format_money(sheet.retained_earnings_eur),
+            format_money(sheet.total_equity_eur())
+        ),
+        format!(
+            "Dettes: emprunts {} + decouvert {} | total passif {}",
+            format_money(sheet.loans_eur),
+            format_money(sheet.bank_overdraft_eur()),
+            format_money(sheet.total_equity_and_liabilities_eur())
+        ),
+    ];
+    for row in sheet_rows {
+        draw_gestion_small_line(&row, col_x, y, true);
+        y += 22.0;
+    }
+
+    y += 8.0;
+    draw_gestion_section_title("Flux de tresorerie", col_x, y);
+    y += 30.0;
+
+    for (label, report) in [("Jour", last_day), ("Semaine", &ledger.week)] {
+        let flows = CashActivity::ALL
+            .iter()
+            .map(|activity| {
+                format!(
+                    "{} {}",
+                    activity.label(),
+                    format_money(report.cash_flow(*activity))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" | ");
+        draw_gestion_small_line(&format!("{label}: {flows}"), col_x, y, true);
+        y += 22.0;
+        draw_gestion_small_line(
+            &format!(
+                "  {} -> {}",
+                format_money(report.opening_cash_eur),
+                format_money(report.closing_cash_eur())
+            ),
+            col_x,
+            y,
+            false,
+        );
+        y += 22.0;
+    }
+
+    y += 8.0;
+    draw_gestion_small_line(
+        "Regle: les investissements sont immobilises puis amortis; seules les dotations pesent sur le resultat.",
+        col_x,
+        y,
+        false,
+    );
+}
+
 fn info_window_rect() -> Rect {
     let sw = screen_width();
     let sh = screen_height();
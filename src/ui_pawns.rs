@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use super::*;
+use crate::gestion::besoins::NeedKind;
 use crate::historique::LogCategorie;
 use crate::interactions::{SocialActionKind, SocialEmoteIcon};
 use crate::social::{SocialEmoteView, SocialVisualStage};
@@ -336,6 +337,17 @@ pub fn sync_dynamic_pawn_metrics(state: &mut GameState) {
     if let Some(worker) = state.pawns.iter_mut().find(|p| p.key == PawnKey::SimWorker) {
         worker.metrics.needs[NeedBar::Dormir as usize] = energie;
         worker.metrics.needs[NeedBar::Calme as usize] = calme;
+        if let Some(needs) = state.sim.primary_agent_needs() {
+            for (bar, need) in [
+                (NeedBar::Manger, NeedKind::Manger),
+                (NeedBar::Boire, NeedKind::Boire),
+                (NeedBar::Toilettes, NeedKind::Toilettes),
+                (NeedBar::Hygiene, NeedKind::Hygiene),
+                (NeedBar::Social, NeedKind::Social),
+            ] {
+                worker.metrics.needs[bar as usize] = needs.level(need).round() as u8;
+            }
+        }
         worker.metrics.synth[SynthBar::Fatigue as usize] = energie;
         // Moral slightly impacted by stress.
         worker.metrics.synth[SynthBar::Moral as usize] = ((calme as u32 + 40) / 2).min(100) as u8;
@@ -1759,12 +1771,7 @@ fn rand_range_u8(seed: u64, idx: u32, lo: u8, hi: u8) -> u8 {
     if lo >= hi {
         return lo;
     }
-    let mut x = seed ^ ((idx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
-    x ^= x >> 30;
-    x = x.wrapping_mul(0xBF58_476D_1CE4_E5B9);
-    x ^= x >> 27;
-    x = x.wrapping_mul(0x94D0_49BB_1331_11EB);
-    x ^= x >> 31;
+    let x = crate::aleatoire::mix_seed(seed, idx as u64);
     let span = (hi as u32).saturating_sub(lo as u32);
     let v = (x as u32) % span;
     (lo as u32 + v).min(100) as u8
@@ -107,7 +107,7 @@ pub(crate) fn starter_factory_bounds(w: i32, h: i32) -> (i32, i32, i32, i32) {
     (min_x, max_x, min_y, max_y)
 }
 
-pub(crate) fn generate_starter_factory_world(w: i32, h: i32) -> World {
+pub fn generate_starter_factory_world(w: i32, h: i32) -> World {
     let mut world = World::new_room(w, h);
 
     // Exterieur type campagne: dominant herbe, avec zones tassees et plaques de terre.